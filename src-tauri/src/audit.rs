@@ -0,0 +1,43 @@
+use crate::logging::LOG_DIR_NAME;
+use serde::Serialize;
+use tauri::Manager;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+/// Kept apart from the rotating app log so purging logs leaves it alone
+const AUDIT_FILE_NAME: &str = "audit.log";
+
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    timestamp: String,
+    action: &'a str,
+    details: serde_json::Value,
+}
+
+/// Appends one JSON line to `Logs/audit.log` for actions that move or rewrite files the
+/// user didn't have open. A failed write is logged; the action itself has already happened.
+pub async fn record(app_handle: &tauri::AppHandle, action: &str, details: serde_json::Value) {
+    let entry = AuditEntry {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        action,
+        details,
+    };
+    if let Err(e) = append(app_handle, &entry).await {
+        log::warn!("Failed to record {} in audit log: {}", action, e);
+    }
+}
+
+async fn append(app_handle: &tauri::AppHandle, entry: &AuditEntry<'_>) -> anyhow::Result<()> {
+    let dir = app_handle.path().app_local_data_dir()?.join(LOG_DIR_NAME);
+    fs::create_dir_all(&dir).await?;
+
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(AUDIT_FILE_NAME))
+        .await?;
+    file.write_all(line.as_bytes()).await?;
+    Ok(())
+}
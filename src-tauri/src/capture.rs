@@ -0,0 +1,205 @@
+use crate::commands::settings::read_setting;
+use crate::state::AppState;
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+/// Asks the frontend to open the quick capture prompt; a string payload prefills it.
+/// Every entry point goes through the prompt, so nothing reaches the inbox unconfirmed.
+pub const QUICK_CAPTURE_EVENT: &str = "quick-capture";
+/// `markdownrs://capture?text=...` opens the prompt with `text` filled in
+pub const URL_SCHEME: &str = "markdownrs";
+/// Used unless `captureShortcut` is set; an empty setting turns the hotkey off
+const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+Space";
+const TRAY_CAPTURE_ID: &str = "quick-capture";
+const TRAY_SHOW_ID: &str = "show";
+
+/// Capture prompts asked for before the frontend listened for them, such as the link
+/// that launched the app. They are sent once the frontend calls `quick_capture_ready`.
+#[derive(Debug, Default)]
+pub struct CaptureQueue {
+    ready: bool,
+    pending: Vec<Option<String>>,
+}
+
+fn show_window(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Brings the window forward and opens the capture prompt, or queues it until the
+/// frontend listens
+pub fn open_prompt(app_handle: &AppHandle, text: Option<String>) {
+    show_window(app_handle);
+    if let Some(state) = app_handle.try_state::<AppState>() {
+        let mut queue = state
+            .capture_queue
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if !queue.ready {
+            queue.pending.push(text);
+            return;
+        }
+    }
+    emit_prompt(app_handle, text);
+}
+
+fn emit_prompt(app_handle: &AppHandle, text: Option<String>) {
+    if let Err(e) = app_handle.emit(QUICK_CAPTURE_EVENT, text) {
+        log::warn!("Failed to emit {}: {}", QUICK_CAPTURE_EVENT, e);
+    }
+}
+
+/// Sends the prompts queued before the frontend listened, and every later one directly
+pub fn mark_ready(app_handle: &AppHandle) {
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        return;
+    };
+    let pending = {
+        let mut queue = state
+            .capture_queue
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        queue.ready = true;
+        std::mem::take(&mut queue.pending)
+    };
+    for text in pending {
+        emit_prompt(app_handle, text);
+    }
+}
+
+/// Whether a launch argument is a capture URL rather than a file to open
+pub fn is_capture_url(arg: &str) -> bool {
+    arg.get(..URL_SCHEME.len() + 1)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(&format!("{}:", URL_SCHEME)))
+}
+
+/// The text a capture URL carries. `None` for URLs that aren't captures.
+fn capture_text(url: &Url) -> Option<Option<String>> {
+    let target = url.host_str().unwrap_or_default();
+    let path = url.path().trim_matches('/');
+    if url.scheme() != URL_SCHEME || (target != "capture" && path != "capture") {
+        return None;
+    }
+    Some(
+        url.query_pairs()
+            .find(|(key, _)| key == "text")
+            .map(|(_, text)| text.into_owned())
+            .filter(|text| !text.trim().is_empty()),
+    )
+}
+
+fn handle_url(app_handle: &AppHandle, url: &Url) {
+    match capture_text(url) {
+        Some(text) => open_prompt(app_handle, text),
+        None => log::warn!("[Capture] Ignoring unsupported link: {}", url),
+    }
+}
+
+/// Sets up the tray icon, the global hotkey and the `markdownrs:` link handler. Each is
+/// optional: one that fails is logged and the others still work.
+pub fn setup(app_handle: &AppHandle) {
+    if let Err(e) = build_tray(app_handle) {
+        log::warn!("[Capture] Failed to create tray icon: {}", e);
+    }
+
+    let shortcuts = tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app_handle, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                open_prompt(app_handle, None);
+            }
+        })
+        .build();
+    match app_handle.plugin(shortcuts) {
+        Ok(()) => {
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move { register_shortcut(&app_handle).await });
+        },
+        Err(e) => log::warn!("[Capture] Failed to set up global shortcuts: {}", e),
+    }
+
+    setup_links(app_handle);
+}
+
+fn build_tray(app_handle: &AppHandle) -> tauri::Result<()> {
+    let menu = Menu::with_items(
+        app_handle,
+        &[
+            &MenuItem::with_id(
+                app_handle,
+                TRAY_CAPTURE_ID,
+                "Quick Capture...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                TRAY_SHOW_ID,
+                "Show MarkdownRS",
+                true,
+                None::<&str>,
+            )?,
+        ],
+    )?;
+    let mut tray = TrayIconBuilder::with_id("main")
+        .tooltip("MarkdownRS")
+        .menu(&menu)
+        .on_menu_event(|app_handle, event| match event.id().as_ref() {
+            TRAY_CAPTURE_ID => open_prompt(app_handle, None),
+            TRAY_SHOW_ID => show_window(app_handle),
+            _ => {},
+        });
+    if let Some(icon) = app_handle.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app_handle)?;
+    Ok(())
+}
+
+async fn register_shortcut(app_handle: &AppHandle) {
+    let shortcut = read_setting::<String>(app_handle, "captureShortcut")
+        .await
+        .unwrap_or_else(|| DEFAULT_SHORTCUT.to_string());
+    let shortcut = shortcut.trim();
+    if shortcut.is_empty() {
+        return;
+    }
+    // Another app holding the same keys is common, so this only warns
+    match app_handle.global_shortcut().register(shortcut) {
+        Ok(()) => log::info!("[Capture] Quick capture hotkey: {}", shortcut),
+        Err(e) => log::warn!("[Capture] Failed to register hotkey '{}': {}", shortcut, e),
+    }
+}
+
+fn setup_links(app_handle: &AppHandle) {
+    let deep_link = app_handle.deep_link();
+    // Installs made by Velopack aren't registered with the OS by an installer
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = deep_link.register_all() {
+        log::warn!(
+            "[Capture] Failed to register the {} link scheme: {}",
+            URL_SCHEME,
+            e
+        );
+    }
+
+    // Links opened while running; with single instance this includes second launches
+    let handle = app_handle.clone();
+    deep_link.on_open_url(move |event| {
+        for url in event.urls() {
+            handle_url(&handle, &url);
+        }
+    });
+
+    // The link that launched the app is queued until the frontend is ready for it
+    if let Ok(Some(urls)) = deep_link.get_current() {
+        for url in urls {
+            handle_url(app_handle, &url);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::db::Annotation;
+use crate::markdown::annotations::{self, AnchorLocation, AnchorStatus, AppendixEntry};
+use crate::state::AppState;
+use crate::utils::{handle_error, path_identity, validate_path};
+use serde::Serialize;
+use tauri::State;
+
+const DEFAULT_APPENDIX_TITLE: &str = "Comments";
+
+#[derive(Debug, Serialize)]
+pub struct LocatedAnnotation {
+    #[serde(flatten)]
+    pub annotation: Annotation,
+    /// Where the comment's text is in the content passed in; `None` without content
+    pub location: Option<AnchorLocation>,
+}
+
+/// Attaches a comment to the UTF-16 range `from..to` of `content`, the document as the
+/// editor shows it. The file itself is not touched.
+#[tauri::command]
+pub fn add_annotation(
+    state: State<'_, AppState>,
+    path: String,
+    content: String,
+    from: usize,
+    to: usize,
+    body: String,
+    author: Option<String>,
+) -> Result<Annotation, String> {
+    validate_path(&path)?;
+    let anchor = annotations::anchor(&content, from, to)
+        .map_err(|e| handle_error(Some(&path), "anchor annotation", e))?;
+    state
+        .db
+        .add_annotation(
+            &path_identity(&path, false),
+            &anchor,
+            &body,
+            author.as_deref(),
+        )
+        .map_err(|e| handle_error(Some(&path), "add annotation", e))
+}
+
+/// Comments on a document. With `content`, each is located in it and comments whose text
+/// moved are re-anchored at their new offsets.
+#[tauri::command]
+pub fn list_annotations(
+    state: State<'_, AppState>,
+    path: String,
+    content: Option<String>,
+) -> Result<Vec<LocatedAnnotation>, String> {
+    validate_path(&path)?;
+    let stored = state
+        .db
+        .list_annotations(&path_identity(&path, false))
+        .map_err(|e| handle_error(Some(&path), "list annotations", e))?;
+    let Some(content) = content else {
+        return Ok(stored
+            .into_iter()
+            .map(|annotation| LocatedAnnotation {
+                annotation,
+                location: None,
+            })
+            .collect());
+    };
+
+    let mut located: Vec<LocatedAnnotation> = stored
+        .into_iter()
+        .map(|annotation| {
+            let location = annotations::locate(&content, &annotation.anchor);
+            if let (AnchorStatus::Moved | AnchorStatus::Edited, Some(from), Some(to)) =
+                (location.status, location.from, location.to)
+                && let Err(e) = state.db.move_annotation(&annotation.id, from, to)
+            {
+                log::warn!("Failed to re-anchor annotation {}: {}", annotation.id, e);
+            }
+            LocatedAnnotation {
+                annotation,
+                location: Some(location),
+            }
+        })
+        .collect();
+    // Document order, with comments whose text is gone at the end
+    located.sort_by_key(|a| a.location.and_then(|l| l.from).unwrap_or(usize::MAX));
+    Ok(located)
+}
+
+/// Edits a comment's text or marks it resolved
+#[tauri::command]
+pub fn update_annotation(
+    state: State<'_, AppState>,
+    id: String,
+    body: Option<String>,
+    resolved: Option<bool>,
+) -> Result<Annotation, String> {
+    state
+        .db
+        .update_annotation(&id, body.as_deref(), resolved)
+        .map_err(|e| handle_error(Some(&id), "update annotation", e))
+}
+
+#[tauri::command]
+pub fn delete_annotation(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    state
+        .db
+        .delete_annotation(&id)
+        .map_err(|e| handle_error(Some(&id), "delete annotation", e))
+}
+
+/// The document's comments as a markdown section, in document order, to append to the
+/// document or copy into a review. Resolved comments are left out unless
+/// `include_resolved` is set.
+#[tauri::command]
+pub fn export_annotations(
+    state: State<'_, AppState>,
+    path: String,
+    content: String,
+    include_resolved: Option<bool>,
+    title: Option<String>,
+) -> Result<String, String> {
+    let include_resolved = include_resolved.unwrap_or(false);
+    let located = list_annotations(state, path, Some(content))?;
+    let entries: Vec<AppendixEntry> = located
+        .iter()
+        .filter(|a| include_resolved || !a.annotation.resolved)
+        .map(|a| AppendixEntry {
+            quote: &a.annotation.anchor.quote,
+            body: &a.annotation.body,
+            author: a.annotation.author.as_deref(),
+            created: &a.annotation.created,
+            resolved: a.annotation.resolved,
+        })
+        .collect();
+    if entries.is_empty() {
+        return Ok(String::new());
+    }
+    let title = title
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_APPENDIX_TITLE.to_string());
+    Ok(annotations::appendix(&title, &entries))
+}
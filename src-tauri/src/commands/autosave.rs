@@ -0,0 +1,204 @@
+use crate::commands::settings::read_setting;
+use crate::state::AppState;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager, State};
+
+pub const SESSION_SAVE_EVENT: &str = "session-save-requested";
+const IDLE_SETTING_KEY: &str = "sessionSaveIdleSeconds";
+const DIRTY_TABS_SETTING_KEY: &str = "sessionSaveDirtyTabs";
+const DEFAULT_IDLE_SECONDS: u64 = 5;
+const DEFAULT_DIRTY_TABS: usize = 3;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Why the backend asked the frontend to save the session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SaveReason {
+    /// No changes for the idle interval
+    Idle,
+    /// The number of changed tabs reached the threshold
+    Threshold,
+    Blur,
+    Close,
+    /// The app is exiting, including at OS shutdown or log-off
+    Exit,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AutosavePolicy {
+    pub idle: Duration,
+    /// Zero disables the threshold; only idle and window events trigger saves
+    pub dirty_tabs: usize,
+}
+
+impl Default for AutosavePolicy {
+    fn default() -> Self {
+        Self {
+            idle: Duration::from_secs(DEFAULT_IDLE_SECONDS),
+            dirty_tabs: DEFAULT_DIRTY_TABS,
+        }
+    }
+}
+
+impl AutosavePolicy {
+    fn from_values(idle_seconds: Option<u64>, dirty_tabs: Option<u64>) -> Self {
+        let default = Self::default();
+        Self {
+            // A zero interval would ask for a save on every poll
+            idle: idle_seconds
+                .map(|s| Duration::from_secs(s.max(1)))
+                .unwrap_or(default.idle),
+            dirty_tabs: dirty_tabs.map_or(default.dirty_tabs, |n| n as usize),
+        }
+    }
+
+    /// Reads the policy from a settings object as sent to `save_settings`
+    pub fn from_settings(settings: &serde_json::Value) -> Self {
+        Self::from_values(
+            settings.get(IDLE_SETTING_KEY).and_then(|v| v.as_u64()),
+            settings
+                .get(DIRTY_TABS_SETTING_KEY)
+                .and_then(|v| v.as_u64()),
+        )
+    }
+}
+
+/// Session changes the frontend has reported since the last successful `save_session`
+#[derive(Debug, Default)]
+pub struct SessionAutosave {
+    policy: AutosavePolicy,
+    dirty: bool,
+    dirty_tabs: HashSet<String>,
+    last_change: Option<Instant>,
+    /// Last reported change of any kind; unlike `last_change` it survives saves
+    last_activity: Option<Instant>,
+    /// A save was requested for the current changes and has not completed yet
+    requested: bool,
+    /// Bumped by every reported change
+    generation: u64,
+    /// `generation` when the pending save was first requested
+    requested_generation: Option<u64>,
+    /// Completed saves, so a waiter can tell when a requested save has landed
+    saves: u64,
+}
+
+impl SessionAutosave {
+    pub fn set_policy(&mut self, policy: AutosavePolicy) {
+        self.policy = policy;
+    }
+
+    /// Records a change; returns true when it takes the changed tabs to the threshold
+    fn record_change(&mut self, tab_id: Option<String>) -> bool {
+        self.dirty = true;
+        self.generation += 1;
+        self.last_change = Some(Instant::now());
+        self.last_activity = self.last_change;
+        self.requested = false;
+        // Only a newly changed tab can trip the threshold, so typing doesn't re-request
+        let new_tab = tab_id.is_some_and(|id| self.dirty_tabs.insert(id));
+        new_tab && self.policy.dirty_tabs > 0 && self.dirty_tabs.len() >= self.policy.dirty_tabs
+    }
+
+    fn idle_save_due(&self, now: Instant) -> bool {
+        self.dirty
+            && !self.requested
+            && self
+                .last_change
+                .is_some_and(|last| now.duration_since(last) >= self.policy.idle)
+    }
+
+    /// The changes a save starting now covers: those up to its request, or up to now for a
+    /// save the frontend started on its own
+    pub fn save_generation(&self) -> u64 {
+        self.requested_generation.unwrap_or(self.generation)
+    }
+
+    /// Called once the session has been written, with the `save_generation` taken when the
+    /// save began. Changes reported after that weren't in the save and stay dirty.
+    pub fn clear(&mut self, generation: u64) {
+        self.requested = false;
+        self.requested_generation = None;
+        self.saves += 1;
+        if generation != self.generation {
+            return;
+        }
+        self.dirty = false;
+        self.dirty_tabs.clear();
+        self.last_change = None;
+    }
+
+    /// Reported changes have not been saved yet
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn saves(&self) -> u64 {
+        self.saves
+    }
+
+    /// Time since the user last changed anything; the whole session when they haven't
+    pub fn idle_for(&self, now: Instant) -> Duration {
+        self.last_activity
+            .map_or(Duration::MAX, |last| now.duration_since(last))
+    }
+}
+
+pub fn request_save(app_handle: &tauri::AppHandle, reason: SaveReason) {
+    if let Some(state) = app_handle.try_state::<AppState>()
+        && let Ok(mut autosave) = state.session_autosave.lock()
+    {
+        autosave.requested = true;
+        let generation = autosave.generation;
+        autosave.requested_generation.get_or_insert(generation);
+    }
+    log::debug!("[Session] save requested | reason={:?}", reason);
+    if let Err(e) = app_handle.emit(SESSION_SAVE_EVENT, reason) {
+        log::warn!("Failed to request session save: {}", e);
+    }
+}
+
+/// Loads the policy from settings and polls for idle sessions for the life of the app.
+pub async fn run_autosave_scheduler(app_handle: tauri::AppHandle) {
+    let policy = AutosavePolicy::from_values(
+        read_setting(&app_handle, IDLE_SETTING_KEY).await,
+        read_setting(&app_handle, DIRTY_TABS_SETTING_KEY).await,
+    );
+    let state = app_handle.state::<AppState>();
+    if let Ok(mut autosave) = state.session_autosave.lock() {
+        autosave.set_policy(policy);
+    }
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let due = state
+            .session_autosave
+            .lock()
+            .is_ok_and(|autosave| autosave.idle_save_due(Instant::now()));
+        if due {
+            request_save(&app_handle, SaveReason::Idle);
+        }
+    }
+}
+
+/// Reports a session change. `tab_id` is set for tab content edits, which count toward
+/// the changed-tab threshold; other changes (scrolling, reordering) only restart the
+/// idle timer.
+#[tauri::command]
+pub fn mark_session_dirty(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    tab_id: Option<String>,
+) -> Result<(), String> {
+    let threshold_reached = state
+        .session_autosave
+        .lock()
+        .map_err(|_| "Session autosave lock poisoned".to_string())?
+        .record_change(tab_id);
+    if threshold_reached {
+        request_save(&app_handle, SaveReason::Threshold);
+    }
+    Ok(())
+}
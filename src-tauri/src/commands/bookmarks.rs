@@ -1,4 +1,4 @@
-use crate::db::Bookmark;
+use crate::db::{Bookmark, TagCount};
 use crate::state::AppState;
 use crate::utils::handle_error;
 use tauri::State;
@@ -19,6 +19,20 @@ pub fn get_all_bookmarks(state: State<'_, AppState>) -> Result<Vec<Bookmark>, St
         .map_err(|e| handle_error(Some("all"), "retrieve bookmarks", e))
 }
 
+#[tauri::command]
+pub fn update_bookmark(
+    state: State<'_, AppState>,
+    id: String,
+    title: String,
+    path: String,
+    tags: Vec<String>,
+) -> Result<Bookmark, String> {
+    state
+        .db
+        .update_bookmark(&id, &title, &path, &tags)
+        .map_err(|e| handle_error(Some(&path), "update bookmark", e))
+}
+
 #[tauri::command]
 pub fn delete_bookmark(state: State<'_, AppState>, id: String) -> Result<(), String> {
     state
@@ -27,6 +41,38 @@ pub fn delete_bookmark(state: State<'_, AppState>, id: String) -> Result<(), Str
         .map_err(|e| handle_error(Some(&id), "delete bookmark", e))
 }
 
+#[tauri::command]
+pub fn delete_bookmarks(state: State<'_, AppState>, ids: Vec<String>) -> Result<usize, String> {
+    state
+        .db
+        .delete_bookmarks(&ids)
+        .map_err(|e| handle_error(Some("bookmarks"), "delete bookmarks", e))
+}
+
+#[tauri::command]
+pub fn tag_bookmarks(
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+    tag: String,
+) -> Result<usize, String> {
+    state
+        .db
+        .tag_bookmarks(&ids, &tag)
+        .map_err(|e| handle_error(Some(&tag), "tag bookmarks", e))
+}
+
+#[tauri::command]
+pub fn move_bookmarks(
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+    folder: Option<String>,
+) -> Result<usize, String> {
+    state
+        .db
+        .move_bookmarks(&ids, folder.as_deref())
+        .map_err(|e| handle_error(folder.as_deref(), "move bookmarks", e))
+}
+
 #[tauri::command]
 pub fn update_bookmark_access_time(
     state: State<'_, AppState>,
@@ -38,3 +84,34 @@ pub fn update_bookmark_access_time(
         .update_bookmark_access_time(&id, &last_accessed)
         .map_err(|e| handle_error(Some(&id), "update bookmark", e))
 }
+
+#[tauri::command]
+pub fn find_bookmarks_by_tag(
+    state: State<'_, AppState>,
+    tag: String,
+) -> Result<Vec<Bookmark>, String> {
+    state
+        .db
+        .find_bookmarks_by_tag(&tag)
+        .map_err(|e| handle_error(Some(&tag), "find bookmarks by tag", e))
+}
+
+#[tauri::command]
+pub fn list_bookmark_tags(state: State<'_, AppState>) -> Result<Vec<TagCount>, String> {
+    state
+        .db
+        .list_bookmark_tags()
+        .map_err(|e| handle_error(Some("all"), "list bookmark tags", e))
+}
+
+#[tauri::command]
+pub fn rename_tag(
+    state: State<'_, AppState>,
+    old_tag: String,
+    new_tag: String,
+) -> Result<usize, String> {
+    state
+        .db
+        .rename_tag(&old_tag, &new_tag)
+        .map_err(|e| handle_error(Some(&old_tag), "rename tag", e))
+}
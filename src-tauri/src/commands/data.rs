@@ -1,8 +1,21 @@
-use crate::db::Bookmark;
+use crate::db::{Bookmark, DatabaseStatus};
+use crate::instance_lock::{self, InstanceStatus};
+use crate::markdown::browser_bookmarks;
 use crate::state::AppState;
-use crate::utils::handle_error;
+use crate::utils::{handle_error, read_text_with_bom_detection, validate_path};
+use serde::Serialize;
+use std::collections::HashSet;
 use tauri::State;
 
+#[derive(Debug, Serialize)]
+pub struct BrowserBookmarkImport {
+    pub markdown: String,
+    pub links: usize,
+    pub folders: usize,
+    /// `file:` bookmarks added to the bookmark table
+    pub local_files_added: usize,
+}
+
 #[tauri::command]
 pub fn export_bookmarks(state: State<'_, AppState>) -> Result<Vec<Bookmark>, String> {
     state
@@ -24,6 +37,100 @@ pub fn import_bookmarks(
     Ok(count)
 }
 
+/// Converts a browser bookmarks export (the HTML file every browser writes) into a markdown
+/// document. With `add_local_files`, bookmarks of files on this machine also go into the
+/// bookmark table, keeping their browser folders; paths already bookmarked are left alone.
+#[tauri::command]
+pub async fn import_browser_bookmarks(
+    state: State<'_, AppState>,
+    path: String,
+    add_local_files: Option<bool>,
+) -> Result<BrowserBookmarkImport, String> {
+    let start = std::time::Instant::now();
+    validate_path(&path)?;
+
+    let raw = tokio::fs::read(&path)
+        .await
+        .map_err(|e| handle_error(Some(&path), "read browser bookmarks", e))?;
+    let source_name = std::path::Path::new(&path)
+        .file_name()
+        .map_or_else(|| path.clone(), |n| n.to_string_lossy().into_owned());
+
+    let (markdown, summary, local_files) = tokio::task::spawn_blocking(move || {
+        let html = read_text_with_bom_detection(&raw);
+        let root = browser_bookmarks::parse(&html);
+        let (markdown, summary) = browser_bookmarks::to_markdown(&root, &source_name);
+        let local_files = if add_local_files.unwrap_or(false) {
+            browser_bookmarks::local_files(&root)
+        } else {
+            Vec::new()
+        };
+        (markdown, summary, local_files)
+    })
+    .await
+    .map_err(|e| format!("Bookmark import task failed: {}", e))?;
+
+    if summary.links == 0 {
+        log::warn!("No bookmarks found in {}", path);
+        return Err("No bookmarks found; expected a browser bookmarks HTML export".to_string());
+    }
+
+    let mut local_files_added = 0;
+    if !local_files.is_empty() {
+        let existing: HashSet<String> = state
+            .db
+            .get_all_bookmarks()
+            .map_err(|e| handle_error(Some("bookmarks"), "retrieve bookmarks", e))?
+            .into_iter()
+            .map(|b| b.path)
+            .collect();
+        let created = chrono::Local::now();
+        let mut seen = HashSet::new();
+        let bookmarks: Vec<Bookmark> = local_files
+            .into_iter()
+            .filter_map(|file| {
+                let file_path = file.path.to_string_lossy().into_owned();
+                if existing.contains(&file_path) || !seen.insert(file_path.clone()) {
+                    return None;
+                }
+                Some(Bookmark {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    path: file_path,
+                    title: file.title,
+                    tags: Vec::new(),
+                    created: file
+                        .added
+                        .unwrap_or(created)
+                        .format("%Y%m%d / %H%M%S")
+                        .to_string(),
+                    last_accessed: None,
+                    folder: file.folder,
+                })
+            })
+            .collect();
+        state
+            .db
+            .import_bookmarks(&bookmarks)
+            .map_err(|e| handle_error(Some("bookmarks"), "import bookmarks", e))?;
+        local_files_added = bookmarks.len();
+    }
+
+    log::info!(
+        "[Data] import_browser_bookmarks | duration={:?} | links={} | folders={} | local_files_added={}",
+        start.elapsed(),
+        summary.links,
+        summary.folders,
+        local_files_added
+    );
+
+    Ok(BrowserBookmarkImport {
+        markdown,
+        links: summary.links,
+        folders: summary.folders,
+        local_files_added,
+    })
+}
+
 #[tauri::command]
 pub fn export_recent_files(state: State<'_, AppState>) -> Result<Vec<String>, String> {
     state
@@ -57,3 +164,58 @@ pub fn delete_orphan_files(state: State<'_, AppState>) -> Result<usize, String>
         .map_err(|e| handle_error(Some("bookmarks"), "delete orphan bookmarks", e))?;
     Ok(recent + bookmarks)
 }
+
+#[tauri::command]
+pub fn get_database_status(state: State<'_, AppState>) -> Result<DatabaseStatus, String> {
+    state
+        .db
+        .status()
+        .map_err(|e| handle_error(Some("database"), "read database status", e))
+}
+
+/// Whether this instance owns the data folder, and which instance does if not
+#[tauri::command]
+pub fn get_instance_status(state: State<'_, AppState>) -> Result<InstanceStatus, String> {
+    Ok(state
+        .instance
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone())
+}
+
+/// Takes over the data folder lock once the instance holding it has exited. The database
+/// stays read-only until restart, which the returned status reports.
+#[tauri::command]
+pub fn recover_instance_lock(state: State<'_, AppState>) -> Result<InstanceStatus, String> {
+    let mut status = state.instance.lock().unwrap_or_else(|e| e.into_inner());
+    let was_owner = status.owner;
+    instance_lock::recover(&mut status).map_err(|e| {
+        log::warn!("Instance lock recovery refused: {}", e);
+        e
+    })?;
+    if !was_owner {
+        log::info!("Recovered instance lock {}", status.lock_path);
+        tauri::async_runtime::spawn(instance_lock::run_heartbeat(std::path::PathBuf::from(
+            &status.lock_path,
+        )));
+    }
+    Ok(status.clone())
+}
+
+/// Copies the session database to `path`. Works while the database is read-only, so
+/// users on an older build can still take their data with them.
+#[tauri::command]
+pub fn export_database(state: State<'_, AppState>, path: String) -> Result<(), String> {
+    validate_path(&path)?;
+
+    let target = std::path::PathBuf::from(&path);
+    if target.exists() {
+        log::warn!("Attempted to export database over existing file: {}", path);
+        return Err("A file with that name already exists".to_string());
+    }
+
+    state
+        .db
+        .export_copy(&target)
+        .map_err(|e| handle_error(Some(&path), "export database", e))
+}
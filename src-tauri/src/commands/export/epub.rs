@@ -0,0 +1,41 @@
+use crate::commands::settings::read_diagram_settings;
+use crate::export::epub::{self, EpubOptions};
+use crate::markdown::front_matter;
+use crate::utils::handle_error;
+
+/// Writes the document as an EPUB book, one chapter per H1/H2 section, with a table of
+/// contents and local images embedded. The front matter `export` flavor applies unless
+/// the caller sets one.
+#[tauri::command]
+pub async fn export_to_epub(
+    app_handle: tauri::AppHandle,
+    path: String,
+    content: String,
+    options: Option<EpubOptions>,
+) -> Result<(), String> {
+    crate::utils::validate_path(&path)?;
+
+    let start = std::time::Instant::now();
+    let mut options = options.unwrap_or_default();
+    if let Some(config) = front_matter::export_config(&content) {
+        options.flavor = options.flavor.or(config.flavor);
+    }
+    options.diagrams = read_diagram_settings(&app_handle).await;
+    let bytes = tokio::task::spawn_blocking(move || epub::build_epub(&content, &options))
+        .await
+        .map_err(|e| format!("EPUB task failed: {}", e))?
+        .map_err(|e| handle_error(Some(&path), "build EPUB", e))?;
+
+    let path_buf = std::path::PathBuf::from(&path);
+    crate::utils::atomic_write(&path_buf, &bytes)
+        .await
+        .map_err(|e| handle_error(Some(&path), "write EPUB file", e))?;
+
+    log::info!(
+        "[Export] export_to_epub | duration={:?} | size={} bytes | path={}",
+        start.elapsed(),
+        bytes.len(),
+        path
+    );
+    Ok(())
+}
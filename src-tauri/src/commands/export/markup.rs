@@ -0,0 +1,69 @@
+use super::resolve_theme_css;
+use crate::commands::settings::read_diagram_settings;
+use crate::export::presets::ExportFormat;
+use crate::export::{self, html};
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::security::SanitizationReport;
+use crate::utils::{IntoTauriError, handle_error};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Converts a document to MediaWiki markup or reStructuredText, for copying or saving.
+#[tauri::command]
+pub async fn convert_to_markup(
+    content: String,
+    format: ExportFormat,
+    flavor: Option<String>,
+) -> Result<String, String> {
+    let start = std::time::Instant::now();
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    let markup = tokio::task::spawn_blocking(move || export::to_markup(format, &content, flavor))
+        .await
+        .map_err(|e| format!("Convert task failed: {}", e))?
+        .ok_or_else(|| format!("'{}' is not a text markup format", format.extension()))?;
+
+    log::info!(
+        "[Export] convert_to_markup | duration={:?} | format={} | size={} bytes",
+        start.elapsed(),
+        format.extension(),
+        markup.len()
+    );
+    Ok(markup)
+}
+
+/// Renders the document and puts it on the clipboard as HTML, with the markdown as the
+/// plain-text alternative, so pasting into a mail client keeps the formatting. Styles are
+/// inlined because mail clients drop stylesheets.
+#[tauri::command]
+pub async fn copy_as_html(
+    app_handle: tauri::AppHandle,
+    content: String,
+    flavor: Option<String>,
+    theme: Option<String>,
+) -> Result<SanitizationReport, String> {
+    let start = std::time::Instant::now();
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    let theme_css = resolve_theme_css(&app_handle, theme.as_deref()).await;
+    let diagrams = read_diagram_settings(&app_handle).await;
+
+    let source = content.clone();
+    let (fragment, report) = tokio::task::spawn_blocking(move || {
+        html::render_body_with_report(&source, flavor, diagrams)
+            .map(|(body, report)| (html::inline_styles(&body, &theme_css), report))
+    })
+    .await
+    .map_err(|e| format!("Render task failed: {}", e))?
+    .to_tauri_result()?;
+
+    let size = fragment.len();
+    app_handle
+        .clipboard()
+        .write_html(fragment, Some(content))
+        .map_err(|e| handle_error(None, "copy HTML to clipboard", e))?;
+
+    log::info!(
+        "[Export] copy_as_html | duration={:?} | size={} bytes",
+        start.elapsed(),
+        size
+    );
+    Ok(report)
+}
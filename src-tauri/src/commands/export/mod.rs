@@ -0,0 +1,82 @@
+pub mod epub;
+pub mod markup;
+pub mod pdf;
+pub mod presets;
+pub mod preview;
+pub mod slides;
+pub mod tabs;
+pub mod watch;
+
+use crate::commands::settings::{get_theme_css, read_setting};
+use crate::export::html;
+use crate::export::pdf::{FontFile, PdfOptions, build_pdf, needs_embedded_font};
+use crate::markdown::security::SanitizationReport;
+use crate::utils::handle_error;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct ExportInput {
+    pub content: String,
+    pub title: String,
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// A written export and what rendering left out of it compared with the source
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportOutcome {
+    pub path: String,
+    pub report: SanitizationReport,
+}
+
+async fn generate_pdf(content: String, options: PdfOptions) -> Result<Vec<u8>, String> {
+    tokio::task::spawn_blocking(move || build_pdf(&content, &options))
+        .await
+        .map_err(|e| format!("PDF task failed: {}", e))?
+        .map_err(|e| format!("Failed to generate PDF: {}", e))
+}
+
+/// The font to embed in a PDF. An explicit font file always wins. Otherwise text the
+/// standard fonts can't draw falls back to the first installed editor font, and plain
+/// Latin text keeps the standard fonts.
+async fn pdf_font(
+    app_handle: &tauri::AppHandle,
+    font_path: Option<String>,
+    content: &str,
+) -> Result<Option<FontFile>, String> {
+    if let Some(font_path) = font_path {
+        crate::utils::validate_path(&font_path)?;
+        let path = std::path::PathBuf::from(&font_path);
+        let font = tokio::task::spawn_blocking(move || FontFile::load(&path))
+            .await
+            .map_err(|e| format!("Font task failed: {}", e))?
+            .map_err(|e| handle_error(Some(&font_path), "load PDF font", e))?;
+        return Ok(Some(font));
+    }
+    if !needs_embedded_font(content) {
+        return Ok(None);
+    }
+    let Some(families) = read_setting::<String>(app_handle, "editorFontFamily").await else {
+        return Ok(None);
+    };
+    let font = tokio::task::spawn_blocking(move || FontFile::from_family_list(&families))
+        .await
+        .map_err(|e| format!("Font task failed: {}", e))?;
+    if font.is_none() {
+        log::warn!("[Export] No installed editor font found for non-Latin PDF text");
+    }
+    Ok(font)
+}
+
+/// Theme CSS for export: bundled themes first, then custom themes from the Themes folder
+async fn resolve_theme_css(app_handle: &tauri::AppHandle, theme: Option<&str>) -> String {
+    let Some(theme) = theme.filter(|t| !t.is_empty()) else {
+        return String::new();
+    };
+    if let Some(css) = html::builtin_theme_css(theme) {
+        return css.to_string();
+    }
+    get_theme_css(app_handle.clone(), theme.to_string())
+        .await
+        .unwrap_or_default()
+}
@@ -0,0 +1,64 @@
+use super::{generate_pdf, pdf_font};
+use crate::commands::settings::read_diagram_settings;
+use crate::export::pdf::PdfOptions;
+use crate::export::presets::PageSetup;
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::front_matter::{self, ExportConfig};
+use crate::utils::handle_error;
+
+/// Paper and orientation from front matter over the default page setup
+fn page_setup_from(config: &ExportConfig) -> PageSetup {
+    let mut page_setup = PageSetup::default();
+    if let Some(paper) = &config.paper {
+        page_setup.paper = paper.clone();
+    }
+    if let Some(landscape) = config.landscape {
+        page_setup.landscape = landscape;
+    }
+    page_setup
+}
+
+/// Writes the document as a PDF. `source_path` is the document's own path, which relative
+/// image paths resolve against. `font_path` is a TrueType/OpenType file to embed; without
+/// it non-Latin text uses the editor font. Paper, orientation and flavor come from the
+/// front matter `export` block.
+#[tauri::command]
+pub async fn export_to_pdf(
+    app_handle: tauri::AppHandle,
+    path: String,
+    content: String,
+    title: String,
+    source_path: Option<String>,
+    font_path: Option<String>,
+) -> Result<(), String> {
+    crate::utils::validate_path(&path)?;
+
+    let start = std::time::Instant::now();
+
+    let config = front_matter::export_config(&content).unwrap_or_default();
+    let options = PdfOptions {
+        title: title.clone(),
+        flavor: MarkdownFlavor::from_option_str(config.flavor.clone()),
+        page_setup: page_setup_from(&config),
+        base_path: source_path,
+        font: pdf_font(&app_handle, font_path, &content).await?,
+        diagrams: read_diagram_settings(&app_handle).await,
+    };
+    let pdf_bytes = generate_pdf(content, options).await?;
+
+    let path_buf = std::path::PathBuf::from(&path);
+    crate::utils::atomic_write(&path_buf, &pdf_bytes)
+        .await
+        .map_err(|e| handle_error(Some(&path), "write PDF file", e))?;
+
+    let duration = start.elapsed();
+    log::info!(
+        "[Export] export_to_pdf | duration={:?} | size={} bytes | title={} | path={}",
+        duration,
+        pdf_bytes.len(),
+        title,
+        path
+    );
+
+    Ok(())
+}
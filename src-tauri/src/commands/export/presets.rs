@@ -0,0 +1,322 @@
+use super::{ExportInput, ExportOutcome, generate_pdf, pdf_font, resolve_theme_css};
+use crate::commands::settings::{read_diagram_settings, read_setting, write_setting};
+use crate::export::pdf::PdfOptions;
+use crate::export::presets::{self, ExportFormat, ExportPreset, PRESETS_SETTING_KEY, PageSetup};
+use crate::export::{self, html};
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::front_matter::{self, ExportConfig};
+use crate::markdown::security::SanitizationReport;
+use crate::markdown::summary;
+use crate::utils::{IntoTauriError, handle_error};
+use std::path::{Component, Path};
+
+// Search engines show roughly this much of a meta description
+const META_DESCRIPTION_CHARS: usize = 160;
+
+pub(super) async fn load_presets(app_handle: &tauri::AppHandle) -> Vec<ExportPreset> {
+    read_setting(app_handle, PRESETS_SETTING_KEY)
+        .await
+        .unwrap_or_default()
+}
+
+async fn render_html_export(
+    app_handle: &tauri::AppHandle,
+    preset: &ExportPreset,
+    input: &ExportInput,
+) -> Result<(String, SanitizationReport), String> {
+    let template = match preset.template.as_deref().filter(|t| !t.is_empty()) {
+        Some(template_path) => {
+            crate::utils::validate_path(template_path)?;
+            tokio::fs::read_to_string(template_path)
+                .await
+                .map_err(|e| handle_error(Some(template_path), "read export template", e))?
+        },
+        None => html::DEFAULT_TEMPLATE.to_string(),
+    };
+
+    let css = format!(
+        "{}{}{}",
+        html::BASE_CSS,
+        resolve_theme_css(app_handle, preset.theme.as_deref()).await,
+        preset.page_setup.to_css()
+    );
+
+    let flavor = MarkdownFlavor::from_option_str(preset.flavor.clone());
+    let content = input.content.clone();
+    let diagrams = read_diagram_settings(app_handle).await;
+    let (body, report, description) = tokio::task::spawn_blocking(move || {
+        let description = summary::extract_summary(&content, META_DESCRIPTION_CHARS, flavor);
+        html::render_body_with_report(&content, flavor, diagrams)
+            .map(|(body, report)| (body, report, description.summary))
+    })
+    .await
+    .map_err(|e| format!("Render task failed: {}", e))?
+    .to_tauri_result()?;
+
+    let document = html::build_document(&template, &input.title, &description, &css, &body);
+    Ok((document, report))
+}
+
+/// Exports a document with a preset, writing to the preset's output folder, and
+/// returns the path of the written file with the sanitization report.
+pub async fn export_preset_to_file(
+    app_handle: &tauri::AppHandle,
+    preset: &ExportPreset,
+    input: &ExportInput,
+) -> Result<ExportOutcome, String> {
+    let output_path = presets::resolve_output_path(preset, input.path.as_deref(), &input.title)
+        .to_tauri_result()?;
+    let output_str = output_path.to_string_lossy().to_string();
+    crate::utils::validate_path(&output_str)?;
+
+    let (bytes, report) = match preset.format {
+        ExportFormat::Html => {
+            let (document, report) = render_html_export(app_handle, preset, input).await?;
+            (document.into_bytes(), report)
+        },
+        ExportFormat::Pdf => {
+            let options = PdfOptions {
+                title: input.title.clone(),
+                flavor: MarkdownFlavor::from_option_str(preset.flavor.clone()),
+                page_setup: preset.page_setup.clone(),
+                base_path: input.path.clone(),
+                font: pdf_font(app_handle, None, &input.content).await?,
+                diagrams: read_diagram_settings(app_handle).await,
+            };
+            let bytes = generate_pdf(input.content.clone(), options).await?;
+            (bytes, SanitizationReport::default())
+        },
+        format @ (ExportFormat::MediaWiki | ExportFormat::Rst) => {
+            let content = input.content.clone();
+            let flavor = MarkdownFlavor::from_option_str(preset.flavor.clone());
+            let markup =
+                tokio::task::spawn_blocking(move || export::to_markup(format, &content, flavor))
+                    .await
+                    .map_err(|e| format!("Convert task failed: {}", e))?
+                    .unwrap_or_default();
+            (markup.into_bytes(), SanitizationReport::default())
+        },
+    };
+
+    if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent).await.map_err(|e| {
+            handle_error(Some(&parent.to_string_lossy()), "create export folder", e)
+        })?;
+    }
+
+    crate::utils::atomic_write(&output_path, &bytes)
+        .await
+        .map_err(|e| handle_error(Some(&output_str), "write export", e))?;
+
+    Ok(ExportOutcome {
+        path: output_str,
+        report,
+    })
+}
+
+#[tauri::command]
+pub async fn list_export_presets(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<ExportPreset>, String> {
+    Ok(load_presets(&app_handle).await)
+}
+
+#[tauri::command]
+pub async fn save_export_preset(
+    app_handle: tauri::AppHandle,
+    preset: ExportPreset,
+) -> Result<(), String> {
+    let mut all = load_presets(&app_handle).await;
+    presets::upsert_preset(&mut all, preset).to_tauri_result()?;
+    write_setting(&app_handle, PRESETS_SETTING_KEY, &all).await
+}
+
+#[tauri::command]
+pub async fn delete_export_preset(
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<bool, String> {
+    let mut all = load_presets(&app_handle).await;
+    let before = all.len();
+    all.retain(|p| !p.name.eq_ignore_ascii_case(name.trim()));
+    if all.len() == before {
+        return Ok(false);
+    }
+    write_setting(&app_handle, PRESETS_SETTING_KEY, &all).await?;
+    Ok(true)
+}
+
+fn parse_format(format: &str) -> Result<ExportFormat, String> {
+    match format.trim().to_ascii_lowercase().as_str() {
+        "html" => Ok(ExportFormat::Html),
+        "pdf" => Ok(ExportFormat::Pdf),
+        "mediawiki" | "wiki" => Ok(ExportFormat::MediaWiki),
+        "rst" | "restructuredtext" => Ok(ExportFormat::Rst),
+        other => Err(format!(
+            "Unsupported export format in front matter: '{}'",
+            other
+        )),
+    }
+}
+
+/// Resolves a template or output folder named in front matter against the document's
+/// folder. Front matter comes with the document and can't be trusted, so only paths
+/// inside that folder are accepted; absolute paths and `..` are refused. An output
+/// folder may still start with `{dir}` and use `{name}` and `{date}`.
+fn front_matter_path(
+    value: &str,
+    source_path: Option<&str>,
+    field: &str,
+) -> Result<String, String> {
+    let relative = value
+        .strip_prefix("{dir}")
+        .map_or(value, |rest| rest.trim_start_matches(['/', '\\']));
+    crate::utils::validate_path(relative)?;
+    let inside = !relative.contains("{dir}")
+        && Path::new(relative)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        return Err(format!(
+            "Front matter '{}' must be a path inside the document's folder",
+            field
+        ));
+    }
+    let dir = source_path
+        .and_then(|p| Path::new(p).parent())
+        .filter(|d| !d.as_os_str().is_empty())
+        .ok_or_else(|| format!("Save the document before using front matter '{}'", field))?;
+
+    let resolved = dir.join(relative);
+    // A symlink inside the folder could still point out of it
+    if let (Ok(target), Ok(dir)) = (dunce::canonicalize(&resolved), dunce::canonicalize(dir))
+        && !target.starts_with(&dir)
+    {
+        return Err(format!(
+            "Front matter '{}' must be a path inside the document's folder",
+            field
+        ));
+    }
+    Ok(resolved.to_string_lossy().to_string())
+}
+
+/// Builds the preset a document's front matter asks for: the named saved preset (if any)
+/// with every field the front matter sets taking precedence. A template or output folder
+/// from front matter must lie in the document's folder.
+fn preset_from_front_matter(
+    config: &ExportConfig,
+    presets: &[ExportPreset],
+    source_path: Option<&str>,
+) -> Result<ExportPreset, String> {
+    let mut preset = match config.preset.as_deref() {
+        Some(name) => presets::find_preset(presets, name)
+            .cloned()
+            .ok_or_else(|| format!("Export preset '{}' not found", name))?,
+        None => ExportPreset {
+            name: "Front matter".to_string(),
+            format: ExportFormat::Html,
+            theme: None,
+            page_setup: PageSetup::default(),
+            template: None,
+            output_folder: None,
+            flavor: None,
+        },
+    };
+
+    if let Some(format) = config.format.as_deref() {
+        preset.format = parse_format(format)?;
+    }
+    if let Some(paper) = &config.paper {
+        preset.page_setup.paper = paper.clone();
+    }
+    if let Some(landscape) = config.landscape {
+        preset.page_setup.landscape = landscape;
+    }
+    preset.theme = config.theme.clone().or(preset.theme);
+    if let Some(template) = &config.template {
+        preset.template = Some(front_matter_path(template, source_path, "template")?);
+    }
+    if let Some(folder) = &config.output_folder {
+        preset.output_folder = Some(front_matter_path(folder, source_path, "outputFolder")?);
+    }
+    preset.flavor = config.flavor.clone().or(preset.flavor);
+    Ok(preset)
+}
+
+/// Export settings declared in the document's front matter, for prefilling export dialogs.
+#[tauri::command]
+pub async fn get_export_config(content: String) -> Result<Option<ExportConfig>, String> {
+    Ok(front_matter::export_config(&content))
+}
+
+/// Exports a document using the settings in its front matter `export` block and returns
+/// the path of the written file with what was stripped from the HTML.
+#[tauri::command]
+pub async fn export_document(
+    app_handle: tauri::AppHandle,
+    input: ExportInput,
+) -> Result<ExportOutcome, String> {
+    let start = std::time::Instant::now();
+
+    let config = front_matter::export_config(&input.content)
+        .ok_or("Document has no export settings in its front matter")?;
+    let preset = preset_from_front_matter(
+        &config,
+        &load_presets(&app_handle).await,
+        input.path.as_deref(),
+    )?;
+
+    let outcome = export_preset_to_file(&app_handle, &preset, &input).await?;
+
+    log::info!(
+        "[Export] export_document | duration={:?} | format={} | path={}",
+        start.elapsed(),
+        preset.format.extension(),
+        outcome.path
+    );
+
+    Ok(outcome)
+}
+
+#[tauri::command]
+pub async fn export_with_preset(
+    app_handle: tauri::AppHandle,
+    preset: String,
+    input: ExportInput,
+) -> Result<ExportOutcome, String> {
+    let start = std::time::Instant::now();
+
+    let all = load_presets(&app_handle).await;
+    let mut preset = presets::find_preset(&all, &preset)
+        .cloned()
+        .ok_or_else(|| format!("Export preset '{}' not found", preset))?;
+
+    // Front matter fills in what the preset leaves open; the chosen preset still wins
+    if let Some(config) = front_matter::export_config(&input.content) {
+        let source_path = input.path.as_deref();
+        if preset.template.is_none()
+            && let Some(template) = &config.template
+        {
+            preset.template = Some(front_matter_path(template, source_path, "template")?);
+        }
+        if preset.output_folder.is_none()
+            && let Some(folder) = &config.output_folder
+        {
+            preset.output_folder = Some(front_matter_path(folder, source_path, "outputFolder")?);
+        }
+        preset.theme = preset.theme.or(config.theme);
+        preset.flavor = preset.flavor.or(config.flavor);
+    }
+
+    let outcome = export_preset_to_file(&app_handle, &preset, &input).await?;
+
+    let duration = start.elapsed();
+    log::info!(
+        "[Export] export_with_preset | duration={:?} | preset={} | path={}",
+        duration,
+        preset.name,
+        outcome.path
+    );
+
+    Ok(outcome)
+}
@@ -0,0 +1,97 @@
+use crate::commands::settings::{read_diagram_settings, read_setting};
+use crate::export::html;
+use crate::markdown::config::MarkdownFlavor;
+use crate::utils::{IntoTauriError, handle_error};
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+
+const LIVE_PREVIEW_EVENT: &str = "live-preview-updated";
+const LIVE_PREVIEW_FILE: &str = "live-preview.html";
+
+#[derive(Debug, Clone, Serialize)]
+struct LivePreviewEvent {
+    path: String,
+    source_path: Option<String>,
+}
+
+/// Where the live preview goes: the `livePreviewExportPath` setting, or a stable file in
+/// the app's local data folder so external tools can keep pointing at the same path.
+async fn live_preview_path(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    if let Some(path) = read_setting::<String>(app_handle, "livePreviewExportPath")
+        .await
+        .filter(|p| !p.trim().is_empty())
+    {
+        crate::utils::validate_path(&path)?;
+        return Ok(std::path::PathBuf::from(path));
+    }
+
+    let dir = app_handle
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| handle_error(None, "get app local data directory", e))?
+        .join("Preview");
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| handle_error(Some(&dir.to_string_lossy()), "create preview directory", e))?;
+    Ok(dir.join(LIVE_PREVIEW_FILE))
+}
+
+/// Writes the rendered HTML of the active tab to the live preview file when
+/// `livePreviewExport` is enabled, then emits `live-preview-updated` with its path.
+/// Returns the written path, or `None` when the option is off.
+#[tauri::command]
+pub async fn write_live_preview(
+    app_handle: tauri::AppHandle,
+    content: String,
+    title: String,
+    path: Option<String>,
+    flavor: Option<String>,
+) -> Result<Option<String>, String> {
+    if !read_setting::<bool>(&app_handle, "livePreviewExport")
+        .await
+        .unwrap_or(false)
+    {
+        return Ok(None);
+    }
+
+    let start = std::time::Instant::now();
+    let output_path = live_preview_path(&app_handle).await?;
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    let diagrams = read_diagram_settings(&app_handle).await;
+
+    let body = tokio::task::spawn_blocking(move || html::render_body(&content, flavor, diagrams))
+        .await
+        .map_err(|e| format!("Render task failed: {}", e))?
+        .to_tauri_result()?;
+    let document = html::build_document(html::DEFAULT_TEMPLATE, &title, "", html::BASE_CSS, &body);
+
+    crate::utils::atomic_write(&output_path, document.as_bytes())
+        .await
+        .map_err(|e| {
+            handle_error(
+                Some(&output_path.to_string_lossy()),
+                "write live preview",
+                e,
+            )
+        })?;
+
+    let output = output_path.to_string_lossy().to_string();
+    if let Err(e) = app_handle.emit(
+        LIVE_PREVIEW_EVENT,
+        &LivePreviewEvent {
+            path: output.clone(),
+            source_path: path,
+        },
+    ) {
+        log::warn!("Failed to emit live preview update: {}", e);
+    }
+
+    log::debug!(
+        "[Export] write_live_preview | duration={:?} | size={} bytes | path={}",
+        start.elapsed(),
+        document.len(),
+        output
+    );
+
+    Ok(Some(output))
+}
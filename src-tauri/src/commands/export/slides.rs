@@ -0,0 +1,59 @@
+use super::resolve_theme_css;
+use crate::commands::settings::read_diagram_settings;
+use crate::export::slides::{self, SlideOptions};
+use crate::markdown::front_matter;
+use crate::markdown::security::SanitizationReport;
+use crate::utils::handle_error;
+use serde::Serialize;
+
+/// A written slide deck
+#[derive(Debug, Clone, Serialize)]
+pub struct SlidesExport {
+    pub slide_count: usize,
+    pub report: SanitizationReport,
+}
+
+/// Writes the document as a standalone HTML slide deck, cut at `---` rules or H1/H2
+/// headings and styled with the given theme. Arrow keys, clicks and `#/n` URLs move
+/// between slides; printing gives one slide per page. The front matter `export` theme and
+/// flavor apply unless the caller sets them.
+#[tauri::command]
+pub async fn export_to_slides(
+    app_handle: tauri::AppHandle,
+    path: String,
+    content: String,
+    options: Option<SlideOptions>,
+) -> Result<SlidesExport, String> {
+    crate::utils::validate_path(&path)?;
+
+    let start = std::time::Instant::now();
+    let mut options = options.unwrap_or_default();
+    if let Some(config) = front_matter::export_config(&content) {
+        options.theme = options.theme.or(config.theme);
+        options.flavor = options.flavor.or(config.flavor);
+    }
+    options.diagrams = read_diagram_settings(&app_handle).await;
+    let theme_css = resolve_theme_css(&app_handle, options.theme.as_deref()).await;
+    let deck =
+        tokio::task::spawn_blocking(move || slides::build_deck(&content, &options, &theme_css))
+            .await
+            .map_err(|e| format!("Slides task failed: {}", e))?
+            .map_err(|e| handle_error(Some(&path), "build slides", e))?;
+
+    let path_buf = std::path::PathBuf::from(&path);
+    crate::utils::atomic_write(&path_buf, deck.html.as_bytes())
+        .await
+        .map_err(|e| handle_error(Some(&path), "write slides file", e))?;
+
+    log::info!(
+        "[Export] export_to_slides | duration={:?} | slides={} | size={} bytes | path={}",
+        start.elapsed(),
+        deck.slide_count,
+        deck.html.len(),
+        path
+    );
+    Ok(SlidesExport {
+        slide_count: deck.slide_count,
+        report: deck.report,
+    })
+}
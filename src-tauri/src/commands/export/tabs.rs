@@ -0,0 +1,167 @@
+use super::{ExportInput, generate_pdf, pdf_font, resolve_theme_css};
+use crate::commands::guard::CommandError;
+use crate::commands::jobs::Job;
+use crate::commands::settings::read_diagram_settings;
+use crate::export::pdf::{self, PdfOptions};
+use crate::export::presets::{ExportFormat, PageSetup};
+use crate::export::{self, html};
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::front_matter;
+use crate::markdown::security::SanitizationReport;
+use crate::utils::{IntoTauriError, handle_error};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TabsExportOptions {
+    pub format: ExportFormat,
+    pub title: String,
+    pub toc: bool,
+    pub theme: Option<String>,
+    pub page_setup: PageSetup,
+    pub flavor: Option<String>,
+}
+
+impl Default for TabsExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Html,
+            title: "Open Tabs".to_string(),
+            toc: true,
+            theme: None,
+            page_setup: PageSetup::default(),
+            flavor: None,
+        }
+    }
+}
+
+/// Renders several documents (all open tabs, or a selection) into one HTML or PDF file,
+/// one section per tab with a table of contents and a page break between tabs. Returns
+/// what was stripped from the HTML across all tabs. With `job_id` the export can be
+/// stopped by `cancel_job` and reports `job-progress` per rendered tab; nothing is written
+/// once it is cancelled.
+#[tauri::command]
+pub async fn export_tabs(
+    app_handle: tauri::AppHandle,
+    path: String,
+    tabs: Vec<ExportInput>,
+    options: Option<TabsExportOptions>,
+    job_id: Option<String>,
+) -> Result<SanitizationReport, CommandError> {
+    crate::utils::validate_path(&path)?;
+    if tabs.is_empty() {
+        return Err("No tabs to export".to_string().into());
+    }
+    let job = Job::start(&app_handle, "export_tabs", job_id);
+
+    let start = std::time::Instant::now();
+    let options = options.unwrap_or_default();
+    let flavor = MarkdownFlavor::from_option_str(options.flavor.clone());
+    let tab_count = tabs.len();
+
+    let mut report = SanitizationReport::default();
+    let bytes = match options.format {
+        ExportFormat::Html => {
+            let css = format!(
+                "{}{}{}{}",
+                html::BASE_CSS,
+                resolve_theme_css(&app_handle, options.theme.as_deref()).await,
+                options.page_setup.to_css(),
+                html::TAB_SECTIONS_CSS
+            );
+            let toc = options.toc;
+            let diagrams = read_diagram_settings(&app_handle).await;
+            let render = job.clone();
+            let rendered = tokio::task::spawn_blocking(move || {
+                let mut tabs_report = SanitizationReport::default();
+                let sections = tabs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, tab)| {
+                        if render.is_cancelled() {
+                            anyhow::bail!("Export cancelled");
+                        }
+                        render.progress(index, tab_count, Some(tab.title.clone()));
+                        let (body, report) =
+                            html::render_body_with_report(&tab.content, flavor, diagrams.clone())?;
+                        tabs_report.merge(&report);
+                        Ok((tab.title, body))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                Ok::<_, anyhow::Error>((html::build_tab_sections(&sections, toc), tabs_report))
+            })
+            .await
+            .map_err(|e| format!("Render task failed: {}", e))?;
+            job.check()?;
+            let (body, tabs_report) = rendered.to_tauri_result()?;
+            report = tabs_report;
+
+            html::build_document(html::DEFAULT_TEMPLATE, &options.title, "", &css, &body)
+                .into_bytes()
+        },
+        // Each tab starts on a new page; tab front matter would otherwise end up
+        // mid-document as text
+        ExportFormat::Pdf => {
+            let mut parts = Vec::with_capacity(tabs.len() + 1);
+            if options.toc && tabs.len() > 1 {
+                let entries: Vec<String> = tabs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, tab)| format!("{}. {}", i + 1, tab.title))
+                    .collect();
+                parts.push(format!("# Contents\n\n{}", entries.join("\n")));
+            }
+            parts.extend(tabs.iter().map(|tab| {
+                let body = front_matter::split(&tab.content).map_or(&*tab.content, |fm| fm.body);
+                format!("# {}\n\n{}", tab.title, body.trim())
+            }));
+            let combined = parts.join(&format!("\n\n{}\n\n", pdf::PAGE_BREAK));
+            let pdf_options = PdfOptions {
+                title: options.title.clone(),
+                flavor,
+                page_setup: options.page_setup.clone(),
+                // Tabs come from different folders; only absolute image paths resolve
+                base_path: None,
+                font: pdf_font(&app_handle, None, &combined).await?,
+                diagrams: read_diagram_settings(&app_handle).await,
+            };
+            job.until_cancelled(generate_pdf(combined, pdf_options))
+                .await??
+        },
+        format @ (ExportFormat::MediaWiki | ExportFormat::Rst) => {
+            // Each tab becomes a top-level section; tab front matter would otherwise end up
+            // mid-document as text
+            let combined = tabs
+                .iter()
+                .map(|tab| {
+                    let body =
+                        front_matter::split(&tab.content).map_or(&*tab.content, |fm| fm.body);
+                    format!("# {}\n\n{}", tab.title, body.trim())
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            tokio::task::spawn_blocking(move || export::to_markup(format, &combined, flavor))
+                .await
+                .map_err(|e| format!("Convert task failed: {}", e))?
+                .unwrap_or_default()
+                .into_bytes()
+        },
+    };
+
+    job.check()?;
+    let path_buf = std::path::PathBuf::from(&path);
+    crate::utils::atomic_write(&path_buf, &bytes)
+        .await
+        .map_err(|e| handle_error(Some(&path), "write export", e))?;
+    job.progress(tab_count, tab_count, None);
+
+    log::info!(
+        "[Export] export_tabs | duration={:?} | tabs={} | size={} bytes | path={}",
+        start.elapsed(),
+        tab_count,
+        bytes.len(),
+        path
+    );
+
+    Ok(report)
+}
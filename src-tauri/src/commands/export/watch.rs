@@ -0,0 +1,158 @@
+use super::ExportInput;
+use super::presets::{export_preset_to_file, load_presets};
+use crate::export::presets;
+use crate::state::AppState;
+use serde::Serialize;
+use tauri::{Emitter, Manager, State};
+
+const EXPORT_WATCH_EVENT: &str = "export-watch-status";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportWatch {
+    pub path: String,
+    pub preset: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ExportWatchEvent {
+    path: String,
+    preset: String,
+    status: &'static str,
+    output_path: Option<String>,
+    error: Option<String>,
+}
+
+fn watch_key(path: &str) -> String {
+    dunce::canonicalize(path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+fn emit_watch_status(app_handle: &tauri::AppHandle, event: ExportWatchEvent) {
+    if let Err(e) = app_handle.emit(EXPORT_WATCH_EVENT, &event) {
+        log::warn!("Failed to emit export watch status: {}", e);
+    }
+}
+
+/// Called after a successful save; re-exports the document in the background when a
+/// watch is registered for its path.
+pub async fn trigger_export_watch(app_handle: &tauri::AppHandle, path: &str, content: &str) {
+    let key = watch_key(path);
+    let preset_name = {
+        let watches = app_handle.state::<AppState>().export_watches.lock().await;
+        match watches.get(&key) {
+            Some(name) => name.clone(),
+            None => return,
+        }
+    };
+
+    let app_handle = app_handle.clone();
+    let input = ExportInput {
+        content: content.to_string(),
+        title: std::path::Path::new(&key)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: Some(key.clone()),
+    };
+
+    tauri::async_runtime::spawn(async move {
+        let all = load_presets(&app_handle).await;
+        let result = match presets::find_preset(&all, &preset_name) {
+            Some(preset) => export_preset_to_file(&app_handle, preset, &input)
+                .await
+                .map(|outcome| outcome.path),
+            None => Err(format!("Export preset '{}' not found", preset_name)),
+        };
+
+        let (status, output_path, error) = match result {
+            Ok(output) => ("exported", Some(output), None),
+            Err(e) => ("failed", None, Some(e)),
+        };
+        log::info!(
+            "[Export] watch_export | status={} | preset={} | path={}",
+            status,
+            preset_name,
+            key
+        );
+        emit_watch_status(
+            &app_handle,
+            ExportWatchEvent {
+                path: key,
+                preset: preset_name,
+                status,
+                output_path,
+                error,
+            },
+        );
+    });
+}
+
+#[tauri::command]
+pub async fn watch_export(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    preset: String,
+) -> Result<(), String> {
+    crate::utils::validate_path(&path)?;
+    if presets::find_preset(&load_presets(&app_handle).await, &preset).is_none() {
+        return Err(format!("Export preset '{}' not found", preset));
+    }
+
+    let key = watch_key(&path);
+    state
+        .export_watches
+        .lock()
+        .await
+        .insert(key.clone(), preset.clone());
+
+    emit_watch_status(
+        &app_handle,
+        ExportWatchEvent {
+            path: key,
+            preset,
+            status: "started",
+            output_path: None,
+            error: None,
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_watch_export(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<bool, String> {
+    let key = watch_key(&path);
+    let removed = state.export_watches.lock().await.remove(&key);
+
+    if let Some(preset) = removed {
+        emit_watch_status(
+            &app_handle,
+            ExportWatchEvent {
+                path: key,
+                preset,
+                status: "stopped",
+                output_path: None,
+                error: None,
+            },
+        );
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+#[tauri::command]
+pub async fn list_export_watches(state: State<'_, AppState>) -> Result<Vec<ExportWatch>, String> {
+    let watches = state.export_watches.lock().await;
+    Ok(watches
+        .iter()
+        .map(|(path, preset)| ExportWatch {
+            path: path.clone(),
+            preset: preset.clone(),
+        })
+        .collect())
+}
@@ -1,8 +1,14 @@
-use crate::commands::settings::get_max_file_size_bytes;
-use crate::utils::{format_system_time, handle_error, validate_path};
-use encoding_rs::{Encoding, UTF_8};
+use crate::commands::jobs::Job;
+use crate::commands::markdown::notes::collect_note_files;
+use crate::commands::settings::{get_archive_path, get_max_file_size_bytes, read_setting};
+use crate::markdown::file_links::{FileMove, retarget_links};
+use crate::utils::{
+    decode_text, detect_line_ending, format_system_time, handle_error, path_identity,
+    validate_path, write_target,
+};
 use path_clean::PathClean;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::PathBuf;
 use tokio::fs;
 
@@ -11,12 +17,49 @@ pub struct FileMetadata {
     pub created: Option<String>,
     pub modified: Option<String>,
     pub size: u64,
+    /// The path itself is a symlink (or junction); the other fields describe its target
+    pub is_symlink: bool,
+    pub link_target: Option<String>,
+}
+
+/// How file commands treat symlinks. Reads always go through the link.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct SymlinkPolicy {
+    /// Save to the link's target; off replaces the link with a regular file
+    #[serde(rename = "followSymlinksOnWrite")]
+    pub follow_on_write: bool,
+    /// Treat a link and its target as the same document when matching open tabs
+    #[serde(rename = "resolveSymlinksForIdentity")]
+    pub resolve_for_identity: bool,
+}
+
+impl Default for SymlinkPolicy {
+    fn default() -> Self {
+        Self {
+            follow_on_write: true,
+            resolve_for_identity: true,
+        }
+    }
+}
+
+pub async fn symlink_policy(app_handle: &tauri::AppHandle) -> SymlinkPolicy {
+    let defaults = SymlinkPolicy::default();
+    SymlinkPolicy {
+        follow_on_write: read_setting(app_handle, "followSymlinksOnWrite")
+            .await
+            .unwrap_or(defaults.follow_on_write),
+        resolve_for_identity: read_setting(app_handle, "resolveSymlinksForIdentity")
+            .await
+            .unwrap_or(defaults.resolve_for_identity),
+    }
 }
 
 #[derive(Serialize)]
 pub struct FileContent {
     pub content: String,
     pub encoding: String,
+    pub has_bom: bool,
+    pub line_ending: String,
 }
 
 #[tauri::command]
@@ -55,31 +98,12 @@ pub async fn read_text_file(
         .await
         .map_err(|e| handle_error(Some(&path), "read file", e))?;
 
-    if let Some((encoding, _)) = Encoding::for_bom(&bytes) {
-        let (cow, _) = encoding.decode_with_bom_removal(&bytes);
-        return Ok(FileContent {
-            content: cow.into_owned(),
-            encoding: encoding.name().to_string(),
-        });
-    }
-
-    let (cow, _, had_errors) = UTF_8.decode(&bytes);
-    if !had_errors {
-        return Ok(FileContent {
-            content: cow.into_owned(),
-            encoding: "UTF-8".to_string(),
-        });
-    }
-
-    // Fallback detection (CPU bound, fine to run here or could use spawn_blocking if huge)
-    let mut detector = chardetng::EncodingDetector::new();
-    detector.feed(&bytes, true);
-    let detected_encoding = detector.guess(None, false);
-    let (cow, _, _) = detected_encoding.decode(&bytes);
-
+    let (cow, encoding, has_bom) = decode_text(&bytes);
     let result = FileContent {
+        line_ending: detect_line_ending(&cow).to_string(),
         content: cow.into_owned(),
-        encoding: detected_encoding.name().to_string(),
+        encoding: encoding.name().to_string(),
+        has_bom,
     };
 
     let duration = start.elapsed();
@@ -94,14 +118,28 @@ pub async fn read_text_file(
 }
 
 #[tauri::command]
-pub async fn write_text_file(path: String, content: String) -> Result<(), String> {
+pub async fn write_text_file(
+    app_handle: tauri::AppHandle,
+    path: String,
+    content: String,
+    encoding: Option<String>,
+    has_bom: Option<bool>,
+) -> Result<(), String> {
     let start = std::time::Instant::now();
     let content_size = content.len();
 
     validate_path(&path)?;
-    let path_buf = PathBuf::from(&path);
+    let policy = symlink_policy(&app_handle).await;
+    let path_buf = write_target(&PathBuf::from(&path), policy.follow_on_write).await;
 
-    crate::utils::atomic_write(&path_buf, content.as_bytes())
+    // Write back in the document's original encoding so files round-trip byte-faithfully
+    let bytes = crate::utils::encode_text(
+        &content,
+        encoding.as_deref().unwrap_or("UTF-8"),
+        has_bom.unwrap_or(false),
+    )?;
+
+    crate::utils::atomic_write(&path_buf, &bytes)
         .await
         .map_err(|e| handle_error(Some(&path), "save file", e))?;
 
@@ -113,6 +151,8 @@ pub async fn write_text_file(path: String, content: String) -> Result<(), String
         path
     );
 
+    crate::commands::export::watch::trigger_export_watch(&app_handle, &path, &content).await;
+
     Ok(())
 }
 
@@ -122,10 +162,23 @@ pub async fn get_file_metadata(path: String) -> Result<FileMetadata, String> {
     let metadata = fs::metadata(&path)
         .await
         .map_err(|e| handle_error(Some(&path), "get metadata", e))?;
+    let is_symlink = fs::symlink_metadata(&path)
+        .await
+        .is_ok_and(|m| m.file_type().is_symlink());
+    let link_target = if is_symlink {
+        dunce::canonicalize(&path)
+            .ok()
+            .map(|p| p.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
     Ok(FileMetadata {
         created: format_system_time(metadata.created()),
         modified: format_system_time(metadata.modified()),
         size: metadata.len(),
+        is_symlink,
+        link_target,
     })
 }
 
@@ -202,9 +255,14 @@ pub async fn resolve_path_relative(
 }
 
 #[tauri::command]
-pub async fn write_binary_file(path: String, content: Vec<u8>) -> Result<(), String> {
+pub async fn write_binary_file(
+    app_handle: tauri::AppHandle,
+    path: String,
+    content: Vec<u8>,
+) -> Result<(), String> {
     validate_path(&path)?;
-    let path_buf = PathBuf::from(&path);
+    let policy = symlink_policy(&app_handle).await;
+    let path_buf = write_target(&PathBuf::from(&path), policy.follow_on_write).await;
 
     crate::utils::atomic_write(&path_buf, &content)
         .await
@@ -214,8 +272,100 @@ pub async fn write_binary_file(path: String, content: Vec<u8>) -> Result<(), Str
     Ok(())
 }
 
+#[derive(Debug, Default, Serialize)]
+pub struct LinkUpdateReport {
+    pub links_updated: usize,
+    /// Other notes whose links were rewritten to the new location
+    pub files_updated: Vec<String>,
+    /// Notes linking to the file that were left alone because they have unsaved changes
+    pub skipped: Vec<String>,
+    /// The scan was stopped by `cancel_job` before any note was rewritten
+    pub cancelled: bool,
+}
+
+/// Rewrites links to a file that moved from `from` to `to` in the notes under `root`,
+/// including the moved note's own relative links. Notes in `skip_paths` are reported
+/// rather than written, so unsaved edits in open tabs aren't overwritten. A job cancelled
+/// during the scan leaves every note as it was.
+async fn update_links_after_move(
+    root: PathBuf,
+    from: PathBuf,
+    to: PathBuf,
+    skip_paths: &[String],
+    job: &Job,
+) -> Result<LinkUpdateReport, String> {
+    let skip: HashSet<String> = skip_paths.iter().map(|p| path_identity(p, false)).collect();
+    let moved_to = to.clone();
+    let scan = job.clone();
+    let rewrites = tokio::task::spawn_blocking(move || {
+        let moved = FileMove {
+            from: &from,
+            to: &to,
+        };
+        let files = collect_note_files(&root);
+        let total = files.len();
+        files
+            .into_iter()
+            .enumerate()
+            .take_while(|_| !scan.is_cancelled())
+            .filter_map(|(index, file)| {
+                scan.progress(index + 1, total, None);
+                let content = std::fs::read_to_string(&file).ok()?;
+                let new_dir = file.parent()?;
+                // The moved note's own links were written against its old folder
+                let (old_dir, identity) = if file == to {
+                    (from.parent()?, &from)
+                } else {
+                    (new_dir, &file)
+                };
+                let (content, count) = retarget_links(&content, old_dir, new_dir, moved);
+                let skipped = skip.contains(&path_identity(&identity.to_string_lossy(), false));
+                (count > 0).then_some((file, content, count, skipped))
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| format!("Link update task failed: {}", e))?;
+
+    let mut report = LinkUpdateReport::default();
+    if job.is_cancelled() {
+        log::info!("[Storage] Link update cancelled; no notes were rewritten");
+        report.cancelled = true;
+        return Ok(report);
+    }
+    for (file, content, count, skipped) in rewrites {
+        let file_str = file.to_string_lossy().to_string();
+        if skipped {
+            log::warn!("Not updating links in {}: it has unsaved changes", file_str);
+            report.skipped.push(file_str);
+            continue;
+        }
+        if let Err(e) = crate::utils::atomic_write(&file, content.as_bytes()).await {
+            log::warn!("Failed to update links in {}: {}", file_str, e);
+            continue;
+        }
+        report.links_updated += count;
+        if file != moved_to {
+            report.files_updated.push(file_str);
+        }
+    }
+    Ok(report)
+}
+
+/// Renames a file. With `update_links`, links and wikilinks to it in the notes under
+/// `workspace` (by default the file's folder) are rewritten to the new name; notes in
+/// `skip_paths`, typically open tabs with unsaved changes, are left alone and reported.
+/// With `job_id` the link scan can be stopped by `cancel_job`; the rename itself stands.
 #[tauri::command]
-pub async fn rename_file(old_path: String, new_path: String) -> Result<(), String> {
+pub async fn rename_file(
+    app_handle: tauri::AppHandle,
+    old_path: String,
+    new_path: String,
+    update_links: Option<bool>,
+    workspace: Option<String>,
+    skip_paths: Option<Vec<String>>,
+    job_id: Option<String>,
+) -> Result<LinkUpdateReport, String> {
     validate_path(&old_path)?;
     validate_path(&new_path)?;
 
@@ -231,7 +381,154 @@ pub async fn rename_file(old_path: String, new_path: String) -> Result<(), Strin
 
     fs::rename(&old_path, &new_path)
         .await
-        .map_err(|e| handle_error(Some(&old_path), "rename file", e))
+        .map_err(|e| handle_error(Some(&old_path), "rename file", e))?;
+
+    if !update_links.unwrap_or(false) {
+        return Ok(LinkUpdateReport::default());
+    }
+
+    let start = std::time::Instant::now();
+    let from = std::path::absolute(&old_path)
+        .map_err(|e| handle_error(Some(&old_path), "resolve renamed file", e))?
+        .clean();
+    let to = std::path::absolute(&new_path)
+        .map_err(|e| handle_error(Some(&new_path), "resolve renamed file", e))?
+        .clean();
+    let root = match workspace.filter(|w| !w.trim().is_empty()) {
+        Some(workspace) => {
+            validate_path(&workspace)?;
+            PathBuf::from(workspace)
+        },
+        None => from.parent().map(PathBuf::from).unwrap_or_default(),
+    };
+
+    let job = Job::start(&app_handle, "rename_file", job_id);
+    let report = update_links_after_move(
+        root,
+        from.clone(),
+        to.clone(),
+        skip_paths.as_deref().unwrap_or_default(),
+        &job,
+    )
+    .await?;
+
+    if report.links_updated > 0 || !report.skipped.is_empty() {
+        crate::audit::record(
+            &app_handle,
+            "rename_file",
+            serde_json::json!({
+                "from": from.to_string_lossy(),
+                "to": to.to_string_lossy(),
+                "linksUpdated": report.links_updated,
+                "filesUpdated": report.files_updated,
+                "skipped": report.skipped,
+            }),
+        )
+        .await;
+    }
+
+    log::info!(
+        "[Storage] rename_file | duration={:?} | links={} | files={} | skipped={} | path={}",
+        start.elapsed(),
+        report.links_updated,
+        report.files_updated.len(),
+        report.skipped.len(),
+        new_path
+    );
+
+    Ok(report)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchivedNote {
+    pub path: String,
+    pub links_updated: usize,
+    /// Other notes whose links were rewritten to the new location
+    pub files_updated: Vec<String>,
+}
+
+/// Moves a note into the archive folder, keeping its path relative to the folder that
+/// holds the archive, and retargets links to it in the notes under that folder. Relative
+/// links inside the note are rebased so they still resolve from the archive.
+#[tauri::command]
+pub async fn archive_note(
+    app_handle: tauri::AppHandle,
+    path: String,
+) -> Result<ArchivedNote, String> {
+    validate_path(&path)?;
+    let start = std::time::Instant::now();
+
+    let source = std::path::absolute(&path)
+        .map_err(|e| handle_error(Some(&path), "resolve note path", e))?
+        .clean();
+    if !fs::metadata(&source).await.is_ok_and(|m| m.is_file()) {
+        return Err("Note does not exist".to_string());
+    }
+
+    let archive_dir = std::path::absolute(get_archive_path(&app_handle, &source).await)
+        .map_err(|e| handle_error(Some(&path), "resolve archive folder", e))?
+        .clean();
+    if source.starts_with(&archive_dir) {
+        return Err("Note is already archived".to_string());
+    }
+    // Notes outside the archive's parent folder have no structure to keep
+    let root = archive_dir.parent().unwrap_or(&archive_dir).to_path_buf();
+    let relative = match source.strip_prefix(&root) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => PathBuf::from(source.file_name().unwrap_or_default()),
+    };
+    let target = archive_dir.join(relative);
+    let target_str = target.to_string_lossy().to_string();
+    validate_path(&target_str)?;
+    if fs::metadata(&target).await.is_ok() {
+        return Err("A note with that name is already in the archive".to_string());
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| handle_error(Some(&target_str), "create archive folder", e))?;
+    }
+    match fs::rename(&source, &target).await {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            fs::copy(&source, &target)
+                .await
+                .map_err(|e| handle_error(Some(&path), "copy note to archive", e))?;
+            fs::remove_file(&source)
+                .await
+                .map_err(|e| handle_error(Some(&path), "remove archived note", e))?;
+        },
+        result => result.map_err(|e| handle_error(Some(&path), "move note to archive", e))?,
+    }
+
+    let job = Job::start(&app_handle, "archive_note", None);
+    let report = update_links_after_move(root, source.clone(), target.clone(), &[], &job).await?;
+
+    crate::audit::record(
+        &app_handle,
+        "archive_note",
+        serde_json::json!({
+            "from": source.to_string_lossy(),
+            "to": target_str,
+            "linksUpdated": report.links_updated,
+            "filesUpdated": report.files_updated,
+        }),
+    )
+    .await;
+
+    log::info!(
+        "[Storage] archive_note | duration={:?} | links={} | files={} | path={}",
+        start.elapsed(),
+        report.links_updated,
+        report.files_updated.len(),
+        target_str
+    );
+
+    Ok(ArchivedNote {
+        path: target_str,
+        links_updated: report.links_updated,
+        files_updated: report.files_updated,
+    })
 }
 
 #[tauri::command]
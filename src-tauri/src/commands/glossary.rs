@@ -0,0 +1,37 @@
+use crate::markdown::glossary::{Glossary, GlossaryTerm, TermMatch};
+use crate::utils::{handle_error, validate_path};
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Debug, Default, Serialize)]
+pub struct GlossaryMatches {
+    /// The glossary file in use; `None` when the document's workspace has none
+    pub glossary_path: Option<String>,
+    pub terms: Vec<GlossaryTerm>,
+    /// Occurrences in the document, each pointing into `terms`
+    pub matches: Vec<TermMatch>,
+}
+
+/// Glossary terms and deprecated forms in `content`, from the nearest `glossary.toml` above
+/// `path`, for hover definitions and deprecated-term underlines in the editor
+#[tauri::command]
+pub async fn get_glossary_matches(
+    path: String,
+    content: String,
+) -> Result<GlossaryMatches, String> {
+    validate_path(&path)?;
+    tokio::task::spawn_blocking(move || {
+        let found = Glossary::find(Path::new(&path))
+            .map_err(|e| handle_error(Some(&path), "load glossary", e))?;
+        let Some((file, glossary)) = found else {
+            return Ok(GlossaryMatches::default());
+        };
+        Ok(GlossaryMatches {
+            glossary_path: Some(file.to_string_lossy().into_owned()),
+            matches: glossary.scan(&content),
+            terms: glossary.terms,
+        })
+    })
+    .await
+    .map_err(|e| format!("Glossary task failed: {}", e))?
+}
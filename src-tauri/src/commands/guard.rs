@@ -0,0 +1,196 @@
+use crate::commands::settings::read_setting;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Per-command call state, shared by every call of that command
+static GUARDS: LazyLock<Mutex<HashMap<&'static str, Arc<CommandGuard>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Limits for the commands a frontend loop could call often or with huge payloads:
+/// rendering, formatting and search. Read from the `commandGuards` settings table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GuardSettings {
+    pub enabled: bool,
+    /// Largest payload, such as a document to render, accepted by a guarded command
+    pub max_payload_mb: u64,
+    /// Calls of one command allowed to run at once; later calls queue
+    pub max_in_flight: usize,
+    /// How long a queued call waits for a slot before it is turned away as busy
+    pub queue_timeout_ms: u64,
+    /// Calls of one command started per second before further calls are turned away
+    pub max_calls_per_second: usize,
+}
+
+impl Default for GuardSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_payload_mb: 64,
+            max_in_flight: 2,
+            queue_timeout_ms: 5000,
+            max_calls_per_second: 60,
+        }
+    }
+}
+
+/// Why a guarded or cancellable command refused or stopped a call; serialized with a
+/// `kind` tag so the frontend can back off instead of reporting a failure
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommandError {
+    /// The payload is over `maxPayloadMb`
+    TooLarge {
+        command: &'static str,
+        message: String,
+        size: u64,
+        limit: u64,
+    },
+    /// Too many calls are running or were started in the last second
+    Busy {
+        command: &'static str,
+        message: String,
+    },
+    /// The job was stopped by `cancel_job`
+    Cancelled {
+        command: &'static str,
+        message: String,
+    },
+    /// The command ran and failed
+    Failed { message: String },
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self::Failed { message }
+    }
+}
+
+struct CommandGuard {
+    max_in_flight: usize,
+    slots: Arc<Semaphore>,
+    /// Start times of calls in the last `RATE_WINDOW`
+    recent: Mutex<VecDeque<Instant>>,
+    /// When a rejection was last logged, so a runaway loop doesn't flood the log
+    last_logged: Mutex<Option<Instant>>,
+}
+
+impl CommandGuard {
+    fn new(max_in_flight: usize) -> Self {
+        Self {
+            max_in_flight,
+            slots: Arc::new(Semaphore::new(max_in_flight)),
+            recent: Mutex::new(VecDeque::new()),
+            last_logged: Mutex::new(None),
+        }
+    }
+
+    /// Records a call start; false when the command is over its rate
+    fn admit(&self, now: Instant, max_per_window: usize) -> bool {
+        let Ok(mut recent) = self.recent.lock() else {
+            return true;
+        };
+        while recent
+            .front()
+            .is_some_and(|&start| now.duration_since(start) >= RATE_WINDOW)
+        {
+            recent.pop_front();
+        }
+        if recent.len() >= max_per_window {
+            return false;
+        }
+        recent.push_back(now);
+        true
+    }
+
+    fn log_rejection(&self, command: &str, reason: &str) {
+        let now = Instant::now();
+        if let Ok(mut last) = self.last_logged.lock()
+            && last.is_none_or(|at| now.duration_since(at) >= RATE_WINDOW)
+        {
+            *last = Some(now);
+            log::warn!("[Guard] Rejected {} | {}", command, reason);
+        }
+    }
+}
+
+/// Held for the duration of a guarded call; dropping it frees the slot
+pub struct CommandPermit {
+    _slot: Option<OwnedSemaphorePermit>,
+}
+
+fn guard_for(command: &'static str, max_in_flight: usize) -> Arc<CommandGuard> {
+    let mut guards = GUARDS.lock().unwrap_or_else(|e| e.into_inner());
+    let guard = guards
+        .entry(command)
+        .or_insert_with(|| Arc::new(CommandGuard::new(max_in_flight)));
+    // A changed setting takes effect for calls made after the running ones
+    if guard.max_in_flight != max_in_flight {
+        *guard = Arc::new(CommandGuard::new(max_in_flight));
+    }
+    guard.clone()
+}
+
+/// Checks a call of `command` carrying `payload_bytes` against the limits and waits for
+/// a free slot. Oversized payloads and calls over the rate are refused at once; calls
+/// that find every slot taken queue for up to `queueTimeoutMs`.
+pub async fn acquire(
+    app_handle: &tauri::AppHandle,
+    command: &'static str,
+    payload_bytes: usize,
+) -> Result<CommandPermit, CommandError> {
+    let settings: GuardSettings = read_setting(app_handle, "commandGuards")
+        .await
+        .unwrap_or_default();
+    if !settings.enabled {
+        return Ok(CommandPermit { _slot: None });
+    }
+    let guard = guard_for(command, settings.max_in_flight.max(1));
+
+    let limit = settings.max_payload_mb.max(1) * 1024 * 1024;
+    let size = payload_bytes as u64;
+    if size > limit {
+        let message = format!(
+            "{} payload of {:.1} MB is over the {} MB limit",
+            command,
+            size as f64 / (1024.0 * 1024.0),
+            settings.max_payload_mb.max(1)
+        );
+        guard.log_rejection(command, &message);
+        return Err(CommandError::TooLarge {
+            command,
+            message,
+            size,
+            limit,
+        });
+    }
+
+    if !guard.admit(Instant::now(), settings.max_calls_per_second.max(1)) {
+        let message = format!(
+            "{} was called more than {} times in a second",
+            command, settings.max_calls_per_second
+        );
+        guard.log_rejection(command, &message);
+        return Err(CommandError::Busy { command, message });
+    }
+
+    let timeout = Duration::from_millis(settings.queue_timeout_ms);
+    match tokio::time::timeout(timeout, guard.slots.clone().acquire_owned()).await {
+        Ok(Ok(permit)) => Ok(CommandPermit {
+            _slot: Some(permit),
+        }),
+        _ => {
+            let message = format!(
+                "{} still had {} calls running after {:?}",
+                command, guard.max_in_flight, timeout
+            );
+            guard.log_rejection(command, &message);
+            Err(CommandError::Busy { command, message })
+        },
+    }
+}
@@ -0,0 +1,87 @@
+use crate::capture;
+use crate::commands::settings::get_inbox_path;
+use crate::utils::{handle_error, validate_path};
+use std::path::Path;
+use tauri::Emitter;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+const INBOX_TEMPLATE: &str = "# Inbox\n\nQuick captures, newest last.\n";
+const ENTRY_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+fn format_entry(text: &str) -> String {
+    let timestamp = chrono::Local::now().format(ENTRY_TIMESTAMP_FORMAT);
+    format!(
+        "\n## {}\n\n{}\n",
+        timestamp,
+        text.trim().replace("\r\n", "\n")
+    )
+}
+
+/// Creates the inbox from the template unless it already exists. `create_new` makes
+/// concurrent captures race safely: only one of them writes the template.
+async fn ensure_inbox_exists(path: &Path) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .await
+    {
+        Ok(mut file) => {
+            file.write_all(INBOX_TEMPLATE.as_bytes()).await?;
+            file.sync_all().await
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Appends a timestamped entry to the configured inbox file and returns its path.
+#[tauri::command]
+pub async fn append_to_inbox(app_handle: tauri::AppHandle, text: String) -> Result<String, String> {
+    if text.trim().is_empty() {
+        return Err("Nothing to capture".to_string());
+    }
+
+    let path = get_inbox_path(&app_handle).await?;
+    let path_str = path.to_string_lossy().to_string();
+    validate_path(&path_str)?;
+
+    ensure_inbox_exists(&path)
+        .await
+        .map_err(|e| handle_error(Some(&path_str), "create inbox", e))?;
+
+    // A single write on an O_APPEND handle keeps concurrent captures from interleaving
+    let entry = format_entry(&text);
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .await
+        .map_err(|e| handle_error(Some(&path_str), "open inbox", e))?;
+    file.write_all(entry.as_bytes())
+        .await
+        .map_err(|e| handle_error(Some(&path_str), "append to inbox", e))?;
+    file.sync_all()
+        .await
+        .map_err(|e| handle_error(Some(&path_str), "flush inbox", e))?;
+
+    log::info!(
+        "[Storage] append_to_inbox | size={} bytes | path={}",
+        entry.len(),
+        path_str
+    );
+
+    let _ = app_handle.emit("inbox-updated", &path_str);
+    Ok(path_str)
+}
+
+/// Called once the frontend listens for capture prompts; sends the ones that arrived
+/// first, such as the link that launched the app
+#[tauri::command]
+pub fn quick_capture_ready(app_handle: tauri::AppHandle) {
+    capture::mark_ready(&app_handle);
+}
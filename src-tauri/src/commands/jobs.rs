@@ -0,0 +1,210 @@
+use crate::commands::guard::CommandError;
+use crate::state::AppState;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager, State};
+use tokio::sync::Notify;
+
+pub const JOB_PROGRESS_EVENT: &str = "job-progress";
+// Progress events are dropped when they come faster than this, except the last one
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Job id -> cancellation flag of a running long command
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<String, Arc<CancelFlag>>>,
+}
+
+#[derive(Default)]
+struct CancelFlag {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancelFlag {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.notify.notify_waiters();
+    }
+}
+
+impl JobRegistry {
+    /// Marks a job cancelled; false when no job with that id is running
+    pub fn cancel(&self, job_id: &str) -> bool {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        match jobs.get(job_id) {
+            Some(flag) => {
+                flag.cancel();
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobProgress {
+    pub job_id: String,
+    pub done: usize,
+    pub total: usize,
+    pub message: Option<String>,
+}
+
+/// A long command that the frontend can cancel. Commands poll `is_cancelled` or `check`
+/// between units of work, so a cancelled job stops at the next file or tab rather than
+/// mid-write. Without a job id the job can't be cancelled and reports no progress.
+#[derive(Clone)]
+pub struct Job {
+    inner: Arc<JobInner>,
+}
+
+struct JobInner {
+    id: Option<String>,
+    command: &'static str,
+    flag: Arc<CancelFlag>,
+    app_handle: tauri::AppHandle,
+    last_progress: Mutex<Option<Instant>>,
+}
+
+impl Drop for JobInner {
+    fn drop(&mut self) {
+        let Some(id) = &self.id else {
+            return;
+        };
+        let state = self.app_handle.state::<AppState>();
+        let mut jobs = state.jobs.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        // A newer job may have reused the id
+        if jobs
+            .get(id)
+            .is_some_and(|flag| Arc::ptr_eq(flag, &self.flag))
+        {
+            jobs.remove(id);
+        }
+    }
+}
+
+impl Job {
+    /// Registers a run of `command` under `job_id` until the returned job is dropped
+    pub fn start(
+        app_handle: &tauri::AppHandle,
+        command: &'static str,
+        job_id: Option<String>,
+    ) -> Self {
+        let job_id = job_id.filter(|id| !id.is_empty());
+        let flag = Arc::new(CancelFlag::default());
+        if let Some(id) = &job_id {
+            let state = app_handle.state::<AppState>();
+            let mut jobs = state.jobs.jobs.lock().unwrap_or_else(|e| e.into_inner());
+            if jobs.insert(id.clone(), flag.clone()).is_some() {
+                log::warn!("[Jobs] {} reused running job id {}", command, id);
+            }
+        }
+        Self {
+            inner: Arc::new(JobInner {
+                id: job_id,
+                command,
+                flag,
+                app_handle: app_handle.clone(),
+                last_progress: Mutex::new(None),
+            }),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.flag.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Err once the job was cancelled, for `?` between units of work
+    pub fn check(&self) -> Result<(), CommandError> {
+        if self.is_cancelled() {
+            Err(self.cancelled_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Runs work that can't poll for cancellation, such as one large format, and stops
+    /// waiting for it once the job is cancelled. The work itself runs to completion in
+    /// the background and its result is dropped.
+    pub async fn until_cancelled<T>(
+        &self,
+        work: impl Future<Output = T>,
+    ) -> Result<T, CommandError> {
+        let mut work = std::pin::pin!(work);
+        let mut cancelled = std::pin::pin!(self.cancelled());
+        let outcome = std::future::poll_fn(|cx| {
+            if let Poll::Ready(value) = work.as_mut().poll(cx) {
+                return Poll::Ready(Some(value));
+            }
+            cancelled.as_mut().poll(cx).map(|()| None)
+        })
+        .await;
+        outcome.ok_or_else(|| self.cancelled_error())
+    }
+
+    async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.flag.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    fn cancelled_error(&self) -> CommandError {
+        log::info!(
+            "[Jobs] Cancelled {} | job={}",
+            self.inner.command,
+            self.inner.id.as_deref().unwrap_or_default()
+        );
+        CommandError::Cancelled {
+            command: self.inner.command,
+            message: format!("{} was cancelled", self.inner.command),
+        }
+    }
+
+    /// Emits `job-progress` for the job, at most every `PROGRESS_INTERVAL` until the
+    /// last unit
+    pub fn progress(&self, done: usize, total: usize, message: Option<String>) {
+        let Some(job_id) = &self.inner.id else {
+            return;
+        };
+        let now = Instant::now();
+        {
+            let mut last = self
+                .inner
+                .last_progress
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            if done < total && last.is_some_and(|at| now.duration_since(at) < PROGRESS_INTERVAL) {
+                return;
+            }
+            *last = Some(now);
+        }
+        let event = JobProgress {
+            job_id: job_id.clone(),
+            done,
+            total,
+            message,
+        };
+        if let Err(e) = self.inner.app_handle.emit(JOB_PROGRESS_EVENT, &event) {
+            log::warn!("Failed to emit job progress: {}", e);
+        }
+    }
+}
+
+/// Asks a running job to stop at its next checkpoint. Returns false when no job with that
+/// id is running, for example because it already finished.
+#[tauri::command]
+pub async fn cancel_job(state: State<'_, AppState>, job_id: String) -> Result<bool, String> {
+    let cancelled = state.jobs.cancel(&job_id);
+    if cancelled {
+        log::info!("[Jobs] cancel_job | job={}", job_id);
+    }
+    Ok(cancelled)
+}
@@ -0,0 +1,405 @@
+use crate::commands::settings::read_setting;
+use crate::logging::{LOG_DIR_NAME, LOG_FILE_NAME};
+use crate::state::AppState;
+use crate::utils::handle_error;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{Manager, State};
+
+const MAINTENANCE_SETTING_KEY: &str = "maintenance";
+const LAST_RUN_FILE: &str = "maintenance.last";
+const VACUUM_POLL_INTERVAL: Duration = Duration::from_secs(60);
+const VACUUM_LOG_LIMIT: usize = 50;
+
+/// Recent vacuum runs, oldest first
+static VACUUM_LOG: LazyLock<Mutex<VecDeque<VacuumRun>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::new()));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MaintenanceSettings {
+    /// Run automatically at startup; `run_maintenance_now` ignores this
+    pub enabled: bool,
+    pub interval_hours: u32,
+    /// Zero keeps closed tabs forever
+    pub closed_tab_max_age_days: u32,
+    /// Closed copies kept per file; zero keeps all
+    pub max_snapshots_per_document: u32,
+    /// Zero leaves recent files to the built-in cap
+    pub max_recent_files: u32,
+    pub vacuum: bool,
+    /// Vacuum in batches while the app is idle once enough pages are free
+    pub auto_vacuum: bool,
+    /// Free pages needed before an automatic vacuum
+    pub vacuum_min_free_pages: u32,
+    /// Share of the file, in percent, that must be free as well
+    pub vacuum_min_free_percent: u32,
+    /// Pages released per automatic run, so one run never stalls the database
+    pub vacuum_max_pages_per_run: u32,
+    /// Seconds without edits before an automatic run
+    pub vacuum_idle_seconds: u32,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: 24,
+            closed_tab_max_age_days: 90,
+            max_snapshots_per_document: 10,
+            max_recent_files: 500,
+            vacuum: true,
+            auto_vacuum: true,
+            vacuum_min_free_pages: 256,
+            vacuum_min_free_percent: 10,
+            vacuum_max_pages_per_run: 512,
+            vacuum_idle_seconds: 60,
+        }
+    }
+}
+
+impl MaintenanceSettings {
+    fn auto_vacuum_due(&self, total_pages: i64, free_pages: i64) -> bool {
+        free_pages > 0
+            && free_pages >= self.vacuum_min_free_pages as i64
+            && free_pages * 100 >= total_pages * self.vacuum_min_free_percent as i64
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VacuumTrigger {
+    /// The idle scheduler
+    Auto,
+    /// Retention policies, scheduled or run from settings
+    Maintenance,
+    /// `vacuum_database`
+    Manual,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VacuumRun {
+    pub timestamp: String,
+    pub trigger: VacuumTrigger,
+    pub pages_reclaimed: i64,
+    /// Free pages left for a later run
+    pub pages_remaining: i64,
+}
+
+/// Releases up to `max_pages` free pages (all with zero) and records the run. Returns
+/// `None` without touching the file when nothing is free.
+pub fn vacuum_pages(
+    state: &AppState,
+    max_pages: i32,
+    trigger: VacuumTrigger,
+) -> Result<Option<VacuumRun>, String> {
+    let (_, free_before) = state
+        .db
+        .page_counts()
+        .map_err(|e| handle_error(Some("freelist count"), "check database", e))?;
+    if free_before == 0 {
+        log::debug!("No free pages to reclaim in database");
+        return Ok(None);
+    }
+
+    state
+        .db
+        .incremental_vacuum(max_pages)
+        .map_err(|e| handle_error(Some("database"), "vacuum database", e))?;
+    let (_, free_after) = state
+        .db
+        .page_counts()
+        .map_err(|e| handle_error(Some("freelist count"), "check database", e))?;
+
+    let run = VacuumRun {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        trigger,
+        pages_reclaimed: free_before - free_after,
+        pages_remaining: free_after,
+    };
+    log::info!(
+        "[Storage] vacuum | trigger={:?} | reclaimed={} | remaining={}",
+        run.trigger,
+        run.pages_reclaimed,
+        run.pages_remaining
+    );
+    if let Ok(mut runs) = VACUUM_LOG.lock() {
+        if runs.len() == VACUUM_LOG_LIMIT {
+            runs.pop_front();
+        }
+        runs.push_back(run.clone());
+    }
+    Ok(Some(run))
+}
+
+/// Watches the freelist for the life of the app. Closed-tab purges and snapshot pruning
+/// leave free pages behind; once they pass the configured thresholds and the user has
+/// been idle for a while, a bounded incremental vacuum hands them back to the OS.
+pub async fn run_vacuum_scheduler(app_handle: tauri::AppHandle) {
+    let state = app_handle.state::<AppState>();
+    if state.safe_mode.active {
+        return;
+    }
+
+    let mut interval = tokio::time::interval(VACUUM_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let settings: MaintenanceSettings = read_setting(&app_handle, MAINTENANCE_SETTING_KEY)
+            .await
+            .unwrap_or_default();
+        if !settings.auto_vacuum {
+            continue;
+        }
+
+        let idle = state
+            .session_autosave
+            .lock()
+            .map_or(Duration::ZERO, |autosave| autosave.idle_for(Instant::now()));
+        if idle < Duration::from_secs(settings.vacuum_idle_seconds as u64) {
+            continue;
+        }
+
+        let Ok((total, free)) = state.db.page_counts() else {
+            continue;
+        };
+        if !settings.auto_vacuum_due(total, free) {
+            continue;
+        }
+        // Zero would mean "everything", which is what the batch limit is there to avoid
+        let max_pages = settings.vacuum_max_pages_per_run.clamp(1, i32::MAX as u32) as i32;
+        if let Err(e) = vacuum_pages(&state, max_pages, VacuumTrigger::Auto) {
+            log::warn!("Automatic vacuum failed: {}", e);
+        }
+    }
+}
+
+/// Recent vacuum runs with the pages each reclaimed, oldest first
+#[tauri::command]
+pub fn get_vacuum_log() -> Vec<VacuumRun> {
+    VACUUM_LOG
+        .lock()
+        .map(|runs| runs.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MaintenanceReport {
+    pub closed_tabs_removed: Vec<String>,
+    pub recent_files_removed: usize,
+    pub pages_reclaimed: i64,
+}
+
+fn run_policies(
+    state: &AppState,
+    settings: &MaintenanceSettings,
+) -> Result<MaintenanceReport, String> {
+    let mut report = MaintenanceReport {
+        closed_tabs_removed: state
+            .db
+            .prune_closed_tabs(
+                settings.closed_tab_max_age_days,
+                settings.max_snapshots_per_document,
+            )
+            .map_err(|e| handle_error(Some("closed tabs"), "prune", e))?,
+        ..Default::default()
+    };
+
+    if settings.max_recent_files > 0 {
+        report.recent_files_removed = state
+            .db
+            .trim_recent_files(settings.max_recent_files)
+            .map_err(|e| handle_error(Some("recent files"), "trim", e))?;
+    }
+
+    if settings.vacuum {
+        report.pages_reclaimed = vacuum_pages(state, 0, VacuumTrigger::Maintenance)?
+            .map_or(0, |run| run.pages_reclaimed);
+    }
+
+    log::info!(
+        "[Storage] maintenance | closed_tabs={} | recent_files={} | pages={}",
+        report.closed_tabs_removed.len(),
+        report.recent_files_removed,
+        report.pages_reclaimed
+    );
+
+    Ok(report)
+}
+
+/// Runs the retention policies if enabled and the interval has passed since the last run.
+/// Called before the session is loaded so pruned tabs never reach the frontend.
+pub async fn run_scheduled_maintenance(app_handle: &tauri::AppHandle, state: &AppState) {
+    let settings: MaintenanceSettings = read_setting(app_handle, MAINTENANCE_SETTING_KEY)
+        .await
+        .unwrap_or_default();
+    if !settings.enabled {
+        return;
+    }
+
+    let Ok(local_dir) = app_handle.path().app_local_data_dir() else {
+        return;
+    };
+    let marker = local_dir.join(LAST_RUN_FILE);
+    let due = tokio::fs::read_to_string(&marker)
+        .await
+        .ok()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s.trim()).ok())
+        .is_none_or(|last| {
+            chrono::Local::now().signed_duration_since(last)
+                >= chrono::Duration::hours(settings.interval_hours as i64)
+        });
+    if !due {
+        return;
+    }
+
+    match run_policies(state, &settings) {
+        Ok(_) => {
+            if let Err(e) = tokio::fs::write(&marker, chrono::Local::now().to_rfc3339()).await {
+                log::warn!("Failed to record maintenance run: {}", e);
+            }
+        },
+        Err(e) => log::warn!("Scheduled maintenance failed: {}", e),
+    }
+}
+
+/// Applies the configured retention policies immediately and reports what was deleted.
+#[tauri::command]
+pub async fn run_maintenance_now(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<MaintenanceReport, String> {
+    let settings: MaintenanceSettings = read_setting(&app_handle, MAINTENANCE_SETTING_KEY)
+        .await
+        .unwrap_or_default();
+    run_policies(&state, &settings)
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct LogPurgeReport {
+    pub files_removed: usize,
+    pub bytes_freed: u64,
+}
+
+async fn truncate_file(path: &std::path::Path) -> std::io::Result<()> {
+    let file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+    file.set_len(0).await
+}
+
+/// Deletes rotated log files. With `include_current` the active log is emptied too;
+/// the logger appends, so it carries on writing from the start of the file.
+#[tauri::command]
+pub async fn purge_logs(
+    app_handle: tauri::AppHandle,
+    include_current: Option<bool>,
+) -> Result<LogPurgeReport, String> {
+    let log_dir = app_handle
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| handle_error(None, "get local data directory for logs", e))?
+        .join(LOG_DIR_NAME);
+    let current_name = format!("{}.log", LOG_FILE_NAME);
+
+    let mut report = LogPurgeReport::default();
+    let mut entries = tokio::fs::read_dir(&log_dir)
+        .await
+        .map_err(|e| handle_error(Some(&log_dir.to_string_lossy()), "read log directory", e))?;
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with(LOG_FILE_NAME) || !name.ends_with(".log") {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+
+        if name == current_name {
+            if !include_current.unwrap_or(false) {
+                continue;
+            }
+            if let Err(e) = truncate_file(&path).await {
+                log::warn!("Failed to truncate {:?}: {}", path, e);
+                continue;
+            }
+        } else if let Err(e) = tokio::fs::remove_file(&path).await {
+            log::warn!("Failed to delete {:?}: {}", path, e);
+            continue;
+        }
+
+        report.files_removed += 1;
+        report.bytes_freed += metadata.len();
+    }
+
+    log::info!(
+        "[Storage] purge_logs | files={} | bytes={}",
+        report.files_removed,
+        report.bytes_freed
+    );
+    Ok(report)
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemoryStats {
+    /// Rough size of the merged spellcheck dictionary; zero when unloaded
+    pub speller_bytes: usize,
+    pub custom_words: usize,
+    pub text_metrics_documents: usize,
+    pub text_metrics_bytes: usize,
+    pub theme_cache_bytes: usize,
+    pub render_cache_bytes: usize,
+    pub database: crate::db::DbMemoryStats,
+}
+
+/// Reports the larger in-memory caches so the frontend can decide when to trim them.
+#[tauri::command]
+pub async fn get_memory_stats(state: State<'_, AppState>) -> Result<MemoryStats, String> {
+    let (text_metrics_documents, text_metrics_bytes) = {
+        let metrics = state.text_metrics.lock().await;
+        (
+            metrics.len(),
+            metrics.values().map(|m| m.estimated_bytes()).sum(),
+        )
+    };
+
+    Ok(MemoryStats {
+        speller_bytes: state
+            .speller_bytes
+            .load(std::sync::atomic::Ordering::Relaxed),
+        custom_words: state.custom_dict.lock().await.len(),
+        text_metrics_documents,
+        text_metrics_bytes,
+        theme_cache_bytes: crate::commands::settings::theme_cache_bytes().await,
+        render_cache_bytes: crate::markdown::renderer::render_cache_bytes(),
+        database: state
+            .db
+            .memory_stats()
+            .map_err(|e| handle_error(Some("database"), "read memory stats", e))?,
+    })
+}
+
+/// Drops caches that are rebuilt on demand (line metrics, theme CSS, rendered HTML) and
+/// releases SQLite's page cache. Returns the approximate bytes freed outside SQLite.
+#[tauri::command]
+pub async fn trim_caches(state: State<'_, AppState>) -> Result<usize, String> {
+    let metrics_bytes = {
+        let mut metrics = state.text_metrics.lock().await;
+        let bytes = metrics.values().map(|m| m.estimated_bytes()).sum::<usize>();
+        metrics.clear();
+        metrics.shrink_to_fit();
+        bytes
+    };
+    let theme_bytes = crate::commands::settings::clear_theme_cache().await;
+    let render_bytes = crate::markdown::renderer::clear_render_cache();
+
+    state
+        .db
+        .shrink_memory()
+        .map_err(|e| handle_error(Some("database"), "shrink memory", e))?;
+
+    let freed = metrics_bytes + theme_bytes + render_bytes;
+    log::info!("[Storage] trim_caches | freed≈{} bytes", freed);
+    Ok(freed)
+}
@@ -0,0 +1,44 @@
+use crate::markdown::compare::{self, TabComparison};
+use crate::markdown::merge::{self, MergeResult};
+
+#[tauri::command]
+pub async fn merge_documents(
+    base: String,
+    ours: String,
+    theirs: String,
+) -> Result<MergeResult, String> {
+    let start = std::time::Instant::now();
+
+    let result = tokio::task::spawn_blocking(move || merge::merge_documents(&base, &ours, &theirs))
+        .await
+        .map_err(|e| format!("Merge task failed: {}", e))?;
+
+    let duration = start.elapsed();
+    log::info!(
+        "[Markdown] merge_documents | duration={:?} | size={} bytes | conflicts={}",
+        duration,
+        result.content.len(),
+        result.conflicts.len()
+    );
+
+    Ok(result)
+}
+
+/// Line-aligned comparison of two tabs' contents for the side-by-side view
+#[tauri::command]
+pub async fn compare_tabs(tab_a: String, tab_b: String) -> Result<TabComparison, String> {
+    let start = std::time::Instant::now();
+
+    let result = tokio::task::spawn_blocking(move || compare::compare(&tab_a, &tab_b))
+        .await
+        .map_err(|e| format!("Compare task failed: {}", e))?;
+
+    log::info!(
+        "[Markdown] compare_tabs | duration={:?} | rows={} | changes={}",
+        start.elapsed(),
+        result.rows.len(),
+        result.changes.len()
+    );
+
+    Ok(result)
+}
@@ -0,0 +1,289 @@
+use crate::commands::guard::{self, CommandError};
+use crate::commands::jobs::Job;
+use crate::markdown::clipboard;
+use crate::markdown::config::{
+    DEFAULT_LINE_WIDTH, DEFAULT_LIST_INDENT, DEFAULT_MAX_BLANK_LINES, MarkdownFlavor,
+};
+use crate::markdown::formatter::{self, FormatterOptions, TocOptions, WrapMode};
+use crate::markdown::html_to_markdown;
+use crate::markdown::inline_html::{self, InlineHtmlCleanup};
+use crate::markdown::lists::{self, ListConversion, ListKind, OrderedListStyle};
+use crate::markdown::plain_text;
+use crate::markdown::reference_links::LinkStyle;
+use crate::markdown::roundtrip::{self, RoundtripReport};
+use crate::utils::IntoTauriError;
+
+/// Runs the formatter on a dedicated thread with a large stack; dprint recurses deeply
+/// on nested lists and quotes.
+pub async fn format_on_thread(
+    content: String,
+    options: FormatterOptions,
+) -> Result<String, String> {
+    on_formatter_thread(move || formatter::format_markdown(&content, &options)).await
+}
+
+async fn on_formatter_thread<T: Send + 'static>(
+    job: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> Result<T, String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    std::thread::Builder::new()
+        .name("markdown-formatter".into())
+        .stack_size(16 * 1024 * 1024)
+        .spawn(move || {
+            let _ = tx.send(job());
+        })
+        .map_err(|e| format!("Failed to spawn formatter thread: {}", e))?;
+
+    match tokio::task::spawn_blocking(move || rx.recv()).await {
+        Ok(Ok(result)) => result.to_tauri_result(),
+        Ok(Err(_)) => Err("Formatter thread panicked or disconnected".to_string()),
+        Err(e) => Err(format!("Formatter task join error: {}", e)),
+    }
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn format_markdown(
+    app_handle: tauri::AppHandle,
+    content: String,
+    flavor: Option<String>,
+    list_indent: Option<usize>,
+    bullet_char: Option<String>,
+    code_block_fence: Option<String>,
+    emphasis_char: Option<String>,
+    table_alignment: Option<bool>,
+    max_blank_lines: Option<usize>,
+    text_wrap: Option<WrapMode>,
+    line_width: Option<u32>,
+    link_style: Option<LinkStyle>,
+    ordered_list_style: Option<OrderedListStyle>,
+    job_id: Option<String>,
+) -> Result<String, CommandError> {
+    let _permit = guard::acquire(&app_handle, "format_markdown", content.len()).await?;
+    let job = Job::start(&app_handle, "format_markdown", job_id);
+    let start = std::time::Instant::now();
+    let content_size = content.len();
+
+    let options = formatter_options(
+        flavor,
+        list_indent,
+        bullet_char,
+        code_block_fence,
+        emphasis_char,
+        table_alignment,
+        max_blank_lines,
+        text_wrap,
+        line_width,
+        link_style,
+        ordered_list_style,
+    );
+
+    let result = job
+        .until_cancelled(format_on_thread(content, options))
+        .await?;
+
+    let duration = start.elapsed();
+    log::info!(
+        "[Markdown] format_markdown | duration={:?} | size={} bytes",
+        duration,
+        content_size
+    );
+
+    Ok(result?)
+}
+
+/// Formats the document twice with the given formatter settings and reports any block
+/// whose text the formatter changed and any line a second pass changes again. For
+/// checking the formatter and attaching reproducible cases to bug reports.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn verify_roundtrip(
+    content: String,
+    flavor: Option<String>,
+    list_indent: Option<usize>,
+    bullet_char: Option<String>,
+    code_block_fence: Option<String>,
+    emphasis_char: Option<String>,
+    table_alignment: Option<bool>,
+    max_blank_lines: Option<usize>,
+    text_wrap: Option<WrapMode>,
+    line_width: Option<u32>,
+    link_style: Option<LinkStyle>,
+    ordered_list_style: Option<OrderedListStyle>,
+) -> Result<RoundtripReport, String> {
+    let start = std::time::Instant::now();
+    let content_size = content.len();
+
+    let options = formatter_options(
+        flavor,
+        list_indent,
+        bullet_char,
+        code_block_fence,
+        emphasis_char,
+        table_alignment,
+        max_blank_lines,
+        text_wrap,
+        line_width,
+        link_style,
+        ordered_list_style,
+    );
+    let report = on_formatter_thread(move || roundtrip::verify(&content, &options)).await?;
+
+    log::info!(
+        "[Markdown] verify_roundtrip | duration={:?} | size={} bytes | content_preserved={} | idempotent={} | divergences={}",
+        start.elapsed(),
+        content_size,
+        report.content_preserved,
+        report.idempotent,
+        report.divergences.len()
+    );
+
+    Ok(report)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn formatter_options(
+    flavor: Option<String>,
+    list_indent: Option<usize>,
+    bullet_char: Option<String>,
+    code_block_fence: Option<String>,
+    emphasis_char: Option<String>,
+    table_alignment: Option<bool>,
+    max_blank_lines: Option<usize>,
+    text_wrap: Option<WrapMode>,
+    line_width: Option<u32>,
+    link_style: Option<LinkStyle>,
+    ordered_list_style: Option<OrderedListStyle>,
+) -> FormatterOptions {
+    FormatterOptions {
+        flavor: MarkdownFlavor::from_option_str(flavor),
+        list_indent: list_indent.unwrap_or(DEFAULT_LIST_INDENT),
+        bullet_char: bullet_char.unwrap_or_else(|| "-".to_string()),
+        code_block_fence: code_block_fence.unwrap_or_else(|| "```".to_string()),
+        emphasis_char: emphasis_char.unwrap_or_else(|| "*".to_string()),
+        table_alignment: table_alignment.unwrap_or(true),
+        normalize_whitespace: true,
+        max_blank_lines: max_blank_lines.unwrap_or(DEFAULT_MAX_BLANK_LINES),
+        text_wrap: text_wrap.unwrap_or_default(),
+        line_width: line_width.unwrap_or(DEFAULT_LINE_WIDTH),
+        link_style: link_style.unwrap_or_default(),
+        ordered_list_style: ordered_list_style.unwrap_or_default(),
+    }
+}
+
+/// Inserts a table of contents between `<!-- toc -->` markers at `line`, or refreshes the
+/// existing one with the new depth and list style.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_toc(
+    content: String,
+    line: Option<usize>,
+    max_depth: Option<u8>,
+    ordered: Option<bool>,
+    flavor: Option<String>,
+    list_indent: Option<usize>,
+    bullet_char: Option<String>,
+) -> Result<String, String> {
+    let defaults = TocOptions::default();
+    let toc = TocOptions {
+        max_depth: max_depth.unwrap_or(defaults.max_depth).clamp(1, 6),
+        ordered: ordered.unwrap_or(defaults.ordered),
+    };
+    let options = FormatterOptions {
+        flavor: MarkdownFlavor::from_option_str(flavor),
+        list_indent: list_indent.unwrap_or(DEFAULT_LIST_INDENT),
+        bullet_char: bullet_char.unwrap_or_else(|| "-".to_string()),
+        ..Default::default()
+    };
+
+    tokio::task::spawn_blocking(move || formatter::insert_toc(&content, line, &toc, &options))
+        .await
+        .map_err(|e| format!("TOC task failed: {}", e))
+}
+
+/// Converts the list(s) under the 1-based line range to bullet, ordered, or task form.
+#[tauri::command]
+pub async fn convert_list(
+    content: String,
+    from_line: usize,
+    to_line: usize,
+    target: ListKind,
+    flavor: Option<String>,
+) -> Result<ListConversion, String> {
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    tokio::task::spawn_blocking(move || {
+        lists::convert_list(&content, from_line, to_line, target, flavor)
+    })
+    .await
+    .map_err(|e| format!("List conversion task failed: {}", e))
+}
+
+/// Renumbers every ordered list in the document: all `1.`, or counting up.
+#[tauri::command]
+pub async fn renumber_ordered_lists(
+    content: String,
+    style: OrderedListStyle,
+    flavor: Option<String>,
+) -> Result<ListConversion, String> {
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    tokio::task::spawn_blocking(move || lists::renumber_ordered_lists(&content, style, flavor))
+        .await
+        .map_err(|e| format!("List renumbering task failed: {}", e))
+}
+
+/// Converts clipboard HTML to markdown for "Paste as Markdown".
+#[tauri::command]
+pub async fn convert_html_to_markdown(html: String) -> Result<String, String> {
+    let start = std::time::Instant::now();
+    let size = html.len();
+    let markdown = tokio::task::spawn_blocking(move || html_to_markdown::convert(&html))
+        .await
+        .map_err(|e| format!("HTML conversion task failed: {}", e))?;
+
+    log::info!(
+        "[Markdown] convert_html_to_markdown | duration={:?} | size={} bytes",
+        start.elapsed(),
+        size
+    );
+    Ok(markdown)
+}
+
+/// Rewrites simple inline HTML in a markdown document (`<b>`, `<i>`, `<a>`, `<img>`,
+/// `<br>`, `<hr>`) as markdown where nothing is lost, and lists the HTML left alone
+#[tauri::command]
+pub async fn convert_inline_html(
+    content: String,
+    flavor: Option<String>,
+) -> Result<InlineHtmlCleanup, String> {
+    let start = std::time::Instant::now();
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    let result = tokio::task::spawn_blocking(move || inline_html::convert(&content, flavor))
+        .await
+        .map_err(|e| format!("Inline HTML task failed: {}", e))?;
+
+    log::info!(
+        "[Markdown] convert_inline_html | duration={:?} | converted={} | left={}",
+        start.elapsed(),
+        result.converted,
+        result.left.len()
+    );
+    Ok(result)
+}
+
+/// Strips Word/Outlook clipboard junk from pasted HTML or text.
+#[tauri::command]
+pub async fn clean_word_paste(html_or_text: String) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || clipboard::clean_word_paste(&html_or_text))
+        .await
+        .map_err(|e| format!("Paste cleanup task failed: {}", e))
+}
+
+/// The document as plain text, for "Copy as plain text".
+#[tauri::command]
+pub async fn strip_markdown(content: String, flavor: Option<String>) -> Result<String, String> {
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    tokio::task::spawn_blocking(move || plain_text::strip(&content, flavor))
+        .await
+        .map_err(|e| format!("Plain text task failed: {}", e))
+}
@@ -0,0 +1,149 @@
+use crate::markdown::accessibility::{self, AccessibilityIssue};
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::stats::{self, DocumentStats};
+use crate::markdown::text_metrics::{
+    self, DocumentMetrics, LineEdit, SelectionMetrics, SelectionRange, WordCountMode,
+};
+use crate::state::AppState;
+use serde::Serialize;
+use tauri::State;
+
+// Bounds memory when many documents are opened and closed without clearing; the least
+// recently edited document is dropped first
+const MAX_CACHED_METRICS: usize = 64;
+
+#[derive(Debug, Serialize)]
+pub struct IncrementalMetrics {
+    pub metrics: (usize, usize, usize, usize),
+    /// Set when the cache was rebuilt from full content
+    pub resynced: bool,
+    /// Set when the cache is missing or drifted; call again with `content`
+    pub needs_full_content: bool,
+}
+
+/// Returns (line_count, word_count, char_count, widest_column). The `prose` word count
+/// mode leaves out code, raw HTML, front matter, and bare URLs.
+#[tauri::command]
+pub async fn compute_text_metrics(
+    content: String,
+    word_count_mode: Option<WordCountMode>,
+    flavor: Option<String>,
+) -> Result<(usize, usize, usize, usize), String> {
+    let mode = word_count_mode.unwrap_or_default();
+    if mode == WordCountMode::All {
+        return Ok(text_metrics::calculate_text_metrics(&content));
+    }
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    tokio::task::spawn_blocking(move || {
+        let (lines, _, chars, width) = text_metrics::calculate_text_metrics(&content);
+        let words = text_metrics::count_words(&content, mode, flavor);
+        (lines, words, chars, width)
+    })
+    .await
+    .map_err(|e| format!("Metrics task failed: {}", e))
+}
+
+#[tauri::command]
+pub async fn compute_selection_metrics(
+    content: String,
+    ranges: Vec<SelectionRange>,
+) -> Result<SelectionMetrics, String> {
+    Ok(text_metrics::compute_selection_metrics(&content, &ranges))
+}
+
+/// Updates cached per-line metrics for a document from line edits. Passing `content`
+/// rebuilds the cache; `expected_length` (editor string length) detects drift.
+#[tauri::command]
+pub async fn update_text_metrics(
+    state: State<'_, AppState>,
+    document_id: String,
+    edits: Vec<LineEdit>,
+    expected_length: usize,
+    content: Option<String>,
+) -> Result<IncrementalMetrics, String> {
+    let mut cache = state.text_metrics.lock().await;
+
+    if let Some(content) = content {
+        let doc = DocumentMetrics::from_content(&content.replace("\r\n", "\n"));
+        let metrics = doc.totals();
+        if cache.len() >= MAX_CACHED_METRICS
+            && !cache.contains_key(&document_id)
+            && let Some(evict) = cache
+                .iter()
+                .min_by_key(|(_, doc)| doc.last_used())
+                .map(|(id, _)| id.clone())
+        {
+            cache.remove(&evict);
+        }
+        cache.insert(document_id, doc);
+        return Ok(IncrementalMetrics {
+            metrics,
+            resynced: true,
+            needs_full_content: false,
+        });
+    }
+
+    let Some(doc) = cache.get_mut(&document_id) else {
+        return Ok(IncrementalMetrics {
+            metrics: (0, 0, 0, 0),
+            resynced: false,
+            needs_full_content: true,
+        });
+    };
+
+    doc.touch();
+    let applied = edits.iter().all(|edit| doc.apply(edit));
+    if !applied || doc.utf16_len() != expected_length {
+        log::debug!(
+            "[Markdown] text metrics drift for {}; requesting full content",
+            document_id
+        );
+        cache.remove(&document_id);
+        return Ok(IncrementalMetrics {
+            metrics: (0, 0, 0, 0),
+            resynced: false,
+            needs_full_content: true,
+        });
+    }
+
+    Ok(IncrementalMetrics {
+        metrics: doc.totals(),
+        resynced: false,
+        needs_full_content: false,
+    })
+}
+
+#[tauri::command]
+pub async fn clear_text_metrics(
+    state: State<'_, AppState>,
+    document_id: String,
+) -> Result<(), String> {
+    state.text_metrics.lock().await.remove(&document_id);
+    Ok(())
+}
+
+/// Counts headings per level, links, images, code blocks, footnotes and task items, for
+/// the document info panel.
+#[tauri::command]
+pub async fn get_document_stats(
+    content: String,
+    flavor: Option<String>,
+) -> Result<DocumentStats, String> {
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    tokio::task::spawn_blocking(move || stats::document_stats(&content, flavor))
+        .await
+        .map_err(|e| format!("Document stats task failed: {}", e))
+}
+
+/// Reports what would make the exported HTML or PDF hard to use with a screen reader:
+/// missing alt text, skipped heading levels, empty or vague links, and empty table headers.
+#[tauri::command]
+pub async fn audit_accessibility(
+    content: String,
+    flavor: Option<String>,
+) -> Result<Vec<AccessibilityIssue>, String> {
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    tokio::task::spawn_blocking(move || accessibility::audit(&content, flavor))
+        .await
+        .map_err(|e| format!("Accessibility task failed: {}", e))
+}
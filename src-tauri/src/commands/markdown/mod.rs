@@ -0,0 +1,6 @@
+pub mod compare;
+pub mod format;
+pub mod metrics;
+pub mod notes;
+pub mod render;
+pub mod structure;
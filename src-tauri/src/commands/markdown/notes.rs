@@ -0,0 +1,272 @@
+use crate::commands::guard::CommandError;
+use crate::commands::jobs::Job;
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::file_links::relative_path;
+use crate::markdown::index_page::{self, IndexEntry, IndexOptions};
+use crate::markdown::similarity::{self, Signature, SimilarDocument};
+use crate::markdown::summary::{self, DocumentSummary};
+use crate::utils::{
+    atomic_write, detect_line_ending, handle_error, path_identity, read_text_with_bom_detection,
+    validate_path,
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_SUMMARY_CHARS: usize = 200;
+const NOTE_FILE_EXTENSIONS: [&str; 4] = ["md", "markdown", "mdown", "txt"];
+// Keeps a scan of a large folder tree from reading a whole drive
+const MAX_NOTE_FILES: usize = 5000;
+const MAX_NOTE_FILE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// A markdown file's title and opening paragraph, for the bookmark and recent file
+/// tooltips. `None` when the file can't be read or is too large to be a note.
+#[tauri::command]
+pub async fn get_file_summary(
+    path: String,
+    max_chars: Option<usize>,
+    flavor: Option<String>,
+) -> Result<Option<DocumentSummary>, String> {
+    validate_path(&path)?;
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    let max_chars = max_chars.unwrap_or(DEFAULT_SUMMARY_CHARS);
+    tokio::task::spawn_blocking(move || {
+        if std::fs::metadata(&path).ok()?.len() > MAX_NOTE_FILE_BYTES {
+            return None;
+        }
+        let raw = std::fs::read(&path).ok()?;
+        let content = read_text_with_bom_detection(&raw);
+        Some(summary::extract_summary(&content, max_chars, flavor))
+    })
+    .await
+    .map_err(|e| format!("Summary task failed: {}", e))
+}
+
+/// Markdown and text files under `folder`, skipping hidden folders and `node_modules`
+pub(crate) fn collect_note_files(folder: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![folder.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            log::debug!("Skipping unreadable folder: {:?}", dir);
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if !name.starts_with('.') && name != "node_modules" {
+                    pending.push(path);
+                }
+            } else if file_type.is_file()
+                && path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| NOTE_FILE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+                && entry
+                    .metadata()
+                    .is_ok_and(|m| m.len() <= MAX_NOTE_FILE_BYTES)
+            {
+                files.push(path);
+                if files.len() >= MAX_NOTE_FILES {
+                    log::warn!(
+                        "Note scan stopped at {} files in {:?}",
+                        MAX_NOTE_FILES,
+                        folder
+                    );
+                    return files;
+                }
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexReport {
+    pub path: String,
+    pub entries: Vec<IndexEntry>,
+    /// False when the index page was already up to date
+    pub written: bool,
+}
+
+/// Writes or updates an index page in `root` (`index.md` by default) listing every markdown
+/// file below it, grouped by subfolder or front matter category and titled by their first
+/// heading. Only the list between `<!-- index -->` and `<!-- /index -->` is replaced, so
+/// text written around it by hand is kept.
+#[tauri::command]
+pub async fn generate_index(
+    root: String,
+    options: Option<IndexOptions>,
+    flavor: Option<String>,
+) -> Result<IndexReport, String> {
+    let start = std::time::Instant::now();
+    validate_path(&root)?;
+    let root = PathBuf::from(root);
+    if !root.is_dir() {
+        return Err(format!("Folder not found: {}", root.display()));
+    }
+    let options = options.unwrap_or_default();
+    let file_name = options.file_name.trim();
+    if file_name.is_empty() || file_name.contains(['/', '\\']) {
+        return Err("Index file name must be a plain file name".to_string());
+    }
+    let index_path = root.join(file_name);
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    let title = options.title.clone().unwrap_or_else(|| {
+        root.file_name()
+            .map_or("Index".to_string(), |n| n.to_string_lossy().into_owned())
+    });
+
+    let scan_root = root.clone();
+    let scan_index = index_path.clone();
+    let (entries, existing) = tokio::task::spawn_blocking(move || {
+        let entries: Vec<IndexEntry> = collect_note_files(&scan_root)
+            .into_iter()
+            .filter(|file| {
+                *file != scan_index
+                    && file
+                        .extension()
+                        .and_then(|e| e.to_str())
+                        .is_some_and(|e| !e.eq_ignore_ascii_case("txt"))
+            })
+            .filter_map(|file| {
+                let relative = relative_path(&scan_root, &file)?;
+                let raw = std::fs::read(&file).ok()?;
+                let content = read_text_with_bom_detection(&raw);
+                Some(index_page::entry(&relative, &content, &options, flavor))
+            })
+            .collect();
+        let existing = std::fs::read(&scan_index)
+            .ok()
+            .map(|raw| read_text_with_bom_detection(&raw));
+        (entries, existing)
+    })
+    .await
+    .map_err(|e| format!("Index task failed: {}", e))?;
+
+    let mut page = index_page::update(existing.as_deref(), &index_page::build(&entries), &title);
+    if existing.as_deref().map(detect_line_ending) == Some("CRLF") {
+        page = page.replace("\r\n", "\n").replace('\n', "\r\n");
+    }
+    let written = existing.as_deref() != Some(page.as_str());
+    if written {
+        atomic_write(&index_path, page.as_bytes())
+            .await
+            .map_err(|e| handle_error(Some(&index_path.to_string_lossy()), "write index", e))?;
+    }
+
+    log::info!(
+        "[Markdown] generate_index | duration={:?} | entries={} | written={}",
+        start.elapsed(),
+        entries.len(),
+        written
+    );
+    Ok(IndexReport {
+        path: index_path.to_string_lossy().into_owned(),
+        entries,
+        written,
+    })
+}
+
+/// Finds near-duplicate notes in `folder` (by default the folder of `path`, searched
+/// recursively) using MinHash over five-word shingles. With `content` or `path` the notes
+/// are compared against that document; with neither, every pair of similar notes in the
+/// folder is returned as merge candidates. `threshold` is the minimum estimated Jaccard
+/// similarity, 0.5 by default. With `job_id` the scan can be stopped by `cancel_job` and
+/// reports `job-progress` per note read.
+#[tauri::command]
+pub async fn find_similar(
+    app_handle: tauri::AppHandle,
+    path: Option<String>,
+    content: Option<String>,
+    folder: Option<String>,
+    threshold: Option<f64>,
+    limit: Option<usize>,
+    job_id: Option<String>,
+) -> Result<Vec<SimilarDocument>, CommandError> {
+    let start = std::time::Instant::now();
+    let path = path.filter(|p| !p.is_empty());
+    if let Some(path) = &path {
+        validate_path(path)?;
+    }
+    let folder = match folder.filter(|f| !f.is_empty()) {
+        Some(folder) => {
+            validate_path(&folder)?;
+            PathBuf::from(folder)
+        },
+        None => path
+            .as_deref()
+            .and_then(|p| Path::new(p).parent())
+            .map(Path::to_path_buf)
+            .ok_or_else(|| "A folder or document path is required".to_string())?,
+    };
+    if !folder.is_dir() {
+        return Err(format!("Folder not found: {}", folder.display()).into());
+    }
+    let threshold = threshold
+        .unwrap_or(similarity::DEFAULT_THRESHOLD)
+        .clamp(0.0, 1.0);
+    let job = Job::start(&app_handle, "find_similar", job_id);
+
+    let scan = job.clone();
+    let (mut matches, scanned) = tokio::task::spawn_blocking(move || -> Result<_, String> {
+        let target = match (&content, &path) {
+            (Some(content), _) => Some(similarity::signature(content)),
+            (None, Some(path)) => {
+                let raw = std::fs::read(path)
+                    .map_err(|e| handle_error(Some(path), "read document for similarity", e))?;
+                Some(similarity::signature(&read_text_with_bom_detection(&raw)))
+            },
+            (None, None) => None,
+        };
+        let target_identity = path.as_deref().map(|p| path_identity(p, true));
+
+        let files = collect_note_files(&folder);
+        let total = files.len();
+        let documents: Vec<(String, Signature)> = files
+            .into_iter()
+            .enumerate()
+            .take_while(|_| !scan.is_cancelled())
+            .filter_map(|(index, file)| {
+                scan.progress(index + 1, total, None);
+                let file = file.to_string_lossy().into_owned();
+                if target_identity.as_deref() == Some(path_identity(&file, true).as_str()) {
+                    return None;
+                }
+                let raw = std::fs::read(&file).ok()?;
+                let sig = similarity::signature(&read_text_with_bom_detection(&raw));
+                Some((file, sig))
+            })
+            .collect();
+        if scan.is_cancelled() {
+            return Ok((Vec::new(), documents.len()));
+        }
+
+        let matches = match &target {
+            Some(target) => similarity::similar_to(target, &documents, threshold),
+            None => similarity::duplicate_pairs(&documents, threshold),
+        };
+        Ok((matches, documents.len()))
+    })
+    .await
+    .map_err(|e| format!("Similarity task failed: {}", e))??;
+    job.check()?;
+
+    if let Some(limit) = limit {
+        matches.truncate(limit);
+    }
+
+    log::info!(
+        "[Markdown] find_similar | duration={:?} | scanned={} | matches={}",
+        start.elapsed(),
+        scanned,
+        matches.len()
+    );
+
+    Ok(matches)
+}
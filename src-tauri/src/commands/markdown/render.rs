@@ -0,0 +1,194 @@
+use crate::commands::guard::{self, CommandError};
+use crate::commands::settings::{read_diagram_settings, read_path_link_settings};
+use crate::markdown::config::{ExtensionToggles, MarkdownFlavor};
+use crate::markdown::diagrams;
+use crate::markdown::diff::{self, MarkdownDiff};
+use crate::markdown::incremental::{self, EditRange, IncrementalRenderResult};
+use crate::markdown::renderer::{self, MarkdownOptions, RenderResult};
+use crate::markdown::security::{HtmlPolicy, RemotePolicy};
+use crate::markdown::text_metrics::WordCountMode;
+use crate::preview_assets;
+use crate::utils::{IntoTauriError, handle_error};
+use tauri::Emitter;
+
+/// Sent when diagrams the preview showed as code have rendered; the preview renders again
+pub const DIAGRAMS_READY_EVENT: &str = "diagrams-ready";
+// Tooltips and popovers; anything longer belongs in the preview
+const MAX_INLINE_SNIPPET_BYTES: usize = 16 * 1024;
+
+/// Tells the frontend when background diagram renders finish
+pub fn setup_diagram_events(app_handle: &tauri::AppHandle) {
+    let app_handle = app_handle.clone();
+    diagrams::on_background_ready(move || {
+        if let Err(e) = app_handle.emit(DIAGRAMS_READY_EVENT, ()) {
+            log::warn!("[Diagrams] Failed to notify the preview: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn render_markdown(
+    app_handle: tauri::AppHandle,
+    content: String,
+    flavor: Option<String>,
+    remote_policy: Option<RemotePolicy>,
+    extensions: Option<ExtensionToggles>,
+    hide_front_matter: Option<bool>,
+    heading_id_prefix: Option<String>,
+    base_path: Option<String>,
+    html_policy: Option<HtmlPolicy>,
+    word_count_mode: Option<WordCountMode>,
+) -> Result<RenderResult, CommandError> {
+    let _permit = guard::acquire(&app_handle, "render_markdown", content.len()).await?;
+    let start = std::time::Instant::now();
+    let content_size = content.len();
+
+    let options = MarkdownOptions {
+        flavor: MarkdownFlavor::from_option_str(flavor),
+        remote_policy: remote_policy.unwrap_or_default(),
+        extensions: extensions.unwrap_or_default(),
+        hide_front_matter: hide_front_matter.unwrap_or(false),
+        heading_id_prefix: heading_id_prefix.unwrap_or_default(),
+        base_path: base_path.filter(|p| !p.is_empty()),
+        html_policy: html_policy.unwrap_or_default(),
+        word_count_mode: word_count_mode.unwrap_or_default(),
+        diagrams: read_diagram_settings(&app_handle).await,
+        path_links: read_path_link_settings(&app_handle).await,
+        preview: true,
+    };
+
+    let result = tokio::task::spawn_blocking(move || renderer::render_markdown(&content, options))
+        .await
+        .map_err(|e| format!("Render task failed: {}", e))?
+        .to_tauri_result();
+    if let Ok(rendered) = &result {
+        preview_assets::allow_files(&rendered.local_images);
+    }
+
+    let duration = start.elapsed();
+    log::info!(
+        "[Markdown] render_markdown | duration={:?} | size={} bytes",
+        duration,
+        content_size
+    );
+
+    Ok(result?)
+}
+
+/// Renders a short snippet for a link tooltip or footnote popover. Skips the line map,
+/// path links and everything else a full render builds, so it runs inline rather than on
+/// a blocking thread.
+#[tauri::command]
+pub async fn render_markdown_inline(
+    snippet: String,
+    flavor: Option<String>,
+) -> Result<String, String> {
+    if snippet.len() > MAX_INLINE_SNIPPET_BYTES {
+        return Err(format!(
+            "Snippet is too long to render inline ({} bytes)",
+            snippet.len()
+        ));
+    }
+    renderer::render_inline(&snippet, MarkdownFlavor::from_option_str(flavor))
+        .map_err(|e| handle_error(None, "render inline markdown", e))
+}
+
+/// Renders `new` with what changed since `old` marked with `<ins>` and `<del>`, for
+/// showing the edits since the last save in the preview.
+#[tauri::command]
+pub async fn render_markdown_diff(
+    app_handle: tauri::AppHandle,
+    old: String,
+    new: String,
+    flavor: Option<String>,
+) -> Result<MarkdownDiff, CommandError> {
+    let _permit =
+        guard::acquire(&app_handle, "render_markdown_diff", old.len() + new.len()).await?;
+    let start = std::time::Instant::now();
+    let options = MarkdownOptions {
+        flavor: MarkdownFlavor::from_option_str(flavor),
+        diagrams: read_diagram_settings(&app_handle).await,
+        path_links: read_path_link_settings(&app_handle).await,
+        preview: true,
+        ..Default::default()
+    };
+
+    let result = tokio::task::spawn_blocking(move || diff::render_diff(&old, &new, &options))
+        .await
+        .map_err(|e| format!("Diff render task failed: {}", e))?
+        .to_tauri_result()?;
+    preview_assets::allow_files(&result.local_images);
+
+    log::info!(
+        "[Markdown] render_markdown_diff | duration={:?} | added={} | removed={} | changed={}",
+        start.elapsed(),
+        result.blocks_added,
+        result.blocks_removed,
+        result.blocks_changed
+    );
+    Ok(result)
+}
+
+/// Block-level render for large documents. Pass the previous result's `content_hash` and
+/// the edited range to get back only the blocks that changed.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn render_markdown_incremental(
+    app_handle: tauri::AppHandle,
+    content: String,
+    flavor: Option<String>,
+    remote_policy: Option<RemotePolicy>,
+    extensions: Option<ExtensionToggles>,
+    hide_front_matter: Option<bool>,
+    heading_id_prefix: Option<String>,
+    base_path: Option<String>,
+    html_policy: Option<HtmlPolicy>,
+    word_count_mode: Option<WordCountMode>,
+    previous_hash: Option<String>,
+    edit: Option<EditRange>,
+) -> Result<IncrementalRenderResult, CommandError> {
+    let _permit = guard::acquire(&app_handle, "render_markdown_incremental", content.len()).await?;
+    let start = std::time::Instant::now();
+    let content_size = content.len();
+
+    let options = MarkdownOptions {
+        flavor: MarkdownFlavor::from_option_str(flavor),
+        remote_policy: remote_policy.unwrap_or_default(),
+        extensions: extensions.unwrap_or_default(),
+        hide_front_matter: hide_front_matter.unwrap_or(false),
+        heading_id_prefix: heading_id_prefix.unwrap_or_default(),
+        base_path: base_path.filter(|p| !p.is_empty()),
+        html_policy: html_policy.unwrap_or_default(),
+        word_count_mode: word_count_mode.unwrap_or_default(),
+        diagrams: read_diagram_settings(&app_handle).await,
+        path_links: read_path_link_settings(&app_handle).await,
+        preview: true,
+    };
+
+    let result = tokio::task::spawn_blocking(move || {
+        incremental::render_incremental(&content, options, previous_hash.as_deref(), edit)
+    })
+    .await
+    .map_err(|e| format!("Render task failed: {}", e))?
+    .to_tauri_result()?;
+    preview_assets::allow_files(&result.local_images);
+
+    log::info!(
+        "[Markdown] render_markdown_incremental | duration={:?} | size={} bytes | patched={}",
+        start.elapsed(),
+        content_size,
+        result.patch.is_some()
+    );
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn get_markdown_flavors() -> Result<Vec<String>, String> {
+    Ok(vec![
+        "commonmark".to_string(),
+        "gfm".to_string(),
+        "custom".to_string(),
+    ])
+}
@@ -0,0 +1,131 @@
+use crate::commands::guard::{self, CommandError};
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::front_matter::{self, FrontMatterInfo};
+use crate::markdown::sections::{
+    self, BreadcrumbItem, HeadingFixResult, HeadingShift, MoveDirection, OutlineNode, SectionMove,
+};
+use crate::markdown::symbols::{self, SymbolKind, SymbolMatch};
+use std::collections::HashMap;
+
+/// Previews or applies unique names for headings whose anchors collide. `renames` maps a
+/// heading line to replacement text; other duplicates get a numeric suffix.
+#[tauri::command]
+pub async fn fix_duplicate_headings(
+    content: String,
+    renames: Option<HashMap<usize, String>>,
+    flavor: Option<String>,
+) -> Result<HeadingFixResult, String> {
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    let renames = renames.unwrap_or_default();
+    tokio::task::spawn_blocking(move || {
+        sections::fix_duplicate_headings(&content, &renames, flavor)
+    })
+    .await
+    .map_err(|e| format!("Heading fix task failed: {}", e))
+}
+
+/// Moves the heading at `heading_line` and its subtree past its previous or next sibling.
+#[tauri::command]
+pub async fn move_section(
+    content: String,
+    heading_line: usize,
+    direction: MoveDirection,
+    flavor: Option<String>,
+) -> Result<SectionMove, String> {
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    tokio::task::spawn_blocking(move || {
+        sections::move_section(&content, heading_line, direction, flavor)
+    })
+    .await
+    .map_err(|e| format!("Move section task failed: {}", e))?
+}
+
+async fn shift_headings_task(
+    content: String,
+    delta: i8,
+    from_line: Option<usize>,
+    to_line: Option<usize>,
+    flavor: Option<String>,
+) -> Result<HeadingShift, String> {
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    let range = from_line.map(|from| (from, to_line.unwrap_or(usize::MAX)));
+    tokio::task::spawn_blocking(move || sections::shift_headings(&content, delta, range, flavor))
+        .await
+        .map_err(|e| format!("Heading shift task failed: {}", e))
+}
+
+/// Raises every heading (or those in the line range) by `levels`, stopping at H1.
+#[tauri::command]
+pub async fn promote_headings(
+    content: String,
+    levels: Option<u8>,
+    from_line: Option<usize>,
+    to_line: Option<usize>,
+    flavor: Option<String>,
+) -> Result<HeadingShift, String> {
+    let delta = -(levels.unwrap_or(1).min(5) as i8);
+    shift_headings_task(content, delta, from_line, to_line, flavor).await
+}
+
+/// Lowers every heading (or those in the line range) by `levels`, stopping at H6.
+#[tauri::command]
+pub async fn demote_headings(
+    content: String,
+    levels: Option<u8>,
+    from_line: Option<usize>,
+    to_line: Option<usize>,
+    flavor: Option<String>,
+) -> Result<HeadingShift, String> {
+    let delta = levels.unwrap_or(1).min(5) as i8;
+    shift_headings_task(content, delta, from_line, to_line, flavor).await
+}
+
+/// Heading tree for the outline sidebar, with slugs and source lines for jump-to-heading.
+#[tauri::command]
+pub async fn get_document_outline(
+    content: String,
+    flavor: Option<String>,
+) -> Result<Vec<OutlineNode>, String> {
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    tokio::task::spawn_blocking(move || sections::outline(&content, flavor))
+        .await
+        .map_err(|e| format!("Outline task failed: {}", e))
+}
+
+/// Headings enclosing the cursor, outermost first, for the breadcrumb bar and
+/// go-to-parent-section.
+#[tauri::command]
+pub async fn get_breadcrumb(
+    content: String,
+    cursor_offset: usize,
+    flavor: Option<String>,
+) -> Result<Vec<BreadcrumbItem>, String> {
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    tokio::task::spawn_blocking(move || sections::breadcrumb(&content, cursor_offset, flavor))
+        .await
+        .map_err(|e| format!("Breadcrumb task failed: {}", e))
+}
+
+/// Emoji shortcodes and `\`-named symbols fuzzily matching `query`, best first, for the
+/// editor's `:` and `\` completion menus. `kind` limits results to one of the two.
+#[tauri::command]
+pub async fn search_symbols(
+    app_handle: tauri::AppHandle,
+    query: String,
+    kind: Option<SymbolKind>,
+    limit: Option<usize>,
+) -> Result<Vec<SymbolMatch>, CommandError> {
+    let _permit = guard::acquire(&app_handle, "search_symbols", query.len()).await?;
+    let matches = tokio::task::spawn_blocking(move || symbols::search_symbols(&query, kind, limit))
+        .await
+        .map_err(|e| format!("Symbol search task failed: {}", e))?;
+    Ok(matches)
+}
+
+/// Parses the leading YAML or TOML front matter block into JSON.
+#[tauri::command]
+pub async fn get_front_matter(content: String) -> Result<Option<FrontMatterInfo>, String> {
+    tokio::task::spawn_blocking(move || front_matter::info(&content))
+        .await
+        .map_err(|e| format!("Front matter task failed: {}", e))
+}
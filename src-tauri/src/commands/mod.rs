@@ -1,10 +1,25 @@
+pub mod annotations;
+pub mod autosave;
 pub mod bookmarks;
 pub mod export;
 pub mod files;
+pub mod glossary;
+pub mod guard;
+pub mod inbox;
+pub mod jobs;
+pub mod maintenance;
 pub mod markdown;
+pub mod open_paths;
+pub mod presave;
+pub mod searches;
+pub mod sections;
 pub mod session;
 pub mod settings;
 pub mod spellcheck;
+pub mod status;
+pub mod templates;
 pub mod updater;
+pub mod versions;
+pub mod writing;
 
 pub mod data;
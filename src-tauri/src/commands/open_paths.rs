@@ -0,0 +1,133 @@
+use crate::commands::files::symlink_policy;
+use crate::safe_mode;
+use crate::state::AppState;
+use crate::utils::path_identity;
+use path_clean::PathClean;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tauri::{Emitter, Manager, State};
+
+pub const FOCUS_TAB_EVENT: &str = "focus-tab";
+
+#[derive(Debug, Deserialize)]
+pub struct OpenTabPath {
+    pub id: String,
+    pub path: String,
+}
+
+/// Open file-backed tabs keyed by path identity, so `./a.md`, `A.md` on Windows and,
+/// when links are resolved, a symlink all match the tab that already has the file.
+#[derive(Debug, Default)]
+pub struct OpenPaths {
+    /// `SymlinkPolicy::resolve_for_identity` at the last sync
+    resolve_links: bool,
+    by_key: HashMap<String, String>,
+}
+
+impl OpenPaths {
+    fn key(&self, path: &str) -> String {
+        path_identity(path, self.resolve_links)
+    }
+
+    fn tab_for(&self, path: &str) -> Option<String> {
+        self.by_key.get(&self.key(path)).cloned()
+    }
+}
+
+/// File arguments resolved against `cwd`, the working directory of the process that
+/// received them. Symlinks are left as given so tabs show the path the user opened;
+/// repeats of the same path are dropped.
+pub fn resolve_launch_paths(args: &[String], cwd: &Path) -> Vec<String> {
+    let mut seen = HashSet::new();
+    safe_mode::file_args(args)
+        .into_iter()
+        .map(|arg| cwd.join(&arg).clean().to_string_lossy().to_string())
+        .filter(|path| seen.insert(path_identity(path, false)))
+        .collect()
+}
+
+/// Focuses the tab for each path that is already open and asks the frontend to open
+/// the rest.
+pub fn open_launch_paths(app_handle: &tauri::AppHandle, paths: &[String]) {
+    for path in paths {
+        if focus_open_tab(app_handle, path) {
+            log::info!("File from command line already open: {}", path);
+        } else {
+            log::info!("Opening file from command line: {}", path);
+            if let Err(e) = app_handle.emit("open-file-from-args", path) {
+                log::warn!("Failed to emit open-file-from-args: {}", e);
+            }
+        }
+    }
+}
+
+fn open_tab_id(state: &AppState, path: &str) -> Option<String> {
+    state
+        .open_paths
+        .lock()
+        .ok()
+        .and_then(|open| open.tab_for(path))
+}
+
+/// Brings the window forward and asks the frontend to activate the tab holding `path`.
+/// Returns `false` when no tab has the file open.
+pub fn focus_open_tab(app_handle: &tauri::AppHandle, path: &str) -> bool {
+    // Not managed yet when a second instance starts during setup
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        return false;
+    };
+    let Some(tab_id) = open_tab_id(&state, path) else {
+        return false;
+    };
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+    if let Err(e) = app_handle.emit(FOCUS_TAB_EVENT, &tab_id) {
+        log::warn!("Failed to emit focus-tab: {}", e);
+        return false;
+    }
+    true
+}
+
+/// Replaces the tracked set with the frontend's current file-backed tabs. Called
+/// whenever a tab is opened, closed, or saved under a new path.
+#[tauri::command]
+pub async fn sync_open_paths(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    tabs: Vec<OpenTabPath>,
+) -> Result<(), String> {
+    let resolve_links = symlink_policy(&app_handle).await.resolve_for_identity;
+    let by_key = tokio::task::spawn_blocking(move || {
+        tabs.into_iter()
+            .filter(|t| !t.path.is_empty())
+            .map(|t| (path_identity(&t.path, resolve_links), t.id))
+            .collect::<HashMap<_, _>>()
+    })
+    .await
+    .map_err(|e| format!("Open paths task failed: {}", e))?;
+
+    let mut open = state
+        .open_paths
+        .lock()
+        .map_err(|_| "Open paths lock poisoned".to_string())?;
+    *open = OpenPaths {
+        resolve_links,
+        by_key,
+    };
+    Ok(())
+}
+
+/// Id of the tab that has `path` open, if any.
+#[tauri::command]
+pub fn is_path_open(state: State<'_, AppState>, path: String) -> Option<String> {
+    open_tab_id(&state, &path)
+}
+
+#[tauri::command]
+pub fn focus_tab_for_path(app_handle: tauri::AppHandle, path: String) -> bool {
+    focus_open_tab(&app_handle, &path)
+}
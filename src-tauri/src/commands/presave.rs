@@ -0,0 +1,283 @@
+use crate::commands::markdown::format::format_on_thread;
+use crate::commands::settings::{read_flavor_setting, read_setting};
+use crate::markdown::config::{DEFAULT_LIST_INDENT, MarkdownFlavor};
+use crate::markdown::formatter::FormatterOptions;
+use crate::markdown::glossary::Glossary;
+use crate::markdown::lint::{self, LintIssue, RULE_SPELLING, Severity};
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::Manager;
+
+const PRESAVE_SETTING_KEY: &str = "preSave";
+// A document full of jargon shouldn't flood the report
+const MAX_SPELLING_ISSUES: usize = 100;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SeverityAction {
+    Ignore,
+    Warn,
+    Fix,
+    Block,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PreSaveSettings {
+    pub enabled: bool,
+    pub run_formatter: bool,
+    pub run_linter: bool,
+    pub check_links: bool,
+    pub check_spelling: bool,
+    pub on_info: SeverityAction,
+    pub on_warning: SeverityAction,
+    pub on_error: SeverityAction,
+}
+
+impl Default for PreSaveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            run_formatter: false,
+            run_linter: true,
+            check_links: true,
+            check_spelling: false,
+            on_info: SeverityAction::Fix,
+            on_warning: SeverityAction::Warn,
+            on_error: SeverityAction::Block,
+        }
+    }
+}
+
+impl PreSaveSettings {
+    fn action_for(&self, severity: Severity) -> SeverityAction {
+        match severity {
+            Severity::Info => self.on_info,
+            Severity::Warning => self.on_warning,
+            Severity::Error => self.on_error,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreSaveReport {
+    /// Content as it would be written, after formatting and auto-fixes
+    pub content: String,
+    pub blocked: bool,
+    pub formatted: bool,
+    pub fixed: usize,
+    pub issues: Vec<LintIssue>,
+}
+
+impl PreSaveReport {
+    fn unchanged(content: String) -> Self {
+        Self {
+            content,
+            blocked: false,
+            formatted: false,
+            fixed: 0,
+            issues: Vec::new(),
+        }
+    }
+}
+
+async fn formatter_options(
+    app_handle: &tauri::AppHandle,
+    flavor: MarkdownFlavor,
+) -> FormatterOptions {
+    let defaults = FormatterOptions::default();
+    FormatterOptions {
+        flavor,
+        list_indent: read_setting::<usize>(app_handle, "defaultIndent")
+            .await
+            .unwrap_or(DEFAULT_LIST_INDENT),
+        bullet_char: read_setting(app_handle, "formatterBulletChar")
+            .await
+            .unwrap_or(defaults.bullet_char),
+        code_block_fence: read_setting(app_handle, "formatterCodeFence")
+            .await
+            .unwrap_or(defaults.code_block_fence),
+        emphasis_char: read_setting(app_handle, "formatterEmphasisChar")
+            .await
+            .unwrap_or(defaults.emphasis_char),
+        table_alignment: read_setting(app_handle, "formatterTableAlignment")
+            .await
+            .unwrap_or(defaults.table_alignment),
+        text_wrap: read_setting(app_handle, "formatterTextWrap")
+            .await
+            .unwrap_or(defaults.text_wrap),
+        line_width: read_setting(app_handle, "formatterLineWidth")
+            .await
+            .unwrap_or(defaults.line_width),
+        link_style: read_setting(app_handle, "formatterLinkStyle")
+            .await
+            .unwrap_or(defaults.link_style),
+        ordered_list_style: read_setting(app_handle, "formatterOrderedListStyle")
+            .await
+            .unwrap_or(defaults.ordered_list_style),
+        ..defaults
+    }
+}
+
+async fn spelling_issues(state: &AppState, words: Vec<(usize, String)>) -> Vec<LintIssue> {
+    let speller_guard = state.speller.lock().await;
+    let Some(speller) = speller_guard.as_ref() else {
+        return Vec::new();
+    };
+    let custom_dict = state.custom_dict.lock().await;
+
+    tokio::task::block_in_place(|| {
+        words
+            .into_iter()
+            .filter(|(_, word)| {
+                let lower = word.to_lowercase();
+                let base = lower
+                    .strip_suffix("'s")
+                    .or_else(|| lower.strip_suffix('\''))
+                    .unwrap_or(&lower);
+                !custom_dict.contains(&lower) && !custom_dict.contains(base) && !speller.check(word)
+            })
+            .take(MAX_SPELLING_ISSUES)
+            .map(|(line, word)| LintIssue {
+                rule: RULE_SPELLING,
+                severity: Severity::Info,
+                line,
+                message: format!("Possible misspelling: {}", word),
+                fixable: false,
+            })
+            .collect()
+    })
+}
+
+/// Runs the configured formatter, linter, link and spelling checks over `content`, applies
+/// fixes for severities set to `fix`, and marks the save blocked if any `block` issue remains.
+async fn run_pipeline(
+    app_handle: &tauri::AppHandle,
+    path: Option<&str>,
+    content: String,
+) -> PreSaveReport {
+    let settings: PreSaveSettings = read_setting(app_handle, PRESAVE_SETTING_KEY)
+        .await
+        .unwrap_or_default();
+    if !settings.enabled {
+        return PreSaveReport::unchanged(content);
+    }
+
+    let flavor = read_flavor_setting(app_handle).await;
+    let mut report = PreSaveReport::unchanged(content);
+
+    if settings.run_formatter {
+        let options = formatter_options(app_handle, flavor).await;
+        match format_on_thread(report.content.clone(), options).await {
+            Ok(formatted) => {
+                report.formatted = formatted != report.content;
+                report.content = formatted;
+            },
+            Err(e) => log::warn!("[PreSave] Formatter failed, saving unformatted: {}", e),
+        }
+    }
+
+    let mut issues = Vec::new();
+    if settings.run_linter {
+        issues.extend(lint::lint(&report.content, flavor));
+        match path.map_or(Ok(None), |p| Glossary::find(Path::new(p))) {
+            Ok(Some((_, glossary))) => {
+                issues.extend(lint::check_terms(&report.content, &glossary));
+            },
+            Ok(None) => {},
+            Err(e) => log::warn!("[PreSave] Skipping glossary check: {}", e),
+        }
+    }
+    if settings.check_links
+        && let Some(base_dir) = path.and_then(|p| Path::new(p).parent())
+    {
+        issues.extend(lint::check_links(&report.content, base_dir, flavor));
+    }
+    if settings.check_spelling {
+        let words = lint::prose_words(&report.content, flavor);
+        issues.extend(spelling_issues(&app_handle.state::<AppState>(), words).await);
+    }
+
+    let fix_rules: Vec<&str> = issues
+        .iter()
+        .filter(|i| i.fixable && settings.action_for(i.severity) == SeverityAction::Fix)
+        .map(|i| i.rule)
+        .collect();
+    if !fix_rules.is_empty() {
+        report.content = lint::apply_fixes(&report.content, &fix_rules);
+    }
+
+    for issue in issues {
+        match settings.action_for(issue.severity) {
+            SeverityAction::Ignore => {},
+            SeverityAction::Fix if issue.fixable => report.fixed += 1,
+            SeverityAction::Block => {
+                report.blocked = true;
+                report.issues.push(issue);
+            },
+            SeverityAction::Fix | SeverityAction::Warn => report.issues.push(issue),
+        }
+    }
+
+    report
+}
+
+#[tauri::command]
+pub async fn run_presave_checks(
+    app_handle: tauri::AppHandle,
+    path: Option<String>,
+    content: String,
+) -> Result<PreSaveReport, String> {
+    Ok(run_pipeline(&app_handle, path.as_deref(), content).await)
+}
+
+/// Saves through the pre-save pipeline. A blocked save writes nothing; otherwise the
+/// (possibly fixed) content is written. Either way the report goes back to the caller, whose
+/// `content` stays LF so the editor can take it over; `line_ending` only applies on disk.
+#[tauri::command]
+pub async fn save_with_checks(
+    app_handle: tauri::AppHandle,
+    path: String,
+    content: String,
+    line_ending: Option<String>,
+    encoding: Option<String>,
+    has_bom: Option<bool>,
+) -> Result<PreSaveReport, String> {
+    let start = std::time::Instant::now();
+
+    let report = run_pipeline(&app_handle, Some(&path), content.replace("\r\n", "\n")).await;
+
+    if report.blocked {
+        log::info!(
+            "[PreSave] save blocked | issues={} | path={}",
+            report.issues.len(),
+            path
+        );
+        return Ok(report);
+    }
+
+    let disk_content = if line_ending.as_deref() == Some("CRLF") {
+        report.content.replace('\n', "\r\n")
+    } else {
+        report.content.clone()
+    };
+    crate::commands::files::write_text_file(
+        app_handle.clone(),
+        path.clone(),
+        disk_content,
+        encoding,
+        has_bom,
+    )
+    .await?;
+
+    log::info!(
+        "[PreSave] save_with_checks | duration={:?} | fixed={} | issues={} | path={}",
+        start.elapsed(),
+        report.fixed,
+        report.issues.len(),
+        path
+    );
+
+    Ok(report)
+}
@@ -0,0 +1,63 @@
+use crate::db::{SavedSearch, SearchQuery};
+use crate::state::AppState;
+use crate::utils::handle_error;
+use tauri::State;
+
+#[tauri::command]
+pub fn record_search(
+    state: State<'_, AppState>,
+    query: SearchQuery,
+) -> Result<SavedSearch, String> {
+    state
+        .db
+        .record_search(&query)
+        .map_err(|e| handle_error(Some("search history"), "record search", e))
+}
+
+#[tauri::command]
+pub fn save_search(
+    state: State<'_, AppState>,
+    name: String,
+    query: SearchQuery,
+) -> Result<SavedSearch, String> {
+    state
+        .db
+        .save_search(&name, &query)
+        .map_err(|e| handle_error(Some(&name), "save search", e))
+}
+
+#[tauri::command]
+pub fn list_searches(
+    state: State<'_, AppState>,
+    saved_only: Option<bool>,
+    limit: Option<u32>,
+) -> Result<Vec<SavedSearch>, String> {
+    state
+        .db
+        .list_searches(saved_only.unwrap_or(false), limit)
+        .map_err(|e| handle_error(Some("searches"), "list searches", e))
+}
+
+#[tauri::command]
+pub fn pin_search(state: State<'_, AppState>, id: String, pinned: bool) -> Result<(), String> {
+    state
+        .db
+        .set_search_pinned(&id, pinned)
+        .map_err(|e| handle_error(Some(&id), "pin search", e))
+}
+
+#[tauri::command]
+pub fn delete_search(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    state
+        .db
+        .delete_search(&id)
+        .map_err(|e| handle_error(Some(&id), "delete search", e))
+}
+
+#[tauri::command]
+pub fn clear_search_history(state: State<'_, AppState>) -> Result<usize, String> {
+    state
+        .db
+        .clear_search_history()
+        .map_err(|e| handle_error(Some("search history"), "clear search history", e))
+}
@@ -0,0 +1,162 @@
+use crate::commands::settings::get_max_file_size_bytes;
+use crate::commands::templates::get_template_variables;
+use crate::markdown::combine::{self, CombineOptions, CombineResult};
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::file_links::relative_path;
+use crate::markdown::sections;
+use crate::state::AppState;
+use crate::utils::{handle_error, validate_path};
+use serde::Serialize;
+use std::path::Path;
+use tauri::State;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+// `{{title}}`, `{{content}}` and the built-in template variables are filled in when the
+// new file is written
+const SECTION_TEMPLATE: &str = "{{content}}\n";
+const RECENT_TIMESTAMP_FORMAT: &str = "%Y%m%d / %H%M%S";
+
+#[derive(Debug, Serialize)]
+pub struct ExtractedSection {
+    /// Source document with the section replaced by a link
+    pub content: String,
+    pub target_path: String,
+    pub link: String,
+}
+
+/// Link to `target` as written in `source`: relative to the source's folder, or the full
+/// path with forward slashes when there is no source or the two are on different drives.
+/// Spaces are percent-encoded so the link stays valid.
+fn link_target(source: Option<&Path>, target: &Path) -> String {
+    let link = source
+        .and_then(Path::parent)
+        .and_then(|folder| relative_path(folder, target))
+        .unwrap_or_else(|| target.to_string_lossy().replace('\\', "/"));
+    link.replace(' ', "%20")
+}
+
+/// Moves the section at `heading_line` into a new file at `target_path` and replaces it with
+/// a link. The target must not already exist. Both files are added to recent files.
+#[tauri::command]
+pub async fn extract_section(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    content: String,
+    heading_line: usize,
+    target_path: String,
+    source_path: Option<String>,
+    template: Option<String>,
+    workspace: Option<String>,
+    flavor: Option<String>,
+) -> Result<ExtractedSection, String> {
+    validate_path(&target_path)?;
+    let variables =
+        get_template_variables(app_handle, Some(target_path.clone()), workspace).await?;
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    let target = Path::new(&target_path);
+    let link = link_target(source_path.as_deref().map(Path::new), target);
+
+    let extract = sections::extract_section(&content, heading_line, &link, flavor)?;
+
+    let now = chrono::Local::now();
+    let template = template
+        .as_deref()
+        .unwrap_or(SECTION_TEMPLATE)
+        .replace("{{title}}", &extract.title);
+    // The section goes in last so placeholders in its text are kept as written
+    let file_content = variables
+        .expand(&template)
+        .replace("{{content}}", &extract.section);
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| handle_error(Some(&target_path), "create directory", e))?;
+    }
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => "A file with that name already exists".to_string(),
+            _ => handle_error(Some(&target_path), "create file", e),
+        })?;
+    file.write_all(file_content.as_bytes())
+        .await
+        .map_err(|e| handle_error(Some(&target_path), "write file", e))?;
+    file.sync_all()
+        .await
+        .map_err(|e| handle_error(Some(&target_path), "flush file", e))?;
+
+    let timestamp = now.format(RECENT_TIMESTAMP_FORMAT).to_string();
+    for path in std::iter::once(&target_path).chain(source_path.as_ref()) {
+        if let Err(e) = state.db.add_recent_file(path, &timestamp) {
+            log::warn!("Failed to add '{}' to recent files: {}", path, e);
+        }
+    }
+
+    log::info!(
+        "[Markdown] extract_section | line={} | size={} bytes | path={}",
+        heading_line,
+        file_content.len(),
+        target_path
+    );
+
+    Ok(ExtractedSection {
+        content: extract.source,
+        target_path,
+        link: extract.link,
+    })
+}
+
+/// Reads the files in the given order and combines them into one document. Nothing is
+/// written; the frontend opens the result in a new tab.
+#[tauri::command]
+pub async fn combine_files(
+    app_handle: tauri::AppHandle,
+    paths: Vec<String>,
+    options: Option<CombineOptions>,
+    flavor: Option<String>,
+) -> Result<CombineResult, String> {
+    let start = std::time::Instant::now();
+
+    if paths.is_empty() {
+        return Err("No files to combine".to_string());
+    }
+
+    let max_file_size = get_max_file_size_bytes(&app_handle).await;
+    let mut documents = Vec::with_capacity(paths.len());
+    for path in &paths {
+        validate_path(path)?;
+        let metadata = fs::metadata(path)
+            .await
+            .map_err(|e| handle_error(Some(path), "read metadata", e))?;
+        if metadata.len() > max_file_size {
+            return Err(format!("File too large to combine: {}", path));
+        }
+        let content = fs::read_to_string(path)
+            .await
+            .map_err(|e| handle_error(Some(path), "read file", e))?;
+        documents.push(content.trim_start_matches('\u{feff}').to_string());
+    }
+
+    let options = options.unwrap_or_default();
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+    let result = tokio::task::spawn_blocking(move || {
+        combine::combine_documents(&documents, &options, flavor)
+    })
+    .await
+    .map_err(|e| format!("Combine task failed: {}", e))?;
+
+    log::info!(
+        "[Markdown] combine_files | duration={:?} | files={} | size={} bytes",
+        start.elapsed(),
+        paths.len(),
+        result.content.len()
+    );
+
+    Ok(result)
+}
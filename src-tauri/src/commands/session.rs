@@ -1,32 +1,83 @@
-use crate::db::{SessionData, TabData, TabState};
+use crate::commands::maintenance::{VacuumTrigger, run_scheduled_maintenance, vacuum_pages};
+use crate::commands::settings::{read_history_settings, read_setting};
+use crate::db::{SessionData, TabData, TabGroup, TabHistory, TabMeta, TabState};
+use crate::markdown::tab_title;
+use crate::safe_mode::{self, SafeMode};
+use crate::startup::{StartupProfiler, StartupReport};
 use crate::state::AppState;
 use crate::utils::handle_error;
-use tauri::State;
+use std::path::{Path, PathBuf};
+use tauri::{Manager, State};
 
+const STARTUP_MODE_SETTING_KEY: &str = "sessionRestore";
+const STARTUP_PATH_SETTING_KEY: &str = "sessionRestorePath";
+const TAB_TITLE_SETTING_KEY: &str = "tabNameFromContent";
+const STARTUP_FILE_EXTENSIONS: [&str; 4] = ["md", "markdown", "mdown", "txt"];
+// Opening a large folder at startup should not flood the tab bar
+const MAX_STARTUP_FOLDER_FILES: usize = 50;
+
+/// What `restore_session` hands back to the frontend on launch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StartupMode {
+    RestoreAll,
+    PinnedOnly,
+    Blank,
+    OpenPath,
+}
+
+impl StartupMode {
+    fn from_setting(value: Option<String>) -> Self {
+        match value.as_deref() {
+            Some("pinned") => Self::PinnedOnly,
+            Some("blank") => Self::Blank,
+            Some("path") => Self::OpenPath,
+            _ => Self::RestoreAll,
+        }
+    }
+}
+
+/// With `tabNameFromContent` on, tabs without a custom title are stored under the title
+/// their content gives them, whatever the frontend last showed.
 #[tauri::command]
-pub fn save_session(
+pub async fn save_session(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
     mut active_tabs: Vec<TabState>,
     mut closed_tabs: Vec<TabState>,
 ) -> Result<(), String> {
     let start = std::time::Instant::now();
+    // Changes reported while this save runs aren't in it, so they must stay unsaved
+    let generation = state
+        .session_autosave
+        .lock()
+        .map_or(0, |autosave| autosave.save_generation());
 
     log::info!("[Rust] save_session called");
     log::info!("  Active tabs: {}", active_tabs.len());
     log::info!("  Closed tabs: {}", closed_tabs.len());
 
+    let titles_from_content = read_setting::<bool>(&app_handle, TAB_TITLE_SETTING_KEY)
+        .await
+        .unwrap_or(false);
+
     let mut tabs_with_content = 0;
     for tab in &mut active_tabs {
         tab.normalize_newlines();
         if tab.content.is_some() {
             tabs_with_content += 1;
         }
+        if titles_from_content {
+            apply_content_title(tab);
+        }
     }
     log::info!("  Tabs with content to save: {}", tabs_with_content);
 
-    closed_tabs
-        .iter_mut()
-        .for_each(|tab| tab.normalize_newlines());
+    for tab in &mut closed_tabs {
+        tab.normalize_newlines();
+        if titles_from_content {
+            apply_content_title(tab);
+        }
+    }
 
     let result = state
         .db
@@ -35,6 +86,9 @@ pub fn save_session(
 
     let duration = start.elapsed();
     if result.is_ok() {
+        if let Ok(mut autosave) = state.session_autosave.lock() {
+            autosave.clear(generation);
+        }
         log::info!(
             "[Storage] save_session | duration={:?} | active_tabs={} | closed_tabs={}",
             duration,
@@ -46,22 +100,192 @@ pub fn save_session(
     result
 }
 
+/// Titles a tab by its first heading or line. Tabs whose content wasn't sent keep their
+/// stored title, and a custom title always wins.
+fn apply_content_title(tab: &mut TabState) {
+    if tab.custom_title.is_some() {
+        return;
+    }
+    if let Some(title) = tab.content.as_deref().and_then(tab_title::derive_tab_title) {
+        tab.title = title;
+    }
+}
+
+/// The title `save_session` would store for a tab with this content, so the tab bar and
+/// the session agree
 #[tauri::command]
-pub fn restore_session(state: State<'_, AppState>) -> Result<SessionData, String> {
+pub fn derive_tab_title(content: String) -> Option<String> {
+    tab_title::derive_tab_title(&content)
+}
+
+/// Writes the given tabs, with content, to a timestamped JSON file so tabs left out of
+/// the restored session can be recovered by hand.
+fn archive_tabs(
+    app_handle: &tauri::AppHandle,
+    state: &AppState,
+    tab_ids: &[String],
+) -> Result<Option<PathBuf>, String> {
+    if tab_ids.is_empty() {
+        return Ok(None);
+    }
+
+    let full = state
+        .db
+        .load_session_with_content(true)
+        .map_err(|e| handle_error(Some("session data"), "load session for archive", e))?;
+    let archived: Vec<&TabState> = full
+        .active_tabs
+        .iter()
+        .filter(|t| tab_ids.contains(&t.id))
+        .collect();
+
+    let archive_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| handle_error(None, "get app data directory for session archive", e))?
+        .join("Archive");
+    std::fs::create_dir_all(&archive_dir).map_err(|e| {
+        handle_error(
+            Some(&archive_dir.to_string_lossy()),
+            "create archive directory",
+            e,
+        )
+    })?;
+
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let archive_path = archive_dir.join(format!("session-{}.json", timestamp));
+    let json = serde_json::to_string_pretty(&archived)
+        .map_err(|e| handle_error(None, "serialize session archive", e))?;
+    std::fs::write(&archive_path, json).map_err(|e| {
+        handle_error(
+            Some(&archive_path.to_string_lossy()),
+            "write session archive",
+            e,
+        )
+    })?;
+
+    Ok(Some(archive_path))
+}
+
+/// Expands the configured startup path: a file opens as-is, a folder opens its
+/// markdown and text files in name order.
+fn startup_paths(path: &Path) -> Vec<String> {
+    if path.is_file() {
+        return vec![path.to_string_lossy().to_string()];
+    }
+
+    let Ok(entries) = std::fs::read_dir(path) else {
+        log::warn!("Startup path not accessible: {:?}", path);
+        return Vec::new();
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| STARTUP_FILE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        })
+        .collect();
+    files.sort();
+    files.truncate(MAX_STARTUP_FOLDER_FILES);
+
+    files
+        .into_iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect()
+}
+
+fn apply_startup_mode(
+    app_handle: &tauri::AppHandle,
+    state: &AppState,
+    mode: StartupMode,
+    startup_path: Option<String>,
+    session: &mut SessionData,
+) -> Result<(), String> {
+    if mode == StartupMode::RestoreAll {
+        return Ok(());
+    }
+
+    let (kept, dropped): (Vec<TabState>, Vec<TabState>) = std::mem::take(&mut session.active_tabs)
+        .into_iter()
+        .partition(|t| mode == StartupMode::PinnedOnly && t.is_pinned);
+    session.active_tabs = kept;
+
+    let dropped_ids: Vec<String> = dropped.into_iter().map(|t| t.id).collect();
+    if let Some(archive_path) = archive_tabs(app_handle, state, &dropped_ids)? {
+        log::info!(
+            "Archived {} tabs not restored at startup to {:?}",
+            dropped_ids.len(),
+            archive_path
+        );
+    }
+
+    if mode == StartupMode::OpenPath {
+        match startup_path.filter(|p| !p.trim().is_empty()) {
+            Some(path) => session.open_paths = startup_paths(Path::new(&path)),
+            None => log::warn!("Startup mode 'path' selected but no path configured"),
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn restore_session(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SessionData, String> {
     let start = std::time::Instant::now();
 
     log::info!("[Rust] restore_session called");
 
+    let mode = StartupMode::from_setting(read_setting(&app_handle, STARTUP_MODE_SETTING_KEY).await);
+    let startup_path: Option<String> = read_setting(&app_handle, STARTUP_PATH_SETTING_KEY).await;
+
     // Seed recent files from existing session data (Backfill)
     if let Err(e) = state.db.seed_recent_files_from_history() {
         log::warn!("Failed to seed recent files: {}", e);
     }
 
-    let result = state
+    if !state.safe_mode.active {
+        run_scheduled_maintenance(&app_handle, state.inner()).await;
+    }
+
+    let mut result = state
         .db
         .load_session()
         .map_err(|e| handle_error(Some("session data"), "restore session", e));
 
+    // Tabs are only archived here; the next save_session drops them from the DB
+    if !state.safe_mode.active
+        && let Ok(ref mut session) = result
+        && let Err(e) = apply_startup_mode(&app_handle, &state, mode, startup_path, session)
+    {
+        log::error!(
+            "Failed to apply startup mode, restoring full session: {}",
+            e
+        );
+        result = state
+            .db
+            .load_session()
+            .map_err(|e| handle_error(Some("session data"), "restore session", e));
+    }
+
+    // Safe mode restores tab metadata only; stored content stays in the DB untouched
+    if state.safe_mode.active
+        && let Ok(ref mut session) = result
+    {
+        for tab in session
+            .active_tabs
+            .iter_mut()
+            .chain(session.closed_tabs.iter_mut())
+        {
+            tab.content = None;
+        }
+    }
+
     let duration = start.elapsed();
     if let Ok(ref session) = result {
         let tabs_with_content = session
@@ -70,8 +294,9 @@ pub fn restore_session(state: State<'_, AppState>) -> Result<SessionData, String
             .filter(|t| t.content.is_some())
             .count();
         log::info!(
-            "[Storage] restore_session | duration={:?} | active_tabs={} | closed_tabs={} | with_content={}",
+            "[Storage] restore_session | duration={:?} | mode={:?} | active_tabs={} | closed_tabs={} | with_content={}",
             duration,
+            mode,
             session.active_tabs.len(),
             session.closed_tabs.len(),
             tabs_with_content
@@ -85,6 +310,14 @@ pub fn restore_session(state: State<'_, AppState>) -> Result<SessionData, String
 pub fn load_tab_content(state: State<'_, AppState>, tab_id: String) -> Result<TabData, String> {
     let start = std::time::Instant::now();
 
+    if state.safe_mode.active {
+        log::info!(
+            "[Storage] load_tab_content skipped in safe mode | tab_id={}",
+            tab_id
+        );
+        return Ok(TabData { content: None });
+    }
+
     let result = state
         .db
         .load_tab_data(&tab_id)
@@ -104,24 +337,145 @@ pub fn load_tab_content(state: State<'_, AppState>, tab_id: String) -> Result<Ta
 }
 
 #[tauri::command]
-pub fn vacuum_database(state: State<'_, AppState>) -> Result<(), String> {
-    let freelist_count = state
+pub fn create_tab_group(
+    state: State<'_, AppState>,
+    name: String,
+    color: Option<String>,
+) -> Result<TabGroup, String> {
+    state
         .db
-        .get_freelist_count()
-        .map_err(|e| handle_error(Some("freelist count"), "check database", e))?;
+        .create_tab_group(&name, color.as_deref())
+        .map_err(|e| handle_error(Some(&name), "create tab group", e))
+}
 
-    if freelist_count > 0 {
-        log::info!(
-            "Vacuuming database: {} free pages to reclaim",
-            freelist_count
-        );
-        state
-            .db
-            .incremental_vacuum(100)
-            .map_err(|e| handle_error(Some("database"), "vacuum database", e))?;
+/// Saves a group's name, color, collapsed flag and position.
+#[tauri::command]
+pub fn update_tab_group(state: State<'_, AppState>, group: TabGroup) -> Result<(), String> {
+    state
+        .db
+        .update_tab_group(&group)
+        .map_err(|e| handle_error(Some(&group.id), "update tab group", e))
+}
+
+#[tauri::command]
+pub fn delete_tab_group(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    state
+        .db
+        .delete_tab_group(&id)
+        .map_err(|e| handle_error(Some(&id), "delete tab group", e))
+}
+
+/// Moves open tabs into a group; `None` ungroups them.
+#[tauri::command]
+pub fn assign_tabs_to_group(
+    state: State<'_, AppState>,
+    tab_ids: Vec<String>,
+    group_id: Option<String>,
+) -> Result<usize, String> {
+    state
+        .db
+        .assign_tabs_to_group(&tab_ids, group_id.as_deref())
+        .map_err(|e| {
+            handle_error(
+                Some(group_id.as_deref().unwrap_or("ungrouped")),
+                "assign tabs to group",
+                e,
+            )
+        })
+}
+
+/// Stores one metadata value for a tab; `null` removes the key. Kept until the tab
+/// leaves both the open tabs and closed-tab history.
+#[tauri::command]
+pub fn set_tab_meta(
+    state: State<'_, AppState>,
+    tab_id: String,
+    key: String,
+    value: serde_json::Value,
+) -> Result<(), String> {
+    state
+        .db
+        .set_tab_meta(&tab_id, &key, &value)
+        .map_err(|e| handle_error(Some(&tab_id), "set tab metadata", e))
+}
+
+#[tauri::command]
+pub fn get_tab_meta(state: State<'_, AppState>, tab_id: String) -> Result<TabMeta, String> {
+    state
+        .db
+        .get_tab_meta(&tab_id)
+        .map_err(|e| handle_error(Some(&tab_id), "get tab metadata", e))
+}
+
+/// Stores a scratch tab's undo history so it survives a restart, trimmed to the
+/// `undoHistory` limits. Returns the number of undo steps kept; with persistence turned
+/// off, any stored history is dropped instead.
+#[tauri::command]
+pub async fn save_tab_history(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    tab_id: String,
+    history: TabHistory,
+) -> Result<usize, String> {
+    let settings = read_history_settings(&app_handle).await;
+    let result = if settings.persist && settings.max_depth > 0 {
+        state.db.save_tab_history(&tab_id, history, &settings)
     } else {
-        log::debug!("No free pages to reclaim in database");
+        state.db.clear_tab_history(&tab_id).map(|_| 0)
+    };
+    result.map_err(|e| handle_error(Some(&tab_id), "save undo history", e))
+}
+
+#[tauri::command]
+pub async fn load_tab_history(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    tab_id: String,
+) -> Result<Option<TabHistory>, String> {
+    if !read_history_settings(&app_handle).await.persist {
+        return Ok(None);
     }
+    state
+        .db
+        .load_tab_history(&tab_id)
+        .map_err(|e| handle_error(Some(&tab_id), "load undo history", e))
+}
 
+/// Forgets a tab's stored undo history. Returns whether there was any.
+#[tauri::command]
+pub fn clear_tab_history(state: State<'_, AppState>, tab_id: String) -> Result<bool, String> {
+    state
+        .db
+        .clear_tab_history(&tab_id)
+        .map_err(|e| handle_error(Some(&tab_id), "clear undo history", e))
+}
+
+#[tauri::command]
+pub fn vacuum_database(state: State<'_, AppState>) -> Result<(), String> {
+    vacuum_pages(&state, 100, VacuumTrigger::Manual)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_safe_mode(state: State<'_, AppState>) -> Result<SafeMode, String> {
+    Ok(state.safe_mode.clone())
+}
+
+/// Called by the frontend once the UI has finished initializing, so this launch no
+/// longer counts towards the crash threshold.
+#[tauri::command]
+pub fn mark_startup_complete(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let local_dir = app_handle
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| handle_error(None, "get local data directory for startup sentinel", e))?;
+    safe_mode::mark_startup_complete(&local_dir);
+    app_handle.state::<StartupProfiler>().mark_interactive();
     Ok(())
 }
+
+/// Timings for this launch's setup phases and the background work deferred past it.
+#[tauri::command]
+pub fn get_startup_report(profiler: State<'_, StartupProfiler>) -> StartupReport {
+    profiler.report()
+}
@@ -1,6 +1,15 @@
+use crate::commands::autosave::AutosavePolicy;
+use crate::db::HistorySettings;
+use crate::export::presets::PRESETS_SETTING_KEY;
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::diagrams::DiagramSettings;
+use crate::markdown::renderer::PathLinkSettings;
+use crate::state::AppState;
 use crate::utils::{handle_error, read_text_with_bom_detection};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
 use std::time::SystemTime;
 use tauri::Manager;
@@ -12,6 +21,26 @@ struct CachedTheme {
     mtime: SystemTime,
 }
 
+/// Keys the frontend doesn't model: written by backend commands or edited by hand in
+/// `settings.toml`. `save_settings` keeps them; every other key is replaced by what the
+/// frontend sends, so settings it drops are removed.
+const BACKEND_MANAGED_KEYS: &[&str] = &[
+    PRESETS_SETTING_KEY,
+    "archivePath",
+    "captureShortcut",
+    "commandGuards",
+    "diagrams",
+    "formatterLineWidth",
+    "formatterLinkStyle",
+    "formatterOrderedListStyle",
+    "formatterTextWrap",
+    "inboxPath",
+    "maintenance",
+    "pathLinks",
+    "preSave",
+    "undoHistory",
+];
+
 static THEME_CACHE: LazyLock<Mutex<HashMap<String, CachedTheme>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
@@ -70,8 +99,7 @@ pub struct Settings {
     pub find_panel_transparent: bool,
     pub find_panel_close_on_blur: bool,
     pub language_dictionaries: Vec<String>,
-    pub technical_dictionaries: bool,
-    pub science_dictionaries: bool,
+    pub specialist_dictionaries: Vec<String>,
     pub tab_name_from_content: bool,
     pub wrap_guide_column: u32,
     pub double_click_selects_trailing_space: bool,
@@ -79,6 +107,11 @@ pub struct Settings {
     pub custom_shortcuts: HashMap<String, String>,
     pub confirmation_suppressed: bool,
     pub max_file_size_mb: u64,
+    pub inbox_path: String,
+    pub archive_path: String,
+    pub export_presets: Vec<crate::export::presets::ExportPreset>,
+    pub pre_save: crate::commands::presave::PreSaveSettings,
+    pub maintenance: crate::commands::maintenance::MaintenanceSettings,
 }
 
 #[tauri::command]
@@ -139,6 +172,15 @@ pub async fn get_app_info(app_handle: tauri::AppHandle) -> Result<AppInfo, Strin
 
 #[tauri::command]
 pub async fn get_available_themes(app_handle: tauri::AppHandle) -> Result<Vec<String>, String> {
+    if app_handle
+        .state::<crate::state::AppState>()
+        .safe_mode
+        .active
+    {
+        log::info!("Safe mode: skipping custom themes");
+        return Ok(Vec::new());
+    }
+
     let app_dir = app_handle
         .path()
         .app_data_dir()
@@ -175,6 +217,14 @@ pub async fn get_theme_css(
     app_handle: tauri::AppHandle,
     theme_name: String,
 ) -> Result<String, String> {
+    if app_handle
+        .state::<crate::state::AppState>()
+        .safe_mode
+        .active
+    {
+        return Err("Custom themes are disabled in safe mode".to_string());
+    }
+
     let app_dir = app_handle
         .path()
         .app_data_dir()
@@ -226,6 +276,23 @@ pub async fn get_theme_css(
     Ok(css)
 }
 
+/// Approximate bytes held by cached custom theme CSS
+pub async fn theme_cache_bytes() -> usize {
+    THEME_CACHE
+        .lock()
+        .await
+        .iter()
+        .map(|(name, theme)| name.len() + theme.css.capacity())
+        .sum()
+}
+
+/// Drops cached theme CSS; themes are re-read from disk on next use. Returns bytes freed.
+pub async fn clear_theme_cache() -> usize {
+    let freed = theme_cache_bytes().await;
+    THEME_CACHE.lock().await.clear();
+    freed
+}
+
 async fn read_settings_file(app_handle: &tauri::AppHandle) -> Result<Option<String>, String> {
     let app_dir = app_handle
         .path()
@@ -255,7 +322,29 @@ pub async fn load_settings(app_handle: tauri::AppHandle) -> Result<serde_json::V
     let toml_val: toml::Value =
         toml::from_str(&content).map_err(|e| handle_error(None, "parse settings TOML", e))?;
 
-    serde_json::to_value(toml_val).map_err(|e| handle_error(None, "convert settings to JSON", e))
+    let mut settings = serde_json::to_value(toml_val)
+        .map_err(|e| handle_error(None, "convert settings to JSON", e))?;
+    migrate_dictionary_toggles(&mut settings);
+    Ok(settings)
+}
+
+/// Turns the old all-or-nothing `technicalDictionaries`/`scienceDictionaries` flags into
+/// the per-dictionary `specialistDictionaries` list.
+fn migrate_dictionary_toggles(settings: &mut serde_json::Value) {
+    let Some(obj) = settings.as_object_mut() else {
+        return;
+    };
+    let technical = obj.remove("technicalDictionaries");
+    let science = obj.remove("scienceDictionaries");
+    if obj.contains_key("specialistDictionaries") || (technical.is_none() && science.is_none()) {
+        return;
+    }
+
+    let ids = crate::commands::spellcheck::legacy_specialist_ids(
+        technical.and_then(|v| v.as_bool()).unwrap_or(true),
+        science.and_then(|v| v.as_bool()).unwrap_or(false),
+    );
+    obj.insert("specialistDictionaries".to_string(), serde_json::json!(ids));
 }
 
 /// Load raw TOML settings as toml::Value to extract specific fields without losing data
@@ -285,6 +374,114 @@ pub async fn get_max_file_size_bytes(app_handle: &tauri::AppHandle) -> u64 {
     }
 }
 
+/// Read a single typed value from settings, or None when missing or malformed
+pub async fn read_setting<T: DeserializeOwned>(
+    app_handle: &tauri::AppHandle,
+    key: &str,
+) -> Option<T> {
+    let toml_val = load_settings_toml(app_handle).await.ok()?;
+    let value = toml_val.get(key)?.clone();
+    value
+        .try_into()
+        .map_err(|e| log::warn!("Ignoring malformed setting '{}': {}", key, e))
+        .ok()
+}
+
+/// The flavor chosen in settings; "custom" picks up the `customFlavor` map.
+pub async fn read_flavor_setting(app_handle: &tauri::AppHandle) -> MarkdownFlavor {
+    match MarkdownFlavor::from_option_str(read_setting(app_handle, "markdownFlavor").await) {
+        MarkdownFlavor::Custom(_) => MarkdownFlavor::Custom(
+            read_setting(app_handle, "customFlavor")
+                .await
+                .unwrap_or_default(),
+        ),
+        flavor => flavor,
+    }
+}
+
+/// Diagram tools from the `diagrams` settings table; `None` when diagram rendering is off
+pub async fn read_diagram_settings(app_handle: &tauri::AppHandle) -> Option<DiagramSettings> {
+    let settings: DiagramSettings = read_setting(app_handle, "diagrams")
+        .await
+        .unwrap_or_default();
+    settings.enabled.then_some(settings)
+}
+
+/// Which plain-text file paths the preview turns into links, from the `pathLinks` table
+pub async fn read_path_link_settings(app_handle: &tauri::AppHandle) -> PathLinkSettings {
+    read_setting(app_handle, "pathLinks")
+        .await
+        .unwrap_or_default()
+}
+
+/// Limits on persisted undo history, from the `undoHistory` table
+pub async fn read_history_settings(app_handle: &tauri::AppHandle) -> HistorySettings {
+    read_setting(app_handle, "undoHistory")
+        .await
+        .unwrap_or_default()
+}
+
+/// Write a single backend-managed value into settings, leaving other keys untouched
+pub async fn write_setting<T: Serialize>(
+    app_handle: &tauri::AppHandle,
+    key: &str,
+    value: &T,
+) -> Result<(), String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| handle_error(None, "get app data directory for write_setting", e))?;
+    let path = app_dir.join("settings.toml");
+
+    let mut toml_val = load_settings_toml(app_handle).await?;
+    let new_value = toml::Value::try_from(value)
+        .map_err(|e| handle_error(Some(key), "serialize setting", e))?;
+    if let Some(table) = toml_val.as_table_mut() {
+        table.insert(key.to_string(), new_value);
+    }
+
+    let toml_str = toml::to_string_pretty(&toml_val)
+        .map_err(|e| handle_error(None, "serialize settings to TOML", e))?;
+    fs::write(&path, toml_str)
+        .await
+        .map_err(|e| handle_error(Some(&path.to_string_lossy()), "write settings file", e))
+}
+
+/// Get the quick capture inbox file from settings
+/// Falls back to `inbox.md` in the app data directory when unset
+pub async fn get_inbox_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let configured = read_setting::<String>(app_handle, "inboxPath")
+        .await
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from);
+
+    match configured {
+        Some(path) => Ok(path),
+        None => app_handle
+            .path()
+            .app_data_dir()
+            .map(|dir| dir.join("inbox.md"))
+            .map_err(|e| handle_error(None, "get app data directory for inbox", e)),
+    }
+}
+
+/// Get the archive folder for a note from settings
+/// Falls back to an `Archive` folder beside the note when unset
+pub async fn get_archive_path(app_handle: &tauri::AppHandle, note: &Path) -> PathBuf {
+    let configured = read_setting::<String>(app_handle, "archivePath")
+        .await
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from);
+
+    configured.unwrap_or_else(|| {
+        note.parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join("Archive")
+    })
+}
+
 #[tauri::command]
 pub async fn save_settings(
     app_handle: tauri::AppHandle,
@@ -306,8 +503,21 @@ pub async fn save_settings(
         max_size_received
     );
 
-    // Validate maxFileSizeMB if present (clamp to 1-500)
+    // Keep keys the frontend doesn't model (backend-managed settings such as export presets)
     let mut settings = settings;
+    if let Some(incoming) = settings.as_object_mut()
+        && let Ok(existing) = load_settings_toml(&app_handle).await
+        && let Ok(serde_json::Value::Object(existing)) = serde_json::to_value(existing)
+    {
+        for (key, value) in existing {
+            if BACKEND_MANAGED_KEYS.contains(&key.as_str()) {
+                incoming.entry(key).or_insert(value);
+            }
+        }
+    }
+    migrate_dictionary_toggles(&mut settings);
+
+    // Validate maxFileSizeMB if present (clamp to 1-500)
     if let Some(max_size) = settings
         .get("maxFileSizeMB")
         .or_else(|| settings.get("max_file_size_mb"))
@@ -321,6 +531,10 @@ pub async fn save_settings(
         }
     }
 
+    if let Ok(mut autosave) = app_handle.state::<AppState>().session_autosave.lock() {
+        autosave.set_policy(AutosavePolicy::from_settings(&settings));
+    }
+
     let toml_str = toml::to_string_pretty(&settings)
         .map_err(|e| handle_error(None, "serialize settings to TOML", e))?;
     fs::write(&path, toml_str)
@@ -1,8 +1,12 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::lint;
+use crate::startup::StartupProfiler;
 use crate::state::AppState;
 use crate::utils::IntoTauriError;
 use anyhow::{Result, anyhow};
+use serde::Serialize;
 use spellbook::Dictionary;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::time::Duration;
 use tauri::{Manager, State};
@@ -12,6 +16,8 @@ use tokio::io::AsyncWriteExt;
 const SPELL_CHECK_TIMEOUT_CONNECT: Duration = Duration::from_secs(2);
 const SPELL_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 const MAX_SUGGESTIONS: usize = 5;
+const CACHE_DIR: &str = "spellcheck_cache";
+const TECHNICAL_CACHE_DIR: &str = "technical";
 
 // --- Helper Functions ---
 
@@ -77,31 +83,87 @@ async fn ensure_file_downloaded(
 
 // --- ID Resolution ---
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpecialistCategory {
+    Technical,
+    Science,
+}
+
+/// A word list that can be merged into the spellchecker on top of the language dictionaries
+pub struct SpecialistDictionary {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub category: SpecialistCategory,
+    url: &'static str,
+}
+
+pub const SPECIALIST_DICTIONARIES: &[SpecialistDictionary] = &[
+    // Technical / Software
+    SpecialistDictionary {
+        id: "software-terms",
+        name: "Software Terms",
+        description: "General programming and computing vocabulary",
+        category: SpecialistCategory::Technical,
+        url: "https://raw.githubusercontent.com/streetsidesoftware/cspell-dicts/main/dictionaries/software-terms/dict/softwareTerms.txt",
+    },
+    SpecialistDictionary {
+        id: "companies",
+        name: "Companies",
+        description: "Company and product names",
+        category: SpecialistCategory::Technical,
+        url: "https://raw.githubusercontent.com/streetsidesoftware/cspell-dicts/main/dictionaries/companies/dict/companies.txt",
+    },
+    SpecialistDictionary {
+        id: "fullstack",
+        name: "Full Stack",
+        description: "Web frameworks, libraries and tooling",
+        category: SpecialistCategory::Technical,
+        url: "https://raw.githubusercontent.com/streetsidesoftware/cspell-dicts/main/dictionaries/fullstack/dict/fullstack.txt",
+    },
+    SpecialistDictionary {
+        id: "filetypes",
+        name: "File Types",
+        description: "File extensions and format names",
+        category: SpecialistCategory::Technical,
+        url: "https://raw.githubusercontent.com/streetsidesoftware/cspell-dicts/main/dictionaries/filetypes/src/filetypes.txt",
+    },
+    // Science & Medical
+    SpecialistDictionary {
+        id: "medical-terms",
+        name: "Medical Terms",
+        description: "Medical terminology (98k+ terms)",
+        category: SpecialistCategory::Science,
+        url: "https://raw.githubusercontent.com/streetsidesoftware/cspell-dicts/main/dictionaries/medicalterms/dict/medicalterms-en.txt",
+    },
+    SpecialistDictionary {
+        id: "scientific-terms-us",
+        name: "Scientific Terms",
+        description: "Scientific terminology, US spelling (670k+ terms, large download)",
+        category: SpecialistCategory::Science,
+        url: "https://raw.githubusercontent.com/streetsidesoftware/cspell-dicts/main/dictionaries/scientific_terms_US/src/custom_scientific_US.dic.txt",
+    },
+];
+
+fn find_specialist(id: &str) -> Option<&'static SpecialistDictionary> {
+    SPECIALIST_DICTIONARIES.iter().find(|d| d.id == id)
+}
+
 fn resolve_technical_url(id: &str) -> Option<&'static str> {
-    match id {
-        // Science & Medical
-        "medical-terms" => Some(
-            "https://raw.githubusercontent.com/streetsidesoftware/cspell-dicts/main/dictionaries/medicalterms/dict/medicalterms-en.txt",
-        ),
-        "scientific-terms-us" => Some(
-            "https://raw.githubusercontent.com/streetsidesoftware/cspell-dicts/main/dictionaries/scientific_terms_US/src/custom_scientific_US.dic.txt",
-        ),
-
-        // Technical / Software
-        "software-terms" => Some(
-            "https://raw.githubusercontent.com/streetsidesoftware/cspell-dicts/main/dictionaries/software-terms/dict/softwareTerms.txt",
-        ),
-        "companies" => Some(
-            "https://raw.githubusercontent.com/streetsidesoftware/cspell-dicts/main/dictionaries/companies/dict/companies.txt",
-        ),
-        "fullstack" => Some(
-            "https://raw.githubusercontent.com/streetsidesoftware/cspell-dicts/main/dictionaries/fullstack/dict/fullstack.txt",
-        ),
-        "filetypes" => Some(
-            "https://raw.githubusercontent.com/streetsidesoftware/cspell-dicts/main/dictionaries/filetypes/src/filetypes.txt",
-        ),
-        _ => None,
-    }
+    find_specialist(id).map(|d| d.url)
+}
+
+/// Specialist ids equivalent to the old all-or-nothing technical/science toggles
+pub fn legacy_specialist_ids(technical: bool, science: bool) -> Vec<String> {
+    SPECIALIST_DICTIONARIES
+        .iter()
+        .filter(|d| match d.category {
+            SpecialistCategory::Technical => technical,
+            SpecialistCategory::Science => science,
+        })
+        .map(|d| d.id.to_string())
+        .collect()
 }
 
 fn resolve_language_urls(dict_code: &str) -> Option<(&'static str, &'static str)> {
@@ -130,29 +192,13 @@ fn resolve_language_urls(dict_code: &str) -> Option<(&'static str, &'static str)
     }
 }
 
-fn list_technical_ids() -> Vec<String> {
-    vec![
-        "software-terms".to_string(),
-        "companies".to_string(),
-        "fullstack".to_string(),
-        "filetypes".to_string(),
-    ]
-}
-
-fn list_scientific_ids() -> Vec<String> {
-    vec![
-        "medical-terms".to_string(),
-        "scientific-terms-us".to_string(),
-    ]
-}
-
 // --- Loaders ---
 
 async fn load_language_dictionary(
     client: reqwest::Client,
     cache_dir: PathBuf,
     dict_code: String,
-) -> Result<(String, String)> {
+) -> Result<(String, String, String)> {
     let aff_path = cache_dir.join(format!("{}.aff", dict_code));
     let dic_path = cache_dir.join(format!("{}.dic", dict_code));
 
@@ -182,7 +228,7 @@ async fn load_language_dictionary(
     );
 
     if let (Ok(aff), Ok(dic)) = (aff_res, dic_res) {
-        Ok((aff, dic))
+        Ok((dict_code, aff, dic))
     } else {
         Err(anyhow!("Failed to load language dictionary: {}", dict_code))
     }
@@ -251,6 +297,49 @@ pub async fn add_to_dictionary(app_handle: tauri::AppHandle, word: String) -> Re
         .to_tauri_result()
 }
 
+/// Appends in-memory custom words that never reached the dictionary file, e.g. because an
+/// earlier write failed. Returns how many were written.
+pub async fn persist_custom_dictionary(app_handle: &tauri::AppHandle) -> Result<usize> {
+    let state = app_handle.state::<AppState>();
+    let words: Vec<String> = state.custom_dict.lock().await.iter().cloned().collect();
+    if words.is_empty() {
+        return Ok(0);
+    }
+
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| anyhow!("Failed to get app data directory: {}", e))?;
+    let dict_path = app_dir.join("custom-spelling.dic");
+    let saved: HashSet<String> = fs::read_to_string(&dict_path)
+        .await
+        .unwrap_or_default()
+        .lines()
+        .map(|l| l.trim().to_lowercase())
+        .collect();
+
+    let mut missing: Vec<&String> = words.iter().filter(|w| !saved.contains(*w)).collect();
+    if missing.is_empty() {
+        return Ok(0);
+    }
+    missing.sort();
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&dict_path)
+        .await
+        .map_err(|e| anyhow!("Failed to open dictionary: {}", e))?;
+    let lines: String = missing.iter().map(|w| format!("{}\n", w)).collect();
+    file.write_all(lines.as_bytes())
+        .await
+        .map_err(|e| anyhow!("Failed to write words: {}", e))?;
+    file.flush()
+        .await
+        .map_err(|e| anyhow!("Failed to flush dictionary: {}", e))?;
+    Ok(missing.len())
+}
+
 async fn load_user_dictionary_inner(app_handle: tauri::AppHandle) -> Result<Vec<String>> {
     let app_dir = app_handle
         .path()
@@ -285,10 +374,13 @@ pub async fn init_spellchecker(
     app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
     dictionaries: Option<Vec<String>>,
-    technical_dictionaries: Option<bool>,
-    science_dictionaries: Option<bool>,
+    specialist_dictionaries: Option<Vec<String>>,
 ) -> Result<(), String> {
     use crate::state::SpellcheckStatus;
+    if state.safe_mode.active {
+        log::info!("[SPELLCHECK-RUST] Safe mode: skipping dictionary loading");
+        return Ok(());
+    }
     {
         let mut status = state.spellcheck_status.lock().await;
         if *status == SpellcheckStatus::Loading || *status == SpellcheckStatus::Ready {
@@ -299,14 +391,22 @@ pub async fn init_spellchecker(
     }
 
     let dict_codes = dictionaries.unwrap_or_else(|| vec!["en".to_string()]);
-    let enable_technical = technical_dictionaries.unwrap_or(true);
-    let enable_science = science_dictionaries.unwrap_or(false);
+    let spec_codes: Vec<String> = specialist_dictionaries
+        .unwrap_or_else(|| legacy_specialist_ids(true, false))
+        .into_iter()
+        .filter(|id| {
+            let known = find_specialist(id).is_some();
+            if !known {
+                log::warn!("Ignoring unknown specialist dictionary: {}", id);
+            }
+            known
+        })
+        .collect();
 
     log::info!(
-        "Starting spellchecker initialization. Langs: {:?}, Tech: {}, Sci: {}",
+        "Starting spellchecker initialization. Langs: {:?}, Specialist: {:?}",
         dict_codes,
-        enable_technical,
-        enable_science
+        spec_codes
     );
 
     // Clone necessary data for the background task
@@ -322,22 +422,15 @@ pub async fn init_spellchecker(
 
     // Spawn initialization in background to avoid blocking
     tauri::async_runtime::spawn(async move {
-        let cache_dir = local_dir.join("spellcheck_cache");
-        let tech_cache_dir = cache_dir.join("technical");
+        let started = std::time::Instant::now();
+        let cache_dir = local_dir.join(CACHE_DIR);
+        let tech_cache_dir = cache_dir.join(TECHNICAL_CACHE_DIR);
         let custom_path = app_dir.join("custom-spelling.dic");
 
         if let Err(e) = fs::create_dir_all(&cache_dir).await {
             log::warn!("Failed to create spellcheck cache directory: {}", e);
         }
 
-        let mut spec_codes = Vec::new();
-        if enable_technical {
-            spec_codes.extend(list_technical_ids());
-        }
-        if enable_science {
-            spec_codes.extend(list_scientific_ids());
-        }
-
         if !spec_codes.is_empty()
             && let Err(e) = fs::create_dir_all(&tech_cache_dir).await
         {
@@ -375,6 +468,7 @@ pub async fn init_spellchecker(
         // Process Language Dictionaries
         let mut combined_aff = String::new();
         let mut unique_words = HashSet::new();
+        let mut loaded = Vec::new();
 
         // Sort to ensure primary dictionary preference for AFF
         let mut dict_results = Vec::new();
@@ -387,7 +481,8 @@ pub async fn init_spellchecker(
 
         for (_, res) in dict_results {
             match res {
-                Ok((aff, dic)) => {
+                Ok((code, aff, dic)) => {
+                    loaded.push(code);
                     if combined_aff.is_empty() {
                         combined_aff = aff.trim_start_matches('\u{feff}').to_string();
                     }
@@ -416,6 +511,7 @@ pub async fn init_spellchecker(
                             }
                         }
                         log::info!("Loaded {}: {} words", code, count);
+                        loaded.push(code);
                     },
                     Err(e) => log::warn!("Failed to load {}: {}", code, e),
                 }
@@ -437,13 +533,23 @@ pub async fn init_spellchecker(
                 combined_dic.push('\n');
             }
 
+            // spellbook's word table takes roughly twice the size of the source text
+            let estimated_bytes = (combined_aff.len() + combined_dic.len()) * 2;
+
             match Dictionary::new(&combined_aff, &combined_dic) {
                 Ok(dict) => {
                     let mut speller = state.speller.lock().await;
                     *speller = Some(dict);
+                    state
+                        .speller_bytes
+                        .store(estimated_bytes, std::sync::atomic::Ordering::Relaxed);
+                    *state.loaded_dictionaries.lock().await = loaded;
                     let mut status = state.spellcheck_status.lock().await;
                     *status = SpellcheckStatus::Ready;
                     log::info!("Spellchecker ready: {} unique words", total_word_count);
+                    app_handle_clone
+                        .state::<StartupProfiler>()
+                        .record_deferred_once("spellchecker", started.elapsed());
                 },
                 Err(e) => {
                     log::error!("Failed to create dictionary: {:?}", e);
@@ -556,13 +662,252 @@ pub async fn get_spelling_suggestions(
 
 #[tauri::command]
 pub async fn get_spellcheck_status(state: State<'_, AppState>) -> Result<String, String> {
-    use crate::state::SpellcheckStatus;
     let status = state.spellcheck_status.lock().await;
-    let status_str = match *status {
-        SpellcheckStatus::Uninitialized => "uninitialized",
-        SpellcheckStatus::Loading => "loading",
-        SpellcheckStatus::Ready => "ready",
-        SpellcheckStatus::Failed => "failed",
-    };
-    Ok(status_str.to_string())
+    Ok(status.as_str().to_string())
+}
+
+#[derive(Debug, Serialize)]
+pub struct AvailableDictionary {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub category: SpecialistCategory,
+    /// Size of the cached download; `None` until it has been downloaded once
+    pub size_bytes: Option<u64>,
+    pub word_count: Option<usize>,
+    /// Merged into the running spellchecker
+    pub loaded: bool,
+}
+
+/// Lists the specialist dictionaries that can be toggled individually, with download
+/// size and word count for those already cached.
+#[tauri::command]
+pub async fn list_available_dictionaries(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<AvailableDictionary>, String> {
+    let tech_cache_dir = app_handle
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| e.to_string())?
+        .join(CACHE_DIR)
+        .join(TECHNICAL_CACHE_DIR);
+    let loaded = state.loaded_dictionaries.lock().await.clone();
+
+    let mut available = Vec::with_capacity(SPECIALIST_DICTIONARIES.len());
+    for dict in SPECIALIST_DICTIONARIES {
+        let cache_path = tech_cache_dir.join(format!("{}.txt", dict.id));
+        let (size_bytes, word_count) = match fs::read_to_string(&cache_path).await {
+            Ok(text) => (
+                Some(text.len() as u64),
+                Some(
+                    text.lines()
+                        .map(str::trim)
+                        .filter(|t| !t.is_empty() && !t.starts_with('#') && !t.starts_with("//"))
+                        .count(),
+                ),
+            ),
+            Err(_) => (None, None),
+        };
+        available.push(AvailableDictionary {
+            id: dict.id,
+            name: dict.name,
+            description: dict.description,
+            category: dict.category,
+            size_bytes,
+            word_count,
+            loaded: loaded.iter().any(|id| id == dict.id),
+        });
+    }
+
+    Ok(available)
+}
+
+#[derive(Debug, Serialize)]
+pub struct DictionaryUsage {
+    pub id: String,
+    /// "language" or "specialist"
+    pub kind: &'static str,
+    /// Distinct document words this dictionary accepts
+    pub matched_words: usize,
+    pub occurrences: usize,
+    /// Distinct words no other loaded dictionary accepts
+    pub unique_words: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DictionaryUsageReport {
+    /// Most matched first
+    pub dictionaries: Vec<DictionaryUsage>,
+    pub unused_custom_words: Vec<String>,
+    pub distinct_words: usize,
+}
+
+enum DictionarySource {
+    Language(Dictionary),
+    Specialist(HashSet<String>),
+}
+
+impl DictionarySource {
+    fn accepts(&self, word: &str, lower: &str) -> bool {
+        match self {
+            Self::Language(dict) => dict.check(word),
+            Self::Specialist(words) => words.contains(lower),
+        }
+    }
+}
+
+/// Reads a loaded dictionary back from the download cache on its own, since the merged
+/// speller no longer knows which source each word came from.
+async fn load_cached_source(cache_dir: &std::path::Path, id: &str) -> Result<DictionarySource> {
+    if resolve_technical_url(id).is_some() {
+        let text = fs::read_to_string(
+            cache_dir
+                .join(TECHNICAL_CACHE_DIR)
+                .join(format!("{}.txt", id)),
+        )
+        .await?;
+        let words = text
+            .lines()
+            .map(str::trim)
+            .filter(|t| !t.is_empty() && !t.starts_with('#') && !t.starts_with("//"))
+            .map(str::to_lowercase)
+            .collect();
+        return Ok(DictionarySource::Specialist(words));
+    }
+
+    let aff = fs::read_to_string(cache_dir.join(format!("{}.aff", id))).await?;
+    let dic = fs::read_to_string(cache_dir.join(format!("{}.dic", id))).await?;
+    let dict = tokio::task::spawn_blocking(move || {
+        Dictionary::new(
+            aff.trim_start_matches('\u{feff}'),
+            dic.trim_start_matches('\u{feff}'),
+        )
+        .map_err(|e| anyhow!("Failed to parse dictionary: {:?}", e))
+    })
+    .await??;
+    Ok(DictionarySource::Language(dict))
+}
+
+/// Reports how much each loaded dictionary contributes to checking `content` and which
+/// custom dictionary entries the document never uses, to help prune dictionaries.
+#[tauri::command]
+pub async fn get_dictionary_usage(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    content: String,
+    flavor: Option<String>,
+) -> Result<DictionaryUsageReport, String> {
+    let start = std::time::Instant::now();
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+
+    // Distinct words keyed by lowercase, keeping the first spelling for affix-aware checks
+    let mut words: HashMap<String, (String, usize)> = HashMap::new();
+    for (_, word) in tokio::task::spawn_blocking(move || lint::prose_words(&content, flavor))
+        .await
+        .map_err(|e| format!("Word extraction task failed: {}", e))?
+    {
+        words
+            .entry(word.to_lowercase())
+            .or_insert_with(|| (word, 0))
+            .1 += 1;
+    }
+
+    let cache_dir = app_handle
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| e.to_string())?
+        .join(CACHE_DIR);
+    let loaded = state.loaded_dictionaries.lock().await.clone();
+
+    let mut sources = Vec::with_capacity(loaded.len());
+    for id in loaded {
+        match load_cached_source(&cache_dir, &id).await {
+            Ok(source) => sources.push((id, source)),
+            Err(e) => log::warn!("Skipping {} in usage report: {}", id, e),
+        }
+    }
+
+    let dictionaries = tokio::task::block_in_place(|| {
+        let mut usage: Vec<DictionaryUsage> = sources
+            .iter()
+            .map(|(id, source)| DictionaryUsage {
+                id: id.clone(),
+                kind: match source {
+                    DictionarySource::Language(_) => "language",
+                    DictionarySource::Specialist(_) => "specialist",
+                },
+                matched_words: 0,
+                occurrences: 0,
+                unique_words: 0,
+            })
+            .collect();
+
+        for (lower, (word, count)) in &words {
+            let matches: Vec<usize> = sources
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, source))| source.accepts(word, lower))
+                .map(|(i, _)| i)
+                .collect();
+            for &i in &matches {
+                usage[i].matched_words += 1;
+                usage[i].occurrences += count;
+            }
+            if let [only] = matches[..] {
+                usage[only].unique_words += 1;
+            }
+        }
+
+        usage.sort_by(|a, b| b.matched_words.cmp(&a.matched_words));
+        usage
+    });
+
+    let mut unused_custom_words: Vec<String> = load_user_dictionary_inner(app_handle)
+        .await
+        .to_tauri_result()?
+        .into_iter()
+        .filter(|entry| {
+            let lower = entry.to_lowercase();
+            !words.contains_key(&lower)
+                && !words.contains_key(&format!("{}'s", lower))
+                && !words.contains_key(&format!("{}'", lower))
+        })
+        .collect();
+    unused_custom_words.sort_by_key(|w| w.to_lowercase());
+
+    log::info!(
+        "[Spellcheck] get_dictionary_usage | duration={:?} | words={} | dictionaries={}",
+        start.elapsed(),
+        words.len(),
+        dictionaries.len()
+    );
+
+    Ok(DictionaryUsageReport {
+        dictionaries,
+        unused_custom_words,
+        distinct_words: words.len(),
+    })
+}
+
+/// Frees the merged dictionary and the custom word set. The frontend re-runs
+/// `init_spellchecker` the next time spellchecking is needed.
+#[tauri::command]
+pub async fn unload_spellchecker(state: State<'_, AppState>) -> Result<usize, String> {
+    use crate::state::SpellcheckStatus;
+    let mut status = state.spellcheck_status.lock().await;
+    if *status == SpellcheckStatus::Loading {
+        return Err("Spellchecker is still loading".to_string());
+    }
+
+    *state.speller.lock().await = None;
+    state.custom_dict.lock().await.clear();
+    state.loaded_dictionaries.lock().await.clear();
+    *status = SpellcheckStatus::Uninitialized;
+    let freed = state
+        .speller_bytes
+        .swap(0, std::sync::atomic::Ordering::Relaxed);
+
+    log::info!("[Spellcheck] unload_spellchecker | freed≈{} bytes", freed);
+    Ok(freed)
 }
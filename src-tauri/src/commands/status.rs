@@ -0,0 +1,219 @@
+use crate::commands::settings::get_max_file_size_bytes;
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::text_metrics::{self, WordCountMode};
+use crate::state::AppState;
+use crate::utils::{decode_text, detect_line_ending, validate_path};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
+use tauri::State;
+
+/// Saved files remembered at once; more than this and the cache starts over
+const MAX_CACHED_FILES: usize = 64;
+
+/// Path -> what was on disk when last checked, so typing doesn't re-read the file
+static SAVED_FILES: LazyLock<Mutex<HashMap<String, SavedFile>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone)]
+struct SavedFile {
+    modified: Option<SystemTime>,
+    len: u64,
+    encoding: &'static str,
+    has_bom: bool,
+    line_ending: &'static str,
+    content_hash: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EditorStatus {
+    pub line_count: usize,
+    pub word_count: usize,
+    pub char_count: usize,
+    pub widest_column: usize,
+    /// 1-based cursor position; the column is a display width, like `widest_column`, so
+    /// emoji and wide CJK text count as they appear in the editor
+    pub line: usize,
+    pub column: usize,
+    /// Display width of the cursor's line
+    pub line_width: usize,
+    /// Characters (grapheme clusters) and words before the cursor
+    pub char_offset: usize,
+    pub word_index: usize,
+    /// Encoding of the file on disk; `None` for unsaved or unreadable documents
+    pub encoding: Option<&'static str>,
+    pub has_bom: bool,
+    /// Line ending of the file on disk; `None` for unsaved or unreadable documents, which
+    /// only have the tab's setting. The editor's text is always LF, so it can't tell.
+    pub line_ending: Option<&'static str>,
+    pub spellcheck: &'static str,
+    /// Branch of the git repository holding the file, or the short commit id when detached
+    pub git_branch: Option<String>,
+    /// The text differs from the file on disk, or there is no file yet
+    pub dirty: bool,
+}
+
+/// Everything the status bar shows, in one call. The text comes from the cache that
+/// `update_text_metrics` keeps for `document_id`, so a cursor move sends only the cursor;
+/// `None` means nothing is cached and the text has to be sent first. `cursor_offset` is in
+/// UTF-16 units, as the editor reports it.
+#[tauri::command]
+pub async fn get_editor_status(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    document_id: String,
+    cursor_offset: usize,
+    path: Option<String>,
+    word_count_mode: Option<WordCountMode>,
+    flavor: Option<String>,
+) -> Result<Option<EditorStatus>, String> {
+    let saved = match path.as_deref() {
+        Some(path) => {
+            validate_path(path)?;
+            saved_file(&app_handle, path).await
+        },
+        None => None,
+    };
+    let spellcheck = state.spellcheck_status.lock().await.as_str();
+    let mode = word_count_mode.unwrap_or_default();
+    let flavor = MarkdownFlavor::from_option_str(flavor);
+
+    let (totals, cursor, hash, word_count, uncounted) = {
+        let mut cache = state.text_metrics.lock().await;
+        let Some(doc) = cache.get_mut(&document_id) else {
+            return Ok(None);
+        };
+        doc.touch();
+        let word_count = doc.word_count(mode, flavor);
+        // Prose counts parse the text, which happens outside the lock
+        let uncounted = word_count.is_none().then(|| (doc.revision(), doc.text()));
+        (
+            doc.totals(),
+            doc.cursor(cursor_offset),
+            content_hash(doc.lines()),
+            word_count,
+            uncounted,
+        )
+    };
+
+    let (word_count, counted, git_branch) = tokio::task::spawn_blocking(move || {
+        let counted = uncounted
+            .map(|(revision, text)| (revision, text_metrics::count_words(&text, mode, flavor)));
+        let word_count = word_count.or(counted.map(|(_, count)| count));
+        (
+            word_count.unwrap_or_default(),
+            counted,
+            path.and_then(|p| git_branch(Path::new(&p))),
+        )
+    })
+    .await
+    .map_err(|e| format!("Status task failed: {}", e))?;
+
+    if let Some((revision, count)) = counted
+        && let Some(doc) = state.text_metrics.lock().await.get_mut(&document_id)
+    {
+        doc.store_word_count(revision, mode, flavor, count);
+    }
+
+    let (line_count, _, char_count, widest_column) = totals;
+    Ok(Some(EditorStatus {
+        line_count,
+        word_count,
+        char_count,
+        widest_column,
+        line: cursor.line,
+        column: cursor.column,
+        line_width: cursor.line_width,
+        char_offset: cursor.char_offset,
+        word_index: cursor.word_index,
+        encoding: saved.as_ref().map(|s| s.encoding),
+        has_bom: saved.as_ref().is_some_and(|s| s.has_bom),
+        line_ending: saved.as_ref().map(|s| s.line_ending),
+        spellcheck,
+        git_branch,
+        dirty: saved.is_none_or(|s| s.content_hash != hash),
+    }))
+}
+
+/// What is on disk at `path`, re-read only when its size or modification time changes.
+/// `None` when the file is missing, unreadable or over the size limit.
+async fn saved_file(app_handle: &tauri::AppHandle, path: &str) -> Option<SavedFile> {
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    let (modified, len) = (metadata.modified().ok(), metadata.len());
+    let cached = SAVED_FILES
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(path).cloned());
+    if let Some(saved) = cached.filter(|s| s.modified == modified && s.len == len) {
+        return Some(saved);
+    }
+
+    if len > get_max_file_size_bytes(app_handle).await {
+        return None;
+    }
+    let bytes = tokio::fs::read(path).await.ok()?;
+    let saved = tokio::task::spawn_blocking(move || {
+        let (text, encoding, has_bom) = decode_text(&bytes);
+        SavedFile {
+            modified,
+            len,
+            encoding: encoding.name(),
+            has_bom,
+            line_ending: detect_line_ending(&text),
+            content_hash: content_hash(text.split('\n')),
+        }
+    })
+    .await
+    .ok()?;
+
+    if let Ok(mut cache) = SAVED_FILES.lock() {
+        if cache.len() >= MAX_CACHED_FILES && !cache.contains_key(path) {
+            cache.clear();
+        }
+        cache.insert(path.to_string(), saved.clone());
+    }
+    Some(saved)
+}
+
+/// Hash of a text's lines, split on `\n`, that ignores CRLF versus LF, which the editor
+/// normalizes
+fn content_hash<'a>(lines: impl Iterator<Item = &'a str>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for line in lines {
+        line.strip_suffix('\r').unwrap_or(line).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Git folder of the repository containing `path`, which may be a file or a folder.
+/// Worktrees and submodules have a `.git` file pointing at the real git folder.
+pub(crate) fn git_dir(path: &Path) -> Option<PathBuf> {
+    for dir in path.ancestors() {
+        let dot_git = dir.join(".git");
+        if dot_git.is_dir() {
+            return Some(dot_git);
+        }
+        if dot_git.is_file() {
+            let link = std::fs::read_to_string(&dot_git).ok()?;
+            return Some(dir.join(link.trim().strip_prefix("gitdir:")?.trim()));
+        }
+    }
+    None
+}
+
+/// Branch checked out in the repository containing `path`, or the short commit id when
+/// `HEAD` is detached
+pub(crate) fn git_branch(path: &Path) -> Option<String> {
+    let head = std::fs::read_to_string(git_dir(path)?.join("HEAD")).ok()?;
+    let head = head.trim();
+    Some(match head.strip_prefix("ref: ") {
+        Some(reference) => reference
+            .strip_prefix("refs/heads/")
+            .unwrap_or(reference)
+            .to_string(),
+        None => head.chars().take(7).collect(),
+    })
+}
@@ -0,0 +1,297 @@
+use crate::commands::status::{git_branch, git_dir};
+use crate::markdown::file_links::relative_path;
+use crate::utils::{atomic_write, handle_error, sanitize_file_name, validate_path};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+use tokio::fs;
+
+const NOTE_EXTENSION: &str = "md";
+const NOTE_EXTENSIONS: [&str; 4] = ["md", "markdown", "mdown", "txt"];
+// `notes.md`, `notes 2.md` ... `notes 999.md`
+const MAX_NAME_SUFFIX: usize = 999;
+// Names Windows reserves for devices, with or without an extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Values for the built-in `{{name}}` placeholders of templates and snippets. Anything
+/// that can't be resolved, such as the branch of a file outside a repository, expands to
+/// an empty string.
+#[derive(Debug, Serialize)]
+pub struct TemplateVariables {
+    /// Name of the workspace folder, by default the file's folder
+    pub workspace: Option<String>,
+    /// The file's path relative to the workspace, with forward slashes
+    pub relative_path: Option<String>,
+    pub file_name: Option<String>,
+    /// The file name without its extension
+    pub title: Option<String>,
+    pub git_branch: Option<String>,
+    /// `user.name` from the repository's git config, then the global one
+    pub author: Option<String>,
+    pub date: String,
+    pub time: String,
+}
+
+impl TemplateVariables {
+    pub fn resolve(path: Option<&Path>, workspace: Option<&Path>, home: Option<&Path>) -> Self {
+        let now = chrono::Local::now();
+        let root = workspace.or_else(|| path.and_then(Path::parent));
+        let repo = path.or(workspace);
+
+        Self {
+            workspace: root
+                .and_then(Path::file_name)
+                .map(|n| n.to_string_lossy().to_string()),
+            relative_path: path
+                .zip(root)
+                .and_then(|(path, root)| relative_path(root, path)),
+            file_name: path
+                .and_then(Path::file_name)
+                .map(|n| n.to_string_lossy().to_string()),
+            title: path
+                .and_then(Path::file_stem)
+                .map(|n| n.to_string_lossy().to_string()),
+            git_branch: repo.and_then(git_branch),
+            author: git_author(repo, home),
+            date: now.format("%Y-%m-%d").to_string(),
+            time: now.format("%H:%M").to_string(),
+        }
+    }
+
+    /// Fills in every built-in placeholder; other `{{...}}` text is left as written
+    pub fn expand(&self, template: &str) -> String {
+        let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+        [
+            ("{{workspace}}", optional(&self.workspace)),
+            ("{{relative_path}}", optional(&self.relative_path)),
+            ("{{file_name}}", optional(&self.file_name)),
+            ("{{title}}", optional(&self.title)),
+            ("{{git_branch}}", optional(&self.git_branch)),
+            ("{{author}}", optional(&self.author)),
+            ("{{date}}", self.date.clone()),
+            ("{{time}}", self.time.clone()),
+        ]
+        .iter()
+        .fold(template.to_string(), |text, (placeholder, value)| {
+            text.replace(placeholder, value)
+        })
+    }
+}
+
+/// `user.name` as git would pick it: the repository config wins over the global
+/// `~/.gitconfig` and `~/.config/git/config`
+fn git_author(path: Option<&Path>, home: Option<&Path>) -> Option<String> {
+    let mut configs: Vec<PathBuf> = Vec::new();
+    if let Some(dir) = path.and_then(git_dir) {
+        // Linked worktrees keep the shared config in the main git folder
+        let common = std::fs::read_to_string(dir.join("commondir"))
+            .map(|common| dir.join(common.trim()))
+            .unwrap_or(dir);
+        configs.push(common.join("config"));
+    }
+    if let Some(home) = home {
+        configs.push(home.join(".gitconfig"));
+    }
+    let xdg_config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.map(|h| h.join(".config")));
+    if let Some(xdg_config) = xdg_config {
+        configs.push(xdg_config.join("git").join("config"));
+    }
+
+    configs.iter().find_map(|config| {
+        let text = std::fs::read_to_string(config).ok()?;
+        config_value(&text, "user", "name")
+    })
+}
+
+/// Last value of `key` in `[section]` of a git config file. Section and key names are
+/// case-insensitive; includes and subsections are not followed.
+fn config_value(text: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    let mut value = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header.split(']').next().unwrap_or("").trim();
+            in_section = name.eq_ignore_ascii_case(section);
+            continue;
+        }
+        if !in_section || line.starts_with(['#', ';']) {
+            continue;
+        }
+        let Some((name, raw)) = line.split_once('=') else {
+            continue;
+        };
+        if name.trim().eq_ignore_ascii_case(key) {
+            let raw = raw.split([';', '#']).next().unwrap_or("").trim();
+            value = Some(raw.trim_matches('"').to_string());
+        }
+    }
+    value.filter(|v| !v.is_empty())
+}
+
+/// Built-in template variables for a file. `path` may be a file that doesn't exist yet,
+/// such as the target of a new note.
+#[tauri::command]
+pub async fn get_template_variables(
+    app_handle: tauri::AppHandle,
+    path: Option<String>,
+    workspace: Option<String>,
+) -> Result<TemplateVariables, String> {
+    for p in path.iter().chain(workspace.iter()) {
+        validate_path(p)?;
+    }
+    let home = app_handle.path().home_dir().ok();
+    tokio::task::spawn_blocking(move || {
+        TemplateVariables::resolve(
+            path.as_deref().map(Path::new),
+            workspace.as_deref().map(Path::new),
+            home.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| format!("Template variables task failed: {}", e))
+}
+
+/// Expands the built-in placeholders in a template or snippet for a file
+#[tauri::command]
+pub async fn expand_template(
+    app_handle: tauri::AppHandle,
+    template: String,
+    path: Option<String>,
+    workspace: Option<String>,
+) -> Result<String, String> {
+    let variables = get_template_variables(app_handle, path, workspace).await?;
+    Ok(variables.expand(&template))
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedNote {
+    pub path: String,
+    /// What was written, with the template's placeholders filled in
+    pub content: String,
+}
+
+/// The note's file name: `name` as typed, with `.md` added unless it already has a note
+/// extension. Names that aren't valid on every platform are refused rather than changed.
+fn note_file_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Note name is empty".to_string());
+    }
+    if sanitize_file_name(name) != name {
+        return Err(format!(
+            "Note name can't contain any of < > : \" / \\ | ? * or end with a dot: {}",
+            name
+        ));
+    }
+    let stem = name.split('.').next().unwrap_or(name).trim();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+    {
+        return Err(format!("'{}' is a reserved file name", stem));
+    }
+
+    let has_extension = Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| NOTE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()));
+    Ok(if has_extension {
+        name.to_string()
+    } else {
+        format!("{}.{}", name, NOTE_EXTENSION)
+    })
+}
+
+/// Creates an empty file at the first free name in `folder`, adding ` 2`, ` 3`... to the
+/// stem. Creating the file claims the name, so two notes made at once never share one.
+async fn claim_note_path(folder: &Path, file_name: &str) -> Result<PathBuf, String> {
+    let file = Path::new(file_name);
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = file.extension().unwrap_or_default().to_string_lossy();
+
+    for n in 1..=MAX_NAME_SUFFIX {
+        let candidate = if n == 1 {
+            folder.join(file_name)
+        } else {
+            folder.join(format!("{} {}.{}", stem, n, extension))
+        };
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&candidate)
+            .await
+        {
+            Ok(_) => return Ok(candidate),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                return Err(handle_error(
+                    Some(candidate.to_string_lossy().as_ref()),
+                    "create note",
+                    e,
+                ));
+            },
+        }
+    }
+    Err(format!(
+        "No free name for '{}' in {}",
+        file_name,
+        folder.display()
+    ))
+}
+
+/// Creates a note in `folder` from an optional template, whose placeholders are filled in
+/// for the new file. An existing file is never overwritten: the name gets a number
+/// instead. Returns where the note ended up and what it contains.
+#[tauri::command]
+pub async fn create_note(
+    app_handle: tauri::AppHandle,
+    folder: String,
+    name: String,
+    template: Option<String>,
+    workspace: Option<String>,
+) -> Result<CreatedNote, String> {
+    validate_path(&folder)?;
+    if let Some(workspace) = &workspace {
+        validate_path(workspace)?;
+    }
+    let start = std::time::Instant::now();
+
+    if !fs::metadata(&folder).await.is_ok_and(|m| m.is_dir()) {
+        return Err("Folder does not exist".to_string());
+    }
+    let file_name = note_file_name(&name)?;
+    let path = claim_note_path(Path::new(&folder), &file_name).await?;
+    let path_str = path.to_string_lossy().to_string();
+
+    let content = match template.filter(|t| !t.is_empty()) {
+        Some(template) => {
+            let variables =
+                get_template_variables(app_handle, Some(path_str.clone()), workspace).await?;
+            variables.expand(&template)
+        },
+        None => String::new(),
+    };
+    if let Err(e) = atomic_write(&path, content.as_bytes()).await {
+        // Don't leave the claimed empty file behind
+        let _ = fs::remove_file(&path).await;
+        return Err(handle_error(Some(&path_str), "write note", e));
+    }
+
+    log::info!(
+        "[Storage] create_note | duration={:?} | size={} bytes | path={}",
+        start.elapsed(),
+        content.len(),
+        path_str
+    );
+
+    Ok(CreatedNote {
+        path: path_str,
+        content,
+    })
+}
@@ -0,0 +1,245 @@
+use crate::utils::validate_path;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileVersion {
+    /// Readable with `read_text_file`
+    pub path: String,
+    /// "shadow-copy" (Volume Shadow Copy) or "file-history"
+    pub source: &'static str,
+    /// When the snapshot or backup was taken
+    pub created: Option<String>,
+    pub modified: Option<String>,
+    pub size: u64,
+}
+
+/// Lists earlier copies of a file kept by the operating system, newest first, so a
+/// version from before the document was opened here can be recovered. Windows reads
+/// Volume Shadow Copies and File History; other platforms return an empty list.
+#[tauri::command]
+pub async fn list_file_versions(path: String) -> Result<Vec<FileVersion>, String> {
+    validate_path(&path)?;
+    let start = std::time::Instant::now();
+
+    #[cfg(target_os = "windows")]
+    let versions = {
+        let target = path.clone();
+        tokio::task::spawn_blocking(move || windows_versions::list(&target))
+            .await
+            .map_err(|e| format!("Version lookup task failed: {}", e))?
+    };
+    #[cfg(not(target_os = "windows"))]
+    let versions: Vec<FileVersion> = Vec::new();
+
+    log::info!(
+        "[Storage] list_file_versions | duration={:?} | versions={} | path={}",
+        start.elapsed(),
+        versions.len(),
+        path
+    );
+
+    Ok(versions)
+}
+
+#[cfg(target_os = "windows")]
+mod windows_versions {
+    use super::FileVersion;
+    use crate::utils::format_system_time;
+    use chrono::{DateTime, Local, NaiveDateTime, Utc};
+    use regex::Regex;
+    use serde::Deserialize;
+    use std::os::windows::process::CommandExt;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use std::sync::LazyLock;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    // Maps each shadow copy to the drive letter of its volume. CIM output is
+    // locale-independent, unlike `vssadmin`. Listing shadow copies needs elevation,
+    // so a normal session usually gets an access error and falls back to File History.
+    const SHADOW_QUERY: &str = "$v=@{};Get-CimInstance Win32_Volume|%{$v[$_.DeviceID]=$_.DriveLetter};\
+        ConvertTo-Json -Compress -InputObject @(Get-CimInstance Win32_ShadowCopy|%{\
+        [pscustomobject]@{device=$_.DeviceObject;drive=$v[$_.VolumeName];created=$_.InstallDate.ToString('o')}})";
+
+    // `notes (2024_03_01 09_15_00 UTC).md`
+    static FILE_HISTORY_NAME_RE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"^(.*) \((\d{4}_\d{2}_\d{2} \d{2}_\d{2}_\d{2}) UTC\)(\.[^.]*)?$")
+            .expect("Invalid FILE_HISTORY_NAME_RE")
+    });
+    static TARGET_URL_RE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"(?i)<TargetUrl>([^<]+)</TargetUrl>").expect("Invalid TARGET_URL_RE")
+    });
+
+    #[derive(Deserialize)]
+    struct ShadowCopy {
+        device: String,
+        drive: Option<String>,
+        created: String,
+    }
+
+    pub fn list(path: &str) -> Vec<FileVersion> {
+        let path = dunce::simplified(Path::new(path)).to_path_buf();
+        let mut versions = shadow_copy_versions(&path);
+        versions.extend(file_history_versions(&path));
+
+        // Shadow copies taken while the file was unchanged hold identical copies
+        versions.sort_by(|a, b| b.created.cmp(&a.created));
+        let current = std::fs::metadata(&path).ok();
+        let current_key = current
+            .as_ref()
+            .map(|m| (format_system_time(m.modified()), m.len()));
+        let mut seen = std::collections::HashSet::new();
+        versions.retain(|v| {
+            let key = (v.modified.clone(), v.size);
+            Some(&key) != current_key.as_ref() && seen.insert(key)
+        });
+        versions
+    }
+
+    /// `C:\Users\me\notes.md` -> ("C:", `\Users\me\notes.md`)
+    fn split_drive(path: &Path) -> Option<(String, String)> {
+        let text = path.to_str()?;
+        let drive = text.get(..2).filter(|d| d.ends_with(':'))?;
+        Some((drive.to_ascii_uppercase(), text[2..].to_string()))
+    }
+
+    fn shadow_copy_versions(path: &Path) -> Vec<FileVersion> {
+        let Some((drive, rest)) = split_drive(path) else {
+            return Vec::new();
+        };
+
+        let output = match Command::new("powershell.exe")
+            .args(["-NoProfile", "-NonInteractive", "-Command", SHADOW_QUERY])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+        {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                log::debug!(
+                    "Shadow copy query failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                return Vec::new();
+            },
+            Err(e) => {
+                log::debug!("Failed to run shadow copy query: {}", e);
+                return Vec::new();
+            },
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let copies: Vec<ShadowCopy> = match serde_json::from_str(stdout.trim()) {
+            Ok(copies) => copies,
+            Err(e) => {
+                log::debug!("Unreadable shadow copy list: {}", e);
+                return Vec::new();
+            },
+        };
+
+        copies
+            .into_iter()
+            .filter(|c| {
+                c.drive
+                    .as_deref()
+                    .is_some_and(|d| d.eq_ignore_ascii_case(&drive))
+            })
+            .filter_map(|c| {
+                let candidate = PathBuf::from(format!("{}{}", c.device, rest));
+                let metadata = std::fs::metadata(&candidate).ok()?;
+                let created = DateTime::parse_from_rfc3339(&c.created).ok().map(|t| {
+                    t.with_timezone(&Local)
+                        .format("%Y%m%d / %H%M%S")
+                        .to_string()
+                });
+                Some(FileVersion {
+                    path: candidate.to_string_lossy().to_string(),
+                    source: "shadow-copy",
+                    created,
+                    modified: format_system_time(metadata.modified()),
+                    size: metadata.len(),
+                })
+            })
+            .collect()
+    }
+
+    /// File History's target folder from its per-user configuration. The environment
+    /// variables may point at the portable data folder, so the profile path is used.
+    fn file_history_target() -> Option<PathBuf> {
+        let profile = std::env::var_os("USERPROFILE")?;
+        let config = PathBuf::from(profile)
+            .join(r"AppData\Local\Microsoft\Windows\FileHistory\Configuration\Config1.xml");
+        let bytes = std::fs::read(config).ok()?;
+        let text = match encoding_rs::Encoding::for_bom(&bytes) {
+            Some((encoding, _)) => encoding.decode_with_bom_removal(&bytes).0.into_owned(),
+            None => String::from_utf8_lossy(&bytes).into_owned(),
+        };
+        let url = TARGET_URL_RE.captures(&text)?[1].trim().to_string();
+        Some(PathBuf::from(url))
+    }
+
+    fn file_history_versions(path: &Path) -> Vec<FileVersion> {
+        let Some(target) = file_history_target() else {
+            return Vec::new();
+        };
+        let (Some((drive, rest)), Ok(user), Ok(machine)) = (
+            split_drive(path),
+            std::env::var("USERNAME"),
+            std::env::var("COMPUTERNAME"),
+        ) else {
+            return Vec::new();
+        };
+
+        // <target>\<user>\<machine>\Data\C\Users\me\notes (2024_03_01 09_15_00 UTC).md
+        let mirrored = target
+            .join(user)
+            .join(machine)
+            .join("Data")
+            .join(drive.trim_end_matches(':'))
+            .join(rest.trim_start_matches('\\'));
+        let (Some(folder), Some(stem)) = (mirrored.parent(), path.file_stem()) else {
+            return Vec::new();
+        };
+        let stem = stem.to_string_lossy();
+        let extension = path
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+
+        let Ok(entries) = std::fs::read_dir(folder) else {
+            return Vec::new();
+        };
+
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let caps = FILE_HISTORY_NAME_RE.captures(&name)?;
+                let same_file = caps[1] == *stem
+                    && caps
+                        .get(3)
+                        .map_or(extension.is_empty(), |e| e.as_str() == extension);
+                if !same_file {
+                    return None;
+                }
+
+                let created = NaiveDateTime::parse_from_str(&caps[2], "%Y_%m_%d %H_%M_%S")
+                    .ok()
+                    .map(|t| {
+                        DateTime::<Utc>::from_naive_utc_and_offset(t, Utc)
+                            .with_timezone(&Local)
+                            .format("%Y%m%d / %H%M%S")
+                            .to_string()
+                    });
+                let metadata = entry.metadata().ok()?;
+                Some(FileVersion {
+                    path: entry.path().to_string_lossy().to_string(),
+                    source: "file-history",
+                    created,
+                    modified: format_system_time(metadata.modified()),
+                    size: metadata.len(),
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,228 @@
+use crate::db::{WritingDay, WritingSessionRecord};
+use crate::state::AppState;
+use crate::utils::handle_error;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+/// A gap between edits longer than this ends a streak and doesn't count as writing time
+const PAUSE: Duration = Duration::from_secs(120);
+/// Sessions this short with no words added are not recorded
+const MIN_RECORDED: Duration = Duration::from_secs(60);
+const DEFAULT_STATS_DAYS: u32 = 30;
+const MAX_RUNNING_SESSIONS: usize = 16;
+
+/// A writing session in progress, fed word counts as the user types
+pub struct WritingSession {
+    path: Option<String>,
+    mode: Option<String>,
+    started_at: DateTime<Local>,
+    started: Instant,
+    words: usize,
+    words_added: usize,
+    words_removed: usize,
+    last_edit: Option<Instant>,
+    streak_start: Option<Instant>,
+    longest_streak: Duration,
+    active: Duration,
+}
+
+impl WritingSession {
+    fn new(path: Option<String>, mode: Option<String>, words: usize) -> Self {
+        Self {
+            path,
+            mode,
+            started_at: Local::now(),
+            started: Instant::now(),
+            words,
+            words_added: 0,
+            words_removed: 0,
+            last_edit: None,
+            streak_start: None,
+            longest_streak: Duration::ZERO,
+            active: Duration::ZERO,
+        }
+    }
+
+    /// Takes the document's current word count. An unchanged count is not an edit, so
+    /// calls while idle don't extend the streak.
+    fn update(&mut self, words: usize, now: Instant) {
+        if words == self.words {
+            return;
+        }
+        if words > self.words {
+            self.words_added += words - self.words;
+        } else {
+            self.words_removed += self.words - words;
+        }
+        self.words = words;
+
+        match self.last_edit {
+            Some(last) if now.duration_since(last) <= PAUSE => {
+                self.active += now.duration_since(last);
+            },
+            _ => self.streak_start = Some(now),
+        }
+        self.last_edit = Some(now);
+        if let Some(start) = self.streak_start {
+            self.longest_streak = self.longest_streak.max(now.duration_since(start));
+        }
+    }
+
+    fn summary(&self, id: &str, now: Instant) -> WritingSessionRecord {
+        WritingSessionRecord {
+            id: id.to_string(),
+            path: self.path.clone(),
+            mode: self.mode.clone(),
+            started: self.started_at.to_rfc3339(),
+            ended: Local::now().to_rfc3339(),
+            duration_secs: now.duration_since(self.started).as_secs(),
+            active_secs: self.active.as_secs(),
+            words_added: self.words_added as u64,
+            words_removed: self.words_removed as u64,
+            longest_streak_secs: self.longest_streak.as_secs(),
+        }
+    }
+}
+
+/// Starts tracking a writing session, typically when focus or typewriter mode is turned
+/// on. `word_count` is the document's count at the start. Returns the session id.
+#[tauri::command]
+pub fn start_session(
+    state: State<'_, AppState>,
+    path: Option<String>,
+    mode: Option<String>,
+    word_count: usize,
+) -> Result<String, String> {
+    let mut sessions = state
+        .writing_sessions
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    // Sessions the frontend never ended, for example after a reload
+    if sessions.len() >= MAX_RUNNING_SESSIONS
+        && let Some(oldest) = sessions
+            .iter()
+            .min_by_key(|(_, s)| s.started)
+            .map(|(id, _)| id.clone())
+    {
+        log::warn!("[Writing] Dropping abandoned session {}", oldest);
+        sessions.remove(&oldest);
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    sessions.insert(id.clone(), WritingSession::new(path, mode, word_count));
+    Ok(id)
+}
+
+/// Reports the document's word count during a session; call on edits, debounced
+#[tauri::command]
+pub fn update_session(
+    state: State<'_, AppState>,
+    session_id: String,
+    word_count: usize,
+) -> Result<(), String> {
+    let mut sessions = state
+        .writing_sessions
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let session = sessions
+        .get_mut(&session_id)
+        .ok_or_else(|| "Writing session not found".to_string())?;
+    session.update(word_count, Instant::now());
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionSummary {
+    #[serde(flatten)]
+    pub session: WritingSessionRecord,
+    /// False when the session was too short to keep in the stats
+    pub recorded: bool,
+}
+
+/// Ends a session and records it in the writing activity. Returns its summary, for an
+/// end-of-session message.
+#[tauri::command]
+pub fn end_session(
+    state: State<'_, AppState>,
+    session_id: String,
+    word_count: Option<usize>,
+) -> Result<SessionSummary, String> {
+    let session = state
+        .writing_sessions
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&session_id);
+    let Some(mut session) = session else {
+        return Err("Writing session not found".to_string());
+    };
+
+    let now = Instant::now();
+    if let Some(words) = word_count {
+        session.update(words, now);
+    }
+    let record = session.summary(&session_id, now);
+    let recorded = record.words_added > 0 || now.duration_since(session.started) >= MIN_RECORDED;
+    if recorded {
+        state
+            .db
+            .record_writing_session(&record)
+            .map_err(|e| handle_error(record.path.as_deref(), "record writing session", e))?;
+    }
+
+    log::info!(
+        "[Writing] end_session | duration={}s | active={}s | added={} | removed={} | streak={}s",
+        record.duration_secs,
+        record.active_secs,
+        record.words_added,
+        record.words_removed,
+        record.longest_streak_secs
+    );
+    Ok(SessionSummary {
+        session: record,
+        recorded,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct WritingStats {
+    pub days: Vec<WritingDay>,
+    pub sessions: Vec<WritingSessionRecord>,
+    pub active_secs: u64,
+    pub words_added: u64,
+    pub longest_streak_secs: u64,
+}
+
+/// Writing activity of the last `days` days (30 by default) for the stats dashboard:
+/// per-day totals, oldest first, and the sessions themselves, newest first
+#[tauri::command]
+pub fn get_writing_stats(
+    state: State<'_, AppState>,
+    days: Option<u32>,
+) -> Result<WritingStats, String> {
+    let days = days.unwrap_or(DEFAULT_STATS_DAYS).max(1);
+    let since = (Local::now().date_naive() - chrono::Days::new(u64::from(days - 1)))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let sessions = state
+        .db
+        .list_writing_sessions(&since)
+        .map_err(|e| handle_error(Some("writing activity"), "list writing sessions", e))?;
+    let days = state
+        .db
+        .writing_days(&since)
+        .map_err(|e| handle_error(Some("writing activity"), "summarize writing days", e))?;
+
+    Ok(WritingStats {
+        active_secs: sessions.iter().map(|s| s.active_secs).sum(),
+        words_added: sessions.iter().map(|s| s.words_added).sum(),
+        longest_streak_secs: sessions
+            .iter()
+            .map(|s| s.longest_streak_secs)
+            .max()
+            .unwrap_or(0),
+        days,
+        sessions,
+    })
+}
@@ -0,0 +1,111 @@
+use super::Database;
+use anyhow::Result;
+use rusqlite::{Row, params};
+use serde::Serialize;
+
+/// One finished writing session
+#[derive(Debug, Serialize, Clone)]
+pub struct WritingSessionRecord {
+    pub id: String,
+    pub path: Option<String>,
+    /// Editor mode the session was started from, such as `focus` or `typewriter`
+    pub mode: Option<String>,
+    pub started: String,
+    pub ended: String,
+    pub duration_secs: u64,
+    /// Time spent writing: gaps between edits longer than a pause are left out
+    pub active_secs: u64,
+    pub words_added: u64,
+    pub words_removed: u64,
+    /// Longest stretch of writing without a pause
+    pub longest_streak_secs: u64,
+}
+
+/// Writing totals for one local calendar day
+#[derive(Debug, Serialize)]
+pub struct WritingDay {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub sessions: u64,
+    pub active_secs: u64,
+    pub words_added: u64,
+}
+
+const SELECT_ACTIVITY_SQL: &str = "SELECT id, path, mode, started, ended, duration_secs,
+        active_secs, words_added, words_removed, longest_streak_secs
+     FROM writing_activity";
+
+fn record_from_row(row: &Row) -> rusqlite::Result<WritingSessionRecord> {
+    Ok(WritingSessionRecord {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        mode: row.get(2)?,
+        started: row.get(3)?,
+        ended: row.get(4)?,
+        duration_secs: row.get::<_, i64>(5)? as u64,
+        active_secs: row.get::<_, i64>(6)? as u64,
+        words_added: row.get::<_, i64>(7)? as u64,
+        words_removed: row.get::<_, i64>(8)? as u64,
+        longest_streak_secs: row.get::<_, i64>(9)? as u64,
+    })
+}
+
+impl Database {
+    pub fn record_writing_session(&self, record: &WritingSessionRecord) -> Result<()> {
+        self.ensure_writable()?;
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT INTO writing_activity (id, path, mode, started, ended, duration_secs,
+                active_secs, words_added, words_removed, longest_streak_secs)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                &record.id,
+                &record.path,
+                &record.mode,
+                &record.started,
+                &record.ended,
+                record.duration_secs as i64,
+                record.active_secs as i64,
+                record.words_added as i64,
+                record.words_removed as i64,
+                record.longest_streak_secs as i64
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Sessions started at or after `since` (RFC 3339), newest first
+    pub fn list_writing_sessions(&self, since: &str) -> Result<Vec<WritingSessionRecord>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE started >= ?1 ORDER BY started DESC",
+            SELECT_ACTIVITY_SQL
+        ))?;
+        let records = stmt
+            .query_map(params![since], record_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(records)
+    }
+
+    /// Per-day totals of the sessions started at or after `since`, oldest day first.
+    /// Days come from the local time stored in `started`.
+    pub fn writing_days(&self, since: &str) -> Result<Vec<WritingDay>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT substr(started, 1, 10) AS day, COUNT(*), SUM(active_secs), SUM(words_added)
+             FROM writing_activity WHERE started >= ?1
+             GROUP BY day ORDER BY day",
+        )?;
+        let days = stmt
+            .query_map(params![since], |row| {
+                Ok(WritingDay {
+                    date: row.get(0)?,
+                    sessions: row.get::<_, i64>(1)? as u64,
+                    active_secs: row.get::<_, i64>(2)? as u64,
+                    words_added: row.get::<_, i64>(3)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(days)
+    }
+}
@@ -0,0 +1,156 @@
+use super::Database;
+use crate::markdown::annotations::TextAnchor;
+use anyhow::{Result, anyhow};
+use chrono::Local;
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use serde::Serialize;
+
+/// A review comment on a range of a document, stored outside the file
+#[derive(Debug, Serialize, Clone)]
+pub struct Annotation {
+    pub id: String,
+    /// Path identity of the document
+    pub path: String,
+    pub anchor: TextAnchor,
+    pub body: String,
+    pub author: Option<String>,
+    pub resolved: bool,
+    pub created: String,
+    pub modified: String,
+}
+
+const SELECT_ANNOTATION_SQL: &str = "SELECT id, path, start_offset, end_offset, quote, prefix,
+        suffix, quote_hash, body, author, resolved, created, modified
+     FROM annotations";
+
+fn annotation_from_row(row: &Row) -> rusqlite::Result<Annotation> {
+    Ok(Annotation {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        anchor: TextAnchor {
+            from: row.get::<_, i64>(2)? as usize,
+            to: row.get::<_, i64>(3)? as usize,
+            quote: row.get(4)?,
+            prefix: row.get(5)?,
+            suffix: row.get(6)?,
+            hash: row.get(7)?,
+        },
+        body: row.get(8)?,
+        author: row.get(9)?,
+        resolved: row.get::<_, i32>(10)? != 0,
+        created: row.get(11)?,
+        modified: row.get(12)?,
+    })
+}
+
+fn get_annotation(conn: &Connection, id: &str) -> Result<Annotation> {
+    conn.query_row(
+        &format!("{} WHERE id = ?1", SELECT_ANNOTATION_SQL),
+        params![id],
+        annotation_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| anyhow!("Annotation not found"))
+}
+
+fn validate_body(body: &str) -> Result<&str> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(anyhow!("Comment cannot be empty"));
+    }
+    Ok(body)
+}
+
+impl Database {
+    pub fn add_annotation(
+        &self,
+        path: &str,
+        anchor: &TextAnchor,
+        body: &str,
+        author: Option<&str>,
+    ) -> Result<Annotation> {
+        self.ensure_writable()?;
+        let body = validate_body(body)?;
+        let author = author.map(str::trim).filter(|a| !a.is_empty());
+
+        let conn = self.pool.get()?;
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = Local::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO annotations (id, path, start_offset, end_offset, quote, prefix, suffix,
+                quote_hash, body, author, created, modified)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11)",
+            params![
+                &id,
+                path,
+                anchor.from as i64,
+                anchor.to as i64,
+                &anchor.quote,
+                &anchor.prefix,
+                &anchor.suffix,
+                &anchor.hash,
+                body,
+                author,
+                &now
+            ],
+        )?;
+        get_annotation(&conn, &id)
+    }
+
+    /// Comments on a document in the order of their stored offsets
+    pub fn list_annotations(&self, path: &str) -> Result<Vec<Annotation>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE path = ?1 ORDER BY start_offset, created",
+            SELECT_ANNOTATION_SQL
+        ))?;
+        let annotations = stmt
+            .query_map(params![path], annotation_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(annotations)
+    }
+
+    /// Changes the text or resolved state of a comment; `None` leaves a field as it is
+    pub fn update_annotation(
+        &self,
+        id: &str,
+        body: Option<&str>,
+        resolved: Option<bool>,
+    ) -> Result<Annotation> {
+        self.ensure_writable()?;
+        let body = body.map(validate_body).transpose()?;
+
+        let conn = self.pool.get()?;
+        let updated = conn.execute(
+            "UPDATE annotations SET
+                body = COALESCE(?2, body),
+                resolved = COALESCE(?3, resolved),
+                modified = ?4
+             WHERE id = ?1",
+            params![id, body, resolved.map(i32::from), Local::now().to_rfc3339()],
+        )?;
+        if updated == 0 {
+            return Err(anyhow!("Annotation not found"));
+        }
+        get_annotation(&conn, id)
+    }
+
+    /// Stores where a comment's quote was found after the document changed, so the next
+    /// lookup starts from there
+    pub fn move_annotation(&self, id: &str, from: usize, to: usize) -> Result<()> {
+        self.ensure_writable()?;
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE annotations SET start_offset = ?2, end_offset = ?3 WHERE id = ?1",
+            params![id, from as i64, to as i64],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_annotation(&self, id: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let conn = self.pool.get()?;
+        conn.execute("DELETE FROM annotations WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+}
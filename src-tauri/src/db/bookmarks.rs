@@ -0,0 +1,400 @@
+use super::Database;
+use anyhow::{Result, anyhow};
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Bookmark {
+    pub id: String,
+    pub path: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub created: String,
+    pub last_accessed: Option<String>,
+    /// Slash-separated folder path; `None` for bookmarks at the top level
+    #[serde(default)]
+    pub folder: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+const UPSERT_BOOKMARK_SQL: &str =
+    "INSERT INTO bookmarks (id, path, title, tags, created, last_accessed, folder)
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+     ON CONFLICT(path) DO UPDATE SET
+        id            = excluded.id,
+        title         = excluded.title,
+        tags          = excluded.tags,
+        created       = excluded.created,
+        last_accessed = excluded.last_accessed,
+        folder        = excluded.folder";
+
+// Tags are read from bookmark_tags; the JSON column is kept in step for older builds
+const SELECT_BOOKMARK_SQL: &str = "SELECT b.id, b.path, b.title,
+        (SELECT json_group_array(tag) FROM (
+            SELECT tag FROM bookmark_tags WHERE bookmark_id = b.id ORDER BY position
+        )),
+        b.created, b.last_accessed, b.folder
+     FROM bookmarks b";
+
+const SYNC_TAGS_JSON_SQL: &str = "UPDATE bookmarks SET tags = (
+        SELECT json_group_array(tag) FROM (
+            SELECT tag FROM bookmark_tags WHERE bookmark_id = bookmarks.id ORDER BY position
+        )
+     )";
+
+fn bookmark_from_row(row: &Row) -> rusqlite::Result<Bookmark> {
+    let tags_json: Option<String> = row.get(3)?;
+    let tags: Vec<String> = tags_json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    Ok(Bookmark {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        title: row.get(2)?,
+        tags,
+        created: row.get(4)?,
+        last_accessed: row.get(5)?,
+        folder: row.get(6)?,
+    })
+}
+
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !normalized.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
+
+/// Trims whitespace and stray separators; an empty folder means the top level.
+fn normalize_folder(folder: Option<&str>) -> Option<String> {
+    let parts: Vec<&str> = folder?
+        .split(['/', '\\'])
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// `IN (?n, ...)` placeholders for `count` parameters starting at `first`.
+fn placeholders(first: usize, count: usize) -> String {
+    (first..first + count)
+        .map(|i| format!("?{}", i))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Replaces the bookmark's rows in bookmark_tags with `tags`, already normalized.
+fn replace_tags(conn: &Connection, bookmark_id: &str, tags: &[String]) -> Result<()> {
+    conn.execute(
+        "DELETE FROM bookmark_tags WHERE bookmark_id = ?1",
+        params![bookmark_id],
+    )?;
+    let mut tag_stmt = conn.prepare_cached(
+        "INSERT OR IGNORE INTO bookmark_tags (bookmark_id, tag, position) VALUES (?1, ?2, ?3)",
+    )?;
+    for (position, tag) in tags.iter().enumerate() {
+        tag_stmt.execute(params![bookmark_id, tag, position as i64])?;
+    }
+    Ok(())
+}
+
+fn upsert_bookmark(conn: &Connection, bookmark: &Bookmark) -> Result<()> {
+    let tags = normalize_tags(&bookmark.tags);
+    let tags_json = serde_json::to_string(&tags)?;
+    conn.prepare_cached(UPSERT_BOOKMARK_SQL)?.execute(params![
+        &bookmark.id,
+        &bookmark.path,
+        &bookmark.title,
+        &tags_json,
+        &bookmark.created,
+        &bookmark.last_accessed,
+        normalize_folder(bookmark.folder.as_deref()),
+    ])?;
+    replace_tags(conn, &bookmark.id, &tags)
+}
+
+impl Database {
+    pub fn add_bookmark(&self, bookmark: &Bookmark) -> Result<()> {
+        self.ensure_writable()?;
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        upsert_bookmark(&tx, bookmark)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_all_bookmarks(&self) -> Result<Vec<Bookmark>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!("{} ORDER BY b.created DESC", SELECT_BOOKMARK_SQL))?;
+        let bookmarks = stmt
+            .query_map([], bookmark_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bookmarks)
+    }
+
+    pub fn find_bookmarks_by_tag(&self, tag: &str) -> Result<Vec<Bookmark>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE b.id IN (SELECT bookmark_id FROM bookmark_tags WHERE tag = ?1)
+             ORDER BY b.created DESC",
+            SELECT_BOOKMARK_SQL
+        ))?;
+        let bookmarks = stmt
+            .query_map(params![tag.trim()], bookmark_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bookmarks)
+    }
+
+    pub fn list_bookmark_tags(&self) -> Result<Vec<TagCount>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT tag, COUNT(*) FROM bookmark_tags GROUP BY tag ORDER BY COUNT(*) DESC, tag",
+        )?;
+        let tags = stmt
+            .query_map([], |row| {
+                Ok(TagCount {
+                    tag: row.get(0)?,
+                    count: row.get::<_, i64>(1)? as usize,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tags)
+    }
+
+    /// Renames a tag across all bookmarks, merging into `new_tag` where a bookmark
+    /// already carries it. Returns the number of bookmarks affected.
+    pub fn rename_tag(&self, old_tag: &str, new_tag: &str) -> Result<usize> {
+        self.ensure_writable()?;
+        let old_tag = old_tag.trim();
+        let new_tag = new_tag.trim();
+        if new_tag.is_empty() {
+            return Err(anyhow!("Tag name cannot be empty"));
+        }
+
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+
+        let affected: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM bookmark_tags WHERE tag = ?1",
+            params![old_tag],
+            |row| row.get(0),
+        )?;
+
+        if affected > 0 {
+            tx.execute(
+                "DELETE FROM bookmark_tags WHERE tag = ?1 AND bookmark_id IN (
+                    SELECT bookmark_id FROM bookmark_tags WHERE tag = ?2
+                 ) AND ?1 != ?2 COLLATE NOCASE",
+                params![old_tag, new_tag],
+            )?;
+            tx.execute(
+                "UPDATE bookmark_tags SET tag = ?2 WHERE tag = ?1",
+                params![old_tag, new_tag],
+            )?;
+            tx.execute(SYNC_TAGS_JSON_SQL, [])?;
+        }
+
+        tx.commit()?;
+        Ok(affected as usize)
+    }
+
+    /// Edits a bookmark in place, keeping its id, creation and access times. Fails if
+    /// another bookmark already points at `path`.
+    pub fn update_bookmark(
+        &self,
+        id: &str,
+        title: &str,
+        path: &str,
+        tags: &[String],
+    ) -> Result<Bookmark> {
+        self.ensure_writable()?;
+        let title = title.trim();
+        let path = path.trim();
+        if title.is_empty() {
+            return Err(anyhow!("Bookmark title cannot be empty"));
+        }
+        if path.is_empty() {
+            return Err(anyhow!("Bookmark path cannot be empty"));
+        }
+
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+
+        let conflict: Option<String> = tx
+            .query_row(
+                "SELECT id FROM bookmarks WHERE path = ?1 AND id != ?2",
+                params![path, id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if conflict.is_some() {
+            return Err(anyhow!("Another bookmark already exists for {}", path));
+        }
+
+        let tags = normalize_tags(tags);
+        let updated = tx.execute(
+            "UPDATE bookmarks SET title = ?2, path = ?3, tags = ?4 WHERE id = ?1",
+            params![id, title, path, serde_json::to_string(&tags)?],
+        )?;
+        if updated == 0 {
+            return Err(anyhow!("Bookmark not found"));
+        }
+        replace_tags(&tx, id, &tags)?;
+
+        let bookmark = tx.query_row(
+            &format!("{} WHERE b.id = ?1", SELECT_BOOKMARK_SQL),
+            params![id],
+            bookmark_from_row,
+        )?;
+        tx.commit()?;
+        Ok(bookmark)
+    }
+
+    pub fn delete_bookmark(&self, id: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let conn = self.pool.get()?;
+        conn.execute("DELETE FROM bookmarks WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Deletes the bookmarks in one transaction. Returns the number removed.
+    pub fn delete_bookmarks(&self, ids: &[String]) -> Result<usize> {
+        self.ensure_writable()?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        let removed = {
+            let mut stmt = tx.prepare_cached("DELETE FROM bookmarks WHERE id = ?1")?;
+            let mut removed = 0;
+            for id in ids {
+                removed += stmt.execute(params![id])?;
+            }
+            removed
+        };
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Appends `tag` to each bookmark that does not already carry it. Returns the number
+    /// of bookmarks that gained the tag.
+    pub fn tag_bookmarks(&self, ids: &[String], tag: &str) -> Result<usize> {
+        self.ensure_writable()?;
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(anyhow!("Tag name cannot be empty"));
+        }
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        let tagged = {
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR IGNORE INTO bookmark_tags (bookmark_id, tag, position)
+                 SELECT id, ?2, (
+                    SELECT COALESCE(MAX(position) + 1, 0) FROM bookmark_tags WHERE bookmark_id = ?1
+                 )
+                 FROM bookmarks WHERE id = ?1",
+            )?;
+            let mut tagged = 0;
+            for id in ids {
+                tagged += stmt.execute(params![id, tag])?;
+            }
+            tagged
+        };
+        if tagged > 0 {
+            tx.execute(SYNC_TAGS_JSON_SQL, [])?;
+        }
+        tx.commit()?;
+        Ok(tagged)
+    }
+
+    /// Moves the bookmarks into `folder`, or to the top level when it is `None` or
+    /// empty. Returns the number of bookmarks updated.
+    pub fn move_bookmarks(&self, ids: &[String], folder: Option<&str>) -> Result<usize> {
+        self.ensure_writable()?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let folder = normalize_folder(folder);
+
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        let sql = format!(
+            "UPDATE bookmarks SET folder = ?1 WHERE id IN ({})",
+            placeholders(2, ids.len())
+        );
+        let mut params: Vec<&dyn rusqlite::types::ToSql> = vec![&folder];
+        params.extend(ids.iter().map(|id| id as &dyn rusqlite::types::ToSql));
+        let moved = tx.execute(&sql, params.as_slice())?;
+        tx.commit()?;
+        Ok(moved)
+    }
+
+    pub fn update_bookmark_access_time(&self, id: &str, last_accessed: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE bookmarks SET last_accessed = ?1 WHERE id = ?2",
+            params![last_accessed, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_orphan_bookmarks(&self) -> Result<usize> {
+        self.ensure_writable()?;
+        let conn = self.pool.get()?;
+        let entries: Vec<(String, String)> = {
+            let mut stmt = conn.prepare("SELECT id, path FROM bookmarks")?;
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<(String, String)>>>()?
+        };
+
+        let dead_ids: Vec<&str> = entries
+            .iter()
+            .filter(|(_, path)| !std::path::Path::new(path.as_str()).exists())
+            .map(|(id, _)| id.as_str())
+            .collect();
+
+        if dead_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let sql = format!(
+            "DELETE FROM bookmarks WHERE id IN ({})",
+            placeholders(1, dead_ids.len())
+        );
+        let params: Vec<&dyn rusqlite::types::ToSql> = dead_ids
+            .iter()
+            .map(|id| id as &dyn rusqlite::types::ToSql)
+            .collect();
+        conn.execute(&sql, params.as_slice())?;
+
+        Ok(dead_ids.len())
+    }
+
+    pub fn import_bookmarks(&self, bookmarks: &[Bookmark]) -> Result<()> {
+        self.ensure_writable()?;
+        if bookmarks.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        for bookmark in bookmarks {
+            upsert_bookmark(&tx, bookmark)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+use super::Database;
+use anyhow::Result;
+use chrono::Local;
+use rusqlite::{OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The `undoHistory` settings table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistorySettings {
+    /// Keep undo history of unsaved scratch tabs across restarts
+    pub persist: bool,
+    /// Most undo steps kept per tab; redo steps are capped the same way
+    pub max_depth: usize,
+    /// Most bytes of stored history per tab; the oldest steps go first
+    pub max_bytes: usize,
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        Self {
+            persist: true,
+            max_depth: 200,
+            max_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// A tab's undo and redo stacks. Each entry is one editor change set, so a step stores
+/// only what it changed rather than a copy of the document.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TabHistory {
+    /// Undo steps, oldest first
+    pub done: Vec<Value>,
+    /// Redo steps, the next redo last
+    pub undone: Vec<Value>,
+    /// Length of the document the history ends at; the frontend drops history that no
+    /// longer fits the restored content
+    pub doc_length: usize,
+}
+
+impl TabHistory {
+    /// Drops the oldest steps until both stacks fit `max_depth` and `max_bytes`
+    fn trim(&mut self, settings: &HistorySettings) -> Result<(String, String)> {
+        for stack in [&mut self.done, &mut self.undone] {
+            if stack.len() > settings.max_depth {
+                stack.drain(..stack.len() - settings.max_depth);
+            }
+        }
+
+        let sizes = |stack: &[Value]| -> Result<Vec<usize>> {
+            stack
+                .iter()
+                .map(|entry| Ok(serde_json::to_string(entry)?.len() + 1))
+                .collect()
+        };
+        let (done_sizes, undone_sizes) = (sizes(&self.done)?, sizes(&self.undone)?);
+        let mut total: usize = done_sizes.iter().chain(&undone_sizes).sum();
+        // Redo steps go first: they are lost anyway as soon as the user types
+        let undone_drop = undone_sizes
+            .iter()
+            .take_while(|&&size| {
+                let over = total > settings.max_bytes;
+                if over {
+                    total -= size;
+                }
+                over
+            })
+            .count();
+        let done_drop = done_sizes
+            .iter()
+            .take_while(|&&size| {
+                let over = total > settings.max_bytes;
+                if over {
+                    total -= size;
+                }
+                over
+            })
+            .count();
+        self.undone.drain(..undone_drop);
+        self.done.drain(..done_drop);
+
+        Ok((
+            serde_json::to_string(&self.done)?,
+            serde_json::to_string(&self.undone)?,
+        ))
+    }
+}
+
+/// Drops history of tabs that were closed or saved to a file; only open scratch tabs keep
+/// theirs. Runs inside the session save transaction, after the tabs are written.
+pub(super) fn delete_orphans(tx: &rusqlite::Transaction) -> Result<usize> {
+    Ok(tx.execute(
+        "DELETE FROM tab_history WHERE tab_id NOT IN (
+            SELECT id FROM tabs WHERE path IS NULL
+         )",
+        [],
+    )?)
+}
+
+impl Database {
+    /// Replaces the tab's stored history, trimmed to the limits in `settings`. Returns the
+    /// number of undo steps kept.
+    pub fn save_tab_history(
+        &self,
+        tab_id: &str,
+        mut history: TabHistory,
+        settings: &HistorySettings,
+    ) -> Result<usize> {
+        self.ensure_writable()?;
+        let (done, undone) = history.trim(settings)?;
+        let conn = self.pool.get()?;
+        if history.done.is_empty() && history.undone.is_empty() {
+            conn.execute("DELETE FROM tab_history WHERE tab_id = ?1", params![tab_id])?;
+            return Ok(0);
+        }
+        conn.execute(
+            "INSERT INTO tab_history (tab_id, done, undone, doc_length, modified)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(tab_id) DO UPDATE SET
+                done = excluded.done,
+                undone = excluded.undone,
+                doc_length = excluded.doc_length,
+                modified = excluded.modified",
+            params![
+                tab_id,
+                done,
+                undone,
+                history.doc_length as i64,
+                Local::now().to_rfc3339()
+            ],
+        )?;
+        Ok(history.done.len())
+    }
+
+    pub fn load_tab_history(&self, tab_id: &str) -> Result<Option<TabHistory>> {
+        let conn = self.pool.get()?;
+        let row = conn
+            .query_row(
+                "SELECT done, undone, doc_length FROM tab_history WHERE tab_id = ?1",
+                params![tab_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let Some((done, undone, doc_length)) = row else {
+            return Ok(None);
+        };
+        match (serde_json::from_str(&done), serde_json::from_str(&undone)) {
+            (Ok(done), Ok(undone)) => Ok(Some(TabHistory {
+                done,
+                undone,
+                doc_length: doc_length as usize,
+            })),
+            _ => {
+                log::warn!("Ignoring unreadable undo history for tab {}", tab_id);
+                Ok(None)
+            },
+        }
+    }
+
+    /// Returns whether the tab had stored history
+    pub fn clear_tab_history(&self, tab_id: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let conn = self.pool.get()?;
+        let deleted = conn.execute("DELETE FROM tab_history WHERE tab_id = ?1", params![tab_id])?;
+        Ok(deleted > 0)
+    }
+}
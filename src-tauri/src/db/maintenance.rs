@@ -0,0 +1,131 @@
+use super::Database;
+use anyhow::Result;
+use rusqlite::params;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct DbMemoryStats {
+    /// Bytes currently allocated by SQLite across all connections
+    pub sqlite_heap_bytes: i64,
+    /// Upper bound of the page cache for one pooled connection
+    pub page_cache_limit_bytes: i64,
+    pub connections: u32,
+}
+
+impl Database {
+    pub fn memory_stats(&self) -> Result<DbMemoryStats> {
+        let conn = self.pool.get()?;
+        let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        // Negative cache_size is a limit in KiB, positive a number of pages
+        let cache_size: i64 = conn.query_row("PRAGMA cache_size", [], |row| row.get(0))?;
+        let page_cache_limit_bytes = if cache_size < 0 {
+            -cache_size * 1024
+        } else {
+            cache_size * page_size
+        };
+
+        // SAFETY: reads a global counter; SQLite documents it as thread-safe
+        let sqlite_heap_bytes = unsafe { rusqlite::ffi::sqlite3_memory_used() };
+
+        Ok(DbMemoryStats {
+            sqlite_heap_bytes,
+            page_cache_limit_bytes,
+            connections: self.pool.state().connections,
+        })
+    }
+
+    /// Asks SQLite to release cached pages on every idle pooled connection.
+    pub fn shrink_memory(&self) -> Result<()> {
+        let idle = self.pool.state().idle_connections.max(1);
+        let conns = (0..idle)
+            .filter_map(|_| self.pool.try_get())
+            .collect::<Vec<_>>();
+        for conn in &conns {
+            conn.execute_batch("PRAGMA shrink_memory")?;
+        }
+        Ok(())
+    }
+
+    /// Pages in the database file and how many of them are free
+    pub fn page_counts(&self) -> Result<(i64, i64)> {
+        let conn = self.pool.get()?;
+        let total: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let free: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+        Ok((total, free))
+    }
+
+    /// Copies what it can from the WAL into the database without waiting on readers or
+    /// writers, so the next launch has less to replay. Returns the frames left behind.
+    pub fn checkpoint(&self) -> Result<i64> {
+        let conn = self.pool.get()?;
+        let (busy, wal_frames, checkpointed): (i64, i64, i64) =
+            conn.query_row("PRAGMA wal_checkpoint(PASSIVE)", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+        if busy != 0 {
+            log::debug!("WAL checkpoint did not complete; a connection was busy");
+        }
+        Ok(wal_frames - checkpointed)
+    }
+
+    /// Deletes closed-tab history older than `max_age_days` and all but the newest
+    /// `max_per_document` closed copies of each file. Zero disables a limit. Returns the
+    /// removed tab ids so the frontend can drop them from its in-memory history.
+    pub fn prune_closed_tabs(
+        &self,
+        max_age_days: u32,
+        max_per_document: u32,
+    ) -> Result<Vec<String>> {
+        self.ensure_writable()?;
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        let mut removed = Vec::new();
+
+        if max_age_days > 0 {
+            let mut stmt = tx.prepare(
+                "DELETE FROM closed_tabs
+                 WHERE closed_at IS NOT NULL AND closed_at < datetime('now', ?1)
+                 RETURNING id",
+            )?;
+            let ids = stmt
+                .query_map(params![format!("-{} days", max_age_days)], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            removed.extend(ids);
+        }
+
+        if max_per_document > 0 {
+            let mut stmt = tx.prepare(
+                "DELETE FROM closed_tabs WHERE id IN (
+                    SELECT id FROM (
+                        SELECT id, ROW_NUMBER() OVER (
+                            PARTITION BY path ORDER BY closed_at DESC, sort_index ASC
+                        ) AS rank
+                        FROM closed_tabs
+                        WHERE path IS NOT NULL AND path != ''
+                    ) WHERE rank > ?1
+                 )
+                 RETURNING id",
+            )?;
+            let ids = stmt
+                .query_map(params![max_per_document], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            removed.extend(ids);
+        }
+
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Keeps only the `max_files` most recently opened entries in recent files.
+    pub fn trim_recent_files(&self, max_files: u32) -> Result<usize> {
+        self.ensure_writable()?;
+        let conn = self.pool.get()?;
+        let removed = conn.execute(
+            "DELETE FROM recent_files WHERE path NOT IN (
+                SELECT path FROM recent_files ORDER BY last_opened DESC LIMIT ?1
+             )",
+            params![max_files],
+        )?;
+        Ok(removed)
+    }
+}
@@ -0,0 +1,288 @@
+use anyhow::{Result, anyhow};
+use rusqlite::{Connection, params};
+use std::path::{Path, PathBuf};
+
+pub(super) const MIGRATIONS: &[&str] = &[
+    // v1: Initial Schema
+    "CREATE TABLE IF NOT EXISTS tabs (
+        id TEXT PRIMARY KEY,
+        title TEXT NOT NULL,
+        content TEXT,
+        is_dirty INTEGER NOT NULL,
+        path TEXT,
+        scroll_percentage REAL NOT NULL,
+        created TEXT,
+        modified TEXT,
+        is_pinned INTEGER DEFAULT 0,
+        custom_title TEXT,
+        file_check_failed INTEGER DEFAULT 0,
+        file_check_performed INTEGER DEFAULT 0,
+        mru_position INTEGER,
+        sort_index INTEGER DEFAULT 0
+    );
+    CREATE TABLE IF NOT EXISTS closed_tabs (
+        id TEXT PRIMARY KEY,
+        title TEXT NOT NULL,
+        content TEXT,
+        is_dirty INTEGER NOT NULL,
+        path TEXT,
+        scroll_percentage REAL NOT NULL,
+        created TEXT,
+        modified TEXT,
+        is_pinned INTEGER DEFAULT 0,
+        custom_title TEXT,
+        file_check_failed INTEGER DEFAULT 0,
+        file_check_performed INTEGER DEFAULT 0,
+        mru_position INTEGER,
+        sort_index INTEGER DEFAULT 0,
+        original_index INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS bookmarks (
+        id TEXT PRIMARY KEY,
+        path TEXT NOT NULL UNIQUE,
+        title TEXT NOT NULL,
+        tags TEXT NOT NULL,
+        created TEXT NOT NULL,
+        last_accessed TEXT
+    );
+    CREATE TABLE IF NOT EXISTS recent_files (
+        path TEXT PRIMARY KEY,
+        last_opened TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_recent_files_last_opened ON recent_files(last_opened DESC);
+    CREATE TRIGGER IF NOT EXISTS prune_recent_files
+    AFTER INSERT ON recent_files
+    WHEN (SELECT COUNT(*) FROM recent_files) > 99
+    BEGIN
+        DELETE FROM recent_files WHERE path NOT IN (
+            SELECT path FROM recent_files ORDER BY last_opened DESC LIMIT 99
+        );
+    END;",
+    // v2: Add index on tabs.sort_index for faster session restore
+    "CREATE INDEX IF NOT EXISTS idx_tabs_sort_index ON tabs(sort_index);",
+    // v3: Increase recent files retention from 99 to 999
+    "DROP TRIGGER IF EXISTS prune_recent_files;
+    CREATE TRIGGER IF NOT EXISTS prune_recent_files
+    AFTER INSERT ON recent_files
+    WHEN (SELECT COUNT(*) FROM recent_files) > 999
+    BEGIN
+        DELETE FROM recent_files WHERE path NOT IN (
+            SELECT path FROM recent_files ORDER BY last_opened DESC LIMIT 999
+        );
+    END;",
+    // v4: Normalize bookmark tags out of the JSON column
+    "CREATE TABLE IF NOT EXISTS bookmark_tags (
+        bookmark_id TEXT NOT NULL REFERENCES bookmarks(id) ON DELETE CASCADE ON UPDATE CASCADE,
+        tag TEXT NOT NULL COLLATE NOCASE,
+        position INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (bookmark_id, tag)
+    );
+    CREATE INDEX IF NOT EXISTS idx_bookmark_tags_tag ON bookmark_tags(tag);
+    INSERT OR IGNORE INTO bookmark_tags (bookmark_id, tag, position)
+    SELECT b.id, TRIM(j.value), j.key
+    FROM bookmarks b, json_each(CASE WHEN json_valid(b.tags) THEN b.tags ELSE '[]' END) j
+    WHERE j.type = 'text' AND TRIM(j.value) != '';",
+    // v5: Remember each document's on-disk encoding, BOM, and line endings
+    "ALTER TABLE tabs ADD COLUMN encoding TEXT;
+    ALTER TABLE tabs ADD COLUMN has_bom INTEGER DEFAULT 0;
+    ALTER TABLE tabs ADD COLUMN line_ending TEXT;
+    ALTER TABLE closed_tabs ADD COLUMN encoding TEXT;
+    ALTER TABLE closed_tabs ADD COLUMN has_bom INTEGER DEFAULT 0;
+    ALTER TABLE closed_tabs ADD COLUMN line_ending TEXT;",
+    // v6: Record when each tab was closed so history can be pruned by age
+    "ALTER TABLE closed_tabs ADD COLUMN closed_at TEXT;
+    UPDATE closed_tabs SET closed_at = datetime('now');",
+    // v7: Named, colored tab groups; tabs keep their group across restarts
+    "CREATE TABLE IF NOT EXISTS tab_groups (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        color TEXT,
+        collapsed INTEGER NOT NULL DEFAULT 0,
+        sort_index INTEGER NOT NULL DEFAULT 0
+    );
+    ALTER TABLE tabs ADD COLUMN group_id TEXT;
+    ALTER TABLE closed_tabs ADD COLUMN group_id TEXT;
+    CREATE INDEX IF NOT EXISTS idx_tabs_group_id ON tabs(group_id);",
+    // v8: Free-form per-tab metadata (JSON values) for frontend features
+    "CREATE TABLE IF NOT EXISTS tab_meta (
+        tab_id TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (tab_id, key)
+    );",
+    // v9: Optional folder for organizing bookmarks
+    "ALTER TABLE bookmarks ADD COLUMN folder TEXT;
+    CREATE INDEX IF NOT EXISTS idx_bookmarks_folder ON bookmarks(folder);",
+    // v10: Find/replace history and named saved searches
+    "CREATE TABLE IF NOT EXISTS searches (
+        id TEXT PRIMARY KEY,
+        name TEXT UNIQUE COLLATE NOCASE,
+        find_text TEXT NOT NULL,
+        replace_text TEXT NOT NULL DEFAULT '',
+        match_case INTEGER NOT NULL DEFAULT 0,
+        whole_word INTEGER NOT NULL DEFAULT 0,
+        use_regex INTEGER NOT NULL DEFAULT 0,
+        scope TEXT NOT NULL DEFAULT 'current',
+        pinned INTEGER NOT NULL DEFAULT 0,
+        last_used TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_searches_last_used ON searches(last_used DESC);
+    CREATE TRIGGER IF NOT EXISTS prune_search_history
+    AFTER INSERT ON searches
+    WHEN (SELECT COUNT(*) FROM searches WHERE name IS NULL AND pinned = 0) > 100
+    BEGIN
+        DELETE FROM searches WHERE name IS NULL AND pinned = 0 AND id NOT IN (
+            SELECT id FROM searches WHERE name IS NULL AND pinned = 0
+            ORDER BY last_used DESC LIMIT 100
+        );
+    END;",
+    // v11: Review comments anchored to text ranges, kept out of the documents themselves
+    "CREATE TABLE IF NOT EXISTS annotations (
+        id TEXT PRIMARY KEY,
+        path TEXT NOT NULL,
+        start_offset INTEGER NOT NULL,
+        end_offset INTEGER NOT NULL,
+        quote TEXT NOT NULL,
+        prefix TEXT NOT NULL DEFAULT '',
+        suffix TEXT NOT NULL DEFAULT '',
+        quote_hash TEXT NOT NULL,
+        body TEXT NOT NULL,
+        author TEXT,
+        resolved INTEGER NOT NULL DEFAULT 0,
+        created TEXT NOT NULL,
+        modified TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_annotations_path ON annotations(path, start_offset);",
+    // v12: Writing sessions, one row per sitting, for the writing stats dashboard
+    "CREATE TABLE IF NOT EXISTS writing_activity (
+        id TEXT PRIMARY KEY,
+        path TEXT,
+        mode TEXT,
+        started TEXT NOT NULL,
+        ended TEXT NOT NULL,
+        duration_secs INTEGER NOT NULL,
+        active_secs INTEGER NOT NULL,
+        words_added INTEGER NOT NULL,
+        words_removed INTEGER NOT NULL,
+        longest_streak_secs INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_writing_activity_started ON writing_activity(started DESC);",
+    // v13: Undo/redo history of scratch tabs, so it survives a restart
+    "CREATE TABLE IF NOT EXISTS tab_history (
+        tab_id TEXT PRIMARY KEY,
+        done TEXT NOT NULL,
+        undone TEXT NOT NULL,
+        doc_length INTEGER NOT NULL,
+        modified TEXT NOT NULL
+    );",
+];
+
+/// Newest schema version this build knows how to read and write.
+pub(super) const SUPPORTED_SCHEMA_VERSION: i32 = MIGRATIONS.len() as i32;
+
+pub(super) fn schema_version(conn: &Connection) -> Result<i32> {
+    // Use PRAGMA user_version for atomic schema versioning
+    Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+/// Brings the schema up to date. Returns `false` when the database was written by a
+/// newer build or a migration fails its dry run: nothing is migrated and the caller must
+/// treat the database as read-only.
+pub(super) fn setup_schema(conn: &mut Connection, db_path: &Path) -> Result<bool> {
+    let current_version = schema_version(conn)?;
+
+    if current_version > SUPPORTED_SCHEMA_VERSION {
+        log::error!(
+            "Database schema v{} is newer than supported v{}; opening read-only",
+            current_version,
+            SUPPORTED_SCHEMA_VERSION
+        );
+        return Ok(false);
+    }
+
+    if current_version == SUPPORTED_SCHEMA_VERSION {
+        return Ok(true);
+    }
+
+    // A fresh database has nothing to lose, so only existing data gets a dry run.
+    // A failed dry run keeps the data readable instead of handing it to corruption recovery.
+    if current_version > 0
+        && let Err(e) = dry_run_migrations(conn, db_path, current_version)
+    {
+        log::error!("{}; opening read-only", e);
+        return Ok(false);
+    }
+
+    apply_migrations(conn, current_version, false)?;
+    Ok(true)
+}
+
+fn apply_migrations(conn: &mut Connection, from_version: i32, verify: bool) -> Result<()> {
+    for (i, migration) in MIGRATIONS.iter().enumerate() {
+        let version = (i + 1) as i32;
+        if version > from_version {
+            log::info!("Applying database migration v{}", version);
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.execute(&format!("PRAGMA user_version = {}", version), [])?;
+            tx.commit()?;
+
+            if verify {
+                verify_integrity(conn, version)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Applies pending migrations to a scratch copy of the database, checking integrity
+/// after each step, so a failing migration never leaves the live file half-migrated.
+fn dry_run_migrations(conn: &Connection, db_path: &Path, from_version: i32) -> Result<()> {
+    let copy_path = dry_run_path(db_path);
+    log::info!(
+        "Dry-running migrations v{} -> v{} against {:?}",
+        from_version,
+        SUPPORTED_SCHEMA_VERSION,
+        copy_path
+    );
+
+    conn.execute("VACUUM INTO ?1", params![copy_path.to_string_lossy()])?;
+
+    let result = Connection::open(&copy_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|mut copy| apply_migrations(&mut copy, from_version, true));
+
+    if let Err(e) = std::fs::remove_file(&copy_path) {
+        log::warn!(
+            "Failed to remove migration dry-run copy {:?}: {}",
+            copy_path,
+            e
+        );
+    }
+
+    result.map_err(|e| anyhow!("Migration dry run failed, database left untouched: {}", e))
+}
+
+fn verify_integrity(conn: &Connection, version: i32) -> Result<()> {
+    let check: String = conn.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+    if check != "ok" {
+        return Err(anyhow!(
+            "integrity check failed after v{}: {}",
+            version,
+            check
+        ));
+    }
+    Ok(())
+}
+
+fn dry_run_path(db_path: &Path) -> PathBuf {
+    let file_name = db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "session.db".to_string());
+    db_path.with_file_name(format!(
+        "{}.migrate.{}.tmp",
+        file_name,
+        uuid::Uuid::new_v4()
+    ))
+}
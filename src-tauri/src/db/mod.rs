@@ -1,10 +1,30 @@
-use anyhow::Result;
+mod activity;
+mod annotations;
+mod bookmarks;
+mod history;
+mod maintenance;
+mod migrations;
+mod searches;
+mod tab_groups;
+mod tab_meta;
+
+pub use activity::{WritingDay, WritingSessionRecord};
+pub use annotations::Annotation;
+pub use bookmarks::{Bookmark, TagCount};
+pub use history::{HistorySettings, TabHistory};
+pub use maintenance::DbMemoryStats;
+pub use searches::{SavedSearch, SearchQuery};
+pub use tab_groups::TabGroup;
+pub use tab_meta::TabMeta;
+
+use anyhow::{Result, anyhow};
 use chrono::Local;
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::{Connection, params};
+use rusqlite::params;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct TabState {
@@ -30,6 +50,14 @@ pub struct TabState {
     pub sort_index: Option<i32>,
     #[serde(default)]
     pub original_index: Option<i32>,
+    #[serde(default)]
+    pub encoding: Option<String>,
+    #[serde(default)]
+    pub has_bom: bool,
+    #[serde(default)]
+    pub line_ending: Option<String>,
+    #[serde(default)]
+    pub group_id: Option<String>,
 }
 
 impl TabState {
@@ -64,16 +92,14 @@ impl fmt::Debug for TabState {
 pub struct SessionData {
     pub active_tabs: Vec<TabState>,
     pub closed_tabs: Vec<TabState>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Bookmark {
-    pub id: String,
-    pub path: String,
-    pub title: String,
-    pub tags: Vec<String>,
-    pub created: String,
-    pub last_accessed: Option<String>,
+    /// Files the frontend should open after restoring, set by the startup mode
+    #[serde(default)]
+    pub open_paths: Vec<String>,
+    #[serde(default)]
+    pub tab_groups: Vec<TabGroup>,
+    /// Metadata for open and closed tabs, keyed by tab id
+    #[serde(default)]
+    pub tab_meta: HashMap<String, TabMeta>,
 }
 
 #[derive(Serialize)]
@@ -81,83 +107,29 @@ pub struct TabData {
     pub content: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct DatabaseStatus {
+    pub schema_version: i32,
+    pub supported_version: i32,
+    pub read_only: bool,
+}
+
 pub type DbPool = r2d2::Pool<SqliteConnectionManager>;
 
+fn build_pool(manager: SqliteConnectionManager) -> Result<DbPool> {
+    Ok(r2d2::Pool::builder()
+        .max_size(3)
+        .min_idle(Some(1))
+        .connection_timeout(std::time::Duration::from_secs(5))
+        .build(manager)?)
+}
+
 #[derive(Clone)]
 pub struct Database {
     pool: DbPool,
+    read_only: bool,
 }
 
-const MIGRATIONS: &[&str] = &[
-    // v1: Initial Schema
-    "CREATE TABLE IF NOT EXISTS tabs (
-        id TEXT PRIMARY KEY,
-        title TEXT NOT NULL,
-        content TEXT,
-        is_dirty INTEGER NOT NULL,
-        path TEXT,
-        scroll_percentage REAL NOT NULL,
-        created TEXT,
-        modified TEXT,
-        is_pinned INTEGER DEFAULT 0,
-        custom_title TEXT,
-        file_check_failed INTEGER DEFAULT 0,
-        file_check_performed INTEGER DEFAULT 0,
-        mru_position INTEGER,
-        sort_index INTEGER DEFAULT 0
-    );
-    CREATE TABLE IF NOT EXISTS closed_tabs (
-        id TEXT PRIMARY KEY,
-        title TEXT NOT NULL,
-        content TEXT,
-        is_dirty INTEGER NOT NULL,
-        path TEXT,
-        scroll_percentage REAL NOT NULL,
-        created TEXT,
-        modified TEXT,
-        is_pinned INTEGER DEFAULT 0,
-        custom_title TEXT,
-        file_check_failed INTEGER DEFAULT 0,
-        file_check_performed INTEGER DEFAULT 0,
-        mru_position INTEGER,
-        sort_index INTEGER DEFAULT 0,
-        original_index INTEGER
-    );
-    CREATE TABLE IF NOT EXISTS bookmarks (
-        id TEXT PRIMARY KEY,
-        path TEXT NOT NULL UNIQUE,
-        title TEXT NOT NULL,
-        tags TEXT NOT NULL,
-        created TEXT NOT NULL,
-        last_accessed TEXT
-    );
-    CREATE TABLE IF NOT EXISTS recent_files (
-        path TEXT PRIMARY KEY,
-        last_opened TEXT NOT NULL
-    );
-    CREATE INDEX IF NOT EXISTS idx_recent_files_last_opened ON recent_files(last_opened DESC);
-    CREATE TRIGGER IF NOT EXISTS prune_recent_files
-    AFTER INSERT ON recent_files
-    WHEN (SELECT COUNT(*) FROM recent_files) > 99
-    BEGIN
-        DELETE FROM recent_files WHERE path NOT IN (
-            SELECT path FROM recent_files ORDER BY last_opened DESC LIMIT 99
-        );
-    END;",
-    // v2: Add index on tabs.sort_index for faster session restore
-    "CREATE INDEX IF NOT EXISTS idx_tabs_sort_index ON tabs(sort_index);",
-    // v3: Increase recent files retention from 99 to 999
-    "DROP TRIGGER IF EXISTS prune_recent_files;
-    CREATE TRIGGER IF NOT EXISTS prune_recent_files
-    AFTER INSERT ON recent_files
-    WHEN (SELECT COUNT(*) FROM recent_files) > 999
-    BEGIN
-        DELETE FROM recent_files WHERE path NOT IN (
-            SELECT path FROM recent_files ORDER BY last_opened DESC LIMIT 999
-        );
-    END;",
-];
-
 impl Database {
     pub fn new(db_path: PathBuf) -> Result<Self> {
         log::info!("Initializing database at {:?}", db_path);
@@ -173,43 +145,74 @@ impl Database {
             Ok(())
         });
 
-        let pool = r2d2::Pool::builder()
-            .max_size(3)
-            .min_idle(Some(1))
-            .connection_timeout(std::time::Duration::from_secs(5))
-            .build(manager)?;
+        let pool = build_pool(manager)?;
 
         let mut conn = pool.get()?;
-        Self::setup_schema(&mut conn)?;
+        let writable = migrations::setup_schema(&mut conn, &db_path)?;
         drop(conn);
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            read_only: !writable,
+        })
     }
 
-    fn setup_schema(conn: &mut Connection) -> Result<()> {
-        // Use PRAGMA user_version for atomic schema versioning
-        let current_version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
-
-        for (i, migration) in MIGRATIONS.iter().enumerate() {
-            let version = (i + 1) as i32;
-            if version > current_version {
-                log::info!("Applying database migration v{}", version);
-                let tx = conn.transaction()?;
-                tx.execute_batch(migration)?;
-                tx.execute(&format!("PRAGMA user_version = {}", version), [])?;
-                tx.commit()?;
-            }
+    /// Opens the database for reading only, without migrating it, for when another
+    /// instance owns the data folder.
+    pub fn open_read_only(db_path: PathBuf) -> Result<Self> {
+        log::info!("Opening database read-only at {:?}", db_path);
+
+        let manager = SqliteConnectionManager::file(&db_path).with_init(|conn| {
+            conn.execute_batch(
+                "PRAGMA busy_timeout = 5000;
+                 PRAGMA query_only = ON;",
+            )?;
+            Ok(())
+        });
+        Ok(Self {
+            pool: build_pool(manager)?,
+            read_only: true,
+        })
+    }
+
+    /// Refuses writes to a database created by a newer build, whose schema we cannot
+    /// safely modify, or owned by another running instance.
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(anyhow!(
+                "Database is open read-only: it was created by a newer version of MarkdownRS \
+                 or another instance is using it"
+            ));
         }
+        Ok(())
+    }
 
+    pub fn status(&self) -> Result<DatabaseStatus> {
+        let conn = self.pool.get()?;
+        Ok(DatabaseStatus {
+            schema_version: migrations::schema_version(&conn)?,
+            supported_version: migrations::SUPPORTED_SCHEMA_VERSION,
+            read_only: self.read_only,
+        })
+    }
+
+    /// Writes a consistent copy of the database to `target`. Read-only on the source,
+    /// so it stays available when the schema is too new to modify.
+    pub fn export_copy(&self, target: &Path) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute("VACUUM INTO ?1", params![target.to_string_lossy()])?;
         Ok(())
     }
 
     pub fn save_session(&self, active_tabs: &[TabState], closed_tabs: &[TabState]) -> Result<()> {
+        self.ensure_writable()?;
         let mut conn = self.pool.get()?;
         let tx = conn.transaction()?;
 
         self.save_active_tabs(&tx, active_tabs)?;
         self.save_closed_tabs(&tx, closed_tabs)?;
+        tab_meta::delete_orphans(&tx)?;
+        history::delete_orphans(&tx)?;
 
         tx.commit()?;
         Ok(())
@@ -239,8 +242,9 @@ impl Database {
             "INSERT INTO tabs (
                 id, title, content, is_dirty, path, scroll_percentage,
                 created, modified, is_pinned, custom_title,
-                file_check_failed, file_check_performed, mru_position, sort_index
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+                file_check_failed, file_check_performed, mru_position, sort_index,
+                encoding, has_bom, line_ending, group_id
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
             ON CONFLICT(id) DO UPDATE SET
                 title              = excluded.title,
                 content            = CASE WHEN excluded.content IS NOT NULL
@@ -256,7 +260,11 @@ impl Database {
                 file_check_failed  = excluded.file_check_failed,
                 file_check_performed = excluded.file_check_performed,
                 mru_position       = excluded.mru_position,
-                sort_index         = excluded.sort_index",
+                sort_index         = excluded.sort_index,
+                encoding           = excluded.encoding,
+                has_bom            = excluded.has_bom,
+                line_ending        = excluded.line_ending,
+                group_id           = excluded.group_id",
         )?;
 
         for tab in tabs {
@@ -277,6 +285,10 @@ impl Database {
                 tab.file_check_performed as i32,
                 &tab.mru_position,
                 &tab.sort_index,
+                &tab.encoding,
+                tab.has_bom as i32,
+                &tab.line_ending,
+                &tab.group_id,
             ])?;
         }
 
@@ -304,8 +316,10 @@ impl Database {
             "INSERT INTO closed_tabs (
                 id, title, content, is_dirty, path, scroll_percentage,
                 created, modified, is_pinned, custom_title,
-                file_check_failed, file_check_performed, mru_position, sort_index, original_index
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+                file_check_failed, file_check_performed, mru_position, sort_index, original_index,
+                encoding, has_bom, line_ending, group_id, closed_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+                ?19, datetime('now'))
             ON CONFLICT(id) DO UPDATE SET
                 title              = excluded.title,
                 content            = CASE WHEN excluded.content IS NOT NULL
@@ -322,7 +336,11 @@ impl Database {
                 file_check_performed = excluded.file_check_performed,
                 mru_position       = excluded.mru_position,
                 sort_index         = excluded.sort_index,
-                original_index     = excluded.original_index",
+                original_index     = excluded.original_index,
+                encoding           = excluded.encoding,
+                has_bom            = excluded.has_bom,
+                line_ending        = excluded.line_ending,
+                group_id           = excluded.group_id",
         )?;
 
         for (i, tab) in tabs.iter().enumerate() {
@@ -343,6 +361,10 @@ impl Database {
                 &tab.mru_position,
                 i as i32,
                 &tab.original_index,
+                &tab.encoding,
+                tab.has_bom as i32,
+                &tab.line_ending,
+                &tab.group_id,
             ])?;
         }
 
@@ -356,10 +378,10 @@ impl Database {
         let conn = self.pool.get()?;
 
         let query = if include_content {
-            "SELECT id, title, content, is_dirty, path, scroll_percentage, created, modified, is_pinned, custom_title, file_check_failed, file_check_performed, mru_position, sort_index
+            "SELECT id, title, content, is_dirty, path, scroll_percentage, created, modified, is_pinned, custom_title, file_check_failed, file_check_performed, mru_position, sort_index, encoding, has_bom, line_ending, group_id
              FROM tabs ORDER BY sort_index ASC"
         } else {
-            "SELECT id, title, NULL as content, is_dirty, path, scroll_percentage, created, modified, is_pinned, custom_title, file_check_failed, file_check_performed, mru_position, sort_index
+            "SELECT id, title, NULL as content, is_dirty, path, scroll_percentage, created, modified, is_pinned, custom_title, file_check_failed, file_check_performed, mru_position, sort_index, encoding, has_bom, line_ending, group_id
              FROM tabs ORDER BY sort_index ASC"
         };
 
@@ -387,15 +409,19 @@ impl Database {
                     mru_position: row.get(12)?,
                     sort_index: row.get(13)?,
                     original_index: None,
+                    encoding: row.get(14)?,
+                    has_bom: row.get::<_, Option<i32>>(15)?.unwrap_or(0) != 0,
+                    line_ending: row.get(16)?,
+                    group_id: row.get(17)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
         let closed_query = if include_content {
-            "SELECT id, title, content, is_dirty, path, scroll_percentage, created, modified, is_pinned, custom_title, file_check_failed, file_check_performed, mru_position, sort_index, original_index
+            "SELECT id, title, content, is_dirty, path, scroll_percentage, created, modified, is_pinned, custom_title, file_check_failed, file_check_performed, mru_position, sort_index, original_index, encoding, has_bom, line_ending, group_id
              FROM closed_tabs ORDER BY sort_index ASC"
         } else {
-            "SELECT id, title, NULL as content, is_dirty, path, scroll_percentage, created, modified, is_pinned, custom_title, file_check_failed, file_check_performed, mru_position, sort_index, original_index
+            "SELECT id, title, NULL as content, is_dirty, path, scroll_percentage, created, modified, is_pinned, custom_title, file_check_failed, file_check_performed, mru_position, sort_index, original_index, encoding, has_bom, line_ending, group_id
              FROM closed_tabs ORDER BY sort_index ASC"
         };
 
@@ -423,6 +449,10 @@ impl Database {
                     mru_position: row.get(12)?,
                     sort_index: row.get(13)?,
                     original_index: row.get(14)?,
+                    encoding: row.get(15)?,
+                    has_bom: row.get::<_, Option<i32>>(16)?.unwrap_or(0) != 0,
+                    line_ending: row.get(17)?,
+                    group_id: row.get(18)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -430,6 +460,9 @@ impl Database {
         Ok(SessionData {
             active_tabs,
             closed_tabs,
+            open_paths: Vec::new(),
+            tab_groups: self.list_tab_groups()?,
+            tab_meta: self.load_all_tab_meta()?,
         })
     }
 
@@ -452,69 +485,8 @@ impl Database {
         Ok(TabData { content })
     }
 
-    pub fn add_bookmark(&self, bookmark: &Bookmark) -> Result<()> {
-        let conn = self.pool.get()?;
-        let tags_json = serde_json::to_string(&bookmark.tags)?;
-        conn.execute(
-            "INSERT INTO bookmarks (id, path, title, tags, created, last_accessed)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-             ON CONFLICT(path) DO UPDATE SET
-                id            = excluded.id,
-                title         = excluded.title,
-                tags          = excluded.tags,
-                created       = excluded.created,
-                last_accessed = excluded.last_accessed",
-            params![
-                &bookmark.id,
-                &bookmark.path,
-                &bookmark.title,
-                &tags_json,
-                &bookmark.created,
-                &bookmark.last_accessed,
-            ],
-        )?;
-        Ok(())
-    }
-
-    pub fn get_all_bookmarks(&self) -> Result<Vec<Bookmark>> {
-        let conn = self.pool.get()?;
-        let mut stmt = conn.prepare(
-            "SELECT id, path, title, tags, created, last_accessed FROM bookmarks ORDER BY created DESC"
-        )?;
-
-        let bookmarks = stmt
-            .query_map([], |row| {
-                let tags_json: String = row.get(3)?;
-                let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-                Ok(Bookmark {
-                    id: row.get(0)?,
-                    path: row.get(1)?,
-                    title: row.get(2)?,
-                    tags,
-                    created: row.get(4)?,
-                    last_accessed: row.get(5)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(bookmarks)
-    }
-
-    pub fn delete_bookmark(&self, id: &str) -> Result<()> {
-        let conn = self.pool.get()?;
-        conn.execute("DELETE FROM bookmarks WHERE id = ?1", params![id])?;
-        Ok(())
-    }
-
-    pub fn update_bookmark_access_time(&self, id: &str, last_accessed: &str) -> Result<()> {
-        let conn = self.pool.get()?;
-        conn.execute(
-            "UPDATE bookmarks SET last_accessed = ?1 WHERE id = ?2",
-            params![last_accessed, id],
-        )?;
-        Ok(())
-    }
-
     pub fn seed_recent_files_from_history(&self) -> Result<()> {
+        self.ensure_writable()?;
         let conn = self.pool.get()?;
         let now = Local::now().to_rfc3339();
 
@@ -543,6 +515,7 @@ impl Database {
     }
 
     pub fn add_recent_file(&self, path: &str, last_opened: &str) -> Result<()> {
+        self.ensure_writable()?;
         let conn = self.pool.get()?;
 
         // Insert or Update the recent file
@@ -565,18 +538,21 @@ impl Database {
     }
 
     pub fn remove_recent_file(&self, path: &str) -> Result<()> {
+        self.ensure_writable()?;
         let conn = self.pool.get()?;
         conn.execute("DELETE FROM recent_files WHERE path = ?1", params![path])?;
         Ok(())
     }
 
     pub fn clear_recent_files(&self) -> Result<()> {
+        self.ensure_writable()?;
         let conn = self.pool.get()?;
         conn.execute("DELETE FROM recent_files", [])?;
         Ok(())
     }
 
     pub fn delete_orphan_recent_files(&self) -> Result<usize> {
+        self.ensure_writable()?;
         let conn = self.pool.get()?;
         let paths: Vec<String> = {
             let mut stmt = conn.prepare("SELECT path FROM recent_files")?;
@@ -608,72 +584,8 @@ impl Database {
         Ok(dead.len())
     }
 
-    pub fn delete_orphan_bookmarks(&self) -> Result<usize> {
-        let conn = self.pool.get()?;
-        let entries: Vec<(String, String)> = {
-            let mut stmt = conn.prepare("SELECT id, path FROM bookmarks")?;
-            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-                .collect::<rusqlite::Result<Vec<(String, String)>>>()?
-        };
-
-        let dead_ids: Vec<&str> = entries
-            .iter()
-            .filter(|(_, path)| !std::path::Path::new(path.as_str()).exists())
-            .map(|(id, _)| id.as_str())
-            .collect();
-
-        if dead_ids.is_empty() {
-            return Ok(0);
-        }
-
-        let placeholders = (1..=dead_ids.len())
-            .map(|i| format!("?{}", i))
-            .collect::<Vec<_>>()
-            .join(",");
-        let sql = format!("DELETE FROM bookmarks WHERE id IN ({})", placeholders);
-        let params: Vec<&dyn rusqlite::types::ToSql> = dead_ids
-            .iter()
-            .map(|id| id as &dyn rusqlite::types::ToSql)
-            .collect();
-        conn.execute(&sql, params.as_slice())?;
-
-        Ok(dead_ids.len())
-    }
-
-    pub fn import_bookmarks(&self, bookmarks: &[Bookmark]) -> Result<()> {
-        if bookmarks.is_empty() {
-            return Ok(());
-        }
-        let mut conn = self.pool.get()?;
-        let tx = conn.transaction()?;
-        {
-            let mut stmt = tx.prepare_cached(
-                "INSERT INTO bookmarks (id, path, title, tags, created, last_accessed)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-                 ON CONFLICT(path) DO UPDATE SET
-                    id            = excluded.id,
-                    title         = excluded.title,
-                    tags          = excluded.tags,
-                    created       = excluded.created,
-                    last_accessed = excluded.last_accessed",
-            )?;
-            for bookmark in bookmarks {
-                let tags_json = serde_json::to_string(&bookmark.tags)?;
-                stmt.execute(params![
-                    &bookmark.id,
-                    &bookmark.path,
-                    &bookmark.title,
-                    tags_json,
-                    &bookmark.created,
-                    &bookmark.last_accessed,
-                ])?;
-            }
-        }
-        tx.commit()?;
-        Ok(())
-    }
-
     pub fn import_recent_files(&self, paths: &[String]) -> Result<()> {
+        self.ensure_writable()?;
         if paths.is_empty() {
             return Ok(());
         }
@@ -692,6 +604,7 @@ impl Database {
         Ok(())
     }
     pub fn incremental_vacuum(&self, max_pages: i32) -> Result<()> {
+        self.ensure_writable()?;
         let conn = self.pool.get()?;
         if max_pages > 0 {
             conn.execute(&format!("PRAGMA incremental_vacuum({})", max_pages), [])?;
@@ -700,10 +613,4 @@ impl Database {
         }
         Ok(())
     }
-
-    pub fn get_freelist_count(&self) -> Result<i32> {
-        let conn = self.pool.get()?;
-        let count: i32 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
-        Ok(count)
-    }
 }
@@ -0,0 +1,226 @@
+use super::Database;
+use anyhow::{Result, anyhow};
+use chrono::Local;
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use serde::{Deserialize, Serialize};
+
+/// A find/replace query as the search panel holds it. `scope` is `"current"` for the
+/// active document or `"all"` for every open tab.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchQuery {
+    pub find_text: String,
+    #[serde(default)]
+    pub replace_text: String,
+    #[serde(default)]
+    pub match_case: bool,
+    #[serde(default)]
+    pub whole_word: bool,
+    #[serde(default)]
+    pub use_regex: bool,
+    #[serde(default = "default_scope")]
+    pub scope: String,
+}
+
+fn default_scope() -> String {
+    "current".to_string()
+}
+
+/// A history entry, or a saved search when `name` is set. Pinned and named entries are
+/// never pruned from history.
+#[derive(Debug, Serialize, Clone)]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub query: SearchQuery,
+    pub pinned: bool,
+    pub last_used: String,
+}
+
+const SELECT_SEARCH_SQL: &str = "SELECT id, name, find_text, replace_text, match_case,
+        whole_word, use_regex, scope, pinned, last_used
+     FROM searches";
+
+fn search_from_row(row: &Row) -> rusqlite::Result<SavedSearch> {
+    Ok(SavedSearch {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        query: SearchQuery {
+            find_text: row.get(2)?,
+            replace_text: row.get(3)?,
+            match_case: row.get::<_, i32>(4)? != 0,
+            whole_word: row.get::<_, i32>(5)? != 0,
+            use_regex: row.get::<_, i32>(6)? != 0,
+            scope: row.get(7)?,
+        },
+        pinned: row.get::<_, i32>(8)? != 0,
+        last_used: row.get(9)?,
+    })
+}
+
+fn validate_query(query: &SearchQuery) -> Result<()> {
+    if query.find_text.is_empty() {
+        return Err(anyhow!("Search text cannot be empty"));
+    }
+    if query.scope != "current" && query.scope != "all" {
+        return Err(anyhow!("Unknown search scope: {}", query.scope));
+    }
+    Ok(())
+}
+
+fn get_search(conn: &Connection, id: &str) -> Result<SavedSearch> {
+    conn.query_row(
+        &format!("{} WHERE id = ?1", SELECT_SEARCH_SQL),
+        params![id],
+        search_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| anyhow!("Search not found"))
+}
+
+impl Database {
+    /// Records a query in history. Repeating an identical query moves it to the top
+    /// instead of adding a duplicate.
+    pub fn record_search(&self, query: &SearchQuery) -> Result<SavedSearch> {
+        self.ensure_writable()?;
+        validate_query(query)?;
+
+        let conn = self.pool.get()?;
+        let now = Local::now().to_rfc3339();
+        let existing: Option<String> = conn
+            .query_row(
+                "SELECT id FROM searches
+                 WHERE name IS NULL AND find_text = ?1 AND replace_text = ?2
+                   AND match_case = ?3 AND whole_word = ?4 AND use_regex = ?5 AND scope = ?6",
+                params![
+                    &query.find_text,
+                    &query.replace_text,
+                    query.match_case as i32,
+                    query.whole_word as i32,
+                    query.use_regex as i32,
+                    &query.scope
+                ],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let id = match existing {
+            Some(id) => {
+                conn.execute(
+                    "UPDATE searches SET last_used = ?2 WHERE id = ?1",
+                    params![&id, &now],
+                )?;
+                id
+            },
+            None => {
+                let id = uuid::Uuid::new_v4().to_string();
+                conn.execute(
+                    "INSERT INTO searches (id, find_text, replace_text, match_case,
+                        whole_word, use_regex, scope, last_used)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        &id,
+                        &query.find_text,
+                        &query.replace_text,
+                        query.match_case as i32,
+                        query.whole_word as i32,
+                        query.use_regex as i32,
+                        &query.scope,
+                        &now
+                    ],
+                )?;
+                id
+            },
+        };
+        get_search(&conn, &id)
+    }
+
+    /// Saves `query` under `name`, replacing the query of a saved search with the same
+    /// name (case-insensitive).
+    pub fn save_search(&self, name: &str, query: &SearchQuery) -> Result<SavedSearch> {
+        self.ensure_writable()?;
+        validate_query(query)?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Saved search name cannot be empty"));
+        }
+
+        let conn = self.pool.get()?;
+        let id: String = conn.query_row(
+            "INSERT INTO searches (id, name, find_text, replace_text, match_case,
+                whole_word, use_regex, scope, last_used)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT(name) DO UPDATE SET
+                find_text    = excluded.find_text,
+                replace_text = excluded.replace_text,
+                match_case   = excluded.match_case,
+                whole_word   = excluded.whole_word,
+                use_regex    = excluded.use_regex,
+                scope        = excluded.scope,
+                last_used    = excluded.last_used
+             RETURNING id",
+            params![
+                uuid::Uuid::new_v4().to_string(),
+                name,
+                &query.find_text,
+                &query.replace_text,
+                query.match_case as i32,
+                query.whole_word as i32,
+                query.use_regex as i32,
+                &query.scope,
+                Local::now().to_rfc3339()
+            ],
+            |row| row.get(0),
+        )?;
+        get_search(&conn, &id)
+    }
+
+    /// Pinned entries first, then most recently used. `saved_only` leaves out unnamed
+    /// history entries.
+    pub fn list_searches(&self, saved_only: bool, limit: Option<u32>) -> Result<Vec<SavedSearch>> {
+        let conn = self.pool.get()?;
+        let filter = if saved_only {
+            " WHERE name IS NOT NULL"
+        } else {
+            ""
+        };
+        let mut stmt = conn.prepare(&format!(
+            "{}{} ORDER BY pinned DESC, last_used DESC LIMIT ?1",
+            SELECT_SEARCH_SQL, filter
+        ))?;
+        // SQLite treats a negative LIMIT as no limit
+        let limit = limit.map(i64::from).unwrap_or(-1);
+        let searches = stmt
+            .query_map(params![limit], search_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(searches)
+    }
+
+    pub fn set_search_pinned(&self, id: &str, pinned: bool) -> Result<()> {
+        self.ensure_writable()?;
+        let conn = self.pool.get()?;
+        let updated = conn.execute(
+            "UPDATE searches SET pinned = ?2 WHERE id = ?1",
+            params![id, pinned as i32],
+        )?;
+        if updated == 0 {
+            return Err(anyhow!("Search not found"));
+        }
+        Ok(())
+    }
+
+    pub fn delete_search(&self, id: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let conn = self.pool.get()?;
+        conn.execute("DELETE FROM searches WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Deletes unpinned history entries; saved searches are kept.
+    pub fn clear_search_history(&self) -> Result<usize> {
+        self.ensure_writable()?;
+        let conn = self.pool.get()?;
+        let removed = conn.execute("DELETE FROM searches WHERE name IS NULL AND pinned = 0", [])?;
+        Ok(removed)
+    }
+}
@@ -0,0 +1,143 @@
+use super::Database;
+use anyhow::{Result, anyhow};
+use rusqlite::{Row, params};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TabGroup {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    #[serde(default)]
+    pub collapsed: bool,
+    #[serde(default)]
+    pub sort_index: i32,
+}
+
+fn tab_group_from_row(row: &Row) -> rusqlite::Result<TabGroup> {
+    Ok(TabGroup {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        collapsed: row.get::<_, i32>(3)? != 0,
+        sort_index: row.get(4)?,
+    })
+}
+
+impl Database {
+    pub fn list_tab_groups(&self) -> Result<Vec<TabGroup>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, color, collapsed, sort_index FROM tab_groups
+             ORDER BY sort_index ASC, name",
+        )?;
+        let groups = stmt
+            .query_map([], tab_group_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(groups)
+    }
+
+    /// Adds a group after the existing ones.
+    pub fn create_tab_group(&self, name: &str, color: Option<&str>) -> Result<TabGroup> {
+        self.ensure_writable()?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Group name cannot be empty"));
+        }
+
+        let conn = self.pool.get()?;
+        let sort_index: i32 = conn.query_row(
+            "SELECT COALESCE(MAX(sort_index) + 1, 0) FROM tab_groups",
+            [],
+            |row| row.get(0),
+        )?;
+        let group = TabGroup {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            color: color.map(str::to_string),
+            collapsed: false,
+            sort_index,
+        };
+        conn.execute(
+            "INSERT INTO tab_groups (id, name, color, collapsed, sort_index)
+             VALUES (?1, ?2, ?3, 0, ?4)",
+            params![&group.id, &group.name, &group.color, group.sort_index],
+        )?;
+        Ok(group)
+    }
+
+    pub fn update_tab_group(&self, group: &TabGroup) -> Result<()> {
+        self.ensure_writable()?;
+        if group.name.trim().is_empty() {
+            return Err(anyhow!("Group name cannot be empty"));
+        }
+
+        let conn = self.pool.get()?;
+        let updated = conn.execute(
+            "UPDATE tab_groups SET name = ?2, color = ?3, collapsed = ?4, sort_index = ?5
+             WHERE id = ?1",
+            params![
+                &group.id,
+                group.name.trim(),
+                &group.color,
+                group.collapsed as i32,
+                group.sort_index
+            ],
+        )?;
+        if updated == 0 {
+            return Err(anyhow!("Tab group not found"));
+        }
+        Ok(())
+    }
+
+    /// Removes the group; its tabs stay open and become ungrouped.
+    pub fn delete_tab_group(&self, id: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE tabs SET group_id = NULL WHERE group_id = ?1",
+            params![id],
+        )?;
+        tx.execute(
+            "UPDATE closed_tabs SET group_id = NULL WHERE group_id = ?1",
+            params![id],
+        )?;
+        tx.execute("DELETE FROM tab_groups WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Moves the tabs into `group_id`, or out of any group when it is `None`.
+    /// Returns the number of tabs updated.
+    pub fn assign_tabs_to_group(
+        &self,
+        tab_ids: &[String],
+        group_id: Option<&str>,
+    ) -> Result<usize> {
+        self.ensure_writable()?;
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+
+        if let Some(group_id) = group_id {
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM tab_groups WHERE id = ?1)",
+                params![group_id],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                return Err(anyhow!("Tab group not found"));
+            }
+        }
+
+        let mut updated = 0;
+        {
+            let mut stmt = tx.prepare_cached("UPDATE tabs SET group_id = ?1 WHERE id = ?2")?;
+            for id in tab_ids {
+                updated += stmt.execute(params![group_id, id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+}
@@ -0,0 +1,88 @@
+use super::Database;
+use anyhow::{Result, anyhow};
+use rusqlite::params;
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub type TabMeta = HashMap<String, Value>;
+
+const MAX_KEY_LEN: usize = 128;
+
+/// Drops metadata for tabs that are neither open nor in closed-tab history. Runs inside
+/// the session save transaction, after both tab tables are written.
+pub(super) fn delete_orphans(tx: &rusqlite::Transaction) -> Result<usize> {
+    Ok(tx.execute(
+        "DELETE FROM tab_meta WHERE tab_id NOT IN (
+            SELECT id FROM tabs UNION SELECT id FROM closed_tabs
+         )",
+        [],
+    )?)
+}
+
+fn parse_value(tab_id: &str, key: &str, raw: &str) -> Option<Value> {
+    serde_json::from_str(raw)
+        .map_err(|e| log::warn!("Ignoring unreadable tab meta {}/{}: {}", tab_id, key, e))
+        .ok()
+}
+
+impl Database {
+    pub fn get_tab_meta(&self, tab_id: &str) -> Result<TabMeta> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare("SELECT key, value FROM tab_meta WHERE tab_id = ?1")?;
+        let rows = stmt
+            .query_map(params![tab_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(key, raw)| parse_value(tab_id, &key, &raw).map(|v| (key, v)))
+            .collect())
+    }
+
+    pub fn load_all_tab_meta(&self) -> Result<HashMap<String, TabMeta>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare("SELECT tab_id, key, value FROM tab_meta")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut meta: HashMap<String, TabMeta> = HashMap::new();
+        for (tab_id, key, raw) in rows {
+            if let Some(value) = parse_value(&tab_id, &key, &raw) {
+                meta.entry(tab_id).or_default().insert(key, value);
+            }
+        }
+        Ok(meta)
+    }
+
+    /// Stores `value` under `key` for the tab; `null` removes the key.
+    pub fn set_tab_meta(&self, tab_id: &str, key: &str, value: &Value) -> Result<()> {
+        self.ensure_writable()?;
+        let key = key.trim();
+        if key.is_empty() || key.len() > MAX_KEY_LEN {
+            return Err(anyhow!("Metadata key must be 1-{} characters", MAX_KEY_LEN));
+        }
+
+        let conn = self.pool.get()?;
+        if value.is_null() {
+            conn.execute(
+                "DELETE FROM tab_meta WHERE tab_id = ?1 AND key = ?2",
+                params![tab_id, key],
+            )?;
+        } else {
+            conn.execute(
+                "INSERT INTO tab_meta (tab_id, key, value) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(tab_id, key) DO UPDATE SET value = excluded.value",
+                params![tab_id, key, serde_json::to_string(value)?],
+            )?;
+        }
+        Ok(())
+    }
+}
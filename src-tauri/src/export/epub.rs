@@ -0,0 +1,475 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::diagrams::DiagramSettings;
+use crate::markdown::front_matter;
+use crate::markdown::renderer::{self, MarkdownOptions};
+use crate::markdown::sections;
+use crate::utils::escape_html;
+use anyhow::{Result, anyhow};
+use comrak::nodes::NodeValue;
+use comrak::{Arena, parse_document};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const DEFAULT_SPLIT_LEVEL: u8 = 2;
+
+/// Reading-system friendly subset of the export CSS; EPUB readers supply the colours
+const EPUB_CSS: &str = r#"body { font-family: serif; line-height: 1.5; }
+h1, h2, h3, h4, h5, h6 { font-family: sans-serif; line-height: 1.2; }
+h1, h2 { page-break-before: auto; }
+pre, code { font-family: monospace; }
+pre { white-space: pre-wrap; padding: 0.5em; border: 1px solid #ccc; }
+blockquote { margin: 1em 1.5em; font-style: italic; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #999; padding: 0.3em 0.5em; }
+img { max-width: 100%; }
+figure.diagram { margin: 1em 0; text-align: center; }
+nav#toc ol { list-style-type: none; }
+"#;
+
+/// Single-line link reference and footnote definitions; copied into every chapter so
+/// references resolve wherever the definition lives
+pub(super) static REFERENCE_DEF_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}\[[^\]\n]+\]:").expect("Invalid REFERENCE_DEF_RE"));
+
+static RENDERED_IMG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(<img\b[^>]*?\bsrc=)"([^"]*)""#).expect("Invalid RENDERED_IMG_RE")
+});
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EpubOptions {
+    /// Falls back to the front matter `title`
+    pub title: String,
+    /// Falls back to the front matter `author`
+    pub author: Option<String>,
+    /// BCP 47 tag; falls back to the front matter `lang`, then English
+    pub language: Option<String>,
+    /// Path of the document; relative image paths resolve against its folder
+    pub base_path: Option<String>,
+    pub flavor: Option<String>,
+    /// Headings at this level or above start a new chapter; defaults to H2
+    pub split_level: Option<u8>,
+    /// Filled in from settings, not by the caller
+    #[serde(skip)]
+    pub diagrams: Option<DiagramSettings>,
+}
+
+struct Chapter {
+    title: String,
+    /// Heading level that opened the chapter; 0 for text before the first heading
+    level: u8,
+    markdown: String,
+}
+
+struct Image {
+    href: String,
+    media_type: &'static str,
+    bytes: Vec<u8>,
+}
+
+/// Builds an EPUB 3 book from one markdown document: one chapter per H1/H2 section, a
+/// navigation document and NCX table of contents, and local images embedded.
+pub fn build_epub(content: &str, options: &EpubOptions) -> Result<Vec<u8>> {
+    let flavor = MarkdownFlavor::from_option_str(options.flavor.clone());
+    let meta = front_matter::parse(content);
+    let field = |key: &str| {
+        meta.as_ref()
+            .and_then(|m| front_matter::string_field(m, key))
+    };
+    let title = Some(options.title.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .or_else(|| field("title"))
+        .unwrap_or_else(|| "Untitled".to_string());
+    let author = options
+        .author
+        .clone()
+        .filter(|a| !a.is_empty())
+        .or_else(|| field("author"));
+    let language = options
+        .language
+        .clone()
+        .filter(|l| !l.is_empty())
+        .or_else(|| field("lang"))
+        .unwrap_or_else(|| "en".to_string());
+
+    let body = front_matter::split(content).map_or(content, |fm| fm.body);
+    let split_level = options
+        .split_level
+        .unwrap_or(DEFAULT_SPLIT_LEVEL)
+        .clamp(1, 6);
+    let chapters = split_chapters(body, flavor, split_level, &title);
+    if chapters.is_empty() {
+        return Err(anyhow!("Document is empty"));
+    }
+
+    let base_dir = options
+        .base_path
+        .as_deref()
+        .and_then(|p| Path::new(p).parent())
+        .map(Path::to_path_buf);
+    let render_options = MarkdownOptions {
+        flavor,
+        diagrams: options.diagrams.clone(),
+        ..Default::default()
+    };
+    let mut images: HashMap<PathBuf, Image> = HashMap::new();
+    let mut pages = Vec::with_capacity(chapters.len());
+    for chapter in &chapters {
+        let fragment = renderer::render_fragment(&chapter.markdown, &render_options)?;
+        let html = embed_images(&fragment.html, base_dir.as_deref(), &mut images);
+        pages.push(chapter_xhtml(&chapter.title, &language, &to_xhtml(&html)));
+    }
+
+    let book_id = format!("urn:uuid:{}", uuid::Uuid::new_v4());
+    let mut images: Vec<Image> = images.into_values().collect();
+    images.sort_by(|a, b| a.href.cmp(&b.href));
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    // The mimetype entry must come first and be stored uncompressed
+    zip.start_file(
+        "mimetype",
+        SimpleFileOptions::default().compression_method(CompressionMethod::Stored),
+    )?;
+    zip.write_all(b"application/epub+zip")?;
+
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut add = |name: &str, bytes: &[u8]| -> Result<()> {
+        zip.start_file(name, deflated)?;
+        zip.write_all(bytes)?;
+        Ok(())
+    };
+    add("META-INF/container.xml", CONTAINER_XML.as_bytes())?;
+    add(
+        "OEBPS/content.opf",
+        package_opf(
+            &book_id,
+            &title,
+            author.as_deref(),
+            &language,
+            chapters.len(),
+            &images,
+        )
+        .as_bytes(),
+    )?;
+    add(
+        "OEBPS/nav.xhtml",
+        nav_xhtml(&title, &language, &chapters).as_bytes(),
+    )?;
+    add(
+        "OEBPS/toc.ncx",
+        toc_ncx(&book_id, &title, &chapters).as_bytes(),
+    )?;
+    add("OEBPS/style.css", EPUB_CSS.as_bytes())?;
+    for (index, page) in pages.iter().enumerate() {
+        add(&format!("OEBPS/{}", chapter_href(index)), page.as_bytes())?;
+    }
+    for image in &images {
+        add(&format!("OEBPS/{}", image.href), &image.bytes)?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Splits at top-level headings of `split_level` or above. Text before the first such
+/// heading becomes an untitled opening chapter named after the book.
+fn split_chapters(
+    body: &str,
+    flavor: MarkdownFlavor,
+    split_level: u8,
+    book_title: &str,
+) -> Vec<Chapter> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, body, &flavor.to_comrak_options());
+    // (0-based start line, level, title)
+    let mut starts: Vec<(usize, u8, String)> = root
+        .children()
+        .filter_map(|node| match &node.data.borrow().value {
+            NodeValue::Heading(heading) if heading.level <= split_level => Some((
+                node.data.borrow().sourcepos.start.line.saturating_sub(1),
+                heading.level,
+                sections::plain_text(node),
+            )),
+            _ => None,
+        })
+        .collect();
+
+    let lines: Vec<&str> = body.lines().collect();
+    if starts.first().is_none_or(|&(line, _, _)| line > 0) {
+        starts.insert(0, (0, 0, book_title.to_string()));
+    }
+
+    let definitions: Vec<(usize, &str)> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| REFERENCE_DEF_RE.is_match(line))
+        .map(|(index, line)| (index, *line))
+        .collect();
+
+    let mut chapters = Vec::new();
+    for (i, (start, level, title)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map_or(lines.len(), |next| next.0);
+        let mut markdown = lines[*start..end].join("\n");
+        if markdown.trim().is_empty() {
+            continue;
+        }
+        let borrowed: Vec<&str> = definitions
+            .iter()
+            .filter(|(index, _)| !(*start..end).contains(index))
+            .map(|(_, line)| *line)
+            .collect();
+        if !borrowed.is_empty() {
+            markdown.push_str("\n\n");
+            markdown.push_str(&borrowed.join("\n"));
+        }
+        chapters.push(Chapter {
+            title: if title.is_empty() {
+                format!("Chapter {}", chapters.len() + 1)
+            } else {
+                title.clone()
+            },
+            level: *level,
+            markdown,
+        });
+    }
+    chapters
+}
+
+fn media_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        _ => return None,
+    })
+}
+
+/// Copies local images into the book and points their `src` at the copy. Remote images
+/// and files that can't be read are left as they are.
+fn embed_images(
+    html: &str,
+    base_dir: Option<&Path>,
+    images: &mut HashMap<PathBuf, Image>,
+) -> String {
+    RENDERED_IMG_RE
+        .replace_all(html, |caps: &regex::Captures| {
+            let src = caps[2].replace("&amp;", "&");
+            let embedded = renderer::resolve_local_image(&src, base_dir.unwrap_or(Path::new("")))
+                .and_then(|path| {
+                    if let Some(image) = images.get(&path) {
+                        return Some(image.href.clone());
+                    }
+                    let media_type = media_type(&path)?;
+                    let bytes = std::fs::read(&path)
+                        .map_err(|e| log::warn!("EPUB: skipping image {:?}: {}", path, e))
+                        .ok()?;
+                    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+                    let href = format!("images/image-{:03}.{}", images.len() + 1, ext);
+                    images.insert(
+                        path,
+                        Image {
+                            href: href.clone(),
+                            media_type,
+                            bytes,
+                        },
+                    );
+                    Some(href)
+                });
+            match embedded {
+                Some(href) => format!("{}\"{}\"", &caps[1], href),
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// The renderer's HTML is already close to XHTML; the footnotes section is the one
+/// place it writes a bare attribute.
+fn to_xhtml(html: &str) -> String {
+    html.replace(" data-footnotes>", " data-footnotes=\"\">")
+}
+
+fn chapter_href(index: usize) -> String {
+    format!("chapter-{:03}.xhtml", index + 1)
+}
+
+fn chapter_xhtml(title: &str, language: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" lang="{lang}" xml:lang="{lang}">
+<head>
+    <meta charset="UTF-8" />
+    <title>{title}</title>
+    <link rel="stylesheet" type="text/css" href="style.css" />
+</head>
+<body>
+{body}
+</body>
+</html>
+"#,
+        lang = escape_html(language),
+        title = escape_html(title),
+        body = body
+    )
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+    <rootfiles>
+        <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml" />
+    </rootfiles>
+</container>
+"#;
+
+fn package_opf(
+    book_id: &str,
+    title: &str,
+    author: Option<&str>,
+    language: &str,
+    chapter_count: usize,
+    images: &[Image],
+) -> String {
+    let creator = author
+        .map(|a| format!("\n        <dc:creator>{}</dc:creator>", escape_html(a)))
+        .unwrap_or_default();
+    let modified = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+
+    let mut manifest = vec![
+        r#"<item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav" />"#
+            .to_string(),
+        r#"<item id="ncx" href="toc.ncx" media-type="application/x-dtbncx+xml" />"#.to_string(),
+        r#"<item id="css" href="style.css" media-type="text/css" />"#.to_string(),
+    ];
+    let mut spine = Vec::with_capacity(chapter_count);
+    for index in 0..chapter_count {
+        manifest.push(format!(
+            r#"<item id="chapter-{n}" href="{href}" media-type="application/xhtml+xml" />"#,
+            n = index + 1,
+            href = chapter_href(index)
+        ));
+        spine.push(format!(r#"<itemref idref="chapter-{}" />"#, index + 1));
+    }
+    for (index, image) in images.iter().enumerate() {
+        manifest.push(format!(
+            r#"<item id="image-{}" href="{}" media-type="{}" />"#,
+            index + 1,
+            image.href,
+            image.media_type
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id">
+    <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+        <dc:identifier id="book-id">{id}</dc:identifier>
+        <dc:title>{title}</dc:title>
+        <dc:language>{lang}</dc:language>{creator}
+        <meta property="dcterms:modified">{modified}</meta>
+    </metadata>
+    <manifest>
+        {manifest}
+    </manifest>
+    <spine toc="ncx">
+        {spine}
+    </spine>
+</package>
+"#,
+        id = book_id,
+        title = escape_html(title),
+        lang = escape_html(language),
+        creator = creator,
+        modified = modified,
+        manifest = manifest.join("\n        "),
+        spine = spine.join("\n        ")
+    )
+}
+
+/// Chapters opened by a deeper heading nest under the chapter before them
+fn nav_xhtml(title: &str, language: &str, chapters: &[Chapter]) -> String {
+    let mut list = String::from("<ol>");
+    // Levels of the list items still open, outermost first
+    let mut open: Vec<u8> = Vec::new();
+    for (index, chapter) in chapters.iter().enumerate() {
+        let level = chapter.level.max(1);
+        let mut closed_sibling = false;
+        while let Some(&top) = open.last()
+            && top >= level
+        {
+            open.pop();
+            list.push_str("</li>");
+            if open.last().is_some_and(|&parent| parent >= level) {
+                list.push_str("</ol>");
+            }
+            closed_sibling = true;
+        }
+        if !closed_sibling && !open.is_empty() {
+            list.push_str("<ol>");
+        }
+        list.push_str(&format!(
+            r#"<li><a href="{}">{}</a>"#,
+            chapter_href(index),
+            escape_html(&chapter.title)
+        ));
+        open.push(level);
+    }
+    while open.pop().is_some() {
+        list.push_str("</li>");
+        if !open.is_empty() {
+            list.push_str("</ol>");
+        }
+    }
+    list.push_str("</ol>");
+
+    let body = format!(
+        r#"<nav epub:type="toc" id="toc">
+    <h1>{}</h1>
+    {}
+</nav>"#,
+        escape_html(title),
+        list
+    );
+    chapter_xhtml(title, language, &body)
+}
+
+/// EPUB 2 table of contents, still read by older devices
+fn toc_ncx(book_id: &str, title: &str, chapters: &[Chapter]) -> String {
+    let points = chapters
+        .iter()
+        .enumerate()
+        .map(|(index, chapter)| {
+            format!(
+                r#"<navPoint id="nav-{n}" playOrder="{n}"><navLabel><text>{}</text></navLabel><content src="{}" /></navPoint>"#,
+                escape_html(&chapter.title),
+                chapter_href(index),
+                n = index + 1
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n        ");
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/" version="2005-1">
+    <head>
+        <meta name="dtb:uid" content="{}" />
+    </head>
+    <docTitle><text>{}</text></docTitle>
+    <navMap>
+        {}
+    </navMap>
+</ncx>
+"#,
+        book_id,
+        escape_html(title),
+        points
+    )
+}
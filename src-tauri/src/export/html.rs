@@ -0,0 +1,267 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::diagrams::DiagramSettings;
+use crate::markdown::renderer::{self, MarkdownOptions};
+use crate::markdown::security::SanitizationReport;
+use crate::utils::escape_html;
+use anyhow::Result;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+static CSS_RULE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([^{}]+)\{([^}]*)\}").expect("Invalid CSS_RULE_RE"));
+
+static CSS_VARIABLE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(--[\w-]+)\s*:\s*([^;}]+)").expect("Invalid CSS_VARIABLE_RE"));
+
+/// `var(--name)` or `var(--name, fallback)`
+static CSS_VAR_USE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"var\(\s*(--[\w-]+)\s*(?:,\s*([^)]+))?\)").expect("Invalid CSS_VAR_USE_RE")
+});
+
+static HTML_TAG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"<(/?)([a-zA-Z][a-zA-Z0-9]*)([^>]*)>").expect("Invalid HTML_TAG_RE")
+});
+
+const DARK_THEME_CSS: &str = include_str!("../../templates/default-dark.css");
+const LIGHT_THEME_CSS: &str = include_str!("../../templates/default-light.css");
+
+/// Layout rules shared by every exported document; colours come from theme variables
+pub const BASE_CSS: &str = r#"body {
+    margin: 0;
+    padding: 2rem;
+    background-color: var(--color-bg-main, #ffffff);
+    color: var(--preview-fg-body, #374151);
+    font-family: system-ui, sans-serif;
+    line-height: 1.6;
+}
+.prose { max-width: 800px; margin: 0 auto; }
+h1, h2, h3, h4, h5, h6 { color: var(--preview-fg-heading); font-weight: bold; margin-top: 1.5em; margin-bottom: 0.5em; }
+h1, h2 { border-bottom: 1px solid currentColor; padding-bottom: 0.3em; }
+a { color: var(--preview-fg-link); text-decoration: underline; }
+code { color: var(--preview-fg-code); background-color: var(--preview-bg-code); padding: 0.2em 0.4em; border-radius: 4px; font-family: monospace; }
+pre { background-color: var(--preview-bg-pre); color: var(--preview-fg-pre); padding: 1em; border-radius: 4px; overflow: auto; margin: 1em 0; }
+pre code { background: transparent; padding: 0; color: inherit; }
+blockquote { color: var(--preview-fg-quote); background-color: var(--preview-bg-quote); border-left: 4px solid var(--preview-border-quote); padding: 0.5em 1em; margin: 1em 0; font-style: italic; }
+table { width: 100%; border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid currentColor; padding: 0.5em; text-align: left; }
+img { max-width: 100%; height: auto; }
+figure.diagram { margin: 1em 0; text-align: center; }
+hr { border: 0; border-top: 1px solid currentColor; margin: 2em 0; }
+"#;
+
+/// Per-tab sections of a combined export start on a new printed page
+pub const TAB_SECTIONS_CSS: &str = r#"section.tab-section + section.tab-section { break-before: page; }
+nav.toc { break-after: page; }
+nav.toc ol { padding-left: 1.5em; }
+"#;
+
+/// Placeholders: `{{title}}`, `{{description}}`, `{{css}}`, `{{content}}`
+pub const DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <meta name="description" content="{{description}}">
+    <title>{{title}}</title>
+    <style>
+{{css}}
+    </style>
+</head>
+<body>
+    <div class="prose">
+{{content}}
+    </div>
+</body>
+</html>
+"#;
+
+/// CSS for the themes bundled with the app; custom themes live in the Themes folder
+pub fn builtin_theme_css(theme: &str) -> Option<&'static str> {
+    match theme {
+        "dark" | "default-dark" => Some(DARK_THEME_CSS),
+        "light" | "default-light" => Some(LIGHT_THEME_CSS),
+        _ => None,
+    }
+}
+
+pub fn render_body(
+    content: &str,
+    flavor: MarkdownFlavor,
+    diagrams: Option<DiagramSettings>,
+) -> Result<String> {
+    Ok(render_body_with_report(content, flavor, diagrams)?.0)
+}
+
+/// Renders like `render_body` and also reports what was left out of the HTML.
+pub fn render_body_with_report(
+    content: &str,
+    flavor: MarkdownFlavor,
+    diagrams: Option<DiagramSettings>,
+) -> Result<(String, SanitizationReport)> {
+    let options = MarkdownOptions {
+        flavor,
+        diagrams,
+        ..Default::default()
+    };
+    let result = renderer::render_markdown(content, options)?;
+    Ok((result.html, result.sanitization))
+}
+
+/// Fills a template. Content is substituted last so placeholder-like text inside the
+/// document body is never expanded.
+pub fn build_document(
+    template: &str,
+    title: &str,
+    description: &str,
+    css: &str,
+    body: &str,
+) -> String {
+    template
+        .replace("{{title}}", &escape_html(title))
+        .replace("{{description}}", &escape_html(description))
+        .replace("{{css}}", css)
+        .replace("{{content}}", body)
+}
+
+/// Joins rendered documents into one body: a linked table of contents (optional) followed
+/// by one `<section>` per document, headed by its title.
+pub fn build_tab_sections(sections: &[(String, String)], toc: bool) -> String {
+    let mut body = String::new();
+
+    if toc && sections.len() > 1 {
+        body.push_str("<nav class=\"toc\">\n<h1>Contents</h1>\n<ol>\n");
+        for (i, (title, _)) in sections.iter().enumerate() {
+            body.push_str(&format!(
+                "<li><a href=\"#tab-{}\">{}</a></li>\n",
+                i + 1,
+                escape_html(title)
+            ));
+        }
+        body.push_str("</ol>\n</nav>\n");
+    }
+
+    for (i, (title, html)) in sections.iter().enumerate() {
+        body.push_str(&format!(
+            "<section class=\"tab-section\" id=\"tab-{}\">\n<h1 class=\"tab-title\">{}</h1>\n{}\n</section>\n",
+            i + 1,
+            escape_html(title),
+            html
+        ));
+    }
+
+    body
+}
+
+/// The rendered body with `BASE_CSS` applied as `style` attributes, for pasting into mail
+/// clients that drop `<style>` blocks. Only element selectors carry over, and `var()`
+/// values are resolved against the theme; declarations that can't be resolved are left
+/// out. Page margins are dropped so the fragment sits flush in a message.
+pub fn inline_styles(body: &str, theme_css: &str) -> String {
+    let mut variables = HashMap::new();
+    for caps in CSS_VARIABLE_RE.captures_iter(theme_css) {
+        variables.insert(caps[1].to_string(), caps[2].trim().to_string());
+    }
+
+    let mut styles: HashMap<String, String> = HashMap::new();
+    for caps in CSS_RULE_RE.captures_iter(BASE_CSS) {
+        let declarations: Vec<String> = caps[2]
+            .split(';')
+            .filter_map(|d| resolve_declaration(d, &variables))
+            .collect();
+        if declarations.is_empty() {
+            continue;
+        }
+        for selector in caps[1].split(',').map(str::trim) {
+            if selector.chars().all(|c| c.is_ascii_alphanumeric()) {
+                let style = styles.entry(selector.to_string()).or_default();
+                for declaration in &declarations {
+                    style.push_str(declaration);
+                    style.push_str("; ");
+                }
+            }
+        }
+    }
+
+    // `pre code` resets the inline-code look; with no descendant selectors, code inside
+    // a pre block simply gets no style of its own
+    let mut pre_depth = 0usize;
+    let body = HTML_TAG_RE.replace_all(body, |caps: &Captures| {
+        let (closing, tag, rest) = (&caps[1] == "/", caps[2].to_ascii_lowercase(), &caps[3]);
+        if tag == "pre" {
+            pre_depth = if closing {
+                pre_depth.saturating_sub(1)
+            } else {
+                pre_depth + 1
+            };
+        }
+        match styles.get(&tag) {
+            Some(style) if !closing && !(tag == "code" && pre_depth > 0) => {
+                with_style(&caps[2], rest, style.trim_end())
+            },
+            _ => caps[0].to_string(),
+        }
+    });
+
+    let wrapper: String = styles
+        .get("body")
+        .map(|style| {
+            style
+                .split("; ")
+                .filter(|d| !d.starts_with("margin") && !d.starts_with("padding"))
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+        .unwrap_or_default();
+    format!("<div style=\"{}\">\n{}</div>\n", wrapper.trim(), body)
+}
+
+/// A declaration with its `var()` references replaced, or `None` when one has no value
+fn resolve_declaration(declaration: &str, variables: &HashMap<String, String>) -> Option<String> {
+    let declaration = declaration.trim();
+    if declaration.is_empty() {
+        return None;
+    }
+    let mut resolved = true;
+    let value = CSS_VAR_USE_RE.replace_all(declaration, |caps: &Captures| {
+        match variables
+            .get(&caps[1])
+            .map(String::as_str)
+            .or(caps.get(2).map(|m| m.as_str().trim()))
+        {
+            Some(value) => value.to_string(),
+            None => {
+                resolved = false;
+                String::new()
+            },
+        }
+    });
+    // Attribute values are double-quoted
+    resolved.then(|| value.replace('"', "'"))
+}
+
+/// An opening tag with `style` prepended to any style it already has
+fn with_style(tag: &str, attributes: &str, style: &str) -> String {
+    match attributes.find("style=\"") {
+        Some(index) => {
+            let split = index + "style=\"".len();
+            format!(
+                "<{}{}{} {}>",
+                tag,
+                &attributes[..split],
+                style,
+                &attributes[split..]
+            )
+        },
+        None => {
+            let (attributes, self_closing) = match attributes.strip_suffix('/') {
+                Some(attributes) => (attributes.trim_end(), " /"),
+                None => (attributes, ""),
+            };
+            format!(
+                "<{}{} style=\"{}\"{}>",
+                tag, attributes, style, self_closing
+            )
+        },
+    }
+}
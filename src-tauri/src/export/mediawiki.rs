@@ -0,0 +1,282 @@
+use crate::markdown::config::MarkdownFlavor;
+use comrak::nodes::{AstNode, ListType, NodeValue, TableAlignment};
+use comrak::{Arena, parse_document};
+use std::collections::{HashMap, HashSet};
+
+/// Sequences MediaWiki would read as markup in plain text
+const WIKI_SPECIALS: [&str; 10] = [
+    "[[", "]]", "{{", "}}", "''", "~~~", "__", "<", "[http", "----",
+];
+
+struct Writer {
+    /// Footnote name to its rendered text; MediaWiki keeps notes inline as `<ref>`
+    footnotes: HashMap<String, String>,
+    used_footnotes: HashSet<String>,
+    in_table: bool,
+}
+
+/// Converts markdown to MediaWiki markup. Footnotes become `<ref>` tags with a
+/// `<references />` list at the end, and front matter is dropped.
+pub fn to_mediawiki(content: &str, flavor: MarkdownFlavor) -> String {
+    let arena = Arena::new();
+    let root = parse_document(&arena, content, &flavor.to_comrak_options());
+
+    let mut writer = Writer {
+        footnotes: HashMap::new(),
+        used_footnotes: HashSet::new(),
+        in_table: false,
+    };
+    for node in root.descendants() {
+        if let NodeValue::FootnoteDefinition(def) = &node.data.borrow().value {
+            let text = node
+                .children()
+                .map(|child| writer.inlines(child))
+                .collect::<Vec<_>>()
+                .join(" ");
+            writer.footnotes.insert(def.name.to_string(), text);
+        }
+    }
+
+    let mut out = writer.blocks(root);
+    if !writer.used_footnotes.is_empty() {
+        out.push_str("\n\n== References ==\n<references />");
+    }
+    out.push('\n');
+    out
+}
+
+impl Writer {
+    fn blocks<'a>(&mut self, parent: &'a AstNode<'a>) -> String {
+        parent
+            .children()
+            .filter_map(|node| Some(self.block(node)).filter(|b| !b.is_empty()))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn block<'a>(&mut self, node: &'a AstNode<'a>) -> String {
+        match &node.data.borrow().value {
+            NodeValue::Heading(heading) => {
+                let marks = "=".repeat(heading.level as usize);
+                format!("{} {} {}", marks, self.inlines(node), marks)
+            },
+            NodeValue::Paragraph => self.inlines(node),
+            NodeValue::List(_) => self.list(node, ""),
+            NodeValue::CodeBlock(code) => {
+                let lang = code.info.split_whitespace().next().unwrap_or("");
+                let literal = code.literal.trim_end_matches('\n');
+                if lang.is_empty() {
+                    format!("<pre>{}</pre>", escape_pre(literal))
+                } else {
+                    format!(
+                        "<syntaxhighlight lang=\"{}\">\n{}\n</syntaxhighlight>",
+                        lang, literal
+                    )
+                }
+            },
+            NodeValue::HtmlBlock(html) => html.literal.trim_end().to_string(),
+            NodeValue::ThematicBreak => "----".to_string(),
+            NodeValue::BlockQuote | NodeValue::MultilineBlockQuote(_) => {
+                format!("<blockquote>\n{}\n</blockquote>", self.blocks(node))
+            },
+            NodeValue::Alert(alert) => {
+                let title = alert
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| format!("{:?}", alert.alert_type));
+                format!(
+                    "<blockquote>\n'''{}'''\n\n{}\n</blockquote>",
+                    title,
+                    self.blocks(node)
+                )
+            },
+            NodeValue::Table(table) => {
+                let alignments = table.alignments.clone();
+                self.table(node, &alignments)
+            },
+            NodeValue::Math(math) => format!("<math display=\"block\">{}</math>", math.literal),
+            NodeValue::DescriptionList => self.blocks(node),
+            NodeValue::DescriptionItem(_) => node
+                .children()
+                .map(|part| {
+                    let is_term = matches!(part.data.borrow().value, NodeValue::DescriptionTerm);
+                    let marker = if is_term { ';' } else { ':' };
+                    let text = part
+                        .children()
+                        .map(|c| self.inlines(c))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    format!("{} {}", marker, text)
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            NodeValue::FrontMatter(_) | NodeValue::FootnoteDefinition(_) => String::new(),
+            _ => self.blocks(node),
+        }
+    }
+
+    /// One line per item, each prefixed with the markers of every enclosing list. Later
+    /// blocks of an item continue it with `:`.
+    fn list<'a>(&mut self, list: &'a AstNode<'a>, prefix: &str) -> String {
+        let ordered = matches!(
+            &list.data.borrow().value,
+            NodeValue::List(data) if data.list_type == ListType::Ordered
+        );
+        let prefix = format!("{}{}", prefix, if ordered { '#' } else { '*' });
+        let mut lines = Vec::new();
+
+        for item in list.children() {
+            // Task list items replace the item node's value
+            let mut lead = match &item.data.borrow().value {
+                NodeValue::TaskItem(task) if task.symbol.is_some() => format!("{} ☑ ", prefix),
+                NodeValue::TaskItem(_) => format!("{} ☐ ", prefix),
+                _ => format!("{} ", prefix),
+            };
+            for child in item.children() {
+                if matches!(child.data.borrow().value, NodeValue::List(_)) {
+                    lines.push(self.list(child, &prefix));
+                    continue;
+                }
+                lines.push(format!("{}{}", lead, self.block(child)));
+                lead = format!("{}: ", prefix);
+            }
+            if item.first_child().is_none() {
+                lines.push(lead.trim_end().to_string());
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn table<'a>(&mut self, table: &'a AstNode<'a>, alignments: &[TableAlignment]) -> String {
+        let mut out = vec!["{| class=\"wikitable\"".to_string()];
+        self.in_table = true;
+        for (row_index, row) in table.children().enumerate() {
+            let header = matches!(row.data.borrow().value, NodeValue::TableRow(true));
+            if row_index > 0 {
+                out.push("|-".to_string());
+            }
+            for (index, cell) in row.children().enumerate() {
+                let style = match alignments.get(index).unwrap_or(&TableAlignment::None) {
+                    TableAlignment::Left => "style=\"text-align:left\" | ",
+                    TableAlignment::Center => "style=\"text-align:center\" | ",
+                    TableAlignment::Right => "style=\"text-align:right\" | ",
+                    TableAlignment::None => "",
+                };
+                let marker = if header { '!' } else { '|' };
+                out.push(format!("{} {}{}", marker, style, self.inlines(cell)));
+            }
+        }
+        self.in_table = false;
+        out.push("|}".to_string());
+        out.join("\n")
+    }
+
+    fn inlines<'a>(&mut self, parent: &'a AstNode<'a>) -> String {
+        let mut out = String::new();
+        for node in parent.children() {
+            self.inline(node, &mut out);
+        }
+        out
+    }
+
+    fn inline<'a>(&mut self, node: &'a AstNode<'a>, out: &mut String) {
+        let value = node.data.borrow().value.clone();
+        match value {
+            NodeValue::Text(text) => out.push_str(&self.escape(&text)),
+            NodeValue::SoftBreak => out.push(' '),
+            NodeValue::LineBreak => out.push_str("<br />"),
+            NodeValue::Code(code) => {
+                out.push_str(&format!("<code><nowiki>{}</nowiki></code>", code.literal))
+            },
+            NodeValue::Emph => out.push_str(&format!("''{}''", self.inlines(node))),
+            NodeValue::Strong => out.push_str(&format!("'''{}'''", self.inlines(node))),
+            NodeValue::Strikethrough => out.push_str(&format!("<s>{}</s>", self.inlines(node))),
+            NodeValue::Underline => out.push_str(&format!("<u>{}</u>", self.inlines(node))),
+            NodeValue::Superscript => out.push_str(&format!("<sup>{}</sup>", self.inlines(node))),
+            NodeValue::Subscript => out.push_str(&format!("<sub>{}</sub>", self.inlines(node))),
+            NodeValue::HtmlInline(html) => out.push_str(&html),
+            NodeValue::Math(math) => out.push_str(&format!("<math>{}</math>", math.literal)),
+            NodeValue::Link(link) => {
+                let text = self.inlines(node);
+                let url = link.url.to_string();
+                if is_external(&url) {
+                    if text == url {
+                        out.push_str(&url);
+                    } else {
+                        out.push_str(&format!("[{} {}]", url, text));
+                    }
+                } else {
+                    out.push_str(&internal_link(&url, &text));
+                }
+            },
+            NodeValue::WikiLink(link) => {
+                out.push_str(&internal_link(&link.url, &self.inlines(node)))
+            },
+            NodeValue::Image(link) => {
+                let alt = self.inlines(node);
+                let url = link.url.to_string();
+                if is_external(&url) {
+                    // Remote images only display when the wiki allows external images
+                    out.push_str(&url);
+                } else {
+                    let name = url.rsplit(['/', '\\']).next().unwrap_or(&url);
+                    out.push_str(&format!("[[File:{}|{}]]", name, alt));
+                }
+            },
+            NodeValue::FootnoteReference(reference) => {
+                let name = reference.name.to_string();
+                if self.used_footnotes.insert(name.clone()) {
+                    let text = self.footnotes.get(&name).cloned().unwrap_or_default();
+                    out.push_str(&format!("<ref name=\"{}\">{}</ref>", name, text));
+                } else {
+                    out.push_str(&format!("<ref name=\"{}\" />", name));
+                }
+            },
+            _ => {
+                for child in node.children() {
+                    self.inline(child, out);
+                }
+            },
+        }
+    }
+
+    fn escape(&self, text: &str) -> String {
+        let special =
+            WIKI_SPECIALS.iter().any(|s| text.contains(s)) || (self.in_table && text.contains('|'));
+        if special {
+            format!("<nowiki>{}</nowiki>", text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+fn is_external(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    ["http://", "https://", "ftp://", "mailto:"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+}
+
+/// `notes/setup.md#install` becomes `[[notes/setup#install|text]]`
+fn internal_link(url: &str, text: &str) -> String {
+    let (path, anchor) = url.split_once('#').unwrap_or((url, ""));
+    let page = path
+        .strip_suffix(".md")
+        .or_else(|| path.strip_suffix(".markdown"))
+        .unwrap_or(path);
+    let target = if anchor.is_empty() {
+        page.to_string()
+    } else {
+        format!("{}#{}", page, anchor)
+    };
+    if text.is_empty() || text == target {
+        format!("[[{}]]", target)
+    } else {
+        format!("[[{}|{}]]", target, text)
+    }
+}
+
+fn escape_pre(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;")
+}
@@ -0,0 +1,20 @@
+pub mod epub;
+pub mod html;
+pub mod mediawiki;
+pub mod pdf;
+pub mod presets;
+pub mod rst;
+pub mod slides;
+
+use crate::markdown::config::MarkdownFlavor;
+use presets::ExportFormat;
+
+/// Converts markdown to one of the text markup formats; `None` for HTML and PDF, which
+/// go through the renderer instead.
+pub fn to_markup(format: ExportFormat, content: &str, flavor: MarkdownFlavor) -> Option<String> {
+    match format {
+        ExportFormat::MediaWiki => Some(mediawiki::to_mediawiki(content, flavor)),
+        ExportFormat::Rst => Some(rst::to_rst(content, flavor)),
+        ExportFormat::Html | ExportFormat::Pdf => None,
+    }
+}
@@ -0,0 +1,272 @@
+use super::fonts::Font;
+use super::images;
+use super::layout::Layout;
+use super::text::{Line, Run, wrap};
+use super::writer;
+use super::{
+    BODY_SIZE, CAPTION_SIZE, HEADING_SIZES, LINE_SPACING, MARGIN, MUTED_COLOR, POINTS_PER_PIXEL,
+    Style, is_page_break,
+};
+use crate::markdown::diagrams::{self, DiagramFormat, DiagramKind};
+use crate::markdown::renderer::resolve_local_image;
+use comrak::nodes::{AlertType, AstNode, NodeValue};
+
+impl Layout<'_> {
+    pub fn number_footnotes<'a>(&mut self, root: &'a AstNode<'a>) {
+        for node in root.descendants() {
+            if let NodeValue::FootnoteReference(reference) = &node.data.borrow().value {
+                let next = self.footnotes.len() + 1;
+                self.footnotes.entry(reference.name.clone()).or_insert(next);
+            }
+        }
+    }
+
+    pub fn blocks<'a>(&mut self, parent: &'a AstNode<'a>) {
+        for node in parent.children() {
+            self.block(node);
+        }
+    }
+
+    fn block<'a>(&mut self, node: &'a AstNode<'a>) {
+        let value = node.data.borrow().value.clone();
+        match value {
+            NodeValue::Paragraph => {
+                let runs = self.inlines(node, &Style::body());
+                self.paragraph(runs);
+                let gap = self.paragraph_gap();
+                self.space(gap);
+            },
+            NodeValue::Heading(heading) => {
+                let size = HEADING_SIZES[(heading.level as usize).clamp(1, 6) - 1];
+                let style = Style {
+                    font: Font::Bold,
+                    size,
+                    ..Style::body()
+                };
+                self.space(size * 0.5);
+                // Keep the heading with at least two lines of what follows
+                self.ensure(size * LINE_SPACING + BODY_SIZE * LINE_SPACING * 2.0);
+                let runs = self.inlines(node, &style);
+                let lines = wrap(&self.fonts, &runs, self.available_width(), size);
+                let x = self.left();
+                for line in &lines {
+                    self.emit_line(line, x);
+                }
+                if heading.level <= 2 {
+                    let y = self.y + size * 0.1;
+                    let right = self.width - MARGIN;
+                    self.rule(x, right, y, 0.75);
+                }
+                self.space(size * 0.35);
+            },
+            NodeValue::List(list) => self.list(node, &list),
+            NodeValue::CodeBlock(code) => match self.diagram(&code.info, &code.literal) {
+                Some(index) => self.place_image(index, ""),
+                None => self.code_block(&code.literal),
+            },
+            NodeValue::BlockQuote | NodeValue::MultilineBlockQuote(_) => {
+                self.quoted(|layout| layout.blocks(node));
+            },
+            NodeValue::Alert(alert) => {
+                let title = alert.title.clone().unwrap_or_else(|| {
+                    match alert.alert_type {
+                        AlertType::Note => "Note",
+                        AlertType::Tip => "Tip",
+                        AlertType::Important => "Important",
+                        AlertType::Warning => "Warning",
+                        AlertType::Caution => "Caution",
+                    }
+                    .to_string()
+                });
+                self.quoted(|layout| {
+                    let style = Style {
+                        font: Font::Bold,
+                        color: MUTED_COLOR,
+                        ..Style::body()
+                    };
+                    let lines = wrap(
+                        &layout.fonts,
+                        &[Run::Text(title, style)],
+                        layout.available_width(),
+                        BODY_SIZE,
+                    );
+                    let x = layout.left();
+                    for line in &lines {
+                        layout.emit_line(line, x);
+                    }
+                    layout.blocks(node);
+                });
+            },
+            NodeValue::ThematicBreak => {
+                self.space(BODY_SIZE * 0.5);
+                self.ensure(BODY_SIZE);
+                let (x, right, y) = (self.left(), self.width - MARGIN, self.y);
+                self.rule(x, right, y, 0.75);
+                self.space(BODY_SIZE);
+            },
+            NodeValue::Table(table) => self.table(node, &table.alignments),
+            NodeValue::DescriptionItem(_) => self.description_item(node),
+            // Collected at the end of the document
+            NodeValue::FootnoteDefinition(_) => {},
+            NodeValue::HtmlBlock(html) if is_page_break(&html.literal) => {
+                if !self.at_page_top() {
+                    self.new_page();
+                }
+            },
+            NodeValue::HtmlBlock(_) | NodeValue::FrontMatter(_) => {},
+            _ => self.blocks(node),
+        }
+    }
+
+    /// Wraps a paragraph's text, placing images that load as blocks between the lines
+    fn paragraph(&mut self, runs: Vec<Run>) {
+        let mut text = Vec::new();
+        for run in runs {
+            let Run::Image(image) = run else {
+                text.push(run);
+                continue;
+            };
+            match self.load_image(&image.url) {
+                Some(index) => {
+                    self.text_lines(&std::mem::take(&mut text));
+                    self.place_image(index, &image.caption);
+                },
+                None => text.push(Run::Image(image)),
+            }
+        }
+        self.text_lines(&text);
+    }
+
+    /// Wraps and places body text, skipping runs that are only whitespace
+    fn text_lines(&mut self, runs: &[Run]) {
+        let blank = runs
+            .iter()
+            .all(|run| run.text().is_none_or(|(text, _)| text.trim().is_empty()));
+        if blank {
+            return;
+        }
+        let lines = wrap(&self.fonts, runs, self.available_width(), BODY_SIZE);
+        let x = self.left();
+        for line in &lines {
+            self.emit_line(line, x);
+        }
+    }
+
+    fn load_image(&mut self, url: &str) -> Option<usize> {
+        let path = resolve_local_image(url, &self.base_dir)?;
+        if let Some(&index) = self.loaded_images.get(&path) {
+            return index;
+        }
+        let index = match images::load(&path) {
+            Ok(image) => {
+                self.images.push(image);
+                Some(self.images.len() - 1)
+            },
+            Err(e) => {
+                log::warn!("PDF: skipping image {:?}: {}", path, e);
+                None
+            },
+        };
+        self.loaded_images.insert(path, index);
+        index
+    }
+
+    /// Renders a diagram fence to PNG with the configured tool; `None` for other fences
+    /// and diagrams that fail, which print as code
+    fn diagram(&mut self, info: &str, source: &str) -> Option<usize> {
+        let kind = DiagramKind::from_info(info)?;
+        let settings = self.diagrams.as_ref().filter(|s| s.enabled)?;
+        let png = diagrams::render(kind, source, DiagramFormat::Png, settings).ok()?;
+        match images::decode(png.to_vec()) {
+            Ok(image) => {
+                self.images.push(image);
+                Some(self.images.len() - 1)
+            },
+            Err(e) => {
+                log::warn!("PDF: skipping diagram: {}", e);
+                None
+            },
+        }
+    }
+
+    /// Draws an image centred in the text column, shrunk to fit the column and page, with
+    /// its caption underneath
+    fn place_image(&mut self, index: usize, caption: &str) {
+        let available = self.available_width();
+        let caption_style = Style {
+            font: Font::Italic,
+            size: CAPTION_SIZE,
+            color: MUTED_COLOR,
+            link: None,
+        };
+        let caption_lines = if caption.trim().is_empty() {
+            Vec::new()
+        } else {
+            wrap(
+                &self.fonts,
+                &[Run::Text(caption.to_string(), caption_style)],
+                available,
+                CAPTION_SIZE,
+            )
+        };
+        let caption_height: f32 = caption_lines.iter().map(Line::height).sum();
+
+        let image = &self.images[index];
+        let natural_width = image.width as f32 * POINTS_PER_PIXEL;
+        let natural_height = image.height as f32 * POINTS_PER_PIXEL;
+        let max_height = (self.height - MARGIN * 2.0 - caption_height) * 0.9;
+        let scale = (available / natural_width)
+            .min(max_height / natural_height)
+            .min(1.0);
+        let (width, height) = (natural_width * scale, natural_height * scale);
+
+        self.space(BODY_SIZE * 0.3);
+        self.ensure(height + caption_height);
+        let x = self.left() + (available - width) / 2.0;
+        let bottom = self.y - height;
+        self.draw_pending_marker(self.y - BODY_SIZE);
+        let op = format!(
+            "q {:.2} 0 0 {:.2} {:.2} {:.2} cm /{} Do Q\n",
+            width,
+            height,
+            x,
+            bottom,
+            writer::image_name(index)
+        );
+        self.page().content.push_str(&op);
+        self.y = bottom - CAPTION_SIZE * 0.3;
+
+        let left = self.left();
+        for line in &caption_lines {
+            self.emit_line(line, left + (available - line.width) / 2.0);
+        }
+        self.space(BODY_SIZE * 0.3);
+    }
+
+    pub fn footnote_section<'a>(&mut self, root: &'a AstNode<'a>) {
+        let mut definitions: Vec<(usize, &'a AstNode<'a>)> = root
+            .children()
+            .filter_map(|node| match &node.data.borrow().value {
+                NodeValue::FootnoteDefinition(def) => {
+                    self.footnotes.get(&def.name).map(|&n| (n, node))
+                },
+                _ => None,
+            })
+            .collect();
+        if definitions.is_empty() {
+            return;
+        }
+        definitions.sort_by_key(|(n, _)| *n);
+
+        self.space(BODY_SIZE);
+        self.ensure(BODY_SIZE * 3.0);
+        let (x, y) = (self.left(), self.y);
+        self.rule(x, x + 120.0, y, 0.5);
+        self.space(BODY_SIZE * 0.6);
+        self.tight.push(true);
+        for (number, node) in definitions {
+            self.list_item(node, format!("[{}]", number));
+        }
+        self.tight.pop();
+    }
+}
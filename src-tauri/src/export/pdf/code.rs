@@ -0,0 +1,67 @@
+use super::fonts::Font;
+use super::layout::Layout;
+use super::text::fitting_prefix;
+use super::{
+    BODY_SIZE, CODE_BACKGROUND, CODE_LINE_SPACING, CODE_PADDING, CODE_SIZE, MARGIN, Style,
+};
+
+impl Layout<'_> {
+    pub fn code_block(&mut self, literal: &str) {
+        let style = Style {
+            font: Font::Mono,
+            size: CODE_SIZE,
+            ..Style::body()
+        };
+        let x = self.left();
+        let width = self.available_width();
+        let line_height = CODE_SIZE * CODE_LINE_SPACING;
+        let text_width = width - CODE_PADDING * 2.0;
+
+        let mut lines: Vec<String> = Vec::new();
+        for source_line in literal.trim_end_matches('\n').split('\n') {
+            // Tabs to four spaces; Courier has no tab stops
+            let source_line = source_line.replace('\t', "    ");
+            let mut rest = source_line.as_str();
+            loop {
+                let split = fitting_prefix(&self.fonts, rest, &style, text_width);
+                lines.push(rest[..split].to_string());
+                rest = &rest[split..];
+                if rest.is_empty() {
+                    break;
+                }
+            }
+        }
+
+        self.ensure(line_height * 2.0 + CODE_PADDING * 2.0);
+        self.fill_rect(
+            x,
+            self.y - CODE_PADDING,
+            width,
+            CODE_PADDING,
+            CODE_BACKGROUND,
+        );
+        self.y -= CODE_PADDING;
+        for text in &lines {
+            if self.y - line_height < MARGIN {
+                self.new_page();
+            }
+            self.fill_rect(x, self.y - line_height, width, line_height, CODE_BACKGROUND);
+            let baseline = self.y - (line_height - CODE_SIZE) / 2.0 - CODE_SIZE * 0.8;
+            self.draw_pending_marker(baseline);
+            if !text.trim().is_empty() {
+                self.draw_text(x + CODE_PADDING, baseline, text, &style);
+            }
+            self.y -= line_height;
+        }
+        self.fill_rect(
+            x,
+            self.y - CODE_PADDING,
+            width,
+            CODE_PADDING,
+            CODE_BACKGROUND,
+        );
+        self.y -= CODE_PADDING;
+        let gap = self.paragraph_gap().max(BODY_SIZE * 0.4);
+        self.space(gap);
+    }
+}
@@ -0,0 +1,214 @@
+use anyhow::{Context, Result, anyhow, bail};
+use std::collections::BTreeMap;
+use std::path::Path;
+use subsetter::GlyphRemapper;
+use ttf_parser::{Face, GlyphId, name_id};
+
+/// Larger files are almost certainly font collections with every weight, or not fonts
+const MAX_FONT_BYTES: u64 = 64 * 1024 * 1024;
+
+/// A TrueType or OpenType font file, and which face in it to use
+#[derive(Debug, Clone)]
+pub struct FontFile {
+    pub data: Vec<u8>,
+    /// Face index inside a collection (`.ttc`); 0 for single fonts
+    pub index: u32,
+}
+
+impl FontFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let size = std::fs::metadata(path)?.len();
+        if size > MAX_FONT_BYTES {
+            bail!("{} bytes exceeds the {} byte limit", size, MAX_FONT_BYTES);
+        }
+        let data = std::fs::read(path)?;
+        Face::parse(&data, 0).context("not a TrueType or OpenType font")?;
+        Ok(Self { data, index: 0 })
+    }
+
+    /// The first installed font from a CSS-style family list such as the editor font
+    /// setting (`"Noto Sans", Consolas, monospace`). Generic families match the system
+    /// default for that kind.
+    pub fn from_family_list(families: &str) -> Option<Self> {
+        let mut db = fontdb::Database::new();
+        db.load_system_fonts();
+        families
+            .split(',')
+            .map(|f| f.trim().trim_matches(['"', '\'']).trim())
+            .filter(|f| !f.is_empty())
+            .find_map(|name| {
+                let family = match name.to_ascii_lowercase().as_str() {
+                    "monospace" => fontdb::Family::Monospace,
+                    "sans-serif" | "system-ui" => fontdb::Family::SansSerif,
+                    "serif" => fontdb::Family::Serif,
+                    _ => fontdb::Family::Name(name),
+                };
+                let id = db.query(&fontdb::Query {
+                    families: &[family],
+                    ..Default::default()
+                })?;
+                db.with_face_data(id, |data, index| Self {
+                    data: data.to_vec(),
+                    index,
+                })
+            })
+    }
+}
+
+/// A font being embedded: glyphs are recorded as text is drawn, then the font is
+/// subset to just those glyphs. Glyph ids in content streams are the subset's ids.
+pub struct CustomFont<'a> {
+    file: &'a FontFile,
+    face: Face<'a>,
+    remapper: GlyphRemapper,
+    /// Subset glyph id to the character it draws, for copy and search in viewers
+    unicode: BTreeMap<u16, char>,
+    /// Subset glyph id to advance width in thousandths of the font size
+    widths: BTreeMap<u16, u16>,
+}
+
+/// The font program and metrics the writer needs for the font objects
+pub struct EmbeddedFont {
+    pub base_font: String,
+    /// OpenType with CFF outlines rather than TrueType `glyf`
+    pub is_cff: bool,
+    /// Deflated subset font file
+    pub program: Vec<u8>,
+    pub widths: BTreeMap<u16, u16>,
+    pub to_unicode: String,
+    pub ascent: i32,
+    pub descent: i32,
+    pub cap_height: i32,
+    pub bbox: [i32; 4],
+    pub italic_angle: f32,
+    pub flags: u32,
+}
+
+impl<'a> CustomFont<'a> {
+    pub fn new(file: &'a FontFile) -> Result<Self> {
+        let face =
+            Face::parse(&file.data, file.index).context("not a TrueType or OpenType font")?;
+        let mut remapper = GlyphRemapper::new();
+        // .notdef keeps id 0 in the subset
+        remapper.remap(0);
+        Ok(Self {
+            file,
+            face,
+            remapper,
+            unicode: BTreeMap::new(),
+            widths: BTreeMap::new(),
+        })
+    }
+
+    fn scale(&self, units: f32) -> f32 {
+        units * 1000.0 / self.face.units_per_em() as f32
+    }
+
+    /// Glyph and advance width (thousandths of the font size) for a character. Missing
+    /// characters draw `.notdef`.
+    fn glyph(&self, c: char) -> (GlyphId, f32) {
+        let c = if c == '\t' { ' ' } else { c };
+        let glyph = self.face.glyph_index(c).unwrap_or(GlyphId(0));
+        let advance = self.face.glyph_hor_advance(glyph).unwrap_or(0);
+        (glyph, self.scale(advance as f32))
+    }
+
+    /// Width of `text` set at `size` points
+    pub fn text_width(&self, text: &str, size: f32) -> f32 {
+        text.chars().map(|c| self.glyph(c).1).sum::<f32>() * size / 1000.0
+    }
+
+    /// `text` as a hex string of subset glyph ids, recording the glyphs it uses
+    pub fn encode(&mut self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len() * 4 + 2);
+        out.push('<');
+        for c in text.chars() {
+            let (glyph, width) = self.glyph(c);
+            let id = self.remapper.remap(glyph.0);
+            if glyph.0 != 0 {
+                self.unicode.entry(id).or_insert(c);
+            }
+            self.widths.insert(id, width.round() as u16);
+            out.push_str(&format!("{:04X}", id));
+        }
+        out.push('>');
+        out
+    }
+
+    /// Subsets the font to the glyphs drawn so far
+    pub fn finish(self) -> Result<EmbeddedFont> {
+        let subset = subsetter::subset(&self.file.data, self.file.index, &self.remapper)
+            .map_err(|e| anyhow!("font subsetting failed: {:?}", e))?;
+        let program = super::images::deflate(&subset)?;
+
+        let face = &self.face;
+        let name = face
+            .names()
+            .into_iter()
+            .filter(|n| n.name_id == name_id::POST_SCRIPT_NAME)
+            .find_map(|n| n.to_string())
+            .unwrap_or_else(|| "Embedded".to_string());
+        let name: String = name
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+            .collect();
+        let bbox = face.global_bounding_box();
+        let units_per_em = face.units_per_em() as f32;
+        let scale = |v: i16| (v as f32 * 1000.0 / units_per_em).round() as i32;
+
+        // Nonsymbolic, plus fixed pitch and italic where they apply
+        let mut flags = 32;
+        if face.is_monospaced() {
+            flags |= 1;
+        }
+        if face.is_italic() {
+            flags |= 64;
+        }
+
+        Ok(EmbeddedFont {
+            // Subset fonts are tagged with six capital letters
+            base_font: format!("MDRSAA+{}", name),
+            is_cff: face.tables().cff.is_some(),
+            program,
+            to_unicode: to_unicode_cmap(&self.unicode),
+            ascent: scale(face.ascender()),
+            descent: scale(face.descender()),
+            cap_height: scale(face.capital_height().unwrap_or(face.ascender())),
+            bbox: [
+                scale(bbox.x_min),
+                scale(bbox.y_min),
+                scale(bbox.x_max),
+                scale(bbox.y_max),
+            ],
+            italic_angle: face.italic_angle(),
+            flags,
+            widths: self.widths,
+        })
+    }
+}
+
+/// A ToUnicode CMap mapping two-byte glyph ids back to text
+fn to_unicode_cmap(unicode: &BTreeMap<u16, char>) -> String {
+    let mut cmap = String::from(
+        "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+         1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+    );
+    let entries: Vec<(&u16, &char)> = unicode.iter().collect();
+    // At most 100 entries per block
+    for chunk in entries.chunks(100) {
+        cmap.push_str(&format!("{} beginbfchar\n", chunk.len()));
+        for (id, c) in chunk {
+            let utf16: String = c
+                .encode_utf16(&mut [0; 2])
+                .iter()
+                .map(|unit| format!("{:04X}", unit))
+                .collect();
+            cmap.push_str(&format!("<{:04X}> <{}>\n", id, utf16));
+        }
+        cmap.push_str("endbfchar\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+    cmap
+}
@@ -0,0 +1,183 @@
+/// The standard PDF fonts the writer uses. Every viewer has them, so nothing is embedded;
+/// text is limited to the WinAnsi (Windows-1252) character set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Font {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+    Mono,
+    MonoBold,
+}
+
+/// Advance widths of `' '..='~'` in Helvetica, in thousandths of the font size
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Advance widths of `' '..='~'` in Helvetica-Bold
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+const COURIER_WIDTH: u16 = 600;
+
+impl Font {
+    pub const ALL: [Font; 6] = [
+        Font::Regular,
+        Font::Bold,
+        Font::Italic,
+        Font::BoldItalic,
+        Font::Mono,
+        Font::MonoBold,
+    ];
+
+    pub fn base_font(self) -> &'static str {
+        match self {
+            Font::Regular => "Helvetica",
+            Font::Bold => "Helvetica-Bold",
+            Font::Italic => "Helvetica-Oblique",
+            Font::BoldItalic => "Helvetica-BoldOblique",
+            Font::Mono => "Courier",
+            Font::MonoBold => "Courier-Bold",
+        }
+    }
+
+    /// Name of the font in each page's resource dictionary
+    pub fn resource_name(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Italic => "F3",
+            Font::BoldItalic => "F4",
+            Font::Mono => "F5",
+            Font::MonoBold => "F6",
+        }
+    }
+
+    pub fn is_bold(self) -> bool {
+        matches!(self, Font::Bold | Font::BoldItalic | Font::MonoBold)
+    }
+
+    pub fn is_italic(self) -> bool {
+        matches!(self, Font::Italic | Font::BoldItalic)
+    }
+
+    pub fn bold(self) -> Font {
+        match self {
+            Font::Regular => Font::Bold,
+            Font::Italic => Font::BoldItalic,
+            Font::Mono => Font::MonoBold,
+            other => other,
+        }
+    }
+
+    /// Monospaced fonts have no oblique variant here and stay upright
+    pub fn italic(self) -> Font {
+        match self {
+            Font::Regular => Font::Italic,
+            Font::Bold => Font::BoldItalic,
+            other => other,
+        }
+    }
+
+    pub fn mono(self) -> Font {
+        if self.is_bold() {
+            Font::MonoBold
+        } else {
+            Font::Mono
+        }
+    }
+
+    /// Width of `text` set at `size` points
+    pub fn text_width(self, text: &str, size: f32) -> f32 {
+        let units: u32 = text
+            .chars()
+            .map(|c| self.glyph_width(win_ansi_byte(c)) as u32)
+            .sum();
+        units as f32 * size / 1000.0
+    }
+
+    fn glyph_width(self, byte: u8) -> u16 {
+        let widths = match self {
+            Font::Mono | Font::MonoBold => return COURIER_WIDTH,
+            Font::Bold | Font::BoldItalic => &HELVETICA_BOLD_WIDTHS,
+            Font::Regular | Font::Italic => &HELVETICA_WIDTHS,
+        };
+        match byte {
+            b' '..=b'~' => widths[(byte - b' ') as usize],
+            // Approximations for the upper half; close enough for line breaking
+            0x85 | 0x89 | 0x97 | 0x99 => 1000,
+            0x91 | 0x92 | 0x82 => 222,
+            0x93 | 0x94 | 0x84 => 333,
+            0x95 | 0xB7 => 350,
+            0xA0 => 278,
+            0xA9 | 0xAE => 737,
+            0xC0..=0xDE => 722,
+            _ => 556,
+        }
+    }
+}
+
+/// Maps a character to its Windows-1252 byte; characters outside it become `?`
+pub fn win_ansi_byte(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        '\t' => b' ',
+        '\u{A0}'..='\u{FF}' => c as u32 as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        'ƒ' => 0x83,
+        '„' => 0x84,
+        '…' => 0x85,
+        '†' => 0x86,
+        '‡' => 0x87,
+        'ˆ' => 0x88,
+        '‰' => 0x89,
+        'Š' => 0x8A,
+        '‹' => 0x8B,
+        'Œ' => 0x8C,
+        'Ž' => 0x8E,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '˜' => 0x98,
+        '™' => 0x99,
+        'š' => 0x9A,
+        '›' => 0x9B,
+        'œ' => 0x9C,
+        'ž' => 0x9E,
+        'Ÿ' => 0x9F,
+        _ => b'?',
+    }
+}
+
+/// `text` as a PDF literal string in WinAnsi encoding, parentheses included
+pub fn pdf_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('(');
+    for byte in text.chars().map(win_ansi_byte) {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                out.push('\\');
+                out.push(byte as char);
+            },
+            0x20..=0x7E => out.push(byte as char),
+            _ => out.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    out.push(')');
+    out
+}
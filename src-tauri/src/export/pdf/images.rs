@@ -0,0 +1,134 @@
+use anyhow::{Context, Result, bail};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use image::ImageFormat;
+use std::io::Write;
+use std::path::Path;
+
+/// Larger files are skipped; a photo this size would dominate the PDF
+const MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// An image ready to be written as a PDF image XObject
+#[derive(Debug)]
+pub struct PdfImage {
+    pub width: u32,
+    pub height: u32,
+    pub color_space: &'static str,
+    pub filter: &'static str,
+    pub data: Vec<u8>,
+    /// Flate-compressed 8-bit alpha channel, written as a soft mask
+    pub alpha: Option<Vec<u8>>,
+    /// Adobe CMYK JPEGs store inverted component values
+    pub inverted: bool,
+}
+
+/// Reads a PNG or JPEG. JPEGs are embedded as they are; PNGs are decoded and recompressed,
+/// with transparency kept as a soft mask.
+pub fn load(path: &Path) -> Result<PdfImage> {
+    let size = std::fs::metadata(path)?.len();
+    if size > MAX_IMAGE_BYTES {
+        bail!("{} bytes exceeds the {} byte limit", size, MAX_IMAGE_BYTES);
+    }
+    decode(std::fs::read(path)?)
+}
+
+/// Like `load`, for an image already in memory
+pub fn decode(bytes: Vec<u8>) -> Result<PdfImage> {
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        jpeg(bytes)
+    } else if bytes.starts_with(b"\x89PNG") {
+        png(&bytes)
+    } else {
+        bail!("unsupported format; only PNG and JPEG images are embedded")
+    }
+}
+
+fn jpeg(bytes: Vec<u8>) -> Result<PdfImage> {
+    let (width, height, components) = jpeg_frame(&bytes).context("no JPEG frame header")?;
+    let color_space = match components {
+        1 => "DeviceGray",
+        3 => "DeviceRGB",
+        4 => "DeviceCMYK",
+        n => bail!("unsupported JPEG with {} components", n),
+    };
+    Ok(PdfImage {
+        width,
+        height,
+        color_space,
+        filter: "DCTDecode",
+        data: bytes,
+        alpha: None,
+        inverted: components == 4,
+    })
+}
+
+/// Width, height and component count from the first start-of-frame segment
+fn jpeg_frame(bytes: &[u8]) -> Option<(u32, u32, u8)> {
+    let mut i = 2;
+    while i + 4 <= bytes.len() {
+        if bytes[i] != 0xFF {
+            return None;
+        }
+        let marker = bytes[i + 1];
+        // Fill bytes and markers without a length
+        if marker == 0xFF {
+            i += 1;
+            continue;
+        }
+        if marker == 0x01 || (0xD0..=0xD8).contains(&marker) {
+            i += 2;
+            continue;
+        }
+        let length = u16::from_be_bytes([bytes[i + 2], bytes[i + 3]]) as usize;
+        // SOF0–SOF15, except DHT, JPG and DAC which share the range
+        if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            let segment = bytes.get(i + 4..i + 2 + length)?;
+            if segment.len() < 6 {
+                return None;
+            }
+            let height = u16::from_be_bytes([segment[1], segment[2]]) as u32;
+            let width = u16::from_be_bytes([segment[3], segment[4]]) as u32;
+            return Some((width, height, segment[5]));
+        }
+        i += 2 + length;
+    }
+    None
+}
+
+fn png(bytes: &[u8]) -> Result<PdfImage> {
+    let image = image::load_from_memory_with_format(bytes, ImageFormat::Png)?;
+    let color = image.color();
+    let (pixels, color_space) = if color.has_color() {
+        (image.to_rgb8().into_raw(), "DeviceRGB")
+    } else {
+        (image.to_luma8().into_raw(), "DeviceGray")
+    };
+
+    let alpha = if color.has_alpha() {
+        let alpha: Vec<u8> = image.to_rgba8().pixels().map(|p| p[3]).collect();
+        // Fully opaque images don't need a mask
+        if alpha.iter().all(|&a| a == u8::MAX) {
+            None
+        } else {
+            Some(deflate(&alpha)?)
+        }
+    } else {
+        None
+    };
+
+    Ok(PdfImage {
+        width: image.width(),
+        height: image.height(),
+        color_space,
+        filter: "FlateDecode",
+        data: deflate(&pixels)?,
+        alpha,
+        inverted: false,
+    })
+}
+
+pub fn deflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
@@ -0,0 +1,283 @@
+use super::fonts::pdf_string;
+use super::images::PdfImage;
+use super::text::Line;
+use super::writer::{CUSTOM_FONT_NAME, LinkArea, Page};
+use super::{BODY_SIZE, Color, Fonts, INDENT, MARGIN, QUOTE_BAR_WIDTH, RULE_COLOR, Style};
+use crate::markdown::diagrams::DiagramSettings;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+fn color_op(color: Color, stroke: bool) -> String {
+    format!(
+        "{:.3} {:.3} {:.3} {}",
+        color.0,
+        color.1,
+        color.2,
+        if stroke { "RG" } else { "rg" }
+    )
+}
+
+/// An open blockquote: its bar is drawn from `top` down once the quote's extent is known
+struct Quote {
+    page: usize,
+    top: f32,
+    x: f32,
+}
+
+pub struct Layout<'f> {
+    pub width: f32,
+    pub height: f32,
+    pub pages: Vec<Page>,
+    /// Top of the free space on the current page, in points from the bottom
+    pub y: f32,
+    /// Indent from the left margin for the current block
+    pub indent: f32,
+    quotes: Vec<Quote>,
+    /// List marker waiting for the first line of its item
+    pub pending_marker: Option<(String, f32, Style)>,
+    /// Tight lists keep their items' paragraphs close together
+    pub tight: Vec<bool>,
+    pub footnotes: HashMap<String, usize>,
+    /// Folder relative image paths resolve against
+    pub base_dir: PathBuf,
+    pub images: Vec<PdfImage>,
+    /// Index into `images` for each file, `None` when it couldn't be loaded
+    pub loaded_images: HashMap<PathBuf, Option<usize>>,
+    pub diagrams: Option<DiagramSettings>,
+    pub fonts: Fonts<'f>,
+}
+
+impl<'f> Layout<'f> {
+    pub fn new(width: f32, height: f32, base_dir: PathBuf, fonts: Fonts<'f>) -> Self {
+        Self {
+            width,
+            height,
+            pages: vec![Page::default()],
+            y: height - MARGIN,
+            indent: 0.0,
+            quotes: Vec::new(),
+            pending_marker: None,
+            tight: Vec::new(),
+            footnotes: HashMap::new(),
+            base_dir,
+            images: Vec::new(),
+            loaded_images: HashMap::new(),
+            diagrams: None,
+            fonts,
+        }
+    }
+
+    pub fn finish(mut self) -> (Vec<Page>, Vec<PdfImage>, Fonts<'f>) {
+        while let Some(quote) = self.quotes.pop() {
+            self.close_quote(quote);
+        }
+        (self.pages, self.images, self.fonts)
+    }
+
+    pub fn left(&self) -> f32 {
+        MARGIN + self.indent
+    }
+
+    pub fn available_width(&self) -> f32 {
+        (self.width - MARGIN - self.left()).max(INDENT)
+    }
+
+    pub fn page(&mut self) -> &mut Page {
+        self.pages.last_mut().expect("layout always has a page")
+    }
+
+    pub fn at_page_top(&self) -> bool {
+        self.y >= self.height - MARGIN
+    }
+
+    pub fn new_page(&mut self) {
+        // Quote bars continue on the next page
+        let page_index = self.pages.len() - 1;
+        let top = self.height - MARGIN;
+        let open: Vec<(usize, f32, f32)> =
+            self.quotes.iter().map(|q| (q.page, q.top, q.x)).collect();
+        for (page, quote_top, x) in open {
+            if page == page_index {
+                self.draw_bar(x, quote_top, MARGIN);
+            }
+        }
+        for quote in &mut self.quotes {
+            quote.page = page_index + 1;
+            quote.top = top;
+        }
+        self.pages.push(Page::default());
+        self.y = top;
+    }
+
+    /// Starts a new page unless `height` fits in the space left
+    pub fn ensure(&mut self, height: f32) {
+        if self.y - height < MARGIN && !self.at_page_top() {
+            self.new_page();
+        }
+    }
+
+    pub fn space(&mut self, amount: f32) {
+        if self.at_page_top() {
+            return;
+        }
+        self.y -= amount;
+        if self.y < MARGIN {
+            self.new_page();
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        let op = format!(
+            "q {} {:.2} {:.2} {:.2} {:.2} re f Q\n",
+            color_op(color, false),
+            x,
+            y,
+            w,
+            h
+        );
+        self.page().content.push_str(&op);
+    }
+
+    pub fn stroke_rect(&mut self, x: f32, y: f32, w: f32, h: f32) {
+        let op = format!(
+            "q 0.5 w {} {:.2} {:.2} {:.2} {:.2} re S Q\n",
+            color_op(RULE_COLOR, true),
+            x,
+            y,
+            w,
+            h
+        );
+        self.page().content.push_str(&op);
+    }
+
+    pub fn rule(&mut self, x1: f32, x2: f32, y: f32, width: f32) {
+        let op = format!(
+            "q {:.2} w {} {:.2} {:.2} m {:.2} {:.2} l S Q\n",
+            width,
+            color_op(RULE_COLOR, true),
+            x1,
+            y,
+            x2,
+            y
+        );
+        self.page().content.push_str(&op);
+    }
+
+    fn draw_bar(&mut self, x: f32, top: f32, bottom: f32) {
+        if top > bottom {
+            self.fill_rect(x, bottom, QUOTE_BAR_WIDTH, top - bottom, RULE_COLOR);
+        }
+    }
+
+    pub fn draw_text(&mut self, x: f32, baseline: f32, text: &str, style: &Style) {
+        let op = match &mut self.fonts.custom {
+            None => format!(
+                "BT /{} {:.2} Tf {} {:.2} {:.2} Td {} Tj ET\n",
+                style.font.resource_name(),
+                style.size,
+                color_op(style.color, false),
+                x,
+                baseline,
+                pdf_string(text)
+            ),
+            Some(custom) => {
+                // Synthetic bold strokes the outline as well as filling it
+                let bold = if style.font.is_bold() {
+                    format!(
+                        "2 Tr {:.2} w {} ",
+                        style.size * 0.03,
+                        color_op(style.color, true)
+                    )
+                } else {
+                    String::new()
+                };
+                let slant = if style.font.is_italic() { 0.2 } else { 0.0 };
+                format!(
+                    "q BT /{} {:.2} Tf {} {}1 0 {:.2} 1 {:.2} {:.2} Tm {} Tj ET Q\n",
+                    CUSTOM_FONT_NAME,
+                    style.size,
+                    color_op(style.color, false),
+                    bold,
+                    slant,
+                    x,
+                    baseline,
+                    custom.encode(text)
+                )
+            },
+        };
+        self.page().content.push_str(&op);
+    }
+
+    pub fn draw_line(&mut self, line: &Line, x: f32, baseline: f32) {
+        let mut cursor = x;
+        for piece in &line.pieces {
+            if !piece.text.trim().is_empty() {
+                self.draw_text(cursor, baseline, &piece.text, &piece.style);
+            }
+            // Only links with a scheme can open; in-document anchors have no target here
+            if let Some(url) = piece.style.link.as_ref().filter(|u| u.contains(':')) {
+                let size = piece.style.size;
+                self.page().links.push(LinkArea {
+                    rect: [
+                        cursor,
+                        baseline - size * 0.25,
+                        cursor + piece.width,
+                        baseline + size * 0.85,
+                    ],
+                    uri: url.clone(),
+                });
+            }
+            cursor += piece.width;
+        }
+    }
+
+    /// Draws the list marker waiting for this line, if any
+    pub fn draw_pending_marker(&mut self, baseline: f32) {
+        if let Some((marker, x, style)) = self.pending_marker.take() {
+            self.draw_text(x, baseline, &marker, &style);
+        }
+    }
+
+    /// Places one line of body text at the current position and moves down past it
+    pub fn emit_line(&mut self, line: &Line, x: f32) {
+        let height = line.height();
+        self.ensure(height);
+        let baseline = self.y - (height - line.size) / 2.0 - line.size * 0.8;
+        self.draw_pending_marker(baseline);
+        self.draw_line(line, x, baseline);
+        self.y -= height;
+    }
+
+    pub fn paragraph_gap(&self) -> f32 {
+        if self.tight.last() == Some(&true) {
+            BODY_SIZE * 0.15
+        } else {
+            BODY_SIZE * 0.6
+        }
+    }
+
+    pub fn quoted(&mut self, body: impl FnOnce(&mut Self)) {
+        self.space(BODY_SIZE * 0.2);
+        let quote = Quote {
+            page: self.pages.len() - 1,
+            top: self.y,
+            x: self.left() + 2.0,
+        };
+        self.quotes.push(quote);
+        self.indent += INDENT;
+        body(self);
+        self.indent -= INDENT;
+        if let Some(quote) = self.quotes.pop() {
+            self.close_quote(quote);
+        }
+        self.space(BODY_SIZE * 0.4);
+    }
+
+    fn close_quote(&mut self, quote: Quote) {
+        // Trailing paragraph space belongs below the bar
+        let bottom = self.y + BODY_SIZE * 0.3;
+        if quote.page == self.pages.len() - 1 {
+            self.draw_bar(quote.x, quote.top, bottom.min(quote.top));
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use super::fonts::Font;
+use super::layout::Layout;
+use super::text::{Line, Run, wrap};
+use super::{BODY_SIZE, CODE_SIZE, INDENT, Style};
+use comrak::nodes::{AstNode, ListType, NodeList, NodeValue};
+
+impl Layout<'_> {
+    pub fn list<'a>(&mut self, node: &'a AstNode<'a>, list: &NodeList) {
+        self.tight.push(list.tight);
+        let mut number = list.start;
+        for item in node.children() {
+            let marker = match &item.data.borrow().value {
+                NodeValue::TaskItem(task) if task.symbol.is_some() => "[x]".to_string(),
+                NodeValue::TaskItem(_) => "[ ]".to_string(),
+                _ if list.list_type == ListType::Ordered => {
+                    number += 1;
+                    format!("{}.", number - 1)
+                },
+                _ => bullet(self.tight.len()).to_string(),
+            };
+            self.list_item(item, marker);
+        }
+        self.tight.pop();
+        if self.tight.is_empty() {
+            self.space(BODY_SIZE * 0.45);
+        }
+    }
+
+    pub fn list_item<'a>(&mut self, item: &'a AstNode<'a>, marker: String) {
+        let style = if marker.starts_with('[') {
+            Style {
+                font: Font::Mono,
+                size: CODE_SIZE,
+                ..Style::body()
+            }
+        } else {
+            Style::body()
+        };
+        let marker_width = self.fonts.width(&marker, &style) + 6.0;
+        let step = INDENT.max(marker_width);
+        self.pending_marker = Some((marker, self.left(), style));
+        self.indent += step;
+        self.blocks(item);
+        self.indent -= step;
+        // An empty item still shows its marker
+        if self.pending_marker.is_some() {
+            let line = Line {
+                size: BODY_SIZE,
+                ..Default::default()
+            };
+            let x = self.left();
+            self.emit_line(&line, x);
+        }
+    }
+
+    /// A definition list entry: the term in bold, its details indented below
+    pub fn description_item<'a>(&mut self, node: &'a AstNode<'a>) {
+        for part in node.children() {
+            let is_term = matches!(part.data.borrow().value, NodeValue::DescriptionTerm);
+            if is_term {
+                let style = Style {
+                    font: Font::Bold,
+                    ..Style::body()
+                };
+                let runs: Vec<Run> = part
+                    .children()
+                    .flat_map(|child| self.inlines(child, &style))
+                    .collect();
+                let lines = wrap(&self.fonts, &runs, self.available_width(), BODY_SIZE);
+                let x = self.left();
+                for line in &lines {
+                    self.emit_line(line, x);
+                }
+            } else {
+                self.indent += INDENT;
+                self.blocks(part);
+                self.indent -= INDENT;
+            }
+        }
+    }
+}
+
+/// Bullets by nesting depth, starting at 1
+fn bullet(depth: usize) -> &'static str {
+    match depth % 3 {
+        1 => "•",
+        2 => "–",
+        _ => "·",
+    }
+}
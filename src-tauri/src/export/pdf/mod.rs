@@ -0,0 +1,179 @@
+mod blocks;
+mod code;
+mod custom_font;
+mod fonts;
+mod images;
+mod layout;
+mod lists;
+mod tables;
+mod text;
+mod writer;
+
+use crate::export::presets::PageSetup;
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::diagrams::DiagramSettings;
+use anyhow::Result;
+use comrak::{Arena, parse_document};
+use custom_font::CustomFont;
+use fonts::Font;
+use layout::Layout;
+use std::path::Path;
+
+pub use custom_font::FontFile;
+
+const MARGIN: f32 = 56.0;
+const BODY_SIZE: f32 = 11.0;
+const CODE_SIZE: f32 = 9.0;
+const LINE_SPACING: f32 = 1.35;
+const CODE_LINE_SPACING: f32 = 1.3;
+const HEADING_SIZES: [f32; 6] = [22.0, 18.0, 15.0, 13.0, 11.5, 11.0];
+const INDENT: f32 = 18.0;
+const CODE_PADDING: f32 = 6.0;
+const CELL_PADDING: f32 = 4.0;
+const QUOTE_BAR_WIDTH: f32 = 2.5;
+const CAPTION_SIZE: f32 = 9.0;
+/// Images are sized as if at 96 DPI, like the preview, then shrunk to fit the page
+const POINTS_PER_PIXEL: f32 = 0.75;
+
+/// An HTML block that starts a new page in the PDF; browsers printing an HTML export
+/// break there too
+pub const PAGE_BREAK: &str = r#"<div style="page-break-after: always"></div>"#;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Color(f32, f32, f32);
+
+const TEXT_COLOR: Color = Color(0.1, 0.1, 0.1);
+const MUTED_COLOR: Color = Color(0.4, 0.4, 0.4);
+const LINK_COLOR: Color = Color(0.0, 0.27, 0.67);
+const CODE_COLOR: Color = Color(0.55, 0.1, 0.25);
+const CODE_BACKGROUND: Color = Color(0.95, 0.95, 0.95);
+const HEADER_BACKGROUND: Color = Color(0.92, 0.92, 0.92);
+const RULE_COLOR: Color = Color(0.75, 0.75, 0.75);
+
+#[derive(Debug, Clone)]
+pub struct PdfOptions {
+    /// Written to the document information dictionary
+    pub title: String,
+    pub flavor: MarkdownFlavor,
+    pub page_setup: PageSetup,
+    /// Path of the document; relative image paths resolve against its folder
+    pub base_path: Option<String>,
+    /// Embedded and used for all text instead of the standard fonts
+    pub font: Option<FontFile>,
+    /// Tools that draw diagram fences as images; `None` prints them as code
+    pub diagrams: Option<DiagramSettings>,
+}
+
+/// Whether `content` has characters the standard fonts can't draw, such as CJK or Cyrillic
+pub fn needs_embedded_font(content: &str) -> bool {
+    content
+        .chars()
+        .any(|c| !c.is_control() && c != '?' && fonts::win_ansi_byte(c) == b'?')
+}
+
+/// Whether an HTML block only asks for a page break: `PAGE_BREAK`, any element styled
+/// `page-break-*: always` or `break-*: page` with nothing inside, or `<!-- pagebreak -->`
+fn is_page_break(html: &str) -> bool {
+    let html = html.trim().to_ascii_lowercase();
+    if html == "<!-- pagebreak -->" {
+        return true;
+    }
+    let compact: String = html.chars().filter(|c| !c.is_whitespace()).collect();
+    let breaks = [
+        "page-break-before:always",
+        "page-break-after:always",
+        "break-before:page",
+        "break-after:page",
+    ]
+    .iter()
+    .any(|style| compact.contains(style));
+    // Empty element: nothing between the opening tag's `>` and the closing tag
+    breaks && compact.matches('>').count() <= 2 && compact.contains("></")
+}
+
+/// Page size in points for a paper name, swapped for landscape. Unknown names get A4.
+fn page_size(setup: &PageSetup) -> (f32, f32) {
+    let (width, height) = match setup.paper.trim().to_ascii_lowercase().as_str() {
+        "letter" => (612.0, 792.0),
+        "legal" => (612.0, 1008.0),
+        "a3" => (841.89, 1190.55),
+        "a5" => (419.53, 595.28),
+        _ => (595.28, 841.89),
+    };
+    if setup.landscape {
+        (height, width)
+    } else {
+        (width, height)
+    }
+}
+
+/// Lays out markdown as a paginated PDF: headings, wrapped paragraphs with inline styles
+/// and links, nested lists, blockquotes and alerts, shaded code blocks, GFM tables with
+/// borders, local PNG/JPEG images with captions, and page breaks. Text uses the standard
+/// PDF fonts, which only cover Windows-1252, unless a font file is given; that font is
+/// subset and embedded so any script it covers prints.
+pub fn build_pdf(content: &str, options: &PdfOptions) -> Result<Vec<u8>> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, content, &options.flavor.to_comrak_options());
+
+    let (width, height) = page_size(&options.page_setup);
+    let base_dir = options
+        .base_path
+        .as_deref()
+        .and_then(|p| Path::new(p).parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let fonts = Fonts {
+        custom: options.font.as_ref().map(CustomFont::new).transpose()?,
+    };
+    let mut layout = Layout::new(width, height, base_dir, fonts);
+    layout.diagrams = options.diagrams.clone();
+    layout.number_footnotes(root);
+    layout.blocks(root);
+    layout.footnote_section(root);
+
+    let (pages, images, fonts) = layout.finish();
+    let custom_font = fonts.custom.map(CustomFont::finish).transpose()?;
+    Ok(writer::write_document(
+        &pages,
+        &images,
+        custom_font.as_ref(),
+        width,
+        height,
+        &options.title,
+    ))
+}
+
+/// Measures text in the font it will be drawn with
+struct Fonts<'f> {
+    /// One embedded font serves every face; bold is stroked and italic slanted
+    custom: Option<CustomFont<'f>>,
+}
+
+impl Fonts<'_> {
+    fn width(&self, text: &str, style: &Style) -> f32 {
+        match &self.custom {
+            Some(custom) => custom.text_width(text, style.size),
+            None => style.font.text_width(text, style.size),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Style {
+    font: Font,
+    size: f32,
+    color: Color,
+    link: Option<String>,
+}
+
+impl Style {
+    fn body() -> Self {
+        Self {
+            font: Font::Regular,
+            size: BODY_SIZE,
+            color: TEXT_COLOR,
+            link: None,
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use super::fonts::Font;
+use super::layout::Layout;
+use super::text::{Line, Run, longest_word, runs_width, wrap};
+use super::{BODY_SIZE, CELL_PADDING, Fonts, HEADER_BACKGROUND, MARGIN, Style};
+use comrak::nodes::{AstNode, NodeValue, TableAlignment};
+
+impl Layout<'_> {
+    pub fn table<'a>(&mut self, table: &'a AstNode<'a>, alignments: &[TableAlignment]) {
+        let header_style = Style {
+            font: Font::Bold,
+            ..Style::body()
+        };
+        let rows: Vec<(bool, Vec<Vec<Run>>)> = table
+            .children()
+            .map(|row| {
+                let header = matches!(row.data.borrow().value, NodeValue::TableRow(true));
+                let style = if header {
+                    header_style.clone()
+                } else {
+                    Style::body()
+                };
+                let cells = row
+                    .children()
+                    .map(|cell| self.inlines(cell, &style))
+                    .collect();
+                (header, cells)
+            })
+            .collect();
+        let columns = rows.iter().map(|(_, cells)| cells.len()).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+
+        let widths = column_widths(&self.fonts, &rows, columns, self.available_width());
+        let x = self.left();
+        let header_rows: Vec<Vec<Vec<Line>>> = rows
+            .iter()
+            .filter(|(header, _)| *header)
+            .map(|(_, cells)| wrap_row(&self.fonts, cells, &widths))
+            .collect();
+        let header_height: f32 = header_rows.iter().map(|cells| row_height(cells)).sum();
+        let page_room = self.height - MARGIN * 2.0;
+
+        self.space(BODY_SIZE * 0.2);
+        for (header, cells) in &rows {
+            let mut cells = wrap_row(&self.fonts, cells, &widths);
+            // Room a row gets on a fresh page, below the repeated header rows
+            let room = if *header {
+                page_room
+            } else {
+                page_room - header_height
+            };
+            let mut fresh_page = self.at_page_top();
+            loop {
+                let height = row_height(&cells);
+                if self.y - height >= MARGIN {
+                    self.draw_row(&cells, *header, &widths, alignments, x);
+                    break;
+                }
+                if !fresh_page && height <= room {
+                    self.table_page_break(*header, &header_rows, &widths, alignments, x);
+                    fresh_page = true;
+                    continue;
+                }
+                // Taller than a page: draw the lines that fit and carry the rest over
+                let (head, rest) =
+                    split_row(cells, self.y - MARGIN - CELL_PADDING * 2.0, fresh_page);
+                if head.iter().any(|lines| !lines.is_empty()) {
+                    self.draw_row(&head, *header, &widths, alignments, x);
+                }
+                cells = rest;
+                if cells.iter().all(Vec::is_empty) {
+                    break;
+                }
+                self.table_page_break(*header, &header_rows, &widths, alignments, x);
+                fresh_page = true;
+            }
+        }
+        self.space(BODY_SIZE * 0.6);
+    }
+
+    /// Starts a new page inside a table, repeating its header rows above body rows
+    fn table_page_break(
+        &mut self,
+        header: bool,
+        header_rows: &[Vec<Vec<Line>>],
+        widths: &[f32],
+        alignments: &[TableAlignment],
+        x: f32,
+    ) {
+        self.new_page();
+        if !header {
+            for cells in header_rows {
+                self.draw_row(cells, true, widths, alignments, x);
+            }
+        }
+    }
+
+    fn draw_row(
+        &mut self,
+        cells: &[Vec<Line>],
+        header: bool,
+        widths: &[f32],
+        alignments: &[TableAlignment],
+        x: f32,
+    ) {
+        let row_height = row_height(cells);
+        let top = self.y;
+        let bottom = top - row_height;
+        let total_width: f32 = widths.iter().sum();
+        if header {
+            self.fill_rect(x, bottom, total_width, row_height, HEADER_BACKGROUND);
+        }
+        // The first cell takes the marker of a table that opens a list item
+        self.draw_pending_marker(top - CELL_PADDING - BODY_SIZE);
+
+        let mut cell_x = x;
+        for (c, lines) in cells.iter().enumerate() {
+            let inner = widths[c] - CELL_PADDING * 2.0;
+            let mut line_top = top - CELL_PADDING;
+            for line in lines {
+                let height = line.height();
+                let baseline = line_top - (height - line.size) / 2.0 - line.size * 0.8;
+                let offset = match alignments.get(c) {
+                    Some(TableAlignment::Right) => inner - line.width,
+                    Some(TableAlignment::Center) => (inner - line.width) / 2.0,
+                    _ => 0.0,
+                };
+                self.draw_line(line, cell_x + CELL_PADDING + offset.max(0.0), baseline);
+                line_top -= height;
+            }
+            self.stroke_rect(cell_x, bottom, widths[c], row_height);
+            cell_x += widths[c];
+        }
+        self.y = bottom;
+    }
+}
+
+/// Wraps each cell to its column
+fn wrap_row(fonts: &Fonts, cells: &[Vec<Run>], widths: &[f32]) -> Vec<Vec<Line>> {
+    widths
+        .iter()
+        .enumerate()
+        .map(|(c, width)| {
+            let runs = cells.get(c).map(Vec::as_slice).unwrap_or_default();
+            wrap(fonts, runs, width - CELL_PADDING * 2.0, BODY_SIZE)
+        })
+        .collect()
+}
+
+/// Height of a row: its tallest cell plus padding
+fn row_height(cells: &[Vec<Line>]) -> f32 {
+    cells
+        .iter()
+        .map(|lines| lines.iter().map(Line::height).sum::<f32>())
+        .fold(0.0, f32::max)
+        + CELL_PADDING * 2.0
+}
+
+/// Splits each cell's lines where they stop fitting in `room`. With `force` every cell
+/// keeps at least one line, so a row too tall for an empty page still makes progress.
+fn split_row(cells: Vec<Vec<Line>>, room: f32, force: bool) -> (Vec<Vec<Line>>, Vec<Vec<Line>>) {
+    cells
+        .into_iter()
+        .map(|mut lines| {
+            let mut used = 0.0;
+            let fitting = lines
+                .iter()
+                .take_while(|line| {
+                    used += line.height();
+                    used <= room
+                })
+                .count();
+            let keep = if force { fitting.max(1) } else { fitting };
+            let rest = lines.split_off(keep.min(lines.len()));
+            (lines, rest)
+        })
+        .unzip()
+}
+
+/// Column widths for a table: natural widths when they fit, otherwise each column keeps
+/// room for its longest word and the rest is shared out by how much text it holds.
+fn column_widths(
+    fonts: &Fonts,
+    rows: &[(bool, Vec<Vec<Run>>)],
+    columns: usize,
+    available: f32,
+) -> Vec<f32> {
+    let padding = CELL_PADDING * 2.0;
+    let mut natural = vec![padding; columns];
+    let mut minimum = vec![padding; columns];
+    for (_, cells) in rows {
+        for (c, runs) in cells.iter().enumerate().take(columns) {
+            natural[c] = natural[c].max(runs_width(fonts, runs) + padding + 1.0);
+            minimum[c] = minimum[c].max(longest_word(fonts, runs) + padding + 1.0);
+        }
+    }
+
+    let natural_total: f32 = natural.iter().sum();
+    if natural_total <= available {
+        return natural;
+    }
+    let minimum_total: f32 = minimum.iter().sum();
+    if minimum_total >= available {
+        // Even the longest words don't fit; scale down and let words split
+        return minimum
+            .iter()
+            .map(|w| w * available / minimum_total)
+            .collect();
+    }
+    let extra = available - minimum_total;
+    let wanted: f32 = natural.iter().zip(&minimum).map(|(n, m)| n - m).sum();
+    natural
+        .iter()
+        .zip(&minimum)
+        .map(|(n, m)| m + (n - m) / wanted * extra)
+        .collect()
+}
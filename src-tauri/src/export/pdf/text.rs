@@ -0,0 +1,286 @@
+use super::fonts::Font;
+use super::layout::Layout;
+use super::{CODE_COLOR, Fonts, LINE_SPACING, LINK_COLOR, MUTED_COLOR, Style};
+use comrak::nodes::{AstNode, NodeValue};
+
+/// Inline content flattened to styled text; `Break` is a hard line break
+#[derive(Debug, Clone)]
+pub enum Run {
+    Text(String, Style),
+    Break,
+    Image(ImageRef),
+}
+
+impl Run {
+    /// The text a run contributes to a line; images outside paragraphs show their label
+    pub fn text(&self) -> Option<(&str, &Style)> {
+        match self {
+            Run::Text(text, style) => Some((text, style)),
+            Run::Image(image) => Some((&image.label, &image.style)),
+            Run::Break => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageRef {
+    pub url: String,
+    pub caption: String,
+    /// Shown instead when the image can't be placed
+    pub label: String,
+    pub style: Style,
+}
+
+#[derive(Debug, Clone)]
+pub struct Piece {
+    pub text: String,
+    pub style: Style,
+    pub width: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Line {
+    pub pieces: Vec<Piece>,
+    pub width: f32,
+    /// Largest font size on the line
+    pub size: f32,
+}
+
+impl Line {
+    pub fn push(&mut self, fonts: &Fonts, text: &str, style: &Style) {
+        let width = fonts.width(text, style);
+        self.width += width;
+        self.size = self.size.max(style.size);
+        match self.pieces.last_mut() {
+            Some(last) if last.style == *style => {
+                last.text.push_str(text);
+                last.width += width;
+            },
+            _ => self.pieces.push(Piece {
+                text: text.to_string(),
+                style: style.clone(),
+                width,
+            }),
+        }
+    }
+
+    pub fn height(&self) -> f32 {
+        self.size * LINE_SPACING
+    }
+}
+
+/// Splits text into alternating runs of whitespace and non-whitespace
+fn tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_space = None;
+    for (i, c) in text.char_indices() {
+        let space = c.is_whitespace();
+        if in_space.is_some_and(|s| s != space) {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        in_space = Some(space);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// Byte length of the longest prefix of `word` that fits in `max_width`; at least one char
+pub fn fitting_prefix(fonts: &Fonts, word: &str, style: &Style, max_width: f32) -> usize {
+    let mut width = 0.0;
+    for (i, c) in word.char_indices() {
+        width += fonts.width(c.encode_utf8(&mut [0; 4]), style);
+        if width > max_width && i > 0 {
+            return i;
+        }
+    }
+    word.len()
+}
+
+/// Greedy line breaking at spaces; words wider than a line are split between characters
+pub fn wrap(fonts: &Fonts, runs: &[Run], max_width: f32, base_size: f32) -> Vec<Line> {
+    let new_line = || Line {
+        size: base_size,
+        ..Default::default()
+    };
+    let mut lines = Vec::new();
+    let mut line = new_line();
+    let mut pending_space: Option<&Style> = None;
+
+    for run in runs {
+        let Some((text, style)) = run.text() else {
+            lines.push(std::mem::replace(&mut line, new_line()));
+            pending_space = None;
+            continue;
+        };
+        for token in tokens(text) {
+            if token.starts_with(char::is_whitespace) {
+                if !line.pieces.is_empty() {
+                    pending_space = Some(style);
+                }
+                continue;
+            }
+            let mut word = token;
+            while !word.is_empty() {
+                let word_width = fonts.width(word, style);
+                let space_width = pending_space.map_or(0.0, |s| fonts.width(" ", s));
+                if line.width + space_width + word_width <= max_width {
+                    if let Some(space_style) = pending_space.take() {
+                        line.push(fonts, " ", space_style);
+                    }
+                    line.push(fonts, word, style);
+                    break;
+                }
+                if !line.pieces.is_empty() {
+                    lines.push(std::mem::replace(&mut line, new_line()));
+                    pending_space = None;
+                    continue;
+                }
+                let split = fitting_prefix(fonts, word, style, max_width);
+                line.push(fonts, &word[..split], style);
+                word = &word[split..];
+                if !word.is_empty() {
+                    lines.push(std::mem::replace(&mut line, new_line()));
+                }
+            }
+        }
+    }
+    if !line.pieces.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+pub fn runs_width(fonts: &Fonts, runs: &[Run]) -> f32 {
+    runs.iter()
+        .filter_map(Run::text)
+        .map(|(text, style)| fonts.width(text, style))
+        .sum()
+}
+
+/// Width of the widest single word, the narrowest a column can get without splitting words
+pub fn longest_word(fonts: &Fonts, runs: &[Run]) -> f32 {
+    runs.iter()
+        .filter_map(Run::text)
+        .map(|(text, style)| {
+            text.split_whitespace()
+                .map(|word| fonts.width(word, style))
+                .fold(0.0, f32::max)
+        })
+        .fold(0.0, f32::max)
+}
+
+fn with_font(style: &Style, font: Font) -> Style {
+    Style {
+        font,
+        ..style.clone()
+    }
+}
+
+fn code_style(style: &Style) -> Style {
+    Style {
+        font: style.font.mono(),
+        size: style.size * 0.9,
+        color: if style.link.is_some() {
+            style.color
+        } else {
+            CODE_COLOR
+        },
+        ..style.clone()
+    }
+}
+
+impl Layout<'_> {
+    pub fn inlines<'a>(&self, parent: &'a AstNode<'a>, style: &Style) -> Vec<Run> {
+        let mut runs = Vec::new();
+        for node in parent.children() {
+            self.inline(node, style, &mut runs);
+        }
+        runs
+    }
+
+    fn inline<'a>(&self, node: &'a AstNode<'a>, style: &Style, runs: &mut Vec<Run>) {
+        let value = node.data.borrow().value.clone();
+        match value {
+            NodeValue::Text(text) => runs.push(Run::Text(text.to_string(), style.clone())),
+            NodeValue::SoftBreak => runs.push(Run::Text(" ".to_string(), style.clone())),
+            NodeValue::LineBreak => runs.push(Run::Break),
+            NodeValue::Code(code) => runs.push(Run::Text(code.literal, code_style(style))),
+            NodeValue::Math(math) => runs.push(Run::Text(math.literal, code_style(style))),
+            NodeValue::Emph => self.children(node, &with_font(style, style.font.italic()), runs),
+            NodeValue::Strong => self.children(node, &with_font(style, style.font.bold()), runs),
+            NodeValue::Superscript | NodeValue::Subscript => {
+                let smaller = Style {
+                    size: style.size * 0.75,
+                    ..style.clone()
+                };
+                self.children(node, &smaller, runs);
+            },
+            NodeValue::Link(link) => {
+                let linked = Style {
+                    color: LINK_COLOR,
+                    link: Some(link.url.to_string()),
+                    ..style.clone()
+                };
+                self.children(node, &linked, runs);
+            },
+            NodeValue::WikiLink(_) => {
+                let linked = Style {
+                    color: LINK_COLOR,
+                    ..style.clone()
+                };
+                self.children(node, &linked, runs);
+            },
+            NodeValue::Image(link) => {
+                let alt: String = node
+                    .descendants()
+                    .skip(1)
+                    .filter_map(|d| match &d.data.borrow().value {
+                        NodeValue::Text(t) => Some(t.to_string()),
+                        _ => None,
+                    })
+                    .collect();
+                let label = if alt.is_empty() {
+                    "[image]".to_string()
+                } else {
+                    format!("[image: {}]", alt)
+                };
+                let muted = Style {
+                    color: MUTED_COLOR,
+                    ..with_font(style, style.font.italic())
+                };
+                let caption = if link.title.trim().is_empty() {
+                    alt
+                } else {
+                    link.title.to_string()
+                };
+                runs.push(Run::Image(ImageRef {
+                    url: link.url.to_string(),
+                    caption,
+                    label,
+                    style: muted,
+                }));
+            },
+            NodeValue::FootnoteReference(reference) => {
+                let number = self.footnotes.get(&reference.name).copied().unwrap_or(0);
+                let marker = Style {
+                    size: style.size * 0.75,
+                    color: LINK_COLOR,
+                    ..style.clone()
+                };
+                runs.push(Run::Text(format!("[{}]", number), marker));
+            },
+            NodeValue::HtmlInline(_) => {},
+            _ => self.children(node, style, runs),
+        }
+    }
+
+    fn children<'a>(&self, node: &'a AstNode<'a>, style: &Style, runs: &mut Vec<Run>) {
+        for child in node.children() {
+            self.inline(child, style, runs);
+        }
+    }
+}
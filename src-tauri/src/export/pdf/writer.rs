@@ -0,0 +1,270 @@
+use super::custom_font::EmbeddedFont;
+use super::fonts::{Font, pdf_string};
+use super::images::PdfImage;
+
+/// A clickable area on a page that opens `uri`
+#[derive(Debug, Clone)]
+pub struct LinkArea {
+    /// Lower-left and upper-right corners, in points from the bottom-left of the page
+    pub rect: [f32; 4],
+    pub uri: String,
+}
+
+/// One laid-out page: its content stream operators and link areas
+#[derive(Debug, Default)]
+pub struct Page {
+    pub content: String,
+    pub links: Vec<LinkArea>,
+}
+
+/// Name of the image at `index` in each page's resource dictionary
+pub fn image_name(index: usize) -> String {
+    format!("Im{}", index + 1)
+}
+
+/// A stream object: `dict` entries plus the length, then the data
+fn stream(dict: &str, data: &[u8]) -> Vec<u8> {
+    let separator = if dict.is_empty() { "" } else { " " };
+    let mut object = format!(
+        "<< {}{}/Length {} >>\nstream\n",
+        dict,
+        separator,
+        data.len()
+    )
+    .into_bytes();
+    object.extend_from_slice(data);
+    object.extend_from_slice(b"\nendstream");
+    object
+}
+
+/// Resource name of the embedded font
+pub const CUSTOM_FONT_NAME: &str = "FC";
+
+/// Serializes pages into a PDF file. Content streams are left uncompressed; text-only
+/// pages stay small without a deflate pass. Every page can draw any of `images`, and the
+/// embedded font when there is one.
+pub fn write_document(
+    pages: &[Page],
+    images: &[PdfImage],
+    custom_font: Option<&EmbeddedFont>,
+    width: f32,
+    height: f32,
+    title: &str,
+) -> Vec<u8> {
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    // Object numbers are 1-based; reserve the catalog, page tree and info dictionary
+    let catalog_id = 1;
+    let pages_id = 2;
+    let info_id = 3;
+    objects.resize(3, Vec::new());
+
+    let font_ids: Vec<(Font, usize)> = Font::ALL
+        .iter()
+        .map(|&font| {
+            objects.push(
+                format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                    font.base_font()
+                )
+                .into_bytes(),
+            );
+            (font, objects.len())
+        })
+        .collect();
+    let mut font_resources: String = font_ids
+        .iter()
+        .map(|(font, id)| format!("/{} {} 0 R ", font.resource_name(), id))
+        .collect();
+    if let Some(font) = custom_font {
+        let id = write_custom_font(&mut objects, font);
+        font_resources.push_str(&format!("/{} {} 0 R ", CUSTOM_FONT_NAME, id));
+    }
+
+    let mut image_resources = String::new();
+    for (index, image) in images.iter().enumerate() {
+        let mut dict = format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} /BitsPerComponent 8 /Filter /{}",
+            image.width, image.height, image.color_space, image.filter
+        );
+        if let Some(alpha) = &image.alpha {
+            objects.push(stream(
+                &format!(
+                    "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceGray /BitsPerComponent 8 /Filter /FlateDecode",
+                    image.width, image.height
+                ),
+                alpha,
+            ));
+            dict.push_str(&format!(" /SMask {} 0 R", objects.len()));
+        }
+        if image.inverted {
+            dict.push_str(" /Decode [1 0 1 0 1 0 1 0]");
+        }
+        objects.push(stream(&dict, &image.data));
+        image_resources.push_str(&format!("/{} {} 0 R ", image_name(index), objects.len()));
+    }
+    let xobjects = if images.is_empty() {
+        String::new()
+    } else {
+        format!(" /XObject << {}>>", image_resources)
+    };
+
+    let mut page_ids = Vec::with_capacity(pages.len());
+    for page in pages {
+        objects.push(stream("", page.content.as_bytes()));
+        let content_id = objects.len();
+
+        let annot_ids: Vec<usize> = page
+            .links
+            .iter()
+            .map(|link| {
+                let [x1, y1, x2, y2] = link.rect;
+                objects.push(
+                    format!(
+                        "<< /Type /Annot /Subtype /Link /Rect [{:.2} {:.2} {:.2} {:.2}] /Border [0 0 0] /A << /S /URI /URI {} >> >>",
+                        x1,
+                        y1,
+                        x2,
+                        y2,
+                        pdf_string(&link.uri)
+                    )
+                    .into_bytes(),
+                );
+                objects.len()
+            })
+            .collect();
+        let annots = if annot_ids.is_empty() {
+            String::new()
+        } else {
+            let refs: Vec<String> = annot_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+            format!(" /Annots [{}]", refs.join(" "))
+        };
+
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /Font << {}>>{} >> /Contents {} 0 R{} >>",
+                pages_id, width, height, font_resources, xobjects, content_id, annots
+            )
+            .into_bytes(),
+        );
+        page_ids.push(objects.len());
+    }
+
+    let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+    objects[catalog_id - 1] = format!("<< /Type /Catalog /Pages {} 0 R >>", pages_id);
+    objects[pages_id - 1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        page_ids.len()
+    );
+    objects[info_id - 1] = format!("<< /Title {} /Producer (MarkdownRS) >>", text_string(title));
+
+    let mut out = Vec::new();
+    // OpenType font files need 1.6
+    let version = if custom_font.is_some_and(|f| f.is_cff) {
+        "1.6"
+    } else {
+        "1.4"
+    };
+    out.extend_from_slice(format!("%PDF-{}\n", version).as_bytes());
+    // Binary comment so transfer tools treat the file as binary
+    out.extend_from_slice(b"%\xE2\xE3\xCF\xD3\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend_from_slice(object);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref_offset = out.len();
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        xref.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    xref.push_str(&format!(
+        "trailer\n<< /Size {} /Root {} 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        catalog_id,
+        info_id,
+        xref_offset
+    ));
+    out.extend_from_slice(xref.as_bytes());
+    out
+}
+
+/// Writes a Type 0 font with Identity-H encoding over the subset, returning its object
+/// number. Codes in content streams are the subset's glyph ids.
+fn write_custom_font(objects: &mut Vec<Vec<u8>>, font: &EmbeddedFont) -> usize {
+    let (file_key, file_dict, cid_subtype) = if font.is_cff {
+        (
+            "FontFile3",
+            "/Subtype /OpenType /Filter /FlateDecode",
+            "CIDFontType0",
+        )
+    } else {
+        ("FontFile2", "/Filter /FlateDecode", "CIDFontType2")
+    };
+    objects.push(stream(file_dict, &font.program));
+    let file_id = objects.len();
+
+    let [x1, y1, x2, y2] = font.bbox;
+    objects.push(
+        format!(
+            "<< /Type /FontDescriptor /FontName /{} /Flags {} /FontBBox [{} {} {} {}] /ItalicAngle {:.1} /Ascent {} /Descent {} /CapHeight {} /StemV 80 /{} {} 0 R >>",
+            font.base_font,
+            font.flags,
+            x1,
+            y1,
+            x2,
+            y2,
+            font.italic_angle,
+            font.ascent,
+            font.descent,
+            font.cap_height,
+            file_key,
+            file_id
+        )
+        .into_bytes(),
+    );
+    let descriptor_id = objects.len();
+
+    let widths: String = font
+        .widths
+        .iter()
+        .map(|(id, width)| format!("{} [{}] ", id, width))
+        .collect();
+    let gid_map = if font.is_cff {
+        ""
+    } else {
+        " /CIDToGIDMap /Identity"
+    };
+    objects.push(
+        format!(
+            "<< /Type /Font /Subtype /{} /BaseFont /{} /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> /FontDescriptor {} 0 R /DW 1000 /W [{}]{} >>",
+            cid_subtype, font.base_font, descriptor_id, widths, gid_map
+        )
+        .into_bytes(),
+    );
+    let cid_font_id = objects.len();
+
+    objects.push(stream("", font.to_unicode.as_bytes()));
+    let to_unicode_id = objects.len();
+
+    objects.push(
+        format!(
+            "<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H /DescendantFonts [{} 0 R] /ToUnicode {} 0 R >>",
+            font.base_font, cid_font_id, to_unicode_id
+        )
+        .into_bytes(),
+    );
+    objects.len()
+}
+
+/// A document-information string; UTF-16 so titles outside WinAnsi survive
+fn text_string(text: &str) -> String {
+    let hex: String = text
+        .encode_utf16()
+        .map(|unit| format!("{:04X}", unit))
+        .collect();
+    format!("<FEFF{}>", hex)
+}
@@ -0,0 +1,137 @@
+use crate::utils::sanitize_file_name;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const PRESETS_SETTING_KEY: &str = "exportPresets";
+const DEFAULT_OUTPUT_FOLDER: &str = "{dir}";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Html,
+    Pdf,
+    MediaWiki,
+    Rst,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+            Self::MediaWiki => "wiki",
+            Self::Rst => "rst",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PageSetup {
+    pub paper: String,
+    pub landscape: bool,
+}
+
+impl Default for PageSetup {
+    fn default() -> Self {
+        Self {
+            paper: "A4".to_string(),
+            landscape: false,
+        }
+    }
+}
+
+impl PageSetup {
+    pub fn to_css(&self) -> String {
+        let orientation = if self.landscape {
+            "landscape"
+        } else {
+            "portrait"
+        };
+        format!("@page {{ size: {} {}; }}\n", self.paper, orientation)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportPreset {
+    pub name: String,
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub theme: Option<String>,
+    #[serde(default)]
+    pub page_setup: PageSetup,
+    /// Path to an HTML template using `{{title}}`, `{{css}}` and `{{content}}`
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Folder pattern supporting `{dir}` (source folder), `{name}` and `{date}`
+    #[serde(default)]
+    pub output_folder: Option<String>,
+    #[serde(default)]
+    pub flavor: Option<String>,
+}
+
+pub fn find_preset<'a>(presets: &'a [ExportPreset], name: &str) -> Option<&'a ExportPreset> {
+    presets
+        .iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// Inserts or replaces a preset by name (case-insensitive)
+pub fn upsert_preset(presets: &mut Vec<ExportPreset>, preset: ExportPreset) -> Result<()> {
+    if preset.name.trim().is_empty() {
+        return Err(anyhow!("Preset name cannot be empty"));
+    }
+    match presets
+        .iter_mut()
+        .find(|p| p.name.eq_ignore_ascii_case(preset.name.trim()))
+    {
+        Some(existing) => *existing = preset,
+        None => presets.push(preset),
+    }
+    Ok(())
+}
+
+/// Resolves the output file for a preset export from its folder pattern and the source
+/// document (or title for unsaved documents).
+pub fn resolve_output_path(
+    preset: &ExportPreset,
+    source_path: Option<&str>,
+    title: &str,
+) -> Result<PathBuf> {
+    let source = source_path.map(Path::new);
+    let stem = source
+        .and_then(|p| p.file_stem())
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| sanitize_file_name(title));
+
+    let pattern = preset
+        .output_folder
+        .as_deref()
+        .filter(|f| !f.trim().is_empty())
+        .unwrap_or(DEFAULT_OUTPUT_FOLDER);
+
+    let source_dir = source.and_then(|p| p.parent());
+    if pattern.contains("{dir}") && source_dir.is_none() {
+        return Err(anyhow!(
+            "Preset '{}' exports next to the document; save the document first",
+            preset.name
+        ));
+    }
+
+    let folder = pattern
+        .replace(
+            "{dir}",
+            &source_dir
+                .map(|d| d.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        )
+        .replace("{name}", &stem)
+        .replace(
+            "{date}",
+            &chrono::Local::now().format("%Y-%m-%d").to_string(),
+        );
+
+    Ok(PathBuf::from(folder).join(format!("{}.{}", stem, preset.format.extension())))
+}
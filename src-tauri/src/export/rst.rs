@@ -0,0 +1,310 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::sections::{self, TextOptions};
+use comrak::nodes::{AlertType, AstNode, ListType, NodeValue};
+use comrak::{Arena, parse_document};
+use unicode_width::UnicodeWidthStr;
+
+/// Underline characters for heading levels 1-6, following the Python docs convention
+const HEADING_CHARS: [char; 6] = ['=', '-', '~', '^', '"', '\''];
+const INDENT: &str = "   ";
+
+#[derive(Default)]
+struct Writer {
+    /// Substitution definitions for inline images, emitted after the block using them
+    substitutions: Vec<String>,
+    image_count: usize,
+}
+
+/// Converts markdown to reStructuredText for Sphinx. Tables become `list-table`
+/// directives, alerts become admonitions, and front matter is dropped.
+pub fn to_rst(content: &str, flavor: MarkdownFlavor) -> String {
+    let arena = Arena::new();
+    let root = parse_document(&arena, content, &flavor.to_comrak_options());
+
+    let mut writer = Writer::default();
+    let mut out = writer.blocks(root);
+    out.push('\n');
+    out
+}
+
+fn indent(text: &str, prefix: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Indents all but the first line to line up under a list marker
+fn hang(marker: &str, text: &str) -> String {
+    let pad = " ".repeat(marker.width());
+    let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+    if rest.is_empty() {
+        format!("{}{}", marker, first)
+    } else {
+        format!("{}{}\n{}", marker, first, indent(rest, &pad))
+    }
+}
+
+fn directive(name: &str, argument: &str, body: &str) -> String {
+    let head = format!(
+        ".. {}::{}",
+        name,
+        if argument.is_empty() { "" } else { " " }
+    );
+    format!("{}{}\n\n{}", head, argument, indent(body, INDENT))
+}
+
+impl Writer {
+    fn blocks<'a>(&mut self, parent: &'a AstNode<'a>) -> String {
+        parent
+            .children()
+            .filter_map(|node| Some(self.block(node)).filter(|b| !b.is_empty()))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn block<'a>(&mut self, node: &'a AstNode<'a>) -> String {
+        let mut out = match &node.data.borrow().value {
+            NodeValue::Heading(heading) => {
+                let title = self.inlines(node);
+                let level = (heading.level as usize).clamp(1, HEADING_CHARS.len());
+                let underline = HEADING_CHARS[level - 1]
+                    .to_string()
+                    .repeat(title.width().max(1));
+                format!("{}\n{}", title, underline)
+            },
+            NodeValue::Paragraph => match standalone_image(node) {
+                Some((url, alt)) => format!(".. image:: {}\n{}:alt: {}", url, INDENT, alt),
+                None => self.inlines(node),
+            },
+            NodeValue::List(list) => {
+                let ordered = list.list_type == ListType::Ordered;
+                let mut number = list.start;
+                node.children()
+                    .map(|item| {
+                        let marker = if ordered {
+                            number += 1;
+                            format!("{}. ", number - 1)
+                        } else {
+                            "- ".to_string()
+                        };
+                        let checkbox = match &item.data.borrow().value {
+                            NodeValue::TaskItem(task) if task.symbol.is_some() => "☑ ",
+                            NodeValue::TaskItem(_) => "☐ ",
+                            _ => "",
+                        };
+                        hang(&marker, &format!("{}{}", checkbox, self.blocks(item)))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n\n")
+            },
+            NodeValue::CodeBlock(code) => {
+                let lang = code.info.split_whitespace().next().unwrap_or("");
+                let literal = code.literal.trim_end_matches('\n');
+                if lang.is_empty() {
+                    format!("::\n\n{}", indent(literal, INDENT))
+                } else {
+                    directive("code-block", lang, literal)
+                }
+            },
+            NodeValue::HtmlBlock(html) => directive("raw", "html", html.literal.trim_end()),
+            NodeValue::ThematicBreak => "----".to_string(),
+            NodeValue::BlockQuote | NodeValue::MultilineBlockQuote(_) => {
+                indent(&self.blocks(node), INDENT)
+            },
+            NodeValue::Alert(alert) => {
+                let kind = match alert.alert_type {
+                    AlertType::Note => "note",
+                    AlertType::Tip => "tip",
+                    AlertType::Important => "important",
+                    AlertType::Warning => "warning",
+                    AlertType::Caution => "caution",
+                };
+                match &alert.title {
+                    Some(title) => directive("admonition", title, &self.blocks(node)),
+                    None => directive(kind, "", &self.blocks(node)),
+                }
+            },
+            NodeValue::Table(_) => self.table(node),
+            NodeValue::Math(math) => directive("math", "", math.literal.trim()),
+            NodeValue::DescriptionItem(_) => node
+                .children()
+                .map(|part| {
+                    if matches!(part.data.borrow().value, NodeValue::DescriptionTerm) {
+                        part.children()
+                            .map(|c| self.inlines(c))
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    } else {
+                        indent(&self.blocks(part), INDENT)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            NodeValue::FootnoteDefinition(def) => {
+                let label = format!(".. [#{}] ", footnote_label(&def.name));
+                hang(&label, &self.blocks(node))
+            },
+            NodeValue::FrontMatter(_) => String::new(),
+            _ => self.blocks(node),
+        };
+
+        let is_container = matches!(
+            node.data.borrow().value,
+            NodeValue::Paragraph | NodeValue::Heading(_) | NodeValue::Table(_)
+        );
+        if is_container && !self.substitutions.is_empty() {
+            out.push_str("\n\n");
+            out.push_str(&self.substitutions.join("\n"));
+            self.substitutions.clear();
+        }
+        out
+    }
+
+    fn table<'a>(&mut self, table: &'a AstNode<'a>) -> String {
+        let header_rows = table
+            .children()
+            .filter(|row| matches!(row.data.borrow().value, NodeValue::TableRow(true)))
+            .count();
+        let rows: Vec<String> = table
+            .children()
+            .map(|row| {
+                let cells: Vec<String> = row
+                    .children()
+                    .map(|cell| hang("  - ", &self.inlines(cell)))
+                    .collect();
+                let row = cells.join("\n");
+                // The first cell's "  - " shares a line with the row's "* "
+                format!("* {}", row.get(2..).unwrap_or_default())
+            })
+            .collect();
+
+        let mut options = String::new();
+        if header_rows > 0 {
+            options = format!("{}:header-rows: {}\n", INDENT, header_rows);
+        }
+        format!(
+            ".. list-table::\n{}\n{}",
+            options,
+            indent(&rows.join("\n"), INDENT)
+        )
+    }
+
+    fn inlines<'a>(&mut self, parent: &'a AstNode<'a>) -> String {
+        let mut out = String::new();
+        for node in parent.children() {
+            self.inline(node, &mut out);
+        }
+        out
+    }
+
+    fn inline<'a>(&mut self, node: &'a AstNode<'a>, out: &mut String) {
+        let value = node.data.borrow().value.clone();
+        match value {
+            NodeValue::Text(text) => out.push_str(&escape(&text)),
+            // reST has no inline line break; a line block would split the paragraph
+            NodeValue::SoftBreak | NodeValue::LineBreak => out.push('\n'),
+            NodeValue::Code(code) => out.push_str(&format!("``{}``", code.literal)),
+            NodeValue::Emph => out.push_str(&format!("*{}*", plain_text(node))),
+            NodeValue::Strong => out.push_str(&format!("**{}**", plain_text(node))),
+            NodeValue::Superscript => out.push_str(&format!(":sup:`{}`", plain_text(node))),
+            NodeValue::Subscript => out.push_str(&format!(":sub:`{}`", plain_text(node))),
+            NodeValue::Math(math) => out.push_str(&format!(":math:`{}`", math.literal)),
+            // Inline raw HTML needs a custom role in reST, so the tags are dropped
+            NodeValue::HtmlInline(_) => {},
+            NodeValue::Link(link) => {
+                let text = plain_text(node);
+                if text.is_empty() || text == *link.url {
+                    out.push_str(&link.url);
+                } else {
+                    // Anonymous references, so repeated link texts don't clash
+                    out.push_str(&format!("`{} <{}>`__", text, link.url));
+                }
+            },
+            NodeValue::WikiLink(link) => {
+                out.push_str(&format!(":doc:`{} <{}>`", plain_text(node), link.url))
+            },
+            NodeValue::Image(link) => {
+                self.image_count += 1;
+                let name = format!("image{}", self.image_count);
+                let mut definition = format!(".. |{}| image:: {}", name, link.url);
+                let alt = plain_text(node);
+                if !alt.is_empty() {
+                    definition.push_str(&format!("\n{}:alt: {}", INDENT, alt));
+                }
+                self.substitutions.push(definition);
+                out.push_str(&format!("|{}|", name));
+            },
+            NodeValue::FootnoteReference(reference) => {
+                // An escaped space separates the reference without rendering a gap
+                if !out.is_empty() && !out.ends_with(char::is_whitespace) {
+                    out.push_str("\\ ");
+                }
+                out.push_str(&format!("[#{}]_", footnote_label(&reference.name)));
+            },
+            _ => {
+                for child in node.children() {
+                    self.inline(child, out);
+                }
+            },
+        }
+    }
+}
+
+/// Paragraphs holding nothing but one image become an `image` directive
+fn standalone_image<'a>(paragraph: &'a AstNode<'a>) -> Option<(String, String)> {
+    let mut children = paragraph.children();
+    let image = children.next()?;
+    if children.next().is_some() {
+        return None;
+    }
+    match &image.data.borrow().value {
+        NodeValue::Image(link) => Some((link.url.to_string(), plain_text(image))),
+        _ => None,
+    }
+}
+
+/// reST inline markup can't nest, so formatted spans keep only their text
+fn plain_text<'a>(node: &'a AstNode<'a>) -> String {
+    let options = TextOptions {
+        image_alt: true,
+        ..Default::default()
+    };
+    escape(&sections::node_text(node, options))
+}
+
+fn footnote_label(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Escapes characters that start inline markup. `_` only ends a reference name before
+/// a non-word character, so `snake_case` is left alone.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next_is_word = chars.peek().is_some_and(|n| n.is_alphanumeric());
+        match c {
+            '\\' | '*' | '`' | '|' => out.push('\\'),
+            '_' if !next_is_word => out.push('\\'),
+            _ => {},
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,241 @@
+use crate::export::epub::REFERENCE_DEF_RE;
+use crate::export::html;
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::diagrams::DiagramSettings;
+use crate::markdown::front_matter;
+use crate::markdown::renderer::{self, MarkdownOptions};
+use crate::markdown::security::SanitizationReport;
+use anyhow::{Result, anyhow};
+use comrak::nodes::NodeValue;
+use comrak::{Arena, parse_document};
+use serde::Deserialize;
+
+/// Where a document is cut into slides
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SlideBreak {
+    /// `---` rules when the document has any, otherwise H1 and H2 headings
+    #[default]
+    Auto,
+    /// Top-level `---` rules, which are dropped
+    Rule,
+    /// Each H1 or H2 heading starts a slide
+    Heading,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SlideOptions {
+    /// Falls back to the front matter `title`
+    pub title: String,
+    pub theme: Option<String>,
+    pub split: SlideBreak,
+    pub flavor: Option<String>,
+    /// Filled in from settings, not by the caller
+    #[serde(skip)]
+    pub diagrams: Option<DiagramSettings>,
+}
+
+pub struct SlideDeck {
+    pub html: String,
+    pub slide_count: usize,
+    pub report: SanitizationReport,
+}
+
+/// Deck layout: one slide on screen at a time, scaled to the window, and one slide per
+/// page when printed. Class names follow reveal.js so its themes mostly apply.
+const SLIDES_CSS: &str = r#"html, body { height: 100%; overflow: hidden; }
+body { padding: 0; }
+.reveal { position: relative; width: 100%; height: 100%; }
+.reveal .slides > section { display: none; box-sizing: border-box; position: absolute; inset: 0; padding: 4vh 8vw; overflow: auto; font-size: clamp(16px, 2.6vmin, 40px); }
+.reveal .slides > section.present { display: flex; flex-direction: column; justify-content: center; }
+.reveal .slides > section > :first-child { margin-top: 0; }
+.reveal .slides h1 { font-size: 2.4em; border: 0; }
+.reveal .slides h2 { font-size: 1.8em; border: 0; }
+.reveal .progress { position: fixed; left: 0; bottom: 0; width: 100%; height: 4px; }
+.reveal .progress span { display: block; height: 100%; width: 0; background-color: var(--preview-fg-link, #2563eb); transition: width 0.2s; }
+.reveal .slide-number { position: fixed; right: 1em; bottom: 0.75em; font-size: 0.8em; opacity: 0.6; }
+@media print {
+    html, body { height: auto; overflow: visible; }
+    .reveal .slides > section { display: flex; position: relative; inset: auto; min-height: 100vh; break-after: page; }
+    .reveal .progress, .reveal .slide-number { display: none; }
+}
+"#;
+
+/// Keyboard, click and `#/n` navigation, as in reveal.js
+const SLIDES_SCRIPT: &str = r#"(function () {
+    var slides = document.querySelectorAll('.reveal .slides > section');
+    var bar = document.querySelector('.reveal .progress span');
+    var number = document.querySelector('.reveal .slide-number');
+    var current = -1;
+    function show(index) {
+        index = Math.max(0, Math.min(slides.length - 1, index));
+        if (index === current) return;
+        if (current >= 0) slides[current].classList.remove('present');
+        current = index;
+        slides[current].classList.add('present');
+        slides[current].scrollTop = 0;
+        bar.style.width = (slides.length > 1 ? (current / (slides.length - 1)) * 100 : 100) + '%';
+        number.textContent = (current + 1) + ' / ' + slides.length;
+        history.replaceState(null, '', '#/' + (current + 1));
+    }
+    function fromHash() {
+        var match = /^#\/(\d+)/.exec(location.hash);
+        show(match ? parseInt(match[1], 10) - 1 : 0);
+    }
+    document.addEventListener('keydown', function (e) {
+        if (e.altKey || e.ctrlKey || e.metaKey) return;
+        switch (e.key) {
+            case 'ArrowRight': case 'ArrowDown': case 'PageDown': case ' ': case 'n':
+                show(current + 1); break;
+            case 'ArrowLeft': case 'ArrowUp': case 'PageUp': case 'Backspace': case 'p':
+                show(current - 1); break;
+            case 'Home': show(0); break;
+            case 'End': show(slides.length - 1); break;
+            case 'f': document.documentElement.requestFullscreen && document.documentElement.requestFullscreen(); return;
+            default: return;
+        }
+        e.preventDefault();
+    });
+    document.addEventListener('click', function (e) {
+        if (e.target.closest('a, button, input, summary')) return;
+        show(e.clientX < window.innerWidth / 3 ? current - 1 : current + 1);
+    });
+    window.addEventListener('hashchange', fromHash);
+    if (slides.length) fromHash();
+})();"#;
+
+const SLIDES_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{{title}}</title>
+    <style>
+{{css}}
+    </style>
+</head>
+<body>
+<div class="reveal">
+<div class="slides">
+{{content}}
+</div>
+<div class="progress"><span></span></div>
+<div class="slide-number"></div>
+</div>
+<script>
+{{script}}
+</script>
+</body>
+</html>"#;
+
+/// Builds a standalone HTML slide deck: the document is cut at `---` rules or H1/H2
+/// headings, each part rendered as one `<section>`, styled with `theme_css`.
+pub fn build_deck(content: &str, options: &SlideOptions, theme_css: &str) -> Result<SlideDeck> {
+    let flavor = MarkdownFlavor::from_option_str(options.flavor.clone());
+    let title = Some(options.title.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .or_else(|| {
+            front_matter::parse(content)
+                .as_ref()
+                .and_then(|m| front_matter::string_field(m, "title"))
+        })
+        .unwrap_or_else(|| "Slides".to_string());
+    let body = front_matter::split(content).map_or(content, |fm| fm.body);
+
+    let slides = split_slides(body, flavor, options.split);
+    if slides.is_empty() {
+        return Err(anyhow!("Document is empty"));
+    }
+
+    let render_options = MarkdownOptions {
+        flavor,
+        diagrams: options.diagrams.clone(),
+        ..Default::default()
+    };
+    let mut report = SanitizationReport::default();
+    let mut sections = String::new();
+    for (index, slide) in slides.iter().enumerate() {
+        let fragment = renderer::render_fragment(slide, &render_options)?;
+        report.merge(&fragment.sanitization);
+        sections.push_str(&format!(
+            "<section id=\"slide-{}\">\n{}</section>\n",
+            index + 1,
+            fragment.html
+        ));
+    }
+
+    let css = format!("{}{}{}", html::BASE_CSS, theme_css, SLIDES_CSS);
+    let template = SLIDES_TEMPLATE.replace("{{script}}", SLIDES_SCRIPT);
+    let document = html::build_document(&template, &title, "", &css, &sections);
+    Ok(SlideDeck {
+        html: document,
+        slide_count: slides.len(),
+        report,
+    })
+}
+
+/// The markdown of each slide. Link reference definitions are copied into every slide so
+/// references resolve wherever the definition lives.
+fn split_slides(body: &str, flavor: MarkdownFlavor, split: SlideBreak) -> Vec<String> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, body, &flavor.to_comrak_options());
+    // (0-based line, is a rule)
+    let breaks: Vec<(usize, bool)> = root
+        .children()
+        .filter_map(|node| {
+            let data = node.data.borrow();
+            let line = data.sourcepos.start.line.saturating_sub(1);
+            match &data.value {
+                NodeValue::ThematicBreak => Some((line, true)),
+                NodeValue::Heading(heading) if heading.level <= 2 => Some((line, false)),
+                _ => None,
+            }
+        })
+        .collect();
+    let by_rule = match split {
+        SlideBreak::Auto => breaks.iter().any(|&(_, rule)| rule),
+        SlideBreak::Rule => true,
+        SlideBreak::Heading => false,
+    };
+
+    let lines: Vec<&str> = body.lines().collect();
+    // (first line, end line) of each slide; a rule's own line belongs to neither side
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for &(line, rule) in breaks.iter().filter(|&&(_, rule)| rule == by_rule) {
+        ranges.push((start, line));
+        start = if rule { line + 1 } else { line };
+    }
+    ranges.push((start, lines.len()));
+
+    let definitions: Vec<(usize, &str)> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| REFERENCE_DEF_RE.is_match(line))
+        .map(|(index, line)| (index, *line))
+        .collect();
+
+    ranges
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let mut markdown = lines[start..end].join("\n");
+            let is_blank = markdown
+                .lines()
+                .all(|line| line.trim().is_empty() || REFERENCE_DEF_RE.is_match(line));
+            if is_blank {
+                return None;
+            }
+            let borrowed: Vec<&str> = definitions
+                .iter()
+                .filter(|(index, _)| !(start..end).contains(index))
+                .map(|(_, line)| *line)
+                .collect();
+            if !borrowed.is_empty() {
+                markdown.push_str("\n\n");
+                markdown.push_str(&borrowed.join("\n"));
+            }
+            Some(markdown)
+        })
+        .collect()
+}
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, TryLockError};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Sits next to `session.db`; whoever holds the OS lock on it is the only instance writing
+/// the folder. The lock goes away with the process, so a crash never leaves it behind.
+const LOCK_FILE: &str = "instance.lock";
+/// Who holds the lock, for showing to the user; never used to decide ownership
+const OWNER_FILE: &str = "instance.json";
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The locked file, kept open for as long as this instance owns the folder
+static HELD: Mutex<Option<File>> = Mutex::new(None);
+
+/// Contents of the owner file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockOwner {
+    pub pid: u32,
+    /// RFC 3339 local time the instance started
+    pub started: String,
+    /// Unix seconds of the owner's last heartbeat
+    pub heartbeat: u64,
+    pub exe: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct InstanceStatus {
+    /// This instance holds the lock; when false the database is open read-only
+    pub owner: bool,
+    pub lock_path: String,
+    /// The running instance that holds the lock instead of this one
+    pub held_by: Option<LockOwner>,
+    /// An instance that exited without releasing the folder, found when taking it over
+    pub recovered: Option<LockOwner>,
+    /// The lock was taken after launch, so the database only becomes writable on restart
+    pub restart_required: bool,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+impl LockOwner {
+    fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            started: chrono::Local::now().to_rfc3339(),
+            heartbeat: unix_now(),
+            exe: std::env::current_exe()
+                .ok()
+                .map(|p| p.to_string_lossy().to_string()),
+        }
+    }
+
+    fn is_current(&self) -> bool {
+        self.pid == std::process::id()
+    }
+}
+
+fn owner_path(lock_path: &Path) -> PathBuf {
+    lock_path.with_file_name(OWNER_FILE)
+}
+
+fn read_owner(path: &Path) -> Option<LockOwner> {
+    let text = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text)
+        .map_err(|e| log::warn!("Ignoring unreadable instance owner file: {}", e))
+        .ok()
+}
+
+/// Replaces the owner file in one step, so another instance never reads half of it
+fn write_owner(path: &Path, owner: &LockOwner) -> std::io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec(owner)?)?;
+    fs::rename(&tmp, path)
+}
+
+/// Takes the lock in `dir` unless a running instance holds it. Taking the lock is one OS
+/// call, so two instances starting together can't both win, and a lock left by a crashed
+/// instance is already free. When the folder can't be locked at all, this instance
+/// carries on as the owner rather than locking the user out.
+pub fn acquire(dir: &Path) -> InstanceStatus {
+    let path = dir.join(LOCK_FILE);
+    let mut status = InstanceStatus {
+        lock_path: path.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+
+    let file = match fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+    {
+        Ok(file) => file,
+        Err(e) => {
+            log::warn!("Failed to open instance lock, continuing without it: {}", e);
+            status.owner = true;
+            return status;
+        },
+    };
+
+    match file.try_lock() {
+        Ok(()) => {
+            // Released cleanly, the owner file is gone; one left behind is from a crash
+            let owner_file = owner_path(&path);
+            status.recovered = read_owner(&owner_file).filter(|owner| !owner.is_current());
+            if let Err(e) = write_owner(&owner_file, &LockOwner::current()) {
+                log::warn!("Failed to write instance owner file: {}", e);
+            }
+            *HELD.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+            status.owner = true;
+        },
+        Err(TryLockError::WouldBlock) => {
+            status.held_by = read_owner(&owner_path(&path));
+        },
+        Err(TryLockError::Error(e)) => {
+            log::warn!("Failed to lock instance lock, continuing without it: {}", e);
+            status.owner = true;
+        },
+    }
+    status
+}
+
+/// Takes over the data folder for an instance that started while another held it, once
+/// that one has exited. Refuses while the holder is still running.
+pub fn recover(status: &mut InstanceStatus) -> Result<(), String> {
+    if status.owner {
+        return Ok(());
+    }
+    let path = PathBuf::from(&status.lock_path);
+    let Some(dir) = path.parent() else {
+        return Err("Instance lock has no folder".to_string());
+    };
+
+    let previous = status.held_by.take();
+    let acquired = acquire(dir);
+    if !acquired.owner {
+        let message = match &acquired.held_by {
+            Some(owner) => format!(
+                "The data folder is still in use by another instance (process {}, started {})",
+                owner.pid, owner.started
+            ),
+            None => "The data folder is still in use by another instance".to_string(),
+        };
+        *status = acquired;
+        return Err(message);
+    }
+    *status = acquired;
+    status.recovered = status.recovered.take().or(previous);
+    status.restart_required = true;
+    Ok(())
+}
+
+/// Refreshes the heartbeat in the owner file. Returns false once the file names another
+/// instance, which stops the heartbeat.
+fn heartbeat(path: &Path) -> bool {
+    let Some(mut owner) = read_owner(path).filter(LockOwner::is_current) else {
+        log::warn!("Instance owner file was replaced; no longer refreshing it");
+        return false;
+    };
+    owner.heartbeat = unix_now();
+    if let Err(e) = write_owner(path, &owner) {
+        log::warn!("Failed to refresh instance owner file: {}", e);
+    }
+    true
+}
+
+pub async fn run_heartbeat(lock_path: PathBuf) {
+    let owner_file = owner_path(&lock_path);
+    let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+    loop {
+        interval.tick().await;
+        if crate::shutdown::is_finished() || !heartbeat(&owner_file) {
+            break;
+        }
+    }
+}
+
+/// Gives up the lock if this instance holds it; part of the shutdown pipeline. The lock
+/// file itself stays: removing it while another instance has it open would let a third
+/// lock a new file at the same path.
+pub fn release(lock_path: &Path) {
+    let owner_file = owner_path(lock_path);
+    if read_owner(&owner_file).is_some_and(|owner| owner.is_current())
+        && let Err(e) = fs::remove_file(&owner_file)
+    {
+        log::warn!("Failed to remove instance owner file: {}", e);
+    }
+    if let Some(file) = HELD.lock().unwrap_or_else(|e| e.into_inner()).take()
+        && let Err(e) = file.unlock()
+    {
+        log::warn!("Failed to release instance lock: {}", e);
+    }
+}
@@ -0,0 +1,118 @@
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri_plugin_log::{Builder, RotationStrategy, Target, TargetKind};
+
+pub const LOG_DIR_NAME: &str = "Logs";
+pub const LOG_FILE_NAME: &str = "markdown-rs";
+const DEFAULT_LEVEL: &str = "info";
+
+/// Logging keys from settings.toml. Read once at startup; changes apply on restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogSettings {
+    #[serde(rename = "logLevel")]
+    pub level: String,
+    /// "keep-one" overwrites the file when full, "keep-some" keeps `retention` rotated
+    /// files, "keep-all" never deletes
+    #[serde(rename = "logRotation")]
+    pub rotation: String,
+    #[serde(rename = "logMaxFileSizeMb")]
+    pub max_file_size_mb: u32,
+    #[serde(rename = "logRetention")]
+    pub retention: usize,
+    /// Module path to level, e.g. `"markdown_rs::commands::session" = "warn"`
+    #[serde(rename = "logModuleLevels")]
+    pub module_levels: HashMap<String, String>,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_LEVEL.to_string(),
+            rotation: "keep-one".to_string(),
+            max_file_size_mb: 10,
+            retention: 5,
+            module_levels: HashMap::new(),
+        }
+    }
+}
+
+impl LogSettings {
+    fn rotation_strategy(&self) -> RotationStrategy {
+        match self.rotation.as_str() {
+            "keep-all" => RotationStrategy::KeepAll,
+            "keep-some" => RotationStrategy::KeepSome(self.retention.max(1)),
+            _ => RotationStrategy::KeepOne,
+        }
+    }
+}
+
+pub fn parse_level(level: &str) -> LevelFilter {
+    match level.to_lowercase().as_str() {
+        "error" => LevelFilter::Error,
+        "warn" | "warning" => LevelFilter::Warn,
+        "info" => LevelFilter::Info,
+        "trace" => LevelFilter::Trace,
+        "off" => LevelFilter::Off,
+        _ => LevelFilter::Debug,
+    }
+}
+
+/// Logging settings from settings.toml, falling back to defaults when the file is missing
+/// or unreadable. Runs before the logger exists, so it reports to stderr.
+pub fn read_startup_settings(config_path: &Path) -> LogSettings {
+    if !config_path.exists() {
+        return LogSettings::default();
+    }
+
+    let raw_bytes = match std::fs::read(config_path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!(
+                "[WARN] Failed to read settings.toml: {} - Using default log settings",
+                e
+            );
+            return LogSettings::default();
+        },
+    };
+    let content = crate::utils::read_text_with_bom_detection(&raw_bytes);
+    toml::from_str::<LogSettings>(&content).unwrap_or_else(|e| {
+        eprintln!(
+            "[WARN] Failed to parse settings.toml: {} - Using default log settings",
+            e
+        );
+        LogSettings::default()
+    })
+}
+
+pub fn build_plugin(
+    settings: &LogSettings,
+    log_dir: PathBuf,
+) -> tauri::plugin::TauriPlugin<tauri::Wry> {
+    let level = parse_level(&settings.level);
+    let mut builder = Builder::default()
+        .level(level)
+        .level_for("tao", LevelFilter::Error)
+        .level_for("wry", LevelFilter::Error)
+        .level_for("markdown_rs", level); // Explicitly set crate level
+
+    // More specific modules win over the crate level above
+    for (module, module_level) in &settings.module_levels {
+        builder = builder.level_for(module.clone(), parse_level(module_level));
+    }
+
+    builder
+        .max_file_size(settings.max_file_size_mb.max(1) as u128 * 1024 * 1024)
+        .rotation_strategy(settings.rotation_strategy())
+        .targets([
+            Target::new(TargetKind::Stdout),
+            Target::new(TargetKind::Folder {
+                path: log_dir,
+                file_name: Some(LOG_FILE_NAME.into()),
+            }),
+            Target::new(TargetKind::Webview),
+        ])
+        .build()
+}
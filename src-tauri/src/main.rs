@@ -1,23 +1,25 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod audit;
+mod capture;
 mod commands;
 mod db;
+mod export;
+mod instance_lock;
+mod logging;
 mod markdown;
+mod preview_assets;
+mod safe_mode;
+mod shutdown;
+mod startup;
 mod state;
 mod utils;
 
-use log::LevelFilter;
 use std::fs;
-use tauri::Emitter;
 use tauri::Manager;
-use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
 use velopack::VelopackApp;
 
-fn default_log_level() -> String {
-    "info".to_string()
-}
-
 struct PortableConfig {
     is_portable: bool,
     data_dir: Option<std::path::PathBuf>,
@@ -75,25 +77,28 @@ fn main() {
         }
     }
 
+    // Created before Tauri so the report covers plugin and webview setup too
+    let profiler = startup::StartupProfiler::new();
+
     tauri::Builder::default()
-        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+        .manage(profiler)
+        .plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
             let windows = app.webview_windows();
             if let Some((_, window)) = windows.iter().next() {
                 let _ = window.set_focus();
                 let _ = window.unminimize();
 
-                if args.len() > 1 {
-                    let file_path = &args[1];
-                    log::info!("Opening file from command line: {}", file_path);
-                    let _ = window.emit("open-file-from-args", file_path);
-                }
+                // Relative arguments are relative to the second instance's directory
+                let paths =
+                    commands::open_paths::resolve_launch_paths(&args, std::path::Path::new(&cwd));
+                commands::open_paths::open_launch_paths(app, &paths);
             }
         }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_dialog::init())
-
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(
@@ -102,55 +107,69 @@ fn main() {
                 .with_filename(".window-state.json")
                 .build(),
         )
+        .register_asynchronous_uri_scheme_protocol(
+            preview_assets::SCHEME,
+            |_ctx, request, responder| {
+                tauri::async_runtime::spawn_blocking(move || {
+                    responder.respond(preview_assets::handle(&request))
+                });
+            },
+        )
         .setup(|app| {
             let app_handle = app.handle();
-            let window = app.get_webview_window("main")
+            let profiler = app.state::<startup::StartupProfiler>();
+            let window = app
+                .get_webview_window("main")
                 .ok_or("Failed to get main window")?;
 
             // Check if portable mode is enabled (set in main() before Tauri init)
             let is_portable = std::env::var("MARKDOWN_RS_PORTABLE").is_ok();
 
             // Get app directories - these will use the overridden APPDATA if in portable mode
-            let app_dir = app_handle
-                .path()
-                .app_data_dir()
-                .map_err(|e| {
-                    log::error!("Failed to get app data dir: {}", e);
-                    format!("Failed to get app data dir: {}", e)
-                })?;
-
-            let local_dir = app_handle
-                .path()
-                .app_local_data_dir()
-                .map_err(|e| {
-                    log::error!("Failed to get local data dir: {}", e);
-                    format!("Failed to get local data dir: {}", e)
-                })?;
+            let app_dir = app_handle.path().app_data_dir().map_err(|e| {
+                log::error!("Failed to get app data dir: {}", e);
+                format!("Failed to get app data dir: {}", e)
+            })?;
+
+            let local_dir = app_handle.path().app_local_data_dir().map_err(|e| {
+                log::error!("Failed to get local data dir: {}", e);
+                format!("Failed to get local data dir: {}", e)
+            })?;
 
             let db_dir = app_dir.join("Database");
-            let log_dir = local_dir.join("Logs");
+            let log_dir = local_dir.join(logging::LOG_DIR_NAME);
             let themes_dir = app_dir.join("Themes");
             let config_path = app_dir.join("settings.toml");
             let dict_path = app_dir.join("custom-spelling.dic");
 
-            for dir in [&app_dir, &local_dir, &db_dir, &log_dir, &themes_dir] {
-                if let Err(e) = fs::create_dir_all(dir) {
-                    log::warn!("Failed to create directory {:?}: {}", dir, e);
+            profiler.time("directories", || {
+                for dir in [&app_dir, &local_dir, &db_dir, &log_dir, &themes_dir] {
+                    if let Err(e) = fs::create_dir_all(dir) {
+                        log::warn!("Failed to create directory {:?}: {}", dir, e);
+                    }
                 }
-            }
+            });
 
             // Cleanup stale temp files from previous crashes (older than 1 hour)
             // Run in background to avoid blocking startup
             let cleanup_app_dir = app_dir.clone();
             let cleanup_local_dir = local_dir.clone();
+            let cleanup_handle = app_handle.clone();
             tauri::async_runtime::spawn(async move {
+                let start = std::time::Instant::now();
                 let one_hour = std::time::Duration::from_secs(3600);
                 if let Err(e) = utils::cleanup_stale_temp_files(&cleanup_app_dir, one_hour).await {
                     log::warn!("Failed to cleanup temp files in app dir: {}", e);
                 }
-                if let Err(e) = utils::cleanup_stale_temp_files(&cleanup_local_dir, one_hour).await {
+                if let Err(e) = utils::cleanup_stale_temp_files(&cleanup_local_dir, one_hour).await
+                {
                     log::warn!("Failed to cleanup temp files in local dir: {}", e);
                 }
+                cleanup_handle.state::<startup::StartupProfiler>().record(
+                    "temp file cleanup",
+                    start.elapsed(),
+                    true,
+                );
             });
 
             println!("[INFO] Portable Mode: {}", is_portable);
@@ -161,7 +180,9 @@ fn main() {
             // These contain commented-out overrides so users know how to create custom themes.
             // They do NOT contain active CSS to avoid conflicting with the app's internal styles (src/styles/variables.css).
             let themes_dir_clone = themes_dir.clone();
+            let themes_handle = app_handle.clone();
             tauri::async_runtime::spawn(async move {
+                let start = std::time::Instant::now();
                 let dark_theme_path = themes_dir_clone.join("default-dark.css");
                 let dark_theme_content = r#"/* MarkdownRS Default Dark Theme Reference
 
@@ -227,113 +248,138 @@ fn main() {
                 if let Err(e) = tokio::fs::write(&light_theme_path, light_theme_content).await {
                     log::warn!("Failed to write light theme reference: {}", e);
                 }
+                themes_handle.state::<startup::StartupProfiler>().record(
+                    "theme references",
+                    start.elapsed(),
+                    true,
+                );
             });
 
-            // Robustly read settings from the TOML file
-            let settings_level = if config_path.exists() {
-                match fs::read(&config_path) {
-                    Ok(raw_bytes) => {
-                        let content = utils::read_text_with_bom_detection(&raw_bytes);
-
-                        match toml::from_str::<toml::Value>(&content) {
-                            Ok(toml_val) => toml_val
-                                .get("logLevel")
-                                .and_then(|v| v.as_str())
-                                .map(|s| s.to_string())
-                                .unwrap_or_else(default_log_level),
-                            Err(e) => {
-                                eprintln!("[WARN] Failed to parse settings.toml: {} - Using default log level", e);
-                                default_log_level()
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("[WARN] Failed to read settings.toml: {} - Using default log level", e);
-                        default_log_level()
-                    }
-                }
-            } else {
-                default_log_level()
-            };
+            profiler.time("logger", || {
+                let log_settings = logging::read_startup_settings(&config_path);
 
-            let log_level = match settings_level.to_lowercase().as_str() {
-                "error" => LevelFilter::Error,
-                "warn" | "warning" => LevelFilter::Warn,
-                "info" => LevelFilter::Info,
-                "trace" => LevelFilter::Trace,
-                "off" => LevelFilter::Off,
-                _ => LevelFilter::Debug,
-            };
+                eprintln!(
+                    "[INFO] Initializing logger with level: {:?} (source: '{}')",
+                    logging::parse_level(&log_settings.level),
+                    log_settings.level
+                );
 
-            eprintln!(
-                "[INFO] Initializing logger with level: {:?} (source: '{}')",
-                log_level, settings_level
-            );
+                app_handle.plugin(logging::build_plugin(&log_settings, log_dir.clone()))
+            })?;
 
-            app_handle.plugin(
-                tauri_plugin_log::Builder::default()
-                    .level(log_level)
-                    .level_for("tao", LevelFilter::Error)
-                    .level_for("wry", LevelFilter::Error)
-                    .level_for("markdown_rs", log_level) // Explicitly set crate level
-                    .max_file_size(10 * 1024 * 1024) // Log file rotation: max 10MB
-                    .rotation_strategy(RotationStrategy::KeepOne)
-                    .targets([
-                        Target::new(TargetKind::Stdout),
-                        Target::new(TargetKind::Folder {
-                            path: log_dir.clone(),
-                            file_name: Some("markdown-rs".into()),
-                        }),
-                        Target::new(TargetKind::Webview),
-                    ])
-                    .build(),
-            )?;
-
-            if !dict_path.exists() && let Err(e) = fs::write(&dict_path, "") {
+            let args: Vec<String> = std::env::args().collect();
+            let safe_mode = safe_mode::begin_startup(&local_dir, &args);
+            if let Some(reason) = &safe_mode.reason {
+                log::warn!("Starting in safe mode: {}", reason);
+            }
+            if safe_mode.unclean_shutdown {
+                log::warn!("The previous session did not shut down cleanly");
+            }
+
+            if !dict_path.exists()
+                && let Err(e) = fs::write(&dict_path, "")
+            {
                 log::warn!("Failed to create custom dictionary file: {}", e);
             }
 
             let db_path = db_dir.join("session.db");
 
-            // Database initialization with corruption recovery
-            let db = match db::Database::new(db_path.clone()) {
-                Ok(db) => db,
-                Err(e) => {
-                    log::error!("Failed to initialize database: {}", e);
-                    log::warn!("Attempting database recovery...");
-
-                    if db_path.exists() {
-                        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-                        let backup_path = db_dir.join(format!("session.db.bak.{}", timestamp));
+            // Another instance on the same data folder gets the database read-only
+            let instance = instance_lock::acquire(&db_dir);
+            if let Some(owner) = &instance.held_by {
+                log::warn!(
+                    "Data folder is in use by process {} (started {}); opening read-only",
+                    owner.pid,
+                    owner.started
+                );
+            }
+            if let Some(owner) = &instance.recovered {
+                log::warn!(
+                    "Process {} exited without releasing the data folder",
+                    owner.pid
+                );
+            }
 
-                        if let Err(io_err) = fs::rename(&db_path, &backup_path) {
-                            log::error!("Failed to rename corrupted database: {}", io_err);
-                            return Err(format!("Database corruption detected. Failed to backup: {}", io_err).into());
+            // Database initialization with corruption recovery; includes migrations
+            let db_start = std::time::Instant::now();
+            let db = if !instance.owner {
+                db::Database::open_read_only(db_path.clone())
+                    .map_err(|e| format!("Failed to open database read-only: {}", e))?
+            } else {
+                match db::Database::new(db_path.clone()) {
+                    Ok(db) => db,
+                    Err(e) => {
+                        log::error!("Failed to initialize database: {}", e);
+                        log::warn!("Attempting database recovery...");
+
+                        if db_path.exists() {
+                            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+                            let backup_path = db_dir.join(format!("session.db.bak.{}", timestamp));
+
+                            if let Err(io_err) = fs::rename(&db_path, &backup_path) {
+                                log::error!("Failed to rename corrupted database: {}", io_err);
+                                return Err(format!(
+                                    "Database corruption detected. Failed to backup: {}",
+                                    io_err
+                                )
+                                .into());
+                            }
+                            log::info!("Corrupted database moved to {:?}", backup_path);
                         }
-                        log::info!("Corrupted database moved to {:?}", backup_path);
-                    }
 
-                    // Retry initialization
-                    db::Database::new(db_path).map_err(|retry_err| {
-                        log::error!("Failed to initialize fresh database: {}", retry_err);
-                        format!("Critical: Failed to create new database after corruption: {}", retry_err)
-                    })?
+                        // Retry initialization
+                        db::Database::new(db_path).map_err(|retry_err| {
+                            log::error!("Failed to initialize fresh database: {}", retry_err);
+                            format!(
+                                "Critical: Failed to create new database after corruption: {}",
+                                retry_err
+                            )
+                        })?
+                    },
                 }
             };
+            profiler.record("database", db_start.elapsed(), false);
+            let heartbeat_lock = instance
+                .owner
+                .then(|| std::path::PathBuf::from(&instance.lock_path));
 
             app.manage(state::AppState {
                 db,
                 speller: tokio::sync::Mutex::new(None),
                 custom_dict: tokio::sync::Mutex::new(std::collections::HashSet::new()),
                 spellcheck_status: tokio::sync::Mutex::new(state::SpellcheckStatus::Uninitialized),
+                loaded_dictionaries: tokio::sync::Mutex::new(Vec::new()),
+                speller_bytes: std::sync::atomic::AtomicUsize::new(0),
+                export_watches: tokio::sync::Mutex::new(std::collections::HashMap::new()),
+                safe_mode,
+                text_metrics: tokio::sync::Mutex::new(std::collections::HashMap::new()),
+                open_paths: std::sync::Mutex::new(Default::default()),
+                session_autosave: std::sync::Mutex::new(Default::default()),
+                jobs: Default::default(),
+                writing_sessions: std::sync::Mutex::new(std::collections::HashMap::new()),
+                capture_queue: std::sync::Mutex::new(Default::default()),
+                instance: std::sync::Mutex::new(instance),
             });
+            if let Some(lock_path) = heartbeat_lock {
+                tauri::async_runtime::spawn(instance_lock::run_heartbeat(lock_path));
+            }
+            tauri::async_runtime::spawn(commands::autosave::run_autosave_scheduler(
+                app_handle.clone(),
+            ));
+            tauri::async_runtime::spawn(commands::maintenance::run_vacuum_scheduler(
+                app_handle.clone(),
+            ));
+            capture::setup(app_handle);
+            commands::markdown::render::setup_diagram_events(app_handle);
 
             // Check for command-line arguments on first launch
-            let args: Vec<String> = std::env::args().collect();
-            if args.len() > 1 {
-                // args[0] is the executable path, args[1] is the file path
-                let file_path = args[1].clone();
+            let launch_paths = commands::open_paths::resolve_launch_paths(
+                &args,
+                &std::env::current_dir().unwrap_or_default(),
+            );
+            if !launch_paths.is_empty() {
                 let window_clone = window.clone();
+                let app_handle = app_handle.clone();
 
                 tauri::async_runtime::spawn(async move {
                     std::thread::sleep(std::time::Duration::from_millis(150));
@@ -341,10 +387,9 @@ fn main() {
                     std::thread::sleep(std::time::Duration::from_millis(50));
                     let _ = window_clone.set_focus();
 
-                    // Give the frontend time to initialize before sending the file path
+                    // Give the frontend time to initialize before sending the file paths
                     std::thread::sleep(std::time::Duration::from_millis(200));
-                    log::info!("Opening file from initial launch: {}", file_path);
-                    let _ = window_clone.emit("open-file-from-args", &file_path);
+                    commands::open_paths::open_launch_paths(&app_handle, &launch_paths);
                 });
             } else {
                 tauri::async_runtime::spawn(async move {
@@ -355,13 +400,52 @@ fn main() {
                 });
             }
 
+            profiler.finish_setup();
             Ok(())
         })
+        .on_window_event(|window, event| match event {
+            tauri::WindowEvent::Focused(false) => {
+                commands::autosave::request_save(
+                    window.app_handle(),
+                    commands::autosave::SaveReason::Blur,
+                );
+            },
+            // Held open until the shutdown pipeline has flushed the session
+            tauri::WindowEvent::CloseRequested { api, .. } if !shutdown::is_finished() => {
+                api.prevent_close();
+                shutdown::begin(window.app_handle(), commands::autosave::SaveReason::Close);
+            },
+            _ => {},
+        })
         .invoke_handler(tauri::generate_handler![
             commands::session::save_session,
+            commands::autosave::mark_session_dirty,
             commands::session::restore_session,
             commands::session::load_tab_content,
             commands::session::vacuum_database,
+            commands::session::create_tab_group,
+            commands::session::update_tab_group,
+            commands::session::delete_tab_group,
+            commands::session::assign_tabs_to_group,
+            commands::session::set_tab_meta,
+            commands::session::get_tab_meta,
+            commands::session::derive_tab_title,
+            commands::session::save_tab_history,
+            commands::session::load_tab_history,
+            commands::session::clear_tab_history,
+            commands::writing::start_session,
+            commands::writing::update_session,
+            commands::writing::end_session,
+            commands::writing::get_writing_stats,
+            commands::maintenance::run_maintenance_now,
+            commands::maintenance::get_vacuum_log,
+            commands::maintenance::get_memory_stats,
+            commands::maintenance::trim_caches,
+            commands::maintenance::purge_logs,
+            commands::jobs::cancel_job,
+            commands::session::get_safe_mode,
+            commands::session::mark_startup_complete,
+            commands::session::get_startup_report,
             commands::files::read_text_file,
             commands::files::write_text_file,
             commands::files::write_binary_file,
@@ -369,10 +453,17 @@ fn main() {
             commands::files::send_to_recycle_bin,
             commands::files::resolve_path_relative,
             commands::files::rename_file,
+            commands::files::archive_note,
             commands::files::add_to_recent_files,
             commands::files::get_recent_files,
             commands::files::remove_from_recent_files,
             commands::files::clear_recent_files,
+            commands::open_paths::sync_open_paths,
+            commands::open_paths::is_path_open,
+            commands::open_paths::focus_tab_for_path,
+            commands::versions::list_file_versions,
+            commands::inbox::append_to_inbox,
+            commands::inbox::quick_capture_ready,
             commands::settings::get_app_info,
             commands::spellcheck::add_to_dictionary,
             commands::spellcheck::load_user_dictionary,
@@ -380,14 +471,73 @@ fn main() {
             commands::spellcheck::check_words,
             commands::spellcheck::get_spelling_suggestions,
             commands::spellcheck::get_spellcheck_status,
-            commands::markdown::render_markdown,
-            commands::markdown::format_markdown,
-            commands::markdown::get_markdown_flavors,
-            commands::markdown::compute_text_metrics,
+            commands::status::get_editor_status,
+            commands::spellcheck::get_dictionary_usage,
+            commands::spellcheck::list_available_dictionaries,
+            commands::spellcheck::unload_spellchecker,
+            commands::markdown::structure::get_front_matter,
+            commands::markdown::structure::get_document_outline,
+            commands::markdown::structure::get_breadcrumb,
+            commands::markdown::structure::search_symbols,
+            commands::markdown::render::render_markdown,
+            commands::markdown::render::render_markdown_incremental,
+            commands::markdown::render::render_markdown_diff,
+            commands::markdown::render::render_markdown_inline,
+            commands::markdown::format::format_markdown,
+            commands::markdown::format::verify_roundtrip,
+            commands::markdown::format::insert_toc,
+            commands::markdown::render::get_markdown_flavors,
+            commands::markdown::metrics::compute_text_metrics,
+            commands::markdown::metrics::compute_selection_metrics,
+            commands::markdown::metrics::update_text_metrics,
+            commands::markdown::metrics::clear_text_metrics,
+            commands::markdown::compare::merge_documents,
+            commands::markdown::compare::compare_tabs,
+            commands::markdown::notes::find_similar,
+            commands::markdown::notes::generate_index,
+            commands::markdown::notes::get_file_summary,
+            commands::markdown::metrics::get_document_stats,
+            commands::markdown::format::strip_markdown,
+            commands::markdown::structure::fix_duplicate_headings,
+            commands::markdown::metrics::audit_accessibility,
+            commands::markdown::format::convert_list,
+            commands::markdown::format::renumber_ordered_lists,
+            commands::markdown::structure::move_section,
+            commands::markdown::structure::promote_headings,
+            commands::markdown::structure::demote_headings,
+            commands::markdown::format::clean_word_paste,
+            commands::markdown::format::convert_html_to_markdown,
+            commands::markdown::format::convert_inline_html,
+            commands::sections::extract_section,
+            commands::templates::get_template_variables,
+            commands::templates::expand_template,
+            commands::templates::create_note,
+            commands::sections::combine_files,
+            commands::presave::run_presave_checks,
+            commands::glossary::get_glossary_matches,
+            commands::presave::save_with_checks,
             commands::bookmarks::add_bookmark,
             commands::bookmarks::get_all_bookmarks,
+            commands::bookmarks::update_bookmark,
             commands::bookmarks::delete_bookmark,
+            commands::bookmarks::delete_bookmarks,
+            commands::bookmarks::tag_bookmarks,
+            commands::bookmarks::move_bookmarks,
             commands::bookmarks::update_bookmark_access_time,
+            commands::bookmarks::find_bookmarks_by_tag,
+            commands::bookmarks::list_bookmark_tags,
+            commands::bookmarks::rename_tag,
+            commands::annotations::add_annotation,
+            commands::annotations::list_annotations,
+            commands::annotations::update_annotation,
+            commands::annotations::delete_annotation,
+            commands::annotations::export_annotations,
+            commands::searches::record_search,
+            commands::searches::save_search,
+            commands::searches::list_searches,
+            commands::searches::pin_search,
+            commands::searches::delete_search,
+            commands::searches::clear_search_history,
             commands::settings::get_available_themes,
             commands::settings::get_theme_css,
             commands::settings::load_settings,
@@ -396,17 +546,46 @@ fn main() {
             commands::settings::check_context_menu_status,
             commands::updater::check_for_updates,
             commands::updater::download_and_install_update,
-            commands::export::export_to_pdf,
+            commands::export::pdf::export_to_pdf,
+            commands::export::epub::export_to_epub,
+            commands::export::slides::export_to_slides,
+            commands::export::presets::list_export_presets,
+            commands::export::presets::save_export_preset,
+            commands::export::presets::delete_export_preset,
+            commands::export::presets::export_with_preset,
+            commands::export::watch::watch_export,
+            commands::export::watch::stop_watch_export,
+            commands::export::watch::list_export_watches,
+            commands::export::tabs::export_tabs,
+            commands::export::markup::convert_to_markup,
+            commands::export::markup::copy_as_html,
+            commands::export::presets::get_export_config,
+            commands::export::presets::export_document,
+            commands::export::preview::write_live_preview,
             commands::data::export_bookmarks,
             commands::data::import_bookmarks,
+            commands::data::import_browser_bookmarks,
             commands::data::export_recent_files,
             commands::data::import_recent_files,
             commands::data::delete_orphan_files,
+            commands::data::get_database_status,
+            commands::data::get_instance_status,
+            commands::data::recover_instance_lock,
+            commands::data::export_database,
         ])
-        .run(tauri::generate_context!())
+        .build(tauri::generate_context!())
         .map_err(|e| {
             log::error!("Error while running tauri application: {}", e);
             e
         })
-        .expect("Error while running tauri application");
+        .expect("Error while running tauri application")
+        .run(|app_handle, event| {
+            // Covers exits that never close the window, such as OS shutdown or log-off
+            if let tauri::RunEvent::ExitRequested { api, .. } = event
+                && !shutdown::is_finished()
+            {
+                api.prevent_exit();
+                shutdown::begin(app_handle, commands::autosave::SaveReason::Exit);
+            }
+        });
 }
@@ -0,0 +1,205 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::lint::Severity;
+use crate::markdown::sections::{self, TextOptions};
+use comrak::nodes::{AstNode, NodeValue};
+use comrak::{Arena, parse_document};
+use regex::Regex;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+pub const RULE_IMAGE_ALT: &str = "image-alt";
+pub const RULE_HEADING_SKIP: &str = "heading-skip";
+pub const RULE_EMPTY_LINK: &str = "empty-link";
+pub const RULE_TABLE_HEADER: &str = "table-header";
+pub const RULE_LINK_TEXT: &str = "link-text";
+
+/// Link text that says nothing about the destination when read out of context, as
+/// screen readers list links
+const VAGUE_LINK_TEXT: [&str; 14] = [
+    "click here",
+    "click",
+    "here",
+    "this",
+    "this link",
+    "link",
+    "more",
+    "read more",
+    "learn more",
+    "more info",
+    "details",
+    "go",
+    "this page",
+    "website",
+];
+
+static HTML_IMG_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<img\b[^>]*>").expect("Invalid HTML_IMG_RE"));
+
+static HTML_ALT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\balt\s*=").expect("Invalid HTML_ALT_RE"));
+
+/// An accessibility problem at a 1-based line and character column
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessibilityIssue {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Checks what exported HTML and PDF need for screen readers and navigation: alt text on
+/// images (including raw `<img>` tags), headings without skipped levels, links with text
+/// that describes them, and tables with a header row.
+pub fn audit(content: &str, flavor: MarkdownFlavor) -> Vec<AccessibilityIssue> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, content, &flavor.to_comrak_options());
+    let lines: Vec<&str> = content.lines().collect();
+    let mut issues = Vec::new();
+    let mut push = |rule, severity, line: usize, byte_column: usize, message| {
+        // Sourcepos columns count bytes
+        let column = lines
+            .get(line.saturating_sub(1))
+            .and_then(|text| text.get(..byte_column.saturating_sub(1)))
+            .map_or(byte_column, |prefix| prefix.chars().count() + 1);
+        issues.push(AccessibilityIssue {
+            rule,
+            severity,
+            line,
+            column,
+            message,
+        });
+    };
+    let mut last_level = 0u8;
+
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        let (line, column) = (data.sourcepos.start.line, data.sourcepos.start.column);
+        match &data.value {
+            NodeValue::Heading(heading) => {
+                if last_level > 0 && heading.level > last_level + 1 {
+                    push(
+                        RULE_HEADING_SKIP,
+                        Severity::Warning,
+                        line,
+                        column,
+                        format!(
+                            "Heading level skips from H{} to H{}; screen reader outlines will have a gap",
+                            last_level, heading.level
+                        ),
+                    );
+                }
+                last_level = heading.level;
+            },
+            NodeValue::Image(image) => {
+                if plain_text(node).is_empty() {
+                    push(
+                        RULE_IMAGE_ALT,
+                        Severity::Error,
+                        line,
+                        column,
+                        format!("Image '{}' has no alt text", image.url),
+                    );
+                }
+            },
+            NodeValue::Link(link) => {
+                let text = plain_text(node);
+                let has_image = node
+                    .descendants()
+                    .any(|n| matches!(n.data.borrow().value, NodeValue::Image(_)));
+                if link.url.trim().is_empty() {
+                    push(
+                        RULE_EMPTY_LINK,
+                        Severity::Error,
+                        line,
+                        column,
+                        "Link has no destination".to_string(),
+                    );
+                } else if text.is_empty() && !has_image {
+                    push(
+                        RULE_EMPTY_LINK,
+                        Severity::Error,
+                        line,
+                        column,
+                        format!("Link to '{}' has no text", link.url),
+                    );
+                } else if is_vague(&text) {
+                    push(
+                        RULE_LINK_TEXT,
+                        Severity::Warning,
+                        line,
+                        column,
+                        format!(
+                            "Link text '{}' doesn't say where '{}' leads",
+                            text, link.url
+                        ),
+                    );
+                }
+            },
+            NodeValue::TableRow(true) => {
+                if node.children().all(|cell| plain_text(cell).is_empty()) {
+                    push(
+                        RULE_TABLE_HEADER,
+                        Severity::Warning,
+                        line,
+                        column,
+                        "Table has an empty header row".to_string(),
+                    );
+                }
+            },
+            NodeValue::HtmlBlock(html) => {
+                for tag in HTML_IMG_RE.find_iter(&html.literal) {
+                    if !HTML_ALT_RE.is_match(tag.as_str()) {
+                        let before = &html.literal[..tag.start()];
+                        let tag_line = line + before.matches('\n').count();
+                        let tag_column = match before.rfind('\n') {
+                            Some(newline) => tag.start() - newline,
+                            None => column + tag.start(),
+                        };
+                        push(
+                            RULE_IMAGE_ALT,
+                            Severity::Error,
+                            tag_line,
+                            tag_column,
+                            "HTML image has no alt attribute".to_string(),
+                        );
+                    }
+                }
+            },
+            NodeValue::HtmlInline(html) => {
+                if HTML_IMG_RE.is_match(html) && !HTML_ALT_RE.is_match(html) {
+                    push(
+                        RULE_IMAGE_ALT,
+                        Severity::Error,
+                        line,
+                        column,
+                        "HTML image has no alt attribute".to_string(),
+                    );
+                }
+            },
+            _ => {},
+        }
+    }
+
+    issues.sort_by_key(|issue| (issue.line, issue.column));
+    issues
+}
+
+/// Text a screen reader would announce for a node, including image alt text
+fn plain_text<'a>(node: &'a AstNode<'a>) -> String {
+    let options = TextOptions {
+        image_alt: true,
+        ..Default::default()
+    };
+    sections::node_text(node, options)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_vague(text: &str) -> bool {
+    let normalized = text
+        .trim_matches(|c: char| c.is_ascii_punctuation() || c.is_whitespace())
+        .to_lowercase();
+    VAGUE_LINK_TEXT.contains(&normalized.as_str())
+}
@@ -0,0 +1,177 @@
+use crate::markdown::text_metrics::utf16_to_byte_offset;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+// Characters of context kept on each side of the quote
+const CONTEXT_CHARS: usize = 32;
+// How much longer or shorter than the quote an edited span between intact context may be
+const EDIT_SLACK: usize = 64;
+
+/// Where a comment is attached: the quoted text with its offsets and context when it was
+/// made. Offsets are UTF-16 code units, matching editor offsets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextAnchor {
+    pub from: usize,
+    pub to: usize,
+    pub quote: String,
+    pub prefix: String,
+    pub suffix: String,
+    /// Hash of the quote with whitespace collapsed, so rewrapped text still matches
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnchorStatus {
+    /// The quote is still at its offsets
+    Exact,
+    /// The quote was found elsewhere, such as after text was inserted above it
+    Moved,
+    /// The quote was reworded, but the text around it is intact
+    Edited,
+    /// The quote and its context are gone
+    Orphaned,
+}
+
+/// An anchor found in the current text, in UTF-16 offsets
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct AnchorLocation {
+    pub status: AnchorStatus,
+    pub from: Option<usize>,
+    pub to: Option<usize>,
+}
+
+/// Builds the anchor for the UTF-16 range `from..to` of `content`
+pub fn anchor(content: &str, from: usize, to: usize) -> Result<TextAnchor> {
+    let (from, to) = (from.min(to), from.max(to));
+    let start = utf16_to_byte_offset(content, from);
+    let end = utf16_to_byte_offset(content, to);
+    let quote = &content[start..end];
+    if quote.trim().is_empty() {
+        return Err(anyhow!("Select some text to comment on"));
+    }
+    let prefix_start = content[..start]
+        .char_indices()
+        .rev()
+        .nth(CONTEXT_CHARS - 1)
+        .map_or(0, |(i, _)| i);
+    let suffix_end = content[end..]
+        .char_indices()
+        .nth(CONTEXT_CHARS)
+        .map_or(content.len(), |(i, _)| end + i);
+
+    Ok(TextAnchor {
+        from: utf16_len(&content[..start]),
+        to: utf16_len(&content[..end]),
+        quote: quote.to_string(),
+        prefix: content[prefix_start..start].to_string(),
+        suffix: content[end..suffix_end].to_string(),
+        hash: quote_hash(quote),
+    })
+}
+
+/// Finds the anchor in `content`: at its offsets, then as the nearest copy of the quote,
+/// then as the span between its surviving context
+pub fn locate(content: &str, anchor: &TextAnchor) -> AnchorLocation {
+    let start = utf16_to_byte_offset(content, anchor.from);
+    let end = utf16_to_byte_offset(content, anchor.to);
+    if quote_hash(&content[start..end]) == anchor.hash {
+        return found(content, AnchorStatus::Exact, start, end);
+    }
+
+    // Prefer the copy with the most matching context, then the one nearest the old spot
+    let best = content
+        .match_indices(anchor.quote.as_str())
+        .map(|(at, _)| {
+            let context = usize::from(content[..at].ends_with(&anchor.prefix))
+                + usize::from(content[at + anchor.quote.len()..].starts_with(&anchor.suffix));
+            (at, context)
+        })
+        .max_by_key(|&(at, context)| (context, std::cmp::Reverse(at.abs_diff(start))));
+    if let Some((at, _)) = best {
+        return found(content, AnchorStatus::Moved, at, at + anchor.quote.len());
+    }
+
+    if !anchor.prefix.trim().is_empty() && !anchor.suffix.trim().is_empty() {
+        let max_len = anchor.quote.len() * 2 + EDIT_SLACK;
+        let edited = content
+            .match_indices(anchor.prefix.as_str())
+            .filter_map(|(at, prefix)| {
+                let span_start = at + prefix.len();
+                let span_len = content[span_start..].find(anchor.suffix.as_str())?;
+                Some((span_start, span_start + span_len))
+            })
+            .filter(|(from, to)| from < to && to - from <= max_len)
+            .min_by_key(|&(from, _)| from.abs_diff(start));
+        if let Some((from, to)) = edited {
+            return found(content, AnchorStatus::Edited, from, to);
+        }
+    }
+
+    AnchorLocation {
+        status: AnchorStatus::Orphaned,
+        from: None,
+        to: None,
+    }
+}
+
+fn found(content: &str, status: AnchorStatus, start: usize, end: usize) -> AnchorLocation {
+    AnchorLocation {
+        status,
+        from: Some(utf16_len(&content[..start])),
+        to: Some(utf16_len(&content[..end])),
+    }
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// FNV-1a over the whitespace-collapsed text; stored in the database, so it must not
+/// change between builds the way `DefaultHasher` may
+fn quote_hash(text: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let words = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    for byte in words.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// One comment for the appendix
+pub struct AppendixEntry<'a> {
+    pub quote: &'a str,
+    pub body: &'a str,
+    pub author: Option<&'a str>,
+    pub created: &'a str,
+    pub resolved: bool,
+}
+
+/// The comments as a markdown section to append to the document: a numbered list in
+/// document order, each quoting the text it was attached to
+pub fn appendix(title: &str, entries: &[AppendixEntry]) -> String {
+    let mut out = format!("## {}\n", title.trim());
+    for (index, entry) in entries.iter().enumerate() {
+        let quote = entry.quote.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut byline = entry.created.get(..10).unwrap_or(entry.created).to_string();
+        if let Some(author) = entry.author.filter(|a| !a.trim().is_empty()) {
+            byline = format!("{}, {}", author.trim(), byline);
+        }
+        if entry.resolved {
+            byline.push_str(", resolved");
+        }
+        let marker = format!("{}.", index + 1);
+        let indent = " ".repeat(marker.len() + 1);
+        out.push_str(&format!("\n{} > {}\n\n", marker, quote));
+        for line in entry.body.trim().lines() {
+            if line.trim().is_empty() {
+                out.push('\n');
+            } else {
+                out.push_str(&format!("{}{}\n", indent, line));
+            }
+        }
+        out.push_str(&format!("\n{}*{}*\n", indent, byline));
+    }
+    out
+}
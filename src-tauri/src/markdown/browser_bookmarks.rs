@@ -0,0 +1,317 @@
+use crate::markdown::renderer::resolve_local_image;
+use chrono::{DateTime, Local};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Tokens of the Netscape bookmark format every browser exports: folder titles, links,
+/// link descriptions and the `<DL>` lists that nest them
+static TOKEN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?is)<h1[^>]*>(?P<h1>.*?)</h1>|<h3[^>]*>(?P<folder>.*?)</h3>|<a\s(?P<attrs>[^>]*)>(?P<title>.*?)</a>|<dd>(?P<desc>[^<]*)|(?P<open><dl\b)|(?P<close></dl>)",
+    )
+    .expect("Invalid TOKEN_RE")
+});
+
+static ATTR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\b([a-z_]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).expect("Invalid ATTR_RE")
+});
+
+static TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").expect("Invalid TAG_RE"));
+
+#[derive(Debug, Clone)]
+pub struct BrowserLink {
+    pub title: String,
+    pub url: String,
+    pub added: Option<DateTime<Local>>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct BookmarkFolder {
+    pub name: String,
+    pub links: Vec<BrowserLink>,
+    pub folders: Vec<BookmarkFolder>,
+}
+
+impl BookmarkFolder {
+    fn link_count(&self) -> usize {
+        self.links.len() + self.folders.iter().map(|f| f.link_count()).sum::<usize>()
+    }
+
+    fn folder_count(&self) -> usize {
+        self.folders
+            .iter()
+            .filter(|f| f.link_count() > 0)
+            .map(|f| 1 + f.folder_count())
+            .sum()
+    }
+}
+
+/// A `file:` bookmark that points at a file on this machine
+#[derive(Debug)]
+pub struct LocalFileLink {
+    pub path: PathBuf,
+    pub title: String,
+    /// Slash-separated folder path, as the bookmark table stores it
+    pub folder: Option<String>,
+    pub added: Option<DateTime<Local>>,
+}
+
+#[derive(Debug)]
+pub struct BrowserBookmarkSummary {
+    pub links: usize,
+    pub folders: usize,
+}
+
+/// Parses a browser bookmarks export (Netscape bookmark file) into its folder tree. The
+/// root takes the file's `<H1>` title. Markup the format doesn't use is ignored, so a
+/// damaged file yields whatever bookmarks could be read.
+pub fn parse(html: &str) -> BookmarkFolder {
+    let mut root = BookmarkFolder::default();
+    // Indices into each level's `folders`, from the root down to the open list
+    let mut path: Vec<usize> = Vec::new();
+    let mut pending_folder: Option<String> = None;
+    // The root <DL> belongs to the root folder rather than opening a new one
+    let mut root_opened = false;
+
+    for caps in TOKEN_RE.captures_iter(html) {
+        if let Some(title) = caps.name("h1") {
+            root.name = inline_text(title.as_str());
+        } else if let Some(name) = caps.name("folder") {
+            pending_folder = Some(inline_text(name.as_str()));
+        } else if caps.name("open").is_some() {
+            match pending_folder.take() {
+                Some(name) => {
+                    let parent = folder_at(&mut root, &path);
+                    parent.folders.push(BookmarkFolder {
+                        name,
+                        ..Default::default()
+                    });
+                    path.push(parent.folders.len() - 1);
+                },
+                None if !root_opened => root_opened = true,
+                // A list without a folder title; keep its links in the enclosing folder
+                None => {},
+            }
+        } else if caps.name("close").is_some() {
+            path.pop();
+        } else if let Some(attrs) = caps.name("attrs") {
+            let attrs = attributes(attrs.as_str());
+            let Some(url) = attr(&attrs, "href").filter(|u| !u.is_empty()) else {
+                continue;
+            };
+            let title = inline_text(&caps["title"]);
+            folder_at(&mut root, &path).links.push(BrowserLink {
+                title: if title.is_empty() { url.clone() } else { title },
+                url,
+                added: attr(&attrs, "add_date").and_then(|d| timestamp(&d)),
+                description: None,
+            });
+        } else if let Some(desc) = caps.name("desc") {
+            let desc = inline_text(desc.as_str());
+            if let Some(link) = folder_at(&mut root, &path).links.last_mut()
+                && !desc.is_empty()
+            {
+                link.description = Some(desc);
+            }
+        }
+    }
+
+    if root.name.is_empty() {
+        root.name = "Bookmarks".to_string();
+    }
+    root
+}
+
+fn folder_at<'a>(root: &'a mut BookmarkFolder, path: &[usize]) -> &'a mut BookmarkFolder {
+    path.iter()
+        .fold(root, |folder, &index| &mut folder.folders[index])
+}
+
+fn attributes(text: &str) -> Vec<(String, String)> {
+    ATTR_RE
+        .captures_iter(text)
+        .map(|caps| {
+            let value = caps.get(2).or(caps.get(3)).map_or("", |m| m.as_str());
+            (caps[1].to_ascii_lowercase(), decode_entities(value))
+        })
+        .collect()
+}
+
+fn attr(attrs: &[(String, String)], name: &str) -> Option<String> {
+    attrs
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.trim().to_string())
+}
+
+/// `ADD_DATE` is Unix seconds; some exporters write milliseconds or microseconds instead
+fn timestamp(value: &str) -> Option<DateTime<Local>> {
+    let mut seconds: i64 = value.trim().parse().ok()?;
+    while seconds > 100_000_000_000 {
+        seconds /= 1000;
+    }
+    (seconds > 0)
+        .then(|| DateTime::from_timestamp(seconds, 0))
+        .flatten()
+        .map(|date| date.with_timezone(&Local))
+}
+
+fn inline_text(html: &str) -> String {
+    decode_entities(&TAG_RE.replace_all(html, ""))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                    .and_then(char::from_u32),
+            }?;
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            },
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Renders the tree as a markdown document: the root title becomes the H1, folders become
+/// headings one level per nesting depth (capped at H6), and bookmarks become list items
+/// with the date they were added. Empty folders are left out.
+pub fn to_markdown(root: &BookmarkFolder, source_name: &str) -> (String, BrowserBookmarkSummary) {
+    let mut out = format!("# {}\n\n", escape_text(&root.name));
+    out.push_str(&format!(
+        "Imported from {} on {}.\n",
+        escape_text(source_name),
+        Local::now().format("%Y-%m-%d")
+    ));
+    write_folder(root, 1, &mut out);
+    let summary = BrowserBookmarkSummary {
+        links: root.link_count(),
+        folders: root.folder_count(),
+    };
+    (out, summary)
+}
+
+fn write_folder(folder: &BookmarkFolder, level: usize, out: &mut String) {
+    if !folder.links.is_empty() {
+        out.push('\n');
+        for link in &folder.links {
+            out.push_str(&format!(
+                "- [{}]({})",
+                escape_text(&link.title),
+                link_destination(&link.url)
+            ));
+            if let Some(added) = link.added {
+                out.push_str(&format!(" — {}", added.format("%Y-%m-%d")));
+            }
+            out.push('\n');
+            if let Some(description) = &link.description {
+                out.push_str(&format!("  {}\n", escape_text(description)));
+            }
+        }
+    }
+    for child in folder.folders.iter().filter(|f| f.link_count() > 0) {
+        let heading_level = (level + 1).min(6);
+        out.push_str(&format!(
+            "\n{} {}\n",
+            "#".repeat(heading_level),
+            escape_text(&child.name)
+        ));
+        write_folder(child, level + 1, out);
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']' | '*' | '_' | '`' | '<' | '>') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Angle brackets keep spaces and unbalanced parentheses inside the link destination
+fn link_destination(url: &str) -> String {
+    let opens = url.matches('(').count();
+    let closes = url.matches(')').count();
+    if url.contains(char::is_whitespace) || opens != closes {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+/// Collects `file:` bookmarks whose targets exist, with their folder paths below the root
+pub fn local_files(root: &BookmarkFolder) -> Vec<LocalFileLink> {
+    let mut found = Vec::new();
+    collect_local_files(root, &mut Vec::new(), &mut found);
+    found
+}
+
+fn collect_local_files(
+    folder: &BookmarkFolder,
+    trail: &mut Vec<String>,
+    found: &mut Vec<LocalFileLink>,
+) {
+    for link in &folder.links {
+        let is_file_url = link
+            .url
+            .get(..5)
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case("file:"));
+        if !is_file_url {
+            continue;
+        }
+        let Some(path) = resolve_local_image(&link.url, Path::new("")) else {
+            continue;
+        };
+        if path.is_file() {
+            found.push(LocalFileLink {
+                path,
+                title: link.title.clone(),
+                folder: (!trail.is_empty()).then(|| trail.join("/")),
+                added: link.added,
+            });
+        }
+    }
+    for child in &folder.folders {
+        // A slash would read as a nested folder in the bookmark table
+        trail.push(child.name.replace(['/', '\\'], "-"));
+        collect_local_files(child, trail, found);
+        trail.pop();
+    }
+}
@@ -0,0 +1,106 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+// Conditional comments, <xml> islands, <style> blocks and head-only tags Word puts on the clipboard
+static WORD_BLOCKS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<!--.*?-->|<xml>.*?</xml>|<style[^>]*>.*?</style>|<(?:meta|link)[^>]*>")
+        .expect("Invalid WORD_BLOCKS_RE")
+});
+// Office namespace tags such as <o:p>, <w:WordDocument>, <v:shape>
+static OFFICE_TAG_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)</?[ovwm]:[^>]*>").expect("Invalid OFFICE_TAG_RE"));
+static STYLE_ATTR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\s+style\s*=\s*("[^"]*"|'[^']*')"#).expect("Invalid STYLE_ATTR_RE")
+});
+static MSO_CLASS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\s+class\s*=\s*("Mso[^"]*"|'Mso[^']*'|Mso\w*)"#)
+        .expect("Invalid MSO_CLASS_RE")
+});
+static LANG_ATTR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\s+lang\s*=\s*("[^"]*"|'[^']*'|[\w-]+)"#).expect("Invalid LANG_ATTR_RE")
+});
+static EMPTY_SPAN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)<span[^>]*>(?:\s|&nbsp;)*</span>").expect("Invalid EMPTY_SPAN_RE")
+});
+static BARE_SPAN_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<span>(.*?)</span>").expect("Invalid BARE_SPAN_RE"));
+
+/// UTF-8 quotes and dashes that were decoded as Windows-1252, most specific first
+const MOJIBAKE: [(&str, &str); 8] = [
+    ("â€œ", "\u{201C}"),
+    ("â€\u{9D}", "\u{201D}"),
+    ("â€˜", "\u{2018}"),
+    ("â€™", "\u{2019}"),
+    ("â€“", "\u{2013}"),
+    ("â€”", "\u{2014}"),
+    ("â€¦", "\u{2026}"),
+    ("Â ", " "),
+];
+
+/// Cleans Word/Outlook clipboard content before conversion to markdown: strips Office
+/// markup, `mso-` styles and empty spans, repairs mis-decoded smart quotes, and turns
+/// non-breaking spaces into plain spaces. Plain text passes through the text fixes only.
+pub fn clean_word_paste(input: &str) -> String {
+    let mut text = repair_characters(input);
+
+    if text.contains('<') {
+        text = WORD_BLOCKS_RE.replace_all(&text, "").into_owned();
+        text = OFFICE_TAG_RE.replace_all(&text, "").into_owned();
+        text = STYLE_ATTR_RE
+            .replace_all(&text, |caps: &regex::Captures| strip_mso_styles(&caps[1]))
+            .into_owned();
+        text = MSO_CLASS_RE.replace_all(&text, "").into_owned();
+        text = LANG_ATTR_RE.replace_all(&text, "").into_owned();
+
+        // Nested empty spans collapse one layer per pass
+        loop {
+            let next = EMPTY_SPAN_RE.replace_all(&text, "");
+            let next = BARE_SPAN_RE.replace_all(&next, "$1").into_owned();
+            if next == text {
+                break;
+            }
+            text = next;
+        }
+        text = text.replace("&nbsp;", " ");
+    }
+
+    text
+}
+
+fn repair_characters(input: &str) -> String {
+    let mut text = MOJIBAKE
+        .iter()
+        .fold(input.to_string(), |acc, (bad, good)| acc.replace(bad, good));
+
+    // C1 control codes left behind when Windows-1252 bytes were read as Latin-1
+    text = text
+        .chars()
+        .map(|c| match c {
+            '\u{0091}' => '\u{2018}',
+            '\u{0092}' => '\u{2019}',
+            '\u{0093}' => '\u{201C}',
+            '\u{0094}' => '\u{201D}',
+            '\u{0096}' => '\u{2013}',
+            '\u{0097}' => '\u{2014}',
+            '\u{00A0}' | '\u{202F}' => ' ',
+            c => c,
+        })
+        .collect();
+    text
+}
+
+/// Keeps non-Office declarations of a `style` attribute; drops the attribute if none remain.
+fn strip_mso_styles(quoted: &str) -> String {
+    let quote = &quoted[..1];
+    let kept: Vec<&str> = quoted[1..quoted.len() - 1]
+        .split(';')
+        .map(str::trim)
+        .filter(|decl| !decl.is_empty() && !decl.to_ascii_lowercase().starts_with("mso-"))
+        .collect();
+
+    if kept.is_empty() {
+        String::new()
+    } else {
+        format!(" style={}{}{}", quote, kept.join("; "), quote)
+    }
+}
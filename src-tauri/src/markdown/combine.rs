@@ -0,0 +1,238 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::front_matter;
+use crate::markdown::lint::update_fence;
+use crate::markdown::sections;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+static DEFINITION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^ {0,3}\[(\^?)([^\]]+)\]:[ \t]*(.*)$").expect("Invalid DEFINITION_RE")
+});
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CombineOptions {
+    /// Added to every heading level, e.g. 1 turns each chapter's H1 into H2
+    pub heading_offset: i8,
+    pub deduplicate_references: bool,
+    pub toc: bool,
+    pub toc_depth: u8,
+    pub toc_title: String,
+    /// Placed on its own line between documents, e.g. `---`
+    pub separator: Option<String>,
+}
+
+impl Default for CombineOptions {
+    fn default() -> Self {
+        Self {
+            heading_offset: 0,
+            deduplicate_references: true,
+            toc: false,
+            toc_depth: 3,
+            toc_title: "Contents".to_string(),
+            separator: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CombineResult {
+    pub content: String,
+    /// Headings that would have gone past H1 or H6 and were clamped
+    pub clamped_headings: usize,
+    /// Footnote and reference labels renamed because another document defined them differently
+    pub renamed_labels: usize,
+    /// Definitions dropped because an earlier document defined them identically
+    pub removed_definitions: usize,
+}
+
+/// A footnote or link reference definition key; labels match case-insensitively.
+type DefinitionKey = (bool, String);
+
+/// Concatenates documents in order, shifting headings and resolving footnote/reference
+/// label collisions between them, with an optional generated table of contents.
+pub fn combine_documents(
+    documents: &[String],
+    options: &CombineOptions,
+    flavor: MarkdownFlavor,
+) -> CombineResult {
+    let mut result = CombineResult::default();
+    let mut defined: HashMap<DefinitionKey, String> = HashMap::new();
+    let mut parts = Vec::with_capacity(documents.len());
+    let mut front_matter = None;
+
+    for (i, document) in documents.iter().enumerate() {
+        let document = document.replace("\r\n", "\n");
+        // Only the first document's front matter survives, and it stays at the top
+        let fm_lines = front_matter::line_count(&document);
+        let mut lines = document.split('\n');
+        let fm: Vec<&str> = lines.by_ref().take(fm_lines).collect();
+        if i == 0 && !fm.is_empty() {
+            front_matter = Some(fm.join("\n"));
+        }
+        let mut document = lines.collect::<Vec<_>>().join("\n");
+
+        if options.heading_offset != 0 {
+            let shift = sections::shift_headings(&document, options.heading_offset, None, flavor);
+            document = shift.content;
+            result.clamped_headings += shift.warnings.len();
+        }
+        if options.deduplicate_references {
+            document = resolve_definitions(&document, i + 1, &mut defined, &mut result);
+        }
+        parts.push(document.trim_matches('\n').to_string());
+    }
+
+    let joiner = match options.separator.as_deref().map(str::trim) {
+        Some(sep) if !sep.is_empty() => format!("\n\n{}\n\n", sep),
+        _ => "\n\n".to_string(),
+    };
+    let mut content = parts.join(&joiner);
+
+    if options.toc {
+        content = with_toc(&content, options, flavor);
+    }
+    if let Some(front_matter) = front_matter {
+        content = format!("{}\n\n{}", front_matter, content);
+    }
+    content.push('\n');
+
+    result.content = content;
+    result
+}
+
+/// Drops definitions already made identically by an earlier document and renames labels
+/// whose earlier definition differs, rewriting this document's references to match.
+fn resolve_definitions(
+    document: &str,
+    doc_number: usize,
+    defined: &mut HashMap<DefinitionKey, String>,
+    result: &mut CombineResult,
+) -> String {
+    let lines: Vec<&str> = document.split('\n').collect();
+    let mut drop_lines: HashSet<usize> = HashSet::new();
+    let mut renames: Vec<(bool, String, String)> = Vec::new();
+    let mut fence = None;
+
+    for (idx, line) in lines.iter().enumerate() {
+        if update_fence(&mut fence, line) {
+            continue;
+        }
+        let Some(caps) = DEFINITION_RE.captures(line) else {
+            continue;
+        };
+        let footnote = !caps[1].is_empty();
+        let label = caps[2].to_string();
+        let text = caps[3].trim().to_string();
+        let key = (footnote, label.to_lowercase());
+
+        match defined.get(&key).cloned() {
+            Some(existing) if existing == text => {
+                drop_lines.insert(idx);
+                // Indented lines continue a footnote definition
+                if footnote {
+                    drop_lines.extend((idx + 1..lines.len()).take_while(|&i| {
+                        lines[i].starts_with("    ") || lines[i].starts_with('\t')
+                    }));
+                }
+                result.removed_definitions += 1;
+            },
+            Some(_) => {
+                let mut suffix = doc_number;
+                let mut new_label = format!("{}-{}", label, suffix);
+                while defined.contains_key(&(footnote, new_label.to_lowercase())) {
+                    suffix += 1;
+                    new_label = format!("{}-{}", label, suffix);
+                }
+                defined.insert((footnote, new_label.to_lowercase()), text);
+                renames.push((footnote, label, new_label));
+                result.renamed_labels += 1;
+            },
+            None => {
+                defined.insert(key, text);
+            },
+        }
+    }
+
+    let mut fence = None;
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| !drop_lines.contains(idx))
+        .map(|(_, line)| {
+            if update_fence(&mut fence, line) {
+                return line.to_string();
+            }
+            renames
+                .iter()
+                .fold(line.to_string(), |acc, (footnote, old, new)| {
+                    rename_label(&acc, old, new, *footnote)
+                })
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renames `[^old]` footnote markers, or `[old]` used as a reference label: in a
+/// definition, after `]` in a full reference, or as a shortcut/collapsed reference.
+/// `[old](url)` and `[old][other]` are link text and are left alone.
+fn rename_label(line: &str, old: &str, new: &str, footnote: bool) -> String {
+    let (needle, replacement) = if footnote {
+        (format!("[^{}]", old), format!("[^{}]", new))
+    } else {
+        (format!("[{}]", old), format!("[{}]", new))
+    };
+    if !line.contains(&needle) {
+        return line.to_string();
+    }
+
+    let mut output = String::with_capacity(line.len());
+    let mut last = 0;
+    for (pos, _) in line.match_indices(&needle) {
+        let before = line[..pos].chars().next_back();
+        let after = &line[pos + needle.len()..];
+        let is_reference = footnote
+            || before == Some(']')
+            || !(after.starts_with('(') || (after.starts_with('[') && !after.starts_with("[]")));
+        if is_reference {
+            output.push_str(&line[last..pos]);
+            output.push_str(&replacement);
+            last = pos + needle.len();
+        }
+    }
+    output.push_str(&line[last..]);
+    output
+}
+
+fn with_toc(content: &str, options: &CombineOptions, flavor: MarkdownFlavor) -> String {
+    let all = sections::headings(content, flavor);
+    let top_level = all.iter().map(|h| h.level).min().unwrap_or(1);
+    let title_heading = format!("{} {}", "#".repeat(top_level as usize), options.toc_title);
+
+    // Anchors are computed with the TOC heading in place so duplicate suffixes line up
+    let titled = format!("{}\n\n{}", title_heading, content);
+    let titled_headings = sections::headings(&titled, flavor);
+    let anchors = sections::anchors(&titled_headings);
+
+    let entries: Vec<String> = titled_headings
+        .iter()
+        .zip(&anchors)
+        .skip(1)
+        .filter(|(h, _)| h.level <= options.toc_depth)
+        .map(|(h, anchor)| {
+            format!(
+                "{}- [{}](#{})",
+                "  ".repeat(h.level.saturating_sub(top_level) as usize),
+                h.text,
+                anchor
+            )
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return content.to_string();
+    }
+    format!("{}\n\n{}\n\n{}", title_heading, entries.join("\n"), content)
+}
@@ -0,0 +1,119 @@
+use crate::markdown::merge::lcs_matches;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RowKind {
+    Equal,
+    /// Lines at the same place on both sides that differ
+    Changed,
+    /// Only on the right
+    Added,
+    /// Only on the left
+    Removed,
+}
+
+/// One row of the side-by-side view. Line numbers are 1-based; a side without a line
+/// shows a filler row.
+#[derive(Debug, Clone, Serialize)]
+pub struct AlignedRow {
+    pub kind: RowKind,
+    pub left_line: Option<usize>,
+    pub right_line: Option<usize>,
+}
+
+/// A run of consecutive non-equal rows, for change navigation and gutters
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeBlock {
+    /// First row, 0-based
+    pub start_row: usize,
+    /// One past the last row
+    pub end_row: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TabComparison {
+    pub rows: Vec<AlignedRow>,
+    pub changes: Vec<ChangeBlock>,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub lines_changed: usize,
+}
+
+/// Line-aligns two documents for a synchronized side-by-side view. Unmatched lines
+/// between two matches pair up as changed rows; the rest are added or removed.
+pub fn compare(left: &str, right: &str) -> TabComparison {
+    let left_lines: Vec<&str> = left.lines().collect();
+    let right_lines: Vec<&str> = right.lines().collect();
+    let matches = lcs_matches(&left_lines, &right_lines);
+
+    let mut rows = Vec::with_capacity(left_lines.len().max(right_lines.len()));
+    let (mut l, mut r) = (0, 0);
+    for (i, matched) in matches.iter().enumerate() {
+        let Some(j) = *matched else {
+            continue;
+        };
+        push_gap(&mut rows, l..i, r..j);
+        rows.push(AlignedRow {
+            kind: RowKind::Equal,
+            left_line: Some(i + 1),
+            right_line: Some(j + 1),
+        });
+        (l, r) = (i + 1, j + 1);
+    }
+    push_gap(&mut rows, l..left_lines.len(), r..right_lines.len());
+
+    let count = |kind| rows.iter().filter(|row| row.kind == kind).count();
+    let (lines_added, lines_removed, lines_changed) = (
+        count(RowKind::Added),
+        count(RowKind::Removed),
+        count(RowKind::Changed),
+    );
+
+    let mut changes: Vec<ChangeBlock> = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        if row.kind == RowKind::Equal {
+            continue;
+        }
+        match changes.last_mut() {
+            Some(block) if block.end_row == index => block.end_row += 1,
+            _ => changes.push(ChangeBlock {
+                start_row: index,
+                end_row: index + 1,
+            }),
+        }
+    }
+
+    TabComparison {
+        rows,
+        changes,
+        lines_added,
+        lines_removed,
+        lines_changed,
+    }
+}
+
+/// Rows for the unmatched lines between two matches
+fn push_gap(
+    rows: &mut Vec<AlignedRow>,
+    left: std::ops::Range<usize>,
+    right: std::ops::Range<usize>,
+) {
+    let paired = left.len().min(right.len());
+    for k in 0..left.len().max(right.len()) {
+        let left_line = (k < left.len()).then(|| left.start + k + 1);
+        let right_line = (k < right.len()).then(|| right.start + k + 1);
+        let kind = if k < paired {
+            RowKind::Changed
+        } else if left_line.is_some() {
+            RowKind::Removed
+        } else {
+            RowKind::Added
+        };
+        rows.push(AlignedRow {
+            kind,
+            left_line,
+            right_line,
+        });
+    }
+}
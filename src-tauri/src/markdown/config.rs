@@ -4,6 +4,112 @@ use serde::{Deserialize, Serialize};
 
 pub const DEFAULT_LIST_INDENT: usize = 2;
 pub const DEFAULT_MAX_BLANK_LINES: usize = 2;
+pub const DEFAULT_LINE_WIDTH: u32 = 80;
+
+/// Optional syntax layered on top of either flavor
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash
+)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExtensionToggles {
+    /// `$inline$`, `$$display$$` and `` $`code`$ `` math
+    pub math: bool,
+    /// `[^1]` references and `^[inline]` notes, rendered with back-references
+    pub footnotes: bool,
+}
+
+/// Extension set for a user-defined flavor, read from a map such as
+/// `{ "table": true, "spoiler": true }`. Omitted keys keep the GFM defaults.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CustomFlavor {
+    pub table: bool,
+    pub strikethrough: bool,
+    pub autolink: bool,
+    pub tasklist: bool,
+    /// Escapes `<script>`, `<iframe>` and the other tags GFM disallows
+    pub tagfilter: bool,
+    pub footnotes: bool,
+    pub math: bool,
+    pub superscript: bool,
+    pub subscript: bool,
+    pub spoiler: bool,
+    pub underline: bool,
+    pub highlight: bool,
+    pub description_lists: bool,
+    pub alerts: bool,
+    pub multiline_block_quotes: bool,
+    /// `[[target|title]]` links
+    pub wikilinks: bool,
+}
+
+impl Default for CustomFlavor {
+    fn default() -> Self {
+        MarkdownFlavor::Gfm.toggles()
+    }
+}
+
+impl CustomFlavor {
+    const NONE: Self = Self {
+        table: false,
+        strikethrough: false,
+        autolink: false,
+        tasklist: false,
+        tagfilter: false,
+        footnotes: false,
+        math: false,
+        superscript: false,
+        subscript: false,
+        spoiler: false,
+        underline: false,
+        highlight: false,
+        description_lists: false,
+        alerts: false,
+        multiline_block_quotes: false,
+        wikilinks: false,
+    };
+
+    fn extension(self) -> Extension<'static> {
+        Extension {
+            strikethrough: self.strikethrough,
+            tagfilter: self.tagfilter,
+            table: self.table,
+            autolink: self.autolink,
+            tasklist: self.tasklist,
+            superscript: self.superscript,
+            header_ids: Some(String::new()),
+            footnotes: self.footnotes,
+            inline_footnotes: self.footnotes,
+            description_lists: self.description_lists,
+            front_matter_delimiter: None,
+            multiline_block_quotes: self.multiline_block_quotes,
+            alerts: self.alerts,
+            math_dollars: self.math,
+            math_code: self.math,
+            shortcodes: false,
+            wikilinks_title_after_pipe: self.wikilinks,
+            wikilinks_title_before_pipe: false,
+            underline: self.underline,
+            subscript: self.subscript,
+            spoiler: self.spoiler,
+            greentext: false,
+            image_url_rewriter: None,
+            link_url_rewriter: None,
+            cjk_friendly_emphasis: false,
+            subtext: false,
+            highlight: self.highlight,
+            phoenix_heex: false,
+        }
+    }
+}
 
 /// Markdown flavor specification
 #[derive(
@@ -24,14 +130,24 @@ pub enum MarkdownFlavor {
     /// GitHub Flavored Markdown (full GFM spec)
     #[default]
     Gfm,
+    /// Extensions picked one by one; serialized as `{ "custom": { ... } }`
+    Custom(CustomFlavor),
 }
 
 impl MarkdownFlavor {
-    /// Convert string to MarkdownFlavor
+    /// Convert string to MarkdownFlavor. Custom flavors arrive as their JSON form,
+    /// e.g. `{"custom":{"spoiler":true}}`; plain `custom` means the GFM defaults.
     pub fn from_str(s: &str) -> Option<Self> {
-        match s.to_lowercase().as_str() {
+        let trimmed = s.trim();
+        if trimmed.starts_with('{') {
+            return serde_json::from_str(trimmed)
+                .map_err(|e| log::warn!("Ignoring malformed custom flavor: {}", e))
+                .ok();
+        }
+        match trimmed.to_lowercase().as_str() {
             "commonmark" | "common-mark" | "cm" => Some(Self::CommonMark),
             "gfm" | "github" => Some(Self::Gfm),
+            "custom" => Some(Self::Custom(CustomFlavor::default())),
             _ => None,
         }
     }
@@ -41,71 +157,42 @@ impl MarkdownFlavor {
         flavor.and_then(|f| Self::from_str(&f)).unwrap_or_default()
     }
 
+    /// The extensions this flavor enables
+    pub fn toggles(self) -> CustomFlavor {
+        match self {
+            Self::CommonMark => CustomFlavor::NONE,
+            Self::Gfm => CustomFlavor {
+                table: true,
+                strikethrough: true,
+                autolink: true,
+                tasklist: true,
+                tagfilter: true,
+                subscript: true,
+                alerts: true,
+                ..CustomFlavor::NONE
+            },
+            Self::Custom(custom) => custom,
+        }
+    }
+
+    /// Flavor options with the optional extensions switched on
+    pub fn to_comrak_options_with(self, toggles: ExtensionToggles) -> Options<'static> {
+        let mut options = self.to_comrak_options();
+        if toggles.math {
+            options.extension.math_dollars = true;
+            options.extension.math_code = true;
+        }
+        if toggles.footnotes {
+            options.extension.footnotes = true;
+            options.extension.inline_footnotes = true;
+        }
+        options
+    }
+
     /// Get central comrak options for this flavor
     pub fn to_comrak_options(self) -> Options<'static> {
         Options {
-            extension: match self {
-                Self::CommonMark => Extension {
-                    strikethrough: false,
-                    tagfilter: false,
-                    table: false,
-                    autolink: false,
-                    tasklist: false,
-                    superscript: false,
-                    header_ids: None,
-                    footnotes: false,
-                    inline_footnotes: false,
-                    description_lists: false,
-                    front_matter_delimiter: None,
-                    multiline_block_quotes: false,
-                    alerts: false,
-                    math_dollars: false,
-                    math_code: false,
-                    shortcodes: false,
-                    wikilinks_title_after_pipe: false,
-                    wikilinks_title_before_pipe: false,
-                    underline: false,
-                    subscript: false,
-                    spoiler: false,
-                    greentext: false,
-                    image_url_rewriter: None,
-                    link_url_rewriter: None,
-                    cjk_friendly_emphasis: false,
-                    subtext: false,
-                    highlight: false,
-                    phoenix_heex: false,
-                },
-                Self::Gfm => Extension {
-                    strikethrough: true,
-                    tagfilter: true,
-                    table: true,
-                    autolink: true,
-                    tasklist: true,
-                    superscript: false,
-                    header_ids: None,
-                    footnotes: false,
-                    inline_footnotes: false,
-                    description_lists: false,
-                    front_matter_delimiter: None,
-                    multiline_block_quotes: false,
-                    alerts: false,
-                    math_dollars: false,
-                    math_code: false,
-                    shortcodes: false,
-                    wikilinks_title_after_pipe: false,
-                    wikilinks_title_before_pipe: false,
-                    underline: false,
-                    subscript: true,
-                    spoiler: false,
-                    greentext: false,
-                    image_url_rewriter: None,
-                    link_url_rewriter: None,
-                    cjk_friendly_emphasis: false,
-                    subtext: false,
-                    highlight: false,
-                    phoenix_heex: false,
-                },
-            },
+            extension: self.toggles().extension(),
             parse: Parse {
                 smart: true,
                 default_info_string: None,
@@ -0,0 +1,529 @@
+use crate::markdown::security::RemotePolicy;
+use crate::preview_assets;
+use anyhow::{Context, Result, anyhow, bail};
+use comrak::nodes::{AstNode, NodeValue};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Condvar, LazyLock, Mutex, Once, OnceLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Rendered diagrams kept in memory; more than this and the cache starts over
+const MAX_CACHED_DIAGRAMS: usize = 128;
+const DEFAULT_TIMEOUT_SECONDS: u64 = 20;
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+/// How long the preview's diagrams must stay unchanged before their tools run, so typing
+/// in a fence doesn't start a tool per keystroke
+const BACKGROUND_DELAY: Duration = Duration::from_millis(750);
+/// PlantUML reads this from the environment; the sandbox profile refuses `!include` of
+/// local files and URLs, so a previewed document can't pull either into its diagrams
+const PLANTUML_SECURITY_PROFILE: (&str, &str) = ("PLANTUML_SECURITY_PROFILE", "SANDBOX");
+
+/// Fence hash -> image, or why the tool failed so a missing tool isn't retried on every
+/// keystroke. The command line is part of the key, so changing settings retries.
+static DIAGRAM_CACHE: LazyLock<Mutex<HashMap<u64, Result<Arc<Vec<u8>>, String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Diagrams the last preview render couldn't take from the cache. Each render replaces
+/// them, so only the document's latest fences run once it has been still for a moment.
+static BACKGROUND: LazyLock<(Mutex<Option<BackgroundBatch>>, Condvar)> =
+    LazyLock::new(|| (Mutex::new(None), Condvar::new()));
+static BACKGROUND_WORKER: Once = Once::new();
+/// Told when background diagrams have rendered, so the preview can render again
+static ON_BACKGROUND_READY: OnceLock<Box<dyn Fn() + Send + Sync>> = OnceLock::new();
+
+// The placeholder a rendered fence leaves in comrak's output, with the `<pre>`
+// attributes (source position) kept for the figure that replaces it
+static PLACEHOLDER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"<pre\b([^>]*)>\s*<code\b[^>]*\bclass="language-diagram-([0-9a-f]{16})"[^>]*>\s*</code>\s*</pre>"#,
+    )
+    .expect("Invalid PLACEHOLDER_RE")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagramKind {
+    Mermaid,
+    PlantUml,
+}
+
+impl DiagramKind {
+    /// The diagram language of a fence's info string: ```` ```mermaid ````, ```` ```plantuml ````
+    /// or ```` ```puml ````
+    pub fn from_info(info: &str) -> Option<Self> {
+        match info
+            .split_whitespace()
+            .next()?
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "mermaid" => Some(Self::Mermaid),
+            "plantuml" | "puml" => Some(Self::PlantUml),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Mermaid => "mermaid",
+            Self::PlantUml => "plantuml",
+        }
+    }
+
+    fn source_extension(self) -> &'static str {
+        match self {
+            Self::Mermaid => "mmd",
+            Self::PlantUml => "puml",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagramFormat {
+    /// For the preview and HTML exports
+    Svg,
+    /// For PDF, which can't draw SVG
+    Png,
+}
+
+impl DiagramFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Svg => "svg",
+            Self::Png => "png",
+        }
+    }
+}
+
+/// External tools that turn diagram fences into images. Each command is a program
+/// followed by its arguments: `{input}` and `{output}` become temporary file paths and
+/// `{format}` becomes `svg` or `png`. Without `{input}` the source is piped to stdin;
+/// without `{output}` the image is read from stdout. Off by default: the tools run on
+/// whatever a previewed document contains, so the user opts in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiagramSettings {
+    /// Off, the default, leaves diagram fences as code blocks
+    pub enabled: bool,
+    /// mermaid-cli by default
+    pub mermaid_command: Vec<String>,
+    pub plantuml_command: Vec<String>,
+    /// A tool still running after this long is stopped and the fence shown as code
+    pub timeout_seconds: u64,
+}
+
+impl Default for DiagramSettings {
+    fn default() -> Self {
+        // npm installs mmdc as a batch file on Windows
+        let mmdc = if cfg!(windows) { "mmdc.cmd" } else { "mmdc" };
+        Self {
+            enabled: false,
+            mermaid_command: [mmdc, "-i", "{input}", "-o", "{output}", "-b", "transparent"]
+                .map(String::from)
+                .to_vec(),
+            plantuml_command: ["plantuml", "-t{format}", "-pipe"]
+                .map(String::from)
+                .to_vec(),
+            timeout_seconds: DEFAULT_TIMEOUT_SECONDS,
+        }
+    }
+}
+
+impl DiagramSettings {
+    fn command(&self, kind: DiagramKind) -> &[String] {
+        match kind {
+            DiagramKind::Mermaid => &self.mermaid_command,
+            DiagramKind::PlantUml => &self.plantuml_command,
+        }
+    }
+}
+
+/// A diagram fence that rendered, waiting to replace its placeholder in the HTML
+#[derive(Debug, Clone)]
+pub struct RenderedDiagram {
+    kind: DiagramKind,
+    svg: Arc<Vec<u8>>,
+}
+
+struct BackgroundBatch {
+    jobs: Vec<(DiagramKind, String)>,
+    settings: DiagramSettings,
+    due: Instant,
+}
+
+/// Renders the diagram fences under `root` to SVG and turns each one that rendered into
+/// an empty placeholder block; `insert_rendered` swaps the placeholders for the images
+/// once the HTML is generated and sanitized. Fences that fail to render stay as code, as
+/// do fences that would fetch remote content while `remote_policy` blocks it.
+///
+/// With `background` no tool runs here: fences missing from the cache stay as code and are
+/// rendered on a worker thread, and the returned flag says some are on their way.
+pub fn render_fences<'a>(
+    root: &'a AstNode<'a>,
+    settings: &DiagramSettings,
+    remote_policy: &RemotePolicy,
+    background: bool,
+) -> (HashMap<String, RenderedDiagram>, bool) {
+    let mut rendered = HashMap::new();
+    let mut jobs = Vec::new();
+    for node in root.descendants() {
+        let mut data = node.data.borrow_mut();
+        let NodeValue::CodeBlock(code) = &mut data.value else {
+            continue;
+        };
+        let Some(kind) = DiagramKind::from_info(&code.info) else {
+            continue;
+        };
+        if remote_policy.block_remote_images && fetches_remote(kind, &code.literal) {
+            log::debug!(
+                "[Diagrams] Skipped {} diagram that loads remote content",
+                kind.name()
+            );
+            continue;
+        }
+        let svg = if background {
+            match cached(kind, &code.literal, DiagramFormat::Svg, settings) {
+                Some(result) => result,
+                None => {
+                    jobs.push((kind, code.literal.to_string()));
+                    continue;
+                },
+            }
+        } else {
+            render(kind, &code.literal, DiagramFormat::Svg, settings).map_err(|e| e.to_string())
+        };
+        let Ok(svg) = svg else {
+            continue;
+        };
+        let id = format!("{:016x}", hash_of(&(kind, svg.as_slice())));
+        code.info = format!("diagram-{}", id).into();
+        code.literal = Default::default();
+        rendered.insert(id, RenderedDiagram { kind, svg });
+    }
+    let pending = !jobs.is_empty();
+    if pending {
+        queue_background(jobs, settings);
+    }
+    (rendered, pending)
+}
+
+/// Sets what is told when background diagrams are ready; only the first call counts
+pub fn on_background_ready(callback: impl Fn() + Send + Sync + 'static) {
+    let _ = ON_BACKGROUND_READY.set(Box::new(callback));
+}
+
+fn queue_background(jobs: Vec<(DiagramKind, String)>, settings: &DiagramSettings) {
+    BACKGROUND_WORKER.call_once(|| {
+        if let Err(e) = std::thread::Builder::new()
+            .name("diagrams".into())
+            .spawn(run_background)
+        {
+            log::warn!("[Diagrams] Failed to start the background renderer: {}", e);
+        }
+    });
+    let (batch, wake) = &*BACKGROUND;
+    *batch.lock().unwrap_or_else(|e| e.into_inner()) = Some(BackgroundBatch {
+        jobs,
+        settings: settings.clone(),
+        due: Instant::now() + BACKGROUND_DELAY,
+    });
+    wake.notify_one();
+}
+
+/// Waits for a batch to stay unreplaced until it is due, renders it into the cache and
+/// tells the preview, for the life of the app
+fn run_background() {
+    let (batch, wake) = &*BACKGROUND;
+    loop {
+        let next = {
+            let mut guard = batch.lock().unwrap_or_else(|e| e.into_inner());
+            loop {
+                let wait = match guard.as_ref() {
+                    None => None,
+                    Some(next) => match next.due.checked_duration_since(Instant::now()) {
+                        Some(wait) if !wait.is_zero() => Some(wait),
+                        _ => break guard.take(),
+                    },
+                };
+                guard = match wait {
+                    Some(wait) => {
+                        wake.wait_timeout(guard, wait)
+                            .unwrap_or_else(|e| e.into_inner())
+                            .0
+                    },
+                    None => wake.wait(guard).unwrap_or_else(|e| e.into_inner()),
+                };
+            }
+        };
+        let Some(next) = next else {
+            continue;
+        };
+
+        let mut any_rendered = false;
+        for (kind, source) in &next.jobs {
+            any_rendered |= render(*kind, source, DiagramFormat::Svg, &next.settings).is_ok();
+        }
+        // Failures are cached as well and stay as code, which the preview already shows
+        if any_rendered && let Some(notify) = ON_BACKGROUND_READY.get() {
+            notify();
+        }
+    }
+}
+
+/// Replaces the placeholders left by `render_fences` with `<figure>`s holding the SVG as
+/// an image. An image rather than inline SVG keeps scripts and styles in the tool's output
+/// from reaching the page. With `linked` the image is served by the `preview` protocol;
+/// otherwise it is a `data:` URI, which works in exported files without anything beside them.
+pub fn insert_rendered(
+    html: &str,
+    rendered: &HashMap<String, RenderedDiagram>,
+    linked: bool,
+) -> String {
+    PLACEHOLDER_RE
+        .replace_all(html, |caps: &regex::Captures| match rendered.get(&caps[2]) {
+            Some(diagram) => format!(
+                r#"<figure class="diagram diagram-{}"{}><img src="{}" alt="{} diagram" /></figure>"#,
+                diagram.kind.name(),
+                &caps[1],
+                if linked {
+                    preview_assets::register_diagram(&caps[2], &diagram.svg)
+                } else {
+                    svg_data_uri(&diagram.svg)
+                },
+                diagram.kind.name()
+            ),
+            None => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+/// Serves a cached render's diagrams to the preview again; the preview only keeps the
+/// most recently linked ones, so those `insert_rendered` linked may have been dropped
+pub fn relink(rendered: &HashMap<String, RenderedDiagram>) {
+    for (id, diagram) in rendered {
+        preview_assets::register_diagram(id, &diagram.svg);
+    }
+}
+
+/// A diagram already rendered, or the remembered failure, without running anything
+fn cached(
+    kind: DiagramKind,
+    source: &str,
+    format: DiagramFormat,
+    settings: &DiagramSettings,
+) -> Option<Result<Arc<Vec<u8>>, String>> {
+    let key = hash_of(&(kind, format, source, settings.command(kind)));
+    DIAGRAM_CACHE
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&key).cloned())
+}
+
+/// Renders one diagram with the configured tool, from the cache when the same source was
+/// rendered before
+pub fn render(
+    kind: DiagramKind,
+    source: &str,
+    format: DiagramFormat,
+    settings: &DiagramSettings,
+) -> Result<Arc<Vec<u8>>> {
+    if let Some(cached) = cached(kind, source, format, settings) {
+        return cached.map_err(|e| anyhow!(e));
+    }
+    let command = settings.command(kind);
+    let key = hash_of(&(kind, format, source, command));
+
+    let start = Instant::now();
+    let timeout = Duration::from_secs(settings.timeout_seconds.max(1));
+    let result = run_tool(command, kind, source, format, timeout)
+        .map(Arc::new)
+        .map_err(|e| format!("{:#}", e));
+    match &result {
+        Ok(image) => log::info!(
+            "[Diagrams] render | duration={:?} | kind={} | size={} bytes",
+            start.elapsed(),
+            kind.name(),
+            image.len()
+        ),
+        Err(e) => log::warn!("[Diagrams] Failed to render {} diagram: {}", kind.name(), e),
+    }
+
+    if let Ok(mut cache) = DIAGRAM_CACHE.lock() {
+        if cache.len() >= MAX_CACHED_DIAGRAMS {
+            cache.clear();
+        }
+        cache.insert(key, result.clone());
+    }
+    result.map_err(|e| anyhow!(e))
+}
+
+/// Approximate bytes held by cached diagrams
+pub fn cache_bytes() -> usize {
+    DIAGRAM_CACHE.lock().map_or(0, |cache| {
+        cache
+            .values()
+            .map(|entry| entry.as_ref().map_or(0, |image| image.len()))
+            .sum()
+    })
+}
+
+/// Drops cached diagrams, including remembered failures
+pub fn clear_cache() {
+    if let Ok(mut cache) = DIAGRAM_CACHE.lock() {
+        cache.clear();
+        cache.shrink_to_fit();
+    }
+}
+
+/// Whether rendering the diagram could make a request: a URL anywhere in it, or a
+/// PlantUML include, which can name a URL through a variable
+fn fetches_remote(kind: DiagramKind, source: &str) -> bool {
+    if source.contains("http://") || source.contains("https://") {
+        return true;
+    }
+    kind == DiagramKind::PlantUml
+        && source.lines().any(|line| {
+            let line = line.trim_start();
+            line.starts_with("!include") || line.starts_with("!import")
+        })
+}
+
+fn hash_of<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Runs the tool in a temporary folder that is removed afterwards
+fn run_tool(
+    command: &[String],
+    kind: DiagramKind,
+    source: &str,
+    format: DiagramFormat,
+    timeout: Duration,
+) -> Result<Vec<u8>> {
+    let dir = std::env::temp_dir().join(format!("markdownrs-diagram-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).context("failed to create a temporary folder")?;
+    let result = run_tool_in(&dir, command, kind, source, format, timeout);
+    if let Err(e) = std::fs::remove_dir_all(&dir) {
+        log::debug!("Failed to remove diagram folder {:?}: {}", dir, e);
+    }
+    result
+}
+
+fn run_tool_in(
+    dir: &Path,
+    command: &[String],
+    kind: DiagramKind,
+    source: &str,
+    format: DiagramFormat,
+    timeout: Duration,
+) -> Result<Vec<u8>> {
+    let (program, args) = command.split_first().context("no command is configured")?;
+    let input = dir.join(format!("diagram.{}", kind.source_extension()));
+    let output = dir.join(format!("diagram.{}", format.extension()));
+    let reads_file = args.iter().any(|arg| arg.contains("{input}"));
+    let writes_file = args.iter().any(|arg| arg.contains("{output}"));
+    if reads_file {
+        std::fs::write(&input, source).context("failed to write the diagram source")?;
+    }
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| {
+            arg.replace("{input}", &input.to_string_lossy())
+                .replace("{output}", &output.to_string_lossy())
+                .replace("{format}", format.extension())
+        })
+        .collect();
+
+    let mut process = Command::new(program);
+    process
+        .args(&args)
+        .current_dir(dir)
+        .stdin(if reads_file {
+            Stdio::null()
+        } else {
+            Stdio::piped()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if kind == DiagramKind::PlantUml {
+        let (name, value) = PLANTUML_SECURITY_PROFILE;
+        process.env(name, value);
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        process.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = process
+        .spawn()
+        .with_context(|| format!("failed to start '{}'", program))?;
+
+    // Pipes are drained on their own threads so a chatty tool can't block on a full pipe
+    if let Some(mut stdin) = child.stdin.take() {
+        let source = source.to_string();
+        std::thread::spawn(move || stdin.write_all(source.as_bytes()));
+    }
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("'{}' did not finish within {:?}", program, timeout);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+
+    if !status.success() {
+        let message = String::from_utf8_lossy(&stderr);
+        bail!("'{}' failed ({}): {}", program, status, message.trim());
+    }
+    let image = if writes_file {
+        std::fs::read(&output).context("the tool wrote no image")?
+    } else {
+        stdout
+    };
+    if image.is_empty() {
+        bail!("'{}' produced an empty image", program);
+    }
+    Ok(image)
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+/// A `data:` URI for SVG text, percent-encoding only what a quoted attribute and URL
+/// parsing need, which keeps it far smaller than base64 for typical diagrams
+fn svg_data_uri(svg: &[u8]) -> String {
+    let mut uri = String::from("data:image/svg+xml;charset=utf-8,");
+    for &byte in svg {
+        if byte.is_ascii_alphanumeric() || b"-_.!~*()/:;=,+@$".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
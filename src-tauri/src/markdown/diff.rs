@@ -0,0 +1,168 @@
+use crate::markdown::front_matter;
+use crate::markdown::merge::{lcs_matches, split_blocks};
+use crate::markdown::renderer::{self, MarkdownOptions};
+use crate::markdown::security::SanitizationReport;
+use anyhow::Result;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct MarkdownDiff {
+    /// The new version's HTML with removed text in `<del>` and added text in `<ins>`
+    pub html: String,
+    pub blocks_added: usize,
+    pub blocks_removed: usize,
+    pub blocks_changed: usize,
+    pub sanitization: SanitizationReport,
+    /// Local files the HTML loads through the `preview` protocol
+    pub local_images: Vec<String>,
+}
+
+/// Renders `new` with the changes since `old` marked up. Blocks are aligned first; a
+/// block only on one side is wrapped whole, and a reworded block of the same kind is
+/// diffed word by word in its rendered HTML, so markup never splits a tag.
+pub fn render_diff(old: &str, new: &str, options: &MarkdownOptions) -> Result<MarkdownDiff> {
+    let old_blocks = split_blocks(front_matter::split(old).map_or(old, |fm| fm.body));
+    let new_blocks = split_blocks(front_matter::split(new).map_or(new, |fm| fm.body));
+    let matches = lcs_matches(&old_blocks, &new_blocks);
+
+    let mut sanitization = SanitizationReport::default();
+    let mut local_images = Vec::new();
+    let mut render = |markdown: &str| -> Result<String> {
+        let fragment = renderer::render_fragment(markdown, options)?;
+        sanitization.merge(&fragment.sanitization);
+        local_images.extend(fragment.local_images);
+        Ok(fragment.html)
+    };
+
+    let mut parts = Vec::new();
+    let (mut blocks_added, mut blocks_removed, mut blocks_changed) = (0, 0, 0);
+    let (mut o, mut n) = (0, 0);
+    let aligned = matches
+        .iter()
+        .enumerate()
+        .filter_map(|(i, m)| m.map(|j| (i, j)))
+        .chain(std::iter::once((old_blocks.len(), new_blocks.len())));
+    for (i, j) in aligned {
+        // Unmatched blocks between two matches pair up as changes, the rest are added or
+        // removed
+        let removed = &old_blocks[o..i];
+        let added = &new_blocks[n..j];
+        for k in 0..removed.len().max(added.len()) {
+            match (removed.get(k), added.get(k)) {
+                (Some(before), Some(after)) => {
+                    let (before, after) = (render(&before.text)?, render(&after.text)?);
+                    if first_tag(&before) == first_tag(&after) {
+                        parts.push(word_diff(&before, &after));
+                    } else {
+                        parts.push(wrap_block("del", &before));
+                        parts.push(wrap_block("ins", &after));
+                    }
+                    blocks_changed += 1;
+                },
+                (Some(before), None) => {
+                    parts.push(wrap_block("del", &render(&before.text)?));
+                    blocks_removed += 1;
+                },
+                (None, Some(after)) => {
+                    parts.push(wrap_block("ins", &render(&after.text)?));
+                    blocks_added += 1;
+                },
+                (None, None) => {},
+            }
+        }
+        if let Some(block) = new_blocks.get(j) {
+            parts.push(render(&block.text)?);
+        }
+        (o, n) = (i + 1, j + 1);
+    }
+
+    Ok(MarkdownDiff {
+        html: parts.join(""),
+        blocks_added,
+        blocks_removed,
+        blocks_changed,
+        sanitization,
+        local_images,
+    })
+}
+
+fn wrap_block(tag: &str, html: &str) -> String {
+    format!("<{tag} class=\"diff-block\">{html}</{tag}>")
+}
+
+/// Name of the block's outer element, such as `p` or `ul`
+fn first_tag(html: &str) -> &str {
+    html.trim_start()
+        .strip_prefix('<')
+        .and_then(|rest| rest.split([' ', '>', '/']).next())
+        .unwrap_or("")
+}
+
+/// Splits HTML into tags, whitespace runs and words
+fn tokenize(html: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+    while let Some(c) = rest.chars().next() {
+        let len = if c == '<' {
+            rest.find('>').map_or(rest.len(), |end| end + 1)
+        } else if c.is_whitespace() {
+            rest.find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len())
+        } else {
+            rest.find(|c: char| c == '<' || c.is_whitespace())
+                .unwrap_or(rest.len())
+        };
+        tokens.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+/// The new HTML with words missing from the old in `<ins>` and removed words in `<del>`.
+/// Removed tags are dropped and added tags kept, so the result nests like the new HTML.
+fn word_diff(old: &str, new: &str) -> String {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    let matches = lcs_matches(&old_tokens, &new_tokens);
+
+    let mut out = String::with_capacity(new.len() + 64);
+    let (mut o, mut n) = (0, 0);
+    let aligned = matches
+        .iter()
+        .enumerate()
+        .filter_map(|(i, m)| m.map(|j| (i, j)))
+        .chain(std::iter::once((old_tokens.len(), new_tokens.len())));
+    for (i, j) in aligned {
+        push_run(&mut out, "del", &old_tokens[o..i], false);
+        push_run(&mut out, "ins", &new_tokens[n..j], true);
+        if let Some(token) = new_tokens.get(j) {
+            out.push_str(token);
+        }
+        (o, n) = (i + 1, j + 1);
+    }
+    out
+}
+
+/// Wraps each stretch of text in `tokens` in `tag`; tags in between are kept or dropped
+fn push_run(out: &mut String, tag: &str, tokens: &[&str], keep_tags: bool) {
+    let mut text = String::new();
+    let flush = |out: &mut String, text: &mut String| {
+        if !text.trim().is_empty() {
+            out.push_str(&format!("<{tag}>{text}</{tag}>"));
+        } else if keep_tags {
+            out.push_str(text);
+        }
+        text.clear();
+    };
+    for token in tokens {
+        if token.starts_with('<') {
+            flush(out, &mut text);
+            if keep_tags {
+                out.push_str(token);
+            }
+        } else {
+            text.push_str(token);
+        }
+    }
+    flush(out, &mut text);
+}
@@ -0,0 +1,217 @@
+use crate::markdown::front_matter;
+use regex::Regex;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+/// Class on the preview element that document CSS is scoped to
+pub const SCOPE_CLASS: &str = "document-css";
+
+/// Longer stylesheets are ignored; this is for tweaks, not themes
+const MAX_CSS_BYTES: usize = 16 * 1024;
+
+/// Properties a document may set. Layout and typography only: nothing that can load a
+/// resource, position content over the app, or hide the document.
+const ALLOWED_PROPERTIES: &[&str] = &[
+    "color",
+    "background-color",
+    "opacity",
+    "line-height",
+    "letter-spacing",
+    "word-spacing",
+    "text-align",
+    "text-decoration",
+    "text-decoration-color",
+    "text-indent",
+    "text-transform",
+    "white-space",
+    "word-break",
+    "overflow-wrap",
+    "hyphens",
+    "tab-size",
+    "vertical-align",
+    "width",
+    "min-width",
+    "max-width",
+    "height",
+    "min-height",
+    "max-height",
+    "overflow-x",
+    "table-layout",
+    "border-collapse",
+    "border-spacing",
+    "caption-side",
+    "list-style-type",
+    "list-style-position",
+    "column-count",
+    "column-gap",
+    "column-width",
+    "break-inside",
+];
+
+/// Property families allowed by prefix, e.g. `margin-left`, `font-weight`, `border-top`
+const ALLOWED_PREFIXES: &[&str] = &["margin", "padding", "border", "font", "outline"];
+
+/// Values that could fetch a resource or run script in older engines
+static UNSAFE_VALUE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)url\s*\(|image-set\s*\(|expression\s*\(|javascript:|\\|<|@")
+        .expect("Invalid UNSAFE_VALUE_RE")
+});
+
+static COMMENT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)/\*.*?\*/").expect("Invalid COMMENT_RE"));
+
+/// The document's `css:` front matter (a string or a list of strings), sanitized and scoped
+/// to `.document-css`. `None` when there is none or nothing survives sanitizing.
+pub fn from_front_matter(content: &str) -> Option<String> {
+    let css = match parse_field(content)? {
+        Value::String(css) => css,
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return None,
+    };
+    if css.len() > MAX_CSS_BYTES {
+        log::warn!(
+            "Ignoring document CSS: {} bytes exceeds the {} byte limit",
+            css.len(),
+            MAX_CSS_BYTES
+        );
+        return None;
+    }
+    let sanitized = sanitize(&css);
+    (!sanitized.is_empty()).then_some(sanitized)
+}
+
+fn parse_field(content: &str) -> Option<Value> {
+    // Skip the YAML/TOML parse for the common case of front matter without styling
+    let fm = front_matter::split(content)?;
+    if !fm.raw.contains("css") {
+        return None;
+    }
+    front_matter::parse(content)?.get("css").cloned()
+}
+
+/// Keeps rules whose selectors and declarations pass the allowlist, scoping every selector
+/// under `.document-css`. At-rules (`@import`, `@media`, `@font-face`...) are dropped.
+/// CSS with no braces is treated as declarations for the document itself.
+pub fn sanitize(css: &str) -> String {
+    let css = COMMENT_RE.replace_all(css, "");
+    if !css.contains('{') {
+        return rule(&format!(".{}", SCOPE_CLASS), &css).unwrap_or_default();
+    }
+
+    let mut rules = Vec::new();
+    let mut rest = css.as_ref();
+    while let Some(open) = rest.find('{') {
+        let prelude = rest[..open].trim();
+        let Some(close) = block_end(&rest[open..]).map(|end| open + end) else {
+            break;
+        };
+        let body = &rest[open + 1..close];
+        rest = &rest[close + 1..];
+
+        if prelude.starts_with('@') {
+            log::debug!("Dropping at-rule from document CSS: {}", prelude);
+            continue;
+        }
+        if let Some(selector) = scope_selectors(prelude)
+            && let Some(rule) = rule(&selector, body)
+        {
+            rules.push(rule);
+        }
+    }
+    rules.join("\n")
+}
+
+/// Offset of the `}` closing the block that opens at the start of `text`
+fn block_end(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return Some(i);
+                }
+            },
+            _ => {},
+        }
+    }
+    None
+}
+
+/// Prefixes each selector in a list with the scope class. `html`, `body` and `:root` mean
+/// the document itself. Returns `None` if any selector looks malformed, or could reach the
+/// scope element itself or its siblings: a leading combinator, `&` or `:scope`.
+fn scope_selectors(prelude: &str) -> Option<String> {
+    let scope = format!(".{}", SCOPE_CLASS);
+    let scoped = prelude
+        .split(',')
+        .map(str::trim)
+        .map(|selector| {
+            if selector.is_empty()
+                || selector.contains(['<', '{', '}', '@', '\\', '&'])
+                || selector.to_ascii_lowercase().contains(":scope")
+            {
+                return None;
+            }
+            let rest = ["html", "body", ":root"]
+                .iter()
+                .find_map(|root| {
+                    selector
+                        .strip_prefix(root)
+                        .filter(|r| r.is_empty() || r.starts_with(char::is_whitespace))
+                })
+                .map(str::trim);
+            if rest
+                .unwrap_or(selector)
+                .starts_with(|c| matches!(c, '>' | '+' | '~'))
+            {
+                return None;
+            }
+            Some(match rest {
+                Some("") => scope.clone(),
+                Some(rest) => format!("{} {}", scope, rest),
+                None => format!("{} {}", scope, selector),
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(scoped.join(", "))
+}
+
+fn rule(selector: &str, body: &str) -> Option<String> {
+    let declarations: Vec<String> = body
+        .split(';')
+        .filter_map(|declaration| {
+            let (property, value) = declaration.split_once(':')?;
+            let property = property.trim().to_ascii_lowercase();
+            let value = value.trim();
+            if value.is_empty()
+                || !is_allowed_property(&property)
+                || UNSAFE_VALUE_RE.is_match(value)
+            {
+                log::debug!("Dropping document CSS declaration: {}", declaration.trim());
+                return None;
+            }
+            Some(format!("{}: {};", property, value))
+        })
+        .collect();
+    (!declarations.is_empty()).then(|| format!("{} {{ {} }}", selector, declarations.join(" ")))
+}
+
+fn is_allowed_property(property: &str) -> bool {
+    // border-image loads an image
+    if property.starts_with("border-image") {
+        return false;
+    }
+    ALLOWED_PROPERTIES.contains(&property)
+        || ALLOWED_PREFIXES.iter().any(|prefix| {
+            property == *prefix
+                || property
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('-'))
+        })
+}
@@ -0,0 +1,175 @@
+use crate::markdown::renderer::resolve_local_image;
+use crate::utils::path_identity;
+use regex::{Captures, Regex};
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Inline link and image destinations: `](dest` or `](<dest>`
+static INLINE_DEST_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(\]\(\s*)(<[^>\n]*>|[^\s()<>]+)").expect("Invalid INLINE_DEST_RE")
+});
+
+/// Reference definitions: `[label]: dest`
+static REFERENCE_DEST_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^( {0,3}\[[^\]\n]+\]:[ \t]*)(<[^>\n]*>|\S+)")
+        .expect("Invalid REFERENCE_DEST_RE")
+});
+
+/// Wikilinks: `[[target]]`, `[[target#heading]]`, `[[target|label]]`
+static WIKILINK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[\[([^\]\[|#\n]+)([^\]\n]*)\]\]").expect("Invalid WIKILINK_RE"));
+
+/// A file that moved from `from` to `to`
+#[derive(Debug, Clone, Copy)]
+pub struct FileMove<'a> {
+    pub from: &'a Path,
+    pub to: &'a Path,
+}
+
+/// Rewrites local link destinations in a document after a file move. `old_dir` is the
+/// folder the document's links were written against and `new_dir` the folder it lives in
+/// now; they differ only for the moved document itself. Links to `moved.from` follow the
+/// file, relative links stay relative, and URLs, anchors and untouched links are left as
+/// written. Wikilinks name notes by file name, so they change only when the name does.
+/// Returns the new content and the number of links changed.
+pub fn retarget_links(
+    content: &str,
+    old_dir: &Path,
+    new_dir: &Path,
+    moved: FileMove<'_>,
+) -> (String, usize) {
+    let from_key = path_identity(&moved.from.to_string_lossy(), false);
+    let mut count = 0;
+    let mut replace =
+        |caps: &Captures| match retarget(&caps[2], old_dir, new_dir, &from_key, moved.to) {
+            Some(dest) => {
+                count += 1;
+                format!("{}{}", &caps[1], dest)
+            },
+            None => caps[0].to_string(),
+        };
+    let content = INLINE_DEST_RE
+        .replace_all(content, &mut replace)
+        .into_owned();
+    let content = REFERENCE_DEST_RE
+        .replace_all(&content, &mut replace)
+        .into_owned();
+
+    let (old_stem, new_stem) = (file_stem(moved.from), file_stem(moved.to));
+    if old_stem == new_stem || old_stem.is_empty() {
+        return (content, count);
+    }
+    let content = WIKILINK_RE
+        .replace_all(&content, |caps: &Captures| {
+            match retarget_wikilink(&caps[1], &old_stem, &new_stem) {
+                Some(target) => {
+                    count += 1;
+                    format!("[[{}{}]]", target, &caps[2])
+                },
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned();
+    (content, count)
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// The wikilink target with the note name swapped, keeping any folder prefix and
+/// extension as written. Note names match case-insensitively, as wikilinks resolve.
+fn retarget_wikilink(target: &str, old_stem: &str, new_stem: &str) -> Option<String> {
+    let trimmed = target.trim();
+    let (folder, name) = match trimmed.rfind('/') {
+        Some(slash) => trimmed.split_at(slash + 1),
+        None => ("", trimmed),
+    };
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, ext)) if ["md", "markdown"].contains(&ext.to_lowercase().as_str()) => {
+            (stem, &name[stem.len()..])
+        },
+        _ => (name, ""),
+    };
+    (stem.to_lowercase() == old_stem.to_lowercase())
+        .then(|| format!("{}{}{}", folder, new_stem, extension))
+}
+
+/// The new destination for one link, or `None` when it stays as written
+fn retarget(
+    dest: &str,
+    old_dir: &Path,
+    new_dir: &Path,
+    from_key: &str,
+    to: &Path,
+) -> Option<String> {
+    let (inner, angled) = match dest.strip_prefix('<').and_then(|d| d.strip_suffix('>')) {
+        Some(inner) => (inner, true),
+        None => (dest, false),
+    };
+    if inner.is_empty() || inner.starts_with('#') {
+        return None;
+    }
+    let target = resolve_local_image(inner, old_dir)?;
+    let moved = path_identity(&target.to_string_lossy(), false) == from_key;
+    if !moved && old_dir == new_dir {
+        return None;
+    }
+    let target = if moved { to.to_path_buf() } else { target };
+
+    let split = inner.find(['?', '#']).unwrap_or(inner.len());
+    let (path_part, suffix) = inner.split_at(split);
+    let absolute = Path::new(path_part).has_root()
+        || path_part.get(1..2) == Some(":")
+        || path_part
+            .get(..5)
+            .is_some_and(|s| s.eq_ignore_ascii_case("file:"));
+    if absolute && !moved {
+        return None;
+    }
+
+    let new_path = if absolute {
+        target.to_string_lossy().replace('\\', "/")
+    } else {
+        relative_path(new_dir, &target)
+            .unwrap_or_else(|| target.to_string_lossy().replace('\\', "/"))
+    };
+    let new_path = if angled {
+        new_path
+    } else {
+        new_path.replace(' ', "%20")
+    };
+    if new_path == path_part {
+        return None;
+    }
+    Some(if angled {
+        format!("<{}{}>", new_path, suffix)
+    } else {
+        format!("{}{}", new_path, suffix)
+    })
+}
+
+/// `target` relative to the folder `base`, with forward slashes. `None` when the two are
+/// on different drives or roots.
+pub fn relative_path(base: &Path, target: &Path) -> Option<String> {
+    let base: Vec<Component> = base.components().collect();
+    let target_components: Vec<Component> = target.components().collect();
+    if base.first() != target_components.first() {
+        return None;
+    }
+    let common = base
+        .iter()
+        .zip(&target_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &target_components[common..] {
+        relative.push(component.as_os_str());
+    }
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
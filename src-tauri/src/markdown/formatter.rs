@@ -1,4 +1,7 @@
-use crate::markdown::config::{DEFAULT_LIST_INDENT, MarkdownFlavor};
+use crate::markdown::config::{DEFAULT_LINE_WIDTH, DEFAULT_LIST_INDENT, MarkdownFlavor};
+use crate::markdown::lists::{self, OrderedListStyle};
+use crate::markdown::reference_links::{self, LinkStyle};
+use crate::markdown::{front_matter, lint, sections};
 use anyhow::{Result, anyhow};
 use dprint_plugin_markdown::configuration::{
     ConfigurationBuilder, EmphasisKind, StrongKind, TextWrap, UnorderedListKind,
@@ -28,6 +31,29 @@ static BOX_DRAWING_RE: LazyLock<Regex> = LazyLock::new(|| {
 static PROTECTED_LINE_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"__PROTECTED_LINE_(\d+)__").expect("Invalid PROTECTED_LINE_RE"));
 
+// `<!-- toc -->`, `<!-- toc depth=2 ordered -->`
+static TOC_START_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*<!--\s*toc((?:\s+[\w=]+)*)\s*-->\s*$").expect("Invalid TOC_START_RE")
+});
+const TOC_END: &str = "<!-- /toc -->";
+
+/// Fewest columns prose is hard-wrapped at, so a stray small width can't put every word
+/// on its own line
+const MIN_LINE_WIDTH: u32 = 20;
+
+/// How the formatter treats line breaks inside paragraphs
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WrapMode {
+    /// Keep the lines as written
+    #[default]
+    Maintain,
+    /// Hard-wrap prose at `line_width` columns
+    Always,
+    /// Join each paragraph onto a single line
+    Never,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FormatterOptions {
     pub flavor: MarkdownFlavor,
@@ -38,6 +64,11 @@ pub struct FormatterOptions {
     pub table_alignment: bool,
     pub normalize_whitespace: bool,
     pub max_blank_lines: usize,
+    pub text_wrap: WrapMode,
+    /// Column prose is wrapped at with `WrapMode::Always`
+    pub line_width: u32,
+    pub link_style: LinkStyle,
+    pub ordered_list_style: OrderedListStyle,
 }
 
 impl Default for FormatterOptions {
@@ -51,11 +82,18 @@ impl Default for FormatterOptions {
             table_alignment: true,
             normalize_whitespace: true,
             max_blank_lines: crate::markdown::config::DEFAULT_MAX_BLANK_LINES,
+            text_wrap: WrapMode::default(),
+            line_width: DEFAULT_LINE_WIDTH,
+            link_style: LinkStyle::default(),
+            ordered_list_style: OrderedListStyle::default(),
         }
     }
 }
 
 pub fn format_markdown(content: &str, options: &FormatterOptions) -> Result<String> {
+    // Before dprint, so the definitions moved to the end get formatted with the rest
+    let content = &*reference_links::apply(content, options.link_style, options.flavor);
+
     // Replace protected lines (box-drawing / ASCII art) with unique tokens before
     // handing the text to dprint, so dprint line-count shifts cannot desync their positions.
     let mut protected_lines: Vec<String> = Vec::new();
@@ -76,7 +114,12 @@ pub fn format_markdown(content: &str, options: &FormatterOptions) -> Result<Stri
     }
 
     let mut builder = ConfigurationBuilder::new();
-    builder.text_wrap(TextWrap::Maintain);
+    builder.text_wrap(match options.text_wrap {
+        WrapMode::Maintain => TextWrap::Maintain,
+        WrapMode::Always => TextWrap::Always,
+        WrapMode::Never => TextWrap::Never,
+    });
+    builder.line_width(options.line_width.max(MIN_LINE_WIDTH));
 
     if let Some(char) = options.emphasis_char.chars().next() {
         let (e_kind, s_kind) = match char {
@@ -132,8 +175,19 @@ pub fn format_markdown(content: &str, options: &FormatterOptions) -> Result<Stri
         formatted
     };
 
+    // dprint keeps list numbers as written
+    let result = if options.ordered_list_style == OrderedListStyle::Preserve {
+        result
+    } else {
+        lists::renumber_ordered_lists(&result, options.ordered_list_style, options.flavor).content
+    };
+
     // Post-processing
-    Ok(post_process_formatting(&result, options))
+    let result = post_process_formatting(&result, options);
+    if result.contains("<!--") {
+        return Ok(update_toc(&result, options));
+    }
+    Ok(result)
 }
 
 fn post_process_formatting(content: &str, options: &FormatterOptions) -> String {
@@ -238,3 +292,201 @@ fn post_process_formatting(content: &str, options: &FormatterOptions) -> String
 fn convert_backslashes_to_spaces(content: &str) -> String {
     BACKSLASH_RE.replace_all(content, "${1}  ").to_string()
 }
+
+/// Table of contents settings, written into the opening marker so later updates keep them.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TocOptions {
+    /// Deepest heading level listed
+    pub max_depth: u8,
+    pub ordered: bool,
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            ordered: false,
+        }
+    }
+}
+
+impl TocOptions {
+    fn from_marker_args(args: &str) -> Self {
+        let mut options = Self::default();
+        for arg in args.split_whitespace() {
+            match arg.split_once('=') {
+                Some(("depth", value)) => {
+                    if let Ok(depth) = value.parse::<u8>() {
+                        options.max_depth = depth.clamp(1, 6);
+                    }
+                },
+                None if arg == "ordered" => options.ordered = true,
+                _ => {},
+            }
+        }
+        options
+    }
+
+    fn start_marker(&self) -> String {
+        format!(
+            "<!-- toc depth={}{} -->",
+            self.max_depth,
+            if self.ordered { " ordered" } else { "" }
+        )
+    }
+}
+
+/// Escapes the characters that would end or nest a link's text
+pub(crate) fn escape_link_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
+
+/// The list between the markers: headings down to `max_depth`, nested by level. A heading
+/// that skips levels nests only one step under the previous entry.
+fn toc_lines(content: &str, toc: &TocOptions, options: &FormatterOptions) -> Vec<String> {
+    let headings = sections::headings(content, options.flavor);
+    let anchors = sections::anchors(&headings);
+    let entries: Vec<_> = headings
+        .iter()
+        .zip(anchors)
+        .filter(|(h, anchor)| h.level <= toc.max_depth && !anchor.is_empty())
+        .collect();
+    let Some(min_level) = entries.iter().map(|(h, _)| h.level).min() else {
+        return Vec::new();
+    };
+
+    let bullet = options.bullet_char.chars().next().unwrap_or('-');
+    let mut counters: Vec<usize> = Vec::new();
+    // Column where entries at each depth start
+    let mut offsets: Vec<usize> = vec![0];
+    let mut lines = Vec::with_capacity(entries.len());
+
+    for (heading, anchor) in entries {
+        let depth = ((heading.level - min_level) as usize).min(counters.len());
+        counters.truncate(depth + 1);
+        if counters.len() == depth {
+            counters.push(0);
+        }
+        counters[depth] += 1;
+
+        let marker = if toc.ordered {
+            format!("{}.", counters[depth])
+        } else {
+            bullet.to_string()
+        };
+        let indent = offsets[depth];
+        lines.push(format!(
+            "{}{} [{}](#{})",
+            " ".repeat(indent),
+            marker,
+            escape_link_text(&heading.text),
+            anchor
+        ));
+
+        let child_offset = if toc.ordered {
+            marker.len() + 1
+        } else {
+            options.list_indent.max(2)
+        };
+        offsets.truncate(depth + 1);
+        offsets.push(indent + child_offset);
+    }
+    lines
+}
+
+/// 0-based line ranges `[start, end]` of TOC blocks outside code fences. A start marker
+/// without a closing marker covers only its own line.
+fn toc_blocks(lines: &[&str]) -> Vec<(usize, usize, TocOptions)> {
+    let mut blocks = Vec::new();
+    let mut fence = None;
+    let mut i = 0;
+    while i < lines.len() {
+        if lint::update_fence(&mut fence, lines[i]) {
+            i += 1;
+            continue;
+        }
+        if let Some(caps) = TOC_START_RE.captures(lines[i]) {
+            let end = lines[i + 1..]
+                .iter()
+                .position(|l| l.trim() == TOC_END)
+                .map_or(i, |offset| i + 1 + offset);
+            blocks.push((i, end, TocOptions::from_marker_args(&caps[1])));
+            i = end + 1;
+            continue;
+        }
+        i += 1;
+    }
+    blocks
+}
+
+fn toc_block(content: &str, toc: &TocOptions, options: &FormatterOptions) -> Vec<String> {
+    let mut block = vec![toc.start_marker()];
+    block.extend(toc_lines(content, toc, options));
+    block.push(TOC_END.to_string());
+    block
+}
+
+fn replace_toc_blocks(
+    content: &str,
+    options: &FormatterOptions,
+    override_toc: Option<&TocOptions>,
+) -> Option<String> {
+    let lines: Vec<&str> = content.split('\n').collect();
+    let blocks = toc_blocks(&lines);
+    if blocks.is_empty() {
+        return None;
+    }
+
+    let mut result: Vec<String> = Vec::with_capacity(lines.len());
+    let mut next = 0;
+    for (start, end, marker_toc) in blocks {
+        result.extend(lines[next..start].iter().map(|l| l.to_string()));
+        result.extend(toc_block(
+            content,
+            override_toc.unwrap_or(&marker_toc),
+            options,
+        ));
+        next = end + 1;
+    }
+    result.extend(lines[next..].iter().map(|l| l.to_string()));
+    Some(result.join("\n"))
+}
+
+/// Regenerates every TOC block from the current headings; content without one is returned
+/// unchanged. Running it twice gives the same result.
+pub fn update_toc(content: &str, options: &FormatterOptions) -> String {
+    replace_toc_blocks(content, options, None).unwrap_or_else(|| content.to_string())
+}
+
+/// Rewrites existing TOC blocks with `toc`, or inserts one before 1-based `line` (after
+/// any front matter when `None`).
+pub fn insert_toc(
+    content: &str,
+    line: Option<usize>,
+    toc: &TocOptions,
+    options: &FormatterOptions,
+) -> String {
+    if let Some(updated) = replace_toc_blocks(content, options, Some(toc)) {
+        return updated;
+    }
+
+    let mut lines: Vec<String> = content.split('\n').map(str::to_string).collect();
+    let fm_lines = front_matter::line_count(content);
+    let at = line
+        .map(|l| l.saturating_sub(1))
+        .unwrap_or(fm_lines)
+        .clamp(fm_lines, lines.len());
+
+    let mut block = toc_block(content, toc, options);
+    if at > 0 && !lines[at - 1].trim().is_empty() {
+        block.insert(0, String::new());
+    }
+    if lines.get(at).is_some_and(|l| !l.trim().is_empty()) {
+        block.push(String::new());
+    }
+    lines.splice(at..at, block);
+    lines.join("\n")
+}
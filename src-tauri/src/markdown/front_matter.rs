@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const YAML_DELIMITER: &str = "---";
+const TOML_DELIMITER: &str = "+++";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrontMatterFormat {
+    Yaml,
+    Toml,
+}
+
+impl FrontMatterFormat {
+    pub fn delimiter(self) -> &'static str {
+        match self {
+            Self::Yaml => YAML_DELIMITER,
+            Self::Toml => TOML_DELIMITER,
+        }
+    }
+}
+
+/// A leading front matter block: the text between the delimiters and the body after it.
+#[derive(Debug, Clone, Copy)]
+pub struct FrontMatter<'a> {
+    pub format: FrontMatterFormat,
+    pub raw: &'a str,
+    pub body: &'a str,
+}
+
+/// Splits `---` (YAML) or `+++` (TOML) front matter from the start of the document.
+pub fn split(content: &str) -> Option<FrontMatter<'_>> {
+    let content = content.trim_start_matches('\u{feff}');
+    let format = [FrontMatterFormat::Yaml, FrontMatterFormat::Toml]
+        .into_iter()
+        .find(|f| content.starts_with(f.delimiter()))?;
+    let rest = content
+        .strip_prefix(format.delimiter())
+        .and_then(|r| r.strip_prefix('\n').or_else(|| r.strip_prefix("\r\n")))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == format.delimiter() {
+            return Some(FrontMatter {
+                format,
+                raw: &rest[..offset],
+                body: &rest[offset + line.len()..],
+            });
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Number of lines taken by the front matter block, delimiters included.
+pub fn line_count(content: &str) -> usize {
+    split(content)
+        .map(|fm| fm.raw.lines().count() + 2)
+        .unwrap_or(0)
+}
+
+/// The front matter as JSON, or `None` when there is none or it does not parse to a map.
+pub fn parse(content: &str) -> Option<Value> {
+    let fm = split(content)?;
+    let value = match fm.format {
+        FrontMatterFormat::Yaml => serde_yaml_ng::from_str::<Value>(fm.raw)
+            .map_err(|e| log::debug!("Ignoring malformed YAML front matter: {}", e))
+            .ok()?,
+        FrontMatterFormat::Toml => toml::from_str::<toml::Value>(fm.raw)
+            .map_err(|e| log::debug!("Ignoring malformed TOML front matter: {}", e))
+            .ok()
+            .and_then(|v| serde_json::to_value(v).ok())?,
+    };
+    value.is_object().then_some(value)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FrontMatterInfo {
+    pub format: FrontMatterFormat,
+    /// `None` when the block does not parse to a map
+    pub data: Option<Value>,
+    pub line_count: usize,
+}
+
+/// Describes the leading front matter block, if any.
+pub fn info(content: &str) -> Option<FrontMatterInfo> {
+    let fm = split(content)?;
+    Some(FrontMatterInfo {
+        format: fm.format,
+        data: parse(content),
+        line_count: fm.raw.lines().count() + 2,
+    })
+}
+
+pub fn string_field(front_matter: &Value, key: &str) -> Option<String> {
+    front_matter
+        .get(key)?
+        .as_str()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// Export defaults a document can carry in front matter:
+/// `export: { format: pdf, theme: light, paper: A4 }`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportConfig {
+    /// Name of a saved export preset to use as the base
+    pub preset: Option<String>,
+    pub format: Option<String>,
+    pub theme: Option<String>,
+    pub paper: Option<String>,
+    pub landscape: Option<bool>,
+    pub template: Option<String>,
+    #[serde(alias = "output", alias = "output_folder")]
+    pub output_folder: Option<String>,
+    pub flavor: Option<String>,
+}
+
+pub fn export_config(content: &str) -> Option<ExportConfig> {
+    let value = parse(content)?.get("export")?.clone();
+    serde_json::from_value(value)
+        .map_err(|e| log::warn!("Ignoring malformed export front matter: {}", e))
+        .ok()
+}
@@ -0,0 +1,183 @@
+use crate::markdown::inline_html::in_code_span;
+use crate::markdown::lint::update_fence;
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Looked for in the document's folder and each folder above it; the nearest one is the
+/// workspace's glossary
+pub const GLOSSARY_FILE: &str = "glossary.toml";
+
+/// One entry of a glossary file:
+///
+/// ```toml
+/// [[term]]
+/// term = "sign in"
+/// definition = "Authenticate with a MarkdownRS account."
+/// deprecated = ["log in", "login"]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GlossaryTerm {
+    /// The preferred form
+    pub term: String,
+    pub definition: Option<String>,
+    /// Forms to flag, each suggesting `term` instead
+    pub deprecated: Vec<String>,
+    /// Match only the exact case; by default `Login` is flagged as well as `login`
+    pub case_sensitive: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Glossary {
+    #[serde(rename = "term", default)]
+    pub terms: Vec<GlossaryTerm>,
+}
+
+/// A glossary term or deprecated form found in a document
+#[derive(Debug, Clone, Serialize)]
+pub struct TermMatch {
+    /// 1-based line
+    pub line: usize,
+    /// UTF-16 offsets into the document, as the editor counts them
+    pub from: usize,
+    pub to: usize,
+    /// Index into the glossary's terms
+    pub term: usize,
+    /// The deprecated form matched; `None` when the preferred term itself was found
+    pub deprecated: Option<String>,
+}
+
+impl Glossary {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut glossary: Self = toml::from_str(text)?;
+        glossary.terms.retain(|t| !t.term.trim().is_empty());
+        Ok(glossary)
+    }
+
+    /// The glossary of the workspace `path` belongs to, if there is one
+    pub fn find(path: &Path) -> Result<Option<(PathBuf, Self)>> {
+        let start = if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(path)
+        };
+        let Some(file) = start
+            .ancestors()
+            .map(|dir| dir.join(GLOSSARY_FILE))
+            .find(|file| file.is_file())
+        else {
+            return Ok(None);
+        };
+        let text = std::fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let glossary =
+            Self::parse(&text).with_context(|| format!("Invalid glossary {}", file.display()))?;
+        Ok(Some((file, glossary)))
+    }
+
+    /// Every term and deprecated form in `content`, outside code. Longer forms win where
+    /// they overlap, so `log in` is reported rather than a `log` inside it.
+    pub fn scan(&self, content: &str) -> Vec<TermMatch> {
+        let Some((regex, forms)) = self.matcher() else {
+            return Vec::new();
+        };
+        let mut matches = Vec::new();
+        let mut fence = None;
+        let mut utf16_offset = 0;
+        for (i, line) in content.split_inclusive('\n').enumerate() {
+            let line_start = utf16_offset;
+            utf16_offset += line.encode_utf16().count();
+            if update_fence(&mut fence, line) {
+                continue;
+            }
+            for found in regex.find_iter(line) {
+                if in_code_span(line, found.start()) {
+                    continue;
+                }
+                let key = form_key(found.as_str());
+                let Some(&(term, deprecated)) =
+                    forms.get(&key).or_else(|| forms.get(&key.to_lowercase()))
+                else {
+                    continue;
+                };
+                let from = line_start + line[..found.start()].encode_utf16().count();
+                matches.push(TermMatch {
+                    line: i + 1,
+                    from,
+                    to: from + found.as_str().encode_utf16().count(),
+                    term,
+                    deprecated: deprecated.then(|| found.as_str().to_string()),
+                });
+            }
+        }
+        matches
+    }
+
+    /// One regex for every form, with a map from each form (lowercased unless the term is
+    /// case sensitive) to its term and whether it is deprecated
+    fn matcher(&self) -> Option<(Regex, HashMap<String, (usize, bool)>)> {
+        let mut forms: Vec<(&str, usize, bool, bool)> = Vec::new();
+        for (index, term) in self.terms.iter().enumerate() {
+            forms.push((term.term.trim(), index, false, term.case_sensitive));
+            for form in &term.deprecated {
+                forms.push((form.trim(), index, true, term.case_sensitive));
+            }
+        }
+        forms.retain(|(form, ..)| !form.is_empty());
+        if forms.is_empty() {
+            return None;
+        }
+        // Alternation takes the first branch that matches, so longest first
+        forms.sort_by_key(|(form, ..)| std::cmp::Reverse(form.len()));
+
+        let pattern = forms
+            .iter()
+            .map(|&(form, _, _, case_sensitive)| {
+                let escaped = form
+                    .split_whitespace()
+                    .map(regex::escape)
+                    .collect::<Vec<_>>()
+                    .join(r"\s+");
+                // `\b` only where the form starts or ends with a word character, so `C++`
+                // still matches
+                let boundary = |c: Option<char>| {
+                    if c.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                        r"\b"
+                    } else {
+                        ""
+                    }
+                };
+                format!(
+                    "{}(?{}:{}){}",
+                    boundary(form.chars().next()),
+                    if case_sensitive { "-i" } else { "i" },
+                    escaped,
+                    boundary(form.chars().last())
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("|");
+        let regex = Regex::new(&pattern)
+            .map_err(|e| log::warn!("[Glossary] Failed to build matcher: {}", e))
+            .ok()?;
+
+        let mut map = HashMap::new();
+        for (form, index, deprecated, case_sensitive) in forms {
+            let key = if case_sensitive {
+                form_key(form)
+            } else {
+                form_key(form).to_lowercase()
+            };
+            map.entry(key).or_insert((index, deprecated));
+        }
+        Some((regex, map))
+    }
+}
+
+/// A form with its whitespace collapsed, so `log  in` is found as `log in`
+fn form_key(form: &str) -> String {
+    form.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,133 @@
+use crate::utils::escape_html;
+use comrak::adapters::SyntaxHighlighterAdapter;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::sync::LazyLock;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{ClassStyle, ClassedHTMLGenerator, css_for_theme_with_class_style};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+/// Token classes carry a prefix so they can't collide with the app's own classes
+pub(crate) const CLASS_PREFIX: &str = "hl-";
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed {
+    prefix: CLASS_PREFIX,
+};
+/// Class on every highlighted `<pre>`
+pub(crate) const PRE_CLASS: &str = "syntax-highlighting";
+const DARK_THEME: &str = "base16-ocean.dark";
+const LIGHT_THEME: &str = "InspiredGitHub";
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+/// Colours for the token classes, following the app's light or dark theme
+static STYLESHEET: LazyLock<String> = LazyLock::new(|| {
+    let themes = ThemeSet::load_defaults();
+    [
+        (DARK_THEME, ":root:not([data-theme='light'])"),
+        (LIGHT_THEME, "[data-theme='light']"),
+    ]
+    .iter()
+    .filter_map(|(name, scope)| {
+        let css = css_for_theme_with_class_style(themes.themes.get(*name)?, CLASS_STYLE)
+            .map_err(|e| log::warn!("[Highlight] No stylesheet for {}: {}", name, e))
+            .ok()?;
+        Some(scoped(&css, scope))
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+});
+
+/// Prefixes every selector in `css` with `scope`
+fn scoped(css: &str, scope: &str) -> String {
+    css.lines()
+        .map(|line| match line.strip_suffix('{') {
+            Some(selectors) if !line.trim_start().starts_with("/*") => format!(
+                "{} {{",
+                selectors
+                    .split(',')
+                    .map(|selector| format!("{} {}", scope, selector.trim()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The stylesheet for highlighted code, served to the preview by `preview_assets`
+pub fn stylesheet() -> &'static str {
+    &STYLESHEET
+}
+
+/// Highlights fenced code with classes rather than inline colours, so the preview's
+/// stylesheet decides the colours and the sanitizer has no styles to strip. The `<pre>`
+/// keeps its attributes, `data-sourcepos` included, so scroll sync still finds it.
+pub struct Highlighter;
+
+pub static HIGHLIGHTER: Highlighter = Highlighter;
+
+impl SyntaxHighlighterAdapter for Highlighter {
+    fn write_highlighted(
+        &self,
+        output: &mut dyn Write,
+        lang: Option<&str>,
+        code: &str,
+    ) -> fmt::Result {
+        let syntax = lang
+            .filter(|lang| !lang.is_empty())
+            .and_then(|lang| SYNTAXES.find_syntax_by_token(lang));
+        // Unknown languages, diagrams included, are written as plain escaped text
+        let Some(syntax) = syntax else {
+            return output.write_str(&escape_html(code));
+        };
+        let mut generator =
+            ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAXES, CLASS_STYLE);
+        for line in LinesWithEndings::from(code) {
+            if generator
+                .parse_html_for_line_which_includes_newline(line)
+                .is_err()
+            {
+                return output.write_str(&escape_html(code));
+            }
+        }
+        output.write_str(&generator.finalize())
+    }
+
+    fn write_pre_tag<'s>(
+        &self,
+        output: &mut dyn Write,
+        mut attributes: HashMap<&'static str, Cow<'s, str>>,
+    ) -> fmt::Result {
+        let class = match attributes.get("class") {
+            Some(existing) => format!("{} {}", existing, PRE_CLASS),
+            None => PRE_CLASS.to_string(),
+        };
+        attributes.insert("class", class.into());
+        write_tag(output, "pre", &attributes)
+    }
+
+    fn write_code_tag<'s>(
+        &self,
+        output: &mut dyn Write,
+        attributes: HashMap<&'static str, Cow<'s, str>>,
+    ) -> fmt::Result {
+        write_tag(output, "code", &attributes)
+    }
+}
+
+fn write_tag(
+    output: &mut dyn Write,
+    tag: &str,
+    attributes: &HashMap<&'static str, Cow<'_, str>>,
+) -> fmt::Result {
+    comrak::html::write_opening_tag(
+        output,
+        tag,
+        attributes
+            .iter()
+            .map(|(name, value)| (*name, value.as_ref())),
+    )
+}
@@ -0,0 +1,522 @@
+use crate::markdown::clipboard::clean_word_paste;
+use regex::Regex;
+use scraper::{ElementRef, Html, Node};
+use std::sync::LazyLock;
+
+static BLANK_LINES_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\n{3,}").expect("Invalid BLANK_LINES_RE"));
+
+/// Text at the start of a line that would otherwise become a heading, quote, list item
+/// or rule
+static BLOCK_START_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(#{1,6}\s|>|[-+*]\s|\d+[.)]\s)").expect("Invalid BLOCK_START_RE")
+});
+
+const SKIPPED_TAGS: [&str; 7] = [
+    "head", "script", "style", "title", "meta", "link", "template",
+];
+
+/// Converts HTML from the clipboard (browsers, Word, Google Docs) to markdown: headings,
+/// paragraphs, emphasis, links, images, code, quotes, nested and task lists, and tables.
+/// Office markup is cleaned first. Elements with no markdown form keep their text.
+pub fn convert(html: &str) -> String {
+    let cleaned = clean_word_paste(html);
+    let fragment = Html::parse_fragment(&cleaned);
+    let markdown = blocks(fragment.root_element()).join("\n\n");
+    BLANK_LINES_RE
+        .replace_all(markdown.trim(), "\n\n")
+        .into_owned()
+}
+
+fn is_block(tag: &str) -> bool {
+    matches!(
+        tag,
+        "p" | "div"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "ul"
+            | "ol"
+            | "li"
+            | "blockquote"
+            | "pre"
+            | "table"
+            | "hr"
+            | "section"
+            | "article"
+            | "main"
+            | "header"
+            | "footer"
+            | "nav"
+            | "aside"
+            | "figure"
+            | "figcaption"
+            | "details"
+            | "summary"
+            | "dl"
+            | "dt"
+            | "dd"
+            | "address"
+            | "body"
+            | "html"
+    )
+}
+
+/// The markdown blocks for an element's children. Runs of inline content between block
+/// children become paragraphs.
+fn blocks(element: ElementRef) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut paragraph = String::new();
+
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => paragraph.push_str(&escape_text(&collapse_whitespace(text))),
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                let tag = child.value().name();
+                if SKIPPED_TAGS.contains(&tag) {
+                    continue;
+                }
+                if is_block(tag) {
+                    flush_paragraph(&mut paragraph, &mut out);
+                    out.extend(block(child));
+                } else {
+                    paragraph.push_str(&inline(child));
+                }
+            },
+            _ => {},
+        }
+    }
+    flush_paragraph(&mut paragraph, &mut out);
+    out
+}
+
+fn flush_paragraph(paragraph: &mut String, out: &mut Vec<String>) {
+    let text = paragraph
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n");
+    // Hard breaks keep their two trailing spaces
+    let text = text.replace('\n', "  \n");
+    let text = text.trim();
+    if !text.is_empty() {
+        out.push(escape_block_start(text));
+    }
+    paragraph.clear();
+}
+
+fn escape_block_start(text: &str) -> String {
+    match BLOCK_START_RE.find(text) {
+        // Only the punctuation after a list number can be escaped
+        Some(m) if m.as_str().starts_with(|c: char| c.is_ascii_digit()) => {
+            let digits = m.as_str().trim_end().len() - 1;
+            format!("{}\\{}", &text[..digits], &text[digits..])
+        },
+        Some(_) => format!("\\{}", text),
+        None => text.to_string(),
+    }
+}
+
+fn block(element: ElementRef) -> Vec<String> {
+    let tag = element.value().name();
+    match tag {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = tag[1..].parse::<usize>().unwrap_or(1);
+            let text = inline_children(element).replace("  \n", " ");
+            let text = text.trim();
+            if text.is_empty() {
+                Vec::new()
+            } else {
+                vec![format!("{} {}", "#".repeat(level), text)]
+            }
+        },
+        "ul" | "ol" => {
+            let start = element
+                .value()
+                .attr("start")
+                .and_then(|s| s.trim().parse::<usize>().ok())
+                .unwrap_or(1);
+            let list = list(element, tag == "ol", start);
+            if list.is_empty() {
+                Vec::new()
+            } else {
+                vec![list]
+            }
+        },
+        "blockquote" => {
+            let inner = blocks(element).join("\n\n");
+            if inner.is_empty() {
+                return Vec::new();
+            }
+            vec![
+                inner
+                    .lines()
+                    .map(|line| {
+                        if line.is_empty() {
+                            ">".to_string()
+                        } else {
+                            format!("> {}", line)
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ]
+        },
+        "pre" => vec![code_block(element)],
+        "table" => table(element).into_iter().collect(),
+        "hr" => vec!["---".to_string()],
+        "dt" => {
+            let text = inline_children(element);
+            let text = text.trim();
+            if text.is_empty() {
+                Vec::new()
+            } else {
+                vec![format!("**{}**", text)]
+            }
+        },
+        _ => blocks(element),
+    }
+}
+
+/// A list with continuation lines indented under their marker. Items holding more than
+/// one paragraph make the list loose.
+fn list(element: ElementRef, ordered: bool, start: usize) -> String {
+    let list_items: Vec<ElementRef> = element
+        .child_elements()
+        .filter(|e| e.value().name() == "li")
+        .collect();
+    let loose = list_items.iter().any(|item| {
+        item.child_elements()
+            .filter(|e| e.value().name() == "p")
+            .count()
+            > 1
+    });
+    let mut items = Vec::new();
+    let mut number = start;
+
+    for item in list_items {
+        let marker = if ordered {
+            format!("{}.", number)
+        } else {
+            "-".to_string()
+        };
+        number += 1;
+
+        let task = item
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .find(|e| {
+                e.value().name() == "input"
+                    && e.value().attr("type").is_some_and(|t| t.eq_ignore_ascii_case("checkbox"))
+                    // A checkbox in a nested list belongs to that list's item
+                    && !e
+                        .ancestors()
+                        .filter_map(ElementRef::wrap)
+                        .take_while(|a| a.id() != item.id())
+                        .any(|a| matches!(a.value().name(), "ul" | "ol"))
+            })
+            .map(|input| {
+                if input.value().attr("checked").is_some() {
+                    "[x] "
+                } else {
+                    "[ ] "
+                }
+            });
+
+        let parts = blocks(item);
+        let body = parts.join(if loose { "\n\n" } else { "\n" });
+        let indent = " ".repeat(marker.len() + 1);
+        let mut lines = body.lines();
+        let first = lines.next().unwrap_or("");
+        let mut text = format!("{} {}{}", marker, task.unwrap_or(""), first);
+        for line in lines {
+            text.push('\n');
+            if !line.is_empty() {
+                text.push_str(&indent);
+                text.push_str(line);
+            }
+        }
+        items.push(text);
+    }
+
+    items.join(if loose { "\n\n" } else { "\n" })
+}
+
+/// A fenced code block, with the language from a `language-*` class on the `pre` or its
+/// `code` child
+fn code_block(element: ElementRef) -> String {
+    let code: String = element.text().collect();
+    let code = code.trim_end_matches('\n');
+    let language = std::iter::once(element)
+        .chain(
+            element
+                .child_elements()
+                .filter(|e| e.value().name() == "code"),
+        )
+        .flat_map(|e| e.value().classes())
+        .find_map(|class| {
+            class
+                .strip_prefix("language-")
+                .or_else(|| class.strip_prefix("lang-"))
+        })
+        .unwrap_or("");
+    let fence = "`".repeat(longest_run(code, '`').max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, language, code, fence)
+}
+
+/// A GFM table. The first row is the header; cells spanning columns are padded so every
+/// row has the same width.
+fn table(element: ElementRef) -> Option<String> {
+    let rows: Vec<ElementRef> = element
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|e| e.value().name() == "tr")
+        .collect();
+
+    let mut cells: Vec<Vec<String>> = Vec::new();
+    let mut alignments: Vec<&str> = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        let mut row_cells = Vec::new();
+        for cell in row
+            .child_elements()
+            .filter(|e| matches!(e.value().name(), "td" | "th"))
+        {
+            let text = blocks(cell)
+                .join("<br>")
+                .replace("  \n", "<br>")
+                .replace('\n', " ")
+                .replace('|', "\\|");
+            let span = cell
+                .value()
+                .attr("colspan")
+                .and_then(|s| s.trim().parse::<usize>().ok())
+                .unwrap_or(1)
+                .max(1);
+            if index == 0 {
+                let align = cell_alignment(cell);
+                alignments.extend(std::iter::repeat_n(align, span));
+            }
+            row_cells.push(text);
+            row_cells.extend(std::iter::repeat_n(String::new(), span - 1));
+        }
+        cells.push(row_cells);
+    }
+
+    let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return None;
+    }
+    alignments.resize(columns, "---");
+
+    let format_row = |row: &[String]| {
+        let padded: Vec<&str> = (0..columns)
+            .map(|i| row.get(i).map_or("", String::as_str))
+            .collect();
+        format!("| {} |", padded.join(" | "))
+    };
+    let mut lines = vec![
+        format_row(&cells[0]),
+        format!("| {} |", alignments.join(" | ")),
+    ];
+    lines.extend(cells[1..].iter().map(|row| format_row(row)));
+    Some(lines.join("\n"))
+}
+
+fn cell_alignment(cell: ElementRef) -> &'static str {
+    let style = cell
+        .value()
+        .attr("style")
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let align = cell
+        .value()
+        .attr("align")
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    if align == "center"
+        || style.contains("text-align: center")
+        || style.contains("text-align:center")
+    {
+        ":---:"
+    } else if align == "right"
+        || style.contains("text-align: right")
+        || style.contains("text-align:right")
+    {
+        "---:"
+    } else {
+        "---"
+    }
+}
+
+fn inline_children(element: ElementRef) -> String {
+    let mut out = String::new();
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(&escape_text(&collapse_whitespace(text))),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    if is_block(child.value().name()) {
+                        // Block content inside inline context, such as a div in a link
+                        out.push(' ');
+                        out.push_str(&blocks(child).join(" "));
+                        out.push(' ');
+                    } else {
+                        out.push_str(&inline(child));
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+    out
+}
+
+fn inline(element: ElementRef) -> String {
+    let value = element.value();
+    let tag = value.name();
+    if SKIPPED_TAGS.contains(&tag) {
+        return String::new();
+    }
+    let style = value
+        .attr("style")
+        .unwrap_or("")
+        .to_ascii_lowercase()
+        .replace(' ', "");
+
+    match tag {
+        "br" => "\n".to_string(),
+        "img" => {
+            let src = value.attr("src").unwrap_or("");
+            if src.is_empty() {
+                return String::new();
+            }
+            let alt = escape_text(value.attr("alt").unwrap_or(""));
+            format!("![{}]({})", alt, destination(src, value.attr("title")))
+        },
+        "a" => {
+            let text = inline_children(element);
+            let text = text.trim();
+            match value.attr("href").filter(|h| !h.trim().is_empty()) {
+                Some(href) if !text.is_empty() => {
+                    format!("[{}]({})", text, destination(href, value.attr("title")))
+                },
+                _ => text.to_string(),
+            }
+        },
+        "code" | "kbd" | "samp" | "tt" => code_span(&element.text().collect::<String>()),
+        // Google Docs wraps the whole paste in a <b> that isn't bold
+        "b" if style.contains("font-weight:normal") => inline_children(element),
+        "strong" | "b" => emphasis(element, "**"),
+        "em" | "i" | "cite" | "dfn" => emphasis(element, "*"),
+        "del" | "s" | "strike" => emphasis(element, "~~"),
+        "sup" | "sub" => format!("<{}>{}</{}>", tag, inline_children(element), tag),
+        "input" => String::new(),
+        _ if style.contains("font-weight:700") || style.contains("font-weight:bold") => {
+            emphasis(element, "**")
+        },
+        _ if style.contains("font-style:italic") => emphasis(element, "*"),
+        _ => inline_children(element),
+    }
+}
+
+/// Wraps content in emphasis markers, keeping surrounding spaces outside them where
+/// markdown requires it
+fn emphasis(element: ElementRef, marker: &str) -> String {
+    let text = inline_children(element);
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text;
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+    format!("{}{}{}{}{}", leading, marker, trimmed, marker, trailing)
+}
+
+fn code_span(code: &str) -> String {
+    let code = collapse_whitespace(code);
+    if code.trim().is_empty() {
+        return code;
+    }
+    let fence = "`".repeat(longest_run(&code, '`') + 1);
+    // A space keeps a backtick at either end from joining the fence
+    let pad = if code.starts_with('`') || code.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{}{}{}{}{}", fence, pad, code, pad, fence)
+}
+
+pub(crate) fn destination(url: &str, title: Option<&str>) -> String {
+    let url = url.trim();
+    let url = if url.contains([' ', '(', ')']) {
+        format!("<{}>", url)
+    } else {
+        url.to_string()
+    };
+    match title.map(str::trim).filter(|t| !t.is_empty()) {
+        Some(title) => format!("{} \"{}\"", url, title.replace('"', "\\\"")),
+        None => url,
+    }
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    let (mut longest, mut current) = (0, 0);
+    for ch in text.chars() {
+        if ch == c {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+/// HTML whitespace rules: any run of whitespace is one space
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+    out
+}
+
+/// Escapes characters that would start markdown syntax inside text. Underscores inside
+/// words are left alone, as they can't start emphasis there.
+fn escape_text(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let escape = match c {
+            '\\' | '*' | '`' | '[' | ']' | '<' => true,
+            '_' => {
+                let before = i.checked_sub(1).and_then(|j| chars.get(j));
+                let after = chars.get(i + 1);
+                !(before.is_some_and(|b| b.is_alphanumeric())
+                    && after.is_some_and(|a| a.is_alphanumeric()))
+            },
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,506 @@
+use crate::markdown::document_css;
+use crate::markdown::front_matter;
+use crate::markdown::lint::{fence_closes, fence_open};
+use crate::markdown::renderer::{self, MarkdownOptions};
+use crate::markdown::sections;
+use crate::markdown::security::SanitizationReport;
+use crate::markdown::text_metrics::{
+    self, Readability, WordCountMode, build_line_map_and_metrics, utf16_to_byte_offset,
+};
+use crate::preview_assets;
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{LazyLock, Mutex};
+
+/// Below this size a full render is fast enough that block bookkeeping isn't worth it
+pub const INCREMENTAL_MIN_BYTES: usize = 128 * 1024;
+/// A blank line only starts a new block once the current one is this long
+const MIN_BLOCK_LINES: usize = 40;
+/// Documents whose block state is kept between edits
+const DOCUMENT_CACHE_CAPACITY: usize = 4;
+
+// Link reference and footnote definitions apply across blocks, so documents using them
+// are always rendered whole
+static REFERENCE_DEF_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^ {0,3}\[[^\]\n]+\]:").expect("Invalid REFERENCE_DEF_RE"));
+
+static SOURCEPOS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"data-sourcepos="(\d+):(\d+)-(\d+):(\d+)""#).expect("Invalid SOURCEPOS_RE")
+});
+
+static DOCUMENT_CACHE: LazyLock<Mutex<VecDeque<DocumentBlocks>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(DOCUMENT_CACHE_CAPACITY)));
+
+/// The changed span of the new content, in UTF-16 offsets as the editor reports them
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct EditRange {
+    pub from: usize,
+    pub to: usize,
+}
+
+struct Block {
+    /// 0-based line range `[start, end)` in the current content
+    start: usize,
+    end: usize,
+    hash: u64,
+    html: String,
+    /// `start` when `html` was rendered; `data-sourcepos` lines are absolute as of then
+    rendered_at: usize,
+    heading_texts: Vec<String>,
+    sanitization: SanitizationReport,
+    local_images: Vec<String>,
+}
+
+impl Block {
+    fn current_html(&self) -> String {
+        shift_sourcepos(&self.html, self.start as isize - self.rendered_at as isize)
+    }
+}
+
+struct DocumentBlocks {
+    content_hash: u64,
+    options_key: u64,
+    line_count: usize,
+    /// Lines of front matter, which never contain a block boundary
+    front_matter_lines: usize,
+    blocks: Vec<Block>,
+    /// The last response was a whole-document render, so the frontend has no blocks to patch
+    sent_whole: bool,
+}
+
+/// Replaces `delete_count` blocks from `start` with `fragments`. Blocks after the patch
+/// keep their HTML, but their `data-sourcepos` lines move by `line_shift`.
+#[derive(Debug, Serialize)]
+pub struct BlockPatch {
+    pub start: usize,
+    pub delete_count: usize,
+    pub fragments: Vec<String>,
+    pub line_shift: isize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IncrementalRenderResult {
+    /// Pass back as `previous_hash` with the next edit
+    pub content_hash: String,
+    /// Every block's HTML, sent when there was no usable previous render
+    pub blocks: Option<Vec<String>>,
+    pub patch: Option<BlockPatch>,
+    pub line_count: usize,
+    pub word_count: usize,
+    pub char_count: usize,
+    pub readability: Readability,
+    pub sanitization: SanitizationReport,
+    /// Heading `id`s in document order, prefix included
+    pub heading_ids: Vec<String>,
+    /// Local files the HTML loads through the `preview` protocol
+    pub local_images: Vec<String>,
+    /// Sanitized `css:` front matter, scoped to `.document-css`
+    pub document_css: Option<String>,
+    /// Stylesheet for highlighted code, for previews
+    pub highlight_css: Option<String>,
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn lines_hash(lines: &[&str]) -> u64 {
+    hash_of(lines)
+}
+
+fn shift_sourcepos(html: &str, delta: isize) -> String {
+    if delta == 0 {
+        return html.to_string();
+    }
+    let shift = |line: &str| (line.parse::<isize>().unwrap_or(0) + delta).max(1);
+    SOURCEPOS_RE
+        .replace_all(html, |caps: &regex::Captures| {
+            format!(
+                r#"data-sourcepos="{}:{}-{}:{}""#,
+                shift(&caps[1]),
+                &caps[2],
+                shift(&caps[3]),
+                &caps[4]
+            )
+        })
+        .into_owned()
+}
+
+fn is_list_marker(line: &str) -> bool {
+    let bullet = line
+        .strip_prefix(['-', '*', '+'])
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']));
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let ordered = (1..=9).contains(&digits)
+        && line[digits..]
+            .strip_prefix(['.', ')'])
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']));
+    bullet || ordered
+}
+
+fn is_atx_heading(line: &str) -> bool {
+    let hashes = line.chars().take_while(|&c| c == '#').count();
+    (1..=6).contains(&hashes)
+        && line[hashes..]
+            .chars()
+            .next()
+            .is_none_or(|c| c == ' ' || c == '\t')
+}
+
+/// Closing marker for raw HTML blocks that may contain blank lines
+fn raw_html_terminator(line: &str) -> Option<&'static str> {
+    let lower = line.trim_start().to_ascii_lowercase();
+    [
+        ("<script", "</script>"),
+        ("<pre", "</pre>"),
+        ("<style", "</style>"),
+        ("<textarea", "</textarea>"),
+        ("<!--", "-->"),
+    ]
+    .into_iter()
+    .find(|(open, _)| lower.starts_with(open))
+    .map(|(_, close)| close)
+}
+
+/// Splits `lines[from..]` into top-level block ranges `[start, end)`. A block ends before
+/// an unindented ATX heading, or before an unindented line following a blank line once the
+/// block has `MIN_BLOCK_LINES` lines; never inside front matter, fenced code or raw HTML,
+/// and never between list items. `stop` sees each boundary and ends the scan early when it
+/// returns true, leaving the last range ending at that boundary. State resets at every
+/// boundary, so scanning from any boundary gives the same split as scanning from the top.
+fn split_blocks(
+    lines: &[&str],
+    from: usize,
+    front_matter_lines: usize,
+    mut stop: impl FnMut(usize) -> bool,
+) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut start = from;
+    let mut fence: Option<(char, usize)> = None;
+    let mut raw_html: Option<&'static str> = None;
+    // The current run of non-blank lines opened with a tag: an HTML block that only a
+    // blank line ends
+    let mut html_run = false;
+
+    for i in from..lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+
+        if i > start
+            && i >= front_matter_lines
+            && fence.is_none()
+            && raw_html.is_none()
+            && !line.starts_with([' ', '\t'])
+        {
+            let prev_blank = lines[i - 1].trim().is_empty();
+            let boundary = if is_atx_heading(line) {
+                prev_blank || !html_run
+            } else {
+                prev_blank
+                    && !trimmed.is_empty()
+                    && !is_list_marker(line)
+                    && i - start >= MIN_BLOCK_LINES
+            };
+            if boundary {
+                ranges.push((start, i));
+                start = i;
+                if stop(i) {
+                    return ranges;
+                }
+            }
+        }
+
+        if trimmed.is_empty() {
+            html_run = false;
+        } else if i == start || lines[i - 1].trim().is_empty() {
+            html_run = trimmed.starts_with('<');
+        }
+
+        if let Some(open) = fence {
+            if fence_closes(line, open) {
+                fence = None;
+            }
+        } else if let Some(close) = raw_html {
+            if line.to_ascii_lowercase().contains(close) {
+                raw_html = None;
+            }
+        } else if let Some(open) = fence_open(line) {
+            fence = Some(open);
+        } else if let Some(close) = raw_html_terminator(line)
+            && !line.to_ascii_lowercase().contains(close)
+        {
+            raw_html = Some(close);
+        }
+    }
+
+    if start < lines.len() {
+        ranges.push((start, lines.len()));
+    }
+    ranges
+}
+
+fn render_block(
+    lines: &[&str],
+    (start, end): (usize, usize),
+    options: &MarkdownOptions,
+) -> Result<Block> {
+    let text = lines[start..end].join("\n");
+    // Front matter can only open the document
+    let block_options = MarkdownOptions {
+        hide_front_matter: options.hide_front_matter && start == 0,
+        ..options.clone()
+    };
+    let fragment = renderer::render_fragment(&text, &block_options)?;
+    Ok(Block {
+        start,
+        end,
+        hash: lines_hash(&lines[start..end]),
+        html: shift_sourcepos(&fragment.html, start as isize),
+        rendered_at: start,
+        heading_texts: fragment.heading_texts,
+        sanitization: fragment.sanitization,
+        local_images: fragment.local_images,
+    })
+}
+
+fn line_of(content: &str, utf16_offset: usize) -> usize {
+    let byte = utf16_to_byte_offset(content, utf16_offset);
+    content.as_bytes()[..byte]
+        .iter()
+        .filter(|&&b| b == b'\n')
+        .count()
+}
+
+/// Re-renders only the blocks touched by `edit`, updating `doc` in place. Returns `None`
+/// when the cached blocks don't match the new content, so the caller starts over.
+fn patch_blocks(
+    doc: &mut DocumentBlocks,
+    content: &str,
+    lines: &[&str],
+    edit: EditRange,
+    options: &MarkdownOptions,
+) -> Result<Option<BlockPatch>> {
+    let from_line = line_of(content, edit.from.min(edit.to));
+    let to_line = line_of(content, edit.to.max(edit.from));
+    let delta = lines.len() as isize - doc.line_count as isize;
+
+    // Blocks ending before the edited line can't have changed, nor can the boundary
+    // after them, which depends only on the lines either side of it
+    let keep = doc.blocks.iter().take_while(|b| b.end < from_line).count();
+    if doc.blocks[..keep]
+        .iter()
+        .any(|b| lines_hash(&lines[b.start..b.end]) != b.hash)
+    {
+        return Ok(None);
+    }
+    let resume = keep.checked_sub(1).map_or(0, |i| doc.blocks[i].end);
+
+    // Past the edit, stop at the first boundary the previous split also had; from there
+    // both splits see the same lines from the same state
+    let mut rejoin = doc.blocks.len();
+    let ranges = split_blocks(lines, resume, doc.front_matter_lines, |boundary| {
+        if boundary <= to_line + 1 {
+            return false;
+        }
+        let old_start = boundary as isize - delta;
+        match doc.blocks[keep..].binary_search_by_key(&old_start, |b| b.start as isize) {
+            Ok(i) => {
+                rejoin = keep + i;
+                true
+            },
+            Err(_) => false,
+        }
+    });
+
+    let mut tail: Vec<Block> = doc.blocks.drain(rejoin..).collect();
+    for block in &mut tail {
+        block.start = (block.start as isize + delta) as usize;
+        block.end = (block.end as isize + delta) as usize;
+        if block.end > lines.len() || lines_hash(&lines[block.start..block.end]) != block.hash {
+            return Ok(None);
+        }
+    }
+
+    // Replaced blocks can still be reused when an edit only moved them
+    let mut replaced: HashMap<u64, Block> = doc
+        .blocks
+        .drain(keep..)
+        .map(|block| (block.hash, block))
+        .collect();
+    let mut middle = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        let hash = lines_hash(&lines[start..end]);
+        let block = match replaced.remove(&hash) {
+            // The first block is rendered with front matter handling, the others without
+            Some(mut block)
+                if !options.hide_front_matter || (block.rendered_at == 0) == (start == 0) =>
+            {
+                block.start = start;
+                block.end = end;
+                block
+            },
+            _ => render_block(lines, (start, end), options)?,
+        };
+        middle.push(block);
+    }
+
+    let patch = BlockPatch {
+        start: keep,
+        delete_count: rejoin - keep,
+        fragments: middle.iter().map(Block::current_html).collect(),
+        line_shift: delta,
+    };
+    doc.blocks.extend(middle);
+    doc.blocks.extend(tail);
+    doc.line_count = lines.len();
+    Ok(Some(patch))
+}
+
+fn take_cached(content_hash: u64, options_key: u64) -> Option<DocumentBlocks> {
+    let mut cache = DOCUMENT_CACHE.lock().ok()?;
+    let index = cache
+        .iter()
+        .position(|d| d.content_hash == content_hash && d.options_key == options_key)?;
+    cache.remove(index)
+}
+
+fn store_cached(doc: DocumentBlocks) {
+    if let Ok(mut cache) = DOCUMENT_CACHE.lock() {
+        if cache.len() >= DOCUMENT_CACHE_CAPACITY {
+            cache.pop_front();
+        }
+        cache.push_back(doc);
+    }
+}
+
+/// Heading ids are deduplicated per block when rendered; returns `None` if a repeat
+/// across blocks means those ids differ from a whole-document render.
+fn document_heading_ids(blocks: &[Block], prefix: &str) -> Option<Vec<String>> {
+    let texts: Vec<String> = blocks
+        .iter()
+        .flat_map(|b| b.heading_texts.iter().cloned())
+        .collect();
+    let ids = renderer::heading_ids(&texts, prefix);
+    let per_block = blocks
+        .iter()
+        .flat_map(|b| sections::unique_slugs(b.heading_texts.iter().map(String::as_str)));
+    ids.iter()
+        .zip(per_block)
+        .all(|(id, slug)| id.strip_prefix(prefix) == Some(slug.as_str()))
+        .then_some(ids)
+}
+
+/// Renders large documents as independent top-level blocks. With the hash of the
+/// previously rendered content and the edit since, only the blocks the edit touched are
+/// rendered and returned as a patch; otherwise every block is returned.
+pub fn render_incremental(
+    content: &str,
+    options: MarkdownOptions,
+    previous_hash: Option<&str>,
+    edit: Option<EditRange>,
+) -> Result<IncrementalRenderResult> {
+    let content_hash = hash_of(content);
+    let options_key = hash_of(&options);
+    let (_, line_count, mut word_count, char_count, _) = build_line_map_and_metrics(content);
+    if options.word_count_mode != WordCountMode::All {
+        word_count = text_metrics::count_words(content, options.word_count_mode, options.flavor);
+    }
+
+    let whole = |options: MarkdownOptions| -> Result<IncrementalRenderResult> {
+        let result = renderer::render_markdown(content, options)?;
+        Ok(IncrementalRenderResult {
+            content_hash: format!("{:016x}", content_hash),
+            blocks: Some(vec![result.html]),
+            patch: None,
+            line_count,
+            word_count,
+            char_count,
+            readability: result.readability,
+            sanitization: result.sanitization,
+            heading_ids: result.heading_ids,
+            local_images: result.local_images,
+            document_css: result.document_css,
+            highlight_css: result.highlight_css,
+        })
+    };
+
+    if content.len() < INCREMENTAL_MIN_BYTES || REFERENCE_DEF_RE.is_match(content) {
+        return whole(options);
+    }
+
+    let lines: Vec<&str> = content.split('\n').collect();
+    let front_matter_lines = front_matter::line_count(content);
+    let previous = previous_hash
+        .and_then(|h| u64::from_str_radix(h, 16).ok())
+        .and_then(|h| take_cached(h, options_key));
+
+    let mut patch = None;
+    let mut patched = None;
+    // A front matter block that grew or shrank may now cover earlier boundaries
+    if let (Some(mut doc), Some(edit)) = (previous, edit)
+        && doc.front_matter_lines == front_matter_lines
+    {
+        patch = patch_blocks(&mut doc, content, &lines, edit, &options)?;
+        if patch.is_some() {
+            if doc.sent_whole {
+                patch = None;
+            }
+            patched = Some(doc);
+        }
+    }
+    let mut doc = match patched {
+        Some(doc) => doc,
+        None => DocumentBlocks {
+            content_hash,
+            options_key,
+            line_count: lines.len(),
+            front_matter_lines,
+            blocks: split_blocks(&lines, 0, front_matter_lines, |_| false)
+                .into_iter()
+                .map(|range| render_block(&lines, range, &options))
+                .collect::<Result<Vec<_>>>()?,
+            sent_whole: false,
+        },
+    };
+    doc.content_hash = content_hash;
+
+    // Block state is kept either way, so the next edit only costs a patch plus this check
+    let Some(heading_ids) = document_heading_ids(&doc.blocks, &options.heading_id_prefix) else {
+        doc.sent_whole = true;
+        store_cached(doc);
+        return whole(options);
+    };
+    doc.sent_whole = false;
+
+    let mut sanitization = SanitizationReport::default();
+    let mut local_images = Vec::new();
+    for block in &doc.blocks {
+        sanitization.merge(&block.sanitization);
+        local_images.extend(block.local_images.iter().cloned());
+    }
+    let blocks = patch
+        .is_none()
+        .then(|| doc.blocks.iter().map(Block::current_html).collect());
+    store_cached(doc);
+    let readability = text_metrics::readability(content, options.flavor, word_count);
+
+    Ok(IncrementalRenderResult {
+        content_hash: format!("{:016x}", content_hash),
+        blocks,
+        patch,
+        line_count,
+        word_count,
+        char_count,
+        readability,
+        sanitization,
+        heading_ids,
+        local_images,
+        document_css: document_css::from_front_matter(content),
+        highlight_css: options.preview.then(preview_assets::highlight_css_url),
+    })
+}
@@ -0,0 +1,152 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::formatter::escape_link_text;
+use crate::markdown::front_matter;
+use crate::markdown::sections;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// The generated list sits between these, so text around it in the index page is kept
+const INDEX_START: &str = "<!-- index -->";
+const INDEX_END: &str = "<!-- /index -->";
+const UNCATEGORIZED: &str = "Uncategorized";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexGrouping {
+    /// A section per subfolder, files in the root folder first
+    #[default]
+    Folder,
+    /// A section per value of the front matter category field
+    Category,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IndexOptions {
+    /// Name of the index page in the root folder
+    pub file_name: String,
+    pub group_by: IndexGrouping,
+    /// Front matter field read for `IndexGrouping::Category`; a list uses its first value
+    pub category_field: String,
+    /// Heading of a new index page; by default the root folder's name
+    pub title: Option<String>,
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        Self {
+            file_name: "index.md".to_string(),
+            group_by: IndexGrouping::Folder,
+            category_field: "category".to_string(),
+            title: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexEntry {
+    /// Relative to the root folder, with forward slashes
+    pub path: String,
+    pub title: String,
+    pub group: String,
+}
+
+/// Index entry for the file at `relative_path`. The title is the first heading, then the
+/// front matter `title`, then the file name.
+pub fn entry(
+    relative_path: &str,
+    content: &str,
+    options: &IndexOptions,
+    flavor: MarkdownFlavor,
+) -> IndexEntry {
+    let front_matter = front_matter::parse(content);
+    let title = sections::headings(content, flavor)
+        .into_iter()
+        .map(|h| h.text.trim().to_string())
+        .find(|t| !t.is_empty())
+        .or_else(|| {
+            front_matter
+                .as_ref()
+                .and_then(|fm| front_matter::string_field(fm, "title"))
+        })
+        .unwrap_or_else(|| file_stem(relative_path).to_string());
+
+    let group = match options.group_by {
+        IndexGrouping::Folder => relative_path
+            .rsplit_once('/')
+            .map_or(String::new(), |(folder, _)| folder.to_string()),
+        IndexGrouping::Category => front_matter
+            .as_ref()
+            .and_then(|fm| category(fm, &options.category_field))
+            .unwrap_or_else(|| UNCATEGORIZED.to_string()),
+    };
+
+    IndexEntry {
+        path: relative_path.to_string(),
+        title,
+        group,
+    }
+}
+
+fn category(front_matter: &Value, field: &str) -> Option<String> {
+    match front_matter.get(field)? {
+        Value::Array(values) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::trim)
+            .find(|v| !v.is_empty())
+            .map(str::to_string),
+        _ => front_matter::string_field(front_matter, field),
+    }
+}
+
+fn file_stem(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
+
+/// The generated list, markers included: ungrouped entries first, then a `##` section per
+/// group in name order, with uncategorized files last. Entries are sorted by title.
+pub fn build(entries: &[IndexEntry]) -> String {
+    let mut groups: BTreeMap<(bool, String), Vec<&IndexEntry>> = BTreeMap::new();
+    for entry in entries {
+        let key = (entry.group == UNCATEGORIZED, entry.group.clone());
+        groups.entry(key).or_default().push(entry);
+    }
+
+    let mut out = String::from(INDEX_START);
+    out.push('\n');
+    for ((_, group), mut entries) in groups {
+        entries.sort_by_cached_key(|e| (e.title.to_lowercase(), e.path.clone()));
+        out.push('\n');
+        if !group.is_empty() {
+            out.push_str(&format!("## {}\n\n", group));
+        }
+        for entry in entries {
+            out.push_str(&format!(
+                "- [{}]({})\n",
+                escape_link_text(&entry.title),
+                entry.path.replace(' ', "%20")
+            ));
+        }
+    }
+    out.push('\n');
+    out.push_str(INDEX_END);
+    out
+}
+
+/// The index page with `list` in place of its generated section. A page without markers
+/// gets the list appended; without a page, a new one is started with `title`.
+pub fn update(existing: Option<&str>, list: &str, title: &str) -> String {
+    let Some(existing) = existing.filter(|e| !e.trim().is_empty()) else {
+        return format!("# {}\n\n{}\n", title, list);
+    };
+    if let Some(start) = existing.find(INDEX_START)
+        && let Some(end) = existing[start..].find(INDEX_END)
+    {
+        let end = start + end + INDEX_END.len();
+        return format!("{}{}{}", &existing[..start], list, &existing[end..]);
+    }
+    format!("{}\n\n{}\n", existing.trim_end(), list)
+}
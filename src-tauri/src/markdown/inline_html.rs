@@ -0,0 +1,346 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::front_matter;
+use crate::markdown::html_to_markdown::destination;
+use crate::utils::detect_line_ending;
+use comrak::nodes::NodeValue;
+use comrak::{Arena, parse_document};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+static EMPHASIS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)<(b|strong|i|em)(\s[^>]*)?>([^<]*)</(b|strong|i|em)\s*>")
+        .expect("Invalid EMPHASIS_RE")
+});
+static LINK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<a(\s[^>]*)?>([^<]*)</a\s*>").expect("Invalid LINK_RE"));
+static IMG_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<img(\s[^>]*?)?\s*/?>").expect("Invalid IMG_RE"));
+static BR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<br\s*/?>").expect("Invalid BR_RE"));
+static HR_LINE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^\s{0,3}<hr\s*/?>\s*$").expect("Invalid HR_LINE_RE"));
+static ATTR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"([A-Za-z][\w:-]*)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+)))?"#)
+        .expect("Invalid ATTR_RE")
+});
+/// Opening tags left over after conversion
+static OPEN_TAG_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<([A-Za-z][\w-]*)[^>]*>").expect("Invalid OPEN_TAG_RE"));
+
+const CONVERTED_TAGS: [&str; 8] = ["b", "strong", "i", "em", "a", "img", "br", "hr"];
+
+/// HTML the cleanup could not turn into markdown without losing something
+#[derive(Debug, Clone, Serialize)]
+pub struct LeftHtml {
+    /// 1-based line
+    pub line: usize,
+    pub tag: String,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InlineHtmlCleanup {
+    pub content: String,
+    pub converted: usize,
+    pub left: Vec<LeftHtml>,
+}
+
+/// Rewrites `<b>`, `<strong>`, `<i>`, `<em>`, `<a>`, `<img>`, `<br>` and `<hr>` as markdown
+/// where the result renders the same: no attributes markdown can't carry, no markup inside
+/// emphasis or link text, `<br>` only at the end of a line. Code, front matter and
+/// multi-line HTML blocks, where markdown isn't parsed, are left untouched.
+pub fn convert(content: &str, flavor: MarkdownFlavor) -> InlineHtmlCleanup {
+    let fm_lines = front_matter::line_count(content);
+    let skipped = skipped_lines(content, fm_lines, flavor);
+    let ending = if detect_line_ending(content) == "CRLF" {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let mut out = String::with_capacity(content.len());
+    let mut converted = 0;
+    let mut left = Vec::new();
+    let mut previous_blank = true;
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    for (i, line) in lines.iter().enumerate() {
+        let line_no = i + 1;
+        let body = line.trim_end_matches(['\r', '\n']);
+        let line_ending = &line[body.len()..];
+        if line_no <= fm_lines || skipped.contains_key(&line_no) {
+            if let Some(tag) = skipped.get(&line_no).and_then(Option::as_deref) {
+                left.push(LeftHtml {
+                    line: line_no,
+                    tag: tag.to_string(),
+                    reason: "inside an HTML block",
+                });
+            }
+            out.push_str(line);
+            previous_blank = body.trim().is_empty();
+            continue;
+        }
+
+        if HR_LINE_RE.is_match(body) {
+            // A rule right under paragraph text would turn it into a heading
+            if !previous_blank {
+                out.push_str(ending);
+            }
+            out.push_str("---");
+            out.push_str(line_ending);
+            converted += 1;
+            previous_blank = false;
+            continue;
+        }
+
+        let mut reasons: Vec<(String, &'static str)> = Vec::new();
+        let table_row = body.trim_start().starts_with('|');
+        let mut rewritten = String::with_capacity(body.len());
+        for (is_code, segment) in code_span_segments(body) {
+            if is_code {
+                rewritten.push_str(segment);
+            } else {
+                rewritten.push_str(&convert_segment(segment, &mut converted, &mut reasons));
+            }
+        }
+        // A trailing `<br>` is a hard break, as long as the paragraph goes on after it;
+        // anywhere else it would split the line
+        let next_blank = lines.get(i + 1).is_none_or(|l| l.trim().is_empty());
+        if let Some(m) = BR_RE.find_iter(&rewritten).last()
+            && rewritten[m.end()..].trim().is_empty()
+            && !rewritten[..m.start()].trim().is_empty()
+            && !next_blank
+            && !table_row
+            && !in_code_span(&rewritten, m.start())
+        {
+            rewritten.replace_range(m.start().., "\\");
+            converted += 1;
+        }
+
+        for (is_code, segment) in code_span_segments(&rewritten) {
+            if is_code {
+                continue;
+            }
+            for caps in OPEN_TAG_RE.captures_iter(segment) {
+                let tag = caps[1].to_ascii_lowercase();
+                let reason = match reasons.iter().position(|(t, _)| *t == tag) {
+                    Some(index) => reasons.remove(index).1,
+                    None if tag == "br" => "not a line break inside a paragraph",
+                    None if CONVERTED_TAGS.contains(&tag.as_str()) => {
+                        "spans lines or contains markup"
+                    },
+                    None => "no markdown equivalent",
+                };
+                left.push(LeftHtml {
+                    line: line_no,
+                    tag,
+                    reason,
+                });
+            }
+        }
+
+        out.push_str(&rewritten);
+        out.push_str(line_ending);
+        previous_blank = body.trim().is_empty();
+    }
+
+    InlineHtmlCleanup {
+        content: out,
+        converted,
+        left,
+    }
+}
+
+/// Lines that must not be rewritten: code blocks and HTML blocks longer than a line. HTML
+/// block lines map to the block's first tag so it is reported once.
+pub(crate) fn skipped_lines(
+    content: &str,
+    fm_lines: usize,
+    flavor: MarkdownFlavor,
+) -> HashMap<usize, Option<String>> {
+    // Front matter is blanked so its closing `---` can't make a heading, keeping line numbers
+    let source = if fm_lines > 0 {
+        content
+            .split('\n')
+            .enumerate()
+            .map(|(i, line)| if i < fm_lines { "" } else { line })
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        content.to_string()
+    };
+    let arena = Arena::new();
+    let root = parse_document(&arena, &source, &flavor.to_comrak_options());
+
+    let mut skipped = HashMap::new();
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        let (start, end) = (data.sourcepos.start.line, data.sourcepos.end.line);
+        match &data.value {
+            NodeValue::CodeBlock(_) => {
+                skipped.extend((start..=end).map(|line| (line, None)));
+            },
+            NodeValue::HtmlBlock(block) if end > start => {
+                let tag = OPEN_TAG_RE
+                    .captures(&block.literal)
+                    .map(|caps| caps[1].to_ascii_lowercase());
+                skipped.insert(start, tag);
+                skipped.extend((start + 1..=end).map(|line| (line, None)));
+            },
+            _ => {},
+        }
+    }
+    skipped
+}
+
+fn convert_segment(
+    segment: &str,
+    converted: &mut usize,
+    reasons: &mut Vec<(String, &'static str)>,
+) -> String {
+    let mut keep = |caps: &Captures, tag: &str, reason: &'static str| {
+        reasons.push((tag.to_ascii_lowercase(), reason));
+        caps[0].to_string()
+    };
+
+    let text = EMPHASIS_RE.replace_all(segment, |caps: &Captures| {
+        let tag = caps[1].to_ascii_lowercase();
+        let marker = match (tag.as_str(), caps[4].to_ascii_lowercase().as_str()) {
+            ("b" | "strong", "b" | "strong") => "**",
+            ("i" | "em", "i" | "em") => "*",
+            _ => return keep(caps, &tag, "mismatched closing tag"),
+        };
+        if caps
+            .get(2)
+            .is_some_and(|attrs| !attrs.as_str().trim().is_empty())
+        {
+            return keep(caps, &tag, "has attributes");
+        }
+        let inner = &caps[3];
+        let trimmed = inner.trim();
+        if trimmed.is_empty() || trimmed.contains(['*', '_']) {
+            return keep(caps, &tag, "text would clash with emphasis markers");
+        }
+        *converted += 1;
+        // Emphasis can't start or end with a space, so spaces move outside the markers
+        let leading = &inner[..inner.len() - inner.trim_start().len()];
+        let trailing = &inner[inner.trim_end().len()..];
+        format!("{}{}{}{}{}", leading, marker, trimmed, marker, trailing)
+    });
+
+    let text = LINK_RE.replace_all(&text, |caps: &Captures| {
+        let Some(attrs) = attributes(caps.get(1).map_or("", |m| m.as_str()), &["href", "title"])
+        else {
+            return keep(caps, "a", "has attributes");
+        };
+        let text = caps[2].trim();
+        let Some(href) = attrs.get("href").filter(|h| !h.trim().is_empty()) else {
+            return keep(caps, "a", "no href");
+        };
+        if text.is_empty() || text.contains(['[', ']']) || href.contains(['<', '>']) {
+            return keep(caps, "a", "text or URL can't be written as a link");
+        }
+        *converted += 1;
+        format!(
+            "[{}]({})",
+            text,
+            destination(href, attrs.get("title").copied())
+        )
+    });
+
+    let text = IMG_RE.replace_all(&text, |caps: &Captures| {
+        let Some(attrs) = attributes(
+            caps.get(1).map_or("", |m| m.as_str()),
+            &["src", "alt", "title"],
+        ) else {
+            return keep(caps, "img", "has attributes");
+        };
+        let Some(src) = attrs.get("src").filter(|s| !s.trim().is_empty()) else {
+            return keep(caps, "img", "no src");
+        };
+        let alt = attrs.get("alt").copied().unwrap_or("");
+        if alt.contains(['[', ']']) || src.contains(['<', '>']) {
+            return keep(caps, "img", "alt text or URL can't be written as an image");
+        }
+        *converted += 1;
+        format!(
+            "![{}]({})",
+            alt,
+            destination(src, attrs.get("title").copied())
+        )
+    });
+
+    text.into_owned()
+}
+
+/// The tag's attributes by lowercase name, or `None` if it has one outside `allowed` or
+/// anything that doesn't parse as an attribute
+fn attributes<'a>(text: &'a str, allowed: &[&str]) -> Option<HashMap<String, &'a str>> {
+    let mut attrs = HashMap::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let caps = ATTR_RE.captures(rest).filter(|c| c.get(0)?.start() == 0)?;
+        let name = caps[1].to_ascii_lowercase();
+        if !allowed.contains(&name.as_str()) {
+            return None;
+        }
+        let value = caps
+            .get(2)
+            .or(caps.get(3))
+            .or(caps.get(4))
+            .map_or("", |m| m.as_str());
+        attrs.insert(name, value);
+        rest = rest[caps[0].len()..].trim_start();
+    }
+    Some(attrs)
+}
+
+/// Splits a line into code spans and the text between them, in order
+pub(crate) fn code_span_segments(line: &str) -> Vec<(bool, &str)> {
+    let mut segments = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+    let bytes = line.as_bytes();
+    while i < bytes.len() {
+        if bytes[i] != b'`' {
+            i += 1;
+            continue;
+        }
+        let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+        let fence = &line[i..i + run];
+        // The closing run must be exactly as long as the opening one
+        let close = line[i + run..].match_indices(fence).find(|&(at, _)| {
+            let at = i + run + at;
+            bytes.get(at + run) != Some(&b'`') && (at == 0 || bytes[at - 1] != b'`')
+        });
+        match close {
+            Some((at, _)) => {
+                let end = i + run + at + run;
+                if text_start < i {
+                    segments.push((false, &line[text_start..i]));
+                }
+                segments.push((true, &line[i..end]));
+                text_start = end;
+                i = end;
+            },
+            None => i += run,
+        }
+    }
+    if text_start < line.len() {
+        segments.push((false, &line[text_start..]));
+    }
+    segments
+}
+
+/// Whether byte `at` of `line` is inside a `` `code` `` span
+pub(crate) fn in_code_span(line: &str, at: usize) -> bool {
+    let mut offset = 0;
+    for (is_code, segment) in code_span_segments(line) {
+        if (offset..offset + segment.len()).contains(&at) {
+            return is_code;
+        }
+        offset += segment.len();
+    }
+    false
+}
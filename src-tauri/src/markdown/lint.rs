@@ -0,0 +1,343 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::glossary::Glossary;
+use crate::markdown::sections;
+use comrak::nodes::{AstNode, NodeValue};
+use comrak::{Arena, parse_document};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintIssue {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub line: usize,
+    pub message: String,
+    pub fixable: bool,
+}
+
+impl LintIssue {
+    fn new(rule: &'static str, severity: Severity, line: usize, message: String) -> Self {
+        Self {
+            rule,
+            severity,
+            line,
+            message,
+            fixable: FIXABLE_RULES.contains(&rule),
+        }
+    }
+}
+
+pub const RULE_TRAILING_WHITESPACE: &str = "trailing-whitespace";
+pub const RULE_FINAL_NEWLINE: &str = "final-newline";
+pub const RULE_HEADING_INCREMENT: &str = "heading-increment";
+pub const RULE_DUPLICATE_HEADING: &str = "duplicate-heading";
+pub const RULE_EMPTY_LINK: &str = "empty-link";
+pub const RULE_BROKEN_LINK: &str = "broken-link";
+pub const RULE_SPELLING: &str = "spelling";
+pub const RULE_DEPRECATED_TERM: &str = "deprecated-term";
+
+const FIXABLE_RULES: [&str; 2] = [RULE_TRAILING_WHITESPACE, RULE_FINAL_NEWLINE];
+
+/// Line-based structural checks plus AST checks for headings and links.
+pub fn lint(content: &str, flavor: MarkdownFlavor) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+
+    for_each_prose_line(content, |line_no, line| {
+        if has_stray_trailing_whitespace(line) {
+            issues.push(LintIssue::new(
+                RULE_TRAILING_WHITESPACE,
+                Severity::Info,
+                line_no,
+                "Trailing whitespace".to_string(),
+            ));
+        }
+    });
+
+    if !content.is_empty() && !content.ends_with('\n') {
+        issues.push(LintIssue::new(
+            RULE_FINAL_NEWLINE,
+            Severity::Info,
+            content.lines().count(),
+            "File does not end with a newline".to_string(),
+        ));
+    }
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, content, &flavor.to_comrak_options());
+    let mut last_level = 0u8;
+
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        let line = data.sourcepos.start.line;
+        match &data.value {
+            NodeValue::Heading(heading) => {
+                if last_level > 0 && heading.level > last_level + 1 {
+                    issues.push(LintIssue::new(
+                        RULE_HEADING_INCREMENT,
+                        Severity::Warning,
+                        line,
+                        format!(
+                            "Heading level jumps from H{} to H{}",
+                            last_level, heading.level
+                        ),
+                    ));
+                }
+                last_level = heading.level;
+            },
+            NodeValue::Link(link) => {
+                if link.url.trim().is_empty() {
+                    issues.push(LintIssue::new(
+                        RULE_EMPTY_LINK,
+                        Severity::Warning,
+                        line,
+                        "Link has no destination".to_string(),
+                    ));
+                } else if node.first_child().is_none() {
+                    issues.push(LintIssue::new(
+                        RULE_EMPTY_LINK,
+                        Severity::Warning,
+                        line,
+                        format!("Link to '{}' has no text", link.url),
+                    ));
+                }
+            },
+            _ => {},
+        }
+    }
+
+    issues.extend(duplicate_heading_issues(content, flavor));
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+/// Headings whose text slugs to the same anchor as an earlier one, so links to them are
+/// ambiguous. Fixed with `sections::fix_duplicate_headings` rather than `apply_fixes`.
+fn duplicate_heading_issues(content: &str, flavor: MarkdownFlavor) -> Vec<LintIssue> {
+    let mut first_seen: HashMap<String, usize> = HashMap::new();
+    let mut issues = Vec::new();
+
+    for heading in sections::headings(content, flavor) {
+        let slug = sections::slugify(&heading.text);
+        if slug.is_empty() {
+            continue;
+        }
+        match first_seen.get(&slug) {
+            Some(first_line) => issues.push(LintIssue::new(
+                RULE_DUPLICATE_HEADING,
+                Severity::Warning,
+                heading.line,
+                format!(
+                    "Heading anchor '#{}' duplicates the heading on line {}",
+                    slug, first_line
+                ),
+            )),
+            None => {
+                first_seen.insert(slug, heading.line);
+            },
+        }
+    }
+
+    issues
+}
+
+/// Reports relative link and image targets that do not exist on disk. Remote URLs and
+/// in-page anchors are not checked.
+pub fn check_links(content: &str, base_dir: &Path, flavor: MarkdownFlavor) -> Vec<LintIssue> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, content, &flavor.to_comrak_options());
+    let mut issues = Vec::new();
+
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        let url = match &data.value {
+            NodeValue::Link(link) | NodeValue::Image(link) => link.url.clone(),
+            _ => continue,
+        };
+
+        let Some(target) = local_target(&url) else {
+            continue;
+        };
+        if !base_dir.join(&target).exists() {
+            issues.push(LintIssue::new(
+                RULE_BROKEN_LINK,
+                Severity::Error,
+                data.sourcepos.start.line,
+                format!("Link target not found: {}", url),
+            ));
+        }
+    }
+
+    issues
+}
+
+/// Deprecated terms from the workspace glossary, each with the preferred form to use
+pub fn check_terms(content: &str, glossary: &Glossary) -> Vec<LintIssue> {
+    glossary
+        .scan(content)
+        .into_iter()
+        .filter_map(|found| {
+            let deprecated = found.deprecated?;
+            Some(LintIssue::new(
+                RULE_DEPRECATED_TERM,
+                Severity::Warning,
+                found.line,
+                format!(
+                    "'{}' is deprecated, use '{}'",
+                    deprecated, glossary.terms[found.term].term
+                ),
+            ))
+        })
+        .collect()
+}
+
+/// Words from prose text nodes, skipping code, HTML, and link destinations.
+pub fn prose_words(content: &str, flavor: MarkdownFlavor) -> Vec<(usize, String)> {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, content, &flavor.to_comrak_options());
+    let mut words = Vec::new();
+
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        let NodeValue::Text(text) = &data.value else {
+            continue;
+        };
+        if is_in_code(node) {
+            continue;
+        }
+        let line = data.sourcepos.start.line;
+        words.extend(
+            text.unicode_words()
+                .filter(|w| w.chars().any(char::is_alphabetic))
+                .filter(|w| !w.chars().any(|c| c.is_ascii_digit()))
+                .map(|w| (line, w.to_string())),
+        );
+    }
+
+    words
+}
+
+/// Applies the automatic fixes for the given rules, leaving fenced code untouched.
+pub fn apply_fixes(content: &str, rules: &[&str]) -> String {
+    let fix_whitespace = rules.contains(&RULE_TRAILING_WHITESPACE);
+    let mut output = String::with_capacity(content.len());
+    let mut fence = None;
+
+    for line in content.split_inclusive('\n') {
+        let (body, ending) = split_line_ending(line);
+        let in_code = update_fence(&mut fence, body);
+
+        if fix_whitespace && !in_code && has_stray_trailing_whitespace(body) {
+            output.push_str(body.trim_end());
+        } else {
+            output.push_str(body);
+        }
+        output.push_str(ending);
+    }
+
+    if rules.contains(&RULE_FINAL_NEWLINE) && !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+
+    output
+}
+
+fn for_each_prose_line(content: &str, mut f: impl FnMut(usize, &str)) {
+    let mut fence = None;
+    for (i, line) in content.lines().enumerate() {
+        if !update_fence(&mut fence, line) {
+            f(i + 1, line);
+        }
+    }
+}
+
+/// Tracks fenced code state; returns true when `line` belongs to a fence (markers included).
+pub fn update_fence(fence: &mut Option<(char, usize)>, line: &str) -> bool {
+    if let Some(open) = *fence {
+        if fence_closes(line, open) {
+            *fence = None;
+        }
+        return true;
+    }
+    *fence = fence_open(line);
+    fence.is_some()
+}
+
+/// An opening code fence: its character and length. Indented four or more spaces it is
+/// code, not a fence.
+pub fn fence_open(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|c| *c == marker).count();
+    // A backtick fence's info string can't contain backticks
+    let info = &trimmed[len..];
+    (len >= 3 && !(marker == '`' && info.contains('`'))).then_some((marker, len))
+}
+
+/// Whether `line` closes a fence opened with `len` of `marker`: at least as many of the
+/// same character and nothing else
+pub fn fence_closes(line: &str, (marker, len): (char, usize)) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    let run = trimmed.chars().take_while(|c| *c == marker).count();
+    line.len() - trimmed.len() <= 3 && run >= len && trimmed[run..].trim().is_empty()
+}
+
+fn split_line_ending(line: &str) -> (&str, &str) {
+    if let Some(body) = line.strip_suffix("\r\n") {
+        (body, "\r\n")
+    } else if let Some(body) = line.strip_suffix('\n') {
+        (body, "\n")
+    } else {
+        (line, "")
+    }
+}
+
+/// Two trailing spaces are a hard line break, so only other trailing whitespace counts.
+fn has_stray_trailing_whitespace(line: &str) -> bool {
+    let trimmed = line.trim_end();
+    if trimmed.len() == line.len() {
+        return false;
+    }
+    trimmed.is_empty() || &line[trimmed.len()..] != "  "
+}
+
+fn local_target(url: &str) -> Option<String> {
+    let url = url.trim();
+    if url.is_empty() || url.starts_with('#') || url.starts_with("//") || url.contains("://") {
+        return None;
+    }
+    // Drive letters (C:/) are paths, anything else with a scheme (mailto:, data:) is not
+    if let Some((scheme, _)) = url.split_once(':')
+        && !(scheme.len() == 1 && scheme.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        return None;
+    }
+
+    let path = url.split(['#', '?']).next().unwrap_or_default();
+    if path.is_empty() {
+        return None;
+    }
+    Some(path.replace("%20", " "))
+}
+
+fn is_in_code<'a>(node: &'a AstNode<'a>) -> bool {
+    node.ancestors().any(|ancestor| {
+        matches!(
+            ancestor.data.borrow().value,
+            NodeValue::Code(_) | NodeValue::CodeBlock(_) | NodeValue::HtmlBlock(_)
+        )
+    })
+}
@@ -0,0 +1,280 @@
+use crate::markdown::config::MarkdownFlavor;
+use comrak::nodes::{ListDelimType, ListType, NodeValue};
+use comrak::{Arena, parse_document};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// More spaces than this after a marker start an indented code block inside the item
+const MAX_MARKER_PADDING: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListKind {
+    Bullet,
+    Ordered,
+    Task,
+}
+
+/// How ordered list items are numbered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderedListStyle {
+    /// Keep the numbers as written
+    #[default]
+    Preserve,
+    /// Every item repeats the list's start number, usually `1.`, so reordering items
+    /// never leaves a diff in their markers
+    One,
+    /// Items count up from the list's start number
+    Increment,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListConversion {
+    pub content: String,
+    pub items_converted: usize,
+}
+
+#[derive(Debug, Default)]
+struct LineChange {
+    /// Byte column, length of the old marker (with checkbox), replacement
+    marker: Option<(usize, usize, String)>,
+    indent_delta: isize,
+}
+
+struct ItemMarker {
+    /// Marker plus following spaces; continuation lines are indented to this width
+    width: usize,
+    /// Width including a task checkbox, if present
+    full_len: usize,
+    checked: Option<bool>,
+}
+
+/// Converts every list with an item starting in `from_line..=to_line` (1-based) to `target`.
+/// Lists are located through the AST, so markers inside code blocks are never touched, and
+/// each item's continuation lines (nested lists, code, paragraphs) are re-indented by the
+/// change in marker width so the structure is preserved.
+pub fn convert_list(
+    content: &str,
+    from_line: usize,
+    to_line: usize,
+    target: ListKind,
+    flavor: MarkdownFlavor,
+) -> ListConversion {
+    let arena = Arena::new();
+    let root = parse_document(&arena, content, &flavor.to_comrak_options());
+    let lines: Vec<&str> = content.split('\n').collect();
+    let mut changes: HashMap<usize, LineChange> = HashMap::new();
+    let mut items_converted = 0;
+
+    for list in root.descendants() {
+        let data = list.data.borrow();
+        let NodeValue::List(list_data) = &data.value else {
+            continue;
+        };
+
+        let items: Vec<_> = list.children().collect();
+        let in_range = items.iter().any(|item| {
+            let line = item.data.borrow().sourcepos.start.line;
+            (from_line..=to_line).contains(&line)
+        });
+        if !in_range {
+            continue;
+        }
+
+        let ordered = matches!(list_data.list_type, ListType::Ordered);
+        let start_number = if ordered { list_data.start } else { 1 };
+        let delimiter = if ordered && matches!(list_data.delimiter, ListDelimType::Paren) {
+            ')'
+        } else {
+            '.'
+        };
+
+        for (i, item) in items.iter().enumerate() {
+            let pos = item.data.borrow().sourcepos;
+            let idx = pos.start.line - 1;
+            let col = pos.start.column - 1;
+            let Some(marker) = lines
+                .get(idx)
+                .and_then(|line| line.get(col..))
+                .and_then(parse_marker)
+            else {
+                continue;
+            };
+
+            let prefix = match target {
+                ListKind::Ordered => format!("{}{} ", start_number + i, delimiter),
+                ListKind::Bullet | ListKind::Task => "- ".to_string(),
+            };
+            let checkbox = match target {
+                ListKind::Task if marker.checked == Some(true) => "[x] ",
+                ListKind::Task => "[ ] ",
+                _ => "",
+            };
+            let delta = prefix.len() as isize - marker.width as isize;
+
+            let mut replacement = format!("{}{}", prefix, checkbox);
+            if lines[idx][col + marker.full_len..].trim().is_empty() {
+                replacement.truncate(replacement.trim_end().len());
+            }
+            changes.entry(idx).or_default().marker = Some((col, marker.full_len, replacement));
+            // Lines after the marker line up to the item's last line (0-based indices)
+            for cont in pos.start.line..pos.end.line {
+                changes.entry(cont).or_default().indent_delta += delta;
+            }
+            items_converted += 1;
+        }
+    }
+
+    apply_changes(content, &lines, &changes, items_converted)
+}
+
+/// Renumbers the items of every ordered list in `style`. Only the digits change, so the
+/// space after each marker is kept; continuation lines move with a marker that grows or
+/// shrinks. `items_converted` counts the items whose number changed.
+pub fn renumber_ordered_lists(
+    content: &str,
+    style: OrderedListStyle,
+    flavor: MarkdownFlavor,
+) -> ListConversion {
+    if style == OrderedListStyle::Preserve {
+        return ListConversion {
+            content: content.to_string(),
+            items_converted: 0,
+        };
+    }
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, content, &flavor.to_comrak_options());
+    let lines: Vec<&str> = content.split('\n').collect();
+    let mut changes: HashMap<usize, LineChange> = HashMap::new();
+    let mut items_converted = 0;
+
+    for list in root.descendants() {
+        let data = list.data.borrow();
+        let NodeValue::List(list_data) = &data.value else {
+            continue;
+        };
+        if !matches!(list_data.list_type, ListType::Ordered) {
+            continue;
+        }
+
+        for (i, item) in list.children().enumerate() {
+            let pos = item.data.borrow().sourcepos;
+            let idx = pos.start.line - 1;
+            let col = pos.start.column - 1;
+            let Some(rest) = lines.get(idx).and_then(|line| line.get(col..)) else {
+                continue;
+            };
+            let digits = rest.bytes().take_while(|b| b.is_ascii_digit()).count();
+            let number = match style {
+                OrderedListStyle::One => list_data.start,
+                _ => list_data.start + i,
+            }
+            .to_string();
+            if digits == 0 || rest[..digits] == number {
+                continue;
+            }
+
+            let delta = number.len() as isize - digits as isize;
+            changes.entry(idx).or_default().marker = Some((col, digits, number));
+            for cont in pos.start.line..pos.end.line {
+                changes.entry(cont).or_default().indent_delta += delta;
+            }
+            items_converted += 1;
+        }
+    }
+
+    apply_changes(content, &lines, &changes, items_converted)
+}
+
+/// Rewrites the changed markers and shifts continuation lines
+fn apply_changes(
+    content: &str,
+    lines: &[&str],
+    changes: &HashMap<usize, LineChange>,
+    items_converted: usize,
+) -> ListConversion {
+    if items_converted == 0 {
+        return ListConversion {
+            content: content.to_string(),
+            items_converted,
+        };
+    }
+
+    let converted: Vec<String> = lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| {
+            let Some(change) = changes.get(&idx) else {
+                return line.to_string();
+            };
+            let mut text = line.to_string();
+            if let Some((col, len, marker)) = &change.marker {
+                text.replace_range(*col..*col + *len, marker);
+            }
+            shift_indent(&text, change.indent_delta)
+        })
+        .collect();
+
+    ListConversion {
+        content: converted.join("\n"),
+        items_converted,
+    }
+}
+
+/// Parses a list marker (`-`, `*`, `+`, `1.`, `1)`) and an optional task checkbox at the
+/// start of `rest`.
+fn parse_marker(rest: &str) -> Option<ItemMarker> {
+    let bytes = rest.as_bytes();
+    let marker_len = match bytes.first()? {
+        b'-' | b'*' | b'+' => 1,
+        b'0'..=b'9' => {
+            let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+            match bytes.get(digits) {
+                Some(b'.') | Some(b')') => digits + 1,
+                _ => return None,
+            }
+        },
+        _ => return None,
+    };
+
+    let mut spaces = bytes[marker_len..]
+        .iter()
+        .take_while(|b| **b == b' ')
+        .count();
+    if spaces > MAX_MARKER_PADDING {
+        spaces = 1;
+    }
+    let width = marker_len + spaces;
+
+    let after = &rest[width..];
+    let checked = match after.get(..3) {
+        Some("[ ]") => Some(false),
+        Some("[x]") | Some("[X]") => Some(true),
+        _ => None,
+    }
+    .filter(|_| after[3..].is_empty() || after[3..].starts_with([' ', '\t', '\r']));
+
+    let full_len = match checked {
+        Some(_) => width + 3 + after[3..].bytes().take_while(|b| *b == b' ').count(),
+        None => width,
+    };
+
+    Some(ItemMarker {
+        width,
+        full_len,
+        checked,
+    })
+}
+
+fn shift_indent(line: &str, delta: isize) -> String {
+    if delta == 0 || line.trim().is_empty() {
+        return line.to_string();
+    }
+    if delta > 0 {
+        return format!("{}{}", " ".repeat(delta as usize), line);
+    }
+    let leading = line.bytes().take_while(|b| *b == b' ').count();
+    line[leading.min(delta.unsigned_abs())..].to_string()
+}
@@ -0,0 +1,314 @@
+use crate::markdown::lint::{fence_closes, fence_open};
+use serde::Serialize;
+
+const CONFLICT_START: &str = "<<<<<<< ours";
+const CONFLICT_SEPARATOR: &str = "=======";
+const CONFLICT_END: &str = ">>>>>>> theirs";
+// Upper bound on the LCS table so pathological inputs can't exhaust memory
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Serialize)]
+pub struct MergeConflict {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub base: String,
+    pub ours: String,
+    pub theirs: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergeResult {
+    pub content: String,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// A top-level block of a document. Blocks compare by their text alone, so a block
+/// that only moved, gained blank lines around it or changed line endings still matches.
+#[derive(Debug, Clone)]
+pub(crate) struct Block {
+    /// The block's lines joined with `\n`, for comparing
+    pub(crate) text: String,
+    /// The block as written, without its last line ending
+    raw: String,
+    /// What followed the block up to the next one: its line ending and any blank lines
+    separator: String,
+}
+
+impl PartialEq for Block {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+    }
+}
+
+/// Three-way merges markdown documents block by block (paragraphs, lists, headings,
+/// fenced code), so independent edits to different blocks merge cleanly and only
+/// blocks changed on both sides become conflicts. Blank lines between blocks and line
+/// endings are kept as written, so merging an unchanged document returns it unchanged.
+pub fn merge_documents(base: &str, ours: &str, theirs: &str) -> MergeResult {
+    let base_blocks = split_blocks(base);
+    let our_blocks = split_blocks(ours);
+    let their_blocks = split_blocks(theirs);
+
+    let ours_match = lcs_matches(&base_blocks, &our_blocks);
+    let theirs_match = lcs_matches(&base_blocks, &their_blocks);
+
+    let mut output = MergeOutput {
+        line_ending: if ours.contains("\r\n") { "\r\n" } else { "\n" },
+        ..Default::default()
+    };
+    let (mut b, mut o, mut t) = (0, 0, 0);
+
+    // Blocks unchanged on both sides are stable anchors; everything between is resolved.
+    // Anchors are written as they appear in our version.
+    for (i, (our_idx, their_idx)) in ours_match.iter().zip(&theirs_match).enumerate() {
+        let (Some(oi), Some(ti)) = (*our_idx, *their_idx) else {
+            continue;
+        };
+        output.resolve(&base_blocks[b..i], &our_blocks[o..oi], &their_blocks[t..ti]);
+        output.push_block(&our_blocks[oi]);
+        (b, o, t) = (i + 1, oi + 1, ti + 1);
+    }
+    output.resolve(&base_blocks[b..], &our_blocks[o..], &their_blocks[t..]);
+
+    output.finish()
+}
+
+/// An ATX heading: up to six `#` followed by a space, a tab or the end of the line
+fn is_atx_heading(line: &str) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return false;
+    }
+    let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&hashes)
+        && trimmed[hashes..]
+            .chars()
+            .next()
+            .is_none_or(|c| c == ' ' || c == '\t')
+}
+
+/// Splits a document into top-level blocks. Fenced code stays whole and ATX headings
+/// stand alone, so an edit beneath a heading never conflicts with the heading itself.
+/// Blank lines before the first block stay with it.
+pub(crate) fn split_blocks(content: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut leading = String::new();
+    let mut fence: Option<(char, usize)> = None;
+
+    for raw in content.split_inclusive('\n') {
+        let line = raw.trim_end_matches(['\r', '\n']);
+
+        if let Some(open) = fence {
+            current.push(raw);
+            if fence_closes(line, open) {
+                fence = None;
+            }
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            match blocks.last_mut() {
+                Some(Block { separator, .. }) if current.is_empty() => separator.push_str(raw),
+                _ if current.is_empty() => leading.push_str(raw),
+                _ => {
+                    flush_block(&mut blocks, &mut current, &mut leading);
+                    push_separator(&mut blocks, raw);
+                },
+            }
+        } else if let Some(open) = fence_open(line) {
+            fence = Some(open);
+            current.push(raw);
+        } else if is_atx_heading(line) {
+            flush_block(&mut blocks, &mut current, &mut leading);
+            current.push(raw);
+            flush_block(&mut blocks, &mut current, &mut leading);
+        } else {
+            current.push(raw);
+        }
+    }
+    flush_block(&mut blocks, &mut current, &mut leading);
+
+    blocks
+}
+
+fn push_separator(blocks: &mut [Block], raw: &str) {
+    if let Some(block) = blocks.last_mut() {
+        block.separator.push_str(raw);
+    }
+}
+
+fn flush_block(blocks: &mut Vec<Block>, current: &mut Vec<&str>, leading: &mut String) {
+    if current.is_empty() {
+        return;
+    }
+    let written: String = current.concat();
+    let body = written.trim_end_matches(['\r', '\n']);
+    let text = current
+        .iter()
+        .map(|line| line.trim_end_matches(['\r', '\n']))
+        .collect::<Vec<_>>()
+        .join("\n");
+    blocks.push(Block {
+        text,
+        raw: format!("{}{}", std::mem::take(leading), body),
+        separator: written[body.len()..].to_string(),
+    });
+    current.clear();
+}
+
+/// Aligns `other` against `base` by longest common subsequence, returning for each base
+/// item the index of its match in `other`. Common prefix/suffix are matched directly so
+/// the quadratic table only covers the edited middle.
+pub fn lcs_matches<T: PartialEq>(base: &[T], other: &[T]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base.len()];
+
+    let prefix = base.iter().zip(other).take_while(|(a, b)| a == b).count();
+    for (i, m) in matches.iter_mut().enumerate().take(prefix) {
+        *m = Some(i);
+    }
+
+    let suffix = base[prefix..]
+        .iter()
+        .rev()
+        .zip(other[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    for k in 0..suffix {
+        matches[base.len() - 1 - k] = Some(other.len() - 1 - k);
+    }
+
+    let base_mid = &base[prefix..base.len() - suffix];
+    let other_mid = &other[prefix..other.len() - suffix];
+    let (n, m) = (base_mid.len(), other_mid.len());
+    if n == 0 || m == 0 || (n + 1) * (m + 1) > MAX_LCS_CELLS {
+        return matches;
+    }
+
+    let mut table = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if base_mid[i] == other_mid[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if base_mid[i] == other_mid[j] {
+            matches[prefix + i] = Some(prefix + j);
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    matches
+}
+
+#[derive(Default)]
+struct MergeOutput {
+    content: String,
+    line_ending: &'static str,
+    conflicts: Vec<MergeConflict>,
+}
+
+impl MergeOutput {
+    /// Appends a block as written and returns the 1-based line it starts on. A block that
+    /// ended its document without a line ending gets a blank line before the next one.
+    fn push_text(&mut self, raw: &str, separator: &str) -> usize {
+        if !self.content.is_empty() && !self.content.ends_with('\n') {
+            self.content.push_str(self.line_ending);
+            self.content.push_str(self.line_ending);
+        }
+        let start_line = self.content.matches('\n').count() + 1;
+        self.content.push_str(raw);
+        self.content.push_str(separator);
+        start_line
+    }
+
+    fn push_block(&mut self, block: &Block) -> usize {
+        self.push_text(&block.raw, &block.separator)
+    }
+
+    fn resolve(&mut self, base: &[Block], ours: &[Block], theirs: &[Block]) {
+        let resolved = if ours == theirs || theirs == base {
+            ours
+        } else if ours == base {
+            theirs
+        } else if base.len() > 1 && ours.len() == base.len() && theirs.len() == base.len() {
+            // Same shape on all sides: edits to neighbouring blocks are independent
+            for i in 0..base.len() {
+                self.resolve(&base[i..=i], &ours[i..=i], &theirs[i..=i]);
+            }
+            return;
+        } else {
+            self.push_conflict(base, ours, theirs);
+            return;
+        };
+
+        for block in resolved {
+            self.push_block(block);
+        }
+    }
+
+    /// The blocks as written, with the separators between them but not after the last
+    fn joined(blocks: &[Block]) -> String {
+        let mut text = String::new();
+        for (i, block) in blocks.iter().enumerate() {
+            text.push_str(&block.raw);
+            if i + 1 < blocks.len() {
+                text.push_str(&block.separator);
+            }
+        }
+        text
+    }
+
+    fn push_conflict(&mut self, base: &[Block], ours: &[Block], theirs: &[Block]) {
+        let ours_text = Self::joined(ours);
+        let theirs_text = Self::joined(theirs);
+
+        let mut lines = vec![CONFLICT_START];
+        if !ours_text.is_empty() {
+            lines.push(&ours_text);
+        }
+        lines.push(CONFLICT_SEPARATOR);
+        if !theirs_text.is_empty() {
+            lines.push(&theirs_text);
+        }
+        lines.push(CONFLICT_END);
+
+        let block = lines.join(self.line_ending);
+        let line_count = block.lines().count();
+        // The conflict takes the place of our blocks, so it is followed the way they were
+        let separator = ours
+            .last()
+            .or(theirs.last())
+            .or(base.last())
+            .map(|b| b.separator.clone())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| self.line_ending.repeat(2));
+        let start_line = self.push_text(&block, &separator);
+
+        self.conflicts.push(MergeConflict {
+            start_line,
+            end_line: start_line + line_count - 1,
+            base: Self::joined(base),
+            ours: ours_text,
+            theirs: theirs_text,
+        });
+    }
+
+    fn finish(self) -> MergeResult {
+        MergeResult {
+            content: self.content,
+            conflicts: self.conflicts,
+        }
+    }
+}
@@ -1,3 +1,34 @@
+pub mod accessibility;
+pub mod annotations;
+pub mod browser_bookmarks;
+pub mod clipboard;
+pub mod combine;
+pub mod compare;
 pub mod config;
+pub mod diagrams;
+pub mod diff;
+pub mod document_css;
+pub mod file_links;
 pub mod formatter;
+pub mod front_matter;
+pub mod glossary;
+pub mod highlight;
+pub mod html_to_markdown;
+pub mod incremental;
+pub mod index_page;
+pub mod inline_html;
+pub mod lint;
+pub mod lists;
+pub mod merge;
+pub mod plain_text;
+pub mod reference_links;
 pub mod renderer;
+pub mod roundtrip;
+pub mod sections;
+pub mod security;
+pub mod similarity;
+pub mod stats;
+pub mod summary;
+pub mod symbols;
+pub mod tab_title;
+pub mod text_metrics;
@@ -0,0 +1,105 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::front_matter;
+use crate::markdown::sections::{self, TextOptions};
+use comrak::nodes::{AstNode, ListType, NodeValue};
+use comrak::{Arena, parse_document};
+
+const BULLET: &str = "•";
+
+/// The document as readable plain text: markup characters, link destinations, raw HTML
+/// and front matter are dropped. Headings and paragraphs are separated by blank lines,
+/// list items keep a bullet or number, table cells are tab-separated, code keeps its text,
+/// images become their alt text, and footnotes are listed at the end.
+pub fn strip(content: &str, flavor: MarkdownFlavor) -> String {
+    let body = front_matter::split(content).map_or(content, |fm| fm.body);
+    let arena = Arena::new();
+    let root = parse_document(&arena, body, &flavor.to_comrak_options());
+
+    let mut blocks = Vec::new();
+    let mut footnotes = Vec::new();
+    for node in root.children() {
+        if let NodeValue::FootnoteDefinition(definition) = &node.data.borrow().value {
+            let text = container_blocks(node).join(" ");
+            footnotes.push(format!("[{}] {}", definition.name, text));
+            continue;
+        }
+        blocks.extend(block(node));
+    }
+    if !footnotes.is_empty() {
+        blocks.push(footnotes.join("\n"));
+    }
+    blocks.join("\n\n")
+}
+
+/// Text blocks of a container's children
+fn container_blocks<'a>(node: &'a AstNode<'a>) -> Vec<String> {
+    node.children().flat_map(block).collect()
+}
+
+fn block<'a>(node: &'a AstNode<'a>) -> Vec<String> {
+    match &node.data.borrow().value {
+        NodeValue::Paragraph | NodeValue::Heading(_) => {
+            let text = inline_text(node);
+            let text = text.trim();
+            if text.is_empty() {
+                Vec::new()
+            } else {
+                vec![text.to_string()]
+            }
+        },
+        NodeValue::CodeBlock(code) => {
+            vec![code.literal.trim_end_matches('\n').to_string()]
+        },
+        NodeValue::List(list) => vec![list_text(node, list.list_type, list.start)],
+        NodeValue::Table(_) => {
+            let rows: Vec<String> = node
+                .children()
+                .map(|row| {
+                    row.children()
+                        .map(|cell| inline_text(cell).trim().to_string())
+                        .collect::<Vec<_>>()
+                        .join("\t")
+                })
+                .collect();
+            vec![rows.join("\n")]
+        },
+        NodeValue::HtmlBlock(_) | NodeValue::ThematicBreak | NodeValue::FrontMatter(_) => {
+            Vec::new()
+        },
+        // Block quotes, alerts, description lists and other containers
+        _ => container_blocks(node),
+    }
+}
+
+/// A list with one item per line; an item's later paragraphs and nested lists are
+/// indented under it
+fn list_text<'a>(node: &'a AstNode<'a>, list_type: ListType, start: usize) -> String {
+    let mut items = Vec::new();
+    for (index, item) in node.children().enumerate() {
+        let marker = match list_type {
+            ListType::Bullet => BULLET.to_string(),
+            ListType::Ordered => format!("{}.", start + index),
+        };
+        let body = container_blocks(item).join("\n");
+        let indent = " ".repeat(marker.chars().count() + 1);
+        let mut lines = body.lines();
+        let mut text = format!("{} {}", marker, lines.next().unwrap_or(""));
+        for line in lines {
+            text.push('\n');
+            text.push_str(&indent);
+            text.push_str(line);
+        }
+        items.push(text);
+    }
+    items.join("\n")
+}
+
+fn inline_text<'a>(node: &'a AstNode<'a>) -> String {
+    let options = TextOptions {
+        image_alt: true,
+        math: true,
+        footnote_refs: true,
+        line_breaks: true,
+    };
+    sections::node_text(node, options)
+}
@@ -0,0 +1,357 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::front_matter;
+use crate::markdown::inline_html::{code_span_segments, skipped_lines};
+use crate::utils::detect_line_ending;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+// Shared by the link patterns: bracketed text allowing escapes and one level of nesting,
+// and an optional title in any of its three quoting styles
+const LINK_TEXT: &str = r"(?:[^\[\]\\\n]|\\.|\[[^\[\]\n]*\])*";
+const LINK_TITLE: &str = r#"(?:"(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*'|\((?:[^()\\]|\\.)*\))"#;
+
+// `[text](destination "title")`, images included so they can be skipped
+static INLINE_LINK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(!?)\[({LINK_TEXT})\]\(\s*(<[^<>\n]*>|(?:[^\s()\\]|\\.|\([^\s()]*\))*)(?:\s+({LINK_TITLE}))?\s*\)"
+    ))
+    .expect("Invalid INLINE_LINK_RE")
+});
+
+// `[text][label]`, `[text][]` and `[text]`
+static REFERENCE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(!?)\[({LINK_TEXT})\](?:\[((?:[^\[\]\\\n]|\\.)*)\])?"
+    ))
+    .expect("Invalid REFERENCE_RE")
+});
+
+// `[label]: destination "title"` on one line; footnote definitions are left alone
+static DEFINITION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"^ {{0,3}}\[([^\^\[\]\\\n](?:[^\[\]\\\n]|\\.)*)\]:[ \t]*(<[^<>\n]*>|\S+)(?:[ \t]+({LINK_TITLE}))?[ \t]*$"
+    ))
+    .expect("Invalid DEFINITION_RE")
+});
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStyle {
+    /// Leave links as written
+    #[default]
+    Maintain,
+    /// Inline links become numbered references, defined at the end of the document
+    Reference,
+    /// References are written out inline and their definitions dropped
+    Inline,
+}
+
+#[derive(Debug, Clone)]
+struct Definition {
+    /// As written, so named labels keep their case
+    label: String,
+    destination: String,
+    title: Option<String>,
+}
+
+impl Definition {
+    fn target(&self) -> String {
+        match &self.title {
+            Some(title) => format!("{} {}", self.destination, title),
+            None => self.destination.clone(),
+        }
+    }
+}
+
+/// The document with its links rewritten in `style`. Code, front matter and HTML blocks
+/// are left alone.
+pub fn apply(content: &str, style: LinkStyle, flavor: MarkdownFlavor) -> Cow<'_, str> {
+    match style {
+        LinkStyle::Maintain => Cow::Borrowed(content),
+        LinkStyle::Reference => Cow::Owned(to_reference(content, flavor)),
+        LinkStyle::Inline => Cow::Owned(to_inline(content, flavor)),
+    }
+}
+
+/// Labels match case-insensitively with runs of whitespace collapsed
+fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn is_numbered(label: &str) -> bool {
+    !label.is_empty() && label.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// The document split into lines, with whether each is markdown text links can be
+/// rewritten in
+struct Lines<'a> {
+    lines: Vec<&'a str>,
+    editable: Vec<bool>,
+    ending: &'static str,
+}
+
+impl<'a> Lines<'a> {
+    fn new(content: &'a str, flavor: MarkdownFlavor) -> Self {
+        let fm_lines = front_matter::line_count(content);
+        let skipped = skipped_lines(content, fm_lines, flavor);
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let editable = (1..=lines.len())
+            .map(|line_no| line_no > fm_lines && !skipped.contains_key(&line_no))
+            .collect();
+        let ending = if detect_line_ending(content) == "CRLF" {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        Self {
+            lines,
+            editable,
+            ending,
+        }
+    }
+
+    /// The first definition of each label, the one CommonMark uses, and the normalized
+    /// label of every definition line
+    fn definitions(&self) -> (Vec<Definition>, HashMap<usize, String>) {
+        let mut definitions: Vec<Definition> = Vec::new();
+        let mut lines = HashMap::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            if !self.editable[i] {
+                continue;
+            }
+            let Some(caps) = DEFINITION_RE.captures(line.trim_end_matches(['\r', '\n'])) else {
+                continue;
+            };
+            let key = normalize_label(&caps[1]);
+            if !definitions.iter().any(|d| normalize_label(&d.label) == key) {
+                definitions.push(Definition {
+                    label: caps[1].to_string(),
+                    destination: caps[2].to_string(),
+                    title: caps.get(3).map(|m| m.as_str().to_string()),
+                });
+            }
+            lines.insert(i, key);
+        }
+        (definitions, lines)
+    }
+}
+
+/// Rewrites the text outside code spans of each editable line, leaving out the lines for
+/// which `keep` is false
+fn rewrite_lines(
+    lines: &Lines,
+    keep: impl Fn(usize) -> bool,
+    mut rewrite: impl FnMut(&str) -> String,
+) -> String {
+    let mut out = String::new();
+    for (i, line) in lines.lines.iter().enumerate() {
+        if !keep(i) {
+            continue;
+        }
+        if !lines.editable[i] {
+            out.push_str(line);
+            continue;
+        }
+        let body = line.trim_end_matches(['\r', '\n']);
+        for (is_code, segment) in code_span_segments(body) {
+            if is_code {
+                out.push_str(segment);
+            } else {
+                out.push_str(&rewrite(segment));
+            }
+        }
+        out.push_str(&line[body.len()..]);
+    }
+    out
+}
+
+/// The label a reference match points to, unless it is really an inline link, a
+/// definition or a footnote
+fn reference_label<'t>(caps: &Captures<'t>, segment: &str) -> Option<&'t str> {
+    let whole = caps.get(0)?;
+    let label = match caps.get(3) {
+        Some(label) if !label.as_str().is_empty() => label.as_str(),
+        explicit => {
+            // `[text]` followed by `(` or `:` is an inline link or a definition
+            if explicit.is_none() && segment[whole.end()..].starts_with(['(', ':']) {
+                return None;
+            }
+            caps.get(2)?.as_str()
+        },
+    };
+    (!label.starts_with('^')).then_some(label)
+}
+
+/// Inline links become `[text][n]` with `[n]: destination` at the end of the document.
+/// Links to the same destination and title share a definition, an existing one if there
+/// is one. Numbered labels are renumbered in order of first use; named labels are kept.
+/// Images stay inline.
+pub fn to_reference(content: &str, flavor: MarkdownFlavor) -> String {
+    let lines = Lines::new(content, flavor);
+    let (mut definitions, definition_lines) = lines.definitions();
+
+    // Reversed so the first definition of a target is the one reused
+    let mut by_target: HashMap<String, String> = definitions
+        .iter()
+        .rev()
+        .map(|d| (d.target(), d.label.clone()))
+        .collect();
+    let mut next_number = definitions
+        .iter()
+        .filter_map(|d| d.label.parse::<usize>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let mut converted = 0;
+    let body = rewrite_lines(
+        &lines,
+        |i| !definition_lines.contains_key(&i),
+        |segment| {
+            INLINE_LINK_RE
+                .replace_all(segment, |caps: &Captures| {
+                    if !caps[1].is_empty() {
+                        return caps[0].to_string();
+                    }
+                    let definition = Definition {
+                        label: String::new(),
+                        destination: caps[3].to_string(),
+                        title: caps.get(4).map(|m| m.as_str().to_string()),
+                    };
+                    let label = by_target
+                        .entry(definition.target())
+                        .or_insert_with(|| {
+                            let label = next_number.to_string();
+                            next_number += 1;
+                            definitions.push(Definition {
+                                label: label.clone(),
+                                ..definition
+                            });
+                            label
+                        })
+                        .clone();
+                    converted += 1;
+                    format!("[{}][{}]", &caps[2], label)
+                })
+                .into_owned()
+        },
+    );
+    if converted == 0 && definition_lines.is_empty() {
+        return content.to_string();
+    }
+
+    // Renumber numbered labels by first use, rewriting `[1]` shortcuts to keep their text
+    let numbered: HashSet<String> = definitions
+        .iter()
+        .map(|d| normalize_label(&d.label))
+        .filter(|label| is_numbered(label))
+        .collect();
+    let mut renumbered: HashMap<String, usize> = HashMap::new();
+    let body_lines = Lines::new(&body, flavor);
+    let body = rewrite_lines(
+        &body_lines,
+        |_| true,
+        |segment| {
+            REFERENCE_RE
+                .replace_all(segment, |caps: &Captures| {
+                    let Some(label) = reference_label(caps, segment)
+                        .map(normalize_label)
+                        .filter(|label| numbered.contains(label))
+                    else {
+                        return caps[0].to_string();
+                    };
+                    let next = renumbered.len() + 1;
+                    let number = *renumbered.entry(label).or_insert(next);
+                    format!("{}[{}][{}]", &caps[1], &caps[2], number)
+                })
+                .into_owned()
+        },
+    );
+
+    // Numbered definitions first, in their new order with unused ones after, then named
+    // ones as they were written
+    let mut numbered_definitions: Vec<(usize, &Definition)> = Vec::new();
+    let mut named_definitions: Vec<&Definition> = Vec::new();
+    for definition in &definitions {
+        let key = normalize_label(&definition.label);
+        if !numbered.contains(&key) {
+            named_definitions.push(definition);
+            continue;
+        }
+        let next = renumbered.len() + 1;
+        let number = *renumbered.entry(key).or_insert(next);
+        numbered_definitions.push((number, definition));
+    }
+    numbered_definitions.sort_by_key(|(number, _)| *number);
+
+    let ending = lines.ending;
+    let mut out = body.trim_end().to_string();
+    out.push_str(ending);
+    out.push_str(ending);
+    let numbered_lines = numbered_definitions
+        .iter()
+        .map(|(number, d)| format!("[{}]: {}", number, d.target()));
+    let named_lines = named_definitions
+        .iter()
+        .map(|d| format!("[{}]: {}", d.label, d.target()));
+    for line in numbered_lines.chain(named_lines) {
+        out.push_str(&line);
+        out.push_str(ending);
+    }
+    out
+}
+
+/// References with a definition become inline links, images included, and the
+/// definitions they used are dropped. Unused definitions are kept where they are.
+pub fn to_inline(content: &str, flavor: MarkdownFlavor) -> String {
+    let lines = Lines::new(content, flavor);
+    let (definitions, definition_lines) = lines.definitions();
+    if definitions.is_empty() {
+        return content.to_string();
+    }
+    let by_label: HashMap<String, &Definition> = definitions
+        .iter()
+        .map(|d| (normalize_label(&d.label), d))
+        .collect();
+
+    let mut used: HashSet<String> = HashSet::new();
+    // Definition lines go through too: `[label]:` is never taken for a reference
+    let body = rewrite_lines(
+        &lines,
+        |_| true,
+        |segment| {
+            REFERENCE_RE
+                .replace_all(segment, |caps: &Captures| {
+                    let Some((label, definition)) = reference_label(caps, segment)
+                        .map(normalize_label)
+                        .and_then(|label| by_label.get(&label).map(|d| (label, *d)))
+                    else {
+                        return caps[0].to_string();
+                    };
+                    used.insert(label);
+                    format!("{}[{}]({})", &caps[1], &caps[2], definition.target())
+                })
+                .into_owned()
+        },
+    );
+    if used.is_empty() {
+        return content.to_string();
+    }
+
+    // Rewriting keeps every line, so line numbers still match the definitions'
+    body.split_inclusive('\n')
+        .enumerate()
+        .filter(|(i, _)| {
+            definition_lines
+                .get(i)
+                .is_none_or(|label| !used.contains(label))
+        })
+        .map(|(_, line)| line)
+        .collect()
+}
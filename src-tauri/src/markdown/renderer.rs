@@ -1,18 +1,86 @@
-use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::config::{ExtensionToggles, MarkdownFlavor};
+use crate::markdown::diagrams::{self, DiagramSettings, RenderedDiagram};
+use crate::markdown::document_css;
+use crate::markdown::front_matter;
+use crate::markdown::highlight;
+use crate::markdown::sections;
+use crate::markdown::security::{self, HtmlPolicy, RemotePolicy, SanitizationReport};
+use crate::markdown::text_metrics::{self, Readability, WordCountMode, build_line_map_and_metrics};
+use crate::preview_assets;
+use crate::utils::escape_html;
 use anyhow::{Result, anyhow};
-use comrak::nodes::{AstNode, NodeValue};
+use comrak::nodes::{AstNode, NodeHtmlBlock, NodeValue};
 use comrak::{Arena, format_html_with_plugins, options::Plugins, parse_document};
+use path_clean::PathClean;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::sync::LazyLock;
-use unicode_segmentation::UnicodeSegmentation;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+/// Rendered documents kept for reuse; covers switching between a few open tabs
+const RENDER_CACHE_CAPACITY: usize = 8;
+
+/// Most recently used last, keyed by `render_cache_key`
+static RENDER_CACHE: LazyLock<Mutex<VecDeque<(u64, RenderResult)>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(RENDER_CACHE_CAPACITY)));
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Hash)]
 pub struct MarkdownOptions {
     pub flavor: MarkdownFlavor,
+    pub remote_policy: RemotePolicy,
+    pub extensions: ExtensionToggles,
+    /// Parse a leading `---`/`+++` block as front matter so it is left out of the HTML
+    pub hide_front_matter: bool,
+    /// Prepended to every heading `id`, to avoid clashes with ids in the surrounding page
+    pub heading_id_prefix: String,
+    /// Path of the document being rendered; relative image paths resolve against its folder
+    pub base_path: Option<String>,
+    pub html_policy: HtmlPolicy,
+    pub word_count_mode: WordCountMode,
+    /// Tools for rendering ```` ```mermaid ```` and ```` ```plantuml ```` fences as images;
+    /// `None` leaves them as code
+    pub diagrams: Option<DiagramSettings>,
+    pub path_links: PathLinkSettings,
+    /// Rendering for the app's preview: local images and diagrams load through the
+    /// `preview` protocol. Off for exports, which embed diagrams as `data:` URIs.
+    pub preview: bool,
+}
+
+/// Which file paths in plain text become clickable links
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    Serialize,
+    Deserialize
+)]
+#[serde(rename_all = "lowercase")]
+pub enum PathLinkMode {
+    /// Windows, Unix, relative and `~/` paths
+    #[default]
+    All,
+    Off,
+    /// Only drive-letter paths such as `C:\notes\todo.md`
+    Windows,
+    /// Matches of `pattern`; its first capture group, if any, is the path
+    Custom,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The `pathLinks` settings table
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PathLinkSettings {
+    pub mode: PathLinkMode,
+    pub pattern: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RenderResult {
     pub html: String,
     pub line_map: Vec<usize>,
@@ -20,46 +88,427 @@ pub struct RenderResult {
     pub word_count: usize,
     pub char_count: usize,
     pub widest_column: usize,
+    pub readability: Readability,
+    pub sanitization: SanitizationReport,
+    /// Heading `id`s in document order, prefix included
+    pub heading_ids: Vec<String>,
+    /// Local files the HTML now loads through the `preview` protocol
+    pub local_images: Vec<String>,
+    /// Sanitized `css:` front matter, scoped to `.document-css`
+    pub document_css: Option<String>,
+    /// Stylesheet for highlighted code, for previews
+    pub highlight_css: Option<String>,
+    /// Diagrams still rendering in the background, shown as code for now
+    #[serde(skip)]
+    pub diagrams_pending: bool,
+    /// Diagrams in `html` by id, linked again when a preview is served from the cache
+    #[serde(skip)]
+    pub diagrams: HashMap<String, RenderedDiagram>,
 }
 
-/// Renders markdown to HTML with line number tracking and document metrics
-pub fn render_markdown(content: &str, options: MarkdownOptions) -> Result<RenderResult> {
-    let comrak_options = options.flavor.to_comrak_options();
+/// HTML for a document or one block of it, before document-wide metrics are added
+pub struct RenderedFragment {
+    pub html: String,
+    pub sanitization: SanitizationReport,
+    /// Heading texts in order, as slugged for their ids
+    pub heading_texts: Vec<String>,
+    pub local_images: Vec<String>,
+    pub diagrams_pending: bool,
+    pub diagrams: HashMap<String, RenderedDiagram>,
+}
 
-    let arena = Arena::new();
-    let root = parse_document(&arena, content, &comrak_options);
+fn render_cache_key(content: &str, options: &MarkdownOptions) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    options.hash(&mut hasher);
+    hasher.finish()
+}
 
-    linkify_file_paths_ast(&arena, root);
+/// Approximate bytes held by cached render results and diagrams
+pub fn render_cache_bytes() -> usize {
+    let rendered = RENDER_CACHE.lock().map_or(0, |cache| {
+        cache
+            .iter()
+            .map(|(_, result)| {
+                result.html.capacity()
+                    + result.line_map.capacity() * std::mem::size_of::<usize>()
+                    + result
+                        .heading_ids
+                        .iter()
+                        .map(String::capacity)
+                        .sum::<usize>()
+            })
+            .sum::<usize>()
+    });
+    rendered + diagrams::cache_bytes()
+}
 
-    let mut html = String::new();
-    format_html_with_plugins(root, &comrak_options, &mut html, &Plugins::default())
-        .map_err(|e| anyhow!("Failed to render markdown: {}", e))?;
+/// Drops cached render results and diagrams. Returns the approximate bytes freed.
+pub fn clear_render_cache() -> usize {
+    let freed = render_cache_bytes();
+    diagrams::clear_cache();
+    if let Ok(mut cache) = RENDER_CACHE.lock() {
+        cache.clear();
+        cache.shrink_to_fit();
+    }
+    freed
+}
+
+/// Renders markdown to HTML with line number tracking and document metrics.
+/// Unchanged content rendered with the same options is served from an LRU cache.
+pub fn render_markdown(content: &str, options: MarkdownOptions) -> Result<RenderResult> {
+    let key = render_cache_key(content, &options);
+    if let Ok(mut cache) = RENDER_CACHE.lock()
+        && let Some(index) = cache.iter().position(|(k, _)| *k == key)
+        && let Some(entry) = cache.remove(index)
+    {
+        let result = entry.1.clone();
+        cache.push_back(entry);
+        if options.preview {
+            diagrams::relink(&result.diagrams);
+        }
+        return Ok(result);
+    }
 
-    let (line_map, line_count, word_count, char_count, widest_column) =
+    let result = render_uncached(content, &options)?;
+    // Rendered again once the diagrams are ready, which must not hit a copy without them
+    if !result.diagrams_pending
+        && let Ok(mut cache) = RENDER_CACHE.lock()
+    {
+        if cache.len() >= RENDER_CACHE_CAPACITY {
+            cache.pop_front();
+        }
+        cache.push_back((key, result.clone()));
+    }
+    Ok(result)
+}
+
+fn render_uncached(content: &str, options: &MarkdownOptions) -> Result<RenderResult> {
+    let fragment = render_fragment(content, options)?;
+    let (line_map, line_count, mut word_count, char_count, widest_column) =
         build_line_map_and_metrics(content);
+    if options.word_count_mode != WordCountMode::All {
+        word_count = text_metrics::count_words(content, options.word_count_mode, options.flavor);
+    }
+    let readability = text_metrics::readability(content, options.flavor, word_count);
 
     Ok(RenderResult {
-        html,
+        html: fragment.html,
         line_map,
         line_count,
         word_count,
         char_count,
         widest_column,
+        readability,
+        sanitization: fragment.sanitization,
+        heading_ids: heading_ids(&fragment.heading_texts, &options.heading_id_prefix),
+        local_images: fragment.local_images,
+        document_css: document_css::from_front_matter(content),
+        highlight_css: options.preview.then(preview_assets::highlight_css_url),
+        diagrams_pending: fragment.diagrams_pending,
+        diagrams: fragment.diagrams,
     })
 }
 
-// Matches file paths in plain text:
+/// Parses and renders `content` to HTML with the sanitization and link passes applied.
+pub fn render_fragment(content: &str, options: &MarkdownOptions) -> Result<RenderedFragment> {
+    let mut comrak_options = options.flavor.to_comrak_options_with(options.extensions);
+    if options.hide_front_matter
+        && let Some(fm) = front_matter::split(content)
+    {
+        comrak_options.extension.front_matter_delimiter = Some(fm.format.delimiter().to_string());
+    }
+    comrak_options.extension.header_ids = Some(options.heading_id_prefix.clone());
+    comrak_options.render.r#unsafe = options.html_policy != HtmlPolicy::Off;
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, content, &comrak_options);
+
+    let mut sanitization = SanitizationReport::default();
+    let has_raw_html = security::count_raw_html(root, options.html_policy, &mut sanitization);
+    security::apply_remote_policy(&arena, root, &options.remote_policy, &mut sanitization);
+    let path_regex = path_link_regex(&options.path_links);
+    let linkified = path_regex.is_some();
+    if let Some(path_regex) = path_regex {
+        linkify_file_paths_ast(&arena, root, &path_regex);
+    }
+    // The preview doesn't wait for diagram tools; it shows the code until they finish
+    let (rendered_diagrams, diagrams_pending) = match &options.diagrams {
+        Some(settings) if settings.enabled => {
+            diagrams::render_fences(root, settings, &options.remote_policy, options.preview)
+        },
+        _ => Default::default(),
+    };
+    let local_images = if options.preview {
+        image_url_rewriter(root, options.base_path.as_deref())
+    } else {
+        Vec::new()
+    };
+    let heading_texts = root
+        .descendants()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::Heading(_)))
+        .map(sections::plain_text)
+        .collect();
+
+    // Highlighting classes are only styled by the preview's stylesheet
+    let mut plugins = Plugins::default();
+    if options.preview {
+        plugins.render.codefence_syntax_highlighter = Some(&highlight::HIGHLIGHTER);
+    }
+    let mut html = String::new();
+    format_html_with_plugins(root, &comrak_options, &mut html, &plugins)
+        .map_err(|e| anyhow!("Failed to render markdown: {}", e))?;
+    if options.extensions.math {
+        html = add_math_classes(&html);
+    }
+    // The renderer's own output is trusted, so documents without raw HTML skip the pass
+    if options.html_policy == HtmlPolicy::Sanitize && has_raw_html {
+        let ids = heading_ids(&heading_texts, &options.heading_id_prefix);
+        html = security::sanitize_html(&html, &ids);
+    }
+    // Styled after sanitizing, which allows no inline styles
+    if linkified {
+        html = html.replace(PATH_LINK_CLASS, PATH_LINK_STYLED);
+    }
+    // Diagrams go in after sanitizing, which would strip their `data:` URLs
+    if !rendered_diagrams.is_empty() {
+        html = diagrams::insert_rendered(&html, &rendered_diagrams, options.preview);
+    }
+
+    Ok(RenderedFragment {
+        html,
+        sanitization,
+        heading_texts,
+        local_images,
+        diagrams_pending,
+        diagrams: rendered_diagrams,
+    })
+}
+
+/// Renders a short snippet, such as a link title or footnote, for a tooltip. A lone
+/// paragraph loses its `<p>` wrapper. No line map, heading ids, path links or local images;
+/// raw HTML is always sanitized.
+pub fn render_inline(snippet: &str, flavor: MarkdownFlavor) -> Result<String> {
+    let mut comrak_options = flavor.to_comrak_options();
+    comrak_options.render.sourcepos = false;
+    comrak_options.extension.header_ids = None;
+    comrak_options.render.r#unsafe = true;
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, snippet, &comrak_options);
+    let mut report = SanitizationReport::default();
+    let has_raw_html = security::count_raw_html(root, HtmlPolicy::Sanitize, &mut report);
+
+    let mut html = String::new();
+    let lone_paragraph = root
+        .first_child()
+        .filter(|child| child.next_sibling().is_none())
+        .filter(|child| matches!(child.data.borrow().value, NodeValue::Paragraph));
+    match lone_paragraph {
+        Some(paragraph) => {
+            for child in paragraph.children() {
+                format_html_with_plugins(child, &comrak_options, &mut html, &Plugins::default())
+                    .map_err(|e| anyhow!("Failed to render markdown: {}", e))?;
+            }
+        },
+        None => format_html_with_plugins(root, &comrak_options, &mut html, &Plugins::default())
+            .map_err(|e| anyhow!("Failed to render markdown: {}", e))?,
+    }
+    if has_raw_html {
+        html = security::sanitize_html(&html, &[]);
+    }
+    Ok(html.trim_end().to_string())
+}
+
+/// The ids comrak gives headings; its anchorizer follows the same GitHub rules as
+/// `sections::slugify`.
+pub fn heading_ids(texts: &[String], prefix: &str) -> Vec<String> {
+    sections::unique_slugs(texts.iter().map(String::as_str))
+        .into_iter()
+        .map(|slug| format!("{}{}", prefix, slug))
+        .collect()
+}
+
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            },
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            },
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Resolves a local image reference against `base_dir`; `None` for URLs with a scheme
+/// (other than `file:`), protocol-relative URLs and fragments.
+pub fn resolve_local_image(url: &str, base_dir: &Path) -> Option<PathBuf> {
+    let url = url.trim();
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    if path.is_empty() || path.starts_with("//") {
+        return None;
+    }
+    let path = match path.split_once(':') {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("file") => {
+            let rest = rest.strip_prefix("//").unwrap_or(rest);
+            // file:///C:/x keeps a slash before the drive letter
+            match rest.strip_prefix('/') {
+                Some(drive) if drive.get(1..2) == Some(":") => drive.to_string(),
+                _ => rest.to_string(),
+            }
+        },
+        // A single letter is a Windows drive, not a scheme
+        Some((scheme, _)) if scheme.len() > 1 => return None,
+        _ => path.to_string(),
+    };
+    let path = PathBuf::from(percent_decode(&path));
+    let absolute = if path.is_absolute() || path.has_root() {
+        path
+    } else {
+        base_dir.join(path)
+    };
+    Some(absolute.clean())
+}
+
+static IMG_SRC_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)(<img\b[^>]*?\bsrc\s*=\s*)(?:"([^"]*)"|'([^']*)')"#)
+        .expect("Invalid IMG_SRC_RE")
+});
+
+/// Rewrites local image paths, in markdown images and raw `<img>` tags, into `preview`
+/// protocol URLs so the preview can load them. `base_path` is the document's path; relative
+/// paths resolve against its folder, and without it only absolute paths are rewritten.
+/// Returns the files referenced.
+fn image_url_rewriter<'a>(root: &'a AstNode<'a>, base_path: Option<&str>) -> Vec<String> {
+    let base_dir = base_path
+        .and_then(|p| Path::new(p).parent())
+        .unwrap_or(Path::new(""));
+    let resolve = |url: &str| {
+        resolve_local_image(url, base_dir).filter(|path| base_path.is_some() || path.has_root())
+    };
+    let mut local_images = Vec::new();
+
+    for node in root.descendants() {
+        let mut data = node.data.borrow_mut();
+        match &mut data.value {
+            NodeValue::Image(link) => {
+                if let Some(path) = resolve(&link.url) {
+                    link.url = preview_assets::file_url(&path).into();
+                    local_images.push(path.to_string_lossy().into_owned());
+                }
+            },
+            NodeValue::HtmlInline(html)
+            | NodeValue::HtmlBlock(NodeHtmlBlock { literal: html, .. })
+                if IMG_SRC_RE.is_match(html) =>
+            {
+                let rewritten = IMG_SRC_RE.replace_all(html, |caps: &regex::Captures| {
+                    let src = caps.get(2).or(caps.get(3)).map_or("", |m| m.as_str());
+                    match resolve(src) {
+                        Some(path) => {
+                            local_images.push(path.to_string_lossy().into_owned());
+                            format!("{}\"{}\"", &caps[1], preview_assets::file_url(&path))
+                        },
+                        None => caps[0].to_string(),
+                    }
+                });
+                *html = rewritten.into_owned();
+            },
+            _ => {},
+        }
+    }
+    local_images
+}
+
+// comrak marks math with data-math-style; ```math blocks also carry language-math
+static MATH_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"<(span|code)( class="language-math")? data-math-style="(inline|display)""#)
+        .expect("Invalid MATH_TAG_REGEX pattern")
+});
+
+/// Adds the `math math-inline` / `math math-display` classes KaTeX auto-render looks for.
+fn add_math_classes(html: &str) -> String {
+    MATH_TAG_REGEX
+        .replace_all(html, |caps: &regex::Captures| {
+            let language = if caps.get(2).is_some() {
+                "language-math "
+            } else {
+                ""
+            };
+            format!(
+                r#"<{} class="{}math math-{}" data-math-style="{}""#,
+                &caps[1], language, &caps[3], &caps[3]
+            )
+        })
+        .into_owned()
+}
+
+const PATH_LINK_CLASS: &str = r#" class="file-path-link">"#;
+const PATH_LINK_STYLED: &str = r#" class="file-path-link" style="color: var(--color-accent-filepath); text-decoration: underline; cursor: pointer;">"#;
+
+// Matches file paths in plain text, at the start of a text node or after whitespace or an
+// opening bracket:
 // - Windows absolute: C:/ or C:\
 // - Unix absolute: /some/dir/file (requires at least one slash-separated segment)
 // - Relative: ./ or ../
 // - Home directory: ~/
 static PATH_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(
-        r#"(?:^|\s)([A-Za-z]:[/\\][^\s<>"'|?*`]*|(?:\./|\.\./|~/)[^\s<>"'|?*`]+|/(?:[^/\s<>"'|?*`]+/)+[^/\s<>"'|?*`]+)"#,
+        r#"(?:^|[\s(\[])([A-Za-z]:[/\\][^\s<>"'|?*`]*|(?:\./|\.\./|~/)[^\s<>"'|?*`]+|/(?:[^/\s<>"'|?*`]+/)+[^/\s<>"'|?*`]+)"#,
     )
     .expect("Invalid PATH_REGEX pattern")
 });
 
+static WINDOWS_PATH_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?:^|[\s(\[])([A-Za-z]:[/\\][^\s<>"'|?*`]*)"#)
+        .expect("Invalid WINDOWS_PATH_REGEX pattern")
+});
+
+// Cap on the compiled size of a custom pattern, so a pathological one can't stall renders
+const CUSTOM_PATH_REGEX_LIMIT: usize = 1 << 20;
+
+/// The last custom pattern compiled, or its error
+static CUSTOM_PATH_REGEX: LazyLock<Mutex<Option<(String, Option<Regex>)>>> =
+    LazyLock::new(|| Mutex::new(None));
+
+fn path_link_regex(settings: &PathLinkSettings) -> Option<Regex> {
+    match settings.mode {
+        PathLinkMode::All => Some(PATH_REGEX.clone()),
+        PathLinkMode::Off => None,
+        PathLinkMode::Windows => Some(WINDOWS_PATH_REGEX.clone()),
+        PathLinkMode::Custom => {
+            let pattern = settings.pattern.trim();
+            if pattern.is_empty() {
+                return None;
+            }
+            let mut cached = CUSTOM_PATH_REGEX.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((cached_pattern, regex)) = cached.as_ref()
+                && cached_pattern == pattern
+            {
+                return regex.clone();
+            }
+            let regex = regex::RegexBuilder::new(pattern)
+                .size_limit(CUSTOM_PATH_REGEX_LIMIT)
+                .build()
+                .map_err(|e| log::warn!("Ignoring invalid path link pattern: {}", e))
+                .ok();
+            *cached = Some((pattern.to_string(), regex.clone()));
+            regex
+        },
+    }
+}
+
 /// Returns true if `node` is inside a code, pre, or link context where
 /// path linkification should be suppressed.
 fn is_in_code_or_link<'a>(node: &'a AstNode<'a>) -> bool {
@@ -76,9 +525,35 @@ fn is_in_code_or_link<'a>(node: &'a AstNode<'a>) -> bool {
     })
 }
 
+/// Drops sentence punctuation after a path, and closing brackets it doesn't open, so
+/// "see (./notes.md)." links `./notes.md`
+fn trim_path_end(path: &str) -> &str {
+    let mut path = path;
+    loop {
+        let trimmed = path.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        let trimmed = match trimmed.chars().last() {
+            Some(close @ (')' | ']')) => {
+                let open = if close == ')' { '(' } else { '[' };
+                let opened = trimmed.matches(open).count();
+                let closed = trimmed.matches(close).count();
+                if closed > opened {
+                    &trimmed[..trimmed.len() - 1]
+                } else {
+                    trimmed
+                }
+            },
+            _ => trimmed,
+        };
+        if trimmed.len() == path.len() {
+            return path;
+        }
+        path = trimmed;
+    }
+}
+
 /// Walks the AST and replaces file-path text segments with HtmlInline link nodes,
 /// operating purely on text nodes so existing HTML attributes are never touched.
-fn linkify_file_paths_ast<'a>(arena: &'a Arena<'a>, root: &'a AstNode<'a>) {
+fn linkify_file_paths_ast<'a>(arena: &'a Arena<'a>, root: &'a AstNode<'a>, path_regex: &Regex) {
     // Collect text nodes first to avoid mutating while iterating descendants
     let text_nodes: Vec<&AstNode<'_>> = root
         .descendants()
@@ -93,7 +568,7 @@ fn linkify_file_paths_ast<'a>(arena: &'a Arena<'a>, root: &'a AstNode<'a>) {
             _ => continue,
         };
 
-        if !PATH_REGEX.is_match(&text) {
+        if !path_regex.is_match(&text) {
             continue;
         }
 
@@ -101,9 +576,14 @@ fn linkify_file_paths_ast<'a>(arena: &'a Arena<'a>, root: &'a AstNode<'a>) {
         let mut last_end = 0;
         let mut new_nodes: Vec<&AstNode<'_>> = Vec::new();
 
-        for cap in PATH_REGEX.captures_iter(&text) {
+        for cap in path_regex.captures_iter(&text) {
             let full = cap.get(0).expect("group 0");
-            let path_match = cap.get(1).expect("group 1");
+            // Custom patterns may have no group, or one that didn't take part in the match
+            let path_match = cap.get(1).unwrap_or(full);
+            let path = trim_path_end(path_match.as_str());
+            if path.is_empty() {
+                continue;
+            }
 
             // Leading whitespace / non-path prefix before the captured group
             let before = &text[last_end..path_match.start()];
@@ -114,14 +594,13 @@ fn linkify_file_paths_ast<'a>(arena: &'a Arena<'a>, root: &'a AstNode<'a>) {
                 new_nodes.push(n);
             }
 
-            let path = path_match.as_str();
-            let link_html = format!(
-                r#"<a href="{path}" class="file-path-link" style="color: var(--color-accent-filepath); text-decoration: underline; cursor: pointer;">{path}</a>"#
-            );
+            let path_end = path_match.start() + path.len();
+            let path = escape_html(path);
+            let link_html = format!(r#"<a href="{path}"{PATH_LINK_CLASS}{path}</a>"#);
             let n = arena.alloc(AstNode::from(NodeValue::HtmlInline(link_html)));
             new_nodes.push(n);
 
-            last_end = full.end();
+            last_end = path_end;
         }
 
         // Trailing text after the last match
@@ -144,45 +623,3 @@ fn linkify_file_paths_ast<'a>(arena: &'a Arena<'a>, root: &'a AstNode<'a>) {
         node.detach();
     }
 }
-
-fn build_line_map_and_metrics(content: &str) -> (Vec<usize>, usize, usize, usize, usize) {
-    if content.is_empty() {
-        return (vec![0], 0, 0, 0, 0);
-    }
-
-    let mut line_map = Vec::new();
-    let mut offset = 0;
-    let mut char_count = 0;
-    let mut widest_column = 0;
-    let mut current_column = 0;
-
-    line_map.push(0);
-    for c in content.chars() {
-        char_count += 1;
-        if c == '\n' {
-            line_map.push(offset + 1);
-            if current_column > widest_column {
-                widest_column = current_column;
-            }
-            current_column = 0;
-        } else {
-            current_column += 1;
-        }
-        offset += c.len_utf8();
-    }
-
-    if current_column > widest_column {
-        widest_column = current_column;
-    }
-
-    let line_count = line_map.len();
-    let word_count = content.unicode_words().count();
-
-    (line_map, line_count, word_count, char_count, widest_column)
-}
-
-pub fn calculate_text_metrics(content: &str) -> (usize, usize, usize, usize) {
-    let (_, line_count, word_count, char_count, widest_column) =
-        build_line_map_and_metrics(content);
-    (line_count, word_count, char_count, widest_column)
-}
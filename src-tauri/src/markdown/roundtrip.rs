@@ -0,0 +1,267 @@
+use crate::markdown::compare::{self, RowKind};
+use crate::markdown::formatter::{self, FormatterOptions};
+use crate::markdown::merge::lcs_matches;
+use anyhow::Result;
+use comrak::nodes::{AstNode, NodeValue};
+use comrak::{Arena, parse_document};
+use serde::Serialize;
+
+/// Enough to show what went wrong without flooding a bug report
+const MAX_DIVERGENCES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DivergenceKind {
+    /// Formatting changed the text of a block
+    Content,
+    /// Formatting the formatted document changed it again
+    Idempotence,
+}
+
+/// One place where a round trip differs. For content, "before" is the original and
+/// "after" the formatted document; for idempotence they are the first and second
+/// formatting passes. Line numbers are 1-based.
+#[derive(Debug, Clone, Serialize)]
+pub struct Divergence {
+    pub kind: DivergenceKind,
+    pub before_line: Option<usize>,
+    pub after_line: Option<usize>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RoundtripReport {
+    pub content_preserved: bool,
+    pub idempotent: bool,
+    /// The first divergences found, content first
+    pub divergences: Vec<Divergence>,
+}
+
+/// A block's text with its starting line
+#[derive(Debug)]
+struct Block {
+    line: usize,
+    text: String,
+}
+
+/// Formats `content` twice and checks that the first pass keeps the text of every block
+/// and that the second pass changes nothing. Block text ignores markup and whitespace,
+/// so reflowed lists or changed emphasis markers are not reported.
+pub fn verify(content: &str, options: &FormatterOptions) -> Result<RoundtripReport> {
+    let formatted = formatter::format_markdown(content, options)?;
+    let reformatted = formatter::format_markdown(&formatted, options)?;
+
+    let mut divergences = content_divergences(content, &formatted, options);
+    let content_preserved = divergences.is_empty();
+
+    let comparison = compare::compare(&formatted, &reformatted);
+    let idempotent = comparison.changes.is_empty();
+    let formatted_lines: Vec<&str> = formatted.lines().collect();
+    let reformatted_lines: Vec<&str> = reformatted.lines().collect();
+    let line_text = |lines: &[&str], line: Option<usize>| {
+        line.and_then(|n| lines.get(n - 1)).map(|s| s.to_string())
+    };
+    for change in &comparison.changes {
+        let row = &comparison.rows[change.start_row];
+        divergences.push(Divergence {
+            kind: DivergenceKind::Idempotence,
+            before_line: row.left_line,
+            after_line: row.right_line,
+            before: line_text(&formatted_lines, row.left_line),
+            after: line_text(&reformatted_lines, row.right_line),
+        });
+    }
+    divergences.truncate(MAX_DIVERGENCES);
+
+    Ok(RoundtripReport {
+        content_preserved,
+        idempotent,
+        divergences,
+    })
+}
+
+/// Blocks whose text differs between the original and the formatted document. Blocks are
+/// aligned by longest common subsequence; unmatched blocks between two matches pair up.
+fn content_divergences(
+    original: &str,
+    formatted: &str,
+    options: &FormatterOptions,
+) -> Vec<Divergence> {
+    let (before_arena, after_arena) = (Arena::new(), Arena::new());
+    let comrak_options = options.flavor.to_comrak_options();
+    let before = blocks(parse_document(&before_arena, original, &comrak_options));
+    let after = blocks(parse_document(&after_arena, formatted, &comrak_options));
+
+    let before_text: Vec<&str> = before.iter().map(|b| b.text.as_str()).collect();
+    let after_text: Vec<&str> = after.iter().map(|b| b.text.as_str()).collect();
+    let matches = lcs_matches(&before_text, &after_text);
+
+    let mut divergences = Vec::new();
+    let mut push_gap = |before: &[Block], after: &[Block]| {
+        for k in 0..before.len().max(after.len()) {
+            let (b, a) = (before.get(k), after.get(k));
+            divergences.push(Divergence {
+                kind: DivergenceKind::Content,
+                before_line: b.map(|b| b.line),
+                after_line: a.map(|a| a.line),
+                before: b.map(|b| b.text.clone()),
+                after: a.map(|a| a.text.clone()),
+            });
+        }
+    };
+    let (mut b, mut a) = (0, 0);
+    for (i, matched) in matches.iter().enumerate() {
+        let Some(j) = *matched else {
+            continue;
+        };
+        push_gap(&before[b..i], &after[a..j]);
+        (b, a) = (i + 1, j + 1);
+    }
+    push_gap(&before[b..], &after[a..]);
+    divergences
+}
+
+/// Text of every leaf block in document order: paragraphs, headings, table cells, code
+/// and HTML blocks, and front matter
+fn blocks<'a>(root: &'a AstNode<'a>) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        let text = match &data.value {
+            NodeValue::Paragraph | NodeValue::Heading(_) | NodeValue::TableCell => {
+                let mut text = String::new();
+                inline_text(node, &mut text);
+                text.split_whitespace().collect::<Vec<_>>().join(" ")
+            },
+            NodeValue::CodeBlock(code) => {
+                format!("{}\n{}", code.info.trim(), code.literal.trim_end())
+            },
+            NodeValue::HtmlBlock(html) => html.literal.trim_end().to_string(),
+            NodeValue::FrontMatter(raw) => raw.trim().to_string(),
+            _ => continue,
+        };
+        // Formatting may add or drop an empty paragraph or cell; only lost text matters
+        if !text.is_empty() {
+            blocks.push(Block {
+                line: data.sourcepos.start.line,
+                text,
+            });
+        }
+    }
+    blocks
+}
+
+/// Visible text plus link and image destinations, which formatting must also keep
+fn inline_text<'a>(node: &'a AstNode<'a>, out: &mut String) {
+    for child in node.children() {
+        match &child.data.borrow().value {
+            NodeValue::Text(t) => out.push_str(t),
+            NodeValue::Code(code) => out.push_str(&code.literal),
+            NodeValue::HtmlInline(html) => out.push_str(html),
+            NodeValue::Math(math) => out.push_str(&math.literal),
+            NodeValue::SoftBreak | NodeValue::LineBreak => out.push(' '),
+            NodeValue::Link(link) | NodeValue::Image(link) => {
+                inline_text(child, out);
+                out.push_str(&format!(" <{}>", link.url));
+            },
+            _ => inline_text(child, out),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Documents covering what the formatter rewrites most: tables, lists, fences and
+    /// front matter
+    const FIXTURES: &[(&str, &str)] = &[
+        (
+            "table",
+            "| Name | Qty | Price |\n|:-----|:---:|------:|\n| Apple | 3 | 1.20 |\n| Pear with a long name | 12 | 0.5 |\n",
+        ),
+        (
+            "table with inline markup",
+            "Before the table.\n\n| Key | Value |\n| --- | --- |\n| `code` | **bold** and [link](https://example.com) |\n| pipe | a \\| b |\n\nAfter the table.\n",
+        ),
+        (
+            "bullet lists",
+            "* one\n* two\n  + nested\n  + nested again\n* three\n\n- [ ] open task\n- [x] done task\n",
+        ),
+        (
+            "ordered lists",
+            "1. first\n2. second\n   1. inner\n   2. inner two\n3. third\n\n5) five\n6) six\n",
+        ),
+        (
+            "loose list with paragraphs",
+            "- item one\n\n  continued paragraph\n\n- item two\n\n  > quoted inside\n",
+        ),
+        (
+            "fences",
+            "```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n\n~~~\ntilde fence\n~~~\n\n````markdown\n```\nnested fence\n```\n````\n",
+        ),
+        (
+            "fence in list",
+            "1. Run this:\n\n   ```sh\n   cargo build\n   ```\n\n2. Then this.\n",
+        ),
+        (
+            "yaml front matter",
+            "---\ntitle: Notes\ntags: [a, b]\n---\n\n# Heading\n\nSome *emphasis* and __strong__ text.\n",
+        ),
+        (
+            "toml front matter",
+            "+++\ntitle = \"Notes\"\n+++\n\nSetext heading\n==============\n\nBody text.\n",
+        ),
+        (
+            "mixed",
+            "# Title\n\n\n\nParagraph with a  \nhard break.\n\n> [!NOTE]\n> An alert.\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n* list\n* items\n\n```\ncode\n```\n",
+        ),
+    ];
+
+    fn option_sets() -> Vec<(&'static str, FormatterOptions)> {
+        vec![
+            ("default", FormatterOptions::default()),
+            (
+                "alternate markers",
+                FormatterOptions {
+                    bullet_char: "*".to_string(),
+                    emphasis_char: "_".to_string(),
+                    code_block_fence: "~~~".to_string(),
+                    table_alignment: false,
+                    list_indent: 4,
+                    ..FormatterOptions::default()
+                },
+            ),
+        ]
+    }
+
+    #[test]
+    fn fixtures_round_trip_without_divergences() {
+        for (options_name, options) in option_sets() {
+            for (name, content) in FIXTURES {
+                let report = verify(content, &options).unwrap_or_else(|e| {
+                    panic!("{} ({}): formatting failed: {}", name, options_name, e)
+                });
+                assert!(
+                    report.divergences.is_empty(),
+                    "{} ({}): {:#?}",
+                    name,
+                    options_name,
+                    report.divergences
+                );
+                assert!(report.content_preserved && report.idempotent);
+            }
+        }
+    }
+
+    #[test]
+    fn lost_text_is_reported() {
+        let original = "# Title\n\nFirst paragraph.\n\nSecond paragraph.\n";
+        let formatted = "# Title\n\nFirst paragraph.\n";
+        let divergences = content_divergences(original, formatted, &FormatterOptions::default());
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].before.as_deref(), Some("Second paragraph."));
+        assert_eq!(divergences[0].after, None);
+    }
+}
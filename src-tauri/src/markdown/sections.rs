@@ -0,0 +1,597 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::front_matter;
+use crate::markdown::text_metrics::utf16_to_byte_offset;
+use comrak::nodes::{AstNode, NodeValue};
+use comrak::{Arena, parse_document};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+static ANCHOR_LINK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\]\(|\]:\s*)#([^)\s]+)").expect("Invalid ANCHOR_LINK_RE"));
+
+#[derive(Debug, Clone)]
+pub struct Heading {
+    /// 1-based line of the heading text
+    pub line: usize,
+    /// 1-based last line; the underline for setext headings
+    pub end_line: usize,
+    /// 1-based byte column where the heading starts (after any container markers)
+    pub column: usize,
+    pub level: u8,
+    pub text: String,
+    pub setext: bool,
+}
+
+/// Headings in document order. Front matter is blanked out before parsing so a closing
+/// `---` is never read as a setext underline, while line numbers stay aligned.
+pub fn headings(content: &str, flavor: MarkdownFlavor) -> Vec<Heading> {
+    let fm_lines = front_matter::line_count(content);
+    let masked;
+    let source = if fm_lines > 0 {
+        masked = content
+            .split('\n')
+            .enumerate()
+            .map(|(i, line)| if i < fm_lines { "" } else { line })
+            .collect::<Vec<_>>()
+            .join("\n");
+        masked.as_str()
+    } else {
+        content
+    };
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, source, &flavor.to_comrak_options());
+
+    root.descendants()
+        .filter_map(|node| {
+            let data = node.data.borrow();
+            let NodeValue::Heading(heading) = &data.value else {
+                return None;
+            };
+            Some(Heading {
+                line: data.sourcepos.start.line,
+                end_line: data.sourcepos.end.line,
+                column: data.sourcepos.start.column,
+                level: heading.level,
+                text: plain_text(node),
+                setext: heading.setext,
+            })
+        })
+        .collect()
+}
+
+/// What `node_text` keeps besides text and inline code
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextOptions {
+    /// Image alt text, as a screen reader announces it
+    pub image_alt: bool,
+    /// Inline and display math source
+    pub math: bool,
+    /// Footnote references as `[name]`
+    pub footnote_refs: bool,
+    /// Hard line breaks as newlines rather than spaces
+    pub line_breaks: bool,
+}
+
+/// The text of `node`'s inline content with markup dropped. Raw HTML never counts.
+pub fn node_text<'a>(node: &'a AstNode<'a>, options: TextOptions) -> String {
+    let mut text = String::new();
+    collect_text(node, options, &mut text);
+    text
+}
+
+fn collect_text<'a>(node: &'a AstNode<'a>, options: TextOptions, out: &mut String) {
+    for child in node.children() {
+        match &child.data.borrow().value {
+            NodeValue::Text(t) => out.push_str(t),
+            NodeValue::Code(code) => out.push_str(&code.literal),
+            NodeValue::Math(math) if options.math => out.push_str(&math.literal),
+            NodeValue::SoftBreak => out.push(' '),
+            NodeValue::LineBreak => out.push(if options.line_breaks { '\n' } else { ' ' }),
+            NodeValue::FootnoteReference(reference) if options.footnote_refs => {
+                out.push_str(&format!("[{}]", reference.name));
+            },
+            NodeValue::Image(_) if !options.image_alt => {},
+            NodeValue::HtmlInline(_) | NodeValue::Math(_) | NodeValue::FootnoteReference(_) => {},
+            _ => collect_text(child, options, out),
+        }
+    }
+}
+
+/// Heading text as slugged for its anchor: inline code and image alt text kept, markup
+/// dropped.
+pub fn plain_text<'a>(node: &'a AstNode<'a>) -> String {
+    let options = TextOptions {
+        image_alt: true,
+        ..Default::default()
+    };
+    node_text(node, options).trim().to_string()
+}
+
+/// GitHub-style anchor: lowercase, punctuation dropped, spaces become hyphens.
+pub fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Anchors for each heading, with `-1`, `-2`... appended to repeats as GitHub does.
+pub fn anchors(headings: &[Heading]) -> Vec<String> {
+    unique_slugs(headings.iter().map(|h| h.text.as_str()))
+}
+
+/// Slugs for heading texts in document order, deduplicated like `anchors`.
+pub fn unique_slugs<'a>(texts: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    texts
+        .into_iter()
+        .map(|text| {
+            let slug = slugify(text);
+            let count = seen.entry(slug.clone()).or_insert(0);
+            let anchor = if *count == 0 {
+                slug.clone()
+            } else {
+                format!("{}-{}", slug, count)
+            };
+            *count += 1;
+            anchor
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutlineNode {
+    pub level: u8,
+    pub text: String,
+    /// Anchor the rendered heading links to, deduplicated like `anchors`
+    pub slug: String,
+    /// 1-based source line
+    pub line: usize,
+    pub children: Vec<OutlineNode>,
+}
+
+/// Headings nested under the nearest preceding heading of a higher rank. Skipped levels
+/// (an H3 straight after an H1) nest one step deep.
+pub fn outline(content: &str, flavor: MarkdownFlavor) -> Vec<OutlineNode> {
+    let headings = headings(content, flavor);
+    let slugs = anchors(&headings);
+
+    let mut roots: Vec<OutlineNode> = Vec::new();
+    // Open ancestors, innermost last; each is attached to its parent when it closes
+    let mut stack: Vec<OutlineNode> = Vec::new();
+
+    for (heading, slug) in headings.into_iter().zip(slugs) {
+        while stack.last().is_some_and(|open| open.level >= heading.level) {
+            close_outline_node(&mut stack, &mut roots);
+        }
+        stack.push(OutlineNode {
+            level: heading.level,
+            text: heading.text,
+            slug,
+            line: heading.line,
+            children: Vec::new(),
+        });
+    }
+    while !stack.is_empty() {
+        close_outline_node(&mut stack, &mut roots);
+    }
+
+    roots
+}
+
+fn close_outline_node(stack: &mut Vec<OutlineNode>, roots: &mut Vec<OutlineNode>) {
+    if let Some(node) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BreadcrumbItem {
+    pub level: u8,
+    pub text: String,
+    pub slug: String,
+    /// 1-based line of the heading
+    pub line: usize,
+    /// UTF-16 offset of the start of the heading line, for moving the editor cursor
+    pub offset: usize,
+    /// 1-based last line of the section the heading opens
+    pub end_line: usize,
+}
+
+/// Headings whose sections contain `cursor_offset` (UTF-16), outermost first. A cursor on
+/// a heading line counts as inside that heading's section.
+pub fn breadcrumb(
+    content: &str,
+    cursor_offset: usize,
+    flavor: MarkdownFlavor,
+) -> Vec<BreadcrumbItem> {
+    let headings = headings(content, flavor);
+    let slugs = anchors(&headings);
+    let cursor_byte = utf16_to_byte_offset(content, cursor_offset);
+    let cursor_line = content[..cursor_byte].matches('\n').count() + 1;
+    let line_count = content.split('\n').count();
+
+    // Indices of the open ancestors at the cursor, innermost last
+    let mut chain: Vec<usize> = Vec::new();
+    for (idx, heading) in headings.iter().enumerate() {
+        if heading.line > cursor_line {
+            break;
+        }
+        while chain
+            .last()
+            .is_some_and(|&open| headings[open].level >= heading.level)
+        {
+            chain.pop();
+        }
+        chain.push(idx);
+    }
+
+    // UTF-16 offset of each line start up to the cursor
+    let mut line_starts = Vec::with_capacity(cursor_line);
+    let mut units = 0;
+    for line in content.split('\n').take(cursor_line) {
+        line_starts.push(units);
+        units += line.encode_utf16().count() + 1;
+    }
+
+    chain
+        .into_iter()
+        .map(|idx| {
+            let heading = &headings[idx];
+            let (_, end) = section_range(&headings, idx, line_count);
+            BreadcrumbItem {
+                level: heading.level,
+                text: heading.text.clone(),
+                slug: slugs[idx].clone(),
+                line: heading.line,
+                offset: line_starts[heading.line - 1],
+                end_line: end,
+            }
+        })
+        .collect()
+}
+
+/// 0-based line range `[start, end)` of the section opened by `headings[idx]`: the heading
+/// and everything up to the next heading of the same or higher rank.
+pub fn section_range(headings: &[Heading], idx: usize, line_count: usize) -> (usize, usize) {
+    let heading = &headings[idx];
+    let start = heading.line - 1;
+    let end = headings[idx + 1..]
+        .iter()
+        .find(|h| h.level <= heading.level)
+        .map(|h| h.line - 1)
+        .unwrap_or(line_count);
+    (start, end)
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeadingShift {
+    pub content: String,
+    pub shifted: usize,
+    /// One entry per heading that would have gone past H1 or H6 and was clamped
+    pub warnings: Vec<String>,
+}
+
+/// Shifts headings by `delta` levels, clamped to H1..H6. With `lines` (1-based, inclusive)
+/// only headings starting in that range move. Shifted setext headings are rewritten as ATX
+/// since setext only covers two levels.
+pub fn shift_headings(
+    content: &str,
+    delta: i8,
+    lines_range: Option<(usize, usize)>,
+    flavor: MarkdownFlavor,
+) -> HeadingShift {
+    let all = headings(content, flavor);
+    let mut lines: Vec<Option<String>> = content.split('\n').map(|l| Some(l.to_string())).collect();
+    let mut shifted = 0;
+    let mut warnings = Vec::new();
+
+    for heading in &all {
+        if let Some((from, to)) = lines_range
+            && !(from..=to).contains(&heading.line)
+        {
+            continue;
+        }
+
+        let wanted = heading.level as i16 + delta as i16;
+        let level = wanted.clamp(1, 6) as u8;
+        if wanted != level as i16 {
+            warnings.push(format!(
+                "Heading on line {} would be H{}; clamped to H{}",
+                heading.line, wanted, level
+            ));
+        }
+        if level == heading.level {
+            continue;
+        }
+
+        let idx = heading.line - 1;
+        let col = heading.column - 1;
+        let Some(line) = lines[idx].take() else {
+            continue;
+        };
+        let (prefix, rest) = line.split_at(col.min(line.len()));
+        let hashes = "#".repeat(level as usize);
+
+        if heading.setext {
+            // Fold the text lines into one ATX line and drop the underline
+            let text = (heading.line..heading.end_line - 1)
+                .filter_map(|i| lines[i].take())
+                .fold(rest.trim().to_string(), |acc, l| {
+                    format!("{} {}", acc, l.trim())
+                });
+            lines[idx] = Some(format!("{}{} {}", prefix, hashes, text));
+            lines[heading.end_line - 1] = None;
+        } else {
+            let rest = rest.trim_start_matches('#');
+            lines[idx] = Some(format!("{}{}{}", prefix, hashes, rest));
+        }
+        shifted += 1;
+    }
+
+    HeadingShift {
+        content: lines.into_iter().flatten().collect::<Vec<_>>().join("\n"),
+        shifted,
+        warnings,
+    }
+}
+
+fn find_heading(headings: &[Heading], line: usize) -> Result<usize, String> {
+    headings
+        .iter()
+        .position(|h| h.line == line)
+        .ok_or_else(|| format!("No heading on line {}", line))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MoveDirection {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SectionMove {
+    pub content: String,
+    /// 1-based line of the moved heading in the new content
+    pub cursor_line: usize,
+}
+
+/// Swaps the section at `heading_line` with its previous or next sibling (same level, same
+/// parent). Blank lines between sections stay where they were so spacing is unchanged.
+pub fn move_section(
+    content: &str,
+    heading_line: usize,
+    direction: MoveDirection,
+    flavor: MarkdownFlavor,
+) -> Result<SectionMove, String> {
+    let all = headings(content, flavor);
+    let idx = find_heading(&all, heading_line)?;
+    let level = all[idx].level;
+
+    let mut lines: Vec<&str> = content.split('\n').collect();
+    // The final newline belongs to the document, not to whichever section ends it
+    let trailing_newline = lines.len() > 1 && lines.last() == Some(&"");
+    if trailing_newline {
+        lines.pop();
+    }
+    let line_count = lines.len();
+    let current = section_range(&all, idx, line_count);
+
+    let (first, second) = match direction {
+        MoveDirection::Up => {
+            let sibling = all[..idx]
+                .iter()
+                .rposition(|h| h.level <= level)
+                .filter(|&j| all[j].level == level)
+                .ok_or("Section is already first among its siblings")?;
+            (section_range(&all, sibling, line_count), current)
+        },
+        MoveDirection::Down => {
+            let sibling = all[idx + 1..]
+                .iter()
+                .position(|h| h.level <= level)
+                .map(|j| j + idx + 1)
+                .filter(|&j| all[j].level == level)
+                .ok_or("Section is already last among its siblings")?;
+            (current, section_range(&all, sibling, line_count))
+        },
+    };
+
+    let first_body = body_len(&lines[first.0..first.1]);
+    let second_body = body_len(&lines[second.0..second.1]);
+
+    let mut moved: Vec<&str> = Vec::with_capacity(lines.len() + 1);
+    moved.extend_from_slice(&lines[..first.0]);
+    moved.extend_from_slice(&lines[second.0..second.0 + second_body]);
+    moved.extend_from_slice(&lines[first.0 + first_body..first.1]);
+    moved.extend_from_slice(&lines[first.0..first.0 + first_body]);
+    moved.extend_from_slice(&lines[second.0 + second_body..second.1]);
+    moved.extend_from_slice(&lines[second.1..]);
+    if trailing_newline {
+        moved.push("");
+    }
+
+    let cursor_line = match direction {
+        MoveDirection::Up => first.0 + 1,
+        MoveDirection::Down => first.0 + second_body + (first.1 - first.0 - first_body) + 1,
+    };
+
+    Ok(SectionMove {
+        content: moved.join("\n"),
+        cursor_line,
+    })
+}
+
+#[derive(Debug)]
+pub struct SectionExtract {
+    /// Source content with the section replaced by a link
+    pub source: String,
+    /// The removed section, without trailing blank lines
+    pub section: String,
+    pub title: String,
+    pub link: String,
+}
+
+/// Cuts the section at `heading_line` out of `content` and leaves a `[title](link_target)`
+/// line in its place.
+pub fn extract_section(
+    content: &str,
+    heading_line: usize,
+    link_target: &str,
+    flavor: MarkdownFlavor,
+) -> Result<SectionExtract, String> {
+    let all = headings(content, flavor);
+    let idx = find_heading(&all, heading_line)?;
+    let title = all[idx].text.clone();
+
+    let lines: Vec<&str> = content.split('\n').collect();
+    let (start, end) = section_range(&all, idx, lines.len());
+    let body = body_len(&lines[start..end]);
+    let section = lines[start..start + body].join("\n");
+
+    let link = format!("[{}]({})", title.replace(['[', ']'], ""), link_target);
+    let mut remaining: Vec<&str> = Vec::with_capacity(lines.len() - body + 1);
+    remaining.extend_from_slice(&lines[..start]);
+    remaining.push(link.as_str());
+    remaining.extend_from_slice(&lines[start + body..]);
+
+    Ok(SectionExtract {
+        source: remaining.join("\n"),
+        section,
+        title,
+        link,
+    })
+}
+
+/// Number of lines in a section before its trailing blank lines.
+fn body_len(block: &[&str]) -> usize {
+    block.len()
+        - block
+            .iter()
+            .rev()
+            .take_while(|line| line.trim().is_empty())
+            .count()
+}
+
+/// Rewrites `](#old)` and `]: #old` references using the anchor map.
+pub fn rewrite_anchor_links(content: &str, renamed: &HashMap<String, String>) -> (String, usize) {
+    let mut count = 0;
+    let rewritten = ANCHOR_LINK_RE.replace_all(content, |caps: &regex::Captures| {
+        match renamed.get(&caps[2]) {
+            Some(new_anchor) => {
+                count += 1;
+                format!("{}#{}", &caps[1], new_anchor)
+            },
+            None => caps[0].to_string(),
+        }
+    });
+    (rewritten.into_owned(), count)
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeadingChange {
+    pub line: usize,
+    pub old_text: String,
+    pub new_text: String,
+    pub old_anchor: String,
+    pub new_anchor: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeadingFixResult {
+    pub content: String,
+    pub changes: Vec<HeadingChange>,
+    pub links_updated: usize,
+}
+
+/// Gives every repeated heading a unique anchor, either with an explicit rename keyed by
+/// line or by appending ` (2)`, ` (3)`..., and retargets in-document links to the moved
+/// anchors. The changes double as a preview; nothing is written back by this function.
+pub fn fix_duplicate_headings(
+    content: &str,
+    renames: &HashMap<usize, String>,
+    flavor: MarkdownFlavor,
+) -> HeadingFixResult {
+    let original = headings(content, flavor);
+    let old_anchors = anchors(&original);
+
+    let mut updated = original.clone();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for heading in updated.iter_mut() {
+        if let Some(name) = renames.get(&heading.line) {
+            heading.text = name.trim().to_string();
+        }
+        let occurrence = seen.entry(slugify(&heading.text)).or_insert(0);
+        *occurrence += 1;
+        if *occurrence > 1 && !renames.contains_key(&heading.line) {
+            heading.text = format!("{} ({})", heading.text, occurrence);
+        }
+    }
+    let new_anchors = anchors(&updated);
+
+    let mut lines: Vec<String> = content.split('\n').map(str::to_string).collect();
+    let mut changes = Vec::new();
+    let mut renamed: HashMap<String, String> = HashMap::new();
+
+    for (i, (old, new)) in original.iter().zip(&updated).enumerate() {
+        if old.text == new.text {
+            if old_anchors[i] != new_anchors[i] {
+                renamed.insert(old_anchors[i].clone(), new_anchors[i].clone());
+            }
+            continue;
+        }
+        let idx = old.line - 1;
+        lines[idx] = replace_heading_text(&lines[idx], old, &new.text);
+        renamed.insert(old_anchors[i].clone(), new_anchors[i].clone());
+        changes.push(HeadingChange {
+            line: old.line,
+            old_text: old.text.clone(),
+            new_text: new.text.clone(),
+            old_anchor: old_anchors[i].clone(),
+            new_anchor: new_anchors[i].clone(),
+        });
+    }
+
+    if changes.is_empty() {
+        return HeadingFixResult {
+            content: content.to_string(),
+            changes,
+            links_updated: 0,
+        };
+    }
+
+    // Single pass, so an anchor renamed onto another heading's old anchor is not rewritten twice
+    let (content, links_updated) = rewrite_anchor_links(&lines.join("\n"), &renamed);
+
+    HeadingFixResult {
+        content,
+        changes,
+        links_updated,
+    }
+}
+
+/// Rewrites a heading's source line with new text, keeping the ATX marker and
+/// indentation. Setext text lines are replaced wholesale.
+fn replace_heading_text(line: &str, heading: &Heading, new_text: &str) -> String {
+    if heading.setext {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        return format!("{}{}", indent, new_text);
+    }
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+    let hashes = &trimmed[..trimmed.len() - trimmed.trim_start_matches('#').len()];
+    format!("{}{} {}", indent, hashes, new_text)
+}
@@ -0,0 +1,329 @@
+use crate::markdown::highlight;
+use crate::preview_assets;
+use comrak::Arena;
+use comrak::nodes::{AstNode, NodeValue};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+/// Query parameters that only identify the click for analytics
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "mc_cid", "mc_eid", "igshid",
+    "yclid", "_hsenc", "_hsmi", "mkt_tok", "ref_src",
+];
+
+/// How rendering treats content that points outside the document.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize
+)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RemotePolicy {
+    /// Replace `http(s)` images with a placeholder so opening a file makes no requests
+    pub block_remote_images: bool,
+    /// Rewrite `http://` links and images to `https://`
+    pub force_https: bool,
+    /// Drop `utm_*` and click-id parameters from link URLs
+    pub strip_tracking_params: bool,
+}
+
+impl RemotePolicy {
+    pub fn is_active(&self) -> bool {
+        self.block_remote_images || self.force_https || self.strip_tracking_params
+    }
+}
+
+/// What happens to raw HTML written in the markdown.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize
+)]
+#[serde(rename_all = "lowercase")]
+pub enum HtmlPolicy {
+    /// Left out of the output
+    #[default]
+    Off,
+    /// Rendered, then filtered to an allowlist of tags and attributes; scripts, event
+    /// handlers and `javascript:` URLs never survive
+    Sanitize,
+    /// Rendered as written
+    Allow,
+}
+
+/// Prefix for ids written in raw HTML, as GitHub does, so they can't collide with the
+/// renderer's own ids or shadow the preview's globals through named element access
+const USER_ID_PREFIX: &str = "user-content-";
+
+/// Classes the renderer emits; raw HTML keeps only these, so it can't borrow app styling
+const RENDERER_CLASSES: &[&str] = &[
+    "anchor",
+    "contains-task-list",
+    "file-path-link",
+    "footnote-backref",
+    "footnote-ref",
+    "footnotes",
+    "math",
+    "spoiler",
+    "task-list-item",
+    "task-list-item-checkbox",
+    highlight::PRE_CLASS,
+];
+const RENDERER_CLASS_PREFIXES: &[&str] = &[
+    "language-",
+    "markdown-alert",
+    "math-",
+    highlight::CLASS_PREFIX,
+];
+
+fn is_renderer_class(class: &str) -> bool {
+    RENDERER_CLASSES.contains(&class)
+        || RENDERER_CLASS_PREFIXES
+            .iter()
+            .any(|prefix| class.starts_with(prefix))
+}
+
+/// Narrows `class` to renderer classes and prefixes every `id` that isn't one of the
+/// renderer's heading or footnote ids.
+fn filter_attribute<'u>(
+    heading_ids: &HashSet<String>,
+    name: &str,
+    value: &'u str,
+) -> Option<Cow<'u, str>> {
+    match name {
+        "class" => {
+            let classes: Vec<&str> = value
+                .split_ascii_whitespace()
+                .filter(|class| is_renderer_class(class))
+                .collect();
+            (!classes.is_empty()).then(|| Cow::Owned(classes.join(" ")))
+        },
+        "id" if heading_ids.contains(value)
+            || value.starts_with("fn-")
+            || value.starts_with("fnref-") =>
+        {
+            Some(Cow::Borrowed(value))
+        },
+        "id" => Some(Cow::Owned(format!("{}{}", USER_ID_PREFIX, value))),
+        _ => Some(Cow::Borrowed(value)),
+    }
+}
+
+/// Filters rendered HTML through the `HtmlPolicy::Sanitize` allowlist: ammonia's defaults
+/// plus what the renderer itself emits (task checkboxes, footnote sections, source
+/// positions, its classes and ids). `heading_ids` are the ids the renderer gave headings.
+pub fn sanitize_html(html: &str, heading_ids: &[String]) -> String {
+    let heading_ids: HashSet<String> = heading_ids.iter().cloned().collect();
+    let mut builder = ammonia::Builder::default();
+    builder
+        .add_tags(["section", "input"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_tag_attributes("details", ["open"])
+        .add_tag_attributes("img", ["title"])
+        .add_tag_attributes("a", ["title"])
+        .add_generic_attributes(["class", "id"])
+        .add_generic_attribute_prefixes(["data-", "aria-"])
+        .add_url_schemes([preview_assets::SCHEME])
+        .link_rel(None)
+        .attribute_filter(move |_, name, value| filter_attribute(&heading_ids, name, value));
+    builder.clean(html).to_string()
+}
+
+/// What rendering stripped or rewrote, so differences from the source can be explained.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SanitizationReport {
+    /// Raw HTML blocks and inline tags left out of the output
+    pub raw_html_removed: usize,
+    /// Raw HTML that contained a `<script>` element
+    pub scripts_dropped: usize,
+    pub remote_images_blocked: usize,
+    pub urls_upgraded: usize,
+    pub links_cleaned: usize,
+}
+
+impl SanitizationReport {
+    pub fn is_empty(&self) -> bool {
+        self.raw_html_removed == 0
+            && self.scripts_dropped == 0
+            && self.remote_images_blocked == 0
+            && self.urls_upgraded == 0
+            && self.links_cleaned == 0
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.raw_html_removed += other.raw_html_removed;
+        self.scripts_dropped += other.scripts_dropped;
+        self.remote_images_blocked += other.remote_images_blocked;
+        self.urls_upgraded += other.urls_upgraded;
+        self.links_cleaned += other.links_cleaned;
+    }
+}
+
+/// Counts the raw HTML the renderer will omit under `policy`; with `Sanitize` only
+/// scripts are counted, since what else the sanitizer trims isn't known up front. Returns
+/// whether the document has any raw HTML. Must run before any pass that inserts HTML
+/// nodes of its own.
+pub fn count_raw_html<'a>(
+    root: &'a AstNode<'a>,
+    policy: HtmlPolicy,
+    report: &mut SanitizationReport,
+) -> bool {
+    let mut found = false;
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        let literal = match &data.value {
+            NodeValue::HtmlBlock(block) => &block.literal,
+            NodeValue::HtmlInline(literal) => literal,
+            _ => continue,
+        };
+        if literal.trim().is_empty() || literal.trim_start().starts_with("<!--") {
+            continue;
+        }
+        found = true;
+        let is_script = literal.to_ascii_lowercase().contains("<script");
+        match policy {
+            HtmlPolicy::Off => report.raw_html_removed += 1,
+            HtmlPolicy::Sanitize if is_script => report.raw_html_removed += 1,
+            _ => {},
+        }
+        if is_script && policy != HtmlPolicy::Allow {
+            report.scripts_dropped += 1;
+        }
+    }
+    found
+}
+
+fn is_remote(url: &str) -> bool {
+    let lower = url.trim_start().to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://") || lower.starts_with("//")
+}
+
+fn upgrade_https(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("http://").or_else(|| {
+        url.get(..7)
+            .filter(|p| p.eq_ignore_ascii_case("http://"))
+            .map(|_| &url[7..])
+    })?;
+    Some(format!("https://{}", rest))
+}
+
+fn is_tracking_param(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    lower.starts_with("utm_") || TRACKING_PARAMS.contains(&lower.as_str())
+}
+
+/// Removes tracking parameters from the query string, keeping the fragment intact.
+/// Returns `None` when nothing was removed.
+pub fn strip_tracking_params(url: &str) -> Option<String> {
+    let (before_fragment, fragment) = match url.find('#') {
+        Some(i) => url.split_at(i),
+        None => (url, ""),
+    };
+    let (base, query) = before_fragment.split_once('?')?;
+
+    let params: Vec<&str> = query.split('&').filter(|p| !p.is_empty()).collect();
+    let kept: Vec<&str> = params
+        .iter()
+        .copied()
+        .filter(|p| !is_tracking_param(p.split('=').next().unwrap_or(p)))
+        .collect();
+    if kept.len() == params.len() {
+        return None;
+    }
+
+    Some(if kept.is_empty() {
+        format!("{}{}", base, fragment)
+    } else {
+        format!("{}?{}{}", base, kept.join("&"), fragment)
+    })
+}
+
+fn image_alt_text<'a>(node: &'a AstNode<'a>) -> String {
+    node.descendants()
+        .filter_map(|n| match &n.data.borrow().value {
+            NodeValue::Text(t) => Some(t.to_string()),
+            NodeValue::Code(c) => Some(c.literal.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Applies the policy to every link and image in the AST.
+pub fn apply_remote_policy<'a>(
+    arena: &'a Arena<'a>,
+    root: &'a AstNode<'a>,
+    policy: &RemotePolicy,
+    report: &mut SanitizationReport,
+) {
+    if !policy.is_active() {
+        return;
+    }
+
+    let nodes: Vec<&AstNode<'_>> = root
+        .descendants()
+        .filter(|n| {
+            matches!(
+                n.data.borrow().value,
+                NodeValue::Link(_) | NodeValue::Image(_)
+            )
+        })
+        .collect();
+
+    for node in nodes {
+        let is_image = matches!(node.data.borrow().value, NodeValue::Image(_));
+
+        {
+            let mut data = node.data.borrow_mut();
+            let (NodeValue::Link(link) | NodeValue::Image(link)) = &mut data.value else {
+                continue;
+            };
+            if policy.force_https
+                && let Some(upgraded) = upgrade_https(&link.url)
+            {
+                link.url = upgraded.into();
+                report.urls_upgraded += 1;
+            }
+            if policy.strip_tracking_params
+                && !is_image
+                && let Some(cleaned) = strip_tracking_params(&link.url)
+            {
+                link.url = cleaned.into();
+                report.links_cleaned += 1;
+            }
+        }
+
+        // Turned into a link to the image so the URL stays visible without a request
+        if is_image && policy.block_remote_images {
+            let link = match &node.data.borrow().value {
+                NodeValue::Image(link) if is_remote(&link.url) => link.clone(),
+                _ => continue,
+            };
+            let alt = image_alt_text(node);
+            let label = if alt.trim().is_empty() {
+                "[Remote image blocked]".to_string()
+            } else {
+                format!("[Remote image blocked: {}]", alt.trim())
+            };
+
+            for child in node.children().collect::<Vec<_>>() {
+                child.detach();
+            }
+            node.append(arena.alloc(AstNode::from(NodeValue::Text(label.into()))));
+            node.data.borrow_mut().value = NodeValue::Link(link);
+            report.remote_images_blocked += 1;
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::markdown::front_matter;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Words per shingle; five words is long enough that shared shingles mean shared passages
+const SHINGLE_WORDS: usize = 5;
+const NUM_HASHES: usize = 128;
+/// Locality-sensitive hashing bands: 32 bands of 4 rows put pairs at about 0.5 similarity
+/// in a shared bucket most of the time, while unrelated notes rarely collide
+const BANDS: usize = 32;
+const ROWS_PER_BAND: usize = NUM_HASHES / BANDS;
+
+pub const DEFAULT_THRESHOLD: f64 = 0.5;
+
+/// MinHash signature of a document's word shingles
+#[derive(Debug, Clone)]
+pub struct Signature {
+    mins: Vec<u64>,
+    /// Number of distinct shingles; zero for documents with no words
+    shingles: usize,
+}
+
+impl Signature {
+    pub fn is_empty(&self) -> bool {
+        self.shingles == 0
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarDocument {
+    pub path: String,
+    /// Estimated Jaccard similarity of the two documents' shingles, 0.0–1.0
+    pub similarity: f64,
+    /// The other document of a duplicate pair; `None` when compared against a target
+    pub similar_to: Option<String>,
+}
+
+/// SplitMix64 finalizer; turns one shingle hash into `NUM_HASHES` independent ones
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Builds the signature from the document body, ignoring front matter, case and
+/// punctuation. Documents shorter than a shingle count as one shingle.
+pub fn signature(content: &str) -> Signature {
+    let body = front_matter::split(content).map_or(content, |fm| fm.body);
+    let words: Vec<String> = body.unicode_words().map(str::to_lowercase).collect();
+    let width = SHINGLE_WORDS.min(words.len()).max(1);
+
+    let shingles: HashSet<u64> = words
+        .windows(width)
+        .map(|window| {
+            // DefaultHasher::new() uses fixed keys, so signatures are stable across runs
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+
+    let mut mins = vec![u64::MAX; NUM_HASHES];
+    for &shingle in &shingles {
+        for (i, min) in mins.iter_mut().enumerate() {
+            let hash = mix(shingle ^ mix(i as u64));
+            if hash < *min {
+                *min = hash;
+            }
+        }
+    }
+    Signature {
+        mins,
+        shingles: shingles.len(),
+    }
+}
+
+/// Fraction of matching MinHash slots, an estimate of the Jaccard similarity
+pub fn similarity(a: &Signature, b: &Signature) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let matching = a.mins.iter().zip(&b.mins).filter(|(x, y)| x == y).count();
+    matching as f64 / NUM_HASHES as f64
+}
+
+/// Documents at least `threshold` similar to `target`, most similar first
+pub fn similar_to(
+    target: &Signature,
+    documents: &[(String, Signature)],
+    threshold: f64,
+) -> Vec<SimilarDocument> {
+    let mut matches: Vec<SimilarDocument> = documents
+        .iter()
+        .map(|(path, sig)| (path, similarity(target, sig)))
+        .filter(|(_, score)| *score >= threshold)
+        .map(|(path, score)| SimilarDocument {
+            path: path.clone(),
+            similarity: score,
+            similar_to: None,
+        })
+        .collect();
+    sort_matches(&mut matches);
+    matches
+}
+
+/// Pairs of documents at least `threshold` similar to each other, most similar first.
+/// Only pairs sharing an LSH band are compared, so large collections stay fast.
+pub fn duplicate_pairs(documents: &[(String, Signature)], threshold: f64) -> Vec<SimilarDocument> {
+    let mut candidates: HashSet<(usize, usize)> = HashSet::new();
+    for band in 0..BANDS {
+        let rows = band * ROWS_PER_BAND..(band + 1) * ROWS_PER_BAND;
+        let mut buckets: HashMap<&[u64], Vec<usize>> = HashMap::new();
+        for (index, (_, sig)) in documents.iter().enumerate() {
+            if !sig.is_empty() {
+                buckets
+                    .entry(&sig.mins[rows.clone()])
+                    .or_default()
+                    .push(index);
+            }
+        }
+        for bucket in buckets.values().filter(|b| b.len() > 1) {
+            for (i, &a) in bucket.iter().enumerate() {
+                for &b in &bucket[i + 1..] {
+                    candidates.insert((a, b));
+                }
+            }
+        }
+    }
+
+    let mut pairs: Vec<SimilarDocument> = candidates
+        .into_iter()
+        .filter_map(|(a, b)| {
+            let score = similarity(&documents[a].1, &documents[b].1);
+            (score >= threshold).then(|| SimilarDocument {
+                path: documents[a].0.clone(),
+                similarity: score,
+                similar_to: Some(documents[b].0.clone()),
+            })
+        })
+        .collect();
+    sort_matches(&mut pairs);
+    pairs
+}
+
+fn sort_matches(matches: &mut [SimilarDocument]) {
+    matches.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+}
@@ -0,0 +1,65 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::front_matter;
+use comrak::nodes::NodeValue;
+use comrak::{Arena, parse_document};
+use serde::Serialize;
+
+/// Counts of the structural elements in a document, for the document info panel
+#[derive(Debug, Default, Serialize)]
+pub struct DocumentStats {
+    /// Headings at levels 1 to 6
+    pub headings: [usize; 6],
+    /// Links to headings, local files and wikilinks
+    pub internal_links: usize,
+    /// Links with a URL scheme, such as `https:` or `mailto:`
+    pub external_links: usize,
+    pub images: usize,
+    /// Fenced and indented code blocks
+    pub code_blocks: usize,
+    /// Footnote definitions
+    pub footnotes: usize,
+    pub open_tasks: usize,
+    pub closed_tasks: usize,
+}
+
+pub fn document_stats(content: &str, flavor: MarkdownFlavor) -> DocumentStats {
+    let body = front_matter::split(content).map_or(content, |fm| fm.body);
+    let arena = Arena::new();
+    let root = parse_document(&arena, body, &flavor.to_comrak_options());
+
+    let mut stats = DocumentStats::default();
+    for node in root.descendants() {
+        match &node.data.borrow().value {
+            NodeValue::Heading(heading) => {
+                let level = usize::from(heading.level).clamp(1, 6);
+                stats.headings[level - 1] += 1;
+            },
+            NodeValue::Link(link) if has_scheme(&link.url) => stats.external_links += 1,
+            NodeValue::Link(_) | NodeValue::WikiLink(_) => stats.internal_links += 1,
+            NodeValue::Image(_) => stats.images += 1,
+            NodeValue::CodeBlock(_) => stats.code_blocks += 1,
+            NodeValue::FootnoteDefinition(_) => stats.footnotes += 1,
+            NodeValue::TaskItem(task) if task.symbol.is_some() => stats.closed_tasks += 1,
+            NodeValue::TaskItem(_) => stats.open_tasks += 1,
+            _ => {},
+        }
+    }
+    stats
+}
+
+/// `scheme:` as in RFC 3986. One letter is taken for a Windows drive rather than a scheme,
+/// and protocol-relative `//host` links count as external.
+fn has_scheme(url: &str) -> bool {
+    let url = url.trim();
+    if url.starts_with("//") {
+        return true;
+    }
+    let Some((scheme, _)) = url.split_once(':') else {
+        return false;
+    };
+    scheme.len() > 1
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
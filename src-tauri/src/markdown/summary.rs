@@ -0,0 +1,89 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::front_matter;
+use crate::markdown::sections::{self, TextOptions};
+use comrak::nodes::{AstNode, NodeValue};
+use comrak::{Arena, parse_document};
+use serde::Serialize;
+
+const ELLIPSIS: char = '…';
+
+#[derive(Debug, Default, Serialize)]
+pub struct DocumentSummary {
+    pub title: Option<String>,
+    pub summary: String,
+}
+
+/// Extracts a title (front matter `title:` or the first heading) and the first meaningful
+/// paragraph as plain text, skipping front matter, headings, and badge-only paragraphs.
+pub fn extract_summary(content: &str, max_chars: usize, flavor: MarkdownFlavor) -> DocumentSummary {
+    let body = front_matter::split(content).map_or(content, |fm| fm.body);
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, body, &flavor.to_comrak_options());
+
+    let mut title =
+        front_matter::parse(content).and_then(|fm| front_matter::string_field(&fm, "title"));
+    let mut summary = String::new();
+
+    for node in root.children() {
+        match &node.data.borrow().value {
+            NodeValue::Heading(_) => {
+                if title.is_none() {
+                    title = Some(plain_text(node)).filter(|t| !t.is_empty());
+                }
+            },
+            NodeValue::Paragraph if summary.is_empty() && !is_badge_paragraph(node) => {
+                summary = plain_text(node);
+            },
+            _ => {},
+        }
+        if title.is_some() && !summary.is_empty() {
+            break;
+        }
+    }
+
+    DocumentSummary {
+        title,
+        summary: truncate_at_word(&summary, max_chars),
+    }
+}
+
+/// Paragraphs made only of images, image links, and inline HTML (CI badges and the like).
+fn is_badge_paragraph<'a>(node: &'a AstNode<'a>) -> bool {
+    node.children()
+        .all(|child| match &child.data.borrow().value {
+            NodeValue::Image(_) | NodeValue::HtmlInline(_) => true,
+            NodeValue::SoftBreak | NodeValue::LineBreak => true,
+            NodeValue::Text(t) => t.trim().is_empty(),
+            NodeValue::Link(_) => child
+                .children()
+                .all(|c| matches!(c.data.borrow().value, NodeValue::Image(_))),
+            _ => false,
+        })
+}
+
+/// Text without image alt text or raw HTML
+fn plain_text<'a>(node: &'a AstNode<'a>) -> String {
+    sections::node_text(node, TextOptions::default())
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn truncate_at_word(text: &str, max_chars: usize) -> String {
+    if max_chars == 0 || text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let cut = text
+        .char_indices()
+        .nth(max_chars.saturating_sub(1))
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    let head = &text[..cut];
+    let head = head.rfind(' ').map(|i| &head[..i]).unwrap_or(head);
+
+    let mut truncated = head.trim_end_matches([',', ';', ':', '.', ' ']).to_string();
+    truncated.push(ELLIPSIS);
+    truncated
+}
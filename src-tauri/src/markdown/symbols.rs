@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+/// `\` completions: LaTeX-style names so math users get the names they already know
+const SYMBOLS: &[(&str, &str, &str)] = &[
+    // Arrows
+    ("rightarrow", "→", "rightwards arrow"),
+    ("leftarrow", "←", "leftwards arrow"),
+    ("uparrow", "↑", "upwards arrow"),
+    ("downarrow", "↓", "downwards arrow"),
+    ("leftrightarrow", "↔", "left right arrow"),
+    ("updownarrow", "↕", "up down arrow"),
+    ("Rightarrow", "⇒", "rightwards double arrow implies"),
+    ("Leftarrow", "⇐", "leftwards double arrow"),
+    ("Leftrightarrow", "⇔", "left right double arrow iff"),
+    ("mapsto", "↦", "maps to"),
+    ("hookrightarrow", "↪", "hook arrow"),
+    ("nearrow", "↗", "north east arrow"),
+    ("searrow", "↘", "south east arrow"),
+    ("swarrow", "↙", "south west arrow"),
+    ("nwarrow", "↖", "north west arrow"),
+    ("circlearrowright", "↻", "clockwise open circle arrow"),
+    ("circlearrowleft", "↺", "anticlockwise open circle arrow"),
+    // Relations
+    ("le", "≤", "less than or equal"),
+    ("ge", "≥", "greater than or equal"),
+    ("ne", "≠", "not equal"),
+    ("approx", "≈", "almost equal"),
+    ("equiv", "≡", "identical to"),
+    ("sim", "∼", "tilde operator similar"),
+    ("propto", "∝", "proportional to"),
+    ("ll", "≪", "much less than"),
+    ("gg", "≫", "much greater than"),
+    ("in", "∈", "element of"),
+    ("notin", "∉", "not an element of"),
+    ("subset", "⊂", "subset of"),
+    ("supset", "⊃", "superset of"),
+    ("subseteq", "⊆", "subset of or equal"),
+    ("supseteq", "⊇", "superset of or equal"),
+    ("perp", "⊥", "perpendicular"),
+    ("parallel", "∥", "parallel to"),
+    // Operators
+    ("pm", "±", "plus minus"),
+    ("mp", "∓", "minus plus"),
+    ("times", "×", "multiplication"),
+    ("div", "÷", "division"),
+    ("cdot", "·", "dot operator"),
+    ("circ", "∘", "ring operator compose"),
+    ("sqrt", "√", "square root"),
+    ("sum", "∑", "n-ary summation"),
+    ("prod", "∏", "n-ary product"),
+    ("int", "∫", "integral"),
+    ("oint", "∮", "contour integral"),
+    ("partial", "∂", "partial differential"),
+    ("nabla", "∇", "nabla del"),
+    ("infty", "∞", "infinity"),
+    ("cup", "∪", "union"),
+    ("cap", "∩", "intersection"),
+    ("emptyset", "∅", "empty set"),
+    ("forall", "∀", "for all"),
+    ("exists", "∃", "there exists"),
+    ("neg", "¬", "not sign"),
+    ("land", "∧", "logical and"),
+    ("lor", "∨", "logical or"),
+    ("oplus", "⊕", "circled plus xor"),
+    ("otimes", "⊗", "circled times tensor"),
+    ("therefore", "∴", "therefore"),
+    ("because", "∵", "because"),
+    ("degree", "°", "degree sign"),
+    ("prime", "′", "prime minutes feet"),
+    // Sets of numbers
+    ("mathbbN", "ℕ", "natural numbers"),
+    ("mathbbZ", "ℤ", "integers"),
+    ("mathbbQ", "ℚ", "rational numbers"),
+    ("mathbbR", "ℝ", "real numbers"),
+    ("mathbbC", "ℂ", "complex numbers"),
+    // Greek
+    ("alpha", "α", "greek small letter alpha"),
+    ("beta", "β", "greek small letter beta"),
+    ("gamma", "γ", "greek small letter gamma"),
+    ("delta", "δ", "greek small letter delta"),
+    ("epsilon", "ε", "greek small letter epsilon"),
+    ("zeta", "ζ", "greek small letter zeta"),
+    ("eta", "η", "greek small letter eta"),
+    ("theta", "θ", "greek small letter theta"),
+    ("iota", "ι", "greek small letter iota"),
+    ("kappa", "κ", "greek small letter kappa"),
+    ("lambda", "λ", "greek small letter lambda"),
+    ("mu", "μ", "greek small letter mu micro"),
+    ("nu", "ν", "greek small letter nu"),
+    ("xi", "ξ", "greek small letter xi"),
+    ("pi", "π", "greek small letter pi"),
+    ("rho", "ρ", "greek small letter rho"),
+    ("sigma", "σ", "greek small letter sigma"),
+    ("tau", "τ", "greek small letter tau"),
+    ("phi", "φ", "greek small letter phi"),
+    ("chi", "χ", "greek small letter chi"),
+    ("psi", "ψ", "greek small letter psi"),
+    ("omega", "ω", "greek small letter omega"),
+    ("Gamma", "Γ", "greek capital letter gamma"),
+    ("Delta", "Δ", "greek capital letter delta"),
+    ("Theta", "Θ", "greek capital letter theta"),
+    ("Lambda", "Λ", "greek capital letter lambda"),
+    ("Pi", "Π", "greek capital letter pi"),
+    ("Sigma", "Σ", "greek capital letter sigma"),
+    ("Phi", "Φ", "greek capital letter phi"),
+    ("Psi", "Ψ", "greek capital letter psi"),
+    ("Omega", "Ω", "greek capital letter omega ohm"),
+    // Typography
+    ("endash", "–", "en dash range"),
+    ("emdash", "—", "em dash"),
+    ("ellipsis", "…", "horizontal ellipsis"),
+    ("bullet", "•", "bullet"),
+    ("section", "§", "section sign"),
+    ("paragraph", "¶", "pilcrow paragraph sign"),
+    ("dagger", "†", "dagger"),
+    ("ddagger", "‡", "double dagger"),
+    ("copyright", "©", "copyright sign"),
+    ("registered", "®", "registered sign"),
+    ("trademark", "™", "trade mark sign"),
+    ("nbsp", "\u{a0}", "no-break space"),
+    ("laquo", "«", "left guillemet"),
+    ("raquo", "»", "right guillemet"),
+    ("checkmark", "✓", "check mark tick"),
+    ("cross", "✗", "ballot x"),
+    ("euro", "€", "euro sign currency"),
+    ("pound", "£", "pound sign currency"),
+    ("yen", "¥", "yen sign currency"),
+    ("cent", "¢", "cent sign currency"),
+];
+
+const DEFAULT_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    /// `:shortcode:` emoji
+    Emoji,
+    /// `\name` symbols
+    Symbol,
+}
+
+struct IndexEntry {
+    kind: SymbolKind,
+    /// Names that complete the trigger; emoji may have several shortcodes
+    names: Vec<&'static str>,
+    symbol: &'static str,
+    /// Lowercase words matched after the names
+    keywords: String,
+    description: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolMatch {
+    pub kind: SymbolKind,
+    /// The shortcode or symbol name that matched
+    pub name: String,
+    pub symbol: String,
+    pub description: String,
+}
+
+static INDEX: LazyLock<Vec<IndexEntry>> = LazyLock::new(|| {
+    let emoji = emojis::iter()
+        .filter(|e| e.shortcode().is_some())
+        .map(|e| IndexEntry {
+            kind: SymbolKind::Emoji,
+            names: e.shortcodes().collect(),
+            symbol: e.as_str(),
+            keywords: e.name().to_lowercase(),
+            description: e.name(),
+        });
+    let symbols = SYMBOLS
+        .iter()
+        .map(|&(name, symbol, description)| IndexEntry {
+            kind: SymbolKind::Symbol,
+            names: vec![name],
+            symbol,
+            keywords: description.to_string(),
+            description,
+        });
+    emoji.chain(symbols).collect()
+});
+
+/// Score for `query` (lowercase) as a fuzzy match of `candidate`: every query character
+/// must appear in order. Prefixes beat substrings, which beat scattered matches, and
+/// shorter candidates win ties. `None` when it doesn't match at all.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let lower = candidate.to_lowercase();
+    let length_penalty = lower.len().min(100) as i32;
+    if lower == query {
+        return Some(10_000);
+    }
+    if lower.starts_with(query) {
+        return Some(5_000 - length_penalty);
+    }
+    if let Some(pos) = lower.find(query) {
+        // Matches at a word start ("arrow" in "left_arrow") rank above mid-word ones
+        let word_start = lower[..pos].ends_with(['_', '-', ' ']);
+        return Some(if word_start { 3_000 } else { 2_000 } - length_penalty);
+    }
+
+    let mut chars = lower.chars();
+    let mut gap = 0;
+    for q in query.chars() {
+        loop {
+            match chars.next() {
+                Some(c) if c == q => break,
+                Some(_) => gap += 1,
+                None => return None,
+            }
+        }
+    }
+    Some(1_000 - length_penalty - gap.min(500))
+}
+
+fn entry_score(query: &str, entry: &IndexEntry) -> Option<(i32, &'static str)> {
+    let by_name = entry
+        .names
+        .iter()
+        .filter_map(|&name| fuzzy_score(query, name).map(|score| (score, name)))
+        .max_by_key(|&(score, _)| score);
+    // Keywords only count as word-prefix hits, ranked below prefix matches on names
+    let by_keyword = entry
+        .keywords
+        .split(' ')
+        .any(|word| word.starts_with(query))
+        .then_some((1_500, entry.names[0]));
+    by_name.max(by_keyword)
+}
+
+/// Emoji and symbols whose names fuzzily match `query`, best first. An empty query
+/// returns the first entries of the index in its natural order.
+pub fn search_symbols(
+    query: &str,
+    kind: Option<SymbolKind>,
+    limit: Option<usize>,
+) -> Vec<SymbolMatch> {
+    let query = query
+        .trim()
+        .trim_start_matches([':', '\\'])
+        .trim_end_matches(':')
+        .to_lowercase();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+
+    let mut matches: Vec<(i32, &IndexEntry, &'static str)> = INDEX
+        .iter()
+        .filter(|entry| kind.is_none_or(|k| entry.kind == k))
+        .filter_map(|entry| {
+            if query.is_empty() {
+                return Some((0, entry, entry.names[0]));
+            }
+            entry_score(&query, entry).map(|(score, name)| (score, entry, name))
+        })
+        .collect();
+    // Stable sort keeps index order (emoji groups, then symbol table order) among ties
+    matches.sort_by_key(|&(score, _, _)| std::cmp::Reverse(score));
+
+    matches
+        .into_iter()
+        .take(limit)
+        .map(|(_, entry, name)| SymbolMatch {
+            kind: entry.kind,
+            name: name.to_string(),
+            symbol: entry.symbol.to_string(),
+            description: entry.description.to_string(),
+        })
+        .collect()
+}
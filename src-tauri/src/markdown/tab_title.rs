@@ -0,0 +1,95 @@
+use crate::markdown::lint::update_fence;
+use crate::markdown::{front_matter, sections};
+use comrak::{Arena, Options, parse_document};
+use regex::Regex;
+use std::sync::LazyLock;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Longest title shown in a tab, in user-perceived characters
+const MAX_TITLE_GRAPHEMES: usize = 25;
+const ELLIPSIS: char = '…';
+
+static ATX_HEADING_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}#{1,6}(?:\s+|$)").expect("Invalid ATX_HEADING_RE"));
+
+static SETEXT_UNDERLINE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}(?:=+|-+)\s*$").expect("Invalid SETEXT_UNDERLINE_RE"));
+
+// Quote, list and task markers in front of the line's text
+static BLOCK_MARKER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:>\s*)*(?:(?:[-*+]|\d{1,9}[.)])\s+)?(?:\[[ xX]\]\s+)?")
+        .expect("Invalid BLOCK_MARKER_RE")
+});
+
+/// A tab title taken from the document: the first heading, or else the first non-empty
+/// line, with markup dropped and clamped to a few words. Front matter and code fences
+/// are skipped. `None` when the document has no text to name it by.
+pub fn derive_tab_title(content: &str) -> Option<String> {
+    let lines: Vec<&str> = content
+        .lines()
+        .skip(front_matter::line_count(content))
+        .collect();
+
+    let mut fallback = None;
+    let mut fence = None;
+    for (i, line) in lines.iter().enumerate() {
+        if update_fence(&mut fence, line) || line.trim().is_empty() {
+            continue;
+        }
+        if let Some(marker) = ATX_HEADING_RE.find(line) {
+            let text = without_closing_sequence(&line[marker.end()..]);
+            if let Some(title) = title_from(text) {
+                return Some(title);
+            }
+            continue;
+        }
+        if lines
+            .get(i + 1)
+            .is_some_and(|next| SETEXT_UNDERLINE_RE.is_match(next))
+            && let Some(title) = title_from(line)
+        {
+            return Some(title);
+        }
+        if fallback.is_none() {
+            fallback = title_from(&BLOCK_MARKER_RE.replace(line, ""));
+        }
+    }
+    fallback
+}
+
+/// An ATX heading's text without its closing `#`s, which only close it after whitespace:
+/// `# C#` keeps its `#`, `# Title ##` doesn't
+fn without_closing_sequence(text: &str) -> &str {
+    let text = text.trim_end();
+    let stripped = text.trim_end_matches('#');
+    if stripped.is_empty() || stripped.ends_with([' ', '\t']) {
+        stripped.trim_end()
+    } else {
+        text
+    }
+}
+
+/// The line's inline markup reduced to plain text and clamped on a grapheme boundary,
+/// so an emoji or accented letter is never cut in half
+fn title_from(line: &str) -> Option<String> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, line.trim(), &Options::default());
+    let text = sections::plain_text(root)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.is_empty() {
+        return None;
+    }
+
+    let graphemes: Vec<&str> = text.graphemes(true).collect();
+    if graphemes.len() <= MAX_TITLE_GRAPHEMES {
+        return Some(text);
+    }
+    let mut title = graphemes[..MAX_TITLE_GRAPHEMES]
+        .concat()
+        .trim_end()
+        .to_string();
+    title.push(ELLIPSIS);
+    Some(title)
+}
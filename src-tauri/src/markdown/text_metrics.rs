@@ -0,0 +1,593 @@
+use crate::markdown::config::MarkdownFlavor;
+use crate::markdown::front_matter;
+use crate::markdown::plain_text;
+use comrak::nodes::{AstNode, NodeValue};
+use comrak::{Arena, parse_document};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Average adult silent reading speed
+const WORDS_PER_MINUTE: f64 = 230.0;
+
+/// What the document word count includes
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Hash,
+    Serialize,
+    Deserialize
+)]
+#[serde(rename_all = "lowercase")]
+pub enum WordCountMode {
+    /// Every word in the source text
+    #[default]
+    All,
+    /// Only prose: code, raw HTML, math, front matter, and bare URLs are left out
+    Prose,
+    /// The text a reader sees: markup, link destinations, raw HTML and front matter are
+    /// left out, code is counted
+    Text,
+}
+
+/// A selection in UTF-16 code units, matching editor (JavaScript string) offsets
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct SelectionRange {
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SelectionMetrics {
+    pub words: usize,
+    pub chars: usize,
+    pub lines: usize,
+    pub reading_time_secs: u64,
+    pub ranges: usize,
+}
+
+/// Builds the byte-offset line map alongside document metrics. Characters are grapheme
+/// clusters and columns are display widths, so emoji, combining marks, ZWJ sequences,
+/// and wide CJK text are measured the way they appear in the editor.
+pub fn build_line_map_and_metrics(content: &str) -> (Vec<usize>, usize, usize, usize, usize) {
+    if content.is_empty() {
+        return (vec![0], 0, 0, 0, 0);
+    }
+
+    let mut line_map = vec![0];
+    let mut offset = 0;
+    let mut char_count = 0;
+    let mut widest_column = 0;
+
+    for (i, line) in content.split('\n').enumerate() {
+        if i > 0 {
+            line_map.push(offset);
+            // The newline (or CRLF pair) is a single grapheme
+            char_count += 1;
+        }
+        offset += line.len() + 1;
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        char_count += line.graphemes(true).count();
+        widest_column = widest_column.max(line.width());
+    }
+
+    let line_count = line_map.len();
+    let word_count = content.unicode_words().count();
+
+    (line_map, line_count, word_count, char_count, widest_column)
+}
+
+/// Returns (line_count, word_count, char_count, widest_column) for a whole document.
+pub fn calculate_text_metrics(content: &str) -> (usize, usize, usize, usize) {
+    let (_, line_count, word_count, char_count, widest_column) =
+        build_line_map_and_metrics(content);
+    (line_count, word_count, char_count, widest_column)
+}
+
+/// Words in the document under `mode`. Prose counts walk the AST, so fenced code and
+/// inline code spans are skipped wherever they appear.
+pub fn count_words(content: &str, mode: WordCountMode, flavor: MarkdownFlavor) -> usize {
+    match mode {
+        WordCountMode::All => content.unicode_words().count(),
+        WordCountMode::Prose => prose_word_count(content, flavor),
+        WordCountMode::Text => plain_text::strip(content, flavor).unicode_words().count(),
+    }
+}
+
+fn prose_word_count(content: &str, flavor: MarkdownFlavor) -> usize {
+    let body = front_matter::split(content).map_or(content, |fm| fm.body);
+    let arena = Arena::new();
+    let root = parse_document(&arena, body, &flavor.to_comrak_options());
+    prose_words(root)
+}
+
+fn prose_words<'a>(node: &'a AstNode<'a>) -> usize {
+    match &node.data.borrow().value {
+        NodeValue::Text(text) => return text.unicode_words().count(),
+        NodeValue::CodeBlock(_)
+        | NodeValue::Code(_)
+        | NodeValue::HtmlBlock(_)
+        | NodeValue::HtmlInline(_)
+        | NodeValue::Math(_)
+        | NodeValue::FrontMatter(_)
+        | NodeValue::Image(_) => return 0,
+        // Autolinks and bare URLs have the URL as their only text
+        NodeValue::Link(link) if is_bare_url(node, &link.url) => return 0,
+        _ => {},
+    }
+    node.children().map(prose_words).sum()
+}
+
+fn is_bare_url<'a>(link: &'a AstNode<'a>, url: &str) -> bool {
+    let mut text = String::new();
+    for child in link.children() {
+        match &child.data.borrow().value {
+            NodeValue::Text(t) => text.push_str(t),
+            _ => return false,
+        }
+    }
+    text == url || url.strip_prefix("mailto:") == Some(text.as_str())
+}
+
+/// Reading time and sentence-level readability of a document's prose
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Readability {
+    /// At `WORDS_PER_MINUTE` over the document word count
+    pub reading_time_secs: u64,
+    /// Sentences in paragraphs; headings, tables and code are left out
+    pub sentence_count: usize,
+    /// Words per sentence, to one decimal
+    pub avg_sentence_length: f64,
+    /// Flesch–Kincaid grade level, to one decimal; `None` without prose. Syllables are
+    /// estimated from vowel groups, so it is only meaningful for English.
+    pub flesch_kincaid_grade: Option<f64>,
+}
+
+/// Readability of the paragraphs in the document. `word_count` is the count already
+/// shown for the document, so reading time follows the word count mode.
+pub fn readability(content: &str, flavor: MarkdownFlavor, word_count: usize) -> Readability {
+    let body = front_matter::split(content).map_or(content, |fm| fm.body);
+    let arena = Arena::new();
+    let root = parse_document(&arena, body, &flavor.to_comrak_options());
+
+    let (mut sentences, mut words, mut syllables) = (0, 0, 0);
+    let mut text = String::new();
+    for node in root.descendants() {
+        if !matches!(node.data.borrow().value, NodeValue::Paragraph) {
+            continue;
+        }
+        text.clear();
+        prose_text(node, &mut text);
+        for sentence in split_sentences(&text) {
+            let sentence_words: Vec<&str> = sentence.unicode_words().collect();
+            if sentence_words.is_empty() {
+                continue;
+            }
+            sentences += 1;
+            words += sentence_words.len();
+            syllables += sentence_words
+                .iter()
+                .map(|w| syllable_count(w))
+                .sum::<usize>();
+        }
+    }
+
+    let one_decimal = |value: f64| (value * 10.0).round() / 10.0;
+    let (avg_sentence_length, flesch_kincaid_grade) = if sentences == 0 {
+        (0.0, None)
+    } else {
+        let words_per_sentence = words as f64 / sentences as f64;
+        let syllables_per_word = syllables as f64 / words as f64;
+        let grade = 0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59;
+        (one_decimal(words_per_sentence), Some(one_decimal(grade)))
+    };
+
+    Readability {
+        reading_time_secs: (word_count as f64 / WORDS_PER_MINUTE * 60.0).ceil() as u64,
+        sentence_count: sentences,
+        avg_sentence_length,
+        flesch_kincaid_grade,
+    }
+}
+
+/// The prose of an inline container, with code, raw HTML, math, images and bare URLs left
+/// out as in the `prose` word count
+fn prose_text<'a>(node: &'a AstNode<'a>, out: &mut String) {
+    for child in node.children() {
+        match &child.data.borrow().value {
+            NodeValue::Text(text) => out.push_str(text),
+            NodeValue::SoftBreak | NodeValue::LineBreak => out.push(' '),
+            NodeValue::Code(_)
+            | NodeValue::HtmlInline(_)
+            | NodeValue::Math(_)
+            | NodeValue::Image(_)
+            | NodeValue::FootnoteReference(_) => {},
+            NodeValue::Link(link) if is_bare_url(child, &link.url) => {},
+            _ => prose_text(child, out),
+        }
+    }
+}
+
+/// Splits at sentence-ending punctuation followed by whitespace or the end of the text
+fn split_sentences(text: &str) -> Vec<&str> {
+    let is_terminal = |c: char| matches!(c, '.' | '!' | '?' | '…' | '。' | '！' | '？');
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if !is_terminal(c) {
+            continue;
+        }
+        match chars.peek() {
+            Some(&(_, next)) if is_terminal(next) || !next.is_whitespace() => {},
+            _ => {
+                let end = index + c.len_utf8();
+                sentences.push(&text[start..end]);
+                start = end;
+            },
+        }
+    }
+    sentences.push(&text[start..]);
+    sentences
+}
+
+/// Vowel groups, less a silent final `e`; at least one per word
+fn syllable_count(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let mut count = 0;
+    let mut after_vowel = false;
+    for c in word.chars() {
+        let vowel = matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+        if vowel && !after_vowel {
+            count += 1;
+        }
+        after_vowel = vowel;
+    }
+    if count > 1 && word.ends_with('e') && !word.ends_with("le") {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+#[derive(Debug, Clone, Default)]
+struct LineStats {
+    text: String,
+    words: usize,
+    chars: usize,
+    width: usize,
+    utf16_len: usize,
+}
+
+impl LineStats {
+    fn measure(text: &str) -> Self {
+        let line = text.strip_suffix('\r').unwrap_or(text);
+        Self {
+            text: text.to_string(),
+            words: line.unicode_words().count(),
+            chars: line.graphemes(true).count(),
+            width: line.width(),
+            utf16_len: text.encode_utf16().count(),
+        }
+    }
+}
+
+/// Replaces lines `from_line..to_line` (0-based, end exclusive) of the previous text with
+/// `text`, which may itself span several lines.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LineEdit {
+    pub from_line: usize,
+    pub to_line: usize,
+    pub text: String,
+}
+
+/// Where a cursor sits in a cached document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CursorPosition {
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column in display width
+    pub column: usize,
+    /// Display width of the cursor's line
+    pub line_width: usize,
+    /// Characters (grapheme clusters) before the cursor
+    pub char_offset: usize,
+    /// Words before the cursor, counting one it is inside
+    pub word_index: usize,
+}
+
+fn next_revision() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Per-line metrics for one open document, updated in place as edits arrive so a keystroke
+/// only re-measures the lines it touched. The lines are kept too, so the status bar can be
+/// answered from a cursor offset alone.
+#[derive(Debug)]
+pub struct DocumentMetrics {
+    lines: Vec<LineStats>,
+    /// Changed by every edit, and unique across documents, so a word count taken outside
+    /// the lock can tell it is stale
+    revision: u64,
+    /// Last parsed word count, as (revision, mode, flavor, count)
+    word_count: Option<(u64, WordCountMode, MarkdownFlavor, usize)>,
+    /// When the editor last sent this document, for evicting the least recently used
+    last_used: Instant,
+}
+
+impl DocumentMetrics {
+    pub fn from_content(content: &str) -> Self {
+        Self {
+            lines: content.split('\n').map(LineStats::measure).collect(),
+            revision: next_revision(),
+            word_count: None,
+            last_used: Instant::now(),
+        }
+    }
+
+    pub fn last_used(&self) -> Instant {
+        self.last_used
+    }
+
+    pub fn touch(&mut self) {
+        self.last_used = Instant::now();
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Heap bytes held for the lines and their stats
+    pub fn estimated_bytes(&self) -> usize {
+        self.lines.capacity() * std::mem::size_of::<LineStats>()
+            + self.lines.iter().map(|l| l.text.capacity()).sum::<usize>()
+    }
+
+    /// Applies an edit, returning false if it does not fit the cached line count.
+    pub fn apply(&mut self, edit: &LineEdit) -> bool {
+        if edit.from_line > edit.to_line || edit.to_line > self.lines.len() {
+            return false;
+        }
+        let replacement = edit.text.split('\n').map(LineStats::measure);
+        self.lines.splice(edit.from_line..edit.to_line, replacement);
+        self.revision = next_revision();
+        true
+    }
+
+    /// Length in UTF-16 code units, comparable to the editor's string length. CRLF line
+    /// endings are not tracked, so callers should send LF-normalized text.
+    pub fn utf16_len(&self) -> usize {
+        self.lines.iter().map(|l| l.utf16_len).sum::<usize>() + self.lines.len().saturating_sub(1)
+    }
+
+    /// Same shape as `calculate_text_metrics`: (line_count, word_count, char_count, widest_column)
+    pub fn totals(&self) -> (usize, usize, usize, usize) {
+        if self.lines.len() == 1 && self.lines[0].utf16_len == 0 {
+            return (0, 0, 0, 0);
+        }
+        let newlines = self.lines.len() - 1;
+        let (words, chars, width) = self.lines.iter().fold((0, 0, 0), |(w, c, x), l| {
+            (w + l.words, c + l.chars, x.max(l.width))
+        });
+        (self.lines.len(), words, chars + newlines, width)
+    }
+
+    /// The cursor at `utf16_offset`, clamped to the end of the document
+    pub fn cursor(&self, utf16_offset: usize) -> CursorPosition {
+        let mut remaining = utf16_offset;
+        let (mut chars, mut words) = (0, 0);
+        let last = self.lines.len() - 1;
+        for (i, line) in self.lines.iter().enumerate() {
+            if remaining <= line.utf16_len || i == last {
+                let before = &line.text[..utf16_to_byte_offset(&line.text, remaining)];
+                return CursorPosition {
+                    line: i + 1,
+                    column: before.width() + 1,
+                    line_width: line.width,
+                    char_offset: chars + before.graphemes(true).count(),
+                    word_index: words + before.unicode_words().count(),
+                };
+            }
+            remaining -= line.utf16_len + 1;
+            // The newline is one character
+            chars += line.chars + 1;
+            words += line.words;
+        }
+        CursorPosition::default()
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(|l| l.text.as_str())
+    }
+
+    pub fn text(&self) -> String {
+        self.lines().collect::<Vec<_>>().join("\n")
+    }
+
+    /// The word count stored for the current revision under `mode`, if any. `All` comes
+    /// straight from the line stats; the other modes parse the text, see `store_word_count`.
+    pub fn word_count(&self, mode: WordCountMode, flavor: MarkdownFlavor) -> Option<usize> {
+        if mode == WordCountMode::All {
+            return Some(self.lines.iter().map(|l| l.words).sum());
+        }
+        self.word_count
+            .filter(|&(rev, m, f, _)| rev == self.revision && m == mode && f == flavor)
+            .map(|(.., count)| count)
+    }
+
+    /// Keeps a word count taken at `revision`, unless the document has moved on since
+    pub fn store_word_count(
+        &mut self,
+        revision: u64,
+        mode: WordCountMode,
+        flavor: MarkdownFlavor,
+        count: usize,
+    ) {
+        if revision == self.revision {
+            self.word_count = Some((revision, mode, flavor, count));
+        }
+    }
+}
+
+/// Counts words, grapheme clusters, and lines across all non-empty selections. Overlapping
+/// ranges are merged first so multi-cursor selections are not double counted.
+pub fn compute_selection_metrics(content: &str, ranges: &[SelectionRange]) -> SelectionMetrics {
+    let mut spans: Vec<(usize, usize)> = ranges
+        .iter()
+        .map(|r| (r.from.min(r.to), r.from.max(r.to)))
+        .filter(|(from, to)| from < to)
+        .collect();
+    spans.sort_unstable();
+
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(spans.len());
+    for (from, to) in spans {
+        match merged.last_mut() {
+            Some(last) if from <= last.1 => last.1 = last.1.max(to),
+            _ => merged.push((from, to)),
+        }
+    }
+
+    let mut metrics = SelectionMetrics {
+        ranges: merged.len(),
+        ..Default::default()
+    };
+
+    for (from, to) in merged {
+        let start = utf16_to_byte_offset(content, from);
+        let end = utf16_to_byte_offset(content, to);
+        let text = &content[start..end];
+
+        metrics.words += text.unicode_words().count();
+        metrics.chars += text.graphemes(true).count();
+        metrics.lines += text.matches('\n').count() + 1;
+    }
+
+    metrics.reading_time_secs = (metrics.words as f64 / WORDS_PER_MINUTE * 60.0).ceil() as u64;
+    metrics
+}
+
+/// Converts a UTF-16 offset to a byte offset on a char boundary, clamped to the content.
+/// An offset that splits a surrogate pair snaps to the start of that character.
+pub fn utf16_to_byte_offset(content: &str, utf16_offset: usize) -> usize {
+    let mut units = 0;
+    for (byte_idx, ch) in content.char_indices() {
+        if units + ch.len_utf16() > utf16_offset {
+            return byte_idx;
+        }
+        units += ch.len_utf16();
+    }
+    content.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAMILY: &str = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+
+    fn range(from: usize, to: usize) -> SelectionRange {
+        SelectionRange { from, to }
+    }
+
+    #[test]
+    fn line_map_counts_graphemes_and_display_width() {
+        let content = format!("👍 ok\n漢字テスト\r\n{}", FAMILY);
+        let (line_map, lines, words, chars, widest) = build_line_map_and_metrics(&content);
+
+        assert_eq!(line_map, vec![0, 8, 25]);
+        assert_eq!(lines, 3);
+        // "ok", each ideograph, and the katakana run; emoji are not words
+        assert_eq!(words, 4);
+        // 4 + newline + 5 + CRLF + one ZWJ family
+        assert_eq!(chars, 12);
+        // Wide CJK text counts two columns per character
+        assert_eq!(widest, 10);
+    }
+
+    #[test]
+    fn zwj_sequence_is_one_character() {
+        let (_, lines, words, chars, widest) = build_line_map_and_metrics(FAMILY);
+        assert_eq!((lines, words, chars, widest), (1, 0, 1, 2));
+
+        let (_, _, _, chars, widest) = build_line_map_and_metrics("👍🏽 e\u{301}");
+        assert_eq!((chars, widest), (3, 4));
+    }
+
+    #[test]
+    fn empty_document_has_one_line() {
+        assert_eq!(build_line_map_and_metrics(""), (vec![0], 0, 0, 0, 0));
+    }
+
+    #[test]
+    fn selection_offsets_are_utf16() {
+        // "漢字" is two UTF-16 units
+        let metrics = compute_selection_metrics("漢字テスト", &[range(0, 2)]);
+        assert_eq!((metrics.words, metrics.chars, metrics.lines), (2, 2, 1));
+
+        // The emoji is a surrogate pair
+        let metrics = compute_selection_metrics("a👍b", &[range(1, 3)]);
+        assert_eq!((metrics.words, metrics.chars), (0, 1));
+    }
+
+    #[test]
+    fn selection_splitting_a_surrogate_pair_snaps_back() {
+        let metrics = compute_selection_metrics("a👍b", &[range(0, 2)]);
+        assert_eq!(metrics.chars, 1);
+    }
+
+    #[test]
+    fn selection_over_zwj_sequence() {
+        let content = format!("x{}y", FAMILY);
+        let metrics = compute_selection_metrics(&content, &[range(1, 9)]);
+        assert_eq!((metrics.words, metrics.chars, metrics.lines), (0, 1, 1));
+    }
+
+    #[test]
+    fn overlapping_selections_are_merged() {
+        let content = format!("x{}y\nz", FAMILY);
+        let metrics = compute_selection_metrics(&content, &[range(6, 0), range(3, 12)]);
+        assert_eq!(metrics.ranges, 1);
+        assert_eq!((metrics.chars, metrics.lines), (5, 2));
+    }
+
+    #[test]
+    fn cursor_column_is_display_width() {
+        let doc = DocumentMetrics::from_content("ab\n漢字x\n");
+        // After "漢字" on the second line: 3 + 2 UTF-16 units
+        assert_eq!(
+            doc.cursor(5),
+            CursorPosition {
+                line: 2,
+                column: 5,
+                line_width: 5,
+                char_offset: 5,
+                word_index: 3,
+            }
+        );
+        assert_eq!(doc.cursor(100).line, 3);
+    }
+
+    #[test]
+    fn cursor_follows_line_edits() {
+        let mut doc = DocumentMetrics::from_content("one\ntwo\nthree");
+        assert!(doc.apply(&LineEdit {
+            from_line: 1,
+            to_line: 2,
+            text: "2\n👍two".to_string(),
+        }));
+        assert_eq!(doc.text(), "one\n2\n👍two\nthree");
+        assert_eq!(doc.utf16_len(), 17);
+        // Just after the emoji, a surrogate pair two columns wide
+        let cursor = doc.cursor(8);
+        assert_eq!(
+            (cursor.column, cursor.char_offset, cursor.word_index),
+            (3, 7, 2)
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::markdown::highlight;
+use crate::markdown::renderer::percent_decode;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::UNIX_EPOCH;
+use tauri::http::{Request, Response, StatusCode, header};
+
+/// URI scheme of everything the preview loads from the app rather than the web:
+/// `preview://localhost/file/<path>` for local images,
+/// `preview://localhost/diagram/<id>.svg` for rendered diagrams and
+/// `preview://localhost/style/highlight.css` for highlighted code
+pub const SCHEME: &str = "preview";
+
+const MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
+const MAX_REGISTERED_DIAGRAMS: usize = 128;
+/// Enough for the images of every open preview; older entries are re-allowed when their
+/// document renders again
+const MAX_ALLOWED_FILES: usize = 1024;
+
+/// Applied to every response: SVGs opened directly can't run script or load anything
+const CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; img-src data:; style-src 'unsafe-inline'";
+
+/// Local files recently rendered documents reference, oldest first; nothing else on disk
+/// is served
+static ALLOWED_FILES: LazyLock<Mutex<VecDeque<PathBuf>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(MAX_ALLOWED_FILES)));
+
+/// Rendered diagrams by content id, oldest first
+static DIAGRAMS: LazyLock<Mutex<VecDeque<(String, Arc<Vec<u8>>)>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(MAX_REGISTERED_DIAGRAMS)));
+
+fn base_url() -> &'static str {
+    if cfg!(any(windows, target_os = "android")) {
+        "http://preview.localhost"
+    } else {
+        "preview://localhost"
+    }
+}
+
+/// The URL the preview loads the local file `path` from. The file is only served once
+/// `allow_files` has been called for it.
+pub fn file_url(path: &Path) -> String {
+    let mut encoded = String::new();
+    for byte in path.to_string_lossy().bytes() {
+        // encodeURIComponent's unreserved set
+        if byte.is_ascii_alphanumeric() || b"-_.!~*'()".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    format!("{}/file/{}", base_url(), encoded)
+}
+
+/// Keeps a rendered diagram available to the preview and returns its URL. The id is
+/// derived from the content, so the same id always serves the same bytes.
+pub fn register_diagram(id: &str, svg: &Arc<Vec<u8>>) -> String {
+    let mut diagrams = DIAGRAMS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(index) = diagrams.iter().position(|(existing, _)| existing == id) {
+        let entry = diagrams.remove(index).expect("index from position");
+        diagrams.push_back(entry);
+    } else {
+        if diagrams.len() >= MAX_REGISTERED_DIAGRAMS {
+            diagrams.pop_front();
+        }
+        diagrams.push_back((id.to_string(), Arc::clone(svg)));
+    }
+    format!("{}/diagram/{}.svg", base_url(), id)
+}
+
+/// The URL of the stylesheet for highlighted code
+pub fn highlight_css_url() -> String {
+    format!("{}/style/highlight.css", base_url())
+}
+
+/// Lets the preview load the given local files, as reported in a render's `local_images`.
+/// Only the most recently allowed files stay allowed.
+pub fn allow_files(paths: &[String]) {
+    let mut allowed = ALLOWED_FILES.lock().unwrap_or_else(|e| e.into_inner());
+    for path in paths.iter().map(PathBuf::from) {
+        if let Some(index) = allowed.iter().position(|existing| *existing == path) {
+            allowed.remove(index);
+        } else if allowed.len() >= MAX_ALLOWED_FILES {
+            allowed.pop_front();
+        }
+        allowed.push_back(path);
+    }
+}
+
+/// Answers a request on the `preview` scheme
+pub fn handle(request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let path = request.uri().path();
+    let if_none_match = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok());
+
+    let result = if let Some(encoded) = path.strip_prefix("/file/") {
+        serve_file(&PathBuf::from(percent_decode(encoded)), if_none_match)
+    } else if let Some(name) = path.strip_prefix("/diagram/") {
+        serve_diagram(name.trim_end_matches(".svg"), if_none_match)
+    } else if path == "/style/highlight.css" {
+        serve_stylesheet(highlight::stylesheet(), if_none_match)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    };
+    result.unwrap_or_else(|status| {
+        if status != StatusCode::NOT_MODIFIED {
+            log::debug!("[Preview] {} {}", status.as_u16(), path);
+        }
+        response(status, None, "no-store", None, Vec::new())
+    })
+}
+
+fn serve_file(path: &Path, if_none_match: Option<&str>) -> Result<Response<Vec<u8>>, StatusCode> {
+    let allowed = ALLOWED_FILES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .any(|allowed| allowed == path);
+    if !allowed {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    // Judged by the file a link points to, so `photo.png -> ~/.ssh/id_rsa` is refused
+    let target = fs::canonicalize(path).map_err(|_| StatusCode::NOT_FOUND)?;
+    let mime = image_mime(&target).ok_or(StatusCode::FORBIDDEN)?;
+    let metadata = fs::metadata(&target).map_err(|_| StatusCode::NOT_FOUND)?;
+    if !metadata.is_file() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if metadata.len() > MAX_FILE_BYTES {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos());
+    let etag = format!("\"{:x}-{:x}\"", metadata.len(), modified);
+    if if_none_match == Some(etag.as_str()) {
+        return Err(StatusCode::NOT_MODIFIED);
+    }
+    let bytes = fs::read(&target).map_err(|_| StatusCode::NOT_FOUND)?;
+    // Files can change on disk, so the webview revalidates every time
+    Ok(response(
+        StatusCode::OK,
+        Some(mime),
+        "no-cache",
+        Some(&etag),
+        bytes,
+    ))
+}
+
+fn serve_diagram(id: &str, if_none_match: Option<&str>) -> Result<Response<Vec<u8>>, StatusCode> {
+    let etag = format!("\"{}\"", id);
+    if if_none_match == Some(etag.as_str()) {
+        return Err(StatusCode::NOT_MODIFIED);
+    }
+    let svg = DIAGRAMS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find(|(existing, _)| existing == id)
+        .map(|(_, svg)| Arc::clone(svg))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(response(
+        StatusCode::OK,
+        Some("image/svg+xml"),
+        "max-age=31536000, immutable",
+        Some(&etag),
+        svg.as_ref().clone(),
+    ))
+}
+
+/// Built into the app, so it only changes with a new version
+fn serve_stylesheet(
+    css: &str,
+    if_none_match: Option<&str>,
+) -> Result<Response<Vec<u8>>, StatusCode> {
+    let etag = format!("\"{}\"", env!("CARGO_PKG_VERSION"));
+    if if_none_match == Some(etag.as_str()) {
+        return Err(StatusCode::NOT_MODIFIED);
+    }
+    Ok(response(
+        StatusCode::OK,
+        Some("text/css"),
+        "no-cache",
+        Some(&etag),
+        css.as_bytes().to_vec(),
+    ))
+}
+
+fn response(
+    status: StatusCode,
+    mime: Option<&str>,
+    cache_control: &str,
+    etag: Option<&str>,
+    body: Vec<u8>,
+) -> Response<Vec<u8>> {
+    let mut builder = Response::builder()
+        .status(status)
+        .header(header::CACHE_CONTROL, cache_control)
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(header::CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY);
+    if let Some(mime) = mime {
+        builder = builder.header(header::CONTENT_TYPE, mime);
+    }
+    if let Some(etag) = etag {
+        builder = builder.header(header::ETAG, etag);
+    }
+    builder.body(body).unwrap_or_else(|e| {
+        log::error!("[Preview] Failed to build response: {}", e);
+        Response::new(Vec::new())
+    })
+}
+
+/// Only images are served, whatever a document links to
+fn image_mime(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        _ => return None,
+    })
+}
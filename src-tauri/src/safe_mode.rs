@@ -0,0 +1,90 @@
+use crate::capture;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+pub const SAFE_MODE_FLAG: &str = "--safe-mode";
+const SENTINEL_FILE: &str = "startup.sentinel";
+/// Holds `running` while the app is up and `clean` once it has shut down gracefully
+const SHUTDOWN_MARKER_FILE: &str = "shutdown.marker";
+const MARKER_RUNNING: &str = "running";
+const MARKER_CLEAN: &str = "clean";
+// Consecutive launches that never reached the frontend before safe mode kicks in
+const CRASH_THRESHOLD: u32 = 3;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SafeMode {
+    pub active: bool,
+    pub reason: Option<String>,
+    /// The previous run ended without going through the shutdown pipeline
+    pub unclean_shutdown: bool,
+}
+
+/// Records a startup attempt in the sentinel file and decides whether this launch should
+/// run in safe mode. The sentinel is cleared by `mark_startup_complete` once the UI is up,
+/// so a count that keeps growing means startup keeps crashing.
+pub fn begin_startup(local_dir: &Path, args: &[String]) -> SafeMode {
+    let sentinel = local_dir.join(SENTINEL_FILE);
+    let failed_starts = fs::read_to_string(&sentinel)
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok())
+        .unwrap_or(0);
+
+    if let Err(e) = fs::write(&sentinel, (failed_starts + 1).to_string()) {
+        log::warn!("Failed to write startup sentinel: {}", e);
+    }
+
+    let marker = local_dir.join(SHUTDOWN_MARKER_FILE);
+    let unclean_shutdown = fs::read_to_string(&marker).is_ok_and(|s| s.trim() == MARKER_RUNNING);
+    if let Err(e) = fs::write(&marker, MARKER_RUNNING) {
+        log::warn!("Failed to write shutdown marker: {}", e);
+    }
+
+    let mut safe_mode = if args.iter().skip(1).any(|a| a == SAFE_MODE_FLAG) {
+        SafeMode {
+            active: true,
+            reason: Some("Started with --safe-mode".to_string()),
+            ..Default::default()
+        }
+    } else if failed_starts >= CRASH_THRESHOLD {
+        SafeMode {
+            active: true,
+            reason: Some(format!(
+                "The previous {} launches did not finish starting up",
+                failed_starts
+            )),
+            ..Default::default()
+        }
+    } else {
+        SafeMode::default()
+    };
+    safe_mode.unclean_shutdown = unclean_shutdown;
+    safe_mode
+}
+
+/// Marks the run as finished cleanly; the last step of the shutdown pipeline.
+pub fn record_clean_shutdown(local_dir: &Path) {
+    let marker = local_dir.join(SHUTDOWN_MARKER_FILE);
+    if let Err(e) = fs::write(&marker, MARKER_CLEAN) {
+        log::warn!("Failed to write shutdown marker: {}", e);
+    }
+}
+
+pub fn mark_startup_complete(local_dir: &Path) {
+    let sentinel = local_dir.join(SENTINEL_FILE);
+    if let Err(e) = fs::remove_file(&sentinel)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        log::warn!("Failed to clear startup sentinel: {}", e);
+    }
+}
+
+/// Command-line arguments that name files to open, with launch flags and capture links
+/// removed.
+pub fn file_args(args: &[String]) -> Vec<String> {
+    args.iter()
+        .skip(1)
+        .filter(|a| a.as_str() != SAFE_MODE_FLAG && !capture::is_capture_url(a))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,114 @@
+use crate::commands::autosave::{self, SaveReason};
+use crate::commands::spellcheck;
+use crate::instance_lock;
+use crate::safe_mode;
+use crate::state::AppState;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+/// How long to wait for the frontend to write the session before exiting anyway
+const SESSION_FLUSH_TIMEOUT: Duration = Duration::from_secs(3);
+const FLUSH_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static FINISHED: AtomicBool = AtomicBool::new(false);
+
+/// Once the pipeline has run, window closes and exit requests are let through
+pub fn is_finished() -> bool {
+    FINISHED.load(Ordering::SeqCst)
+}
+
+/// Starts the shutdown pipeline, then exits. The caller must have prevented the close or
+/// exit that triggered it; repeat calls while it runs are ignored.
+pub fn begin(app_handle: &tauri::AppHandle, reason: SaveReason) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        run(&app_handle, reason).await;
+        FINISHED.store(true, Ordering::SeqCst);
+        app_handle.exit(0);
+    });
+}
+
+/// Flushes the session, checkpoints the WAL, writes out custom words, releases the
+/// instance lock, and records the clean-shutdown marker, in that order. Failures are
+/// logged and never block the exit.
+async fn run(app_handle: &tauri::AppHandle, reason: SaveReason) {
+    let start = Instant::now();
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        // Setup failed before state existed; there is nothing to flush
+        return;
+    };
+
+    let session_flushed = flush_session(app_handle, &state, reason).await;
+    if !session_flushed {
+        log::warn!(
+            "Session save did not finish within {:?}",
+            SESSION_FLUSH_TIMEOUT
+        );
+    }
+
+    match state.db.checkpoint() {
+        Ok(frames_left) => log::debug!("WAL checkpoint | frames_left={}", frames_left),
+        Err(e) => log::warn!("WAL checkpoint failed: {}", e),
+    }
+
+    match spellcheck::persist_custom_dictionary(app_handle).await {
+        Ok(0) => {},
+        Ok(count) => log::info!("Saved {} custom dictionary words at exit", count),
+        Err(e) => log::warn!("Failed to save custom dictionary: {}", e),
+    }
+
+    let lock_path = state
+        .instance
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .lock_path
+        .clone();
+    instance_lock::release(std::path::Path::new(&lock_path));
+
+    match app_handle.path().app_local_data_dir() {
+        Ok(local_dir) => safe_mode::record_clean_shutdown(&local_dir),
+        Err(e) => log::warn!(
+            "Failed to get local data directory for shutdown marker: {}",
+            e
+        ),
+    }
+
+    log::info!(
+        "[Shutdown] complete | duration={:?} | reason={:?} | session_flushed={}",
+        start.elapsed(),
+        reason,
+        session_flushed
+    );
+}
+
+/// Asks the frontend to save and waits until it has, or until nothing is left unsaved.
+/// Returns false on timeout.
+async fn flush_session(
+    app_handle: &tauri::AppHandle,
+    state: &AppState,
+    reason: SaveReason,
+) -> bool {
+    let saves_before = state
+        .session_autosave
+        .lock()
+        .map_or(0, |autosave| autosave.saves());
+    autosave::request_save(app_handle, reason);
+
+    let deadline = Instant::now() + SESSION_FLUSH_TIMEOUT;
+    while Instant::now() < deadline {
+        let pending = state
+            .session_autosave
+            .lock()
+            .is_ok_and(|autosave| autosave.is_dirty() && autosave.saves() == saves_before);
+        if !pending {
+            return true;
+        }
+        tokio::time::sleep(FLUSH_POLL_INTERVAL).await;
+    }
+    false
+}
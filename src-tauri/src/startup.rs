@@ -0,0 +1,109 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupPhase {
+    pub name: String,
+    pub duration_ms: f64,
+    /// Ran in the background after setup instead of delaying the window
+    pub deferred: bool,
+    /// Milliseconds from process start to the end of the phase
+    pub finished_at_ms: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StartupReport {
+    pub phases: Vec<StartupPhase>,
+    /// Time spent inside Tauri's `setup()`
+    pub setup_ms: Option<f64>,
+    /// Time until the frontend reported it was interactive
+    pub interactive_ms: Option<f64>,
+}
+
+/// Collects phase timings for the current launch. Managed as Tauri state before the
+/// logger and database exist so every phase can be recorded.
+pub struct StartupProfiler {
+    started: Instant,
+    report: Mutex<StartupReport>,
+}
+
+impl Default for StartupProfiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl StartupProfiler {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            report: Mutex::new(StartupReport::default()),
+        }
+    }
+
+    /// Runs `f` as a setup phase and records how long it took.
+    pub fn time<T>(&self, name: &str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(name, start.elapsed(), false);
+        result
+    }
+
+    pub fn record(&self, name: &str, duration: Duration, deferred: bool) {
+        let finished_at_ms = millis(self.started.elapsed());
+        log::debug!(
+            "[Startup] {} | duration={:?} | deferred={}",
+            name,
+            duration,
+            deferred
+        );
+        if let Ok(mut report) = self.report.lock() {
+            report.phases.push(StartupPhase {
+                name: name.to_string(),
+                duration_ms: millis(duration),
+                deferred,
+                finished_at_ms,
+            });
+        }
+    }
+
+    /// Records a deferred phase once; later runs (e.g. reloading dictionaries) are not
+    /// part of startup.
+    pub fn record_deferred_once(&self, name: &str, duration: Duration) {
+        let seen = self
+            .report
+            .lock()
+            .is_ok_and(|report| report.phases.iter().any(|p| p.name == name));
+        if !seen {
+            self.record(name, duration, true);
+        }
+    }
+
+    pub fn finish_setup(&self) {
+        if let Ok(mut report) = self.report.lock() {
+            report.setup_ms = Some(millis(self.started.elapsed()));
+        }
+    }
+
+    pub fn mark_interactive(&self) {
+        let elapsed = millis(self.started.elapsed());
+        if let Ok(mut report) = self.report.lock()
+            && report.interactive_ms.is_none()
+        {
+            report.interactive_ms = Some(elapsed);
+            log::info!("[Startup] interactive | elapsed={:.1}ms", elapsed);
+        }
+    }
+
+    pub fn report(&self) -> StartupReport {
+        self.report
+            .lock()
+            .map(|report| report.clone())
+            .unwrap_or_default()
+    }
+}
@@ -1,6 +1,7 @@
 use crate::db::Database;
 use spellbook::Dictionary;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::AtomicUsize;
 use tokio::sync::Mutex;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -11,9 +12,47 @@ pub enum SpellcheckStatus {
     Failed,
 }
 
+impl SpellcheckStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SpellcheckStatus::Uninitialized => "uninitialized",
+            SpellcheckStatus::Loading => "loading",
+            SpellcheckStatus::Ready => "ready",
+            SpellcheckStatus::Failed => "failed",
+        }
+    }
+}
+
 pub struct AppState {
     pub db: Database,
     pub speller: Mutex<Option<Dictionary>>,
     pub custom_dict: Mutex<HashSet<String>>,
     pub spellcheck_status: Mutex<SpellcheckStatus>,
+    /// Language codes and specialist ids merged into `speller`, in load order
+    pub loaded_dictionaries: Mutex<Vec<String>>,
+    /// Rough heap size of `speller`, measured from the merged word list when it was built
+    pub speller_bytes: AtomicUsize,
+    /// Canonical document path -> export preset name, re-exported on every save
+    pub export_watches: Mutex<HashMap<String, String>>,
+    /// Set at launch; skips custom themes, dictionaries, and stored tab content
+    pub safe_mode: crate::safe_mode::SafeMode,
+    /// Whether this instance owns the data folder. A std mutex because the sync
+    /// instance commands read and update it.
+    pub instance: std::sync::Mutex<crate::instance_lock::InstanceStatus>,
+    /// Document id -> per-line metrics maintained by incremental updates
+    pub text_metrics: Mutex<HashMap<String, crate::markdown::text_metrics::DocumentMetrics>>,
+    /// Path identity -> id of the tab showing it. A std mutex because the
+    /// single-instance callback reads it outside the async runtime.
+    pub open_paths: std::sync::Mutex<crate::commands::open_paths::OpenPaths>,
+    /// Changes since the last session save. A std mutex because window events are
+    /// handled outside the async runtime.
+    pub session_autosave: std::sync::Mutex<crate::commands::autosave::SessionAutosave>,
+    /// Cancellation flags of running long commands, by job id
+    pub jobs: crate::commands::jobs::JobRegistry,
+    /// Session id -> writing session in progress
+    pub writing_sessions:
+        std::sync::Mutex<HashMap<String, crate::commands::writing::WritingSession>>,
+    /// Capture prompts waiting for the frontend. A std mutex because the tray, hotkey and
+    /// link handlers run outside the async runtime.
+    pub capture_queue: std::sync::Mutex<crate::capture::CaptureQueue>,
 }
@@ -1,7 +1,9 @@
 use anyhow::Result;
 use chrono::{DateTime, Local};
-use encoding_rs::{UTF_16BE, UTF_16LE};
-use std::path::Path;
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE};
+use path_clean::PathClean;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
@@ -31,6 +33,41 @@ pub fn handle_error(context: Option<&str>, operation: &str, e: impl std::fmt::Di
     msg
 }
 
+/// Escapes text for safe inclusion in HTML element content and attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Replaces characters that are invalid in file names on any supported platform
+pub fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let sanitized = sanitized.trim_end_matches(['.', ' ']);
+    if sanitized.is_empty() {
+        "untitled".to_string()
+    } else {
+        sanitized.to_string()
+    }
+}
+
 pub fn format_system_time(time: std::io::Result<SystemTime>) -> Option<String> {
     time.ok().map(|t| {
         let datetime: DateTime<Local> = t.into();
@@ -71,6 +108,44 @@ pub fn validate_path(path: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Key for telling whether two paths name the same document. With `resolve_links`,
+/// symlinks and junctions are followed to their target; otherwise only `.`/`..` segments
+/// are normalized. Case-insensitive on Windows. Missing files fall back to the cleaned path.
+pub fn path_identity(path: &str, resolve_links: bool) -> String {
+    let resolved = if resolve_links {
+        dunce::canonicalize(path).ok()
+    } else {
+        None
+    };
+    let key = resolved
+        .or_else(|| std::path::absolute(path).ok())
+        .map(|p| p.clean().to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    if cfg!(windows) {
+        key.to_lowercase()
+    } else {
+        key
+    }
+}
+
+/// Where a write to `path` should land. `atomic_write` renames a temp file over its
+/// target, which would replace a symlink with a regular file; following the link writes
+/// to the file it points at instead and leaves the link in place.
+pub async fn write_target(path: &Path, follow_links: bool) -> PathBuf {
+    if !follow_links {
+        return path.to_path_buf();
+    }
+    match fs::symlink_metadata(path).await {
+        Ok(meta) if meta.file_type().is_symlink() => {
+            dunce::canonicalize(path).unwrap_or_else(|e| {
+                log::warn!("Symlink {:?} has no reachable target: {}", path, e);
+                path.to_path_buf()
+            })
+        },
+        _ => path.to_path_buf(),
+    }
+}
+
 /// atomic_write writes content to a temporary file and then renames it to the target path.
 /// This ensures that the target file is not corrupted if the write fails or is interrupted.
 pub async fn atomic_write(path: &Path, content: &[u8]) -> std::io::Result<()> {
@@ -139,6 +214,77 @@ pub async fn cleanup_stale_temp_files(
     Ok(())
 }
 
+/// Dominant line-ending style of `content`: "CRLF" when CRLF pairs outnumber bare LFs.
+pub fn detect_line_ending(content: &str) -> &'static str {
+    let crlf = content.matches("\r\n").count();
+    let lf_only = content.matches('\n').count() - crlf;
+    if crlf > 0 && crlf >= lf_only {
+        "CRLF"
+    } else {
+        "LF"
+    }
+}
+
+/// Decodes file bytes: by BOM when there is one, as UTF-8 when valid, otherwise with
+/// chardetng's best guess. Returns the text, its encoding and whether a BOM was removed.
+pub fn decode_text(bytes: &[u8]) -> (Cow<'_, str>, &'static Encoding, bool) {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        let (cow, _) = encoding.decode_with_bom_removal(bytes);
+        return (cow, encoding, true);
+    }
+
+    let (cow, _, had_errors) = UTF_8.decode(bytes);
+    if !had_errors {
+        return (cow, UTF_8, false);
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, false);
+    let (cow, _, _) = encoding.decode(bytes);
+    (cow, encoding, false)
+}
+
+/// Encodes text back to the bytes it was read from, optionally with a BOM. encoding_rs
+/// only encodes UTF-16 as UTF-8, so UTF-16 is handled by hand.
+pub fn encode_text(content: &str, encoding: &str, with_bom: bool) -> Result<Vec<u8>, String> {
+    let encoding = Encoding::for_label(encoding.as_bytes())
+        .ok_or_else(|| format!("Unsupported encoding: {}", encoding))?;
+
+    let mut bytes = Vec::with_capacity(content.len() + 3);
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        if with_bom {
+            bytes.extend_from_slice(if encoding == UTF_16LE {
+                &[0xFF, 0xFE]
+            } else {
+                &[0xFE, 0xFF]
+            });
+        }
+        for unit in content.encode_utf16() {
+            let pair = if encoding == UTF_16LE {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            };
+            bytes.extend_from_slice(&pair);
+        }
+        return Ok(bytes);
+    }
+
+    if with_bom && encoding == UTF_8 {
+        bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+    }
+    let (encoded, _, had_unmappable) = encoding.encode(content);
+    if had_unmappable {
+        return Err(format!(
+            "Text contains characters that cannot be saved as {}",
+            encoding.name()
+        ));
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
 /// Reads text file with automatic BOM (Byte Order Mark) detection and stripping.
 /// Handles UTF-8, UTF-16LE, and UTF-16BE encoded files.
 pub fn read_text_with_bom_detection(raw_bytes: &[u8]) -> String {
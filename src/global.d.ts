@@ -5,6 +5,8 @@ export interface AppEditorView extends EditorView {
     _currentTabId?: string;
     getHistoryState?: () => HistoryState | undefined;
     flushPendingContent?: () => void;
+    /** Drops the undo and redo stacks of the tab on screen */
+    clearHistory?: () => void;
 }
 
 declare global {
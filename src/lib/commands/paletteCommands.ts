@@ -11,6 +11,7 @@ import { addBookmark } from '$lib/stores/bookmarkStore.svelte';
 import { addTab } from '$lib/stores/editorStore.svelte';
 import {
     openFind,
+    openQuickCapture,
     openReplace,
     toggleAbout,
     toggleBookmarks,
@@ -22,6 +23,7 @@ import {
 } from '$lib/stores/interfaceStore.svelte';
 import { showToast } from '$lib/stores/toastStore.svelte';
 import { appContext } from '$lib/stores/state.svelte';
+import { callBackendSafe } from '$lib/utils/backend';
 import {
     openFile,
     openFileByPath,
@@ -135,6 +137,46 @@ export const exportCommands: Command[] = [
         label: 'Export: PDF',
         action: () => exportService.exportToPdf(),
     },
+    {
+        id: 'export-epub',
+        label: 'Export: EPUB',
+        action: () => exportService.exportToEpub(),
+    },
+    {
+        id: 'export-slides',
+        label: 'Export: Slides (HTML)',
+        action: () => exportService.exportToSlides(),
+    },
+    {
+        id: 'export-mediawiki',
+        label: 'Export: MediaWiki',
+        action: () => exportService.exportToMarkup('mediawiki'),
+    },
+    {
+        id: 'export-rst',
+        label: 'Export: reStructuredText',
+        action: () => exportService.exportToMarkup('rst'),
+    },
+    {
+        id: 'copy-html',
+        label: 'Copy as HTML',
+        action: () => exportService.copyAsHtml(),
+    },
+    {
+        id: 'copy-plain-text',
+        label: 'Copy as Plain Text',
+        action: () => exportService.copyAsPlainText(),
+    },
+    {
+        id: 'copy-mediawiki',
+        label: 'Copy as MediaWiki',
+        action: () => exportService.copyAsMarkup('mediawiki'),
+    },
+    {
+        id: 'copy-rst',
+        label: 'Copy as reStructuredText',
+        action: () => exportService.copyAsMarkup('rst'),
+    },
     {
         id: 'export-png',
         label: 'Export: PNG',
@@ -222,6 +264,11 @@ export const windowCommands: Command[] = [
         label: 'Window: Command Palette',
         action: toggleCommandPalette,
     },
+    {
+        id: 'quick-capture',
+        label: 'Window: Quick Capture to Inbox',
+        action: () => openQuickCapture(),
+    },
     {
         id: 'settings',
         label: 'Window: Settings',
@@ -271,6 +318,18 @@ export const editorCommands: Command[] = [
         label: 'Editor: Go to Line...',
         action: () => dispatchKeyEvent('g', true, false),
     },
+    {
+        id: 'editor.clear_history',
+        label: 'Editor: Clear Undo History',
+        action: async () => {
+            const tabId = appContext.app.activeTabId;
+            if (!tabId) return;
+            window._activeEditorView?.clearHistory?.();
+            // Stored history only exists for scratch tabs, and clearing is a no-op otherwise
+            await callBackendSafe('clear_tab_history', { tabId }, 'Session:Save');
+            showToast('success', 'Undo history cleared');
+        },
+    },
 ];
 
 // Combine all base commands
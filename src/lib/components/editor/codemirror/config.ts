@@ -3,6 +3,7 @@ import { addBookmark } from '$lib/stores/bookmarkStore.svelte';
 import { performTextTransform } from '$lib/stores/editorStore.svelte';
 import { appContext } from '$lib/stores/state.svelte.ts';
 import { showToast } from '$lib/stores/toastStore.svelte';
+import { callBackend } from '$lib/utils/backend';
 import { toggleSelectionComment } from '$lib/utils/commentToggle';
 import { scrollSync } from '$lib/utils/scrollSync.svelte.ts';
 import {
@@ -101,6 +102,44 @@ export function smartCompleteAnyWord(context: CompletionContext): CompletionResu
     };
 }
 
+/**
+ * Completes `:shortcode` with emoji and `\name` with symbols (arrows, math, Greek).
+ * Ranking happens in the backend, so results are the same on every platform.
+ */
+export async function symbolCompletion(
+    context: CompletionContext,
+): Promise<CompletionResult | null> {
+    // Require a non-word character before `:` so times like 10:30 don't trigger it
+    const match = context.matchBefore(/(?:^|[^\w:])(:[\w+-]{2,}|\\[A-Za-z]+)$/);
+    if (!match) return null;
+
+    const trigger = match.text.search(/[:\\][^:\\]*$/);
+    const from = match.from + trigger;
+    const query = match.text.slice(trigger);
+    const kind = query.startsWith(':') ? 'emoji' : 'symbol';
+
+    const results = await callBackend(
+        'search_symbols',
+        { query, kind, limit: 50 },
+        'Editor:Completion',
+        undefined,
+        { ignore: true },
+    );
+    if (!results?.length || context.aborted) return null;
+
+    return {
+        from,
+        filter: false,
+        options: results.map((item) => ({
+            label: (kind === 'emoji' ? ':' : '\\') + item.name,
+            displayLabel: `${item.symbol}  ${item.name}`,
+            detail: item.description,
+            apply: item.symbol,
+            type: 'text',
+        })),
+    };
+}
+
 export function getAutocompletionConfig() {
     if (!appContext.app.enableAutocomplete) return [];
     return autocompletion({
@@ -110,7 +149,7 @@ export function getAutocompletionConfig() {
         defaultKeymap: true,
         aboveCursor: false,
         maxRenderedOptions: 100,
-        override: [smartCompleteAnyWord],
+        override: [symbolCompletion, smartCompleteAnyWord],
     });
 }
 
@@ -1,12 +1,35 @@
 import { appContext } from '$lib/stores/state.svelte.ts';
 import { showToast } from '$lib/stores/toastStore.svelte';
+import type { SanitizationReport } from '$lib/types/markdown';
 import { callBackend } from '$lib/utils/backend';
 import { CONFIG } from '$lib/utils/config';
 import { AppError } from '$lib/utils/errorHandling';
-import { renderMarkdown } from '$lib/utils/markdownRust';
+import { flavorArg, renderMarkdown } from '$lib/utils/markdownRust';
 import { save } from '@tauri-apps/plugin-dialog';
 import { domToPng, domToWebp, domToSvg } from 'modern-screenshot';
 
+export type MarkupFormat = 'mediawiki' | 'rst';
+
+const MARKUP_FORMATS: Record<MarkupFormat, { name: string; extension: string }> = {
+    mediawiki: { name: 'MediaWiki', extension: 'wiki' },
+    rst: { name: 'reStructuredText', extension: 'rst' },
+};
+
+/** Summarizes what rendering removed or rewrote, or '' when nothing changed. */
+function describeSanitization(report?: SanitizationReport): string {
+    if (!report) return '';
+    const parts: string[] = [];
+    if (report.raw_html_removed)
+        parts.push(`${report.raw_html_removed} raw HTML element(s) removed`);
+    if (report.scripts_dropped) parts.push(`${report.scripts_dropped} script(s) dropped`);
+    if (report.remote_images_blocked)
+        parts.push(`${report.remote_images_blocked} remote image(s) blocked`);
+    if (report.urls_upgraded) parts.push(`${report.urls_upgraded} URL(s) switched to HTTPS`);
+    if (report.links_cleaned)
+        parts.push(`${report.links_cleaned} link(s) stripped of tracking parameters`);
+    return parts.join(', ');
+}
+
 export class ExportService {
     private getActiveTab() {
         const tabId = appContext.app.activeTabId;
@@ -38,7 +61,7 @@ export class ExportService {
         try {
             const result = await renderMarkdown(
                 tab.content,
-                appContext.app.markdownFlavor === 'gfm',
+                appContext.app.markdownFlavor,
                 tab.path,
             );
             container.innerHTML = result.html;
@@ -87,10 +110,11 @@ export class ExportService {
 
             const result = await renderMarkdown(
                 tab.content,
-                appContext.app.markdownFlavor === 'gfm',
+                appContext.app.markdownFlavor,
                 tab.path,
             );
             const bodyContent = result.html;
+            const sanitization = result.sanitization;
 
             // Extract the native preview styles we added to app.css for the export container
             // This ensures the exported HTML looks exactly like the preview
@@ -167,6 +191,9 @@ export class ExportService {
                 { report: true, msg: 'Failed to save HTML file' },
             );
             showToast('success', `Exported to ${path}`);
+
+            const stripped = describeSanitization(sanitization);
+            if (stripped) showToast('info', `Export differs from source: ${stripped}`);
         } catch {
             // Error already reported by backend
         }
@@ -191,7 +218,13 @@ export class ExportService {
 
             await callBackend(
                 'export_to_pdf',
-                { path, content: tab.content, title: tab.title, backgroundColor: bgColor },
+                {
+                    path,
+                    content: tab.content,
+                    title: tab.title,
+                    backgroundColor: bgColor,
+                    sourcePath: tab.path,
+                },
                 'Export:PDF',
                 { path: tab?.path },
                 { report: true, msg: 'Failed to generate PDF' },
@@ -202,6 +235,153 @@ export class ExportService {
         }
     }
 
+    async exportToEpub() {
+        const tab = this.getActiveTab();
+        if (!tab) return;
+
+        try {
+            const title = tab.title.replace(/\.[^/.]+$/, '');
+            const path = await save({
+                defaultPath: `${title}.epub`,
+                filters: [{ name: 'EPUB', extensions: ['epub'] }],
+            });
+
+            if (!path) return;
+
+            showToast('info', 'Generating EPUB...');
+
+            await callBackend(
+                'export_to_epub',
+                {
+                    path,
+                    content: tab.content,
+                    options: { title, basePath: tab.path, flavor: flavorArg() },
+                },
+                'Export:EPUB',
+                { path: tab.path },
+                { report: true, msg: 'Failed to generate EPUB' },
+            );
+            showToast('success', `Exported to ${path}`);
+        } catch {
+            // Error already reported
+        }
+    }
+
+    async exportToSlides() {
+        const tab = this.getActiveTab();
+        if (!tab) return;
+
+        try {
+            const title = tab.title.replace(/\.[^/.]+$/, '');
+            const path = await save({
+                defaultPath: `${title}.slides.html`,
+                filters: [{ name: 'HTML', extensions: ['html'] }],
+            });
+
+            if (!path) return;
+
+            const result = await callBackend(
+                'export_to_slides',
+                {
+                    path,
+                    content: tab.content,
+                    options: { title, theme: appContext.app.activeTheme, flavor: flavorArg() },
+                },
+                'Export:HTML',
+                { path: tab.path },
+                { report: true, msg: 'Failed to export slides' },
+            );
+            if (result === null) return;
+            showToast('success', `Exported ${result.slide_count} slide(s) to ${path}`);
+            const stripped = describeSanitization(result.report);
+            if (stripped) showToast('info', `Export differs from source: ${stripped}`);
+        } catch {
+            // Error already reported
+        }
+    }
+
+    private async convertToMarkup(format: MarkupFormat): Promise<string | null> {
+        const tab = this.getActiveTab();
+        if (!tab) return null;
+        return callBackend(
+            'convert_to_markup',
+            { content: tab.content, format, flavor: appContext.app.markdownFlavor },
+            'Export:Markup',
+            { path: tab.path },
+            { report: true },
+        );
+    }
+
+    async exportToMarkup(format: MarkupFormat) {
+        const tab = this.getActiveTab();
+        if (!tab) return;
+        const { name, extension } = MARKUP_FORMATS[format];
+
+        try {
+            const path = await save({
+                defaultPath: `${tab.title.replace(/\.[^/.]+$/, '')}.${extension}`,
+                filters: [{ name, extensions: [extension] }],
+            });
+            if (!path) return;
+
+            const markup = await this.convertToMarkup(format);
+            if (markup === null) return;
+
+            await callBackend(
+                'write_text_file',
+                { path, content: markup },
+                'File:Write',
+                { path: tab.path },
+                { report: true, msg: `Failed to save ${name} file` },
+            );
+            showToast('success', `Exported to ${path}`);
+        } catch {
+            // Error already reported by backend
+        }
+    }
+
+    async copyAsMarkup(format: MarkupFormat) {
+        const markup = await this.convertToMarkup(format);
+        if (markup === null) return;
+        await navigator.clipboard.writeText(markup);
+        showToast('success', `Copied as ${MARKUP_FORMATS[format].name}`);
+    }
+
+    async copyAsPlainText() {
+        const tab = this.getActiveTab();
+        if (!tab) return;
+        const text = await callBackend(
+            'strip_markdown',
+            { content: tab.content, flavor: appContext.app.markdownFlavor },
+            'Export:Markup',
+            { path: tab.path },
+            { report: true },
+        );
+        if (text === null) return;
+        await navigator.clipboard.writeText(text);
+        showToast('success', 'Copied as plain text');
+    }
+
+    async copyAsHtml() {
+        const tab = this.getActiveTab();
+        if (!tab) return;
+        const sanitization = await callBackend(
+            'copy_as_html',
+            {
+                content: tab.content,
+                flavor: appContext.app.markdownFlavor,
+                theme: appContext.app.activeTheme,
+            },
+            'Export:HTML',
+            { path: tab.path },
+            { report: true, msg: 'Failed to copy as HTML' },
+        );
+        if (sanitization === null) return;
+        showToast('success', 'Copied as HTML');
+        const stripped = describeSanitization(sanitization);
+        if (stripped) showToast('info', `Copy differs from source: ${stripped}`);
+    }
+
     async exportToImage(format: 'png' | 'webp' | 'svg') {
         const container = await this.prepareExportContent();
         if (!container) return;
@@ -9,6 +9,8 @@ import { callBackendSafe } from '$lib/utils/backend';
 export type FileContent = {
     content: string;
     encoding: string;
+    has_bom: boolean;
+    line_ending: 'LF' | 'CRLF';
 };
 
 type FileMetadata = {
@@ -86,6 +88,14 @@ export async function checkAndReloadIfChanged(tabId: string): Promise<boolean> {
 
     if (tab.isDirty) return false;
 
+    return hasChangedOnDisk(tabId);
+}
+
+/** Whether the tab's file was modified since the tab last read or wrote it */
+export async function hasChangedOnDisk(tabId: string): Promise<boolean> {
+    const tab = appContext.editor.tabs.find((t) => t.id === tabId);
+    if (!tab || !tab.path) return false;
+
     const meta = await callBackendSafe('get_file_metadata', { path: tab.path }, 'File:Metadata', {
         showToast: false,
         severity: 'warning',
@@ -115,15 +125,17 @@ export async function reloadFileContent(tabId: string): Promise<void> {
 
     if (!result) return;
 
-    const crlfCount = (result.content.match(/\r\n/g) || []).length;
-    const lfOnlyCount = (result.content.match(/(?<!\r)\n/g) || []).length;
-    const detectedLineEnding: 'LF' | 'CRLF' =
-        crlfCount > 0 && (crlfCount >= lfOnlyCount || lfOnlyCount === 0) ? 'CRLF' : 'LF';
-
     const content = normalizeLineEndings(result.content);
     const sizeBytes = new TextEncoder().encode(result.content).length;
 
-    reloadTabContent(tabId, content, detectedLineEnding, result.encoding.toUpperCase(), sizeBytes);
+    reloadTabContent(
+        tabId,
+        content,
+        result.line_ending,
+        result.encoding.toUpperCase(),
+        sizeBytes,
+        result.has_bom,
+    );
 
     await refreshMetadata(tabId, sanitizedPath);
 }
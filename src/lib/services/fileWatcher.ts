@@ -1,16 +1,25 @@
 import {
-    checkAndReloadIfChanged,
+    hasChangedOnDisk,
+    normalizeLineEndings,
+    refreshMetadata,
     reloadFileContent,
     sanitizePath,
 } from '$lib/services/fileMetadata';
-import { reloadTabContent } from '$lib/stores/editorStore.svelte';
+import {
+    applyMergedContent,
+    getSavedContent,
+    reloadTabContent,
+} from '$lib/stores/editorStore.svelte';
 import { appContext } from '$lib/stores/state.svelte.ts';
 import { showToast } from '$lib/stores/toastStore.svelte';
+import { callBackendSafe } from '$lib/utils/backend';
 import { CONFIG } from '$lib/utils/config';
 import { AppError } from '$lib/utils/errorHandling';
 import { debounce } from '$lib/utils/timing';
 import { watch } from '@tauri-apps/plugin-fs';
 
+import type { EditorTab } from '$lib/stores/editorStore.svelte';
+
 type UnwatchFn = () => void;
 
 class FileWatcherService {
@@ -150,7 +159,7 @@ class FileWatcherService {
             }
 
             const firstTab = tabs[0];
-            const hasChanged = await checkAndReloadIfChanged(firstTab.id);
+            const hasChanged = await hasChangedOnDisk(firstTab.id);
 
             if (!hasChanged || signal?.aborted) {
                 this.pendingChecks.delete(path);
@@ -161,12 +170,7 @@ class FileWatcherService {
             const cleanTabs = tabs.filter((t) => !t.isDirty);
 
             if (dirtyTabs.length > 0 && !signal?.aborted) {
-                const tabNames = dirtyTabs.map((t) => t.title).join(', ');
-                showToast(
-                    'warning',
-                    `File changed on disk: ${tabNames}. You have unsaved changes.`,
-                    5000,
-                );
+                await this.mergeIntoDirtyTabs(path, dirtyTabs, signal);
             }
 
             if (cleanTabs.length > 0 && !signal?.aborted) {
@@ -227,6 +231,68 @@ class FileWatcherService {
         }
     }
 
+    /**
+     * Carries unsaved edits over onto the file's new content on disk, merging against the
+     * content the tab last saved. Tabs whose edits conflict are left as they are.
+     */
+    private async mergeIntoDirtyTabs(
+        path: string,
+        tabs: EditorTab[],
+        signal?: AbortSignal,
+    ): Promise<void> {
+        const disk = await callBackendSafe('read_text_file', { path }, 'File:Read', {
+            showToast: false,
+            additionalInfo: { path },
+        });
+        if (!disk || signal?.aborted) return;
+        const theirs = normalizeLineEndings(disk.content);
+
+        // Unsaved keystrokes still in the editor's debounce would be lost to the merge
+        window._editorFlushFunctions?.forEach((fn) => fn());
+
+        const merged: string[] = [];
+        const conflicted: string[] = [];
+        for (const { id, title } of tabs) {
+            const ours = appContext.editor.tabs.find((t) => t.id === id)?.content;
+            const base = getSavedContent(id);
+            if (ours === undefined) continue;
+            if (base === undefined) {
+                conflicted.push(title);
+                continue;
+            }
+
+            const result = await callBackendSafe(
+                'merge_documents',
+                { base, ours, theirs },
+                'FileWatcher:Watch',
+                { showToast: false, additionalInfo: { path } },
+            );
+            if (signal?.aborted) return;
+            // Edits made while the merge ran would be overwritten
+            const current = appContext.editor.tabs.find((t) => t.id === id);
+            if (!current) continue;
+            if (!result || result.conflicts.length > 0 || current.content !== ours) {
+                conflicted.push(title);
+                continue;
+            }
+
+            applyMergedContent(id, result.content, theirs);
+            await refreshMetadata(id, path);
+            merged.push(title);
+        }
+
+        if (merged.length > 0) {
+            showToast('info', `Merged changes from disk into ${merged.join(', ')}`);
+        }
+        if (conflicted.length > 0) {
+            showToast(
+                'warning',
+                `File changed on disk: ${conflicted.join(', ')}. Unsaved changes conflict.`,
+                5000,
+            );
+        }
+    }
+
     cleanup(): void {
         for (const controller of this.abortControllers.values()) {
             controller.abort();
@@ -0,0 +1,21 @@
+import type { EditorTab } from '$lib/stores/editorStore.svelte';
+import { callBackend } from '$lib/utils/backend';
+
+let lastSynced = '';
+
+/** Tells the backend which files are open so a second launch can focus the existing tab */
+export async function syncOpenPaths(tabs: Pick<EditorTab, 'id' | 'path'>[]): Promise<void> {
+    const open = tabs.filter((t) => t.path).map((t) => ({ id: t.id, path: t.path as string }));
+    const key = JSON.stringify(open);
+    if (key === lastSynced) return;
+    lastSynced = key;
+
+    await callBackend('sync_open_paths', { tabs: open }, 'Session:Save', undefined, {
+        ignore: true,
+    });
+}
+
+/** Id of the tab showing `path`, matched by canonical path rather than exact spelling */
+export async function findOpenTab(path: string): Promise<string | null> {
+    return callBackend('is_path_open', { path }, 'File:Read', undefined, { ignore: true });
+}
@@ -0,0 +1,94 @@
+import { callBackend } from '$lib/utils/backend';
+import { searchState } from '$lib/utils/searchManager.svelte.ts';
+
+export type SearchScope = 'current' | 'all';
+
+export interface SearchHistoryQuery {
+    find_text: string;
+    replace_text: string;
+    match_case: boolean;
+    whole_word: boolean;
+    use_regex: boolean;
+    scope: SearchScope;
+}
+
+/** A history entry, or a saved search when `name` is set. */
+export interface SavedSearch extends SearchHistoryQuery {
+    id: string;
+    name: string | null;
+    pinned: boolean;
+    last_used: string;
+}
+
+let lastRecorded = '';
+
+export function currentSearchQuery(scope: SearchScope): SearchHistoryQuery {
+    return {
+        find_text: searchState.findText,
+        replace_text: searchState.replaceText,
+        match_case: searchState.matchCase,
+        whole_word: searchState.matchWholeWord,
+        use_regex: searchState.useRegex,
+        scope,
+    };
+}
+
+/** Records the panel's query in history; repeats of the last query are skipped. */
+export async function recordSearch(scope: SearchScope): Promise<void> {
+    if (!searchState.findText || searchState.regexError) return;
+
+    const query = currentSearchQuery(scope);
+    const key = JSON.stringify(query);
+    if (key === lastRecorded) return;
+    lastRecorded = key;
+
+    await callBackend('record_search', { query }, 'Search:History', undefined, { ignore: true });
+}
+
+export async function saveSearch(name: string, scope: SearchScope): Promise<SavedSearch | null> {
+    return callBackend(
+        'save_search',
+        { name, query: currentSearchQuery(scope) },
+        'Search:History',
+        undefined,
+        { report: true },
+    );
+}
+
+export async function listSearches(savedOnly = false, limit?: number): Promise<SavedSearch[]> {
+    const searches = await callBackend(
+        'list_searches',
+        { savedOnly, limit },
+        'Search:History',
+        undefined,
+        { ignore: true },
+    );
+    return searches ?? [];
+}
+
+/** Loads a history entry or saved search into the find panel. Returns its scope. */
+export function applySearch(search: SearchHistoryQuery): SearchScope {
+    searchState.findText = search.find_text;
+    searchState.replaceText = search.replace_text;
+    searchState.matchCase = search.match_case;
+    searchState.matchWholeWord = search.whole_word;
+    searchState.useRegex = search.use_regex;
+    lastRecorded = JSON.stringify(search);
+    return search.scope;
+}
+
+export async function pinSearch(id: string, pinned: boolean): Promise<void> {
+    await callBackend('pin_search', { id, pinned }, 'Search:History', undefined, { report: true });
+}
+
+export async function deleteSearch(id: string): Promise<void> {
+    await callBackend('delete_search', { id }, 'Search:History', undefined, { report: true });
+}
+
+export async function clearSearchHistory(): Promise<number> {
+    const removed = await callBackend('clear_search_history', {}, 'Search:History', undefined, {
+        report: true,
+    });
+    lastRecorded = '';
+    return removed ?? 0;
+}
@@ -2,14 +2,17 @@ import { appState } from '$lib/stores/appState.svelte';
 import {
     addTab,
     editorStore,
+    getHistoryState,
     getTransientState,
     initTransientState,
     markTabPersisted,
     setFileCheckStatus,
+    setSavedContent,
     setLineChangeTracker,
+    updateHistoryState,
     updateTransientState,
 } from '$lib/stores/editorStore.svelte';
-import { callBackend } from '$lib/utils/backend';
+import { callBackend, callBackendSafe } from '$lib/utils/backend';
 import { CONFIG } from '$lib/utils/config';
 import { formatTimestampForDisplay } from '$lib/utils/date';
 import { AppError } from '$lib/utils/errorHandling';
@@ -18,6 +21,7 @@ import { LineChangeTracker } from '$lib/utils/lineChangeTracker.svelte';
 import { logger } from '$lib/utils/logger';
 import { countWords, fastCountWords } from '$lib/utils/textMetrics';
 import { debounce } from '$lib/utils/timing';
+import { deserializeHistory, serializeHistory } from '$lib/utils/undoHistory';
 import {
     checkAndReloadIfChanged,
     checkFileExists,
@@ -26,9 +30,10 @@ import {
     reloadFileContent,
 } from './fileMetadata';
 import { fileWatcher } from './fileWatcher';
+import { hydrateTabMeta } from './tabMeta';
 
 // Only import types if needed
-import type { EditorTab } from '$lib/stores/editorStore.svelte';
+import type { EditorTab, TabGroup } from '$lib/stores/editorStore.svelte';
 
 type RustTabState = {
     id: string;
@@ -46,8 +51,37 @@ type RustTabState = {
     mru_position?: number | null;
     sort_index?: number;
     original_index?: number | null;
+    encoding?: string | null;
+    has_bom?: boolean;
+    line_ending?: 'LF' | 'CRLF' | null;
+    group_id?: string | null;
 };
 
+const lastChangeReports = new Map<string, number>();
+const trailingChangeReports = new Map<string, number>();
+
+/**
+ * Tells the backend the session changed; its autosave policy decides when to ask for a
+ * save. Reports are throttled per tab, with a trailing report so the last edit of a burst
+ * still counts, and a null tab id covers changes other than edits.
+ */
+export function reportSessionChange(tabId: string | null = null): void {
+    const key = tabId ?? '';
+    const elapsed = Date.now() - (lastChangeReports.get(key) ?? 0);
+    if (elapsed < CONFIG.SESSION.CHANGE_REPORT_INTERVAL_MS) {
+        if (!trailingChangeReports.has(key)) {
+            const timer = window.setTimeout(() => {
+                trailingChangeReports.delete(key);
+                reportSessionChange(tabId);
+            }, CONFIG.SESSION.CHANGE_REPORT_INTERVAL_MS - elapsed);
+            trailingChangeReports.set(key, timer);
+        }
+        return;
+    }
+    lastChangeReports.set(key, Date.now());
+    void callBackendSafe('mark_session_dirty', { tabId }, 'Session:Save', { showToast: false });
+}
+
 class SessionPersistenceManager {
     private saveQueue = Promise.resolve();
     private savePending = false;
@@ -75,6 +109,12 @@ class SessionPersistenceManager {
 
             // 1. Map Active Tabs
             const activeTabs = editorStore.tabs;
+            // Scratch tabs whose content changed also have new undo history to store
+            const historyTabs = activeTabs.filter((t) => {
+                const ts = getTransientState(t.id);
+                const changed = ts ? ts.contentChanged || !ts.isPersisted : true;
+                return !t.path && t.contentLoaded !== false && changed;
+            });
             const activeRustTabs: RustTabState[] = activeTabs.map((t, index) => {
                 const ts = getTransientState(t.id);
                 const needsContent = ts ? ts.contentChanged || !ts.isPersisted : true;
@@ -95,6 +135,10 @@ class SessionPersistenceManager {
                     mru_position: mruPositionMap.get(t.id) ?? null,
                     sort_index: index,
                     original_index: null,
+                    encoding: t.encoding,
+                    has_bom: t.hasBom ?? false,
+                    line_ending: t.lineEnding,
+                    group_id: t.groupId ?? null,
                 };
             });
 
@@ -120,6 +164,10 @@ class SessionPersistenceManager {
                     mru_position: null,
                     sort_index: index,
                     original_index: entry.index,
+                    encoding: entry.tab.encoding,
+                    has_bom: entry.tab.hasBom ?? false,
+                    line_ending: entry.tab.lineEnding,
+                    group_id: entry.tab.groupId ?? null,
                 };
             });
 
@@ -138,8 +186,12 @@ class SessionPersistenceManager {
                 withContent: tabsWithContent,
             });
 
+            // History rows of tabs the session no longer has are dropped with it, so these go after
+            await Promise.all(historyTabs.map((t) => saveTabHistory(t.id, t.content)));
+
             // 3. Update persistence state on success
             editorStore.sessionDirty = false;
+            lastChangeReports.clear();
 
             activeTabs.forEach((t) => {
                 markTabPersisted(t.id);
@@ -160,6 +212,33 @@ class SessionPersistenceManager {
 
 const persistenceManager = new SessionPersistenceManager();
 
+async function saveTabHistory(tabId: string, content: string): Promise<void> {
+    const state = getHistoryState(tabId);
+    if (!state) return;
+    await callBackendSafe(
+        'save_tab_history',
+        { tabId, history: serializeHistory(state, content) },
+        'Session:Save',
+        { showToast: false },
+    );
+}
+
+/** Restores a scratch tab's stored undo history, dropping it if it no longer fits `content` */
+async function restoreTabHistory(tabId: string, content: string): Promise<void> {
+    const saved = await callBackendSafe('load_tab_history', { tabId }, 'Session:Load', {
+        showToast: false,
+    });
+    if (!saved) return;
+
+    const state = deserializeHistory(saved, content);
+    if (state) {
+        updateHistoryState(tabId, state);
+    } else {
+        logger.session.debug('UndoHistoryDiscarded', { tabId, docLength: saved.doc_length });
+        await callBackendSafe('clear_tab_history', { tabId }, 'Session:Load', { showToast: false });
+    }
+}
+
 export async function initializeTabFileState(tab: EditorTab): Promise<void> {
     if (!tab.path) {
         return;
@@ -192,6 +271,7 @@ export async function initializeTabFileState(tab: EditorTab): Promise<void> {
             const storeTab = editorStore.tabs.find((x) => x.id === tab.id);
             if (storeTab) {
                 const normalizedContent = normalizeLineEndings(res.content);
+                setSavedContent(tab.id, normalizedContent);
                 storeTab.lastSavedHash = hashContent(normalizedContent);
                 storeTab.isDirty = hashContent(storeTab.content) !== storeTab.lastSavedHash;
             }
@@ -326,7 +406,9 @@ export async function loadTabContentLazy(tabId: string): Promise<void> {
                         'File:Read',
                     );
                     if (fileData && fileData.content) {
-                        lastSavedHash = hashContent(normalizeLineEndings(fileData.content));
+                        const savedContent = normalizeLineEndings(fileData.content);
+                        setSavedContent(tabId, savedContent);
+                        lastSavedHash = hashContent(savedContent);
                     } else {
                         lastSavedHash = hashContent(normalizedContent);
                     }
@@ -334,6 +416,7 @@ export async function loadTabContentLazy(tabId: string): Promise<void> {
                     lastSavedHash = hashContent(normalizedContent);
                 }
             } else {
+                setSavedContent(tabId, normalizedContent);
                 lastSavedHash = hashContent(normalizedContent);
             }
         } else {
@@ -348,6 +431,11 @@ export async function loadTabContentLazy(tabId: string): Promise<void> {
             lastSavedHash = '';
         }
 
+        // Before the content lands, so the editor picks the history up when it builds the tab
+        if (!tab.path) {
+            await restoreTabHistory(tabId, normalizedContent);
+        }
+
         const sizeBytes = new TextEncoder().encode(normalizedContent).length;
         const wordCount =
             sizeBytes < CONFIG.PERFORMANCE.LARGE_FILE_SIZE_BYTES
@@ -363,7 +451,7 @@ export async function loadTabContentLazy(tabId: string): Promise<void> {
                 lastSavedHash,
                 sizeBytes,
                 wordCount,
-                lineEnding: normalizedContent.indexOf('\r\n') !== -1 ? 'CRLF' : 'LF',
+                lineEnding: currentTab.lineEnding,
                 contentLoaded: true,
                 isDirty: tab.isDirty && hashContent(normalizedContent) !== lastSavedHash,
             };
@@ -442,15 +530,17 @@ function convertRustTabToEditorTab(t: RustTabState, contentLoaded: boolean = tru
         formattedTimestamp: formatTimestampForDisplay(timestamp),
         isPinned: t.is_pinned,
         customTitle: t.custom_title || undefined,
-        lineEnding: (t.content && t.content.indexOf('\r\n') !== -1 ? 'CRLF' : 'LF') as
-            | 'LF'
-            | 'CRLF',
-        encoding: 'UTF-8',
+        lineEnding:
+            t.line_ending ?? (t.content && t.content.indexOf('\r\n') !== -1 ? 'CRLF' : 'LF'),
+        encoding: t.encoding ?? 'UTF-8',
+        hasBom: t.has_bom ?? false,
         fileCheckFailed: t.file_check_failed || false,
         contentLoaded,
+        groupId: t.group_id || undefined,
     };
 
     setLineChangeTracker(t.id, new LineChangeTracker());
+    if (t.path && !t.is_dirty && contentLoaded) setSavedContent(t.id, content);
     initTransientState(
         t.id,
         {
@@ -467,8 +557,10 @@ function convertRustTabToEditorTab(t: RustTabState, contentLoaded: boolean = tru
     return editorTab;
 }
 
-export async function loadSession(): Promise<void> {
+/** Returns file paths the startup mode asks to open once the session is restored. */
+export async function loadSession(): Promise<string[]> {
     const start = performance.now();
+    let openPaths: string[] = [];
 
     try {
         const sessionData = await callBackend('restore_session', {}, 'Session:Load');
@@ -479,9 +571,18 @@ export async function loadSession(): Promise<void> {
         if (Array.isArray(sessionData)) {
             activeRustTabs = sessionData as RustTabState[];
         } else if (sessionData && typeof sessionData === 'object') {
-            const sd = sessionData as { active_tabs?: unknown[]; closed_tabs?: unknown[] };
+            const sd = sessionData as {
+                active_tabs?: unknown[];
+                closed_tabs?: unknown[];
+                open_paths?: string[];
+                tab_groups?: TabGroup[];
+                tab_meta?: Record<string, Record<string, unknown>>;
+            };
             activeRustTabs = (sd.active_tabs || []) as RustTabState[];
             closedRustTabs = (sd.closed_tabs || []) as RustTabState[];
+            openPaths = sd.open_paths || [];
+            editorStore.tabGroups = sd.tab_groups || [];
+            hydrateTabMeta(sd.tab_meta);
         }
 
         if (activeRustTabs.length > 0) {
@@ -537,7 +638,7 @@ export async function loadSession(): Promise<void> {
         if (editorStore.tabs.length === 0 || appState.startupBehavior === 'new') {
             if (appState.startupBehavior === 'new' && activeRustTabs.length > 0) {
                 appState.activeTabId = addTab();
-            } else if (editorStore.tabs.length === 0) {
+            } else if (editorStore.tabs.length === 0 && openPaths.length === 0) {
                 appState.activeTabId = addTab();
             }
         }
@@ -568,6 +669,8 @@ export async function loadSession(): Promise<void> {
             activeTabs: editorStore.tabs.length,
             closedTabs: editorStore.closedTabsHistory.length,
         });
+
+        return openPaths;
     } catch (err) {
         AppError.handle('Session:Load', err, {
             showToast: false,
@@ -575,6 +678,7 @@ export async function loadSession(): Promise<void> {
         });
 
         appState.activeTabId = addTab();
+        return [];
     }
 }
 
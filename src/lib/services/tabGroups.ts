@@ -0,0 +1,70 @@
+import { editorStore, type TabGroup } from '$lib/stores/editorStore.svelte';
+import { callBackend } from '$lib/utils/backend';
+
+export async function createTabGroup(
+    name: string,
+    color: string | null = null,
+    tabIds: string[] = [],
+): Promise<TabGroup | null> {
+    const group = await callBackend(
+        'create_tab_group',
+        { name, color },
+        'TabGroup:Update',
+        undefined,
+        { report: true },
+    );
+    if (!group) return null;
+
+    editorStore.tabGroups.push(group);
+    if (tabIds.length > 0) {
+        await assignTabsToGroup(tabIds, group.id);
+    }
+    return group;
+}
+
+export async function updateTabGroup(
+    id: string,
+    changes: Partial<Omit<TabGroup, 'id'>>,
+): Promise<void> {
+    const index = editorStore.tabGroups.findIndex((g) => g.id === id);
+    if (index === -1) return;
+
+    const updated: TabGroup = { ...editorStore.tabGroups[index], ...changes };
+    await callBackend('update_tab_group', { group: updated }, 'TabGroup:Update', undefined, {
+        report: true,
+    });
+    editorStore.tabGroups[index] = updated;
+}
+
+export async function toggleTabGroupCollapsed(id: string): Promise<void> {
+    const group = editorStore.tabGroups.find((g) => g.id === id);
+    if (group) {
+        await updateTabGroup(id, { collapsed: !group.collapsed });
+    }
+}
+
+/** Deletes the group and leaves its tabs open, ungrouped. */
+export async function deleteTabGroup(id: string): Promise<void> {
+    editorStore.tabGroups = editorStore.tabGroups.filter((g) => g.id !== id);
+    editorStore.tabs.forEach((t) => {
+        if (t.groupId === id) t.groupId = undefined;
+    });
+    editorStore.closedTabsHistory.forEach((entry) => {
+        if (entry.tab.groupId === id) entry.tab.groupId = undefined;
+    });
+    await callBackend('delete_tab_group', { id }, 'TabGroup:Update', undefined, {
+        report: true,
+    });
+}
+
+/** Moves tabs into a group, or out of any group when `groupId` is null. */
+export async function assignTabsToGroup(tabIds: string[], groupId: string | null): Promise<void> {
+    editorStore.tabs.forEach((t) => {
+        if (tabIds.includes(t.id)) t.groupId = groupId ?? undefined;
+    });
+    // Tabs not yet in the database pick up the group on the next session save
+    editorStore.sessionDirty = true;
+    await callBackend('assign_tabs_to_group', { tabIds, groupId }, 'TabGroup:Update', undefined, {
+        report: true,
+    });
+}
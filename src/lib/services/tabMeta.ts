@@ -0,0 +1,35 @@
+import { callBackend } from '$lib/utils/backend';
+
+type TabMeta = Record<string, unknown>;
+
+// Mirrors the tab_meta table so reads stay synchronous; filled by loadSession
+// eslint-disable-next-line svelte/prefer-svelte-reactivity
+const metaCache = new Map<string, TabMeta>();
+
+export function hydrateTabMeta(meta: Record<string, TabMeta> | undefined): void {
+    metaCache.clear();
+    Object.entries(meta ?? {}).forEach(([tabId, values]) => metaCache.set(tabId, values));
+}
+
+export function getTabMeta<T = unknown>(tabId: string, key: string): T | undefined {
+    return metaCache.get(tabId)?.[key] as T | undefined;
+}
+
+/** Persists a value for the tab; `null` or `undefined` removes it. */
+export async function setTabMeta(tabId: string, key: string, value: unknown): Promise<void> {
+    const values = { ...(metaCache.get(tabId) ?? {}) };
+    if (value === null || value === undefined) {
+        delete values[key];
+    } else {
+        values[key] = value;
+    }
+    metaCache.set(tabId, values);
+
+    await callBackend(
+        'set_tab_meta',
+        { tabId, key, value: value ?? null },
+        'Session:Save',
+        undefined,
+        { ignore: true },
+    );
+}
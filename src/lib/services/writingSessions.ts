@@ -0,0 +1,67 @@
+import { callBackendSafe } from '$lib/utils/backend';
+import { CONFIG } from '$lib/utils/config';
+
+/**
+ * Writing sessions for the writing stats. A session starts when the editor takes focus,
+ * follows its tab's word count, and ends when the tab goes idle, loses the editor to
+ * another tab, closes, or the app exits. One session runs at a time.
+ */
+type RunningSession = {
+    tabId: string;
+    // Resolves to null when the backend could not start it
+    sessionId: Promise<string | null>;
+    idleTimer: number;
+};
+
+let running: RunningSession | null = null;
+
+function idleTimer(tabId: string): number {
+    return window.setTimeout(() => endWritingSession(tabId), CONFIG.EDITOR.WRITING_IDLE_MS);
+}
+
+/** Starts a session for the tab unless it already has one, ending any other tab's session */
+export function startWritingSession(tabId: string, path: string | null, wordCount: number) {
+    if (running?.tabId === tabId) {
+        clearTimeout(running.idleTimer);
+        running.idleTimer = idleTimer(tabId);
+        return;
+    }
+    endWritingSession();
+
+    running = {
+        tabId,
+        sessionId: callBackendSafe('start_session', { path, wordCount }, 'Editor:Status', {
+            showToast: false,
+        }),
+        idleTimer: idleTimer(tabId),
+    };
+}
+
+/** Reports the tab's word count after an edit; ignored unless the tab has the session */
+export function reportWritingProgress(tabId: string, wordCount: number) {
+    if (running?.tabId !== tabId) return;
+    clearTimeout(running.idleTimer);
+    running.idleTimer = idleTimer(tabId);
+
+    void running.sessionId.then((sessionId) => {
+        if (!sessionId) return;
+        callBackendSafe('update_session', { sessionId, wordCount }, 'Editor:Status', {
+            showToast: false,
+        });
+    });
+}
+
+/** Ends the running session, or only the given tab's when `tabId` is set */
+export function endWritingSession(tabId?: string, wordCount?: number) {
+    if (!running || (tabId !== undefined && running.tabId !== tabId)) return;
+    const { sessionId, idleTimer: timer } = running;
+    clearTimeout(timer);
+    running = null;
+
+    void sessionId.then((id) => {
+        if (!id) return;
+        callBackendSafe('end_session', { sessionId: id, wordCount }, 'Editor:Status', {
+            showToast: false,
+        });
+    });
+}
@@ -1,3 +1,10 @@
+import type {
+    CustomFlavor,
+    HtmlPolicy,
+    MarkdownFlavor,
+    WordCountMode,
+} from '$lib/types/markdown';
+
 // The state object
 export const appState = $state({
     activeTabId: null as string | null,
@@ -11,8 +18,13 @@ export const appState = $state({
     tabWidthMin: 80,
     tabWidthMax: 180,
     statusBarTransparency: 0,
+    wordCountMode: 'all' as WordCountMode,
     newTabPosition: 'end' as 'beginning' | 'right' | 'end',
     startupBehavior: 'last-focused' as 'first' | 'last-focused' | 'new',
+    sessionRestore: 'all' as 'all' | 'pinned' | 'blank' | 'path',
+    sessionRestorePath: '',
+    sessionSaveIdleSeconds: 5,
+    sessionSaveDirtyTabs: 3,
     editorFontFamily: "'Source Code Pro', 'Cascadia Code', monospace, ui-monospace",
     editorFontSize: 14,
     editorWordWrap: true,
@@ -25,8 +37,20 @@ export const appState = $state({
     previewFontFamily: 'system-ui, -apple-system, sans-serif',
     previewFontSize: 16,
     gfmEnabled: true,
-    markdownFlavor: 'gfm' as 'commonmark' | 'gfm',
+    markdownFlavor: 'gfm' as MarkdownFlavor,
+    customFlavor: {} as CustomFlavor,
+    blockRemoteImages: false,
+    forceHttps: false,
+    stripTrackingParams: false,
+    mathRendering: false,
+    renderFootnotes: true,
+    hideFrontMatter: false,
+    headingIdPrefix: '',
+    htmlPolicy: 'off' as HtmlPolicy,
     logLevel: 'info' as 'trace' | 'debug' | 'info' | 'warn' | 'error',
+    logRotation: 'keep-one' as 'keep-one' | 'keep-some' | 'keep-all',
+    logMaxFileSizeMb: 10,
+    logRetention: 5,
     formatOnSave: false,
     formatOnPaste: false,
     defaultIndent: 2,
@@ -39,8 +63,7 @@ export const appState = $state({
     findPanelTransparent: false,
     findPanelCloseOnBlur: false,
     languageDictionaries: ['en-US'] as string[],
-    technicalDictionaries: true,
-    scienceDictionaries: false,
+    specialistDictionaries: ['software-terms', 'companies', 'fullstack', 'filetypes'] as string[],
     tabNameFromContent: false,
     wrapGuideColumn: 0,
     doubleClickSelectsTrailingSpace: false,
@@ -50,6 +73,10 @@ export const appState = $state({
     // Tab switching flag to prevent auto-format during transitions
     isTabSwitching: false,
     maxFileSizeMB: 50,
+    followSymlinksOnWrite: true,
+    resolveSymlinksForIdentity: true,
+    livePreviewExport: false,
+    livePreviewExportPath: '',
     writerMode: false,
 });
 
@@ -8,6 +8,7 @@ export type Bookmark = {
     tags: string[];
     created: string;
     last_accessed: string | null;
+    folder?: string | null;
 };
 
 // State
@@ -52,21 +53,58 @@ export async function deleteBookmark(id: string) {
     await callBackend('delete_bookmark', { id }, 'Bookmark:Remove', undefined, { report: true });
 }
 
+export async function deleteBookmarks(ids: string[]) {
+    if (ids.length === 0) return;
+    const removed = new Set(ids);
+    bookmarkStore.bookmarks = bookmarkStore.bookmarks.filter((b) => !removed.has(b.id));
+    await callBackend('delete_bookmarks', { ids }, 'Bookmark:Remove', undefined, { report: true });
+}
+
+export async function tagBookmarks(ids: string[], tag: string) {
+    const trimmed = tag.trim();
+    if (ids.length === 0 || !trimmed) return;
+    const result = await callBackend(
+        'tag_bookmarks',
+        { ids, tag: trimmed },
+        'Bookmark:Update',
+        undefined,
+        { report: true },
+    );
+    if (result === null) return;
+
+    const tagged = new Set(ids);
+    for (const bookmark of bookmarkStore.bookmarks) {
+        const hasTag = bookmark.tags.some((t) => t.toLowerCase() === trimmed.toLowerCase());
+        if (tagged.has(bookmark.id) && !hasTag) bookmark.tags.push(trimmed);
+    }
+}
+
+export async function moveBookmarks(ids: string[], folder: string | null) {
+    if (ids.length === 0) return;
+    const result = await callBackend(
+        'move_bookmarks',
+        { ids, folder },
+        'Bookmark:Update',
+        undefined,
+        { report: true },
+    );
+    if (result === null) return;
+    // Reload so the folder matches the backend's normalized form
+    await loadBookmarks();
+}
+
 export async function updateBookmark(id: string, title: string, tags: string[], path?: string) {
     const index = bookmarkStore.bookmarks.findIndex((b) => b.id === id);
     if (index === -1) return;
 
-    const updated: Bookmark = {
-        ...bookmarkStore.bookmarks[index],
-        title,
-        tags,
-        path: path ?? bookmarkStore.bookmarks[index].path,
-    };
-
-    await callBackend('add_bookmark', { bookmark: updated }, 'Bookmark:Add', undefined, {
-        report: true,
-    });
-    bookmarkStore.bookmarks[index] = updated;
+    const updated = await callBackend(
+        'update_bookmark',
+        { id, title, tags, path: path ?? bookmarkStore.bookmarks[index].path },
+        'Bookmark:Update',
+        undefined,
+        { report: true },
+    );
+    if (updated) bookmarkStore.bookmarks[index] = updated;
 }
 
 export async function updateAccessTime(id: string) {
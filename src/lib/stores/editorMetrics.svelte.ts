@@ -1,9 +1,8 @@
+// Columns, widths and word positions depend on graphemes and display width, so the status
+// bar takes those from the backend; the editor only reports where the cursor is
 export type EditorMetrics = {
     cursorOffset: number;
     cursorLine: number;
-    cursorCol: number;
-    currentLineLength: number;
-    currentWordIndex: number;
     insertMode: 'INS' | 'OVR';
 };
 
@@ -11,9 +10,6 @@ export type EditorMetrics = {
 export const editorMetrics = $state({
     cursorOffset: 0,
     cursorLine: 1,
-    cursorCol: 1,
-    currentLineLength: 0,
-    currentWordIndex: 0,
     insertMode: 'INS' as 'INS' | 'OVR',
 });
 
@@ -21,11 +17,6 @@ export const editorMetrics = $state({
 export function updateMetrics(metrics: Partial<EditorMetrics>) {
     if (metrics.cursorOffset !== undefined) editorMetrics.cursorOffset = metrics.cursorOffset;
     if (metrics.cursorLine !== undefined) editorMetrics.cursorLine = metrics.cursorLine;
-    if (metrics.cursorCol !== undefined) editorMetrics.cursorCol = metrics.cursorCol;
-    if (metrics.currentLineLength !== undefined)
-        editorMetrics.currentLineLength = metrics.currentLineLength;
-    if (metrics.currentWordIndex !== undefined)
-        editorMetrics.currentWordIndex = metrics.currentWordIndex;
     if (metrics.insertMode !== undefined) editorMetrics.insertMode = metrics.insertMode;
 }
 
@@ -32,14 +32,16 @@
  */
 
 import type { OperationId } from '$lib/config/textOperationsRegistry';
-import { initializeTabLoadState } from '$lib/services/sessionPersistence';
+import { initializeTabLoadState, reportSessionChange } from '$lib/services/sessionPersistence';
+import { endWritingSession, reportWritingProgress } from '$lib/services/writingSessions';
+import { callBackend } from '$lib/utils/backend';
 import { CONFIG } from '$lib/utils/config';
 import { formatTimestampForDisplay, getCurrentTimestamp } from '$lib/utils/date';
 import { isMarkdownFile } from '$lib/utils/fileValidation';
 import { LineChangeTracker } from '$lib/utils/lineChangeTracker.svelte';
 import { clearRendererCache } from '$lib/utils/markdown';
 import { hashContent } from '$lib/utils/contentHash';
-import { countWords, fastCountWords } from '$lib/utils/textMetrics';
+import { clearTextMetrics, countWords, fastCountWords } from '$lib/utils/textMetrics';
 import { appState } from './appState.svelte';
 
 export type EditorTab = {
@@ -62,11 +64,23 @@ export type EditorTab = {
     customTitle?: string;
     lineEnding: 'LF' | 'CRLF';
     encoding: string;
+    hasBom?: boolean;
     fileCheckFailed?: boolean;
     preferredExtension?: 'md' | 'txt';
     contentLoaded?: boolean;
     wordCountPending?: boolean;
     forceSync?: number;
+    /** Renders this document without the remote content policy */
+    trustRemoteContent?: boolean;
+    groupId?: string;
+};
+
+export type TabGroup = {
+    id: string;
+    name: string;
+    color: string | null;
+    collapsed: boolean;
+    sort_index: number;
 };
 
 /** Non-reactive transient state: scroll position, persistence flags, internal strategy. */
@@ -99,6 +113,17 @@ export type ClosedTab = {
 // eslint-disable-next-line svelte/prefer-svelte-reactivity
 const historyStateCache = new Map<string, unknown>();
 
+/**
+ * NON-REACTIVE CACHE: Saved Content
+ *
+ * The text each file tab last matched on disk, the common ancestor when the file changes
+ * on disk under unsaved edits and the two are merged.
+ *
+ * Lifecycle: Set when a file is opened, saved, reloaded or restored; removed in closeTab()
+ */
+// eslint-disable-next-line svelte/prefer-svelte-reactivity
+const savedContentCache = new Map<string, string>();
+
 /**
  * NON-REACTIVE CACHE: LineChangeTracker Instances
  *
@@ -151,6 +176,7 @@ function normalizeLineEndings(text: string): string {
  * - sessionDirty: Whether session has unsaved changes (reactive - UI shows indicators)
  * - mruStack: Most Recently Used tab order (reactive - affects tab navigation)
  * - closedTabsHistory: Recently closed tabs for reopening (reactive - affects UI menus)
+ * - tabGroups: Named tab groups, in display order (reactive - affects tab bar)
  * - lastScrollSource: Tracks scroll sync direction (reactive - prevents circular sync)
  * - pendingTransform: Queued text operation (reactive - triggers editor transformations)
  */
@@ -159,6 +185,7 @@ export const editorStore = $state({
     sessionDirty: false,
     mruStack: [] as string[],
     closedTabsHistory: [] as ClosedTab[],
+    tabGroups: [] as TabGroup[],
     lastScrollSource: null as 'editor' | 'preview' | null,
     pendingTransform: null as { tabId: string; op: OperationId; timestamp: number } | null,
 });
@@ -226,12 +253,46 @@ function scheduleWordCountUpdate(tabId: string, content: string, sizeBytes: numb
             wordCount,
             wordCountPending: false,
         };
+        reportWritingProgress(tabId, wordCount);
 
         wordCountDebounceMap.delete(tabId);
     }, CONFIG.PERFORMANCE.WORD_COUNT_DEBOUNCE_MS);
 
     wordCountDebounceMap.set(tabId, timeout);
 }
+
+// Debounce map for content-derived tab titles
+const titleDebounceMap = new Map<string, number>();
+
+// The backend derives the title, so the tab bar shows what a session save stores
+function scheduleTitleUpdate(tabId: string, content: string) {
+    const existing = titleDebounceMap.get(tabId);
+    if (existing) clearTimeout(existing);
+
+    const timeout = window.setTimeout(async () => {
+        titleDebounceMap.delete(tabId);
+        let derived: string | null = null;
+        try {
+            derived = await callBackend('derive_tab_title', { content }, 'Session:Save');
+        } catch {
+            return;
+        }
+
+        const index = editorStore.tabs.findIndex((t) => t.id === tabId);
+        // Skip results overtaken by newer edits or by the setting being turned off
+        if (index === -1 || !appState.tabNameFromContent) return;
+        const tab = editorStore.tabs[index];
+        if (tab.content !== content) return;
+
+        const title = derived || tab.originalTitle || tab.title;
+        if (title !== tab.title) {
+            editorStore.tabs[index] = { ...tab, title };
+        }
+    }, CONFIG.PERFORMANCE.TAB_TITLE_DEBOUNCE_MS);
+
+    titleDebounceMap.set(tabId, timeout);
+}
+
 export function performTextTransform(operationId: OperationId) {
     const activeId = appState.activeTabId;
 
@@ -376,6 +437,7 @@ export function closeTab(id: string) {
     if (index === -1) return;
 
     const tab = editorStore.tabs[index];
+    endWritingSession(id, tab.wordCount);
 
     if (tab.path || (tab.content && tab.content.trim().length > 0)) {
         const limit = CONFIG.EDITOR.CLOSED_TABS_HISTORY_LIMIT;
@@ -402,10 +464,12 @@ export function closeTab(id: string) {
 
     historyStateCache.delete(id);
     transientStateCache.delete(id);
+    savedContentCache.delete(id);
     removeLineChangeTracker(id);
 
     editorStore.sessionDirty = true;
     clearRendererCache(id);
+    clearTextMetrics(id);
 }
 
 export function reopenLastClosed() {
@@ -467,36 +531,8 @@ export function updateContent(id: string, content: string, lineCount: number) {
     const oldTab = editorStore.tabs[index];
     if (oldTab.content === content) return;
 
-    let newTitle = oldTab.title;
     if (appState.tabNameFromContent) {
-        const trimmed = content.trim();
-        if (trimmed.length > 0) {
-            // Scan for first non-empty line without split()
-            let lineStart = 0;
-            let firstLine = '';
-            for (let i = 0; i <= content.length; i++) {
-                if (i === content.length || content[i] === '\n') {
-                    const line = content.slice(lineStart, i).trim();
-                    if (line.length > 0) {
-                        firstLine = line;
-                        break;
-                    }
-                    lineStart = i + 1;
-                }
-            }
-            let smartTitle = firstLine.replace(/^#+\s*/, '').trim();
-            const MAX_LEN = 25;
-            if (smartTitle.length > MAX_LEN) {
-                smartTitle = smartTitle.substring(0, MAX_LEN).trim() + '...';
-            }
-            if (smartTitle.length > 0) {
-                newTitle = smartTitle;
-            } else if (oldTab.originalTitle) {
-                newTitle = oldTab.originalTitle;
-            }
-        } else if (oldTab.originalTitle) {
-            newTitle = oldTab.originalTitle;
-        }
+        scheduleTitleUpdate(id, content);
     }
 
     const now = getCurrentTimestamp();
@@ -512,7 +548,6 @@ export function updateContent(id: string, content: string, lineCount: number) {
 
     const updatedTab = {
         ...oldTab,
-        title: newTitle,
         content,
         isDirty: true,
         modified: now,
@@ -525,6 +560,7 @@ export function updateContent(id: string, content: string, lineCount: number) {
 
     editorStore.tabs[index] = updatedTab;
     editorStore.sessionDirty = true;
+    reportSessionChange(id);
 }
 
 export function updateScroll(
@@ -585,8 +621,18 @@ export function getHistoryState(id: string): unknown | undefined {
     return historyStateCache.get(id);
 }
 
+export function getSavedContent(id: string): string | undefined {
+    return savedContentCache.get(id);
+}
+
+export function setSavedContent(id: string, content: string) {
+    savedContentCache.set(id, content);
+}
+
 export function markAsSaved(id: string) {
     const now = getCurrentTimestamp();
+    const tab = editorStore.tabs.find((t) => t.id === id);
+    if (tab) savedContentCache.set(id, tab.content);
     updateTab(id, (tab) => ({
         lastSavedHash: hashContent(tab.content),
         isDirty: false,
@@ -635,6 +681,7 @@ export function reloadTabContent(
     lineEnding: 'LF' | 'CRLF',
     encoding: string,
     sizeBytes: number,
+    hasBom: boolean = false,
 ) {
     const lineArray = content.split('\n');
     const lineCount = lineArray.length;
@@ -651,6 +698,7 @@ export function reloadTabContent(
         ts.fileCheckPerformed = false;
         ts.contentChanged = true;
     }
+    savedContentCache.set(id, content);
 
     updateTab(id, (tab) => ({
         content,
@@ -658,6 +706,7 @@ export function reloadTabContent(
         isDirty: false,
         lineEnding,
         encoding,
+        hasBom,
         sizeBytes,
         wordCount,
         lineCount,
@@ -676,6 +725,22 @@ export function updateContentOnly(id: string, content: string, forceSync: boolea
     }));
 }
 
+/**
+ * Takes `saved`, the file's new content on disk, as the saved version and shows `merged`,
+ * the unsaved edits carried over onto it. Still dirty unless the two match.
+ */
+export function applyMergedContent(id: string, merged: string, saved: string) {
+    const ts = transientStateCache.get(id);
+    if (ts) ts.contentChanged = true;
+    savedContentCache.set(id, saved);
+    updateTab(id, (tab) => ({
+        content: merged,
+        lastSavedHash: hashContent(saved),
+        isDirty: merged !== saved,
+        forceSync: (tab.forceSync ?? 0) + 1,
+    }));
+}
+
 export function updateLineEnding(id: string, lineEnding: 'LF' | 'CRLF') {
     updateTab(id, () => ({ lineEnding }));
 }
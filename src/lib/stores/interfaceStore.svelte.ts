@@ -13,6 +13,9 @@ export const interfaceStore = $state({
     showCommandPalette: false,
     showTransform: false,
     showData: false,
+    showQuickCapture: false,
+    // Prefilled into the quick capture prompt when it opens
+    quickCaptureText: '',
 
     // Editor Panels
     showFind: false,
@@ -66,3 +69,8 @@ export function toggleAbout() {
 export function toggleData() {
     interfaceStore.showData = !interfaceStore.showData;
 }
+
+export function openQuickCapture(text = '') {
+    interfaceStore.quickCaptureText = text;
+    interfaceStore.showQuickCapture = true;
+}
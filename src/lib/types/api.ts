@@ -1,6 +1,16 @@
 import type { OperationId } from '$lib/config/textOperationsRegistry';
+import type { SavedSearch, SearchHistoryQuery } from '$lib/services/searchHistory';
 import type { Bookmark } from '$lib/stores/bookmarkStore.svelte';
-import type { RenderResult } from './markdown';
+import type { TabGroup } from '$lib/stores/editorStore.svelte';
+import type {
+    HtmlPolicy,
+    MarkdownExtensions,
+    Readability,
+    RemotePolicy,
+    RenderResult,
+    SanitizationReport,
+    WordCountMode,
+} from './markdown';
 
 export interface AppInfo {
     name: string;
@@ -17,16 +27,183 @@ export interface FileMetadata {
     created?: string;
     modified?: string;
     size: number;
+    is_symlink?: boolean;
+    link_target?: string | null;
 }
 
 export interface FileContent {
     content: string;
     encoding: string;
+    has_bom: boolean;
+    line_ending: 'LF' | 'CRLF';
+}
+
+export interface OutlineNode {
+    level: number;
+    text: string;
+    slug: string;
+    line: number;
+    children: OutlineNode[];
+}
+
+/** A finished writing session from the activity log */
+export interface WritingSession {
+    id: string;
+    path: string | null;
+    mode: string | null;
+    started: string;
+    ended: string;
+    duration_secs: number;
+    active_secs: number;
+    words_added: number;
+    words_removed: number;
+    longest_streak_secs: number;
+}
+
+/** A review comment stored outside the document; offsets are UTF-16 code units */
+export interface Annotation {
+    id: string;
+    path: string;
+    anchor: {
+        from: number;
+        to: number;
+        quote: string;
+        prefix: string;
+        suffix: string;
+        hash: string;
+    };
+    body: string;
+    author: string | null;
+    resolved: boolean;
+    created: string;
+    modified: string;
+}
+
+export interface AnchorLocation {
+    status: 'exact' | 'moved' | 'edited' | 'orphaned';
+    from: number | null;
+    to: number | null;
+}
+
+// Payload of the `job-progress` event emitted by cancellable commands
+export interface JobProgress {
+    job_id: string;
+    done: number;
+    total: number;
+    message: string | null;
+}
+
+export type SymbolKind = 'emoji' | 'symbol';
+
+export interface SymbolMatch {
+    kind: SymbolKind;
+    /** Shortcode or symbol name, without the `:` or `\` trigger */
+    name: string;
+    symbol: string;
+    description: string;
+}
+
+export interface BreadcrumbItem {
+    level: number;
+    text: string;
+    slug: string;
+    line: number;
+    /** UTF-16 offset of the heading line start */
+    offset: number;
+    /** Last line of the section the heading opens */
+    end_line: number;
 }
 
 export interface TabData {
     content: string | null;
-    history_state: unknown;
+}
+
+/** Undo and redo stacks of a scratch tab, as serialized editor change sets */
+export interface TabHistory {
+    done: unknown[];
+    undone: unknown[];
+    doc_length: number;
+}
+
+/** Owner of the data folder lock */
+export interface LockOwner {
+    pid: number;
+    started: string;
+    heartbeat: number;
+    exe: string | null;
+}
+
+/** Whether this instance owns the data folder; the database is read-only when it doesn't */
+export interface InstanceStatus {
+    owner: boolean;
+    lock_path: string;
+    held_by: LockOwner | null;
+    recovered: LockOwner | null;
+    restart_required: boolean;
+}
+
+export type ExportFormat = 'html' | 'pdf' | 'mediawiki' | 'rst';
+
+export interface ExportPreset {
+    name: string;
+    format: ExportFormat;
+    theme?: string | null;
+    pageSetup?: { paper: string; landscape: boolean };
+    template?: string | null;
+    outputFolder?: string | null;
+    flavor?: string | null;
+}
+
+export interface ExportConfig {
+    preset?: string | null;
+    format?: ExportFormat | 'wiki' | 'restructuredtext' | null;
+    theme?: string | null;
+    paper?: string | null;
+    landscape?: boolean | null;
+    template?: string | null;
+    outputFolder?: string | null;
+    flavor?: string | null;
+}
+
+export interface ExportInput {
+    content: string;
+    title: string;
+    path?: string | null;
+}
+
+export interface LintIssue {
+    rule: string;
+    severity: 'info' | 'warning' | 'error';
+    line: number;
+    message: string;
+    fixable: boolean;
+}
+
+export interface GlossaryTerm {
+    term: string;
+    definition: string | null;
+    deprecated: string[];
+    case_sensitive: boolean;
+}
+
+export interface GlossaryMatches {
+    glossary_path: string | null;
+    terms: GlossaryTerm[];
+    matches: {
+        line: number;
+        from: number;
+        to: number;
+        term: number;
+        deprecated: string | null;
+    }[];
+}
+
+export interface PreSaveReport {
+    content: string;
+    blocked: boolean;
+    formatted: boolean;
+    fixed: number;
+    issues: LintIssue[];
 }
 
 // Format document is handled via format_markdown, not transform_text_content
@@ -39,9 +216,128 @@ export interface BackendCommands {
         args: { activeTabs: unknown[]; closedTabs: unknown[] };
         return: void;
     };
+    mark_session_dirty: {
+        args: { tabId?: string | null };
+        return: void;
+    };
+    get_safe_mode: {
+        args: Record<string, never>;
+        return: { active: boolean; reason: string | null; unclean_shutdown: boolean };
+    };
+    mark_startup_complete: {
+        args: Record<string, never>;
+        return: void;
+    };
+    get_startup_report: {
+        args: Record<string, never>;
+        return: {
+            phases: Array<{
+                name: string;
+                duration_ms: number;
+                deferred: boolean;
+                finished_at_ms: number;
+            }>;
+            setup_ms: number | null;
+            interactive_ms: number | null;
+        };
+    };
     restore_session: {
         args: Record<string, never>;
-        return: { active_tabs: unknown[]; closed_tabs: unknown[] } | unknown[];
+        return:
+            | {
+                  active_tabs: unknown[];
+                  closed_tabs: unknown[];
+                  open_paths?: string[];
+                  tab_groups?: TabGroup[];
+                  tab_meta?: Record<string, Record<string, unknown>>;
+              }
+            | unknown[];
+    };
+    create_tab_group: {
+        args: { name: string; color?: string | null };
+        return: TabGroup;
+    };
+    update_tab_group: {
+        args: { group: TabGroup };
+        return: void;
+    };
+    delete_tab_group: {
+        args: { id: string };
+        return: void;
+    };
+    record_search: {
+        args: { query: SearchHistoryQuery };
+        return: SavedSearch;
+    };
+    save_search: {
+        args: { name: string; query: SearchHistoryQuery };
+        return: SavedSearch;
+    };
+    list_searches: {
+        args: { savedOnly?: boolean; limit?: number };
+        return: SavedSearch[];
+    };
+    pin_search: {
+        args: { id: string; pinned: boolean };
+        return: void;
+    };
+    delete_search: {
+        args: { id: string };
+        return: void;
+    };
+    clear_search_history: {
+        args: Record<string, never>;
+        return: number;
+    };
+    set_tab_meta: {
+        args: { tabId: string; key: string; value: unknown };
+        return: void;
+    };
+    get_tab_meta: {
+        args: { tabId: string };
+        return: Record<string, unknown>;
+    };
+    derive_tab_title: {
+        args: { content: string };
+        return: string | null;
+    };
+    save_tab_history: {
+        args: { tabId: string; history: TabHistory };
+        return: number;
+    };
+    load_tab_history: {
+        args: { tabId: string };
+        return: TabHistory | null;
+    };
+    clear_tab_history: {
+        args: { tabId: string };
+        return: boolean;
+    };
+    start_session: {
+        args: { path?: string | null; mode?: string | null; wordCount: number };
+        return: string;
+    };
+    update_session: {
+        args: { sessionId: string; wordCount: number };
+        return: void;
+    };
+    end_session: {
+        args: { sessionId: string; wordCount?: number };
+        return: WritingSession & { recorded: boolean };
+    };
+    get_writing_stats: {
+        args: { days?: number };
+        return: {
+            days: { date: string; sessions: number; active_secs: number; words_added: number }[];
+            sessions: WritingSession[];
+            active_secs: number;
+            words_added: number;
+            longest_streak_secs: number;
+        };
+    };
+    assign_tabs_to_group: {
+        args: { tabIds: string[]; groupId: string | null };
+        return: number;
     };
     load_tab_content: {
         args: { tabId: string };
@@ -51,6 +347,51 @@ export interface BackendCommands {
         args: Record<string, never>;
         return: void;
     };
+    purge_logs: {
+        args: { includeCurrent?: boolean };
+        return: { files_removed: number; bytes_freed: number };
+    };
+    cancel_job: {
+        args: { jobId: string };
+        return: boolean;
+    };
+    run_maintenance_now: {
+        args: Record<string, never>;
+        return: {
+            closed_tabs_removed: string[];
+            recent_files_removed: number;
+            pages_reclaimed: number;
+        };
+    };
+    get_vacuum_log: {
+        args: Record<string, never>;
+        return: {
+            timestamp: string;
+            trigger: 'auto' | 'maintenance' | 'manual';
+            pages_reclaimed: number;
+            pages_remaining: number;
+        }[];
+    };
+    get_memory_stats: {
+        args: Record<string, never>;
+        return: {
+            speller_bytes: number;
+            custom_words: number;
+            text_metrics_documents: number;
+            text_metrics_bytes: number;
+            theme_cache_bytes: number;
+            render_cache_bytes: number;
+            database: {
+                sqlite_heap_bytes: number;
+                page_cache_limit_bytes: number;
+                connections: number;
+            };
+        };
+    };
+    trim_caches: {
+        args: Record<string, never>;
+        return: number;
+    };
 
     // File System
     read_text_file: {
@@ -58,7 +399,7 @@ export interface BackendCommands {
         return: FileContent;
     };
     write_text_file: {
-        args: { path: string; content: string };
+        args: { path: string; content: string; encoding?: string; hasBom?: boolean };
         return: void;
     };
     write_binary_file: {
@@ -69,6 +410,16 @@ export interface BackendCommands {
         args: { path: string };
         return: FileMetadata;
     };
+    list_file_versions: {
+        args: { path: string };
+        return: {
+            path: string;
+            source: 'shadow-copy' | 'file-history';
+            created: string | null;
+            modified: string | null;
+            size: number;
+        }[];
+    };
     resolve_path_relative: {
         args: { basePath: string | null; clickPath: string };
         return: string;
@@ -78,8 +429,24 @@ export interface BackendCommands {
         return: void;
     };
     rename_file: {
-        args: { oldPath: string; newPath: string };
-        return: void;
+        args: {
+            oldPath: string;
+            newPath: string;
+            updateLinks?: boolean;
+            workspace?: string;
+            skipPaths?: string[];
+            jobId?: string;
+        };
+        return: {
+            links_updated: number;
+            files_updated: string[];
+            skipped: string[];
+            cancelled: boolean;
+        };
+    };
+    archive_note: {
+        args: { path: string };
+        return: { path: string; links_updated: number; files_updated: string[] };
     };
     add_to_recent_files: {
         args: { path: string; lastOpened: string };
@@ -97,6 +464,14 @@ export interface BackendCommands {
         args: Record<string, never>;
         return: void;
     };
+    append_to_inbox: {
+        args: { text: string };
+        return: string;
+    };
+    quick_capture_ready: {
+        args: Record<string, never>;
+        return: void;
+    };
 
     // App Info
     get_app_info: {
@@ -116,11 +491,26 @@ export interface BackendCommands {
     init_spellchecker: {
         args: {
             dictionaries?: string[];
-            technicalDictionaries?: boolean;
-            scienceDictionaries?: boolean;
+            specialistDictionaries?: string[];
         };
         return: void;
     };
+    unload_spellchecker: {
+        args: Record<string, never>;
+        return: number;
+    };
+    list_available_dictionaries: {
+        args: Record<string, never>;
+        return: {
+            id: string;
+            name: string;
+            description: string;
+            category: 'technical' | 'science';
+            size_bytes: number | null;
+            word_count: number | null;
+            loaded: boolean;
+        }[];
+    };
     check_words: {
         args: { words: string[] };
         return: string[];
@@ -133,12 +523,143 @@ export interface BackendCommands {
         args: Record<string, never>;
         return: string;
     };
+    get_editor_status: {
+        args: {
+            documentId: string;
+            cursorOffset: number;
+            path?: string | null;
+            wordCountMode?: WordCountMode;
+            flavor?: string;
+        };
+        return: {
+            line_count: number;
+            word_count: number;
+            char_count: number;
+            widest_column: number;
+            line: number;
+            column: number;
+            line_width: number;
+            char_offset: number;
+            word_index: number;
+            encoding: string | null;
+            has_bom: boolean;
+            line_ending: 'LF' | 'CRLF' | null;
+            spellcheck: 'uninitialized' | 'loading' | 'ready' | 'failed';
+            git_branch: string | null;
+            dirty: boolean;
+        } | null;
+    };
+    get_dictionary_usage: {
+        args: { content: string; flavor?: string };
+        return: {
+            dictionaries: {
+                id: string;
+                kind: 'language' | 'specialist';
+                matched_words: number;
+                occurrences: number;
+                unique_words: number;
+            }[];
+            unused_custom_words: string[];
+            distinct_words: number;
+        };
+    };
 
     // Markdown / Text
     render_markdown: {
-        args: { content: string; flavor?: string };
+        args: {
+            content: string;
+            flavor?: string;
+            remotePolicy?: RemotePolicy;
+            extensions?: MarkdownExtensions;
+            hideFrontMatter?: boolean;
+            headingIdPrefix?: string;
+            basePath?: string | null;
+            htmlPolicy?: HtmlPolicy;
+            wordCountMode?: WordCountMode;
+        };
         return: RenderResult;
     };
+    render_markdown_incremental: {
+        args: {
+            content: string;
+            flavor?: string;
+            remotePolicy?: RemotePolicy;
+            extensions?: MarkdownExtensions;
+            hideFrontMatter?: boolean;
+            headingIdPrefix?: string;
+            basePath?: string | null;
+            htmlPolicy?: HtmlPolicy;
+            wordCountMode?: WordCountMode;
+            previousHash?: string | null;
+            edit?: { from: number; to: number } | null;
+        };
+        return: {
+            content_hash: string;
+            blocks: string[] | null;
+            patch: {
+                start: number;
+                delete_count: number;
+                fragments: string[];
+                line_shift: number;
+            } | null;
+            line_count: number;
+            word_count: number;
+            char_count: number;
+            readability: Readability;
+            sanitization: SanitizationReport;
+            heading_ids: string[];
+            local_images: string[];
+            document_css: string | null;
+            highlight_css: string | null;
+        };
+    };
+    render_markdown_diff: {
+        args: { old: string; new: string; flavor?: string };
+        return: {
+            html: string;
+            blocks_added: number;
+            blocks_removed: number;
+            blocks_changed: number;
+            sanitization: SanitizationReport;
+            local_images: string[];
+        };
+    };
+    render_markdown_inline: {
+        args: { snippet: string; flavor?: string };
+        return: string;
+    };
+    get_document_outline: {
+        args: { content: string; flavor?: string };
+        return: OutlineNode[];
+    };
+    get_breadcrumb: {
+        args: { content: string; cursorOffset: number; flavor?: string };
+        return: BreadcrumbItem[];
+    };
+    search_symbols: {
+        args: { query: string; kind?: SymbolKind; limit?: number };
+        return: SymbolMatch[];
+    };
+    get_front_matter: {
+        args: { content: string };
+        return: {
+            format: 'yaml' | 'toml';
+            data: Record<string, unknown> | null;
+            line_count: number;
+        } | null;
+    };
+    insert_toc: {
+        args: {
+            content: string;
+            line?: number;
+            maxDepth?: number;
+            ordered?: boolean;
+            flavor?: string;
+            listIndent?: number;
+            bulletChar?: string;
+        };
+        return: string;
+    };
     format_markdown: {
         args: {
             content: string;
@@ -148,17 +669,315 @@ export interface BackendCommands {
             codeBlockFence?: string;
             emphasisChar?: string;
             tableAlignment?: boolean;
+            textWrap?: 'maintain' | 'always' | 'never';
+            lineWidth?: number;
+            linkStyle?: 'maintain' | 'reference' | 'inline';
+            orderedListStyle?: 'preserve' | 'one' | 'increment';
+            jobId?: string;
         };
         return: string;
     };
+    verify_roundtrip: {
+        args: {
+            content: string;
+            flavor?: string;
+            listIndent?: number;
+            bulletChar?: string;
+            codeBlockFence?: string;
+            emphasisChar?: string;
+            tableAlignment?: boolean;
+            maxBlankLines?: number;
+            textWrap?: 'maintain' | 'always' | 'never';
+            lineWidth?: number;
+            linkStyle?: 'maintain' | 'reference' | 'inline';
+            orderedListStyle?: 'preserve' | 'one' | 'increment';
+        };
+        return: {
+            content_preserved: boolean;
+            idempotent: boolean;
+            divergences: {
+                kind: 'content' | 'idempotence';
+                before_line: number | null;
+                after_line: number | null;
+                before: string | null;
+                after: string | null;
+            }[];
+        };
+    };
     get_markdown_flavors: {
         args: Record<string, never>;
         return: string[];
     };
     compute_text_metrics: {
-        args: { content: string };
+        args: { content: string; wordCountMode?: WordCountMode; flavor?: string };
         return: [number, number, number, number];
     };
+    update_text_metrics: {
+        args: {
+            documentId: string;
+            edits: { from_line: number; to_line: number; text: string }[];
+            expectedLength: number;
+            content?: string;
+        };
+        return: {
+            metrics: [number, number, number, number];
+            resynced: boolean;
+            needs_full_content: boolean;
+        };
+    };
+    clear_text_metrics: {
+        args: { documentId: string };
+        return: void;
+    };
+    compute_selection_metrics: {
+        args: { content: string; ranges: { from: number; to: number }[] };
+        return: {
+            words: number;
+            chars: number;
+            lines: number;
+            reading_time_secs: number;
+            ranges: number;
+        };
+    };
+    strip_markdown: {
+        args: { content: string; flavor?: string };
+        return: string;
+    };
+    get_file_summary: {
+        args: { path: string; maxChars?: number; flavor?: string };
+        return: { title: string | null; summary: string } | null;
+    };
+    get_document_stats: {
+        args: { content: string; flavor?: string };
+        return: {
+            headings: [number, number, number, number, number, number];
+            internal_links: number;
+            external_links: number;
+            images: number;
+            code_blocks: number;
+            footnotes: number;
+            open_tasks: number;
+            closed_tasks: number;
+        };
+    };
+    fix_duplicate_headings: {
+        args: { content: string; renames?: Record<number, string>; flavor?: string };
+        return: {
+            content: string;
+            changes: {
+                line: number;
+                old_text: string;
+                new_text: string;
+                old_anchor: string;
+                new_anchor: string;
+            }[];
+            links_updated: number;
+        };
+    };
+    audit_accessibility: {
+        args: { content: string; flavor?: string };
+        return: {
+            rule: 'image-alt' | 'heading-skip' | 'empty-link' | 'table-header' | 'link-text';
+            severity: 'info' | 'warning' | 'error';
+            line: number;
+            column: number;
+            message: string;
+        }[];
+    };
+    convert_list: {
+        args: {
+            content: string;
+            fromLine: number;
+            toLine: number;
+            target: 'bullet' | 'ordered' | 'task';
+            flavor?: string;
+        };
+        return: { content: string; items_converted: number };
+    };
+    renumber_ordered_lists: {
+        args: { content: string; style: 'preserve' | 'one' | 'increment'; flavor?: string };
+        return: { content: string; items_converted: number };
+    };
+    move_section: {
+        args: { content: string; headingLine: number; direction: 'up' | 'down'; flavor?: string };
+        return: { content: string; cursor_line: number };
+    };
+    promote_headings: {
+        args: {
+            content: string;
+            levels?: number;
+            fromLine?: number;
+            toLine?: number;
+            flavor?: string;
+        };
+        return: { content: string; shifted: number; warnings: string[] };
+    };
+    demote_headings: {
+        args: {
+            content: string;
+            levels?: number;
+            fromLine?: number;
+            toLine?: number;
+            flavor?: string;
+        };
+        return: { content: string; shifted: number; warnings: string[] };
+    };
+    extract_section: {
+        args: {
+            content: string;
+            headingLine: number;
+            targetPath: string;
+            sourcePath?: string;
+            template?: string;
+            workspace?: string;
+            flavor?: string;
+        };
+        return: { content: string; target_path: string; link: string };
+    };
+    get_template_variables: {
+        args: { path?: string; workspace?: string };
+        return: {
+            workspace: string | null;
+            relative_path: string | null;
+            file_name: string | null;
+            title: string | null;
+            git_branch: string | null;
+            author: string | null;
+            date: string;
+            time: string;
+        };
+    };
+    expand_template: {
+        args: { template: string; path?: string; workspace?: string };
+        return: string;
+    };
+    create_note: {
+        args: { folder: string; name: string; template?: string; workspace?: string };
+        return: { path: string; content: string };
+    };
+    convert_html_to_markdown: {
+        args: { html: string };
+        return: string;
+    };
+    convert_inline_html: {
+        args: { content: string; flavor?: string };
+        return: {
+            content: string;
+            converted: number;
+            left: { line: number; tag: string; reason: string }[];
+        };
+    };
+    clean_word_paste: {
+        args: { htmlOrText: string };
+        return: string;
+    };
+    combine_files: {
+        args: {
+            paths: string[];
+            options?: {
+                headingOffset?: number;
+                deduplicateReferences?: boolean;
+                toc?: boolean;
+                tocDepth?: number;
+                tocTitle?: string;
+                separator?: string | null;
+            };
+            flavor?: string;
+        };
+        return: {
+            content: string;
+            clamped_headings: number;
+            renamed_labels: number;
+            removed_definitions: number;
+        };
+    };
+    merge_documents: {
+        args: { base: string; ours: string; theirs: string };
+        return: {
+            content: string;
+            conflicts: {
+                start_line: number;
+                end_line: number;
+                base: string;
+                ours: string;
+                theirs: string;
+            }[];
+        };
+    };
+    compare_tabs: {
+        args: { tabA: string; tabB: string };
+        return: {
+            rows: {
+                kind: 'equal' | 'changed' | 'added' | 'removed';
+                left_line: number | null;
+                right_line: number | null;
+            }[];
+            changes: { start_row: number; end_row: number }[];
+            lines_added: number;
+            lines_removed: number;
+            lines_changed: number;
+        };
+    };
+    find_similar: {
+        args: {
+            path?: string | null;
+            content?: string | null;
+            folder?: string | null;
+            threshold?: number;
+            limit?: number;
+            jobId?: string;
+        };
+        return: { path: string; similarity: number; similar_to: string | null }[];
+    };
+    generate_index: {
+        args: {
+            root: string;
+            options?: {
+                fileName?: string;
+                groupBy?: 'folder' | 'category';
+                categoryField?: string;
+                title?: string | null;
+            };
+            flavor?: string;
+        };
+        return: {
+            path: string;
+            entries: { path: string; title: string; group: string }[];
+            written: boolean;
+        };
+    };
+    run_presave_checks: {
+        args: { path?: string | null; content: string };
+        return: PreSaveReport;
+    };
+    save_with_checks: {
+        args: {
+            path: string;
+            content: string;
+            lineEnding?: string;
+            encoding?: string;
+            hasBom?: boolean;
+        };
+        return: PreSaveReport;
+    };
+    get_glossary_matches: {
+        args: { path: string; content: string };
+        return: GlossaryMatches;
+    };
+
+    // Open paths
+    sync_open_paths: {
+        args: { tabs: { id: string; path: string }[] };
+        return: void;
+    };
+    is_path_open: {
+        args: { path: string };
+        return: string | null;
+    };
+    focus_tab_for_path: {
+        args: { path: string };
+        return: boolean;
+    };
 
     // Bookmarks
     add_bookmark: {
@@ -169,14 +988,71 @@ export interface BackendCommands {
         args: Record<string, never>;
         return: Bookmark[];
     };
+    update_bookmark: {
+        args: { id: string; title: string; path: string; tags: string[] };
+        return: Bookmark;
+    };
     delete_bookmark: {
         args: { id: string };
         return: void;
     };
+    delete_bookmarks: {
+        args: { ids: string[] };
+        return: number;
+    };
+    tag_bookmarks: {
+        args: { ids: string[]; tag: string };
+        return: number;
+    };
+    move_bookmarks: {
+        args: { ids: string[]; folder: string | null };
+        return: number;
+    };
     update_bookmark_access_time: {
         args: { id: string; lastAccessed: string };
         return: void;
     };
+    find_bookmarks_by_tag: {
+        args: { tag: string };
+        return: Bookmark[];
+    };
+    list_bookmark_tags: {
+        args: Record<string, never>;
+        return: { tag: string; count: number }[];
+    };
+    rename_tag: {
+        args: { oldTag: string; newTag: string };
+        return: number;
+    };
+
+    // Annotations
+    add_annotation: {
+        args: {
+            path: string;
+            content: string;
+            from: number;
+            to: number;
+            body: string;
+            author?: string | null;
+        };
+        return: Annotation;
+    };
+    list_annotations: {
+        args: { path: string; content?: string | null };
+        return: (Annotation & { location: AnchorLocation | null })[];
+    };
+    update_annotation: {
+        args: { id: string; body?: string | null; resolved?: boolean | null };
+        return: Annotation;
+    };
+    delete_annotation: {
+        args: { id: string };
+        return: void;
+    };
+    export_annotations: {
+        args: { path: string; content: string; includeResolved?: boolean; title?: string };
+        return: string;
+    };
 
     // Settings / Themes
     get_available_themes: {
@@ -216,9 +1092,108 @@ export interface BackendCommands {
 
     // Export
     export_to_pdf: {
-        args: { path: string; content: string; title: string; backgroundColor: string | null };
+        args: {
+            path: string;
+            content: string;
+            title: string;
+            backgroundColor: string | null;
+            sourcePath?: string | null;
+            fontPath?: string | null;
+        };
         return: void;
     };
+    export_to_epub: {
+        args: {
+            path: string;
+            content: string;
+            options?: {
+                title?: string;
+                author?: string | null;
+                language?: string | null;
+                basePath?: string | null;
+                flavor?: string;
+                splitLevel?: number;
+            };
+        };
+        return: void;
+    };
+    export_to_slides: {
+        args: {
+            path: string;
+            content: string;
+            options?: {
+                title?: string;
+                theme?: string | null;
+                split?: 'auto' | 'rule' | 'heading';
+                flavor?: string;
+            };
+        };
+        return: { slide_count: number; report: SanitizationReport };
+    };
+    list_export_presets: {
+        args: Record<string, never>;
+        return: ExportPreset[];
+    };
+    save_export_preset: {
+        args: { preset: ExportPreset };
+        return: void;
+    };
+    delete_export_preset: {
+        args: { name: string };
+        return: boolean;
+    };
+    export_with_preset: {
+        args: { preset: string; input: ExportInput };
+        return: { path: string; report: SanitizationReport };
+    };
+    convert_to_markup: {
+        args: { content: string; format: 'mediawiki' | 'rst'; flavor?: string };
+        return: string;
+    };
+    copy_as_html: {
+        args: { content: string; flavor?: string; theme?: string };
+        return: SanitizationReport;
+    };
+    get_export_config: {
+        args: { content: string };
+        return: ExportConfig | null;
+    };
+    export_document: {
+        args: { input: ExportInput };
+        return: { path: string; report: SanitizationReport };
+    };
+    export_tabs: {
+        args: {
+            path: string;
+            tabs: ExportInput[];
+            options?: {
+                format?: ExportFormat;
+                title?: string;
+                toc?: boolean;
+                theme?: string | null;
+                pageSetup?: { paper: string; landscape: boolean };
+                flavor?: string | null;
+            };
+            jobId?: string;
+        };
+        return: SanitizationReport;
+    };
+    watch_export: {
+        args: { path: string; preset: string };
+        return: void;
+    };
+    stop_watch_export: {
+        args: { path: string };
+        return: boolean;
+    };
+    list_export_watches: {
+        args: Record<string, never>;
+        return: { path: string; preset: string }[];
+    };
+    write_live_preview: {
+        args: { content: string; title: string; path?: string | null; flavor?: string };
+        return: string | null;
+    };
     // Data Management
     export_bookmarks: {
         args: Record<string, never>;
@@ -228,6 +1203,10 @@ export interface BackendCommands {
         args: { bookmarks: Bookmark[] };
         return: number;
     };
+    import_browser_bookmarks: {
+        args: { path: string; addLocalFiles?: boolean };
+        return: { markdown: string; links: number; folders: number; local_files_added: number };
+    };
     export_recent_files: {
         args: Record<string, never>;
         return: string[];
@@ -240,6 +1219,22 @@ export interface BackendCommands {
         args: Record<string, never>;
         return: number;
     };
+    get_database_status: {
+        args: Record<string, never>;
+        return: { schema_version: number; supported_version: number; read_only: boolean };
+    };
+    get_instance_status: {
+        args: Record<string, never>;
+        return: InstanceStatus;
+    };
+    recover_instance_lock: {
+        args: Record<string, never>;
+        return: InstanceStatus;
+    };
+    export_database: {
+        args: { path: string };
+        return: void;
+    };
 
     // Window State (Plugin)
     'plugin:window-state|save_window_state': {
@@ -1,4 +1,24 @@
-export type MarkdownFlavor = 'commonmark' | 'gfm';
+export type MarkdownFlavor = 'commonmark' | 'gfm' | 'custom';
+
+/** Extensions for the 'custom' flavor; omitted keys keep the GFM defaults */
+export interface CustomFlavor {
+    table?: boolean;
+    strikethrough?: boolean;
+    autolink?: boolean;
+    tasklist?: boolean;
+    tagfilter?: boolean;
+    footnotes?: boolean;
+    math?: boolean;
+    superscript?: boolean;
+    subscript?: boolean;
+    spoiler?: boolean;
+    underline?: boolean;
+    highlight?: boolean;
+    descriptionLists?: boolean;
+    alerts?: boolean;
+    multilineBlockQuotes?: boolean;
+    wikilinks?: boolean;
+}
 
 export interface FormatterOptions {
     flavor?: MarkdownFlavor;
@@ -13,4 +33,51 @@ export interface RenderResult {
     line_map: Record<number, number>;
     word_count: number;
     char_count: number;
+    readability?: Readability;
+    sanitization?: SanitizationReport;
+    /** Heading ids in document order, prefix included */
+    heading_ids?: string[];
+    /** Local files the HTML loads through the `preview` protocol */
+    local_images?: string[];
+    /** Sanitized `css:` front matter, scoped to `.document-css` */
+    document_css?: string | null;
+    /** Stylesheet for highlighted code */
+    highlight_css?: string | null;
+}
+
+/** Reading time and readability of the document's paragraphs */
+export interface Readability {
+    reading_time_secs: number;
+    sentence_count: number;
+    /** Words per sentence */
+    avg_sentence_length: number;
+    /** Flesch–Kincaid grade level; null when there is no prose */
+    flesch_kincaid_grade: number | null;
+}
+
+/** What rendering left out of or rewrote in the HTML */
+export interface SanitizationReport {
+    raw_html_removed: number;
+    scripts_dropped: number;
+    remote_images_blocked: number;
+    urls_upgraded: number;
+    links_cleaned: number;
+}
+
+/** Optional syntax on top of the selected flavor */
+export interface MarkdownExtensions {
+    math?: boolean;
+    footnotes?: boolean;
+}
+
+/** Raw HTML in markdown: left out, filtered to safe tags, or rendered as written */
+export type HtmlPolicy = 'off' | 'sanitize' | 'allow';
+
+/** Word count over all text, or prose only (no code, HTML, front matter or bare URLs) */
+export type WordCountMode = 'all' | 'prose' | 'text';
+
+export interface RemotePolicy {
+    blockRemoteImages?: boolean;
+    forceHttps?: boolean;
+    stripTrackingParams?: boolean;
 }
@@ -12,6 +12,7 @@ const DEFAULT_CONFIG = {
         SMART_TITLE_MAX_LENGTH: 25,
         CLOSED_TABS_HISTORY_LIMIT: 12,
         LINE_CHANGE_TRACK_LIMIT: 50,
+        WRITING_IDLE_MS: 600000, // A writing session ends after this long without edits
     },
 
     // Spellcheck Settings
@@ -40,10 +41,10 @@ const DEFAULT_CONFIG = {
 
     // Session & Auto-save
     SESSION: {
-        AUTO_SAVE_INTERVAL_MS: 5000, // Main auto-save interval for session persistence
-        SETTINGS_SAVE_INTERVAL_MS: 5000, // Settings auto-save interval (same as session for consistency)
+        // Session saves are timed by the backend's autosave policy
+        SETTINGS_SAVE_INTERVAL_MS: 5000,
         SAVE_DEBOUNCE_MS: 500,
-        SAVE_ON_BLUR: true,
+        CHANGE_REPORT_INTERVAL_MS: 1000, // Throttle for reporting edits to the backend
     },
 
     // Performance
@@ -55,12 +56,15 @@ const DEFAULT_CONFIG = {
         SCROLL_SYNC_RESIZE_DEBOUNCE_MS: 200,
         FILE_WATCH_DEBOUNCE_MS: 300,
         WORD_COUNT_DEBOUNCE_MS: 500,
+        TAB_TITLE_DEBOUNCE_MS: 300,
         PREVIEW_RENDER_DEBOUNCE_MS: 250,
         PREVIEW_SPINNER_DELAY_MS: 500,
         FILE_WATCHER_LOCK_BUFFER_MS: 700,
         INCREMENTAL_RENDER_MIN_SIZE: 1000,
         INCREMENTAL_BLOCK_SIZE_LIMIT: 200,
         INCREMENTAL_CACHE_LIMIT: 1000,
+        // Matches the backend's threshold for block-level rendering
+        INCREMENTAL_PREVIEW_MIN_BYTES: 128 * 1024,
     },
 
     // UI Timing - Delays and debounces for UI interactions
@@ -12,6 +12,8 @@ export type ErrorContext =
     | 'Settings:Load'
     | 'Settings:Save'
     | 'Editor:Init'
+    | 'Editor:Completion'
+    | 'Editor:Status'
     | 'Database:Init'
     | 'Database:Migration'
     | 'Transform:Text'
@@ -22,15 +24,22 @@ export type ErrorContext =
     | 'Update:Install'
     | 'FileWatcher:Unwatch'
     | 'Export:PDF'
+    | 'Export:EPUB'
     | 'Export:HTML'
+    | 'Export:Markup'
     | 'Spellcheck:Init'
     | 'Bookmark:Add'
     | 'Bookmark:Remove'
+    | 'Bookmark:Update'
+    | 'TabGroup:Update'
+    | 'Search:History'
     | 'Data:ExportBookmarks'
     | 'Data:ImportBookmarks'
+    | 'Data:ImportBrowserBookmarks'
     | 'Data:ExportRecent'
     | 'Data:ImportRecent'
-    | 'Data:DeleteOrphans';
+    | 'Data:DeleteOrphans'
+    | 'Inbox:Capture';
 
 export type ErrorSeverity = 'info' | 'warning' | 'error' | 'critical';
 
@@ -43,6 +52,27 @@ export interface ErrorOptions {
     logToDisk?: boolean;
 }
 
+/**
+ * Structured error from a guarded backend command (render, format, search): `too_large`
+ * and `busy` mean the call was turned away before running.
+ */
+export interface BackendError {
+    kind: 'too_large' | 'busy' | 'cancelled' | 'failed';
+    message: string;
+    command?: string;
+    size?: number;
+    limit?: number;
+}
+
+export function isBackendError(error: unknown): error is BackendError {
+    return (
+        typeof error === 'object' &&
+        error !== null &&
+        typeof (error as BackendError).kind === 'string' &&
+        typeof (error as BackendError).message === 'string'
+    );
+}
+
 // Helper to truncate long strings in error logs
 function safeStringify(obj: unknown): string {
     try {
@@ -94,7 +124,12 @@ export class AppError extends Error {
             return error;
         }
 
-        const message = error instanceof Error ? error.message : String(error);
+        const message =
+            error instanceof Error
+                ? error.message
+                : isBackendError(error)
+                  ? error.message
+                  : String(error);
         const originalError = error instanceof Error ? error : undefined;
 
         return new AppError(context, message, { ...options, originalError });
@@ -222,12 +257,20 @@ export class AppError extends Error {
             case 'Dictionary:Add':
                 return 'Failed to add word to dictionary';
             case 'Export:PDF':
+            case 'Export:EPUB':
             case 'Export:HTML':
+            case 'Export:Markup':
                 return 'Export failed';
             case 'Bookmark:Add':
                 return 'Failed to add bookmark';
             case 'Bookmark:Remove':
                 return 'Failed to remove bookmark';
+            case 'Bookmark:Update':
+                return 'Failed to update bookmark';
+            case 'TabGroup:Update':
+                return 'Failed to update tab group';
+            case 'Search:History':
+                return 'Failed to update saved searches';
             default:
                 return this.message || 'An error occurred';
         }
@@ -0,0 +1,30 @@
+import { callBackendSafe } from '$lib/utils/backend';
+import { isMarkdownFile } from '$lib/utils/fileValidation';
+import { flavorArg } from '$lib/utils/markdownRust';
+import { SvelteMap } from 'svelte/reactivity';
+
+/**
+ * Tooltips for files listed in the bookmarks and recent files: a markdown file's title
+ * and opening paragraph. Loaded on first hover and kept until `clearFileSummaries`, so a
+ * list shows what the files held when it opened.
+ */
+export const fileSummaries = new SvelteMap<string, string>();
+const requested = new Set<string>();
+
+export function loadFileSummary(path: string) {
+    if (requested.has(path) || !isMarkdownFile(path)) return;
+    requested.add(path);
+
+    void callBackendSafe('get_file_summary', { path, flavor: flavorArg() }, 'File:Read', {
+        showToast: false,
+    }).then((result) => {
+        if (!result) return;
+        const text = [result.title, result.summary].filter(Boolean).join('\n\n');
+        if (text) fileSummaries.set(path, text);
+    });
+}
+
+export function clearFileSummaries() {
+    fileSummaries.clear();
+    requested.clear();
+}
@@ -9,6 +9,7 @@ import {
     normalizeLineEndings,
 } from '$lib/services/fileMetadata';
 import { fileWatcher } from '$lib/services/fileWatcher';
+import { findOpenTab } from '$lib/services/openPaths';
 import {
     loadSession,
     persistSession,
@@ -23,6 +24,7 @@ import {
     pushToMru,
     reopenClosedTab,
     saveTabComplete,
+    setSavedContent,
     updateContentOnly,
     updateTabMetadataAndPath,
     updateTabTitle,
@@ -40,6 +42,7 @@ import { callBackend } from './backend';
 import { CONFIG } from './config';
 import { isMarkdownFile, SUPPORTED_TEXT_EXTENSIONS } from './fileValidation';
 import { formatMarkdown } from './formatterRust';
+import { flavorArg } from './markdownRust';
 import { countWords } from './textMetrics';
 
 export {
@@ -87,7 +90,11 @@ export async function openFile(path?: string): Promise<void> {
         }
 
         const sanitizedPath = sanitizePath(targetPath);
-        const existingTab = appContext.editor.tabs.find((t) => t.path === sanitizedPath);
+        // Exact match first; the backend also catches other spellings of the same file
+        const existingTabId =
+            appContext.editor.tabs.find((t) => t.path === sanitizedPath)?.id ??
+            (await findOpenTab(sanitizedPath));
+        const existingTab = appContext.editor.tabs.find((t) => t.id === existingTabId);
 
         // Always update recent files, even if tab exists
         addToRecentFiles(sanitizedPath);
@@ -128,29 +135,22 @@ export async function openFile(path?: string): Promise<void> {
 
         const fileName = sanitizedPath.split(/[\\/]/).pop() || 'Untitled';
 
-        const crlfCount = (result.content.match(/\r\n/g) || []).length;
-        const lfOnlyCount = (result.content.match(/(?<!\r)\n/g) || []).length;
-        const detectedLineEnding: 'LF' | 'CRLF' =
-            crlfCount > 0 && (crlfCount >= lfOnlyCount || lfOnlyCount === 0) ? 'CRLF' : 'LF';
+        const detectedLineEnding = result.line_ending;
 
         let initialTitle = fileName;
         if (appContext.app.tabNameFromContent) {
-            const trimmed = result.content.trim();
-            if (trimmed.length > 0) {
-                const lines = result.content.split('\n');
-                const firstLine = lines.find((l) => l.trim().length > 0) || '';
-                let smartTitle = firstLine.replace(/^#+\s*/, '').trim();
-                const MAX_LEN = 25;
-                if (smartTitle.length > MAX_LEN) {
-                    smartTitle = smartTitle.substring(0, MAX_LEN).trim() + '...';
-                }
-                if (smartTitle.length > 0) {
-                    initialTitle = smartTitle;
-                }
+            const smartTitle = await callBackend(
+                'derive_tab_title',
+                { content: result.content },
+                'File:Read',
+            );
+            if (smartTitle) {
+                initialTitle = smartTitle;
             }
         }
 
         const id = addTab(initialTitle, result.content);
+        setSavedContent(id, normalizeLineEndings(result.content));
 
         const lineArray = result.content.split('\n');
         const lineCount = lineArray.length;
@@ -158,10 +158,15 @@ export async function openFile(path?: string): Promise<void> {
         const widestColumn = lineArray.reduce((max, line) => Math.max(max, line.length), 0);
 
         let initialWordCount = 0;
-        if (result.content.length > CONFIG.PERFORMANCE.LARGE_FILE_SIZE_BYTES) {
+        const astCount = appContext.app.wordCountMode !== 'all';
+        if (astCount || result.content.length > CONFIG.PERFORMANCE.LARGE_FILE_SIZE_BYTES) {
             const metrics = await callBackend(
                 'compute_text_metrics',
-                { content: result.content },
+                {
+                    content: result.content,
+                    wordCountMode: appContext.app.wordCountMode,
+                    flavor: flavorArg(),
+                },
                 'File:Read',
             );
             if (metrics) initialWordCount = metrics[1];
@@ -175,6 +180,7 @@ export async function openFile(path?: string): Promise<void> {
             isDirty: false,
             lineEnding: detectedLineEnding,
             encoding: result.encoding.toUpperCase(),
+            hasBom: result.has_bom,
             sizeBytes: new TextEncoder().encode(result.content).length,
             wordCount: initialWordCount,
             lineCount,
@@ -344,11 +350,53 @@ async function saveFile(forceNewPath: boolean): Promise<boolean> {
             fileWatcher.setWriteLock(sanitizedPath, true);
 
             try {
-                await callBackend(
-                    'write_text_file',
-                    { path: sanitizedPath, content: diskContent },
-                    'File:Write',
-                );
+                if (isMarkdownFile(sanitizedPath)) {
+                    // Markdown saves go through the pre-save checks, which may fix or block them
+                    const report = await callBackend(
+                        'save_with_checks',
+                        {
+                            path: sanitizedPath,
+                            content: normalizeLineEndings(contentToSave),
+                            lineEnding: targetLineEnding,
+                            encoding: tab.encoding,
+                            hasBom: tab.hasBom ?? false,
+                        },
+                        'File:Write',
+                    );
+                    if (report.blocked) {
+                        fileWatcher.setWriteLock(sanitizedPath, false);
+                        if (pendingSavePath) activeSaves.delete(pendingSavePath);
+                        const first = report.issues[0];
+                        showToast(
+                            'error',
+                            `Save blocked: ${first ? `line ${first.line}: ${first.message}` : 'pre-save checks failed'}`,
+                        );
+                        return false;
+                    }
+                    if (report.content !== normalizeLineEndings(contentToSave)) {
+                        contentToSave = report.content;
+                        updateContentOnly(tabId, contentToSave, true);
+                        tab = getTab()!;
+                    }
+                    if (report.issues.length > 0) {
+                        const first = report.issues[0];
+                        showToast(
+                            'warning',
+                            `Saved with ${report.issues.length} issue(s), first at line ${first.line}: ${first.message}`,
+                        );
+                    }
+                } else {
+                    await callBackend(
+                        'write_text_file',
+                        {
+                            path: sanitizedPath,
+                            content: diskContent,
+                            encoding: tab.encoding,
+                            hasBom: tab.hasBom ?? false,
+                        },
+                        'File:Write',
+                    );
+                }
             } catch (err) {
                 fileWatcher.setWriteLock(sanitizedPath, false);
                 if (pendingSavePath) activeSaves.delete(pendingSavePath);
@@ -367,10 +415,12 @@ async function saveFile(forceNewPath: boolean): Promise<boolean> {
             let finalTitle = fileName;
 
             if (appContext.app.tabNameFromContent) {
-                const firstLine = contentToSave.split('\n').find((l) => l.trim().length > 0) || '';
-                let smartTitle = firstLine.replace(/^#+\s*/, '').trim();
-                if (smartTitle.length > 25) smartTitle = smartTitle.substring(0, 25).trim() + '...';
-                if (smartTitle.length > 0) finalTitle = smartTitle;
+                const smartTitle = await callBackend(
+                    'derive_tab_title',
+                    { content: contentToSave },
+                    'File:Write',
+                );
+                if (smartTitle) finalTitle = smartTitle;
             }
 
             saveTabComplete(tabId, sanitizedPath, finalTitle, targetLineEnding);
@@ -449,6 +499,54 @@ export async function requestCloseTab(id: string, force = false): Promise<void>
     persistSessionDebounced();
 }
 
+/**
+ * Moves the tab's file into the archive folder and points the tab at its new location.
+ * Links to it in other notes are updated by the backend.
+ */
+export async function archiveNote(tabId: string): Promise<boolean> {
+    const tab = appContext.editor.tabs.find((t) => t.id === tabId);
+    if (!tab?.path) return false;
+
+    if (tab.isDirty) {
+        showToast('warning', 'Save the note before archiving it');
+        return false;
+    }
+
+    const oldPath = sanitizePath(tab.path);
+    const result = await callBackend('archive_note', { path: oldPath }, 'File:Write', undefined, {
+        report: true,
+        msg: 'Failed to archive note',
+    });
+    if (!result) return false;
+
+    const newPath = sanitizePath(result.path);
+    fileWatcher.unwatch(oldPath);
+    await fileWatcher.watch(newPath);
+
+    for (const t of appContext.editor.tabs) {
+        if (t.path && sanitizePath(t.path) === oldPath) {
+            updateTabMetadataAndPath(t.id, { path: newPath });
+        }
+    }
+    invalidateMetadataCache(oldPath);
+    invalidateMetadataCache(newPath);
+    addToRecentFiles(newPath);
+
+    const bookmark = getBookmarkByPath(oldPath);
+    if (bookmark) {
+        await updateBookmark(bookmark.id, bookmark.title, bookmark.tags, newPath);
+    }
+
+    const links = result.links_updated;
+    showToast(
+        'success',
+        links > 0
+            ? `Archived note and updated ${links} link${links === 1 ? '' : 's'}`
+            : 'Archived note',
+    );
+    return true;
+}
+
 export function triggerReopenClosedTab(historyIndex: number): void {
     const reopenedTabId = reopenClosedTab(historyIndex);
     if (reopenedTabId) {
@@ -457,6 +555,32 @@ export function triggerReopenClosedTab(historyIndex: number): void {
     }
 }
 
+/** Paths of open tabs with unsaved changes; link updates must not overwrite them on disk. */
+export function dirtyTabPaths(): string[] {
+    return appContext.editor.tabs
+        .filter((t) => t.isDirty && t.path)
+        .map((t) => sanitizePath(t.path!));
+}
+
+/** Tells the user which notes had links rewritten after a rename, and which were skipped. */
+export function reportLinkUpdates(report: {
+    links_updated: number;
+    files_updated: string[];
+    skipped: string[];
+}): void {
+    if (report.links_updated > 0) {
+        const files = report.files_updated.length;
+        showToast(
+            'info',
+            `Updated ${report.links_updated} link${report.links_updated === 1 ? '' : 's'} in ${files} file${files === 1 ? '' : 's'}`,
+        );
+    }
+    if (report.skipped.length > 0) {
+        const names = report.skipped.map((p) => p.split(/[\\/]/).pop()).join(', ');
+        showToast('warning', `Links not updated in unsaved files: ${names}`);
+    }
+}
+
 export async function renameFile(tabId: string, newName: string): Promise<boolean> {
     const tab = appContext.editor.tabs.find((t) => t.id === tabId);
     if (!tab) return false;
@@ -487,9 +611,9 @@ export async function renameFile(tabId: string, newName: string): Promise<boolea
 
         if (oldPath === newPath) return true;
 
-        await callBackend(
+        const report = await callBackend(
             'rename_file',
-            { oldPath: oldPath, newPath: newPath },
+            { oldPath: oldPath, newPath: newPath, updateLinks: true, skipPaths: dirtyTabPaths() },
             'File:Write',
             undefined,
             {
@@ -497,6 +621,7 @@ export async function renameFile(tabId: string, newName: string): Promise<boolea
                 msg: 'Failed to rename file',
             },
         );
+        if (!report) return false;
 
         fileWatcher.unwatch(oldPath);
         await fileWatcher.watch(newPath);
@@ -519,6 +644,7 @@ export async function renameFile(tabId: string, newName: string): Promise<boolea
         }
 
         showToast('success', `Renamed to ${finalNewName}`);
+        reportLinkUpdates(report);
         return true;
     } catch {
         return false;
@@ -1,5 +1,6 @@
 import { appContext } from '$lib/stores/state.svelte.ts';
 import { callBackendSafe } from '$lib/utils/backend';
+import { flavorArg } from '$lib/utils/markdownRust';
 
 export interface FormatterOptions {
     listIndent: number;
@@ -24,7 +25,7 @@ export async function formatMarkdown(
     const final = { ...defaults, ...options };
 
     const apiOptions = {
-        flavor: appContext.app.markdownFlavor,
+        flavor: flavorArg(),
         listIndent: final.listIndent,
         bulletChar: final.bulletChar,
         codeBlockFence: final.codeBlockFence,
@@ -44,3 +45,29 @@ export async function formatMarkdown(
     // This prevents the editor from detecting changes when only line endings differ
     return result ? result.replace(/\r\n/g, '\n') : content;
 }
+
+/**
+ * Inserts a table of contents before `line` (1-based), or refreshes the existing
+ * `<!-- toc -->` block with the given depth and list style.
+ */
+export async function insertToc(
+    content: string,
+    line?: number,
+    maxDepth: number = 3,
+    ordered: boolean = false,
+): Promise<string> {
+    const result = await callBackendSafe(
+        'insert_toc',
+        {
+            content,
+            line,
+            maxDepth,
+            ordered,
+            flavor: flavorArg(),
+            listIndent: appContext.app.defaultIndent,
+            bulletChar: appContext.app.formatterBulletChar,
+        },
+        'Markdown:Render',
+    );
+    return result ?? content;
+}
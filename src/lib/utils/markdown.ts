@@ -125,12 +125,13 @@ export async function getMarkdownFlavors(): Promise<string[]> {
  * Helper to check if a flavor is supported
  */
 export function isValidFlavor(flavor: string): flavor is MarkdownFlavor {
-    return flavor === 'commonmark' || flavor === 'gfm';
+    return flavor === 'commonmark' || flavor === 'gfm' || flavor === 'custom';
 }
 
 /**
  * Get display name for a markdown flavor
  */
 export function getFlavorDisplayName(flavor: MarkdownFlavor): string {
+    if (flavor === 'custom') return 'Custom';
     return flavor === 'commonmark' ? 'CommonMark' : 'GitHub Flavored Markdown';
 }
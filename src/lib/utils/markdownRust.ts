@@ -1,33 +1,14 @@
-import type { RenderResult } from '$lib/types/markdown';
-import { convertFileSrc } from '@tauri-apps/api/core';
+import { appContext } from '$lib/stores/state.svelte.ts';
+import type {
+    MarkdownExtensions,
+    MarkdownFlavor,
+    RemotePolicy,
+    RenderResult,
+} from '$lib/types/markdown';
 import { error } from '@tauri-apps/plugin-log';
 import DOMPurify from 'dompurify';
 import { callBackendSafe } from './backend';
 
-/**
- * Resolves a relative path to a clean, absolute path with forward slashes.
- * Ensures no '..' segments remain, as they break Tauri's asset protocol.
- */
-function resolvePath(baseDir: string, relativePath: string): string {
-    const cleanBase = baseDir.replace(/\\/g, '/');
-    const cleanRelative = relativePath.replace(/\\/g, '/');
-    const parts = [...cleanBase.split('/'), ...cleanRelative.split('/')].filter(
-        (p) => p && p !== '.',
-    );
-
-    const resolved: string[] = [];
-    for (const part of parts) {
-        if (part === '..') {
-            resolved.pop();
-        } else {
-            resolved.push(part);
-        }
-    }
-
-    const result = resolved.join('/');
-    return /^[a-zA-Z]:/.test(result) ? result : '/' + result;
-}
-
 /**
  * Detects if a path references the static assets directory
  */
@@ -45,15 +26,42 @@ function resolveStaticAssetPath(src: string): string {
     return '/' + filename;
 }
 
+/** Flavor argument for backend commands; custom flavors travel as their JSON form */
+export function flavorArg(flavor: MarkdownFlavor = appContext.app.markdownFlavor): string {
+    if (flavor !== 'custom') return flavor;
+    return JSON.stringify({ custom: appContext.app.customFlavor });
+}
+
+/** Syntax extensions switched on in settings */
+export function currentExtensions(): MarkdownExtensions {
+    return {
+        math: appContext.app.mathRendering,
+        footnotes: appContext.app.renderFootnotes,
+    };
+}
+
 export async function renderMarkdown(
     content: string,
-    gfm: boolean = true,
+    markdownFlavor: MarkdownFlavor = appContext.app.markdownFlavor,
     basePath: string | null = null,
+    remotePolicy?: RemotePolicy,
+    extensions: MarkdownExtensions = currentExtensions(),
+    hideFrontMatter: boolean = false,
 ): Promise<RenderResult> {
-    const flavor = gfm ? 'gfm' : 'commonmark';
+    const flavor = flavorArg(markdownFlavor);
     const result = await callBackendSafe(
         'render_markdown',
-        { content, flavor },
+        {
+            content,
+            flavor,
+            remotePolicy,
+            extensions,
+            hideFrontMatter,
+            headingIdPrefix: appContext.app.headingIdPrefix,
+            basePath,
+            htmlPolicy: appContext.app.htmlPolicy,
+            wordCountMode: appContext.app.wordCountMode,
+        },
         'Markdown:Render',
         {
             showToast: false,
@@ -73,38 +81,27 @@ export async function renderMarkdown(
         };
     }
 
-    let html = result.html;
+    return { ...result, html: sanitizeRenderedHtml(result.html) };
+}
 
+/**
+ * Points images under the app's static folder at the web root and strips anything unsafe
+ * from rendered HTML. Other local images already load through the backend's `preview`
+ * protocol, which only serves files the document references.
+ */
+function sanitizeRenderedHtml(html: string): string {
     if (html.includes('<img')) {
         const parser = new DOMParser();
         const doc = parser.parseFromString(html, 'text/html');
         const images = doc.querySelectorAll('img');
 
         if (images.length > 0) {
-            const directory = basePath ? basePath.replace(/[\\/][^\\/]+$/, '') : '';
-
             images.forEach((img) => {
-                let src = img.getAttribute('src');
-                if (!src || /^(https?|data|blob|asset|tauri):/i.test(src)) return;
-
-                // Standardize slashes before resolution to prevent encoding errors
-                src = src.replace(/\\/g, '/');
+                const src = img.getAttribute('src');
+                if (!src || /^(https?|data|blob|preview|tauri):/i.test(src)) return;
 
-                // Handle static assets specially
-                if (isStaticAssetPath(src)) {
+                if (isStaticAssetPath(src.replace(/\\/g, '/'))) {
                     img.setAttribute('src', resolveStaticAssetPath(src));
-                    return;
-                }
-
-                const absolutePath =
-                    src.startsWith('/') || /^[a-zA-Z]:/.test(src)
-                        ? resolvePath('', src)
-                        : directory
-                          ? resolvePath(directory, src)
-                          : '';
-
-                if (absolutePath) {
-                    img.setAttribute('src', convertFileSrc(absolutePath));
                 }
             });
             html = doc.body.innerHTML;
@@ -117,6 +114,7 @@ export async function renderMarkdown(
             'target',
             'class',
             'data-sourcepos',
+            'data-math-style',
             'align',
             'start',
             'type',
@@ -125,8 +123,121 @@ export async function renderMarkdown(
             'src',
         ],
         ALLOWED_URI_REGEXP:
-            /^(?:(?:(?:f|ht)tps?|mailto|tel|callto|cid|xmpp|asset):|[^a-z]|[a-z+.-]+(?:[^a-z+.\-:]|$))/i,
+            /^(?:(?:(?:f|ht)tps?|mailto|tel|callto|cid|xmpp|preview):|[^a-z]|[a-z+.-]+(?:[^a-z+.\-:]|$))/i,
     });
 
-    return { ...result, html: cleanHtml };
+    return cleanHtml;
+}
+
+/** Block HTML from the last incremental render of one document */
+export interface IncrementalRenderState {
+    hash: string | null;
+    content: string;
+    blocks: string[];
+}
+
+export function createIncrementalState(): IncrementalRenderState {
+    return { hash: null, content: '', blocks: [] };
+}
+
+/** UTF-16 span of `next` that differs from `previous` */
+function changedRange(previous: string, next: string): { from: number; to: number } {
+    const max = Math.min(previous.length, next.length);
+    let from = 0;
+    while (from < max && previous.charCodeAt(from) === next.charCodeAt(from)) from++;
+    let suffix = 0;
+    while (
+        suffix < max - from &&
+        previous.charCodeAt(previous.length - 1 - suffix) ===
+            next.charCodeAt(next.length - 1 - suffix)
+    ) {
+        suffix++;
+    }
+    return { from, to: next.length - suffix };
+}
+
+function shiftSourcepos(html: string, shift: number): string {
+    if (shift === 0) return html;
+    return html.replace(
+        /data-sourcepos="(\d+):(\d+)-(\d+):(\d+)"/g,
+        (_, startLine, startCol, endLine, endCol) => {
+            const start = Number(startLine) + shift;
+            const end = Number(endLine) + shift;
+            return `data-sourcepos="${start}:${startCol}-${end}:${endCol}"`;
+        },
+    );
+}
+
+/**
+ * Renders a large document block by block. Only blocks touched since the render held in
+ * `state` are rendered and sanitized again; `state` is updated in place.
+ */
+export async function renderMarkdownIncremental(
+    content: string,
+    state: IncrementalRenderState,
+    markdownFlavor: MarkdownFlavor = appContext.app.markdownFlavor,
+    basePath: string | null = null,
+    remotePolicy?: RemotePolicy,
+    extensions: MarkdownExtensions = currentExtensions(),
+    hideFrontMatter: boolean = false,
+): Promise<RenderResult | null> {
+    const baseHash = state.hash;
+    const result = await callBackendSafe(
+        'render_markdown_incremental',
+        {
+            content,
+            flavor: flavorArg(markdownFlavor),
+            remotePolicy,
+            extensions,
+            hideFrontMatter,
+            headingIdPrefix: appContext.app.headingIdPrefix,
+            basePath,
+            htmlPolicy: appContext.app.htmlPolicy,
+            wordCountMode: appContext.app.wordCountMode,
+            previousHash: state.hash,
+            edit: state.hash ? changedRange(state.content, content) : null,
+        },
+        'Markdown:Render',
+        {
+            showToast: false,
+            onError: async (e) => {
+                await error(`[Markdown] Incremental render error: ${e}`);
+            },
+        },
+    );
+    if (!result) return null;
+
+    // Another render finished first; this patch no longer applies to the blocks held
+    if (result.patch && state.hash !== baseHash) {
+        state.hash = null;
+        return null;
+    }
+
+    if (result.blocks) {
+        state.blocks = result.blocks.map((html) => sanitizeRenderedHtml(html));
+    } else if (result.patch) {
+        const { start, delete_count, fragments, line_shift } = result.patch;
+        const tail = state.blocks
+            .slice(start + delete_count)
+            .map((html) => shiftSourcepos(html, line_shift));
+        state.blocks = [
+            ...state.blocks.slice(0, start),
+            ...fragments.map((html) => sanitizeRenderedHtml(html)),
+            ...tail,
+        ];
+    }
+    state.hash = result.content_hash;
+    state.content = content;
+
+    return {
+        html: state.blocks.join('\n'),
+        line_map: {},
+        word_count: result.word_count,
+        char_count: result.char_count,
+        readability: result.readability,
+        sanitization: result.sanitization,
+        heading_ids: result.heading_ids,
+        document_css: result.document_css,
+        highlight_css: result.highlight_css,
+    };
 }
@@ -32,8 +32,21 @@ function getSettingsObject() {
         previewFontFamily: appState.previewFontFamily,
         previewFontSize: appState.previewFontSize,
         markdownFlavor: appState.markdownFlavor,
+        customFlavor: appState.customFlavor,
+        blockRemoteImages: appState.blockRemoteImages,
+        forceHttps: appState.forceHttps,
+        stripTrackingParams: appState.stripTrackingParams,
+        mathRendering: appState.mathRendering,
+        renderFootnotes: appState.renderFootnotes,
+        hideFrontMatter: appState.hideFrontMatter,
+        headingIdPrefix: appState.headingIdPrefix,
+        htmlPolicy: appState.htmlPolicy,
         logLevel: appState.logLevel,
+        logRotation: appState.logRotation,
+        logMaxFileSizeMb: appState.logMaxFileSizeMb,
+        logRetention: appState.logRetention,
         statusBarTransparency: appState.statusBarTransparency,
+        wordCountMode: appState.wordCountMode,
         newTabPosition: appState.newTabPosition,
         formatOnSave: appState.formatOnSave,
         formatOnPaste: appState.formatOnPaste,
@@ -43,13 +56,16 @@ function getSettingsObject() {
         formatterCodeFence: appState.formatterCodeFence,
         formatterTableAlignment: appState.formatterTableAlignment,
         startupBehavior: appState.startupBehavior,
+        sessionRestore: appState.sessionRestore,
+        sessionRestorePath: appState.sessionRestorePath,
+        sessionSaveIdleSeconds: appState.sessionSaveIdleSeconds,
+        sessionSaveDirtyTabs: appState.sessionSaveDirtyTabs,
         lineEndingPreference: appState.lineEndingPreference,
         tooltipDelay: appState.tooltipDelay,
         findPanelTransparent: appState.findPanelTransparent,
         findPanelCloseOnBlur: appState.findPanelCloseOnBlur,
         languageDictionaries: appState.languageDictionaries,
-        technicalDictionaries: appState.technicalDictionaries,
-        scienceDictionaries: appState.scienceDictionaries,
+        specialistDictionaries: appState.specialistDictionaries,
         tabNameFromContent: appState.tabNameFromContent,
         wrapGuideColumn: appState.wrapGuideColumn,
         doubleClickSelectsTrailingSpace: appState.doubleClickSelectsTrailingSpace,
@@ -57,6 +73,10 @@ function getSettingsObject() {
         customShortcuts: appState.customShortcuts,
         confirmationSuppressed: appState.confirmationSuppressed,
         maxFileSizeMB: appState.maxFileSizeMB,
+        followSymlinksOnWrite: appState.followSymlinksOnWrite,
+        resolveSymlinksForIdentity: appState.resolveSymlinksForIdentity,
+        livePreviewExport: appState.livePreviewExport,
+        livePreviewExportPath: appState.livePreviewExportPath,
     };
 }
 
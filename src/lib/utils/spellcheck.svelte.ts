@@ -44,13 +44,12 @@ export class SpellcheckManager {
             await this.loadCustomDictionary();
 
             const dictionaries = appState.languageDictionaries || ['en-US'];
-            const technicalDictionaries = appState.technicalDictionaries;
-            const scienceDictionaries = appState.scienceDictionaries;
+            const specialistDictionaries = appState.specialistDictionaries;
 
             try {
                 await callBackend(
                     'init_spellchecker',
-                    { dictionaries, technicalDictionaries, scienceDictionaries },
+                    { dictionaries, specialistDictionaries },
                     'Spellcheck:Init',
                     undefined,
                     { ignore: true },
@@ -164,6 +163,16 @@ export class SpellcheckManager {
         return new SvelteSet(this.customDictionary);
     }
 
+    /**
+     * Frees the backend dictionary (idle or memory pressure). The next init() reloads it.
+     */
+    async unload(): Promise<void> {
+        await callBackend('unload_spellchecker', {}, 'Spellcheck:Init', undefined, {
+            ignore: true,
+        });
+        this.clear();
+    }
+
     clear(): void {
         this.customDictionary.clear();
         this.misspelledCache.clear();
@@ -184,3 +193,4 @@ export const getCachedSuggestions = (w: string) => spellcheckState.getCachedSugg
 export const getSuggestions = (w: string) => spellcheckState.getSuggestions(w);
 export const getCustomDictionary = () => spellcheckState.getCustomDictionarySet();
 export const clearDictionaries = () => spellcheckState.clear();
+export const unloadSpellchecker = () => spellcheckState.unload();
@@ -1,4 +1,6 @@
-import { CONFIG } from './config';
+import type { BackendCommands } from '$lib/types/api';
+import type { ChangeSet, Text } from '@codemirror/state';
+import { callBackendSafe } from './backend';
 
 export interface TextMetrics {
     lineCount: number;
@@ -6,14 +8,6 @@ export interface TextMetrics {
     charCount: number;
 }
 
-export interface CursorMetrics {
-    cursorOffset: number;
-    cursorLine: number;
-    cursorCol: number;
-    currentLineLength: number;
-    currentWordIndex: number;
-}
-
 const segmenter = new Intl.Segmenter(undefined, { granularity: 'word' });
 
 export function countWords(text: string): number {
@@ -48,24 +42,83 @@ export function fastCountWords(text: string): number {
     return count;
 }
 
-export function calculateCursorMetrics(
-    content: string,
-    cursorOffset: number,
-    line: { number: number; from: number; text: string },
-): CursorMetrics {
-    const textUpToCursor = content.substring(0, cursorOffset);
-    const currentWordIndex =
-        content.length < CONFIG.PERFORMANCE.LARGE_FILE_SIZE_BYTES
-            ? countWords(textUpToCursor)
-            : fastCountWords(textUpToCursor);
+type LineEdit = BackendCommands['update_text_metrics']['args']['edits'][number];
+type IncrementalMetrics = BackendCommands['update_text_metrics']['return'];
+
+/**
+ * Line edits for `update_text_metrics` from the changes between `before` and `after`.
+ * Changes touching the same line merge into one edit, and the last edit comes first, so
+ * every edit's line numbers still refer to `before` when the backend applies them in order.
+ */
+export function lineEdits(changes: ChangeSet, before: Text, after: Text): LineEdit[] {
+    const spans: { fromLine: number; toLine: number; fromB: number; toB: number }[] = [];
+    changes.iterChangedRanges((fromA, toA, fromB, toB) => {
+        const fromLine = before.lineAt(fromA).number - 1;
+        const toLine = before.lineAt(toA).number;
+        const last = spans[spans.length - 1];
+        if (last && fromLine < last.toLine) {
+            last.toLine = Math.max(last.toLine, toLine);
+            last.toB = toB;
+        } else {
+            spans.push({ fromLine, toLine, fromB, toB });
+        }
+    });
+
+    return spans.reverse().map((span) => ({
+        from_line: span.fromLine,
+        to_line: span.toLine,
+        text: after.sliceString(after.lineAt(span.fromB).from, after.lineAt(span.toB).to),
+    }));
+}
+
+// Latest update per document, so readers wait for the edits already sent
+const pendingUpdates = new Map<string, Promise<IncrementalMetrics | null>>();
+
+/**
+ * Brings the backend's per-line metrics for a document up to `doc` by sending `edits`.
+ * The whole text follows only when the backend has no cache for it or the cache drifted,
+ * so an empty `edits` checks the cache and seeds it when needed.
+ */
+export function updateTextMetrics(
+    documentId: string,
+    doc: Text,
+    edits: LineEdit[],
+): Promise<IncrementalMetrics | null> {
+    const send = (changes: LineEdit[], content?: string) =>
+        callBackendSafe(
+            'update_text_metrics',
+            { documentId, edits: changes, expectedLength: doc.length, content },
+            'Editor:Status',
+            { showToast: false },
+        );
+
+    const previous = pendingUpdates.get(documentId) ?? Promise.resolve(null);
+    const update = previous.then(async () => {
+        const result = await send(edits);
+        if (!result?.needs_full_content) return result;
+        return send([], doc.toString());
+    });
+    pendingUpdates.set(documentId, update);
+    update.finally(() => {
+        if (pendingUpdates.get(documentId) === update) pendingUpdates.delete(documentId);
+    });
+    return update;
+}
+
+/** Resolves once every update sent for the document has been applied */
+export async function textMetricsSettled(documentId: string): Promise<void> {
+    await pendingUpdates.get(documentId);
+}
 
-    return {
-        cursorOffset,
-        cursorLine: line.number,
-        cursorCol: cursorOffset - line.from + 1,
-        currentLineLength: line.text.length,
-        currentWordIndex,
-    };
+/** Drops the backend cache for a closed document, after any update still in flight */
+export function clearTextMetrics(documentId: string) {
+    const pending = pendingUpdates.get(documentId);
+    pendingUpdates.delete(documentId);
+    void Promise.resolve(pending).then(() =>
+        callBackendSafe('clear_text_metrics', { documentId }, 'Editor:Status', {
+            showToast: false,
+        }),
+    );
 }
 
 export function formatNumber(num: number): string {
@@ -0,0 +1,40 @@
+import type { TabHistory } from '$lib/types/api';
+import { history, historyField } from '@codemirror/commands';
+import { EditorState } from '@codemirror/state';
+
+type HistoryJson = { done: unknown[]; undone: unknown[] };
+
+/**
+ * Serializes a tab's cached editor history for `save_tab_history`.
+ * `doc` is the content the history ends at.
+ */
+export function serializeHistory(state: unknown, doc: string): TabHistory {
+    const json = EditorState.create({
+        doc,
+        extensions: [history(), historyField.init(() => state)],
+    }).toJSON({ history: historyField }).history as HistoryJson;
+
+    return { done: json.done, undone: json.undone, doc_length: doc.length };
+}
+
+/**
+ * Rebuilds an editor history from `load_tab_history`.
+ * Returns null when it no longer fits `doc`, since undoing would then corrupt the text.
+ */
+export function deserializeHistory(saved: TabHistory, doc: string): unknown | null {
+    if (saved.doc_length !== doc.length) return null;
+
+    try {
+        return EditorState.fromJSON(
+            {
+                doc,
+                selection: { ranges: [{ anchor: 0, head: 0 }], main: 0 },
+                history: { done: saved.done, undone: saved.undone },
+            },
+            { extensions: [history()] },
+            { history: historyField },
+        ).field(historyField);
+    } catch {
+        return null;
+    }
+}